@@ -246,7 +246,7 @@ class StreamService:
         try:
             async with httpx.AsyncClient() as client:
                 response = await client.get(
-                    f"{self.core_service_url}/api/streams/{stream_id}",
+                    f"{self.core_service_url}/api/v1/streams/{stream_id}",
                     timeout=5.0
                 )
                 
@@ -0,0 +1,48 @@
+//! Analytics frames from the vision service, parsed on ingest and encoded
+//! again for subscribers, in JSON and in MessagePack for clients that ask.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use morphine_core::analytics::AnalyticsEvent;
+use serde_json::{json, Value};
+
+fn frame(detections: usize) -> Value {
+    let detected_objects: Vec<Value> = (0..detections)
+        .map(|index| json!({
+            "object_type": if index % 2 == 0 { "person" } else { "ball" },
+            "confidence": 0.8 + (index % 20) as f64 / 100.0,
+            "bounding_box": { "x": index as f64, "y": 120.5, "width": 48.0, "height": 96.0 },
+            "speed": 30.0 + index as f64 * 0.25,
+        }))
+        .collect();
+
+    json!({
+        "event_type": "detections",
+        "timestamp": 1767268800.0,
+        "confidence": 0.91,
+        "detected_objects": detected_objects,
+    })
+}
+
+fn analytics_json(c: &mut Criterion) {
+    let mut group = c.benchmark_group("analytics_json");
+    // A quiet frame, a busy one, and a crowd scene
+    for detections in [5, 50, 500] {
+        let event = AnalyticsEvent::from(frame(detections));
+        let body = serde_json::to_vec(&event).expect("frame encodes");
+        group.throughput(Throughput::Bytes(body.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("deserialize", detections), &body, |b, body| {
+            b.iter(|| serde_json::from_slice::<AnalyticsEvent>(black_box(body)).expect("frame decodes"))
+        });
+        group.bench_with_input(BenchmarkId::new("serialize", detections), &event, |b, event| {
+            b.iter(|| serde_json::to_vec(black_box(event)).expect("frame encodes"))
+        });
+        group.bench_with_input(BenchmarkId::new("msgpack", detections), &event, |b, event| {
+            b.iter(|| rmp_serde::to_vec_named(black_box(event)).expect("frame encodes"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, analytics_json);
+criterion_main!(benches);
@@ -0,0 +1,120 @@
+//! The reasoning work behind every settlement: the full hybrid evaluation
+//! across the three paradigms, and fuzzy inference on its own.
+
+use std::collections::HashMap;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use tokio::runtime::Runtime;
+use tokio::sync::watch;
+
+use morphine_core::config::ReasoningConfig;
+use morphine_core::reasoning::fuzzy::FuzzyEngine;
+use morphine_core::reasoning::{
+    BetCondition, ConditionType, Constraint, ConstraintType, FuzzySet, HybridReasoningEngine, ImperativeRule,
+    LogicalPredicate, MembershipFunction, PredicateType,
+};
+
+// A speed-milestone market of the kind the vision service settles most often
+fn speed_condition(fuzzy_sets: usize) -> BetCondition {
+    let functions = [
+        MembershipFunction::Triangular,
+        MembershipFunction::Trapezoidal,
+        MembershipFunction::Gaussian,
+        MembershipFunction::Sigmoid,
+        MembershipFunction::Bell,
+    ];
+    let fuzzy_sets = (0..fuzzy_sets)
+        .map(|index| {
+            let name = format!("speed_{}", index);
+            let set = FuzzySet {
+                name: name.clone(),
+                membership_function: functions[index % functions.len()].clone(),
+                parameters: vec![20.0 + index as f64, 35.0, 50.0, 65.0],
+            };
+            (name, set)
+        })
+        .collect();
+
+    BetCondition {
+        condition_id: "bench-speed".to_string(),
+        condition_type: ConditionType::SpeedMilestone,
+        parameters: HashMap::from([("threshold".to_string(), json!(40.0))]),
+        fuzzy_sets,
+        logical_predicates: vec![LogicalPredicate {
+            predicate_id: "exceeds-threshold".to_string(),
+            predicate_type: PredicateType::Exists,
+            variables: vec!["speed".to_string()],
+            constraints: vec![Constraint {
+                constraint_type: ConstraintType::GreaterThan,
+                target_value: 40.0,
+                tolerance: 0.5,
+                weight: 1.0,
+            }],
+        }],
+        imperative_rules: vec![ImperativeRule {
+            rule_id: "speed-check".to_string(),
+            condition: "speed > threshold".to_string(),
+            action: "win".to_string(),
+            priority: 1.0,
+            execution_context: HashMap::new(),
+        }],
+    }
+}
+
+fn event_data() -> Value {
+    json!({
+        "speed": 42.7,
+        "confidence": 0.93,
+        "detections": [
+            { "object_type": "person", "confidence": 0.97, "speed": 42.7 },
+            { "object_type": "ball", "confidence": 0.88, "speed": 61.2 },
+        ],
+        "timestamp": 1_700_000_000.0,
+    })
+}
+
+fn hybrid_evaluation(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("tokio runtime");
+    // Simulated evaluations never touch the database, so the pool never connects
+    let (_config, receiver) = watch::channel(ReasoningConfig::default());
+    let engine = runtime.block_on(async {
+        let db_pool = PgPoolOptions::new()
+            .connect_lazy("postgres://bench@localhost/bench")
+            .expect("lazy pool");
+        HybridReasoningEngine::new(receiver, db_pool).await.expect("reasoning engine")
+    });
+    let condition = speed_condition(3);
+    let event = event_data();
+    let context = HashMap::new();
+
+    c.bench_function("hybrid_evaluation", |b| {
+        b.to_async(&runtime).iter(|| async {
+            engine
+                .simulate_bet_outcome("bench-bet", black_box(&condition), black_box(&event), &context)
+                .await
+                .expect("evaluation")
+        })
+    });
+}
+
+fn fuzzy_inference(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("tokio runtime");
+    let engine = FuzzyEngine::new();
+    let event = event_data();
+    let context = HashMap::new();
+
+    let mut group = c.benchmark_group("fuzzy_inference");
+    for sets in [1, 5, 25] {
+        let condition = speed_condition(sets);
+        group.bench_with_input(BenchmarkId::from_parameter(sets), &condition, |b, condition| {
+            b.to_async(&runtime).iter(|| async {
+                engine.evaluate(black_box(condition), black_box(&event), &context).await.expect("inference")
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, hybrid_evaluation, fuzzy_inference);
+criterion_main!(benches);
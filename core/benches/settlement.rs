@@ -0,0 +1,90 @@
+//! Batch settlement as the resolve endpoint drives it: deciding each bet,
+//! grouping the batch by balance shard, and applying payouts to balances.
+//! The settlement transaction itself is Postgres's time, not ours.
+
+use std::collections::HashMap;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+use morphine_core::betting::shards::shard_of;
+use morphine_core::betting::{ActualResult, Bet, BetType, Prediction, UserBalance};
+
+const SHARDS: usize = 16;
+
+fn open_bets(count: usize) -> Vec<Bet> {
+    (0..count)
+        .map(|index| {
+            let (bet_type, prediction) = match index % 4 {
+                0 => (BetType::Binary, Prediction::Binary { will_occur: index % 3 == 0 }),
+                1 => (BetType::Quantity, Prediction::Quantity { predicted_value: 12.0, tolerance: 1.5 }),
+                2 => (BetType::Timing, Prediction::Timing { predicted_seconds: 30.0, tolerance: 2.0 }),
+                _ => (
+                    BetType::Pattern,
+                    Prediction::Pattern { sequence: vec!["jump".to_string(), "land".to_string()] },
+                ),
+            };
+            Bet::new(
+                format!("user-{}", index % 200),
+                "bench-stream".to_string(),
+                bet_type,
+                10.0,
+                prediction,
+                300,
+                1.9,
+            )
+        })
+        .collect()
+}
+
+fn actual_result(bet: &Bet) -> ActualResult {
+    match bet.bet_type {
+        BetType::Binary => ActualResult::Binary { occurred: true },
+        BetType::Quantity => ActualResult::Quantity { actual_value: 12.8 },
+        BetType::Timing => ActualResult::Timing { actual_seconds: 33.1 },
+        BetType::Pattern => ActualResult::Pattern { actual_sequence: vec!["jump".to_string(), "land".to_string()] },
+    }
+}
+
+fn balances(bets: &[Bet]) -> HashMap<String, UserBalance> {
+    bets.iter()
+        .map(|bet| {
+            let mut balance = UserBalance::new(bet.user_id.clone(), bet.stream_id.clone(), 1000.0, 100.0);
+            balance.place_bet(bet.stake_amount);
+            (bet.user_id.clone(), balance)
+        })
+        .collect()
+}
+
+fn settle_batch(bets: Vec<Bet>, mut balances: HashMap<String, UserBalance>) -> Vec<Vec<Bet>> {
+    let mut shards = vec![Vec::new(); SHARDS];
+    for mut bet in bets {
+        let result = actual_result(&bet);
+        let won = bet.settle(result, 0.95);
+        let payout = if won { bet.potential_payout } else { 0.0 };
+        if let Some(balance) = balances.get_mut(&bet.user_id) {
+            balance.resolve_bet(bet.stake_amount, payout);
+        }
+        shards[shard_of(&bet.user_id, SHARDS)].push(bet);
+    }
+    shards
+}
+
+fn batch_settlement(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_settlement");
+    // Up to the most one resolve request may carry
+    for size in [10, 100, 500] {
+        let bets = open_bets(size);
+        let balances = balances(&bets);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter_batched(
+                || (bets.clone(), balances.clone()),
+                |(bets, balances)| settle_batch(black_box(bets), balances),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, batch_settlement);
+criterion_main!(benches);
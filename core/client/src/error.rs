@@ -0,0 +1,54 @@
+use morphine_core::error::ErrorCode;
+use morphine_core::validation::FieldError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The server answered with its error body,
+    /// `{"success": false, "error": {"code", "message", "correlation_id", "fields"?}}`.
+    #[error("{message} ({status})")]
+    Api {
+        status: u16,
+        // None when the response wasn't the server's error body, e.g. from a proxy
+        code: Option<ErrorCode>,
+        message: String,
+        correlation_id: Option<String>,
+        fields: Vec<FieldError>,
+    },
+
+    #[error("unexpected response body: {0}")]
+    Decode(#[from] serde_json::Error),
+
+    #[error("websocket error: {0}")]
+    WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
+
+    #[error("invalid URL: {0}")]
+    InvalidUrl(String),
+}
+
+impl ClientError {
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            ClientError::Api { code, .. } => *code,
+            _ => None,
+        }
+    }
+
+    /// Whether retrying the same request may succeed: timeouts, connection
+    /// failures, rate limiting and unavailability.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClientError::Http(e) => e.is_timeout() || e.is_connect(),
+            ClientError::Api { code, status, .. } => match code {
+                Some(code) => matches!(code, ErrorCode::RateLimited | ErrorCode::Unavailable | ErrorCode::Timeout),
+                None => *status >= 500,
+            },
+            _ => false,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
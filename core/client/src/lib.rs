@@ -0,0 +1,369 @@
+//! Typed client for the Morphine HTTP and WebSocket APIs.
+//!
+//! Requests, responses and WebSocket messages are the server's own types from
+//! [`morphine_core::api`], so a service built against this crate breaks at
+//! compile time, not in production, when the API changes.
+//!
+//! ```no_run
+//! # async fn run() -> morphine_client::Result<()> {
+//! use morphine_client::{Credentials, MorphineClient};
+//!
+//! let client = MorphineClient::new("https://morphine.internal")?
+//!     .with_credentials(Credentials::ApiKey("mk_live_...".into()));
+//! let streams = client.list_streams(&Default::default()).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod error;
+pub mod socket;
+
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::{Method, RequestBuilder, Response, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use morphine_core::api_version::{ApiVersion, API_VERSION_HEADER};
+use morphine_core::auth::api_keys::API_KEY_HEADER;
+use morphine_core::auth::users::User;
+use morphine_core::betting::{Bet, UserBettingStats};
+use morphine_core::error::{ErrorCode, CORRELATION_ID_HEADER};
+use morphine_core::geolocation::LocationVerification;
+use morphine_core::idempotency::IDEMPOTENCY_KEY_HEADER;
+use morphine_core::stream::{StreamActivity, StreamInfo, StreamStatus};
+use morphine_core::validation::FieldError;
+
+pub use error::{ClientError, Result};
+pub use morphine_core::api::{
+    BalanceSummary, BetResponse, LocationVerificationRequest, LoginRequest, PlaceBetRequest, ResolveBetRequest,
+    WebSocketMessage,
+};
+pub use morphine_core::pagination::PageParams;
+pub use socket::{SocketEvent, StreamSocket};
+
+#[derive(Debug, Clone)]
+pub enum Credentials {
+    // A user token from `login`
+    Bearer(String),
+    // A service API key, sent as `x-api-key`
+    ApiKey(String),
+}
+
+/// A client for one Morphine deployment. Cheap to clone; clones share the
+/// connection pool.
+#[derive(Debug, Clone)]
+pub struct MorphineClient {
+    http: reqwest::Client,
+    base_url: Url,
+    credentials: Option<Credentials>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Session {
+    pub user: User,
+    pub token: String,
+}
+
+/// One page of a list endpoint. Pass `next_cursor` as `PageParams::cursor`
+/// for the next one; it is `None` on the last page.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub limit: usize,
+    pub total: usize,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PageBody<T> {
+    data: Vec<T>,
+    pagination: Pagination,
+}
+
+#[derive(Deserialize)]
+struct Pagination {
+    limit: usize,
+    total: usize,
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: ErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetail {
+    code: ErrorCode,
+    message: String,
+    correlation_id: Option<String>,
+    #[serde(default)]
+    fields: Vec<FieldError>,
+}
+
+#[derive(Serialize)]
+struct BetHistoryQuery<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_id: Option<&'a str>,
+}
+
+impl MorphineClient {
+    /// `base_url` is the deployment's root, e.g. `https://morphine.internal`.
+    pub fn new(base_url: &str) -> Result<Self> {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    /// Uses `http` for every request, e.g. one with timeouts or a proxy configured.
+    pub fn with_http_client(base_url: &str, http: reqwest::Client) -> Result<Self> {
+        let mut base_url = Url::parse(base_url).map_err(|e| ClientError::InvalidUrl(format!("{}: {}", base_url, e)))?;
+        if base_url.cannot_be_a_base() {
+            return Err(ClientError::InvalidUrl(base_url.to_string()));
+        }
+        // Paths below are joined onto the base, which needs a trailing slash to keep its own
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        Ok(Self { http, base_url, credentials: None })
+    }
+
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    pub fn credentials(&self) -> Option<&Credentials> {
+        self.credentials.as_ref()
+    }
+
+    // Auth
+
+    /// Signs in with email and password. The returned token can be passed to
+    /// `with_credentials` as `Credentials::Bearer`.
+    pub async fn login(&self, email: &str, password: &str) -> Result<Session> {
+        let request = LoginRequest {
+            email: email.to_string(),
+            password: password.to_string(),
+        };
+        self.data(self.request(Method::POST, "api/auth/login")?.json(&request)).await
+    }
+
+    // Streams
+
+    pub async fn list_streams(&self, page: &PageParams) -> Result<Page<StreamInfo>> {
+        self.page(self.request(Method::GET, "api/streams")?.query(page)).await
+    }
+
+    /// `None` when there is no stream with this ID.
+    pub async fn get_stream(&self, stream_id: &str) -> Result<Option<StreamInfo>> {
+        let path = format!("api/streams/{}", segment(stream_id));
+        not_found_as_none(self.data(self.request(Method::GET, &path)?).await)
+    }
+
+    /// `None` when there is no stream with this ID.
+    pub async fn stream_status(&self, stream_id: &str) -> Result<Option<StreamStatus>> {
+        #[derive(Deserialize)]
+        struct Body {
+            status: Option<StreamStatus>,
+        }
+        let path = format!("api/streams/{}/status", segment(stream_id));
+        let body: Body = self.json(self.request(Method::GET, &path)?).await?;
+        Ok(body.status)
+    }
+
+    pub async fn start_stream(&self, stream_id: &str) -> Result<()> {
+        let path = format!("api/streams/{}/start", segment(stream_id));
+        self.json::<Value>(self.request(Method::POST, &path)?).await.map(|_| ())
+    }
+
+    pub async fn stop_stream(&self, stream_id: &str) -> Result<()> {
+        let path = format!("api/streams/{}/stop", segment(stream_id));
+        self.json::<Value>(self.request(Method::POST, &path)?).await.map(|_| ())
+    }
+
+    // Betting
+
+    /// Places a bet. Retrying with the same `idempotency_key` replays the
+    /// first response instead of placing the bet twice.
+    pub async fn place_bet(&self, request: &PlaceBetRequest, idempotency_key: Option<&str>) -> Result<BetResponse> {
+        let mut builder = self.request(Method::POST, "api/betting/place")?.json(request);
+        if let Some(key) = idempotency_key {
+            builder = builder.header(IDEMPOTENCY_KEY_HEADER, key);
+        }
+        self.json(builder).await
+    }
+
+    /// The caller's balance on a stream.
+    pub async fn balance(&self, stream_id: &str) -> Result<BalanceSummary> {
+        let path = format!("api/betting/balance/{}", segment(stream_id));
+        self.data(self.request(Method::GET, &path)?).await
+    }
+
+    /// The caller's bets, newest first, on one stream or all of them.
+    pub async fn bet_history(&self, stream_id: Option<&str>, page: &PageParams) -> Result<Page<Bet>> {
+        let builder = self.request(Method::GET, "api/betting/history")?.query(&BetHistoryQuery { stream_id }).query(page);
+        self.page(builder).await
+    }
+
+    pub async fn stream_activity(&self, stream_id: &str, page: &PageParams) -> Result<Page<StreamActivity>> {
+        let path = format!("api/betting/stream/{}/activity", segment(stream_id));
+        self.page(self.request(Method::GET, &path)?.query(page)).await
+    }
+
+    /// `None` when the user hasn't placed a bet.
+    pub async fn user_stats(&self, user_id: &str) -> Result<Option<UserBettingStats>> {
+        let path = format!("api/users/{}/stats", segment(user_id));
+        not_found_as_none(self.data(self.request(Method::GET, &path)?).await)
+    }
+
+    /// Settles a bet against the actual result. `None` when the bet was
+    /// already settled.
+    pub async fn resolve_bet(&self, bet_id: &str, request: &ResolveBetRequest) -> Result<Option<Bet>> {
+        #[derive(Deserialize)]
+        struct Body {
+            resolved: bool,
+            data: Option<Bet>,
+        }
+        let path = format!("api/betting/resolve/{}", segment(bet_id));
+        let body: Body = self.json(self.request(Method::POST, &path)?.json(request)).await?;
+        Ok(if body.resolved { body.data } else { None })
+    }
+
+    // Geolocation
+
+    /// Starts a verification session; pass its ID in `LocationVerificationRequest::session_id`.
+    pub async fn start_location_session(&self, user_id: &str) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Body {
+            session_id: String,
+        }
+        let path = format!("api/geolocation/session/start/{}", segment(user_id));
+        let body: Body = self.json(self.request(Method::POST, &path)?).await?;
+        Ok(body.session_id)
+    }
+
+    pub async fn verify_location(&self, request: &LocationVerificationRequest) -> Result<LocationVerification> {
+        #[derive(Deserialize)]
+        struct Body {
+            verification: LocationVerification,
+        }
+        let body: Body = self.json(self.request(Method::POST, "api/geolocation/verify")?.json(request)).await?;
+        Ok(body.verification)
+    }
+
+    pub async fn location_history(&self, user_id: &str, page: &PageParams) -> Result<Page<LocationVerification>> {
+        let path = format!("api/geolocation/history/{}", segment(user_id));
+        self.page(self.request(Method::GET, &path)?.query(page)).await
+    }
+
+    // WebSocket
+
+    /// Opens the stream's WebSocket, `/ws/{stream_id}`, reconnecting until the
+    /// socket is dropped. Needs `Credentials::Bearer`.
+    pub fn stream_socket(&self, stream_id: &str) -> Result<StreamSocket> {
+        let token = match &self.credentials {
+            Some(Credentials::Bearer(token)) => Some(token.clone()),
+            _ => None,
+        };
+        let mut url = self.url(&format!("ws/{}", segment(stream_id)))?;
+        let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+        url.set_scheme(scheme).map_err(|_| ClientError::InvalidUrl(url.to_string()))?;
+        Ok(StreamSocket::connect(url, token))
+    }
+
+    fn url(&self, path: &str) -> Result<Url> {
+        self.base_url.join(path).map_err(|e| ClientError::InvalidUrl(format!("{}: {}", path, e)))
+    }
+
+    fn request(&self, method: Method, path: &str) -> Result<RequestBuilder> {
+        let mut headers = HeaderMap::new();
+        // Pinned to the version these types describe, not whatever is current on the server
+        headers.insert(API_VERSION_HEADER, HeaderValue::from_static(ApiVersion::V1.as_str()));
+        let builder = self.http.request(method, self.url(path)?).headers(headers);
+        Ok(match &self.credentials {
+            Some(Credentials::Bearer(token)) => builder.header(AUTHORIZATION, format!("Bearer {}", token)),
+            Some(Credentials::ApiKey(key)) => builder.header(API_KEY_HEADER, key),
+            None => builder,
+        })
+    }
+
+    async fn json<T: DeserializeOwned>(&self, builder: RequestBuilder) -> Result<T> {
+        let response = check(builder.send().await?).await?;
+        let bytes = response.bytes().await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    // `{"success": true, "data": ...}`
+    async fn data<T: DeserializeOwned>(&self, builder: RequestBuilder) -> Result<T> {
+        #[derive(Deserialize)]
+        struct Body<T> {
+            data: T,
+        }
+        let body: Body<T> = self.json(builder).await?;
+        Ok(body.data)
+    }
+
+    async fn page<T: DeserializeOwned>(&self, builder: RequestBuilder) -> Result<Page<T>> {
+        let body: PageBody<T> = self.json(builder).await?;
+        Ok(Page {
+            items: body.data,
+            limit: body.pagination.limit,
+            total: body.pagination.total,
+            next_cursor: body.pagination.next_cursor,
+        })
+    }
+}
+
+async fn check(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let header_correlation_id = response
+        .headers()
+        .get(CORRELATION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let bytes = response.bytes().await?;
+    Err(match serde_json::from_slice::<ErrorBody>(&bytes) {
+        Ok(body) => ClientError::Api {
+            status: status.as_u16(),
+            code: Some(body.error.code),
+            message: body.error.message,
+            correlation_id: body.error.correlation_id.or(header_correlation_id),
+            fields: body.error.fields,
+        },
+        Err(_) => ClientError::Api {
+            status: status.as_u16(),
+            code: None,
+            message: status.canonical_reason().unwrap_or("request failed").to_string(),
+            correlation_id: header_correlation_id,
+            fields: Vec::new(),
+        },
+    })
+}
+
+fn not_found_as_none<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.code() == Some(ErrorCode::NotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// IDs are caller-supplied; keep a `/` or `?` in one from changing the route
+fn segment(id: &str) -> String {
+    let mut encoded = String::with_capacity(id.len());
+    for byte in id.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
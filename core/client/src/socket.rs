@@ -0,0 +1,216 @@
+use std::time::Duration;
+use futures::{SinkExt, StreamExt};
+use reqwest::Url;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::{debug, warn};
+
+use morphine_core::api::WebSocketMessage;
+use morphine_core::error::CORRELATION_ID_HEADER;
+
+use crate::error::ClientError;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// Well inside the idle timeouts of the load balancers in front of the server
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug)]
+pub enum SocketEvent {
+    /// Connected, or reconnected after a drop. Streams joined before the
+    /// drop have been joined again.
+    Connected,
+    Disconnected,
+    Message(WebSocketMessage),
+    /// The server refused the connection, e.g. for a bad token; the socket
+    /// won't reconnect and `recv` returns `None` after this.
+    Closed(ClientError),
+}
+
+/// A stream's WebSocket that reconnects, with exponential backoff, whenever
+/// the connection drops. Messages sent while disconnected go out once it is
+/// back. Dropping the socket closes it.
+pub struct StreamSocket {
+    outgoing: mpsc::UnboundedSender<WebSocketMessage>,
+    events: mpsc::Receiver<SocketEvent>,
+    task: JoinHandle<()>,
+}
+
+enum SessionEnd {
+    // The `StreamSocket` was dropped
+    Dropped,
+    Disconnected(Option<tungstenite::Error>),
+}
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+impl StreamSocket {
+    /// Starts connecting in the background; must be called within a Tokio runtime.
+    pub(crate) fn connect(mut url: Url, token: Option<String>) -> Self {
+        // The server takes the token from the query, as browsers can't set upgrade headers
+        if let Some(token) = token {
+            url.query_pairs_mut().append_pair("token", &token);
+        }
+        let (outgoing_tx, outgoing_rx) = mpsc::unbounded_channel();
+        let (events_tx, events_rx) = mpsc::channel(EVENT_BUFFER);
+        let task = tokio::spawn(run(url, outgoing_rx, events_tx));
+        Self {
+            outgoing: outgoing_tx,
+            events: events_rx,
+            task,
+        }
+    }
+
+    /// Queues `message`. Bets and pledges queued while disconnected for longer
+    /// than the server's clock skew allowance are refused as stale once sent.
+    pub fn send(&self, message: WebSocketMessage) -> Result<(), ClientError> {
+        self.outgoing
+            .send(message)
+            .map_err(|_| ClientError::WebSocket(tungstenite::Error::AlreadyClosed))
+    }
+
+    /// The next event, or `None` once the socket is closed for good.
+    pub async fn recv(&mut self) -> Option<SocketEvent> {
+        self.events.recv().await
+    }
+}
+
+impl Drop for StreamSocket {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run(
+    url: Url,
+    mut outgoing: mpsc::UnboundedReceiver<WebSocketMessage>,
+    events: mpsc::Sender<SocketEvent>,
+) {
+    let mut backoff = INITIAL_BACKOFF;
+    // Each stream joined and its join, re-sent after every reconnect so callers don't have to track them
+    let mut joined: Vec<(String, WebSocketMessage)> = Vec::new();
+
+    loop {
+        match connect_async(url.as_str()).await {
+            Ok((socket, _)) => {
+                backoff = INITIAL_BACKOFF;
+                if events.send(SocketEvent::Connected).await.is_err() {
+                    return;
+                }
+                match session(socket, &mut outgoing, &events, &mut joined).await {
+                    SessionEnd::Dropped => return,
+                    SessionEnd::Disconnected(error) => {
+                        match error {
+                            Some(e) => warn!("WebSocket to {} dropped: {}", url.path(), e),
+                            None => debug!("WebSocket to {} closed by the server", url.path()),
+                        }
+                        if events.send(SocketEvent::Disconnected).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+            Err(tungstenite::Error::Http(response)) if matches!(response.status().as_u16(), 401 | 403 | 404) => {
+                // Retrying won't change the answer
+                let error = ClientError::Api {
+                    status: response.status().as_u16(),
+                    code: None,
+                    message: format!("WebSocket upgrade rejected: {}", response.status()),
+                    correlation_id: response
+                        .headers()
+                        .get(CORRELATION_ID_HEADER)
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string),
+                    fields: Vec::new(),
+                };
+                let _ = events.send(SocketEvent::Closed(error)).await;
+                return;
+            }
+            Err(e) => warn!("Failed to connect WebSocket to {}, retrying in {:?}: {}", url.path(), backoff, e),
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = events.closed() => return,
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+async fn session(
+    socket: Socket,
+    outgoing: &mut mpsc::UnboundedReceiver<WebSocketMessage>,
+    events: &mpsc::Sender<SocketEvent>,
+    joined: &mut Vec<(String, WebSocketMessage)>,
+) -> SessionEnd {
+    let (mut sink, mut stream) = socket.split();
+    for (_, join) in joined.iter() {
+        if let Err(e) = send(&mut sink, join).await {
+            return SessionEnd::Disconnected(Some(e));
+        }
+    }
+
+    let mut ping = tokio::time::interval_at(tokio::time::Instant::now() + PING_INTERVAL, PING_INTERVAL);
+    loop {
+        tokio::select! {
+            message = outgoing.recv() => {
+                let Some(message) = message else {
+                    let _ = sink.close().await;
+                    return SessionEnd::Dropped;
+                };
+                match &message {
+                    WebSocketMessage::JoinStream { stream_id, .. } => {
+                        if !joined.iter().any(|(joined_id, _)| joined_id == stream_id) {
+                            joined.push((stream_id.clone(), message.clone()));
+                        }
+                    }
+                    WebSocketMessage::LeaveStream { stream_id } => {
+                        joined.retain(|(joined_id, _)| joined_id != stream_id);
+                    }
+                    _ => {}
+                }
+                if let Err(e) = send(&mut sink, &message).await {
+                    return SessionEnd::Disconnected(Some(e));
+                }
+            }
+            frame = stream.next() => match frame {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<WebSocketMessage>(&text) {
+                    Ok(WebSocketMessage::Pong) => {}
+                    Ok(WebSocketMessage::Ping) => {
+                        if let Err(e) = send(&mut sink, &WebSocketMessage::Pong).await {
+                            return SessionEnd::Disconnected(Some(e));
+                        }
+                    }
+                    Ok(message) => {
+                        if events.send(SocketEvent::Message(message)).await.is_err() {
+                            return SessionEnd::Dropped;
+                        }
+                    }
+                    // A message type newer than this client
+                    Err(e) => debug!("Ignoring unrecognized WebSocket message: {}", e),
+                },
+                Some(Ok(Message::Close(_))) | None => return SessionEnd::Disconnected(None),
+                // tungstenite answers protocol-level pings itself
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return SessionEnd::Disconnected(Some(e)),
+            },
+            _ = ping.tick() => {
+                if let Err(e) = send(&mut sink, &WebSocketMessage::Ping).await {
+                    return SessionEnd::Disconnected(Some(e));
+                }
+            }
+        }
+    }
+}
+
+async fn send<S>(sink: &mut S, message: &WebSocketMessage) -> Result<(), tungstenite::Error>
+where
+    S: SinkExt<Message, Error = tungstenite::Error> + Unpin,
+{
+    let text = serde_json::to_string(message).map_err(|e| tungstenite::Error::Io(std::io::Error::other(e)))?;
+    sink.send(Message::Text(text)).await
+}
@@ -0,0 +1,18 @@
+-- Audit log of metacognitive orchestrator decisions
+
+CREATE TABLE orchestrator_decisions (
+    decision_id VARCHAR PRIMARY KEY,
+    stream_id VARCHAR NOT NULL,
+    decision_type TEXT NOT NULL,
+    confidence DOUBLE PRECISION NOT NULL,
+    inputs_hash VARCHAR NOT NULL,
+    evidence JSONB NOT NULL,
+    layer_weights JSONB NOT NULL,
+    metabolic_state JSONB NOT NULL,
+    decision JSONB NOT NULL,
+    decided_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_orchestrator_decisions_stream_time ON orchestrator_decisions(stream_id, decided_at);
+CREATE INDEX idx_orchestrator_decisions_type ON orchestrator_decisions(decision_type);
@@ -0,0 +1,19 @@
+-- Versioned registry of intuition-layer pattern models
+
+CREATE TABLE intuition_pattern_models (
+    model_id VARCHAR PRIMARY KEY,
+    stream_category VARCHAR NOT NULL,
+    version INTEGER NOT NULL,
+    patterns JSONB NOT NULL,
+    training_window_start TIMESTAMPTZ,
+    training_window_end TIMESTAMPTZ,
+    metrics JSONB NOT NULL DEFAULT '{}',
+    notes TEXT,
+    is_active BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (stream_category, version)
+);
+
+-- At most one active version per stream category
+CREATE UNIQUE INDEX idx_intuition_pattern_models_active
+    ON intuition_pattern_models(stream_category) WHERE is_active;
@@ -0,0 +1,18 @@
+-- Machine credentials for service-to-service callers
+
+CREATE TABLE api_keys (
+    key_id VARCHAR PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    -- Leading characters of the key, so operators can tell keys apart
+    key_prefix VARCHAR NOT NULL,
+    -- SHA-256 of the full key; the key itself is never stored
+    key_hash VARCHAR NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL,
+    rotated_from VARCHAR REFERENCES api_keys(key_id),
+    expires_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_api_keys_active ON api_keys(created_at) WHERE revoked_at IS NULL;
@@ -0,0 +1,9 @@
+-- Which creator manages each stream
+
+CREATE TABLE stream_owners (
+    stream_id VARCHAR PRIMARY KEY,
+    creator_id VARCHAR NOT NULL,
+    assigned_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_stream_owners_creator ON stream_owners(creator_id);
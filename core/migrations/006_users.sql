@@ -0,0 +1,67 @@
+-- User accounts, profiles and credentials
+
+CREATE TABLE users (
+    user_id VARCHAR PRIMARY KEY,
+    -- Stored lowercased
+    email VARCHAR NOT NULL UNIQUE,
+    username VARCHAR NOT NULL UNIQUE,
+    roles TEXT[] NOT NULL DEFAULT '{bettor}',
+    email_verified_at TIMESTAMPTZ,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_login_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE user_profiles (
+    user_id VARCHAR PRIMARY KEY REFERENCES users(user_id) ON DELETE CASCADE,
+    display_name VARCHAR,
+    avatar_url TEXT,
+    bio TEXT,
+    preferences JSONB NOT NULL DEFAULT '{}',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One row per way of signing in: a password, or an identity at an OAuth provider
+CREATE TABLE user_credentials (
+    credential_id VARCHAR PRIMARY KEY,
+    user_id VARCHAR NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('password', 'oauth')),
+    password_hash VARCHAR,
+    provider VARCHAR,
+    provider_subject VARCHAR,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (kind <> 'password' OR password_hash IS NOT NULL),
+    CHECK (kind <> 'oauth' OR (provider IS NOT NULL AND provider_subject IS NOT NULL)),
+    UNIQUE (provider, provider_subject)
+);
+
+CREATE UNIQUE INDEX idx_user_credentials_password ON user_credentials(user_id) WHERE kind = 'password';
+
+CREATE TABLE email_verifications (
+    -- SHA-256 of the token sent to the user
+    token_hash VARCHAR PRIMARY KEY,
+    user_id VARCHAR NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    email VARCHAR NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    consumed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_email_verifications_user ON email_verifications(user_id);
+
+-- User IDs used to be free-form; give every ID already in use a placeholder
+-- account so the foreign keys below can be added
+INSERT INTO users (user_id, email, username, is_active)
+SELECT user_id, user_id || '@legacy.invalid', 'legacy-' || user_id, FALSE
+FROM (
+    SELECT user_id FROM user_balances
+    UNION
+    SELECT user_id FROM bets
+) AS existing
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE user_balances
+    ADD CONSTRAINT fk_user_balances_user FOREIGN KEY (user_id) REFERENCES users(user_id);
+ALTER TABLE bets
+    ADD CONSTRAINT fk_bets_user FOREIGN KEY (user_id) REFERENCES users(user_id);
@@ -0,0 +1,34 @@
+-- Outbound webhooks: integrator subscriptions and the delivery log
+
+CREATE TABLE webhook_subscriptions (
+    subscription_id VARCHAR PRIMARY KEY,
+    url TEXT NOT NULL,
+    description TEXT,
+    event_types TEXT[] NOT NULL,
+    -- HMAC key for signing deliveries; kept in the clear since we must sign with it
+    secret VARCHAR NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_webhook_subscriptions_active ON webhook_subscriptions USING GIN (event_types) WHERE is_active;
+
+-- One row per event per subscription; rows past max attempts are kept as the dead-letter queue
+CREATE TABLE webhook_deliveries (
+    delivery_id VARCHAR PRIMARY KEY,
+    subscription_id VARCHAR NOT NULL REFERENCES webhook_subscriptions(subscription_id) ON DELETE CASCADE,
+    event_id VARCHAR NOT NULL,
+    event_type VARCHAR NOT NULL,
+    payload JSONB NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'succeeded', 'dead_lettered')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_response_status INTEGER,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX idx_webhook_deliveries_subscription ON webhook_deliveries(subscription_id, created_at DESC);
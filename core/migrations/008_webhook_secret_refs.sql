@@ -0,0 +1,6 @@
+-- Webhook subscriptions may sign with a secret held by the secrets provider instead of a generated one
+
+ALTER TABLE webhook_subscriptions ADD COLUMN secret_ref TEXT;
+ALTER TABLE webhook_subscriptions ALTER COLUMN secret DROP NOT NULL;
+ALTER TABLE webhook_subscriptions ADD CONSTRAINT webhook_subscriptions_secret_source
+    CHECK ((secret IS NULL) <> (secret_ref IS NULL));
@@ -0,0 +1,113 @@
+-- Tenants sharing one database. Rows carry the tenant they were written in,
+-- and row-level security limits each connection to the tenant set by the core
+-- service for the request it is serving. Connections with no tenant set (the
+-- migration runner, background workers) see every tenant. Orchestrator
+-- decisions and pattern models stay shared across tenants.
+
+CREATE FUNCTION morphine_current_tenant() RETURNS TEXT AS $$
+    SELECT NULLIF(current_setting('morphine.tenant_id', true), '')
+$$ LANGUAGE sql STABLE;
+
+ALTER TABLE users ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE users ENABLE ROW LEVEL SECURITY;
+ALTER TABLE users FORCE ROW LEVEL SECURITY;
+CREATE POLICY users_tenant_isolation ON users
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE user_profiles ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE user_profiles ENABLE ROW LEVEL SECURITY;
+ALTER TABLE user_profiles FORCE ROW LEVEL SECURITY;
+CREATE POLICY user_profiles_tenant_isolation ON user_profiles
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE user_credentials ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE user_credentials ENABLE ROW LEVEL SECURITY;
+ALTER TABLE user_credentials FORCE ROW LEVEL SECURITY;
+CREATE POLICY user_credentials_tenant_isolation ON user_credentials
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE email_verifications ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE email_verifications ENABLE ROW LEVEL SECURITY;
+ALTER TABLE email_verifications FORCE ROW LEVEL SECURITY;
+CREATE POLICY email_verifications_tenant_isolation ON email_verifications
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE user_balances ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE user_balances ENABLE ROW LEVEL SECURITY;
+ALTER TABLE user_balances FORCE ROW LEVEL SECURITY;
+CREATE POLICY user_balances_tenant_isolation ON user_balances
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE bets ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE bets ENABLE ROW LEVEL SECURITY;
+ALTER TABLE bets FORCE ROW LEVEL SECURITY;
+CREATE POLICY bets_tenant_isolation ON bets
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE streams ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE streams ENABLE ROW LEVEL SECURITY;
+ALTER TABLE streams FORCE ROW LEVEL SECURITY;
+CREATE POLICY streams_tenant_isolation ON streams
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE stream_owners ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE stream_owners ENABLE ROW LEVEL SECURITY;
+ALTER TABLE stream_owners FORCE ROW LEVEL SECURITY;
+CREATE POLICY stream_owners_tenant_isolation ON stream_owners
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE analytics_events ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE analytics_events ENABLE ROW LEVEL SECURITY;
+ALTER TABLE analytics_events FORCE ROW LEVEL SECURITY;
+CREATE POLICY analytics_events_tenant_isolation ON analytics_events
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE betting_opportunities ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE betting_opportunities ENABLE ROW LEVEL SECURITY;
+ALTER TABLE betting_opportunities FORCE ROW LEVEL SECURITY;
+CREATE POLICY betting_opportunities_tenant_isolation ON betting_opportunities
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE api_keys ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE api_keys ENABLE ROW LEVEL SECURITY;
+ALTER TABLE api_keys FORCE ROW LEVEL SECURITY;
+CREATE POLICY api_keys_tenant_isolation ON api_keys
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE webhook_subscriptions ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE webhook_subscriptions ENABLE ROW LEVEL SECURITY;
+ALTER TABLE webhook_subscriptions FORCE ROW LEVEL SECURITY;
+CREATE POLICY webhook_subscriptions_tenant_isolation ON webhook_subscriptions
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE webhook_deliveries ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default');
+ALTER TABLE webhook_deliveries ENABLE ROW LEVEL SECURITY;
+ALTER TABLE webhook_deliveries FORCE ROW LEVEL SECURITY;
+CREATE POLICY webhook_deliveries_tenant_isolation ON webhook_deliveries
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+-- Sign-in names are unique within a tenant, not across the cluster
+ALTER TABLE users DROP CONSTRAINT users_email_key;
+ALTER TABLE users DROP CONSTRAINT users_username_key;
+ALTER TABLE users ADD CONSTRAINT users_tenant_email_key UNIQUE (tenant_id, email);
+ALTER TABLE users ADD CONSTRAINT users_tenant_username_key UNIQUE (tenant_id, username);
+ALTER TABLE user_credentials DROP CONSTRAINT user_credentials_provider_provider_subject_key;
+ALTER TABLE user_credentials ADD CONSTRAINT user_credentials_tenant_provider_subject_key
+    UNIQUE (tenant_id, provider, provider_subject);
+
+CREATE INDEX idx_streams_tenant ON streams(tenant_id);
+CREATE INDEX idx_bets_tenant_user ON bets(tenant_id, user_id);
+CREATE INDEX idx_users_tenant ON users(tenant_id);
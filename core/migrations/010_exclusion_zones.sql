@@ -0,0 +1,14 @@
+-- Geolocation exclusion zones, loaded into memory at startup. Shared across tenants.
+
+CREATE TABLE exclusion_zones (
+    zone_id VARCHAR PRIMARY KEY,
+    center_lat DOUBLE PRECISION NOT NULL,
+    center_lon DOUBLE PRECISION NOT NULL,
+    radius_meters DOUBLE PRECISION NOT NULL CHECK (radius_meters > 0),
+    zone_type TEXT NOT NULL,
+    active_from TIMESTAMPTZ NOT NULL,
+    active_until TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_exclusion_zones_active_until ON exclusion_zones(active_until);
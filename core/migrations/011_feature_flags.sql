@@ -0,0 +1,14 @@
+-- Runtime feature flags. Shared across tenants; a flag can be limited to some of them with `tenants`.
+
+CREATE TABLE feature_flags (
+    name VARCHAR(100) PRIMARY KEY,
+    description TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    rollout_percentage DOUBLE PRECISION NOT NULL DEFAULT 0
+        CHECK (rollout_percentage >= 0 AND rollout_percentage <= 100),
+    user_ids TEXT[] NOT NULL DEFAULT '{}',
+    stream_ids TEXT[] NOT NULL DEFAULT '{}',
+    tenants TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,26 @@
+-- Every change a bet makes to a balance, written in the same transaction as
+-- the change itself, so balances can be audited and rebuilt.
+
+CREATE TABLE balance_ledger (
+    entry_id BIGSERIAL PRIMARY KEY,
+    user_id VARCHAR NOT NULL,
+    stream_id VARCHAR NOT NULL,
+    bet_id VARCHAR NOT NULL REFERENCES bets(id),
+    entry_type TEXT NOT NULL CHECK (entry_type IN ('bet_placed', 'bet_settled')),
+    -- Signed change to betting_balance: the stake leaving, or the payout arriving
+    amount NUMERIC(10,2) NOT NULL,
+    balance_after NUMERIC(10,2) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    FOREIGN KEY (user_id, stream_id) REFERENCES user_balances(user_id, stream_id)
+);
+
+CREATE INDEX idx_balance_ledger_balance ON balance_ledger(user_id, stream_id, created_at);
+CREATE INDEX idx_balance_ledger_bet ON balance_ledger(bet_id);
+
+ALTER TABLE balance_ledger ENABLE ROW LEVEL SECURITY;
+ALTER TABLE balance_ledger FORCE ROW LEVEL SECURITY;
+CREATE POLICY balance_ledger_tenant_isolation ON balance_ledger
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
@@ -0,0 +1,39 @@
+-- Settled bets move out of `bets` once they are old enough, so the table the
+-- engine writes to stays small. Bet history reads both tables.
+
+-- Rows written before the betting repository stored statuses and bet types as JSON strings
+UPDATE bets SET
+    status = lower(btrim(status, '"')),
+    bet_type = lower(btrim(bet_type, '"'));
+
+CREATE TABLE bets_archive (
+    id VARCHAR PRIMARY KEY,
+    user_id VARCHAR NOT NULL,
+    stream_id VARCHAR NOT NULL,
+    bet_type TEXT NOT NULL,
+    stake_amount NUMERIC(10,2) NOT NULL,
+    prediction JSONB NOT NULL,
+    status TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    resolution_deadline TIMESTAMPTZ NOT NULL,
+    resolution_result JSONB,
+    potential_payout NUMERIC(10,2) NOT NULL,
+    odds NUMERIC(10,4) NOT NULL,
+    tenant_id VARCHAR NOT NULL,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_bets_archive_user_created ON bets_archive(user_id, created_at);
+CREATE INDEX idx_bets_archive_tenant_user ON bets_archive(tenant_id, user_id);
+
+ALTER TABLE bets_archive ENABLE ROW LEVEL SECURITY;
+ALTER TABLE bets_archive FORCE ROW LEVEL SECURITY;
+CREATE POLICY bets_archive_tenant_isolation ON bets_archive
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+-- Ledger entries are kept for good and outlive the bet's row in `bets`
+ALTER TABLE balance_ledger DROP CONSTRAINT balance_ledger_bet_id_fkey;
+
+-- What the archival job scans for
+CREATE INDEX idx_bets_status_created ON bets(status, created_at);
@@ -0,0 +1,28 @@
+-- Domain events, written in the same transaction as the change they describe
+-- and relayed to webhooks and other consumers afterwards, so an event is never
+-- lost to a crash between the commit and its publication.
+
+CREATE TABLE outbox_events (
+    event_id VARCHAR PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    data JSONB NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Consumers that already have the event, so a retry only goes to the rest
+    completed_handlers TEXT[] NOT NULL DEFAULT '{}',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    published_at TIMESTAMPTZ,
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default')
+);
+
+CREATE INDEX idx_outbox_events_due ON outbox_events(next_attempt_at) WHERE published_at IS NULL;
+
+ALTER TABLE outbox_events ENABLE ROW LEVEL SECURITY;
+ALTER TABLE outbox_events FORCE ROW LEVEL SECURITY;
+CREATE POLICY outbox_events_tenant_isolation ON outbox_events
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+-- A relayed event is queued for each subscription once, even if the relay retries it
+CREATE UNIQUE INDEX idx_webhook_deliveries_event ON webhook_deliveries(subscription_id, event_id);
@@ -0,0 +1,13 @@
+-- Streams are kept here for good; Redis only caches them. Until now they
+-- lived in Redis alone, under keys that could expire. The pledge columns
+-- aren't set for streams created through the API.
+
+ALTER TABLE streams ALTER COLUMN activation_threshold DROP NOT NULL;
+ALTER TABLE streams ALTER COLUMN cost_per_viewer DROP NOT NULL;
+
+-- Why a stream in the error status failed
+ALTER TABLE streams ADD COLUMN status_detail TEXT;
+ALTER TABLE streams ADD COLUMN settings JSONB;
+ALTER TABLE streams ADD COLUMN analytics_enabled BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE streams ADD COLUMN viewer_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE streams ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
@@ -0,0 +1,60 @@
+-- Who wrote each row and how often it has changed, and deletion that can be
+-- undone, for the tables disputes are settled from. The core service sets
+-- `morphine.actor` on its connections to the caller of the request; writes
+-- with none set are background work, recorded as 'system'. Rows from before
+-- this migration have no `created_by`.
+
+CREATE FUNCTION morphine_current_actor() RETURNS TEXT AS $$
+    SELECT NULLIF(current_setting('morphine.actor', true), '')
+$$ LANGUAGE sql STABLE;
+
+CREATE FUNCTION morphine_audit_update() RETURNS trigger AS $$
+BEGIN
+    NEW.row_version := OLD.row_version + 1;
+    NEW.updated_by := COALESCE(morphine_current_actor(), 'system');
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE bets
+    ADD COLUMN deleted_at TIMESTAMPTZ,
+    ADD COLUMN created_by VARCHAR,
+    ADD COLUMN updated_by VARCHAR,
+    ADD COLUMN row_version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE bets ALTER COLUMN created_by SET DEFAULT COALESCE(morphine_current_actor(), 'system');
+CREATE TRIGGER bets_audit BEFORE UPDATE ON bets
+    FOR EACH ROW EXECUTE FUNCTION morphine_audit_update();
+
+-- Carried over when a bet is archived
+ALTER TABLE bets_archive
+    ADD COLUMN deleted_at TIMESTAMPTZ,
+    ADD COLUMN created_by VARCHAR,
+    ADD COLUMN updated_by VARCHAR,
+    ADD COLUMN row_version BIGINT NOT NULL DEFAULT 1;
+
+ALTER TABLE user_balances
+    ADD COLUMN deleted_at TIMESTAMPTZ,
+    ADD COLUMN created_by VARCHAR,
+    ADD COLUMN updated_by VARCHAR,
+    ADD COLUMN row_version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE user_balances ALTER COLUMN created_by SET DEFAULT COALESCE(morphine_current_actor(), 'system');
+CREATE TRIGGER user_balances_audit BEFORE UPDATE ON user_balances
+    FOR EACH ROW EXECUTE FUNCTION morphine_audit_update();
+
+ALTER TABLE streams
+    ADD COLUMN deleted_at TIMESTAMPTZ,
+    ADD COLUMN created_by VARCHAR,
+    ADD COLUMN updated_by VARCHAR,
+    ADD COLUMN row_version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE streams ALTER COLUMN created_by SET DEFAULT COALESCE(morphine_current_actor(), 'system');
+CREATE TRIGGER streams_audit BEFORE UPDATE ON streams
+    FOR EACH ROW EXECUTE FUNCTION morphine_audit_update();
+
+ALTER TABLE exclusion_zones
+    ADD COLUMN deleted_at TIMESTAMPTZ,
+    ADD COLUMN created_by VARCHAR,
+    ADD COLUMN updated_by VARCHAR,
+    ADD COLUMN row_version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE exclusion_zones ALTER COLUMN created_by SET DEFAULT COALESCE(morphine_current_actor(), 'system');
+CREATE TRIGGER exclusion_zones_audit BEFORE UPDATE ON exclusion_zones
+    FOR EACH ROW EXECUTE FUNCTION morphine_audit_update();
@@ -0,0 +1,47 @@
+-- Running totals per bettor, kept up to date by placement and settlement in
+-- the same transactions, so stats and leaderboards never scan `bets`.
+
+CREATE TABLE user_betting_stats (
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    user_id VARCHAR NOT NULL,
+    bets_placed INTEGER NOT NULL DEFAULT 0,
+    total_staked NUMERIC(14,2) NOT NULL DEFAULT 0,
+    bets_settled INTEGER NOT NULL DEFAULT 0,
+    bets_won INTEGER NOT NULL DEFAULT 0,
+    -- Stake on settled bets only, which ROI is measured against
+    settled_stake NUMERIC(14,2) NOT NULL DEFAULT 0,
+    total_returned NUMERIC(14,2) NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, user_id)
+);
+
+CREATE INDEX idx_user_betting_stats_staked ON user_betting_stats(tenant_id, total_staked DESC);
+
+ALTER TABLE user_betting_stats ENABLE ROW LEVEL SECURITY;
+ALTER TABLE user_betting_stats FORCE ROW LEVEL SECURITY;
+CREATE POLICY user_betting_stats_tenant_isolation ON user_betting_stats
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+-- Totals for the bets placed so far, archived ones included
+INSERT INTO user_betting_stats (
+    tenant_id, user_id, bets_placed, total_staked, bets_settled, bets_won, settled_stake, total_returned
+)
+SELECT
+    tenant_id,
+    user_id,
+    COUNT(*),
+    SUM(stake_amount),
+    COUNT(*) FILTER (WHERE resolution_result IS NOT NULL),
+    COUNT(*) FILTER (WHERE (resolution_result->>'won')::boolean),
+    COALESCE(SUM(stake_amount) FILTER (WHERE resolution_result IS NOT NULL), 0),
+    -- Only a payout above the stake is paid out, as in settlement
+    COALESCE(SUM((resolution_result->>'payout_amount')::numeric) FILTER (
+        WHERE (resolution_result->>'payout_amount')::numeric > stake_amount
+    ), 0)
+FROM (
+    SELECT tenant_id, user_id, stake_amount, resolution_result FROM bets WHERE deleted_at IS NULL
+    UNION ALL
+    SELECT tenant_id, user_id, stake_amount, resolution_result FROM bets_archive WHERE deleted_at IS NULL
+) AS placed
+GROUP BY tenant_id, user_id;
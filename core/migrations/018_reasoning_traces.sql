@@ -0,0 +1,24 @@
+-- Every evaluation of a bet, with the event it was evaluated against, so
+-- explanations and audit replays outlive the reasoning engine's cache.
+-- Payloads over the configured size are stored as a truncated preview,
+-- with `payloads_truncated` set.
+
+CREATE TABLE reasoning_traces (
+    evaluation_id UUID PRIMARY KEY,
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    bet_id VARCHAR NOT NULL,
+    event_snapshot JSONB NOT NULL,
+    context_snapshot JSONB NOT NULL,
+    trace JSONB NOT NULL,
+    outcome JSONB NOT NULL,
+    payloads_truncated BOOLEAN NOT NULL DEFAULT FALSE,
+    evaluated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_reasoning_traces_bet ON reasoning_traces(tenant_id, bet_id, evaluated_at DESC);
+
+ALTER TABLE reasoning_traces ENABLE ROW LEVEL SECURITY;
+ALTER TABLE reasoning_traces FORCE ROW LEVEL SECURITY;
+CREATE POLICY reasoning_traces_tenant_isolation ON reasoning_traces
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
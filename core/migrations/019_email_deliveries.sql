@@ -0,0 +1,32 @@
+-- Notification emails rendered from outbox events, one row per event per
+-- recipient, sent in the background with retries. Rows past max attempts
+-- are kept as the dead-letter queue.
+
+CREATE TABLE email_deliveries (
+    delivery_id VARCHAR PRIMARY KEY,
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    event_id VARCHAR NOT NULL,
+    template TEXT NOT NULL,
+    user_id VARCHAR NOT NULL,
+    recipient TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'sent', 'dead_lettered')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    sent_at TIMESTAMPTZ,
+    -- A relayed event is rendered for each recipient once, even if the relay retries it
+    UNIQUE (event_id, user_id)
+);
+
+CREATE INDEX idx_email_deliveries_due ON email_deliveries(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX idx_email_deliveries_user ON email_deliveries(tenant_id, user_id, created_at DESC);
+
+ALTER TABLE email_deliveries ENABLE ROW LEVEL SECURITY;
+ALTER TABLE email_deliveries FORCE ROW LEVEL SECURITY;
+CREATE POLICY email_deliveries_tenant_isolation ON email_deliveries
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
@@ -0,0 +1,39 @@
+-- Devices registered for mobile push, and which notifications each user
+-- wants pushed to them.
+
+CREATE TABLE push_devices (
+    device_id VARCHAR PRIMARY KEY,
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    user_id VARCHAR NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    platform TEXT NOT NULL CHECK (platform IN ('fcm', 'apns')),
+    -- A token belongs to one app install; registering it again moves it to the new user
+    token TEXT NOT NULL,
+    app_version VARCHAR,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_registered_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (tenant_id, platform, token)
+);
+
+CREATE INDEX idx_push_devices_user ON push_devices(tenant_id, user_id);
+
+-- No row means everything is on
+CREATE TABLE push_preferences (
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    user_id VARCHAR NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    bet_settled BOOLEAN NOT NULL DEFAULT TRUE,
+    stream_activated BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, user_id)
+);
+
+ALTER TABLE push_devices ENABLE ROW LEVEL SECURITY;
+ALTER TABLE push_devices FORCE ROW LEVEL SECURITY;
+CREATE POLICY push_devices_tenant_isolation ON push_devices
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE push_preferences ENABLE ROW LEVEL SECURITY;
+ALTER TABLE push_preferences FORCE ROW LEVEL SECURITY;
+CREATE POLICY push_preferences_tenant_isolation ON push_preferences
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
@@ -0,0 +1,66 @@
+-- Every change to a stream, bet or balance, appended by trigger in the same
+-- transaction as the change, so state as of any instant can be rebuilt when
+-- a bettor disputes what they saw. Each event holds the row as it was after
+-- the change. Changes to bookkeeping columns alone, such as a stream's
+-- viewer count or the audit columns, aren't recorded.
+
+CREATE TABLE state_events (
+    event_id BIGSERIAL PRIMARY KEY,
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    entity_type TEXT NOT NULL CHECK (entity_type IN ('stream', 'bet', 'balance')),
+    -- A balance's ID is `user_id:stream_id`
+    entity_id VARCHAR NOT NULL,
+    change TEXT NOT NULL CHECK (change IN ('created', 'updated', 'deleted', 'restored')),
+    state JSONB NOT NULL,
+    actor VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_actor(), 'system'),
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_state_events_entity ON state_events(tenant_id, entity_type, entity_id, event_id);
+CREATE INDEX idx_state_events_occurred ON state_events(tenant_id, occurred_at);
+
+-- TG_ARGV[0] is the entity type; any further arguments are columns whose changes alone aren't recorded
+CREATE FUNCTION morphine_record_state_event() RETURNS trigger AS $$
+DECLARE
+    ignored TEXT[] := ARRAY['created_by', 'updated_by', 'row_version'] || TG_ARGV[1:TG_NARGS - 1];
+    new_state JSONB := to_jsonb(NEW) - 'created_by' - 'updated_by' - 'row_version' - 'tenant_id';
+    event_change TEXT := 'created';
+BEGIN
+    IF TG_OP = 'UPDATE' THEN
+        IF (to_jsonb(OLD) - ignored) = (to_jsonb(NEW) - ignored) THEN
+            RETURN NULL;
+        END IF;
+        event_change := CASE
+            WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN 'deleted'
+            WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN 'restored'
+            ELSE 'updated'
+        END;
+    END IF;
+
+    INSERT INTO state_events (tenant_id, entity_type, entity_id, change, state)
+    VALUES (
+        NEW.tenant_id,
+        TG_ARGV[0],
+        CASE TG_ARGV[0]
+            WHEN 'balance' THEN (new_state->>'user_id') || ':' || (new_state->>'stream_id')
+            ELSE new_state->>'id'
+        END,
+        event_change,
+        new_state
+    );
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER streams_state_events AFTER INSERT OR UPDATE ON streams
+    FOR EACH ROW EXECUTE FUNCTION morphine_record_state_event('stream', 'viewer_count', 'updated_at');
+CREATE TRIGGER bets_state_events AFTER INSERT OR UPDATE ON bets
+    FOR EACH ROW EXECUTE FUNCTION morphine_record_state_event('bet');
+CREATE TRIGGER user_balances_state_events AFTER INSERT OR UPDATE ON user_balances
+    FOR EACH ROW EXECUTE FUNCTION morphine_record_state_event('balance', 'last_updated');
+
+ALTER TABLE state_events ENABLE ROW LEVEL SECURITY;
+ALTER TABLE state_events FORCE ROW LEVEL SECURITY;
+CREATE POLICY state_events_tenant_isolation ON state_events
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
@@ -0,0 +1,38 @@
+-- Bulk exports requested through the admin API, generated in the background
+-- by whichever instance claims them. The files themselves live in object
+-- storage; `files` records where.
+
+CREATE TABLE data_exports (
+    export_id VARCHAR PRIMARY KEY,
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    datasets TEXT[] NOT NULL,
+    format TEXT NOT NULL CHECK (format IN ('csv', 'parquet')),
+    -- Rows from range_start up to, not including, range_end
+    range_start TIMESTAMPTZ NOT NULL,
+    range_end TIMESTAMPTZ NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'running', 'completed', 'failed')),
+    -- One object per dataset: its key, row count and size
+    files JSONB NOT NULL DEFAULT '[]',
+    error TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- While running, until when the claiming instance holds it
+    lease_until TIMESTAMPTZ,
+    requested_by VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_actor(), 'system'),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+    CHECK (range_start < range_end)
+);
+
+CREATE INDEX idx_data_exports_due ON data_exports(created_at) WHERE status IN ('pending', 'running');
+CREATE INDEX idx_data_exports_tenant ON data_exports(tenant_id, created_at DESC);
+
+-- The ledger export reads by date across all balances
+CREATE INDEX idx_balance_ledger_created ON balance_ledger(created_at);
+
+ALTER TABLE data_exports ENABLE ROW LEVEL SECURITY;
+ALTER TABLE data_exports FORCE ROW LEVEL SECURITY;
+CREATE POLICY data_exports_tenant_isolation ON data_exports
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
@@ -0,0 +1,42 @@
+-- Append-only record of privileged and financial changes: who made each,
+-- and the thing changed before and after. Each tenant's entries form a hash
+-- chain; `hash` covers the entry and the previous entry's hash, so editing
+-- or removing an entry shows up when the chain is verified.
+
+CREATE TABLE audit_log (
+    seq BIGSERIAL PRIMARY KEY,
+    entry_id VARCHAR NOT NULL UNIQUE,
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    actor VARCHAR NOT NULL,
+    action TEXT NOT NULL,
+    target_type TEXT NOT NULL,
+    target_id VARCHAR NOT NULL,
+    before JSONB,
+    after JSONB,
+    correlation_id VARCHAR,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    prev_hash CHAR(64) NOT NULL,
+    hash CHAR(64) NOT NULL UNIQUE
+);
+
+CREATE INDEX idx_audit_log_tenant_seq ON audit_log(tenant_id, seq);
+CREATE INDEX idx_audit_log_target ON audit_log(target_type, target_id);
+CREATE INDEX idx_audit_log_actor ON audit_log(actor, occurred_at);
+
+-- Entries are never changed once written, not even by the service
+CREATE FUNCTION morphine_audit_log_immutable() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_immutable BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION morphine_audit_log_immutable();
+CREATE TRIGGER audit_log_no_truncate BEFORE TRUNCATE ON audit_log
+    FOR EACH STATEMENT EXECUTE FUNCTION morphine_audit_log_immutable();
+
+ALTER TABLE audit_log ENABLE ROW LEVEL SECURITY;
+ALTER TABLE audit_log FORCE ROW LEVEL SECURITY;
+CREATE POLICY audit_log_tenant_isolation ON audit_log
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
@@ -0,0 +1,68 @@
+-- Anomalous betting raised by the risk monitor, the per-bettor model state it
+-- raises from, and the accounts frozen pending review of an alert.
+
+CREATE TABLE risk_user_models (
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    user_id VARCHAR NOT NULL,
+    -- Decayed win statistics and the current streak, as the monitor serializes them
+    state JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, user_id)
+);
+
+CREATE TABLE risk_alerts (
+    alert_id VARCHAR PRIMARY KEY,
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    signal TEXT NOT NULL CHECK (signal IN ('odds_exploitation', 'improbable_win_streak', 'coordinated_stakes')),
+    severity TEXT NOT NULL CHECK (severity IN ('warning', 'critical')),
+    -- Repeats of the same anomaly share this, and are held back for the cooldown
+    dedup_key VARCHAR NOT NULL,
+    user_ids TEXT[] NOT NULL,
+    stream_id VARCHAR,
+    summary TEXT NOT NULL,
+    score DOUBLE PRECISION NOT NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    froze_accounts BOOLEAN NOT NULL DEFAULT FALSE,
+    status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'confirmed', 'dismissed')),
+    raised_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    reviewed_by VARCHAR,
+    reviewed_at TIMESTAMPTZ,
+    review_note TEXT
+);
+
+CREATE INDEX idx_risk_alerts_tenant ON risk_alerts(tenant_id, raised_at DESC);
+CREATE INDEX idx_risk_alerts_dedup ON risk_alerts(tenant_id, dedup_key, raised_at DESC);
+
+CREATE TABLE account_freezes (
+    freeze_id BIGSERIAL PRIMARY KEY,
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    user_id VARCHAR NOT NULL,
+    alert_id VARCHAR REFERENCES risk_alerts(alert_id),
+    frozen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    released_at TIMESTAMPTZ,
+    released_by VARCHAR
+);
+
+-- At most one freeze in force per account; placement checks it on every bet
+CREATE UNIQUE INDEX idx_account_freezes_active ON account_freezes(tenant_id, user_id) WHERE released_at IS NULL;
+
+-- Coordination is looked for among recent bets on the same stream
+CREATE INDEX idx_bets_stream_created ON bets(stream_id, created_at);
+
+ALTER TABLE risk_user_models ENABLE ROW LEVEL SECURITY;
+ALTER TABLE risk_user_models FORCE ROW LEVEL SECURITY;
+CREATE POLICY risk_user_models_tenant_isolation ON risk_user_models
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE risk_alerts ENABLE ROW LEVEL SECURITY;
+ALTER TABLE risk_alerts FORCE ROW LEVEL SECURITY;
+CREATE POLICY risk_alerts_tenant_isolation ON risk_alerts
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
+
+ALTER TABLE account_freezes ENABLE ROW LEVEL SECURITY;
+ALTER TABLE account_freezes FORCE ROW LEVEL SECURITY;
+CREATE POLICY account_freezes_tenant_isolation ON account_freezes
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
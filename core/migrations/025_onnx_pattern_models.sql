@@ -0,0 +1,4 @@
+-- An ONNX model a pattern model version runs in the intuition layer: its
+-- file under the model directory, digest, input fields and output names
+
+ALTER TABLE intuition_pattern_models ADD COLUMN onnx JSONB;
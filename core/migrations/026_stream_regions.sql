@@ -0,0 +1,4 @@
+-- The region a stream's ingest lands in, set by the instance that activates
+-- it; NULL for streams not activated since regions were introduced
+
+ALTER TABLE streams ADD COLUMN region VARCHAR;
@@ -0,0 +1,17 @@
+-- Streams whose markets are closed to new bets because the analytics behind
+-- them can't be trusted. A row exists only while the suspension is in force.
+
+CREATE TABLE market_suspensions (
+    stream_id VARCHAR PRIMARY KEY,
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    reason TEXT NOT NULL,
+    -- Of the decision that suspended it; NULL when decisions stopped arriving
+    confidence DOUBLE PRECISION,
+    suspended_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE market_suspensions ENABLE ROW LEVEL SECURITY;
+ALTER TABLE market_suspensions FORCE ROW LEVEL SECURITY;
+CREATE POLICY market_suspensions_tenant_isolation ON market_suspensions
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
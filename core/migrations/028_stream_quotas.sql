@@ -0,0 +1,23 @@
+-- Admin overrides of how many streams a creator or a whole tenant may have
+-- active at once; without one, `[stream_quotas]` applies.
+
+CREATE TABLE stream_quotas (
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    scope TEXT NOT NULL CHECK (scope IN ('creator', 'tenant')),
+    -- The creator's user ID, or the tenant ID for the tenant's own quota
+    subject_id VARCHAR NOT NULL,
+    -- 0 means no limit
+    max_active INTEGER NOT NULL CHECK (max_active >= 0),
+    updated_by VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_actor(), 'system'),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, scope, subject_id)
+);
+
+-- Quota checks count a creator's or tenant's active streams on every activation
+CREATE INDEX idx_streams_tenant_status ON streams(tenant_id, status) WHERE deleted_at IS NULL;
+
+ALTER TABLE stream_quotas ENABLE ROW LEVEL SECURITY;
+ALTER TABLE stream_quotas FORCE ROW LEVEL SECURITY;
+CREATE POLICY stream_quotas_tenant_isolation ON stream_quotas
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
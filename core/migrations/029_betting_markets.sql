@@ -0,0 +1,27 @@
+-- Short-lived markets the orchestrator opens from confident event
+-- predictions. Rows are kept after they close, as a record of what was offered.
+
+CREATE TABLE betting_markets (
+    market_id VARCHAR PRIMARY KEY,
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    stream_id VARCHAR NOT NULL,
+    -- The decision whose prediction opened it
+    decision_id VARCHAR NOT NULL,
+    event TEXT NOT NULL,
+    bet_type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    time_window_seconds BIGINT NOT NULL,
+    probability DOUBLE PRECISION NOT NULL,
+    suggested_odds DOUBLE PRECISION NOT NULL,
+    confidence DOUBLE PRECISION NOT NULL,
+    opens_at TIMESTAMPTZ NOT NULL,
+    closes_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_betting_markets_stream ON betting_markets(stream_id, closes_at);
+
+ALTER TABLE betting_markets ENABLE ROW LEVEL SECURITY;
+ALTER TABLE betting_markets FORCE ROW LEVEL SECURITY;
+CREATE POLICY betting_markets_tenant_isolation ON betting_markets
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
@@ -0,0 +1,13 @@
+-- Emergency stops: suspensions an admin puts on a stream by hand, for a
+-- compromised feed or a misfiring model. Unlike automatic suspensions they
+-- also hold back settlement of the stream's bets, and only an admin lifts them.
+
+ALTER TABLE market_suspensions ADD COLUMN emergency BOOLEAN NOT NULL DEFAULT FALSE;
+-- What the stop did with the bets open at the time; NULL for automatic suspensions
+ALTER TABLE market_suspensions ADD COLUMN open_bets TEXT CHECK (open_bets IN ('void', 'freeze'));
+ALTER TABLE market_suspensions ADD COLUMN suspended_by VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_actor(), 'system');
+
+-- A voided bet's stake going back to the balance
+ALTER TABLE balance_ledger DROP CONSTRAINT balance_ledger_entry_type_check;
+ALTER TABLE balance_ledger ADD CONSTRAINT balance_ledger_entry_type_check
+    CHECK (entry_type IN ('bet_placed', 'bet_settled', 'bet_voided'));
@@ -0,0 +1,5 @@
+-- The location verification that cleared the bettor to place each bet, for
+-- audit; NULL for bets placed before it was required or while it was off.
+
+ALTER TABLE bets ADD COLUMN location_verification_id VARCHAR;
+ALTER TABLE bets_archive ADD COLUMN location_verification_id VARCHAR;
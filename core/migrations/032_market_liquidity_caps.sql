@@ -0,0 +1,18 @@
+-- Admin overrides of how much a stream's open bets may owe if they all win;
+-- without one, `[betting] market_liquidity` applies.
+
+CREATE TABLE market_liquidity_caps (
+    tenant_id VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_tenant(), 'default'),
+    stream_id VARCHAR NOT NULL,
+    -- 0 means no limit
+    max_liability NUMERIC(12,2) NOT NULL CHECK (max_liability >= 0),
+    updated_by VARCHAR NOT NULL DEFAULT COALESCE(morphine_current_actor(), 'system'),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, stream_id)
+);
+
+ALTER TABLE market_liquidity_caps ENABLE ROW LEVEL SECURITY;
+ALTER TABLE market_liquidity_caps FORCE ROW LEVEL SECURITY;
+CREATE POLICY market_liquidity_caps_tenant_isolation ON market_liquidity_caps
+    USING (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant())
+    WITH CHECK (morphine_current_tenant() IS NULL OR tenant_id = morphine_current_tenant());
@@ -0,0 +1,5 @@
+-- Suspensions are kept per tenant, like market_liquidity_caps, so one
+-- tenant's stop can't replace another's on a stream with the same ID.
+
+ALTER TABLE market_suspensions DROP CONSTRAINT market_suspensions_pkey;
+ALTER TABLE market_suspensions ADD PRIMARY KEY (tenant_id, stream_id);
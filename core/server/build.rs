@@ -0,0 +1,9 @@
+// Generates the gRPC server stubs from the protobuf definitions shared with
+// other services in the top-level proto directory.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=../../proto");
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&["../../proto/morphine/core/v1/core.proto"], &["../../proto"])?;
+    Ok(())
+}
@@ -0,0 +1,282 @@
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::auth::{self, api_keys::API_KEY_HEADER};
+use crate::orchestrator::{feedback, onnx, opportunities, pattern_models, replay, windowing};
+use crate::{analytics, analytics_client, audit, betting, exports, features, push, region, reload, risk, stream, timeline, webhooks};
+
+/// OpenAPI document for the core HTTP API, served with Swagger UI at `/api/docs`.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Morphine Core API",
+        description = "Streams, betting, geolocation and the metacognitive orchestrator.",
+    ),
+    paths(
+        crate::health_check,
+        crate::liveness,
+        crate::readiness,
+        crate::prometheus_metrics,
+        crate::system_health,
+        crate::register_user,
+        crate::login_user,
+        crate::verify_email,
+        crate::resend_email_verification,
+        crate::oauth_sign_in,
+        crate::get_current_user,
+        crate::update_current_profile,
+        crate::list_push_devices,
+        crate::register_push_device,
+        crate::remove_push_device,
+        crate::list_user_sessions,
+        crate::revoke_user_session,
+        crate::get_push_preferences,
+        crate::set_push_preferences,
+        crate::list_streams,
+        crate::get_stream,
+        crate::route_to_region,
+        crate::start_stream,
+        crate::stop_stream,
+        crate::stream_status,
+        crate::place_bet,
+        crate::validate_bet_slip,
+        crate::get_open_markets,
+        crate::get_balance,
+        crate::get_bet_history,
+        crate::get_betting_activity,
+        crate::graphql::execute,
+        crate::get_user_betting_stats,
+        crate::get_leaderboard,
+        crate::get_bet_types,
+        crate::resolve_bet,
+        crate::resolve_bets,
+        crate::get_bet_explanation,
+        crate::list_bet_evaluations,
+        crate::verify_location,
+        crate::start_location_session,
+        crate::get_location_history,
+        crate::add_exclusion_zone,
+        crate::analytics_update,
+        crate::get_analytics_history,
+        crate::get_analytics_archive,
+        crate::record_decision_outcome,
+        crate::get_orchestrator_decisions,
+        crate::stream_orchestrator_decisions,
+        crate::get_weight_history,
+        crate::query_knowledge,
+        crate::list_pattern_models,
+        crate::get_pattern_model,
+        crate::snapshot_pattern_model,
+        crate::activate_pattern_model,
+        crate::rollback_pattern_model,
+        crate::export_dream_scenarios,
+        crate::trigger_dream_cycle,
+        crate::backtest_dream_scenarios,
+        crate::stream_admin_events,
+        crate::list_ai_systems,
+        crate::set_system_weight,
+        crate::pause_stream_processing,
+        crate::resume_stream_processing,
+        crate::get_stream_window,
+        crate::set_stream_window,
+        crate::assign_stream_owner,
+        crate::get_admin_overview,
+        crate::export_financial_snapshot,
+        crate::request_export,
+        crate::list_exports,
+        crate::get_export,
+        crate::get_fairness_report,
+        crate::emergency_stop_stream,
+        crate::lift_emergency_stop,
+        crate::list_liquidity_caps,
+        crate::get_market_liquidity,
+        crate::set_liquidity_cap,
+        crate::clear_liquidity_cap,
+        crate::download_export,
+        crate::list_audit_entries,
+        crate::verify_audit_log,
+        crate::list_risk_alerts,
+        crate::review_risk_alert,
+        crate::release_account_freeze,
+        crate::get_state_as_of,
+        crate::get_entity_history,
+        crate::verify_frame_hash,
+        crate::rerun_detection,
+        crate::reload_config,
+        crate::dump_heap_profile,
+        crate::get_feature_flags,
+        crate::list_feature_flags,
+        crate::set_feature_flag,
+        crate::delete_feature_flag,
+        crate::list_stream_quotas,
+        crate::set_tenant_stream_quota,
+        crate::clear_tenant_stream_quota,
+        crate::set_creator_stream_quota,
+        crate::clear_creator_stream_quota,
+        crate::get_creator_quota,
+        crate::get_queue_summaries,
+        crate::get_recent_alerts,
+        crate::start_context_replay,
+        crate::get_context_replay,
+        crate::cancel_context_replay,
+        crate::list_api_keys,
+        crate::create_api_key,
+        crate::rotate_api_key,
+        crate::revoke_api_key,
+        crate::list_webhook_subscriptions,
+        crate::create_webhook_subscription,
+        crate::deactivate_webhook_subscription,
+        crate::list_webhook_deliveries,
+        crate::retry_webhook_delivery,
+        crate::websocket_handler,
+    ),
+    components(schemas(
+        crate::SystemHealth,
+        crate::AdminOverview,
+        crate::StreamOverview,
+        crate::DependencyHealth,
+        crate::Readiness,
+        crate::ComponentHealth,
+        crate::ExclusionZoneRequest,
+        crate::DreamBacktestRequest,
+        crate::LoginRequest,
+        crate::VerifyEmailRequest,
+        crate::StreamOwnerRequest,
+        crate::StreamQuotaRequest,
+        crate::EmergencyStopRequest,
+        crate::LiquidityCapRequest,
+        crate::RotateApiKeyRequest,
+        crate::SystemWeightRequest,
+        crate::CreateStreamRequest,
+        crate::PlaceBetRequest,
+        crate::BetResponse,
+        crate::BetSlipRequest,
+        crate::BetSlipLine,
+        crate::BetSlipValidation,
+        crate::SlipLineDiagnostics,
+        crate::SlipProblem,
+        crate::SlipCheck,
+        crate::BalanceSummary,
+        crate::ResolveBetRequest,
+        crate::ResolveBetsRequest,
+        crate::BatchResolution,
+        crate::LocationVerificationRequest,
+        crate::VerifyFrameHashRequest,
+        analytics::FrameInfo,
+        analytics::DetectionsEvent,
+        analytics::DetectedObject,
+        analytics::BoundingBox,
+        analytics::PoseEvent,
+        analytics::Pose,
+        analytics::Keypoint,
+        analytics::MotionEvent,
+        analytics::SpeedEvent,
+        analytics_client::FrameHashVerification,
+        analytics_client::Clip,
+        analytics_client::DetectionRerun,
+        analytics_client::ClipDetection,
+        timeline::StateAsOf,
+        timeline::StateEvent,
+        timeline::EntityType,
+        auth::Role,
+        auth::User,
+        auth::ApiKey,
+        auth::api_keys::ApiKeyScope,
+        auth::api_keys::IssuedApiKey,
+        auth::api_keys::NewApiKey,
+        auth::users::UserProfile,
+        auth::users::Registration,
+        auth::users::ProfileUpdate,
+        auth::users::OAuthIdentity,
+        auth::sessions::UserSession,
+        auth::sessions::SessionKind,
+        push::PushPlatform,
+        push::PushDevice,
+        push::NewPushDevice,
+        push::PushPreferences,
+        feedback::OutcomeFeedback,
+        feedback::FeedbackSource,
+        pattern_models::NewPatternModel,
+        onnx::OnnxModelSpec,
+        opportunities::BettingOpportunity,
+        opportunities::MarketTemplate,
+        windowing::WindowKind,
+        windowing::WindowPolicy,
+        replay::ReplayRequest,
+        webhooks::WebhookEventType,
+        webhooks::WebhookSubscription,
+        webhooks::IssuedWebhookSubscription,
+        webhooks::NewWebhookSubscription,
+        webhooks::DeliveryStatus,
+        webhooks::WebhookDelivery,
+        exports::ExportRequest,
+        exports::ExportJob,
+        betting::fairness::FairnessReport,
+        betting::fairness::MarketFairness,
+        betting::fairness::ProbabilityBand,
+        betting::suspension::EmergencyStop,
+        betting::liquidity::LiquidityCap,
+        betting::liquidity::MarketLiquidity,
+        betting::suspension::OpenBetPolicy,
+        exports::ExportFile,
+        exports::ExportDataset,
+        exports::ExportFormat,
+        exports::ExportStatus,
+        audit::AuditAction,
+        audit::AuditEntry,
+        audit::ChainVerification,
+        risk::RiskAlert,
+        risk::RiskSignal,
+        risk::RiskAlertStatus,
+        risk::AlertReview,
+        region::GeoRoutes,
+        region::RegionRoute,
+        stream::quotas::QuotaScope,
+        stream::quotas::QuotaOverride,
+        stream::quotas::QuotaUsage,
+        reload::ReloadReport,
+        features::FeatureFlag,
+        features::FeatureFlagUpdate,
+    )),
+    tags(
+        (name = "health", description = "Liveness and metrics"),
+        (name = "auth", description = "Registration, login and email verification"),
+        (name = "users", description = "The signed-in user's account"),
+        (name = "streams", description = "Stream lifecycle"),
+        (name = "betting", description = "Bets and balances"),
+        (name = "graphql", description = "Streams, activity, odds and balances in one query"),
+        (name = "geolocation", description = "Location verification and exclusion zones"),
+        (name = "analytics", description = "Analytics ingestion from the vision service"),
+        (name = "orchestrator", description = "Decisions, feedback, knowledge and pattern models"),
+        (name = "admin", description = "Orchestrator administration"),
+        (name = "features", description = "Runtime feature flags and their rollout"),
+        (name = "webhooks", description = "Outbound event subscriptions and their delivery log"),
+        (name = "websocket", description = "Live stream updates"),
+    ),
+    modifiers(&SecurityAddon),
+)]
+pub struct ApiDoc;
+
+// The names here are the ones handlers list under `security(...)`
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(
+                    HttpBuilder::new()
+                        .scheme(HttpAuthScheme::Bearer)
+                        .bearer_format("JWT")
+                        .description(Some("A user token, or the admin token for admin endpoints"))
+                        .build(),
+                ),
+            );
+            components.add_security_scheme(
+                "api_key",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))),
+            );
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use hmac::{Hmac, Mac};
+use prometheus::{Encoder, IntCounter, IntCounterVec, Opts, Registry, TextEncoder};
+use prost::Message;
+use sha2::Sha256;
+use tokio::net::UdpSocket;
+use tracing::{debug, error, info};
+
+use crate::analytics::AnalyticsEvent;
+use crate::cache::{BoundedCache, CacheStats};
+use crate::config::DatagramIngestConfig;
+use crate::grpc::proto::AnalyticsDatagram;
+use crate::runtime;
+use crate::shutdown::Shutdown;
+use crate::tenant::{self, Tenant};
+use crate::validation::ValidationErrors;
+use crate::{route_analytics, AppState};
+
+type MetricsError = Box<dyn std::error::Error + Send + Sync>;
+
+// Largest payload a UDP datagram can carry
+const MAX_DATAGRAM_BYTES: usize = 65_507;
+
+/// Checks analytics datagrams before they're routed: the signature, then the
+/// sequence, so a lost frame costs one gap in the count and a late or
+/// repeated one is dropped rather than processed out of order.
+pub struct DatagramIngest {
+    secret: Vec<u8>,
+    reorder_window: u64,
+    // Highest sequence seen per stream
+    sequences: BoundedCache<String, u64>,
+    // The tenant each stream belongs to, which never changes once it's created
+    tenants: BoundedCache<String, Tenant>,
+    registry: Registry,
+    frames: IntCounterVec,
+    lost: IntCounter,
+    resets: IntCounter,
+}
+
+enum Sequence {
+    // The first frame heard from the stream, or the first since its count restarted
+    Fresh,
+    Next { lost: u64 },
+    Stale,
+}
+
+impl DatagramIngest {
+    pub fn new(config: &DatagramIngestConfig) -> Result<Self, MetricsError> {
+        let registry = Registry::new_custom(Some("morphine".to_string()), None)?;
+
+        let frames = IntCounterVec::new(
+            Opts::new("datagram_frames_total", "Analytics datagrams received, by what became of them"),
+            &["result"],
+        )?;
+        let lost = IntCounter::new(
+            "datagram_frames_lost_total",
+            "Analytics datagrams missing from a stream's sequence",
+        )?;
+        let resets = IntCounter::new(
+            "datagram_sequence_resets_total",
+            "Streams whose producer started its sequence again",
+        )?;
+
+        registry.register(Box::new(frames.clone()))?;
+        registry.register(Box::new(lost.clone()))?;
+        registry.register(Box::new(resets.clone()))?;
+
+        Ok(Self {
+            secret: config.secret.clone().unwrap_or_default().into_bytes(),
+            reorder_window: config.reorder_window,
+            sequences: BoundedCache::new(
+                "datagram_sequences",
+                config.max_streams,
+                Some(Duration::from_secs(config.stream_idle_secs)),
+            ),
+            tenants: BoundedCache::new(
+                "datagram_tenants",
+                config.max_streams,
+                Some(Duration::from_secs(config.stream_idle_secs)),
+            ),
+            registry,
+            frames,
+            lost,
+            resets,
+        })
+    }
+
+    pub fn cache_stats(&self) -> Vec<CacheStats> {
+        vec![self.sequences.stats(), self.tenants.stats()]
+    }
+
+    /// Encodes the datagram counters in the Prometheus text exposition format.
+    pub fn encode(&self) -> Result<String, MetricsError> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    // A datagram carries no tenant, so it's routed as the one its stream was created in
+    async fn tenant(&self, state: &AppState, stream_id: &str) -> anyhow::Result<Option<Tenant>> {
+        let key = stream_id.to_string();
+        if let Some(tenant) = self.tenants.get(&key) {
+            return Ok(Some(tenant));
+        }
+        let tenant = state.stream_manager.stream_tenant(stream_id).await?;
+        if let Some(tenant) = &tenant {
+            self.tenants.insert(key, tenant.clone());
+        }
+        Ok(tenant)
+    }
+
+    // The frame the datagram carries, or the result it's counted under if it's dropped
+    fn accept(&self, bytes: &[u8]) -> Result<(String, AnalyticsEvent), &'static str> {
+        let datagram = AnalyticsDatagram::decode(bytes).map_err(|_| "malformed")?;
+        if !self.signed(&datagram) {
+            return Err("bad_signature");
+        }
+        if datagram.stream_id.is_empty() {
+            return Err("invalid");
+        }
+
+        // Parsed before the sequence is recorded, so a bad frame doesn't open a gap of its own
+        let event = serde_json::from_str::<AnalyticsEvent>(&datagram.payload_json).map_err(|_| "invalid")?;
+        ValidationErrors::collect(&event).map_err(|_| "invalid")?;
+
+        match self.sequence(&datagram.stream_id, datagram.sequence) {
+            Sequence::Stale => return Err("stale"),
+            Sequence::Next { lost } => self.lost.inc_by(lost),
+            Sequence::Fresh => {}
+        }
+        Ok((datagram.stream_id, event))
+    }
+
+    fn signed(&self, datagram: &AnalyticsDatagram) -> bool {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC takes any key length");
+        mac.update(format!("{}\n{}\n{}", datagram.stream_id, datagram.sequence, datagram.payload_json).as_bytes());
+        mac.verify_slice(&datagram.signature).is_ok()
+    }
+
+    // Only the receive loop records sequences, so reading then writing doesn't race
+    fn sequence(&self, stream_id: &str, sequence: u64) -> Sequence {
+        let key = stream_id.to_string();
+        let verdict = match self.sequences.get(&key) {
+            None => Sequence::Fresh,
+            Some(last) if sequence > last => Sequence::Next { lost: sequence - last - 1 },
+            Some(last) if last - sequence < self.reorder_window => return Sequence::Stale,
+            Some(_) => {
+                self.resets.inc();
+                Sequence::Fresh
+            }
+        };
+        self.sequences.insert(key, sequence);
+        verdict
+    }
+}
+
+/// Receives analytics datagrams until shutdown. Each accepted frame is routed
+/// like one posted to the REST endpoint, on a task of its own so a slow
+/// store never holds up the socket.
+pub async fn serve(
+    state: AppState,
+    ingest: Arc<DatagramIngest>,
+    address: SocketAddr,
+    shutdown: Shutdown,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let socket = UdpSocket::bind(address).await?;
+    info!("Analytics datagram listener on {}", address);
+
+    let mut buffer = vec![0u8; MAX_DATAGRAM_BYTES];
+    loop {
+        let (length, peer) = tokio::select! {
+            received = socket.recv_from(&mut buffer) => received?,
+            _ = shutdown.triggered() => break,
+        };
+
+        let (stream_id, event) = match ingest.accept(&buffer[..length]) {
+            Ok(frame) => frame,
+            Err(result) => {
+                debug!("Dropped analytics datagram from {}: {}", peer, result);
+                ingest.frames.with_label_values(&[result]).inc();
+                continue;
+            }
+        };
+
+        let state = state.clone();
+        let ingest = ingest.clone();
+        runtime::spawn("datagram:route", async move {
+            let result = match ingest.tenant(&state, &stream_id).await {
+                Ok(Some(tenant)) => match tenant::scope(tenant, route_analytics(&state, &stream_id, event)).await {
+                    Ok(()) => "accepted",
+                    Err(e) => {
+                        error!("Failed to process analytics for stream {}: {}", stream_id, e);
+                        "failed"
+                    }
+                },
+                Ok(None) => {
+                    debug!("Dropped analytics datagram for unknown stream {}", stream_id);
+                    "unknown_stream"
+                }
+                Err(e) => {
+                    error!("Failed to look up the tenant of stream {}: {}", stream_id, e);
+                    "failed"
+                }
+            };
+            ingest.frames.with_label_values(&[result]).inc();
+        });
+    }
+
+    info!("Analytics datagram listener stopped");
+    Ok(())
+}
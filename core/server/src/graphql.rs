@@ -0,0 +1,456 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::Arc;
+use async_graphql::dataloader::{DataLoader, HashMapCache, Loader};
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Enum, Error, Object, Schema, SimpleObject, ID};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::extract::State;
+use axum::response::Html;
+use axum::Extension;
+use chrono::{DateTime, Utc};
+use futures::future::{try_join_all, BoxFuture};
+use tokio::task::JoinHandle;
+use tracing::error;
+
+use crate::auth::{Principal, Role};
+use crate::betting::{self, BettingEngine, OpenBook, UserBalance};
+use crate::features::{self, FlagContext};
+use crate::pagination::{PageRequest, DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT};
+use crate::stream::{self, StreamActivity, StreamInfo, StreamManager};
+use crate::tenant::{self, Tenant};
+use crate::{runtime, AppState, MAX_BET_WINDOW_SECS};
+
+// A stream page is three levels deep; anything much deeper or costlier is refused
+const MAX_DEPTH: usize = 8;
+const MAX_COMPLEXITY: usize = 1_000;
+// The window odds are quoted for when the query doesn't say
+const DEFAULT_ODDS_WINDOW_SECS: u32 = 60;
+
+pub type MorphineSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// The GraphQL schema: the same streams, activity, odds and balances as the
+/// REST routes, for pages that would otherwise make a request for each.
+pub fn schema() -> MorphineSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/graphql",
+    tag = "graphql",
+    request_body(content = Object, content_type = "application/json", description = "A GraphQL query, with its variables and operation name"),
+    responses(
+        (status = 200, description = "The query's data and any field errors", body = Object),
+    ),
+)]
+pub async fn execute(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    principal: Option<Extension<Principal>>,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    let mut request = with_loaders(request.into_inner(), &state, tenant).data(state.clone());
+    if let Some(Extension(principal)) = principal {
+        request = request.data(principal);
+    }
+    state.graphql_schema.execute(request).await.into()
+}
+
+/// GraphiQL, for trying queries in a browser.
+pub async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/api/graphql").finish())
+}
+
+// Loaders last one request, so each stream, book and balance is fetched once
+// per query however many fields ask for it
+fn with_loaders(request: async_graphql::Request, state: &AppState, tenant: Tenant) -> async_graphql::Request {
+    request
+        .data(DataLoader::with_cache(
+            StreamLoader(state.stream_manager.clone()),
+            spawner(tenant.clone()),
+            HashMapCache::default(),
+        ))
+        .data(DataLoader::with_cache(
+            ActivityLoader(state.betting_engine.clone()),
+            spawner(tenant.clone()),
+            HashMapCache::default(),
+        ))
+        .data(DataLoader::with_cache(
+            OpenBookLoader(state.betting_engine.clone()),
+            spawner(tenant.clone()),
+            HashMapCache::default(),
+        ))
+        .data(DataLoader::with_cache(
+            BalanceLoader(state.betting_engine.clone()),
+            spawner(tenant),
+            HashMapCache::default(),
+        ))
+}
+
+// Batches run on tasks of their own, which need the request's tenant for its keys and rows
+fn spawner(tenant: Tenant) -> impl Fn(BoxFuture<'static, ()>) -> JoinHandle<()> + Send + Sync + 'static {
+    move |batch| runtime::spawn("graphql:dataloader", tenant::scope(tenant.clone(), batch))
+}
+
+// Logs a failure and reports it without its detail, as the REST handlers do
+fn internal<E: std::fmt::Display>(what: &'static str) -> impl FnOnce(E) -> Error {
+    move |e| {
+        error!("GraphQL failed to load {}: {}", what, e);
+        Error::new("Internal server error")
+    }
+}
+
+// The caller, if they may see their own balance and bets; an anonymous
+// visitor's page leaves those fields null rather than failing
+fn bettor<'a>(ctx: &Context<'a>) -> Option<&'a Principal> {
+    ctx.data_opt::<Principal>()
+        .filter(|principal| principal.is_admin() || principal.has_role(Role::Bettor))
+}
+
+fn page_limit(first: Option<usize>) -> usize {
+    first.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT)
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// A stream by ID, or null if there's no such stream.
+    async fn stream(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<Option<Stream>> {
+        let stream = ctx.data_unchecked::<DataLoader<StreamLoader>>()
+            .load_one(id.to_string())
+            .await
+            .map_err(internal("stream"))?;
+        Ok(stream.map(Stream))
+    }
+
+    /// Streams, newest first.
+    async fn streams(&self, ctx: &Context<'_>, first: Option<usize>) -> async_graphql::Result<Vec<Stream>> {
+        let state = ctx.data_unchecked::<AppState>();
+        let mut streams = state.stream_manager.list_streams().await.map_err(internal("streams"))?;
+        streams.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        streams.truncate(page_limit(first));
+        Ok(streams.into_iter().map(Stream).collect())
+    }
+}
+
+/// A stream and everything its page shows. Fields that cost a lookup are
+/// only resolved when asked for, and batched across the streams in a query.
+pub struct Stream(StreamInfo);
+
+#[Object]
+impl Stream {
+    async fn id(&self) -> ID {
+        ID(self.0.id.clone())
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn status(&self) -> StreamState {
+        StreamState::from(&self.0.status)
+    }
+
+    /// Why the stream failed, when `status` is `ERROR`.
+    async fn error(&self) -> Option<&str> {
+        match &self.0.status {
+            stream::StreamStatus::Error(reason) => Some(reason),
+            _ => None,
+        }
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn analytics_enabled(&self) -> bool {
+        self.0.analytics_enabled
+    }
+
+    async fn betting_enabled(&self) -> bool {
+        self.0.settings.enable_betting
+    }
+
+    async fn viewers(&self) -> u32 {
+        self.0.viewer_count
+    }
+
+    async fn max_viewers(&self) -> Option<u32> {
+        self.0.settings.max_viewers
+    }
+
+    /// Recent activity, newest first.
+    async fn activity(&self, ctx: &Context<'_>, first: Option<usize>) -> async_graphql::Result<Vec<Activity>> {
+        let activity = ctx.data_unchecked::<DataLoader<ActivityLoader>>()
+            .load_one(self.0.id.clone())
+            .await
+            .map_err(internal("stream activity"))?
+            .unwrap_or_default();
+        Ok(activity.into_iter().take(page_limit(first)).map(Activity::from).collect())
+    }
+
+    /// Bets on the stream still awaiting settlement.
+    async fn open_book(&self, ctx: &Context<'_>) -> async_graphql::Result<Book> {
+        let book = ctx.data_unchecked::<DataLoader<OpenBookLoader>>()
+            .load_one(self.0.id.clone())
+            .await
+            .map_err(internal("open book"))?
+            .unwrap_or_default();
+        Ok(Book::from(book))
+    }
+
+    /// The odds each bet type would be placed at now, over a window of
+    /// `timeWindowSeconds` (60 unless given).
+    async fn odds(&self, ctx: &Context<'_>, time_window_seconds: Option<u32>) -> async_graphql::Result<Vec<Quote>> {
+        let window = time_window_seconds.unwrap_or(DEFAULT_ODDS_WINDOW_SECS);
+        if window == 0 || window > MAX_BET_WINDOW_SECS {
+            return Err(Error::new(format!("timeWindowSeconds must be between 1 and {}", MAX_BET_WINDOW_SECS)));
+        }
+
+        let state = ctx.data_unchecked::<AppState>();
+        let flag_context = FlagContext {
+            user_id: ctx.data_opt::<Principal>().map(|principal| principal.subject.as_str()),
+            stream_id: Some(self.0.id.as_str()),
+        };
+        let pattern_bets = state.feature_flags.is_enabled(features::PATTERN_BETS, &flag_context);
+        Ok([BetType::Binary, BetType::Quantity, BetType::Timing, BetType::Pattern]
+            .into_iter()
+            .filter(|bet_type| *bet_type != BetType::Pattern || pattern_bets)
+            .map(|bet_type| Quote {
+                bet_type,
+                odds: state.betting_engine.quote_odds(bet_type.into(), window.into(), &flag_context),
+            })
+            .collect())
+    }
+
+    /// The caller's balance on the stream; null unless they're a signed-in bettor.
+    async fn my_balance(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Balance>> {
+        let Some(principal) = bettor(ctx) else {
+            return Ok(None);
+        };
+        let balance = ctx.data_unchecked::<DataLoader<BalanceLoader>>()
+            .load_one((principal.subject.clone(), self.0.id.clone()))
+            .await
+            .map_err(internal("balance"))?;
+        Ok(balance.map(Balance::from))
+    }
+
+    /// The caller's bets on the stream, newest first; null unless they're a signed-in bettor.
+    async fn my_bets(&self, ctx: &Context<'_>, first: Option<usize>) -> async_graphql::Result<Option<Vec<Bet>>> {
+        let Some(principal) = bettor(ctx) else {
+            return Ok(None);
+        };
+        let page = PageRequest { limit: page_limit(first), offset: 0, sort: None, fields: None };
+        let bets = ctx.data_unchecked::<AppState>()
+            .betting_engine
+            .bet_history(&principal.subject, Some(&self.0.id), &page)
+            .await
+            .map_err(internal("bet history"))?;
+        Ok(Some(bets.items.into_iter().map(Bet::from).collect()))
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+pub enum StreamState {
+    Inactive,
+    Activating,
+    Active,
+    Error,
+}
+
+impl From<&stream::StreamStatus> for StreamState {
+    fn from(status: &stream::StreamStatus) -> Self {
+        match status {
+            stream::StreamStatus::Inactive => StreamState::Inactive,
+            stream::StreamStatus::Activating => StreamState::Activating,
+            stream::StreamStatus::Active => StreamState::Active,
+            stream::StreamStatus::Error(_) => StreamState::Error,
+        }
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "crate::stream::ActivityType")]
+pub enum ActivityType {
+    PledgeReceived,
+    ViewerJoined,
+    ViewerLeft,
+    StreamActivated,
+    StreamConcluded,
+    BetPlaced,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "crate::betting::BetType")]
+pub enum BetType {
+    Binary,
+    Quantity,
+    Timing,
+    Pattern,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "crate::betting::BetStatus")]
+pub enum BetStatus {
+    Active,
+    Resolved,
+    Cancelled,
+    Expired,
+}
+
+#[derive(SimpleObject)]
+pub struct Activity {
+    timestamp: DateTime<Utc>,
+    activity_type: ActivityType,
+    amount: Option<f64>,
+    user_id: Option<String>,
+}
+
+impl From<StreamActivity> for Activity {
+    fn from(activity: StreamActivity) -> Self {
+        Self {
+            timestamp: activity.timestamp,
+            activity_type: activity.activity_type.into(),
+            amount: activity.amount,
+            user_id: activity.user_id,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Book {
+    open_bets: usize,
+    open_stake: f64,
+    /// Total owed if every open bet wins
+    exposure: f64,
+}
+
+impl From<OpenBook> for Book {
+    fn from(book: OpenBook) -> Self {
+        Self { open_bets: book.open_bets, open_stake: book.open_stake, exposure: book.exposure }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Quote {
+    bet_type: BetType,
+    odds: f64,
+}
+
+#[derive(SimpleObject)]
+pub struct Balance {
+    available: f64,
+    total_deposited: f64,
+    active_bets: f64,
+    total_winnings: f64,
+    bet_count: u32,
+}
+
+impl From<UserBalance> for Balance {
+    fn from(balance: UserBalance) -> Self {
+        Self {
+            available: balance.available_balance(),
+            total_deposited: balance.total_deposited,
+            active_bets: balance.active_bets_total,
+            total_winnings: balance.total_winnings,
+            bet_count: balance.bet_count,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Bet {
+    id: ID,
+    bet_type: BetType,
+    stake_amount: f64,
+    status: BetStatus,
+    created_at: DateTime<Utc>,
+    resolution_deadline: DateTime<Utc>,
+    potential_payout: f64,
+    odds: f64,
+}
+
+impl From<betting::Bet> for Bet {
+    fn from(bet: betting::Bet) -> Self {
+        Self {
+            id: ID(bet.id),
+            bet_type: bet.bet_type.into(),
+            stake_amount: bet.stake_amount,
+            status: bet.status.into(),
+            created_at: bet.created_at,
+            resolution_deadline: bet.resolution_deadline,
+            potential_payout: bet.potential_payout,
+            odds: bet.odds,
+        }
+    }
+}
+
+// Shared by every field waiting on the same batch
+type LoadError = Arc<anyhow::Error>;
+
+// Loads each key of a batch concurrently; keys that load as None are left out
+async fn load_each<K, V, F, Fut>(keys: &[K], load: F) -> Result<HashMap<K, V>, LoadError>
+where
+    K: Clone + Eq + Hash,
+    F: Fn(K) -> Fut,
+    Fut: Future<Output = anyhow::Result<Option<V>>>,
+{
+    let values = try_join_all(keys.iter().cloned().map(&load)).await.map_err(Arc::new)?;
+    Ok(keys.iter().cloned().zip(values).filter_map(|(key, value)| Some((key, value?))).collect())
+}
+
+pub struct StreamLoader(Arc<StreamManager>);
+
+impl Loader<String> for StreamLoader {
+    type Value = StreamInfo;
+    type Error = LoadError;
+
+    async fn load(&self, ids: &[String]) -> Result<HashMap<String, StreamInfo>, LoadError> {
+        load_each(ids, |id| async move { self.0.get_stream(&id).await }).await
+    }
+}
+
+pub struct ActivityLoader(Arc<BettingEngine>);
+
+impl Loader<String> for ActivityLoader {
+    type Value = Vec<StreamActivity>;
+    type Error = LoadError;
+
+    async fn load(&self, ids: &[String]) -> Result<HashMap<String, Vec<StreamActivity>>, LoadError> {
+        load_each(ids, |id| async move { self.0.get_stream_activity(&id).await.map(Some) }).await
+    }
+}
+
+// One pass over the open bets serves every stream in the batch
+pub struct OpenBookLoader(Arc<BettingEngine>);
+
+impl Loader<String> for OpenBookLoader {
+    type Value = OpenBook;
+    type Error = LoadError;
+
+    async fn load(&self, ids: &[String]) -> Result<HashMap<String, OpenBook>, LoadError> {
+        let mut books = self.0.open_books();
+        Ok(ids.iter().filter_map(|id| books.remove_entry(id)).collect())
+    }
+}
+
+// Keyed by (user_id, stream_id)
+pub struct BalanceLoader(Arc<BettingEngine>);
+
+impl Loader<(String, String)> for BalanceLoader {
+    type Value = UserBalance;
+    type Error = LoadError;
+
+    async fn load(&self, keys: &[(String, String)]) -> Result<HashMap<(String, String), UserBalance>, LoadError> {
+        load_each(keys, |(user_id, stream_id)| async move {
+            self.0.get_user_balance(&user_id, &stream_id).await.map(Some)
+        })
+        .await
+    }
+}
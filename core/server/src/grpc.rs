@@ -0,0 +1,487 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use futures::Stream;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::metadata::MetadataMap;
+use tonic::transport::Server;
+use tonic::{Code, Request, Response, Status, Streaming};
+use tracing::{error, info};
+
+use crate::analytics::AnalyticsEvent;
+use crate::auth::{Access, Role};
+use crate::betting::{ActualResult, BetRequest, Prediction};
+use crate::config::TenancyConfig;
+use crate::error::{ApiError, ErrorCode};
+use crate::outbox;
+use crate::pagination::{PageParams, PageRequest};
+use crate::shutdown::Shutdown;
+use crate::stream::{StreamInfo, StreamStatus};
+use crate::tenant::{self, Tenant};
+use crate::validation::ValidationErrors;
+use crate::webhooks::WebhookEventType;
+use crate::{route_analytics, AppState, ResolveBetRequest, MAX_BET_WINDOW_SECS};
+
+pub mod proto {
+    tonic::include_proto!("morphine.core.v1");
+}
+
+use proto::analytics_service_server::{AnalyticsService, AnalyticsServiceServer};
+use proto::betting_service_server::{BettingService, BettingServiceServer};
+use proto::stream_service_server::{StreamService, StreamServiceServer};
+
+// Method paths as API key scopes see them, e.g. `/morphine.core.v1.BettingService/*`
+const STREAM_SERVICE: &str = "/morphine.core.v1.StreamService";
+const BETTING_SERVICE: &str = "/morphine.core.v1.BettingService";
+const ANALYTICS_SERVICE: &str = "/morphine.core.v1.AnalyticsService";
+
+const DEFAULT_WATCH_INTERVAL_MS: u32 = 1000;
+
+/// Serves the internal gRPC API until shutdown.
+pub async fn serve(
+    state: AppState,
+    address: SocketAddr,
+    tenancy: TenancyConfig,
+    shutdown: Shutdown,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Bound up front so readiness reflects whether ingest can actually connect
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    let listening = state.grpc_listening.clone().unwrap_or_default();
+    let api = GrpcApi { state, tenancy: Arc::new(tenancy) };
+    info!("gRPC API listening on {}", address);
+
+    listening.store(true, Ordering::Relaxed);
+    let served = Server::builder()
+        .add_service(StreamServiceServer::new(api.clone()))
+        .add_service(BettingServiceServer::new(api.clone()))
+        .add_service(AnalyticsServiceServer::new(api))
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async move { shutdown.triggered().await })
+        .await;
+    listening.store(false, Ordering::Relaxed);
+
+    Ok(served?)
+}
+
+#[derive(Clone)]
+struct GrpcApi {
+    state: AppState,
+    tenancy: Arc<TenancyConfig>,
+}
+
+impl GrpcApi {
+    // Same credentials and API key scopes as REST; every method is for services only.
+    // Returns the tenant the call runs as: the credential's, as `tenant::enforce`
+    // resolves it, since gRPC calls have no host to go by
+    async fn authorize(&self, metadata: &MetadataMap, service: &str, method: &str) -> Result<Tenant, Status> {
+        // tonic is still on http 0.2, so its headers are copied over rather than converted
+        let mut headers = HeaderMap::new();
+        for (name, value) in metadata.clone().into_headers().iter() {
+            if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_str().as_bytes()), HeaderValue::from_bytes(value.as_bytes())) {
+                headers.append(name, value);
+            }
+        }
+        let path = format!("{}/{}", service, method);
+
+        let principal = self.state.authenticator
+            .resolve(&headers, "POST", &path).await
+            .map_err(ApiError::from)?
+            .map(|(principal, _)| principal);
+        Access::roles(&[Role::Service])
+            .check(principal.as_ref(), &HashMap::new()).await
+            .map_err(ApiError::from)?;
+
+        let tenant_id = principal.and_then(|principal| principal.tenant)
+            .filter(|_| self.tenancy.enabled)
+            .unwrap_or_else(|| self.tenancy.default_tenant.clone());
+        Ok(Tenant { is_default: tenant_id == self.tenancy.default_tenant, id: tenant_id })
+    }
+}
+
+// Runs a method's body as `tenant`, so its queries and Redis keys are the tenant's
+async fn scoped<T>(tenant: Tenant, call: impl Future<Output = Result<T, Status>>) -> Result<T, Status> {
+    tenant::scope(tenant, call).await
+}
+
+impl From<ApiError> for Status {
+    fn from(error: ApiError) -> Self {
+        let code = match error.code {
+            ErrorCode::MalformedBody | ErrorCode::ValidationFailed | ErrorCode::BadRequest => Code::InvalidArgument,
+            ErrorCode::Unauthorized => Code::Unauthenticated,
+            ErrorCode::Forbidden => Code::PermissionDenied,
+            ErrorCode::NotFound | ErrorCode::UnsupportedApiVersion => Code::NotFound,
+            ErrorCode::Conflict | ErrorCode::BetRejected => Code::FailedPrecondition,
+            ErrorCode::RateLimited | ErrorCode::PayloadTooLarge => Code::ResourceExhausted,
+            ErrorCode::Unavailable => Code::Unavailable,
+            ErrorCode::Timeout => Code::DeadlineExceeded,
+            ErrorCode::Internal => Code::Internal,
+        };
+
+        // Field errors travel in the message; gRPC has no standard slot for them
+        let mut message = error.message;
+        if !error.fields.is_empty() {
+            let fields: Vec<String> = error.fields.iter()
+                .map(|field| format!("{} {}", field.field, field.message))
+                .collect();
+            message = format!("{}: {}", message, fields.join("; "));
+        }
+        Status::new(code, message)
+    }
+}
+
+fn status_message(stream: &StreamInfo) -> proto::StreamStatus {
+    let (state, error) = match &stream.status {
+        StreamStatus::Inactive => (proto::StreamState::Inactive, String::new()),
+        StreamStatus::Activating => (proto::StreamState::Activating, String::new()),
+        StreamStatus::Active => (proto::StreamState::Active, String::new()),
+        StreamStatus::Error(error) => (proto::StreamState::Error, error.clone()),
+    };
+    proto::StreamStatus {
+        stream_id: stream.id.clone(),
+        state: state.into(),
+        error,
+        viewer_count: stream.viewer_count,
+    }
+}
+
+fn stream_message(stream: &StreamInfo) -> proto::Stream {
+    proto::Stream {
+        id: stream.id.clone(),
+        title: stream.title.clone(),
+        status: Some(status_message(stream)),
+        created_at: stream.created_at.timestamp(),
+        analytics_enabled: stream.analytics_enabled,
+        betting_enabled: stream.settings.enable_betting,
+    }
+}
+
+async fn fetch_status(state: &AppState, stream_id: &str) -> Result<proto::StreamStatus, Status> {
+    match state.stream_manager.get_stream(stream_id).await {
+        Ok(Some(stream)) => Ok(status_message(&stream)),
+        Ok(None) => Err(ApiError::not_found(format!("Stream {} not found", stream_id)).into()),
+        Err(e) => {
+            error!("Failed to get stream {}: {}", stream_id, e);
+            Err(ApiError::internal().into())
+        }
+    }
+}
+
+fn prediction(prediction: Option<proto::place_bet_request::Prediction>) -> Option<Prediction> {
+    use proto::place_bet_request::Prediction as P;
+    prediction.map(|prediction| match prediction {
+        P::Binary(p) => Prediction::Binary { will_occur: p.will_occur },
+        P::Quantity(p) => Prediction::Quantity { predicted_value: p.predicted_value, tolerance: p.tolerance },
+        P::Timing(p) => Prediction::Timing { predicted_seconds: p.predicted_seconds, tolerance: p.tolerance },
+        P::Pattern(p) => Prediction::Pattern { sequence: p.sequence },
+    })
+}
+
+fn actual_result(result: Option<proto::resolve_bet_request::ActualResult>) -> Option<ActualResult> {
+    use proto::resolve_bet_request::ActualResult as R;
+    result.map(|result| match result {
+        R::Binary(r) => ActualResult::Binary { occurred: r.occurred },
+        R::Quantity(r) => ActualResult::Quantity { actual_value: r.actual_value },
+        R::Timing(r) => ActualResult::Timing { actual_seconds: r.actual_seconds },
+        R::Pattern(r) => ActualResult::Pattern { actual_sequence: r.actual_sequence },
+    })
+}
+
+// Mirrors the checks on the REST body, which the oneof makes partly redundant
+fn bet_request(request: proto::PlaceBetRequest) -> Result<BetRequest, ApiError> {
+    let mut errors = ValidationErrors::new();
+    errors.require_non_empty("user_id", &request.user_id);
+    errors.require_non_empty("stream_id", &request.stream_id);
+    errors.require_positive("stake_amount", request.stake_amount);
+    if request.time_window_seconds == 0 || request.time_window_seconds > MAX_BET_WINDOW_SECS {
+        errors.add("time_window_seconds", format!("must be between 1 and {}", MAX_BET_WINDOW_SECS));
+    }
+    let prediction = prediction(request.prediction);
+    match &prediction {
+        Some(prediction) => errors.nested("prediction", prediction),
+        None => errors.add("prediction", "is required"),
+    }
+
+    match prediction {
+        Some(prediction) if errors.is_empty() => Ok(BetRequest {
+            user_id: request.user_id,
+            stream_id: request.stream_id,
+            bet_type: prediction.bet_type(),
+            stake_amount: request.stake_amount,
+            prediction,
+            time_window_seconds: request.time_window_seconds.into(),
+        }),
+        _ => Err(errors.into()),
+    }
+}
+
+type StatusStream = Pin<Box<dyn Stream<Item = Result<proto::StreamStatus, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl StreamService for GrpcApi {
+    type WatchStreamStatusStream = StatusStream;
+
+    async fn list_streams(
+        &self,
+        request: Request<proto::ListStreamsRequest>,
+    ) -> Result<Response<proto::ListStreamsResponse>, Status> {
+        let tenant = self.authorize(request.metadata(), STREAM_SERVICE, "ListStreams").await?;
+        scoped(tenant, async move {
+            let request = request.into_inner();
+
+            let page = PageRequest::from_params(PageParams {
+                limit: (request.page_size > 0).then_some(request.page_size as usize),
+                cursor: Some(request.page_token),
+                ..PageParams::default()
+            }).map_err(ApiError::from)?;
+
+            let streams = self.state.stream_manager.list_streams().await.map_err(|e| {
+                error!("Failed to list streams: {}", e);
+                ApiError::internal()
+            })?;
+            let page = page.paginate(streams, &[])?;
+
+            Ok(Response::new(proto::ListStreamsResponse {
+                streams: page.items.iter().map(stream_message).collect(),
+                next_page_token: page.next_cursor.unwrap_or_default(),
+                total: page.total as u32,
+            }))
+        }).await
+    }
+
+    async fn get_stream_status(
+        &self,
+        request: Request<proto::GetStreamStatusRequest>,
+    ) -> Result<Response<proto::StreamStatus>, Status> {
+        let tenant = self.authorize(request.metadata(), STREAM_SERVICE, "GetStreamStatus").await?;
+        scoped(tenant, async move {
+            let status = fetch_status(&self.state, &request.into_inner().stream_id).await?;
+            Ok(Response::new(status))
+        }).await
+    }
+
+    async fn activate_stream(
+        &self,
+        request: Request<proto::ActivateStreamRequest>,
+    ) -> Result<Response<proto::ActivateStreamResponse>, Status> {
+        let tenant = self.authorize(request.metadata(), STREAM_SERVICE, "ActivateStream").await?;
+        scoped(tenant, async move {
+            let stream_id = request.into_inner().stream_id;
+
+            match self.state.stream_manager.try_activate_stream(&stream_id).await {
+                Ok(result) => {
+                    if result.success {
+                        info!("Activated stream {} over gRPC", stream_id);
+                        let event = json!({ "stream_id": stream_id });
+                        if let Err(e) = outbox::enqueue(&self.state.db_pool, WebhookEventType::StreamActivated.as_str(), &event).await {
+                            error!("Failed to record activation of stream {}: {}", stream_id, e);
+                        }
+                    }
+                    Ok(Response::new(proto::ActivateStreamResponse {
+                        activated: result.success,
+                        message: result.message,
+                        status: Some(fetch_status(&self.state, &stream_id).await?),
+                    }))
+                }
+                Err(e) => {
+                    error!("Failed to activate stream {}: {}", stream_id, e);
+                    Err(ApiError::conflict(e.to_string()).into())
+                }
+            }
+        }).await
+    }
+
+    async fn stop_stream(
+        &self,
+        request: Request<proto::StopStreamRequest>,
+    ) -> Result<Response<proto::StopStreamResponse>, Status> {
+        let tenant = self.authorize(request.metadata(), STREAM_SERVICE, "StopStream").await?;
+        scoped(tenant, async move {
+            let stream_id = request.into_inner().stream_id;
+            // Unlike deactivation, which ignores unknown streams, report them
+            fetch_status(&self.state, &stream_id).await?;
+
+            match self.state.stream_manager.deactivate_stream(&stream_id).await {
+                Ok(()) => {
+                    self.state.metacognitive_orchestrator.stop_stream(&stream_id).await;
+                    info!("Stopped stream {} over gRPC", stream_id);
+                    let event = json!({ "stream_id": stream_id });
+                    if let Err(e) = outbox::enqueue(&self.state.db_pool, WebhookEventType::StreamStopped.as_str(), &event).await {
+                        error!("Failed to record stop of stream {}: {}", stream_id, e);
+                    }
+                    Ok(Response::new(proto::StopStreamResponse {
+                        status: Some(fetch_status(&self.state, &stream_id).await?),
+                    }))
+                }
+                Err(e) => {
+                    error!("Failed to stop stream {}: {}", stream_id, e);
+                    Err(ApiError::conflict(e.to_string()).into())
+                }
+            }
+        }).await
+    }
+
+    async fn watch_stream_status(
+        &self,
+        request: Request<proto::WatchStreamStatusRequest>,
+    ) -> Result<Response<Self::WatchStreamStatusStream>, Status> {
+        let tenant = self.authorize(request.metadata(), STREAM_SERVICE, "WatchStreamStatus").await?;
+        scoped(tenant.clone(), async move {
+            let request = request.into_inner();
+
+            let interval_ms = match request.interval_ms {
+                0 => DEFAULT_WATCH_INTERVAL_MS,
+                interval_ms if (100..=60_000).contains(&interval_ms) => interval_ms,
+                _ => {
+                    let mut errors = ValidationErrors::new();
+                    errors.add("interval_ms", "must be between 100 and 60000");
+                    return Err(ApiError::from(errors).into());
+                }
+            };
+            // Fail the call itself, not the first message, for an unknown stream
+            let first = fetch_status(&self.state, &request.stream_id).await?;
+
+            let (sender, receiver) = mpsc::channel(4);
+            let state = self.state.clone();
+            // Polled as the caller's tenant for as long as the watch lasts
+            self.state.shutdown.spawn_loop("grpc:stream_status", tenant::scope(tenant, async move {
+                let mut interval = tokio::time::interval(Duration::from_millis(interval_ms.into()));
+                interval.tick().await;
+                if sender.send(Ok(first)).await.is_err() {
+                    return;
+                }
+                loop {
+                    interval.tick().await;
+                    let status = fetch_status(&state, &request.stream_id).await;
+                    let failed = status.is_err();
+                    // The client has gone away once the receiver is dropped
+                    if sender.send(status).await.is_err() || failed {
+                        return;
+                    }
+                }
+            }));
+
+            Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+        }).await
+    }
+}
+
+#[tonic::async_trait]
+impl BettingService for GrpcApi {
+    async fn place_bet(
+        &self,
+        request: Request<proto::PlaceBetRequest>,
+    ) -> Result<Response<proto::PlaceBetResponse>, Status> {
+        let tenant = self.authorize(request.metadata(), BETTING_SERVICE, "PlaceBet").await?;
+        scoped(tenant, async move {
+            let bet_request = bet_request(request.into_inner())?;
+
+            match self.state.betting_engine.place_bet(bet_request).await {
+                Ok(result) if result.success => {
+                    let bet = result.bet_details.as_ref();
+                    Ok(Response::new(proto::PlaceBetResponse {
+                        bet_id: result.bet_id,
+                        message: result.message,
+                        remaining_balance: result.remaining_balance,
+                        odds: bet.map(|bet| bet.odds).unwrap_or_default(),
+                        potential_payout: bet.map(|bet| bet.potential_payout).unwrap_or_default(),
+                        resolution_deadline: bet.map(|bet| bet.resolution_deadline.timestamp()).unwrap_or_default(),
+                        matched_stake: result.matched_stake,
+                        unmatched_stake: result.unmatched_stake,
+                    }))
+                }
+                Ok(result) => Err(ApiError::new(ErrorCode::BetRejected, result.message).into()),
+                Err(e) => {
+                    error!("Failed to place bet: {}", e);
+                    Err(ApiError::internal().into())
+                }
+            }
+        }).await
+    }
+
+    async fn resolve_bet(
+        &self,
+        request: Request<proto::ResolveBetRequest>,
+    ) -> Result<Response<proto::ResolveBetResponse>, Status> {
+        let tenant = self.authorize(request.metadata(), BETTING_SERVICE, "ResolveBet").await?;
+        scoped(tenant, async move {
+            let request = request.into_inner();
+
+            let Some(actual_result) = actual_result(request.actual_result) else {
+                let mut errors = ValidationErrors::new();
+                errors.add("actual_result", "is required");
+                return Err(ApiError::from(errors).into());
+            };
+            let resolution = ResolveBetRequest { actual_result, confidence_score: request.confidence_score };
+            ValidationErrors::collect(&resolution).map_err(ApiError::from)?;
+
+            match self.state.betting_engine
+                .resolve_bet(&request.bet_id, resolution.actual_result, resolution.confidence_score).await
+            {
+                Ok(Some(bet)) => {
+                    let settlement = bet.resolution_result.as_ref();
+                    Ok(Response::new(proto::ResolveBetResponse {
+                        resolved: true,
+                        won: settlement.map(|s| s.won).unwrap_or_default(),
+                        payout_amount: settlement.map(|s| s.payout_amount).unwrap_or_default(),
+                    }))
+                }
+                Ok(None) => Ok(Response::new(proto::ResolveBetResponse::default())),
+                Err(e) => {
+                    error!("Failed to resolve bet {}: {}", request.bet_id, e);
+                    Err(ApiError::internal().into())
+                }
+            }
+        }).await
+    }
+}
+
+#[tonic::async_trait]
+impl AnalyticsService for GrpcApi {
+    async fn ingest_analytics(
+        &self,
+        request: Request<Streaming<proto::AnalyticsFrame>>,
+    ) -> Result<Response<proto::IngestAnalyticsResponse>, Status> {
+        let tenant = self.authorize(request.metadata(), ANALYTICS_SERVICE, "IngestAnalytics").await?;
+        scoped(tenant, async move {
+            let mut frames = request.into_inner();
+
+            // A bad frame is reported and skipped; the rest of the stream still counts
+            let mut response = proto::IngestAnalyticsResponse::default();
+            let mut index = 0u32;
+            while let Some(frame) = frames.message().await? {
+                let outcome = if frame.stream_id.is_empty() {
+                    Err("stream_id is required".to_string())
+                } else {
+                    match serde_json::from_str::<AnalyticsEvent>(&frame.payload_json) {
+                        Ok(payload) => match ValidationErrors::collect(&payload) {
+                            Ok(()) => route_analytics(&self.state, &frame.stream_id, payload).await
+                                .map_err(|e| {
+                                    error!("Failed to process analytics for stream {}: {}", frame.stream_id, e);
+                                    "processing failed".to_string()
+                                }),
+                            Err(errors) => Err(Status::from(ApiError::from(errors)).message().to_string()),
+                        },
+                        Err(e) => Err(format!("payload_json is not a valid analytics frame: {}", e)),
+                    }
+                };
+
+                match outcome {
+                    Ok(()) => response.accepted += 1,
+                    Err(reason) => {
+                        response.rejected += 1;
+                        response.rejections.push(proto::FrameRejection { index, reason });
+                    }
+                }
+                index += 1;
+            }
+
+            Ok(Response::new(response))
+        }).await
+    }
+}
@@ -0,0 +1,5215 @@
+mod websocket;
+mod api_docs;
+mod grpc;
+mod datagram;
+mod graphql;
+
+use morphine_core::{
+    Morphine, MorphineBuilder,
+    allocator, analytics, analytics_client, api, api_version, audit, auth, betting, cache, cli, config, cors, email, encoding, error, error_reporting, events, exports, features, geolocation,
+    idempotency, latency, limits, loadtest, orchestrator, outbox, pagination, pool_metrics, push, rate_limit, reasoning, reload,
+    region, replay, risk, runtime, shutdown, state, stream, tenant, timeline, tls, validation, webhooks,
+};
+
+use axum::{
+    routing::{get, post, patch, put, delete},
+    Router,
+    ServiceExt,
+    extract::{DefaultBodyLimit, Extension, FromRequestParts, Path, Query, Request, State},
+    http::{header, HeaderMap},
+    middleware::{self, Next},
+    response::Json as AxumJson,
+    response::Response,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::stream::Stream;
+use std::convert::Infallible;
+use tokio::sync::broadcast;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tower::Layer;
+use tracing::{info, warn, error};
+use anyhow::Result;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{Pool, Postgres};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+use uuid::Uuid;
+
+use crate::{
+    analytics::AnalyticsEvent,
+    api::{
+        BalanceSummary, BetResponse, BetSlipLine, BetSlipRequest, BetSlipValidation, CreateStreamRequest, LocationVerificationRequest, LoginRequest, PlaceBetRequest, ResolveBetRequest,
+        SlipLineDiagnostics, WebSocketMessage, MAX_BET_WINDOW_SECS,
+    },
+    analytics_client::{AnalyticsClient, Clip, DetectionRerun, FrameHashVerification},
+    api_version::ApiVersionLayer,
+    audit::{AuditAction, AuditEntry, AuditLog, AuditQuery, AuditRecord, ChainVerification},
+    auth::{
+        Access, ApiKey, ApiKeyStore, Authenticator, Principal, Role, SessionKind, SessionOrigin, SessionRegistry,
+        StreamOwnership, User, UserStore,
+        api_keys::NewApiKey,
+        principal::Credential,
+        users::{LogVerificationHook, OAuthIdentity, ProfileUpdate, Registration, VerificationHook, WebhookVerificationHook},
+    },
+    config::{Command, Config, LaunchOptions},
+    email::EmailService,
+    exports::{ExportJob, ExportRequest, ExportService},
+    error::{ApiError, ErrorCode},
+    events::EventBusRelay,
+    pagination::{PageParams, PageRequest, Paginated},
+    push::{NewPushDevice, PushDevice, PushPreferences, PushService},
+    risk::{AlertReview, RiskAlert, RiskAlertQuery, RiskMonitor},
+    state::StateManager,
+    stream::{
+        StreamActivity, StreamInfo, StreamManager,
+        quotas::{QuotaOverride, QuotaScope},
+    },
+    betting::{
+        Bet, BettingEngine, LeaderboardMetric, SlipCheck, SlipProblem, UserBettingStats,
+        liquidity::{LiquidityCap, MarketLiquidity},
+        repository::BettingRepository,
+        suspension::{EmergencyStop, MarketSuspensions, OpenBetPolicy},
+    },
+    websocket::WebSocketManager,
+    orchestrator::{
+        MetacognitiveOrchestrator,
+        decision_log::DecisionQuery,
+        opportunities::BettingOpportunity,
+        feedback::OutcomeFeedback,
+        knowledge_graph::KnowledgeQuery,
+        pattern_models::NewPatternModel,
+        replay::ReplayRequest,
+        windowing::WindowPolicy,
+        connectors::AISystemManifest,
+        analytics_adapter::AnalyticsServiceAdapter,
+    },
+    geolocation::{clearance::LocationClearances, GeolocationService, LocationVerification, zones::ExclusionZoneStore},
+    encoding::{Encoding, Negotiated},
+    features::{FeatureFlag, FeatureFlagUpdate, FeatureFlags, FlagContext},
+    idempotency::IdempotencyStore,
+    region::{GeoRoutes, RegionReplicator, RegionRouter, ReplicatedStreams},
+    replay::ReplayGuard,
+    latency::{Stage, Stamp},
+    limits::RequestLimits,
+    outbox::{OutboxHandler, OutboxRelay},
+    allocator::AllocatorMetrics,
+    pool_metrics::PoolMetrics,
+    cache::CacheMetrics,
+    rate_limit::RateLimiter,
+    reload::{ConfigReloader, ReloadReport},
+    shutdown::Shutdown,
+    timeline::{EntityType, StateAsOf, StateEvent, Timeline, TimelineFilter},
+    validation::{ValidJson, Validate, ValidationErrors},
+    webhooks::{
+        DeliveryQuery, IssuedWebhookSubscription, NewWebhookSubscription, WebhookDelivery, WebhookEventType,
+        WebhookService, WebhookSubscription,
+    },
+    reasoning::{traces::StoredEvaluation, HybridReasoningEngine},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SystemHealth {
+    pub core_status: String,
+    pub orchestrator_health: serde_json::Value,
+    pub geolocation_active: bool,
+    pub reasoning_engine_status: String,
+    pub active_streams: usize,
+    pub total_ai_systems: usize,
+    pub metabolic_state: serde_json::Value,
+    pub exclusion_zones_count: usize,
+}
+
+/// Everything an operator checks first, gathered in one call.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AdminOverview {
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    pub active_streams: Vec<StreamOverview>,
+    // Across all streams, active or not
+    pub open_exposure: f64,
+    pub pending_settlements: usize,
+    pub orchestrator_queue_depth: usize,
+    pub paused_streams: usize,
+    pub redis: DependencyHealth,
+    pub database: DependencyHealth,
+    pub exclusion_zones_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StreamOverview {
+    pub stream_id: String,
+    pub title: String,
+    pub viewer_count: u32,
+    pub open_bets: usize,
+    pub open_stake: f64,
+    pub exposure: f64,
+    // Frames waiting in the orchestrator for this stream
+    pub queued: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DependencyHealth {
+    pub healthy: bool,
+    pub latency_ms: f64,
+    pub error: Option<String>,
+}
+
+/// What `/readyz` checked. Not ready while any component is unhealthy or
+/// once shutdown has begun, so traffic drains away before the process exits.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Readiness {
+    pub ready: bool,
+    pub shutting_down: bool,
+    pub redis: DependencyHealth,
+    pub database: DependencyHealth,
+    pub orchestrator: ComponentHealth,
+    pub ingest: ComponentHealth,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ComponentHealth {
+    pub healthy: bool,
+    pub detail: String,
+}
+
+// A hung dependency should show as unhealthy, not hang the dashboard
+const DEPENDENCY_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+async fn check_dependency<E: std::fmt::Display>(
+    check: impl std::future::Future<Output = Result<(), E>>,
+) -> DependencyHealth {
+    let started = std::time::Instant::now();
+    let outcome = tokio::time::timeout(DEPENDENCY_CHECK_TIMEOUT, check).await;
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    let error = match outcome {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("no response within {:?}", DEPENDENCY_CHECK_TIMEOUT)),
+    };
+    DependencyHealth { healthy: error.is_none(), latency_ms, error }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamingDecisionRequest {
+    pub stream_id: String,
+    pub data: serde_json::Value,
+    pub context: std::collections::HashMap<String, serde_json::Value>,
+    pub location_data: Option<LocationUpdateRequest>,
+    pub video_frame_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationUpdateRequest {
+    pub gps_data: Option<geolocation::GeolocationPoint>,
+    pub cell_towers: Vec<geolocation::CellTowerData>,
+    pub wifi_points: Vec<geolocation::WiFiAccessPoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BetEvaluationRequest {
+    pub bet_id: String,
+    pub event_data: AnalyticsEvent,
+    pub context: std::collections::HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExclusionZoneRequest {
+    #[schema(value_type = Object)]
+    pub zone: geolocation::ExclusionZone,
+}
+
+#[derive(Clone)]
+pub struct AppState {
+    pub state_manager: Arc<StateManager>,
+    pub stream_manager: Arc<StreamManager>,
+    pub betting_engine: Arc<BettingEngine>,
+    pub metacognitive_orchestrator: Arc<MetacognitiveOrchestrator>,
+    pub geolocation_service: Arc<GeolocationService>,
+    pub location_clearances: Arc<LocationClearances>,
+    pub analytics_client: Arc<AnalyticsClient>,
+    pub exclusion_zones: Arc<ExclusionZoneStore>,
+    pub timeline: Arc<Timeline>,
+    pub audit_log: Arc<AuditLog>,
+    pub reasoning_engine: Arc<HybridReasoningEngine>,
+    pub websocket_manager: Arc<WebSocketManager>,
+    pub replay_guard: Arc<ReplayGuard>,
+    pub regions: Arc<RegionRouter>,
+    pub api_keys: Arc<ApiKeyStore>,
+    pub stream_ownership: Arc<StreamOwnership>,
+    pub authenticator: Arc<Authenticator>,
+    pub sessions: Arc<SessionRegistry>,
+    pub users: Arc<UserStore>,
+    pub verification_hook: Arc<dyn VerificationHook>,
+    pub webhooks: Arc<WebhookService>,
+    pub exports: Arc<ExportService>,
+    pub risk: Arc<RiskMonitor>,
+    pub market_suspensions: Arc<MarketSuspensions>,
+    pub push: Arc<PushService>,
+    pub feature_flags: Arc<FeatureFlags>,
+    pub pool_metrics: Arc<PoolMetrics>,
+    pub cache_metrics: Arc<CacheMetrics>,
+    pub allocator_metrics: Arc<AllocatorMetrics>,
+    pub config_reloader: Arc<ConfigReloader>,
+    pub graphql_schema: graphql::MorphineSchema,
+    // Set while the gRPC ingest listener accepts connections; None when gRPC is disabled
+    pub grpc_listening: Option<Arc<std::sync::atomic::AtomicBool>>,
+    // None when analytics datagrams are disabled
+    pub datagram_ingest: Option<Arc<datagram::DatagramIngest>>,
+    pub shutdown: Shutdown,
+    pub db_pool: Pool<Postgres>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DreamExportQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct DreamBacktestRequest {
+    // Restrict the backtest to these bets; all active bets when omitted
+    bet_ids: Option<Vec<String>>,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct WeightHistoryQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AlertHistoryQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FairnessReportQuery {
+    // Bets settled from `from` up to, not including, `to`
+    from: chrono::DateTime<chrono::Utc>,
+    to: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct VerifyEmailRequest {
+    token: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct RegionRouteQuery {
+    // Routes the stream's HLS playlist too, from the region its ingest lands in
+    stream_id: Option<String>,
+    // The client's position; without it, routes are ranked from the region that answers
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct WebSocketAuthQuery {
+    // Browsers can't set headers on a WebSocket upgrade, so the token comes in the URL
+    token: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct StreamOwnerRequest {
+    creator_id: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct StreamQuotaRequest {
+    // Streams active or activating at once; 0 means no limit
+    max_active: u32,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct EmergencyStopRequest {
+    // Shown to bettors as why betting stopped, and kept in the audit log
+    reason: String,
+    open_bets: OpenBetPolicy,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct LiquidityCapRequest {
+    // Most the stream's open bets may owe if they all win; 0 means no limit
+    max_liability: f64,
+}
+
+#[derive(Deserialize, Default, ToSchema)]
+struct RotateApiKeyRequest {
+    // Keep the old key valid this long so callers can switch over
+    grace_secs: Option<u64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct SystemWeightRequest {
+    weight: f64,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct ResolveBetsRequest {
+    resolutions: Vec<BatchResolution>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct BatchResolution {
+    bet_id: String,
+    #[schema(value_type = Object)]
+    actual_result: betting::ActualResult,
+    confidence_score: f64,
+}
+
+// Keeps one request from holding every settlement worker for long
+const MAX_BATCH_RESOLUTIONS: usize = 500;
+
+impl Validate for ResolveBetsRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        if self.resolutions.is_empty() || self.resolutions.len() > MAX_BATCH_RESOLUTIONS {
+            errors.add("resolutions", format!("must hold between 1 and {} resolutions", MAX_BATCH_RESOLUTIONS));
+        }
+        for (index, resolution) in self.resolutions.iter().enumerate() {
+            errors.nested(&format!("resolutions[{}]", index), resolution);
+        }
+    }
+}
+
+impl Validate for BatchResolution {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("bet_id", &self.bet_id);
+        errors.nested("actual_result", &self.actual_result);
+        errors.require_range("confidence_score", self.confidence_score, 0.0, 1.0);
+    }
+}
+
+impl Validate for ExclusionZoneRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.nested("zone", &self.zone);
+    }
+}
+
+impl Validate for DreamBacktestRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        if self.bet_ids.as_ref().map(|ids| ids.iter().any(|id| id.trim().is_empty())).unwrap_or(false) {
+            errors.add("bet_ids", "must not contain empty IDs");
+        }
+        if self.limit == Some(0) {
+            errors.add("limit", "must be greater than zero");
+        }
+    }
+}
+
+impl Validate for VerifyEmailRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("token", &self.token);
+    }
+}
+
+impl Validate for StreamOwnerRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("creator_id", &self.creator_id);
+    }
+}
+
+impl Validate for StreamQuotaRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_range("max_active", self.max_active as f64, 0.0, i32::MAX as f64);
+    }
+}
+
+// Bettors see the reason in every market status message
+const MAX_EMERGENCY_REASON_LENGTH: usize = 500;
+
+impl Validate for EmergencyStopRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("reason", &self.reason);
+        if self.reason.chars().count() > MAX_EMERGENCY_REASON_LENGTH {
+            errors.add("reason", format!("must be at most {} characters", MAX_EMERGENCY_REASON_LENGTH));
+        }
+    }
+}
+
+impl Validate for LiquidityCapRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        // What NUMERIC(12,2) holds
+        errors.require_range("max_liability", self.max_liability, 0.0, 9_999_999_999.99);
+    }
+}
+
+impl Validate for SystemWeightRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_range("weight", self.weight, 0.0, f64::MAX);
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Records every allocation until main returns, in a dhat-heap build
+    let _profiling = allocator::start_profiling();
+    // Load configuration before logging starts, so check-config output is clean
+    let options = LaunchOptions::parse();
+    let command = options.command.clone().unwrap_or_default();
+    // Load tests only talk to the instance under test, so they need no configuration of their own
+    if let Command::Loadtest(load) = command {
+        tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .init();
+        return loadtest::run(load).await;
+    }
+    let mut config = Config::load(options.config_path.as_deref())?;
+    if let Command::CheckConfig { print } = command {
+        if print {
+            println!("{}", config.to_redacted_toml()?);
+        } else {
+            println!("Configuration is valid");
+        }
+        return Ok(());
+    }
+
+    // Initialize tracing, and tokio-console's instrumentation in a build with it
+    runtime::init_tracing();
+    // Held until main returns, so events queued at exit are still sent
+    let _error_reporting = error_reporting::init(&config.error_reporting);
+
+    match command {
+        Command::Serve { migrate, require_migrated } => serve(options, config, migrate, require_migrated).await,
+        Command::Migrate { dry_run } => {
+            cli::resolve_secrets(&mut config).await?;
+            cli::migrate(&config, dry_run).await
+        }
+        Command::Seed { tenant } => {
+            cli::resolve_secrets(&mut config).await?;
+            cli::seed(&config, tenant).await
+        }
+        Command::ExportSnapshot { output, tenant, ledger_since } => {
+            cli::resolve_secrets(&mut config).await?;
+            cli::export_snapshot(&config, &output, tenant, ledger_since).await
+        }
+        Command::StateAsOf { as_of, output, tenant, user_id, stream_id } => {
+            cli::resolve_secrets(&mut config).await?;
+            cli::state_as_of(&config, &output, tenant, as_of, TimelineFilter { user_id, stream_id }).await
+        }
+        Command::Loadtest(_) | Command::CheckConfig { .. } => Ok(()),
+    }
+}
+
+async fn serve(options: LaunchOptions, config: Config, migrate: bool, require_migrated: bool) -> Result<()> {
+    info!("Starting Morphine Core Service");
+    match &options.config_path {
+        Some(path) => info!("Loaded configuration from {} and environment", path.display()),
+        None => info!("Loaded configuration from environment"),
+    }
+
+    // Background loops and open connections watch this; tracked work is drained before exit
+    let shutdown = Shutdown::new();
+
+    // State, streams, betting, geolocation and the orchestrator, connected in dependency order
+    let Morphine {
+        config,
+        shutdown: _,
+        config_reloader,
+        secrets,
+        db_pool,
+        reads,
+        state_manager,
+        stream_manager,
+        feature_flags,
+        betting_engine,
+        metacognitive_orchestrator,
+        analytics_client,
+        geolocation_service,
+        location_clearances,
+        exclusion_zones,
+        reasoning_engine,
+    } = MorphineBuilder::new(config)
+        .config_path(options.config_path.clone())
+        .shutdown(shutdown.clone())
+        .migrate(migrate)
+        .require_migrated(require_migrated)
+        .build()
+        .await?;
+
+    // Safe-to-change settings are reloaded on SIGHUP or via the admin API
+    shutdown.spawn_loop("config:sighup_reload", reload::reload_on_sighup(config_reloader.clone()));
+
+    // Initialize websocket manager
+    let websocket_manager = Arc::new(WebSocketManager::new());
+
+    // Machine credentials for internal callers such as the analytics service
+    let api_keys = Arc::new(ApiKeyStore::new(db_pool.clone(), config_reloader.api_keys()));
+    let stream_ownership = Arc::new(StreamOwnership::new(db_pool.clone()));
+    // Users' tokens, sockets and location sessions, revocable from any of their devices
+    let sessions = Arc::new(SessionRegistry::new(
+        state_manager.clone(),
+        std::time::Duration::from_secs(config.user_auth.token_ttl_secs),
+    ));
+    let authenticator = Arc::new(Authenticator::new(
+        config.admin_api.token.as_deref(),
+        &config.user_auth,
+        api_keys.clone(),
+        sessions.clone(),
+    ));
+    let users = Arc::new(UserStore::new(
+        db_pool.clone(),
+        config.user_auth.default_roles.clone(),
+        std::time::Duration::from_secs(config.user_auth.email_verification_ttl_secs),
+    ));
+    let verification_hook: Arc<dyn VerificationHook> = match &config.user_auth.email_verification_webhook_url {
+        Some(url) => Arc::new(WebhookVerificationHook::new(url.clone())),
+        None => Arc::new(LogVerificationHook),
+    };
+
+    // Request counters live in Redis so limits hold across instances
+    let rate_limiter = Arc::new(
+        RateLimiter::connect(&config.redis_url, config_reloader.rate_limit())
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?
+    );
+
+    // First responses to retried writes, in Redis so a retry may land on any instance
+    let idempotency = Arc::new(
+        IdempotencyStore::connect(&config.redis_url, config.idempotency.clone())
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?
+    );
+
+    // Nonces spent by WebSocket bets and pledges, in Redis so a replay is caught on any instance
+    let replay_guard = Arc::new(
+        ReplayGuard::connect(&config.redis_url, config.replay_protection.clone())
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?
+    );
+
+    let request_limits = Arc::new(RequestLimits::new(config.limits.clone()));
+
+    // Pool saturation on /metrics, next to the orchestrator's series
+    let mut pools = vec![
+        ("primary".to_string(), db_pool.clone()),
+        ("betting".to_string(), betting_engine.db_pool().clone()),
+    ];
+    pools.extend(reads.replica_pools());
+    let pool_metrics = Arc::new(PoolMetrics::new(pools).map_err(|e| anyhow::anyhow!(e))?);
+    let cache_metrics = Arc::new(CacheMetrics::new().map_err(|e| anyhow::anyhow!(e))?);
+    let allocator_metrics = Arc::new(AllocatorMetrics::new().map_err(|e| anyhow::anyhow!(e))?);
+    info!("Using the {} allocator", allocator::NAME);
+
+    // Push notifications to integrators, queued in Postgres and sent in the background
+    let webhooks = Arc::new(WebhookService::new(
+        db_pool.clone(),
+        config.webhooks.clone(),
+        secrets.clone(),
+        shutdown.clone(),
+    ));
+    webhooks.start();
+
+    // Domain events are written to the outbox with the change they describe and relayed from there,
+    // to webhooks and, when configured, the message bus and notification email
+    let mut outbox_handlers = vec![webhooks.clone() as Arc<dyn OutboxHandler>];
+    if let Some(publisher) = events::connect(&config.event_bus).await.map_err(|e| anyhow::anyhow!(e))? {
+        outbox_handlers.push(Arc::new(EventBusRelay::new(
+            publisher,
+            config.event_bus.subject_prefix.clone(),
+            config.region.id.clone(),
+        )));
+    }
+    if let Some(sender) = email::sender(&config.email).map_err(|e| anyhow::anyhow!(e))? {
+        let email = Arc::new(
+            EmailService::new(db_pool.clone(), config.email.clone(), sender, shutdown.clone())
+                .map_err(|e| anyhow::anyhow!(e))?,
+        );
+        email.start();
+        outbox_handlers.push(email);
+    }
+    // Device registration is always served; pushes go out for the platforms with credentials
+    let push = Arc::new(PushService::new(
+        db_pool.clone(),
+        push::senders(&config.push).map_err(|e| anyhow::anyhow!(e))?,
+    ));
+    if push.has_senders() {
+        outbox_handlers.push(push.clone());
+    }
+    // Bulk exports for finance and compliance, generated in the background into object storage
+    let exports = Arc::new(
+        ExportService::new(
+            db_pool.clone(),
+            config.exports.clone(),
+            geolocation_service.clone(),
+            betting_engine.clone(),
+            config.tenancy.default_tenant.clone(),
+            shutdown.clone(),
+        )
+        .map_err(|e| anyhow::anyhow!(e))?,
+    );
+    exports.start();
+    // Anomalous betting is raised on the admin channel and kept for review
+    let audit_log = Arc::new(AuditLog::new(db_pool.clone()));
+    let risk = Arc::new(RiskMonitor::new(
+        db_pool.clone(),
+        config.risk.clone(),
+        audit_log.clone(),
+        metacognitive_orchestrator.admin_event_sender(),
+    ));
+    if config.risk.enabled {
+        outbox_handlers.push(risk.clone());
+    }
+    let outbox_relay = Arc::new(OutboxRelay::new(
+        db_pool.clone(),
+        outbox_handlers,
+        config.outbox.clone(),
+        config.tenancy.default_tenant.clone(),
+        shutdown.clone(),
+    ));
+    outbox_relay.start();
+
+    // Other regions' events, from the bus, keep this region's view of their streams current
+    if config.region.replicate_events {
+        let group = format!("morphine-region-{}", config.region.id);
+        if let Some(subscriber) = events::subscribe(&config.event_bus, &group).await.map_err(|e| anyhow::anyhow!(e))? {
+            let replicator = Arc::new(RegionReplicator::new(
+                config.region.id.clone(),
+                vec![Arc::new(ReplicatedStreams::new(stream_manager.clone())) as Arc<dyn OutboxHandler>],
+                config.tenancy.default_tenant.clone(),
+                shutdown.clone(),
+            ));
+            replicator.start(subscriber);
+        }
+    }
+
+    // Markets close to new bets while the analytics behind them can't be trusted; clients hear of each change
+    let market_suspensions = Arc::new(MarketSuspensions::new(
+        BettingRepository::new(db_pool.clone(), reads.clone()),
+        config.market_suspension.clone(),
+        config.tenancy.default_tenant.clone(),
+        shutdown.clone(),
+    ));
+    if config.market_suspension.enabled {
+        market_suspensions.start(metacognitive_orchestrator.subscribe_all_decisions());
+    }
+    // Emergency stops go out whether or not automatic suspension is on
+    let mut changes = market_suspensions.subscribe();
+    let status_sockets = websocket_manager.clone();
+    shutdown.spawn_loop("markets:status_broadcast", async move {
+        loop {
+            match changes.recv().await {
+                Ok(status) => status_sockets.broadcast(WebSocketMessage::MarketStatus {
+                    stream_id: status.stream_id,
+                    bettable: status.bettable,
+                    reason: status.reason,
+                }),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    // Markets the orchestrator opens from confident predictions are announced to the stream's followers
+    let mut opportunities = metacognitive_orchestrator.subscribe_opportunities();
+    let opportunity_sockets = websocket_manager.clone();
+    shutdown.spawn_loop("markets:opportunity_broadcast", async move {
+        loop {
+            match opportunities.recv().await {
+                Ok(opportunity) => opportunity_sockets.broadcast(WebSocketMessage::BettingOpportunity { opportunity }),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    // Location sessions revoked here end at once; other instances refuse them on their next update
+    let mut revoked_sessions = sessions.subscribe();
+    let location_sessions = geolocation_service.clone();
+    shutdown.spawn_loop("sessions:revoked_locations", async move {
+        loop {
+            match revoked_sessions.recv().await {
+                Ok(session_id) => {
+                    location_sessions.end_location_session(&session_id).await;
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    // Pick up rotated database credentials and JWT secrets without a restart
+    secrets.watch_rotations(
+        config.secrets.clone(),
+        vec![db_pool.clone(), betting_engine.db_pool().clone()],
+        authenticator.clone(),
+        &shutdown,
+    );
+
+    let datagram_ingest = match config.datagram_ingest.enabled {
+        true => Some(Arc::new(datagram::DatagramIngest::new(&config.datagram_ingest).map_err(|e| anyhow::anyhow!(e))?)),
+        false => None,
+    };
+
+    // Create shared application state
+    let app_state = AppState {
+        state_manager,
+        stream_manager,
+        betting_engine,
+        metacognitive_orchestrator,
+        geolocation_service,
+        location_clearances,
+        analytics_client,
+        exclusion_zones,
+        timeline: Arc::new(Timeline::new(db_pool.clone())),
+        audit_log,
+        reasoning_engine,
+        websocket_manager,
+        replay_guard,
+        regions: Arc::new(RegionRouter::new(config.region.clone())),
+        api_keys,
+        stream_ownership,
+        authenticator: authenticator.clone(),
+        sessions,
+        users,
+        verification_hook,
+        webhooks,
+        exports,
+        risk,
+        market_suspensions,
+        push,
+        feature_flags,
+        pool_metrics,
+        cache_metrics,
+        allocator_metrics,
+        config_reloader,
+        graphql_schema: graphql::schema(),
+        grpc_listening: config.grpc.enabled.then(Default::default),
+        datagram_ingest,
+        shutdown: shutdown.clone(),
+        db_pool,
+    };
+
+    // Register AI systems with the orchestrator
+    register_ai_systems(&app_state, &config).await?;
+
+    // Internal services talk to us over gRPC on a port of its own
+    if config.grpc.enabled {
+        let address = config.grpc.bind_address.parse()?;
+        let grpc_server = grpc::serve(app_state.clone(), address, config.tenancy.clone(), shutdown.clone());
+        shutdown.spawn_tracked("grpc:server", async move {
+            if let Err(e) = grpc_server.await {
+                error!("gRPC server failed: {}", e);
+            }
+        });
+    }
+
+    // The analytics service can send frames as UDP datagrams instead, without a round trip each
+    if let Some(ingest) = app_state.datagram_ingest.clone() {
+        let address = config.datagram_ingest.bind_address.parse()?;
+        let listener = datagram::serve(app_state.clone(), ingest, address, shutdown.clone());
+        shutdown.spawn_tracked("datagram:listener", async move {
+            if let Err(e) = listener.await {
+                error!("Analytics datagram listener failed: {}", e);
+            }
+        });
+    }
+
+    if config.admin_api.token.is_none() {
+        warn!("ADMIN_API_TOKEN not set; admin endpoints only accept user tokens with the admin role");
+    }
+    if config.user_auth.jwt_secret.is_none() {
+        warn!("JWT_SECRET not set; creator and bettor endpoints will reject all requests");
+    }
+    if config.cors.permissive {
+        warn!("CORS_PERMISSIVE is on; any website can call this API from a browser. Development only");
+    } else if config.cors.allowed_origins.is_empty() {
+        info!("No CORS origins configured; browsers may only call this API from its own origin");
+    }
+
+    // Every route group below declares who may call it; admins pass all of them.
+    // Operator endpoints
+    let admin_routes = Router::new()
+        .route("/api/admin/overview", get(get_admin_overview))
+        .route("/api/admin/financial-snapshot", get(export_financial_snapshot))
+        .route("/api/admin/exports", get(list_exports).post(request_export).layer(idempotency.layer()))
+        .route("/api/admin/exports/:export_id", get(get_export))
+        .route("/api/admin/reports/fairness", get(get_fairness_report))
+        .route("/api/admin/audit", get(list_audit_entries))
+        .route("/api/admin/audit/verify", get(verify_audit_log))
+        .route("/api/admin/risk/alerts", get(list_risk_alerts))
+        .route("/api/admin/risk/alerts/:alert_id/review", post(review_risk_alert))
+        .route("/api/admin/risk/freezes/:user_id", delete(release_account_freeze))
+        .route("/api/admin/timeline", get(get_state_as_of))
+        .route("/api/admin/timeline/:entity_type/:entity_id", get(get_entity_history))
+        .route("/api/admin/analytics/verify-frame", post(verify_frame_hash))
+        .route("/api/admin/analytics/:stream_id/rerun-detection", post(rerun_detection))
+        .route("/api/admin/config/reload", post(reload_config))
+        .route("/api/admin/debug/heap-profile", post(dump_heap_profile))
+        .route("/api/admin/features", get(list_feature_flags))
+        .route("/api/admin/features/:name", put(set_feature_flag).delete(delete_feature_flag))
+        .route("/api/admin/stream-quotas", get(list_stream_quotas))
+        .route("/api/admin/stream-quotas/tenant", put(set_tenant_stream_quota).delete(clear_tenant_stream_quota))
+        .route("/api/admin/stream-quotas/creators/:creator_id", put(set_creator_stream_quota).delete(clear_creator_stream_quota))
+        .route("/api/admin/streams/:stream_id/emergency-stop", post(emergency_stop_stream).delete(lift_emergency_stop))
+        .route("/api/admin/liquidity-caps", get(list_liquidity_caps))
+        .route(
+            "/api/admin/streams/:stream_id/liquidity",
+            get(get_market_liquidity).put(set_liquidity_cap).delete(clear_liquidity_cap),
+        )
+        .route("/api/orchestrator/admin/events", get(stream_admin_events))
+        .route("/api/orchestrator/admin/systems", get(list_ai_systems))
+        .route("/api/orchestrator/admin/systems/:system_id/weight", patch(set_system_weight))
+        .route("/api/orchestrator/admin/streams/:stream_id/pause", post(pause_stream_processing))
+        .route("/api/orchestrator/admin/streams/:stream_id/resume", post(resume_stream_processing))
+        .route("/api/orchestrator/admin/streams/:stream_id/window", get(get_stream_window).put(set_stream_window))
+        .route("/api/orchestrator/admin/queues", get(get_queue_summaries))
+        .route("/api/orchestrator/admin/alerts", get(get_recent_alerts))
+        .route("/api/orchestrator/admin/replay", post(start_context_replay))
+        .route("/api/orchestrator/admin/replay/:run_id", get(get_context_replay).delete(cancel_context_replay))
+        .route("/api/orchestrator/admin/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/api/orchestrator/admin/api-keys/:key_id/rotate", post(rotate_api_key))
+        .route("/api/orchestrator/admin/api-keys/:key_id", delete(revoke_api_key))
+        .route("/api/orchestrator/admin/streams/:stream_id/owner", put(assign_stream_owner))
+        .route("/api/orchestrator/models/:category", post(snapshot_pattern_model))
+        .route("/api/orchestrator/models/:category/:version/activate", post(activate_pattern_model))
+        .route("/api/orchestrator/models/:category/rollback", post(rollback_pattern_model))
+        .route("/api/orchestrator/dreams/trigger", post(trigger_dream_cycle))
+        .route("/api/orchestrator/dreams/backtest", post(backtest_dream_scenarios))
+        .route("/api/betting/resolve", post(resolve_bets))
+        .route("/api/betting/resolve/:bet_id", post(resolve_bet))
+        .route("/api/betting/bets/:bet_id/explanation", get(get_bet_explanation))
+        .route("/api/betting/bets/:bet_id/evaluations", get(list_bet_evaluations))
+        .route("/api/geolocation/exclusion-zones", post(add_exclusion_zone).layer(idempotency.layer()))
+        .route("/api/webhooks/subscriptions", get(list_webhook_subscriptions).post(create_webhook_subscription))
+        .route("/api/webhooks/subscriptions/:subscription_id", delete(deactivate_webhook_subscription))
+        .route("/api/webhooks/deliveries", get(list_webhook_deliveries))
+        .route("/api/webhooks/deliveries/:delivery_id/retry", post(retry_webhook_delivery))
+        .route_layer(middleware::from_fn_with_state(Access::roles(&[Role::Admin]), enforce_access));
+
+    // Creators manage the streams they own
+    let creator_routes = Router::new()
+        .route("/api/streams", post(create_stream).layer(idempotency.layer()))
+        .route("/api/creators/me/quota", get(get_creator_quota))
+        .route_layer(middleware::from_fn_with_state(Access::roles(&[Role::Creator]), enforce_access));
+    let stream_owner_routes = Router::new()
+        .route("/api/streams/:id/start", post(start_stream))
+        .route("/api/streams/:id/stop", post(stop_stream))
+        .route_layer(middleware::from_fn_with_state(
+            Access::stream_owner("id", app_state.stream_ownership.clone()),
+            enforce_access,
+        ));
+
+    // Bettors act only as themselves
+    let bettor_routes = Router::new()
+        .route("/api/betting/place", post(place_bet).layer(idempotency.layer()))
+        .route("/api/betting/slip/validate", post(validate_bet_slip))
+        .route("/api/betting/balance/:stream_id", get(get_balance))
+        .route("/api/betting/history", get(get_bet_history))
+        .route("/api/geolocation/verify", post(verify_location))
+        .route_layer(middleware::from_fn_with_state(Access::roles(&[Role::Bettor]), enforce_access));
+    let own_user_routes = Router::new()
+        .route("/api/geolocation/session/start/:user_id", post(start_location_session))
+        .route("/api/geolocation/history/:user_id", get(get_location_history))
+        .route("/api/users/:user_id/stats", get(get_user_betting_stats))
+        .route_layer(middleware::from_fn_with_state(
+            Access::own_user("user_id", &[Role::Bettor]),
+            enforce_access,
+        ));
+
+    // Any signed-in user, acting on their own account
+    let user_routes = Router::new()
+        .route("/api/users/me", get(get_current_user))
+        .route("/api/users/me/profile", put(update_current_profile))
+        .route("/api/users/me/devices", get(list_push_devices).post(register_push_device))
+        .route("/api/users/me/devices/:device_id", delete(remove_push_device))
+        .route("/api/users/me/sessions", get(list_user_sessions))
+        .route("/api/users/me/sessions/:session_id", delete(revoke_user_session))
+        .route("/api/users/me/notifications", get(get_push_preferences).put(set_push_preferences))
+        .route("/api/auth/verify-email/resend", post(resend_email_verification))
+        .route_layer(middleware::from_fn_with_state(
+            Access::roles(&[Role::Bettor, Role::Creator]),
+            enforce_access,
+        ));
+
+    // Ingestion endpoints for internal services; API keys are also held to their scopes
+    let service_routes = Router::new()
+        .route("/api/auth/oauth", post(oauth_sign_in))
+        .route(
+            "/api/analytics/:stream_id/notify",
+            post(analytics_update).layer(DefaultBodyLimit::max(config.limits.max_analytics_body_bytes)),
+        )
+        .route("/api/analytics/:stream_id/history", get(get_analytics_history))
+        .route("/api/analytics/:stream_id/archive", get(get_analytics_archive))
+        .route("/api/orchestrator/feedback", post(record_decision_outcome))
+        .route_layer(middleware::from_fn_with_state(Access::roles(&[Role::Service]), enforce_access));
+
+    // Build application routes
+    let app = Router::new()
+        // Health check
+        .route("/health", get(health_check))
+        .route("/healthz", get(liveness))
+        .route("/readyz", get(readiness))
+        .route("/metrics", get(prometheus_metrics))
+        .route("/api/system/health", get(system_health))
+        .route("/api/features", get(get_feature_flags))
+        
+        // Accounts
+        .route("/api/auth/register", post(register_user))
+        .route("/api/auth/login", post(login_user))
+        .route("/api/auth/verify-email", post(verify_email))
+        
+        // Stream management
+        .route("/api/streams", get(list_streams))
+        .route("/api/streams/:id", get(get_stream))
+        .route("/api/regions/route", get(route_to_region))
+        .route("/api/streams/:id/status", get(stream_status))
+        
+        // Betting endpoints
+        .route("/api/betting/stream/:stream_id/activity", get(get_betting_activity))
+        .route("/api/betting/stream/:stream_id/markets", get(get_open_markets))
+        .route("/api/betting/types", get(get_bet_types))
+        .route("/api/leaderboards/:metric", get(get_leaderboard))
+        
+        // Export files in local storage; the signed URL is the credential
+        .route("/api/exports/download/:export_id/:file", get(download_export))
+        
+        // The stream page's data in one round trip; REST remains the primary API
+        .route("/api/graphql", get(graphql::graphiql).post(graphql::execute))
+        
+        // Orchestrator introspection
+        .route("/api/orchestrator/decisions/:stream_id", get(get_orchestrator_decisions))
+        .route("/api/orchestrator/decisions/:stream_id/live", get(stream_orchestrator_decisions))
+        .route("/api/orchestrator/feedback/history", get(get_weight_history))
+        .route("/api/orchestrator/knowledge/query", get(query_knowledge))
+        .route("/api/orchestrator/models/:category", get(list_pattern_models))
+        .route("/api/orchestrator/models/:category/:version", get(get_pattern_model))
+        .route("/api/orchestrator/dreams/export", get(export_dream_scenarios))
+        
+        // WebSocket for real-time updates
+        .route("/ws/:stream_id", get(websocket_handler))
+        
+        // OpenAPI document and Swagger UI
+        .merge(SwaggerUi::new("/api/docs").url("/api/docs/openapi.json", api_docs::ApiDoc::openapi()))
+        
+        .merge(admin_routes)
+        .merge(creator_routes)
+        .merge(stream_owner_routes)
+        .merge(bettor_routes)
+        .merge(own_user_routes)
+        .merge(user_routes)
+        .merge(service_routes)
+        .layer(rate_limiter.layer())
+        .layer(middleware::from_fn(error_reporting::attribute))
+        .layer(middleware::from_fn(audit::attribute))
+        .layer(middleware::from_fn_with_state(Arc::new(config.tenancy.clone()), tenant::enforce))
+        .layer(middleware::from_fn_with_state(authenticator, authenticate))
+        .layer(DefaultBodyLimit::max(config.limits.max_body_bytes))
+        // Outside authentication so shed requests cost no credential lookups
+        .layer(middleware::from_fn_with_state(request_limits, limits::enforce))
+        .layer(cors::layer(&config.cors))
+        .layer(Extension(app_state));
+
+    // Routes are registered unversioned and served under /api/v1; the version
+    // layer rewrites paths, so it wraps the router instead of sitting inside it
+    let app = ApiVersionLayer::new(config.api_version.clone()).layer(app);
+    // Outermost, so every response and log line, including version and auth
+    // rejections, carries the correlation ID
+    let app = middleware::from_fn(error::correlate).layer(app);
+
+    // Connection info is the rate limiter's fallback identity for anonymous callers.
+    // On SIGTERM the listener stops accepting, WebSockets are closed and in-flight
+    // requests finish before serve returns
+    let make_service = ServiceExt::<Request>::into_make_service_with_connect_info::<std::net::SocketAddr>(app);
+
+    // Start server
+    if config.tls.enabled {
+        let address: std::net::SocketAddr = config.bind_address.parse()?;
+        let handle = axum_server::Handle::new();
+        let graceful = handle.clone();
+        let signal = shutdown::wait_for_signal(shutdown.clone());
+        runtime::spawn("server:tls_shutdown", async move {
+            signal.await;
+            graceful.graceful_shutdown(None);
+        });
+
+        info!("Server listening on {} (TLS)", config.bind_address);
+        match tls::acceptor(&config.tls, &shutdown).await? {
+            tls::TlsAcceptor::Files(rustls_config) => {
+                axum_server::bind_rustls(address, rustls_config)
+                    .handle(handle)
+                    .serve(make_service)
+                    .await?;
+            }
+            tls::TlsAcceptor::Acme(acceptor) => {
+                axum_server::bind(address)
+                    .acceptor(acceptor)
+                    .handle(handle)
+                    .serve(make_service)
+                    .await?;
+            }
+        }
+    } else {
+        let listener = TcpListener::bind(&config.bind_address).await?;
+        info!("Server listening on {}", config.bind_address);
+
+        axum::serve(listener, make_service)
+            .with_graceful_shutdown(shutdown::wait_for_signal(shutdown.clone()))
+            .await?;
+    }
+
+    // Let balance flushes, decision log writes and alert deliveries finish
+    let drain_timeout = std::time::Duration::from_secs(config.shutdown.drain_timeout_secs);
+    if shutdown.drain(drain_timeout).await {
+        info!("Shutdown complete");
+    }
+
+    Ok(())
+}
+
+async fn register_ai_systems(app_state: &AppState, config: &Config) -> Result<()> {
+    println!("🤖 Registering AI Systems with Metacognitive Orchestrator...");
+    
+    // The Python analytics service is always available as a sidecar
+    if config.analytics_adapter.enabled {
+        let adapter = AnalyticsServiceAdapter::new(
+            &config.analytics_service_url,
+            config.analytics_adapter.clone(),
+        ).map_err(|e| anyhow::anyhow!(e))?;
+        let weight = adapter.weight();
+        app_state.metacognitive_orchestrator
+            .register_ai_system(Box::new(adapter), weight)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?;
+        info!("Registered analytics service adapter at {}", config.analytics_service_url);
+    }
+    
+    let manifest_path = match &config.ai_systems_manifest_path {
+        Some(path) => path,
+        None => {
+            warn!("AI_SYSTEMS_MANIFEST not set; orchestrator will run without external AI systems");
+            return Ok(());
+        }
+    };
+    
+    let manifest = AISystemManifest::load(manifest_path)
+        .map_err(|e| anyhow::anyhow!(e))?;
+    let connectors = manifest.build_connectors()
+        .map_err(|e| anyhow::anyhow!(e))?;
+    
+    for (system, weight) in connectors {
+        let system_id = system.get_system_id();
+        app_state.metacognitive_orchestrator
+            .register_ai_system(system, weight)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?;
+        info!("Registered AI system {} (weight {:.2})", system_id, weight);
+    }
+    
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "Service is up", body = Object),
+    ),
+)]
+async fn health_check(State(state): State<AppState>) -> Json<Value> {
+    Json(json!({
+        "status": "healthy",
+        "service": "morphine-core",
+        "version": "1.0.0",
+        "region": state.regions.id(),
+        "timestamp": chrono::Utc::now().timestamp()
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses(
+        (status = 200, description = "The process is running; restart it if this fails", body = Object),
+    ),
+)]
+async fn liveness() -> Json<Value> {
+    Json(json!({ "status": "alive" }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "Every component is healthy; send traffic", body = Readiness),
+        (status = 503, description = "A component is unhealthy or the service is shutting down", body = Readiness),
+    ),
+)]
+async fn readiness(
+    State(state): State<AppState>,
+) -> (axum::http::StatusCode, Json<Readiness>) {
+    let (redis, database) = tokio::join!(
+        check_dependency(state.state_manager.ping()),
+        check_dependency(async {
+            sqlx::query("SELECT 1").execute(&state.db_pool).await.map(|_| ())
+        }),
+    );
+
+    let heartbeat_age = state.metacognitive_orchestrator.heartbeat_age();
+    let orchestrator = ComponentHealth {
+        healthy: heartbeat_age < state.metacognitive_orchestrator.heartbeat_stale_after(),
+        detail: format!("background loops last ticked {} ms ago", heartbeat_age.as_millis()),
+    };
+
+    let ingest = match &state.grpc_listening {
+        Some(listening) if listening.load(std::sync::atomic::Ordering::Relaxed) => ComponentHealth {
+            healthy: true,
+            detail: "gRPC ingest listener accepting connections".to_string(),
+        },
+        Some(_) => ComponentHealth {
+            healthy: false,
+            detail: "gRPC ingest listener is not accepting connections".to_string(),
+        },
+        None => ComponentHealth {
+            healthy: true,
+            detail: "gRPC disabled; ingest over HTTP only".to_string(),
+        },
+    };
+
+    let shutting_down = state.shutdown.is_triggered();
+    let ready = !shutting_down && redis.healthy && database.healthy && orchestrator.healthy && ingest.healthy;
+    let status = if ready {
+        axum::http::StatusCode::OK
+    } else {
+        axum::http::StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(Readiness { ready, shutting_down, redis, database, orchestrator, ingest }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/system/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "Status of the core service and its subsystems", body = SystemHealth),
+        (status = 500, description = "Active streams could not be listed"),
+    ),
+)]
+async fn system_health(
+    State(state): State<AppState>,
+) -> Result<AxumJson<SystemHealth>, ApiError> {
+    let orchestrator = &state.metacognitive_orchestrator;
+    let (orchestrator_health, metabolic_state, total_ai_systems) = tokio::join!(
+        orchestrator.get_system_health(),
+        orchestrator.metabolic_state(),
+        orchestrator.ai_system_count(),
+    );
+    let active_streams = state.stream_manager.get_active_streams().await.map_err(|e| {
+        error!("Failed to list active streams: {}", e);
+        ApiError::internal()
+    })?;
+    
+    // Check if reasoning engine is functional
+    let reasoning_status = "operational"; // Would check actual status
+    
+    let health = SystemHealth {
+        core_status: "operational".to_string(),
+        orchestrator_health: serde_json::to_value(orchestrator_health).unwrap_or_default(),
+        geolocation_active: true,
+        reasoning_engine_status: reasoning_status.to_string(),
+        active_streams: active_streams.len(),
+        total_ai_systems,
+        metabolic_state: serde_json::to_value(metabolic_state).unwrap_or_default(),
+        exclusion_zones_count: state.geolocation_service.exclusion_zone_count().await,
+    };
+    
+    Ok(AxumJson(health))
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses(
+        (status = 200, description = "Prometheus text exposition", body = String),
+    ),
+)]
+async fn prometheus_metrics(
+    State(state): State<AppState>,
+) -> Result<([(header::HeaderName, &'static str); 1], String), ApiError> {
+    let mut caches = state.geolocation_service.cache_stats();
+    caches.push(state.reasoning_engine.cache_stats());
+    caches.push(state.metacognitive_orchestrator.cache_stats());
+    caches.push(state.metacognitive_orchestrator.latency().cache_stats());
+    let mut datagrams = String::new();
+    if let Some(ingest) = &state.datagram_ingest {
+        caches.extend(ingest.cache_stats());
+        match ingest.encode() {
+            Ok(encoded) => datagrams = encoded,
+            Err(e) => warn!("Failed to render datagram metrics: {}", e),
+        }
+    }
+    let rendered = match state.metacognitive_orchestrator.render_metrics().await {
+        Ok(orchestrator) => state.pool_metrics.encode()
+            .and_then(|pools| Ok(orchestrator + &pools + &state.cache_metrics.encode(&caches)? + &datagrams))
+            .and_then(|body| Ok(body + &state.allocator_metrics.encode()?)),
+        Err(e) => Err(e),
+    };
+    match rendered {
+        Ok(body) => Ok(([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], body)),
+        Err(e) => {
+            error!("Failed to render metrics: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/streams",
+    tag = "streams",
+    params(PageParams),
+    responses(
+        (status = 200, description = "A page of streams", body = Object),
+        (status = 422, description = "Invalid page parameters"),
+    ),
+)]
+async fn list_streams(
+    State(state): State<AppState>,
+    page: PageRequest,
+) -> Result<Paginated<StreamInfo>, ApiError> {
+    match state.stream_manager.list_streams().await {
+        Ok(streams) => page.paginate(streams, &["created_at", "title", "viewer_count"]),
+        Err(e) => {
+            error!("Failed to list streams: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/streams/{id}",
+    tag = "streams",
+    params(("id" = String, Path, description = "Stream ID")),
+    responses(
+        (status = 200, description = "The stream", body = Object),
+        (status = 404, description = "Unknown stream"),
+    ),
+)]
+async fn get_stream(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.stream_manager.get_stream(&stream_id).await {
+        Ok(Some(stream)) => Ok(Json(json!({
+            "success": true,
+            "data": stream
+        }))),
+        Ok(None) => Err(ApiError::not_found(format!("Stream {} not found", stream_id))),
+        Err(e) => {
+            error!("Failed to get stream {}: {}", stream_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/regions/route",
+    tag = "streams",
+    params(RegionRouteQuery),
+    responses(
+        (status = 200, description = "Regional WebSocket and HLS endpoints, lowest estimated latency first", body = GeoRoutes),
+        (status = 404, description = "Unknown stream"),
+        (status = 400, description = "Incomplete or out of range position"),
+    ),
+)]
+async fn route_to_region(
+    State(state): State<AppState>,
+    Query(query): Query<RegionRouteQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let position = match (query.latitude, query.longitude) {
+        (Some(latitude), Some(longitude)) if (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude) => {
+            Some((latitude, longitude))
+        }
+        (None, None) => None,
+        _ => return Err(ApiError::bad_request("latitude and longitude must be given together, in degrees")),
+    };
+
+    let stream = match &query.stream_id {
+        Some(stream_id) => match state.stream_manager.get_stream(stream_id).await {
+            Ok(Some(stream)) => Some(stream),
+            Ok(None) => return Err(ApiError::not_found(format!("Stream {} not found", stream_id))),
+            Err(e) => {
+                error!("Failed to get stream {}: {}", stream_id, e);
+                return Err(ApiError::internal());
+            }
+        },
+        None => None,
+    };
+
+    let routes = state.regions.route(
+        stream.as_ref().map(|stream| (stream.id.as_str(), stream.region.as_deref())),
+        position,
+    );
+    Ok(Json(json!({
+        "success": true,
+        "data": routes
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/streams/{id}/start",
+    tag = "streams",
+    params(("id" = String, Path, description = "Stream ID")),
+    responses(
+        (status = 200, description = "Stream starting", body = Object),
+        (status = 409, description = "The stream could not be started"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn start_stream(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.stream_manager.start_stream(&stream_id).await {
+        Ok(_) => {
+            info!("Started stream: {}", stream_id);
+            let event = json!({ "stream_id": stream_id });
+            if let Err(e) = outbox::enqueue(&state.db_pool, WebhookEventType::StreamActivated.as_str(), &event).await {
+                error!("Failed to record activation of stream {}: {}", stream_id, e);
+            }
+            Ok(Json(json!({
+                "success": true,
+                "data": {"status": "starting"}
+            })))
+        }
+        Err(e) => {
+            error!("Failed to start stream {}: {}", stream_id, e);
+            Err(ApiError::conflict(e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/streams/{id}/stop",
+    tag = "streams",
+    params(("id" = String, Path, description = "Stream ID")),
+    responses(
+        (status = 200, description = "Stream stopped", body = Object),
+        (status = 409, description = "The stream could not be stopped"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn stop_stream(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.stream_manager.stop_stream(&stream_id).await {
+        Ok(_) => {
+            state.metacognitive_orchestrator.stop_stream(&stream_id).await;
+            info!("Stopped stream: {}", stream_id);
+            let event = json!({ "stream_id": stream_id });
+            if let Err(e) = outbox::enqueue(&state.db_pool, WebhookEventType::StreamStopped.as_str(), &event).await {
+                error!("Failed to record stop of stream {}: {}", stream_id, e);
+            }
+            Ok(Json(json!({
+                "success": true,
+                "data": {"status": "stopped"}
+            })))
+        }
+        Err(e) => {
+            error!("Failed to stop stream {}: {}", stream_id, e);
+            Err(ApiError::conflict(e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/streams/{id}/status",
+    tag = "streams",
+    params(("id" = String, Path, description = "Stream ID")),
+    responses(
+        (status = 200, description = "Current stream status, as JSON or, with `Accept: application/msgpack`, MessagePack", body = Object),
+    ),
+)]
+async fn stream_status(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+    encoding: Encoding,
+) -> Result<Negotiated<Value>, ApiError> {
+    match state.stream_manager.get_stream_status(&stream_id).await {
+        Ok(status) => Ok(encoding.respond(json!({
+            "success": true,
+            "status": status,
+            "timestamp": chrono::Utc::now().timestamp()
+        }))),
+        Err(e) => {
+            error!("Failed to get stream status for {}: {}", stream_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/betting/place",
+    tag = "betting",
+    request_body = PlaceBetRequest,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key replay the first response")),
+    responses(
+        (status = 200, description = "Bet placed", body = BetResponse),
+        (status = 409, description = "Bet rejected, e.g. for insufficient balance"),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn place_bet(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidJson(request): ValidJson<PlaceBetRequest>,
+) -> Result<Json<BetResponse>, ApiError> {
+    let bet_request = betting::BetRequest {
+        user_id: principal.acting_as(&request.user_id)?,
+        stream_id: request.stream_id,
+        // Both were checked by validation
+        bet_type: request.bet_type.parse().map_err(ApiError::bad_request)?,
+        stake_amount: request.stake_amount,
+        prediction: serde_json::from_value(request.prediction)
+            .map_err(|e| ApiError::bad_request(format!("Invalid prediction: {}", e)))?,
+        time_window_seconds: request.time_window_seconds.into(),
+    };
+
+    match state.betting_engine.place_bet(bet_request).await {
+        Ok(result) if result.success => Ok(Json(BetResponse {
+            success: true,
+            bet_id: result.bet_id,
+            message: result.message,
+            remaining_balance: result.remaining_balance,
+            bet_details: result.bet_details.map(|bet| json!(bet)),
+            matched_stake: result.matched_stake,
+            unmatched_stake: result.unmatched_stake,
+        })),
+        Ok(result) => Err(ApiError::new(ErrorCode::BetRejected, result.message)),
+        Err(e) => {
+            error!("Failed to place bet: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/betting/slip/validate",
+    tag = "betting",
+    request_body = BetSlipRequest,
+    responses(
+        (status = 200, description = "Per-line diagnostics for the slip; nothing is placed", body = BetSlipValidation),
+        (status = 422, description = "The slip has no lines, or too many"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn validate_bet_slip(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidJson(slip): ValidJson<BetSlipRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let mut lines: Vec<SlipLineDiagnostics> = Vec::with_capacity(slip.lines.len());
+    // Well-formed lines, with their position, go on to the engine
+    let mut requests = Vec::new();
+    let mut positions = Vec::new();
+    for (index, line) in slip.lines.iter().enumerate() {
+        let problems: Vec<SlipProblem> = match ValidationErrors::collect(line) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.into_fields().into_iter()
+                .map(|error| SlipProblem { check: SlipCheck::Invalid, message: format!("{} {}", error.field, error.message) })
+                .collect(),
+        };
+        if problems.is_empty() {
+            // Both were checked just now
+            if let (Ok(bet_type), Ok(prediction)) = (line.bet_type.parse(), serde_json::from_value(line.prediction.clone())) {
+                requests.push(betting::BetRequest {
+                    user_id: principal.subject.clone(),
+                    stream_id: line.stream_id.clone(),
+                    bet_type,
+                    stake_amount: line.stake_amount,
+                    prediction,
+                    time_window_seconds: line.time_window_seconds.into(),
+                });
+                positions.push(index);
+            }
+        }
+        lines.push(SlipLineDiagnostics { index, ok: false, odds: None, matched_stake: None, potential_payout: None, problems });
+    }
+
+    let checks = state.betting_engine.check_slip(&principal.subject, &requests).await.map_err(|e| {
+        error!("Failed to check bet slip for {}: {}", principal.subject, e);
+        ApiError::internal()
+    })?;
+    for (position, check) in positions.into_iter().zip(checks) {
+        let line = &mut lines[position];
+        line.odds = Some(check.odds);
+        line.matched_stake = Some(check.matched_stake);
+        line.potential_payout = Some(check.potential_payout);
+        line.problems.extend(check.problems);
+    }
+
+    for line in &mut lines {
+        line.ok = line.problems.is_empty();
+    }
+    let validation = BetSlipValidation {
+        valid: lines.iter().all(|line| line.ok),
+        total_stake: slip.lines.iter().map(|line| line.stake_amount).filter(|stake| stake.is_finite()).sum(),
+        total_potential_payout: lines.iter().filter(|line| line.ok).filter_map(|line| line.potential_payout).sum(),
+        lines,
+    };
+    Ok(Json(json!({
+        "success": true,
+        "data": validation
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/betting/balance/{stream_id}",
+    tag = "betting",
+    params(("stream_id" = String, Path, description = "Stream ID")),
+    responses(
+        (status = 200, description = "The caller's balance on the stream", body = Object),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_balance(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(stream_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.betting_engine.get_user_balance(&principal.subject, &stream_id).await {
+        Ok(balance) => Ok(Json(json!({
+            "success": true,
+            "data": BalanceSummary::from(&balance)
+        }))),
+        Err(e) => {
+            error!("Failed to get balance: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct BetHistoryQuery {
+    // Only bets on this stream
+    stream_id: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/betting/history",
+    tag = "betting",
+    params(BetHistoryQuery, PageParams),
+    responses(
+        (status = 200, description = "A page of the caller's bets, newest first, archived ones included", body = Object),
+        (status = 422, description = "Invalid page parameters"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_bet_history(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<BetHistoryQuery>,
+    page: PageRequest,
+) -> Result<Paginated<Bet>, ApiError> {
+    page.check_sort(&["created_at"])?;
+    state.betting_engine.bet_history(&principal.subject, query.stream_id.as_deref(), &page).await.map_err(|e| {
+        error!("Failed to load bet history for {}: {}", principal.subject, e);
+        ApiError::internal()
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/betting/stream/{stream_id}/activity",
+    tag = "betting",
+    params(("stream_id" = String, Path, description = "Stream ID"), PageParams),
+    responses(
+        (status = 200, description = "A page of recent betting activity", body = Object),
+        (status = 422, description = "Invalid page parameters"),
+    ),
+)]
+async fn get_betting_activity(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+    page: PageRequest,
+) -> Result<Paginated<StreamActivity>, ApiError> {
+    match state.betting_engine.get_stream_activity(&stream_id).await {
+        Ok(activity) => page.paginate(activity, &["timestamp", "amount"]),
+        Err(e) => {
+            error!("Failed to get betting activity: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/betting/stream/{stream_id}/markets",
+    tag = "betting",
+    params(("stream_id" = String, Path, description = "Stream ID")),
+    responses(
+        (status = 200, description = "Markets opened from the stream's predictions that are still taking bets, closing soonest first", body = [BettingOpportunity]),
+    ),
+)]
+async fn get_open_markets(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.open_opportunities(&stream_id).await {
+        Ok(markets) => Ok(Json(json!({
+            "success": true,
+            "data": markets
+        }))),
+        Err(e) => {
+            error!("Failed to list open markets for stream {}: {}", stream_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/{user_id}/stats",
+    tag = "betting",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The user's betting totals, win rate and ROI across every stream", body = Object),
+        (status = 404, description = "The user hasn't placed a bet"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_user_betting_stats(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.betting_engine.user_stats(&user_id).await {
+        Ok(Some(stats)) => Ok(Json(json!({
+            "success": true,
+            "data": stats
+        }))),
+        Ok(None) => Err(ApiError::not_found(format!("No bets placed by user {}", user_id))),
+        Err(e) => {
+            error!("Failed to load betting stats for {}: {}", user_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/leaderboards/{metric}",
+    tag = "betting",
+    params(
+        ("metric" = String, Path, description = "volume, net_profit, win_rate or roi"),
+        PageParams,
+    ),
+    responses(
+        (status = 200, description = "A page of bettors ranked by the metric, highest first", body = Object),
+        (status = 422, description = "Unknown metric or invalid page parameters"),
+    ),
+)]
+async fn get_leaderboard(
+    State(state): State<AppState>,
+    Path(metric): Path<String>,
+    page: PageRequest,
+) -> Result<Paginated<UserBettingStats>, ApiError> {
+    let metric: LeaderboardMetric = metric.parse().map_err(|message: String| {
+        let mut errors = ValidationErrors::new();
+        errors.add("metric", message);
+        ApiError::from(errors)
+    })?;
+    state.betting_engine.leaderboard(metric, &page).await.map_err(|e| {
+        error!("Failed to load the {} leaderboard: {}", metric.as_str(), e);
+        ApiError::internal()
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/betting/types",
+    tag = "betting",
+    responses(
+        (status = 200, description = "Supported bet types", body = Object),
+    ),
+)]
+async fn get_bet_types() -> Json<Value> {
+    Json(json!({
+        "success": true,
+        "data": [
+            {
+                "id": "speed_milestone",
+                "name": "Speed Milestone",
+                "description": "Bet on reaching a specific speed"
+            },
+            {
+                "id": "pose_event",
+                "name": "Pose Event", 
+                "description": "Bet on specific pose or joint angles"
+            },
+            {
+                "id": "detection_count",
+                "name": "Object Count",
+                "description": "Bet on number of detected objects"
+            },
+            {
+                "id": "motion_threshold",
+                "name": "Motion Level",
+                "description": "Bet on motion energy levels"
+            }
+        ]
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/betting/resolve/{bet_id}",
+    tag = "betting",
+    params(("bet_id" = String, Path, description = "Bet ID")),
+    request_body = ResolveBetRequest,
+    responses(
+        (status = 200, description = "Settlement result", body = Object),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn resolve_bet(
+    State(state): State<AppState>,
+    Path(bet_id): Path<String>,
+    ValidJson(request): ValidJson<ResolveBetRequest>,
+) -> Result<Json<Value>, ApiError> {
+    match state.betting_engine.resolve_bet(&bet_id, request.actual_result, request.confidence_score).await {
+        Ok(Some(bet)) => {
+            record_settlement(&state, &bet).await;
+            Ok(Json(json!({
+                "success": true,
+                "resolved": true,
+                "data": bet
+            })))
+        }
+        Ok(None) => Ok(Json(json!({
+            "success": true,
+            "resolved": false
+        }))),
+        Err(e) => {
+            error!("Failed to resolve bet {}: {}", bet_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/betting/resolve",
+    tag = "betting",
+    request_body = ResolveBetsRequest,
+    responses(
+        (status = 200, description = "Each bet's settlement result, in the order given", body = Object),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn resolve_bets(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<ResolveBetsRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let bet_ids: Vec<String> = request.resolutions.iter().map(|r| r.bet_id.clone()).collect();
+    let resolutions = request.resolutions.into_iter()
+        .map(|r| (r.bet_id, r.actual_result, r.confidence_score))
+        .collect();
+    let results = state.betting_engine.resolve_bets(resolutions).await;
+    for bet in results.iter().filter_map(|result| result.as_ref().ok().and_then(Option::as_ref)) {
+        record_settlement(&state, bet).await;
+    }
+
+    let data: Vec<Value> = bet_ids.into_iter().zip(results)
+        .map(|(bet_id, result)| match result {
+            Ok(Some(bet)) => json!({ "bet_id": bet_id, "resolved": true, "bet": bet }),
+            Ok(None) => json!({ "bet_id": bet_id, "resolved": false }),
+            Err(e) => {
+                error!("Failed to resolve bet {}: {}", bet_id, e);
+                json!({ "bet_id": bet_id, "resolved": false, "error": "Settlement failed" })
+            }
+        })
+        .collect();
+    Ok(Json(json!({
+        "success": true,
+        "data": data
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/betting/bets/{bet_id}/explanation",
+    tag = "betting",
+    params(("bet_id" = String, Path, description = "Bet ID")),
+    responses(
+        (status = 200, description = "The bet's latest evaluation: its reasoning steps, outcome and inputs", body = Object),
+        (status = 404, description = "The bet hasn't been evaluated"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_bet_explanation(
+    State(state): State<AppState>,
+    Path(bet_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.reasoning_engine.explain(&bet_id).await {
+        Ok(Some(evaluation)) => Ok(Json(json!({
+            "success": true,
+            "data": evaluation
+        }))),
+        Ok(None) => Err(ApiError::not_found(format!("Bet {} has not been evaluated", bet_id))),
+        Err(e) => {
+            error!("Failed to load the explanation for bet {}: {}", bet_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/betting/bets/{bet_id}/evaluations",
+    tag = "betting",
+    params(("bet_id" = String, Path, description = "Bet ID"), PageParams),
+    responses(
+        (status = 200, description = "A page of the bet's stored evaluations, newest first, for audit replay", body = Object),
+        (status = 422, description = "Invalid page parameters"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_bet_evaluations(
+    State(state): State<AppState>,
+    Path(bet_id): Path<String>,
+    page: PageRequest,
+) -> Result<Paginated<StoredEvaluation>, ApiError> {
+    match state.reasoning_engine.evaluations(&bet_id, page.limit, page.offset).await {
+        Ok((evaluations, total)) => Ok(page.page_of(evaluations, total)),
+        Err(e) => {
+            error!("Failed to load evaluations for bet {}: {}", bet_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/analytics/{stream_id}/notify",
+    tag = "analytics",
+    params(("stream_id" = String, Path, description = "Stream ID")),
+    request_body(
+        content = Object,
+        description = "An AnalyticsEvent: a detections, pose, motion or speed frame by `event_type`; frames of any other type are accepted as sent",
+    ),
+    responses(
+        (status = 200, description = "Frame accepted"),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "API key is not scoped for this endpoint"),
+    ),
+    security(("api_key" = [])),
+)]
+async fn analytics_update(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+    ValidJson(analytics): ValidJson<AnalyticsEvent>,
+) -> Result<Json<Value>, ApiError> {
+    match route_analytics(&state, &stream_id, analytics).await {
+        Ok(()) => Ok(Json(json!({"success": true}))),
+        Err(e) => {
+            error!("Failed to process analytics for stream {}: {}", stream_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+/// What happens to an accepted analytics frame however it arrived, over
+/// REST, gRPC or a datagram.
+async fn route_analytics(
+    state: &AppState,
+    stream_id: &str,
+    analytics: AnalyticsEvent,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let latency = state.metacognitive_orchestrator.latency();
+    let mut stamp = Stamp::received(analytics.timestamp());
+    if let Some(lag) = stamp.capture_lag() {
+        latency.observe(Stage::Capture, lag);
+    }
+
+    // Kept for the history endpoint and pushed to viewers; neither holds up processing
+    if let Err(e) = state.state_manager.set_analytics(stream_id, &analytics).await {
+        warn!("Failed to store analytics for stream {}: {}", stream_id, e);
+    }
+    latency.observe(Stage::Store, stamp.lap());
+
+    latency.observe(Stage::Fanout, stamp.since_received());
+    // Delivery and the orchestrator's queue are timed from the hand-off
+    stamp.lap();
+    state.websocket_manager.broadcast(WebSocketMessage::AnalyticsUpdate {
+        stream_id: stream_id.to_string(),
+        data: analytics.clone(),
+        stamp: Some(stamp),
+    });
+    latency.reached_viewers(stream_id, &stamp);
+
+    state.metacognitive_orchestrator.process_analytics(stream_id, analytics, stamp).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/decisions/{stream_id}",
+    tag = "orchestrator",
+    params(
+        ("stream_id" = String, Path, description = "Stream ID"),
+        DecisionQuery,
+    ),
+    responses(
+        (status = 200, description = "Logged decisions, newest first", body = Object),
+    ),
+)]
+async fn get_orchestrator_decisions(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+    Query(query): Query<DecisionQuery>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.query_decisions(&stream_id, &query).await {
+        Ok(decisions) => Ok(Json(json!({
+            "success": true,
+            "data": decisions
+        }))),
+        Err(e) => {
+            error!("Failed to query decisions for stream {}: {}", stream_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/orchestrator/dreams/trigger",
+    tag = "orchestrator",
+    responses(
+        (status = 200, description = "Dream cycle report", body = Object),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn trigger_dream_cycle(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.trigger_dream_cycle().await {
+        Ok(report) => Ok(Json(json!({
+            "success": true,
+            "data": report
+        }))),
+        Err(e) => {
+            warn!("Manual dream trigger rejected: {}", e);
+            Err(ApiError::conflict(e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/dreams/export",
+    tag = "orchestrator",
+    params(DreamExportQuery),
+    responses(
+        (status = 200, description = "Generated dream scenarios", body = Object),
+    ),
+)]
+async fn export_dream_scenarios(
+    State(state): State<AppState>,
+    Query(query): Query<DreamExportQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let export = state.metacognitive_orchestrator.export_dream_scenarios(query.limit).await;
+    
+    Ok(Json(json!({
+        "success": true,
+        "data": export
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/orchestrator/dreams/backtest",
+    tag = "orchestrator",
+    request_body = DreamBacktestRequest,
+    responses(
+        (status = 200, description = "Backtest results", body = Object),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn backtest_dream_scenarios(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<DreamBacktestRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let export = state.metacognitive_orchestrator.export_dream_scenarios(request.limit).await;
+    let report = state.reasoning_engine
+        .backtest_scenarios(&export.scenarios, request.bet_ids.as_deref())
+        .await;
+    
+    info!(
+        "Backtested {} dream scenarios against {} markets",
+        report.scenarios_evaluated, report.markets_evaluated
+    );
+    
+    Ok(Json(json!({
+        "success": true,
+        "data": report
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/decisions/{stream_id}/live",
+    tag = "orchestrator",
+    params(("stream_id" = String, Path, description = "Stream ID")),
+    responses(
+        (status = 200, description = "Server-sent events, one per decision", body = String),
+    ),
+)]
+async fn stream_orchestrator_decisions(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let decisions = state.metacognitive_orchestrator.subscribe_decisions(&stream_id).await;
+    
+    let events = futures::stream::unfold(decisions, |mut decisions| async move {
+        let event = match decisions.recv().await {
+            Ok(decision) => Event::default()
+                .event("decision")
+                .id(decision.decision_id.clone())
+                .json_data(&decision)
+                .unwrap_or_else(|_| Event::default().comment("failed to serialize decision")),
+            // Slow consumers skip ahead rather than stalling the stream
+            Err(broadcast::error::RecvError::Lagged(skipped)) => Event::default()
+                .event("lagged")
+                .data(skipped.to_string()),
+            Err(broadcast::error::RecvError::Closed) => return None,
+        };
+        Some((Ok(event), decisions))
+    });
+    
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/orchestrator/feedback",
+    tag = "orchestrator",
+    request_body = OutcomeFeedback,
+    responses(
+        (status = 200, description = "Outcome recorded", body = Object),
+        (status = 422, description = "Request validation failed"),
+        (status = 404, description = "Unknown decision"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "API key is not scoped for this endpoint"),
+    ),
+    security(("api_key" = [])),
+)]
+async fn record_decision_outcome(
+    State(state): State<AppState>,
+    ValidJson(feedback): ValidJson<OutcomeFeedback>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.record_outcome(feedback).await {
+        Ok(adjustments) => Ok(Json(json!({
+            "success": true,
+            "data": adjustments
+        }))),
+        Err(e) => {
+            error!("Failed to record decision outcome: {}", e);
+            Err(ApiError::not_found(e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/feedback/history",
+    tag = "orchestrator",
+    params(WeightHistoryQuery),
+    responses(
+        (status = 200, description = "Recent weight adjustments", body = Object),
+    ),
+)]
+async fn get_weight_history(
+    State(state): State<AppState>,
+    Query(query): Query<WeightHistoryQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let history = state.metacognitive_orchestrator.weight_history(query.limit.unwrap_or(100));
+    
+    Ok(Json(json!({
+        "success": true,
+        "data": history
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/knowledge/query",
+    tag = "orchestrator",
+    params(KnowledgeQuery),
+    responses(
+        (status = 200, description = "Matching entities, relations and facts", body = Object),
+    ),
+)]
+async fn query_knowledge(
+    State(state): State<AppState>,
+    Query(query): Query<KnowledgeQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let result = state.metacognitive_orchestrator.query_knowledge(&query);
+    
+    Ok(Json(json!({
+        "success": true,
+        "data": result
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/orchestrator/models/{category}",
+    tag = "orchestrator",
+    params(("category" = String, Path, description = "Stream category")),
+    request_body = NewPatternModel,
+    responses(
+        (status = 200, description = "Saved version", body = Object),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn snapshot_pattern_model(
+    State(state): State<AppState>,
+    Path(category): Path<String>,
+    ValidJson(request): ValidJson<NewPatternModel>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.snapshot_pattern_model(&category, request).await {
+        Ok(snapshot) => Ok(Json(json!({
+            "success": true,
+            "data": snapshot
+        }))),
+        Err(e) => {
+            error!("Failed to snapshot pattern model for {}: {}", category, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/models/{category}",
+    tag = "orchestrator",
+    params(("category" = String, Path, description = "Stream category")),
+    responses(
+        (status = 200, description = "Versions, newest first", body = Object),
+    ),
+)]
+async fn list_pattern_models(
+    State(state): State<AppState>,
+    Path(category): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.list_pattern_models(&category).await {
+        Ok(versions) => Ok(Json(json!({
+            "success": true,
+            "data": versions
+        }))),
+        Err(e) => {
+            error!("Failed to list pattern models for {}: {}", category, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/models/{category}/{version}",
+    tag = "orchestrator",
+    params(
+        ("category" = String, Path, description = "Stream category"),
+        ("version" = i32, Path, description = "Model version"),
+    ),
+    responses(
+        (status = 200, description = "The model version", body = Object),
+        (status = 404, description = "Unknown version"),
+    ),
+)]
+async fn get_pattern_model(
+    State(state): State<AppState>,
+    Path((category, version)): Path<(String, i32)>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.get_pattern_model(&category, version).await {
+        Ok(Some(snapshot)) => Ok(Json(json!({
+            "success": true,
+            "data": snapshot
+        }))),
+        Ok(None) => Err(ApiError::not_found(format!("No {} pattern model version {}", category, version))),
+        Err(e) => {
+            error!("Failed to get pattern model {} v{}: {}", category, version, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/orchestrator/models/{category}/{version}/activate",
+    tag = "orchestrator",
+    params(
+        ("category" = String, Path, description = "Stream category"),
+        ("version" = i32, Path, description = "Model version"),
+    ),
+    responses(
+        (status = 200, description = "Activated version", body = Object),
+        (status = 404, description = "Unknown version"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn activate_pattern_model(
+    State(state): State<AppState>,
+    Path((category, version)): Path<(String, i32)>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.activate_pattern_model(&category, version).await {
+        Ok(snapshot) => Ok(Json(json!({
+            "success": true,
+            "data": snapshot
+        }))),
+        Err(e) => {
+            warn!("Failed to activate pattern model {} v{}: {}", category, version, e);
+            Err(ApiError::not_found(e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/orchestrator/models/{category}/rollback",
+    tag = "orchestrator",
+    params(("category" = String, Path, description = "Stream category")),
+    responses(
+        (status = 200, description = "Re-activated version", body = Object),
+        (status = 409, description = "Nothing to roll back to"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn rollback_pattern_model(
+    State(state): State<AppState>,
+    Path(category): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.rollback_pattern_model(&category).await {
+        Ok(snapshot) => Ok(Json(json!({
+            "success": true,
+            "data": snapshot
+        }))),
+        Err(e) => {
+            warn!("Failed to roll back pattern model for {}: {}", category, e);
+            Err(ApiError::conflict(e.to_string()))
+        }
+    }
+}
+
+// Establishes who is calling; requests without credentials continue anonymously
+async fn authenticate(
+    State(authenticator): State<Arc<Authenticator>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let resolved = authenticator
+        .resolve(request.headers(), request.method().as_str(), request.uri().path())
+        .await?;
+
+    if let Some((principal, api_key)) = resolved {
+        // Handlers can take Extension<ApiKey> to see which service is calling
+        if let Some(api_key) = api_key {
+            request.extensions_mut().insert::<ApiKey>(api_key);
+        }
+        request.extensions_mut().insert(principal);
+    }
+
+    Ok(next.run(request).await)
+}
+
+async fn enforce_access(
+    State(access): State<Access>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let (mut parts, body) = request.into_parts();
+    let params = Path::<std::collections::HashMap<String, String>>::from_request_parts(&mut parts, &())
+        .await
+        .map(|Path(params)| params)
+        .unwrap_or_default();
+
+    if let Err(status) = access.check(parts.extensions.get::<Principal>(), &params).await {
+        warn!("Denied {} {} ({})", parts.method, parts.uri.path(), status);
+        return Err(status.into());
+    }
+
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/register",
+    tag = "auth",
+    request_body = Registration,
+    responses(
+        (status = 200, description = "Account created; returns the user and a token", body = Object),
+        (status = 422, description = "Request validation failed"),
+        (status = 400, description = "Invalid registration"),
+        (status = 409, description = "Email or username already taken"),
+    ),
+)]
+async fn register_user(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(registration): ValidJson<Registration>,
+) -> Result<Json<Value>, ApiError> {
+    let user = match state.users.register(&registration).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return Err(ApiError::conflict("Email or username already taken")),
+        Err(e) => {
+            error!("Failed to register user: {}", e);
+            return Err(ApiError::internal());
+        }
+    };
+    info!("Registered user {}", user.user_id);
+    
+    request_email_verification(&state, &user).await;
+    signed_in(&state, user, &headers).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Returns the user and a token", body = Object),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Invalid credentials"),
+    ),
+)]
+async fn login_user(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(request): ValidJson<LoginRequest>,
+) -> Result<Json<Value>, ApiError> {
+    match state.users.login(&request.email, &request.password).await {
+        Ok(Some(user)) => signed_in(&state, user, &headers).await,
+        Ok(None) => Err(ApiError::new(ErrorCode::Unauthorized, "Invalid email or password")),
+        Err(e) => {
+            error!("Failed to log in user: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+// The gateway completes the OAuth flow and hands over the verified identity
+#[utoipa::path(
+    post,
+    path = "/api/auth/oauth",
+    tag = "auth",
+    request_body = OAuthIdentity,
+    responses(
+        (status = 200, description = "Returns the user and a token", body = Object),
+        (status = 422, description = "Request validation failed"),
+        (status = 409, description = "Email belongs to an unlinked account"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "API key is not scoped for this endpoint"),
+    ),
+    security(("api_key" = [])),
+)]
+async fn oauth_sign_in(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ValidJson(identity): ValidJson<OAuthIdentity>,
+) -> Result<Json<Value>, ApiError> {
+    match state.users.sign_in_oauth(&identity).await {
+        Ok(Some(user)) => signed_in(&state, user, &headers).await,
+        Ok(None) => {
+            warn!("OAuth sign-in via {} conflicts with an existing account", identity.provider);
+            Err(ApiError::conflict("Email is already registered to another account"))
+        }
+        Err(e) => {
+            error!("Failed OAuth sign-in via {}: {}", identity.provider, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+// Each sign-in is a session of its own, so the device can be signed out alone
+async fn signed_in(state: &AppState, user: User, headers: &HeaderMap) -> Result<Json<Value>, ApiError> {
+    let session_id = Uuid::new_v4().to_string();
+    let token = state.authenticator.issue_user_token(&user, &session_id).map_err(|e| {
+        error!("Failed to issue token for user {}: {}", user.user_id, e);
+        ApiError::unavailable("User sign-in is not configured")
+    })?;
+    let origin = SessionOrigin::from_headers(headers);
+    if let Err(e) = state.sessions.open(session_id, &user.user_id, SessionKind::AuthToken, None, origin).await {
+        error!("Failed to record session for user {}: {}", user.user_id, e);
+        return Err(ApiError::unavailable("Unable to start a session"));
+    }
+    
+    Ok(Json(json!({
+        "success": true,
+        "data": { "user": user, "token": token }
+    })))
+}
+
+async fn request_email_verification(state: &AppState, user: &User) {
+    match state.users.create_email_verification(user).await {
+        Ok(token) => {
+            let hook = state.verification_hook.clone();
+            let user = user.clone();
+            runtime::spawn("auth:verification_hook", async move {
+                hook.verification_requested(&user, &token).await;
+            });
+        }
+        Err(e) => warn!("Failed to create email verification for user {}: {}", user.user_id, e),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/verify-email",
+    tag = "auth",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 200, description = "Email verified", body = User),
+        (status = 422, description = "Request validation failed"),
+        (status = 400, description = "Token is unknown, used or expired"),
+    ),
+)]
+async fn verify_email(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<VerifyEmailRequest>,
+) -> Result<Json<Value>, ApiError> {
+    match state.users.verify_email(&request.token).await {
+        Ok(Some(user)) => Ok(Json(json!({
+            "success": true,
+            "data": user
+        }))),
+        Ok(None) => Err(ApiError::bad_request("Verification token is unknown, used or expired")),
+        Err(e) => {
+            error!("Failed to verify email: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/verify-email/resend",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Verification requested"),
+        (status = 409, description = "Email is already verified"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn resend_email_verification(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<Value>, ApiError> {
+    let user = match state.users.get(&principal.subject).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return Err(ApiError::not_found("User not found")),
+        Err(e) => {
+            error!("Failed to load user {}: {}", principal.subject, e);
+            return Err(ApiError::internal());
+        }
+    };
+    if user.email_verified_at.is_some() {
+        return Err(ApiError::conflict("Email is already verified"));
+    }
+    
+    request_email_verification(&state, &user).await;
+    Ok(Json(json!({"success": true})))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/me",
+    tag = "users",
+    responses(
+        (status = 200, description = "The caller's account and profile", body = Object),
+        (status = 404, description = "Account not found"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_current_user(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<Value>, ApiError> {
+    let (user, profile) = tokio::join!(
+        state.users.get(&principal.subject),
+        state.users.profile(&principal.subject)
+    );
+    
+    match (user, profile) {
+        (Ok(Some(user)), Ok(profile)) => Ok(Json(json!({
+            "success": true,
+            "data": { "user": user, "profile": profile }
+        }))),
+        (Ok(None), _) => Err(ApiError::not_found("User not found")),
+        (Err(e), _) | (_, Err(e)) => {
+            error!("Failed to load user {}: {}", principal.subject, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/users/me/profile",
+    tag = "users",
+    request_body = ProfileUpdate,
+    responses(
+        (status = 200, description = "Updated profile", body = UserProfile),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn update_current_profile(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidJson(update): ValidJson<ProfileUpdate>,
+) -> Result<Json<Value>, ApiError> {
+    match state.users.update_profile(&principal.subject, &update).await {
+        Ok(profile) => Ok(Json(json!({
+            "success": true,
+            "data": profile
+        }))),
+        Err(e) => {
+            error!("Failed to update profile for user {}: {}", principal.subject, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/me/devices",
+    tag = "users",
+    responses(
+        (status = 200, description = "The caller's devices registered for push, most recently registered first", body = [PushDevice]),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_push_devices(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<Value>, ApiError> {
+    match state.push.list_devices(&principal.subject).await {
+        Ok(devices) => Ok(Json(json!({
+            "success": true,
+            "data": devices
+        }))),
+        Err(e) => {
+            error!("Failed to list push devices for user {}: {}", principal.subject, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/users/me/devices",
+    tag = "users",
+    request_body = NewPushDevice,
+    responses(
+        (status = 200, description = "Device registered, or its registration refreshed", body = PushDevice),
+        (status = 422, description = "Request validation failed, or push is not enabled for the platform"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn register_push_device(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidJson(device): ValidJson<NewPushDevice>,
+) -> Result<Json<Value>, ApiError> {
+    if !state.push.is_enabled(device.platform) {
+        let mut errors = ValidationErrors::new();
+        errors.add("platform", format!("{} push is not enabled on this server", device.platform.as_str()));
+        return Err(errors.into());
+    }
+
+    match state.push.register_device(&principal.subject, &device).await {
+        Ok(registered) => Ok(Json(json!({
+            "success": true,
+            "data": registered
+        }))),
+        Err(e) => {
+            error!("Failed to register push device for user {}: {}", principal.subject, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/users/me/devices/{device_id}",
+    tag = "users",
+    params(("device_id" = String, Path, description = "Device ID")),
+    responses(
+        (status = 200, description = "Device removed; it gets no more pushes", body = Object),
+        (status = 404, description = "The caller has no such device"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn remove_push_device(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(device_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.push.remove_device(&principal.subject, &device_id).await {
+        Ok(true) => Ok(Json(json!({
+            "success": true,
+            "data": { "device_id": device_id }
+        }))),
+        Ok(false) => Err(ApiError::not_found(format!("Device {} not found", device_id))),
+        Err(e) => {
+            error!("Failed to remove push device {} for user {}: {}", device_id, principal.subject, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/me/sessions",
+    tag = "users",
+    responses(
+        (status = 200, description = "The caller's signed-in tokens, WebSockets and location sessions, newest first, and which one is making this request", body = Object),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_user_sessions(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<Value>, ApiError> {
+    match state.sessions.list(&principal.subject).await {
+        Ok(sessions) => Ok(Json(json!({
+            "success": true,
+            "data": {
+                "sessions": sessions,
+                "current_session_id": principal.session_id,
+            }
+        }))),
+        Err(e) => {
+            error!("Failed to list sessions for user {}: {}", principal.subject, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/users/me/sessions/{session_id}",
+    tag = "users",
+    params(("session_id" = String, Path, description = "Session ID")),
+    responses(
+        (status = 200, description = "Session revoked, with the WebSockets and location sessions opened with it; lists the revoked session IDs", body = Object),
+        (status = 404, description = "The caller has no such session"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn revoke_user_session(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let revoked = match state.sessions.revoke(&principal.subject, &session_id).await {
+        Ok(Some(revoked)) => revoked,
+        Ok(None) => return Err(ApiError::not_found(format!("Session {} not found", session_id))),
+        Err(e) => {
+            error!("Failed to revoke session {} for user {}: {}", session_id, principal.subject, e);
+            return Err(ApiError::internal());
+        }
+    };
+    for session in &revoked {
+        record_audit(&state, AuditRecord {
+            action: AuditAction::SessionRevoked,
+            target_type: "session",
+            target_id: &session.session_id,
+            before: serde_json::to_value(session).ok(),
+            after: None,
+        }).await;
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "revoked": revoked.iter().map(|session| &session.session_id).collect::<Vec<_>>(),
+        }
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/me/notifications",
+    tag = "users",
+    responses(
+        (status = 200, description = "Which notifications are pushed to the caller", body = PushPreferences),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_push_preferences(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<Value>, ApiError> {
+    match state.push.preferences(&principal.subject).await {
+        Ok(preferences) => Ok(Json(json!({
+            "success": true,
+            "data": preferences
+        }))),
+        Err(e) => {
+            error!("Failed to load push preferences for user {}: {}", principal.subject, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/users/me/notifications",
+    tag = "users",
+    request_body = PushPreferences,
+    responses(
+        (status = 200, description = "Updated push preferences", body = PushPreferences),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn set_push_preferences(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidJson(preferences): ValidJson<PushPreferences>,
+) -> Result<Json<Value>, ApiError> {
+    match state.push.set_preferences(&principal.subject, &preferences).await {
+        Ok(()) => Ok(Json(json!({
+            "success": true,
+            "data": preferences
+        }))),
+        Err(e) => {
+            error!("Failed to update push preferences for user {}: {}", principal.subject, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/orchestrator/admin/streams/{stream_id}/owner",
+    tag = "admin",
+    params(("stream_id" = String, Path, description = "Stream ID")),
+    request_body = StreamOwnerRequest,
+    responses(
+        (status = 200, description = "Owner assigned", body = Object),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn assign_stream_owner(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+    ValidJson(request): ValidJson<StreamOwnerRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let previous = match state.stream_ownership.owner_of(&stream_id).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            error!("Failed to look up the owner of stream {}: {}", stream_id, e);
+            return Err(ApiError::internal());
+        }
+    };
+    match state.stream_ownership.assign(&stream_id, &request.creator_id).await {
+        Ok(()) => {
+            info!("Admin assigned stream {} to creator {}", stream_id, request.creator_id);
+            record_audit(&state, AuditRecord {
+                action: AuditAction::StreamOwnerAssigned,
+                target_type: "stream",
+                target_id: &stream_id,
+                before: previous.map(|creator_id| json!({ "creator_id": creator_id })),
+                after: Some(json!({ "creator_id": request.creator_id })),
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": { "stream_id": stream_id, "creator_id": request.creator_id }
+            })))
+        }
+        Err(e) => {
+            error!("Failed to assign owner of stream {}: {}", stream_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/admin/api-keys",
+    tag = "admin",
+    responses(
+        (status = 200, description = "All API keys, newest first", body = [ApiKey]),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_api_keys(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    match state.api_keys.list().await {
+        Ok(keys) => Ok(Json(json!({
+            "success": true,
+            "data": keys
+        }))),
+        Err(e) => {
+            error!("Failed to list API keys: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/orchestrator/admin/api-keys",
+    tag = "admin",
+    request_body = NewApiKey,
+    responses(
+        (status = 200, description = "The key and its secret, shown only once", body = IssuedApiKey),
+        (status = 422, description = "Request validation failed"),
+        (status = 400, description = "Invalid key request"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn create_api_key(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<NewApiKey>,
+) -> Result<Json<Value>, ApiError> {
+    match state.api_keys.create(&request).await {
+        Ok(issued) => {
+            info!("Admin created API key {} ({})", issued.key.key_id, issued.key.name);
+            record_audit(&state, AuditRecord {
+                action: AuditAction::ApiKeyCreated,
+                target_type: "api_key",
+                target_id: &issued.key.key_id,
+                before: None,
+                after: serde_json::to_value(&issued.key).ok(),
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": issued
+            })))
+        }
+        Err(e) => {
+            error!("Failed to create API key: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/orchestrator/admin/api-keys/{key_id}/rotate",
+    tag = "admin",
+    params(("key_id" = String, Path, description = "API key ID")),
+    request_body(content = Option<RotateApiKeyRequest>, description = "Optional grace period for the old key"),
+    responses(
+        (status = 200, description = "The replacement key and its secret", body = IssuedApiKey),
+        (status = 422, description = "Request validation failed"),
+        (status = 404, description = "Unknown or revoked key"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn rotate_api_key(
+    State(state): State<AppState>,
+    Path(key_id): Path<String>,
+    request: Option<Json<RotateApiKeyRequest>>,
+) -> Result<Json<Value>, ApiError> {
+    let grace = request.and_then(|Json(r)| r.grace_secs).map(std::time::Duration::from_secs);
+    
+    match state.api_keys.rotate(&key_id, grace).await {
+        Ok(Some(issued)) => {
+            info!("Admin rotated API key {} to {}", key_id, issued.key.key_id);
+            record_audit(&state, AuditRecord {
+                action: AuditAction::ApiKeyRotated,
+                target_type: "api_key",
+                target_id: &key_id,
+                before: Some(json!({ "key_id": key_id, "grace_secs": grace.map(|grace| grace.as_secs()) })),
+                after: serde_json::to_value(&issued.key).ok(),
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": issued
+            })))
+        }
+        Ok(None) => Err(ApiError::not_found(format!("API key {} not found", key_id))),
+        Err(e) => {
+            error!("Failed to rotate API key {}: {}", key_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/orchestrator/admin/api-keys/{key_id}",
+    tag = "admin",
+    params(("key_id" = String, Path, description = "API key ID")),
+    responses(
+        (status = 200, description = "Key revoked", body = Object),
+        (status = 404, description = "Unknown or already revoked key"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path(key_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.api_keys.revoke(&key_id).await {
+        Ok(true) => {
+            info!("Admin revoked API key {}", key_id);
+            record_audit(&state, AuditRecord {
+                action: AuditAction::ApiKeyRevoked,
+                target_type: "api_key",
+                target_id: &key_id,
+                before: Some(json!({ "key_id": key_id })),
+                after: None,
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": { "key_id": key_id, "revoked": true }
+            })))
+        }
+        Ok(false) => Err(ApiError::not_found(format!("API key {} not found", key_id))),
+        Err(e) => {
+            error!("Failed to revoke API key {}: {}", key_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/subscriptions",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "All subscriptions, newest first", body = [WebhookSubscription]),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_webhook_subscriptions(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    match state.webhooks.list_subscriptions().await {
+        Ok(subscriptions) => Ok(Json(json!({
+            "success": true,
+            "data": subscriptions
+        }))),
+        Err(e) => {
+            error!("Failed to list webhook subscriptions: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/webhooks/subscriptions",
+    tag = "webhooks",
+    request_body = NewWebhookSubscription,
+    responses(
+        (status = 200, description = "The subscription and its signing secret, shown only once", body = IssuedWebhookSubscription),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn create_webhook_subscription(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<NewWebhookSubscription>,
+) -> Result<Json<Value>, ApiError> {
+    match state.webhooks.create_subscription(&request).await {
+        Ok(issued) => {
+            info!("Admin subscribed {} to {:?}", issued.subscription.url, issued.subscription.event_types);
+            Ok(Json(json!({
+                "success": true,
+                "data": issued
+            })))
+        }
+        Err(e) => match e.downcast::<ValidationErrors>() {
+            Ok(errors) => Err((*errors).into()),
+            Err(e) => {
+                error!("Failed to create webhook subscription: {}", e);
+                Err(ApiError::internal())
+            }
+        },
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/subscriptions/{subscription_id}",
+    tag = "webhooks",
+    params(("subscription_id" = String, Path, description = "Subscription ID")),
+    responses(
+        (status = 200, description = "Subscription deactivated; its delivery log is kept", body = Object),
+        (status = 404, description = "Unknown or already deactivated subscription"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn deactivate_webhook_subscription(
+    State(state): State<AppState>,
+    Path(subscription_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.webhooks.deactivate_subscription(&subscription_id).await {
+        Ok(true) => {
+            info!("Admin deactivated webhook subscription {}", subscription_id);
+            Ok(Json(json!({
+                "success": true,
+                "data": { "subscription_id": subscription_id, "is_active": false }
+            })))
+        }
+        Ok(false) => Err(ApiError::not_found(format!("Webhook subscription {} not found", subscription_id))),
+        Err(e) => {
+            error!("Failed to deactivate webhook subscription {}: {}", subscription_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/deliveries",
+    tag = "webhooks",
+    params(DeliveryQuery, PageParams),
+    responses(
+        (status = 200, description = "A page of the delivery log, newest first", body = Object),
+        (status = 422, description = "Invalid page parameters"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_webhook_deliveries(
+    State(state): State<AppState>,
+    Query(query): Query<DeliveryQuery>,
+    page: PageRequest,
+) -> Result<Paginated<WebhookDelivery>, ApiError> {
+    page.check_sort(&["created_at"])?;
+    state.webhooks.list_deliveries(&query, &page).await.map_err(|e| {
+        error!("Failed to list webhook deliveries: {}", e);
+        ApiError::internal()
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/webhooks/deliveries/{delivery_id}/retry",
+    tag = "webhooks",
+    params(("delivery_id" = String, Path, description = "Delivery ID")),
+    responses(
+        (status = 200, description = "Delivery requeued", body = Object),
+        (status = 404, description = "No dead-lettered delivery with this ID"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn retry_webhook_delivery(
+    State(state): State<AppState>,
+    Path(delivery_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.webhooks.retry_delivery(&delivery_id).await {
+        Ok(Some(delivery)) => {
+            info!("Admin requeued dead-lettered webhook delivery {}", delivery_id);
+            Ok(Json(json!({
+                "success": true,
+                "data": delivery
+            })))
+        }
+        Ok(None) => Err(ApiError::not_found(format!("No dead-lettered delivery {}", delivery_id))),
+        Err(e) => {
+            error!("Failed to requeue webhook delivery {}: {}", delivery_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/admin/systems",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Registered AI systems", body = Object),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_ai_systems(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    let systems = state.metacognitive_orchestrator.list_ai_systems().await;
+    
+    Ok(Json(json!({
+        "success": true,
+        "data": systems
+    })))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/orchestrator/admin/systems/{system_id}/weight",
+    tag = "admin",
+    params(("system_id" = String, Path, description = "AI system ID")),
+    request_body = SystemWeightRequest,
+    responses(
+        (status = 200, description = "Previous and new weight", body = Object),
+        (status = 422, description = "Request validation failed"),
+        (status = 404, description = "Unknown system"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn set_system_weight(
+    State(state): State<AppState>,
+    Path(system_id): Path<String>,
+    ValidJson(request): ValidJson<SystemWeightRequest>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.set_system_weight(&system_id, request.weight).await {
+        Ok(previous) => {
+            info!("Admin set weight of {} from {:.3} to {:.3}", system_id, previous, request.weight);
+            record_audit(&state, AuditRecord {
+                action: AuditAction::SystemWeightChanged,
+                target_type: "ai_system",
+                target_id: &system_id,
+                before: Some(json!({ "weight": previous })),
+                after: Some(json!({ "weight": request.weight })),
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": {
+                    "system_id": system_id,
+                    "previous": previous,
+                    "weight": request.weight
+                }
+            })))
+        }
+        Err(e) => {
+            warn!("Failed to set weight for {}: {}", system_id, e);
+            Err(ApiError::bad_request(e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/orchestrator/admin/streams/{stream_id}/pause",
+    tag = "admin",
+    params(("stream_id" = String, Path, description = "Stream ID")),
+    responses(
+        (status = 200, description = "Stream paused", body = Object),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn pause_stream_processing(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let changed = state.metacognitive_orchestrator.pause_stream(&stream_id).await;
+    info!("Admin paused orchestration for stream {}", stream_id);
+    
+    Ok(Json(json!({
+        "success": true,
+        "data": {"stream_id": stream_id, "paused": true, "changed": changed}
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/orchestrator/admin/streams/{stream_id}/resume",
+    tag = "admin",
+    params(("stream_id" = String, Path, description = "Stream ID")),
+    responses(
+        (status = 200, description = "Stream resumed", body = Object),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn resume_stream_processing(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let changed = state.metacognitive_orchestrator.resume_stream(&stream_id).await;
+    info!("Admin resumed orchestration for stream {}", stream_id);
+    
+    Ok(Json(json!({
+        "success": true,
+        "data": {"stream_id": stream_id, "paused": false, "changed": changed}
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/admin/streams/{stream_id}/window",
+    tag = "admin",
+    params(("stream_id" = String, Path, description = "Stream ID")),
+    responses(
+        (status = 200, description = "Window policy in effect", body = WindowPolicy),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_stream_window(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let policy = state.metacognitive_orchestrator.get_stream_window(&stream_id).await;
+    
+    Ok(Json(json!({
+        "success": true,
+        "data": policy
+    })))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/orchestrator/admin/streams/{stream_id}/window",
+    tag = "admin",
+    params(("stream_id" = String, Path, description = "Stream ID")),
+    request_body = WindowPolicy,
+    responses(
+        (status = 200, description = "Policy applied", body = Object),
+        (status = 422, description = "Request validation failed"),
+        (status = 400, description = "Invalid policy"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn set_stream_window(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+    ValidJson(policy): ValidJson<WindowPolicy>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.set_stream_window(&stream_id, policy.clone()).await {
+        Ok(()) => {
+            info!("Admin set window policy for stream {}: {:?}", stream_id, policy.kind);
+            Ok(Json(json!({
+                "success": true,
+                "data": policy
+            })))
+        }
+        Err(e) => {
+            warn!("Rejected window policy for stream {}: {}", stream_id, e);
+            Err(ApiError::bad_request(e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/admin/alerts",
+    tag = "admin",
+    params(AlertHistoryQuery),
+    responses(
+        (status = 200, description = "Recent alerts, newest first", body = Object),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_recent_alerts(
+    State(state): State<AppState>,
+    Query(query): Query<AlertHistoryQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let alerts = state.metacognitive_orchestrator.recent_alerts(query.limit.unwrap_or(50));
+    
+    Ok(Json(json!({
+        "success": true,
+        "data": alerts
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/orchestrator/admin/replay",
+    tag = "admin",
+    request_body = ReplayRequest,
+    responses(
+        (status = 200, description = "Replay run started", body = Object),
+        (status = 422, description = "Request validation failed"),
+        (status = 400, description = "Invalid replay request"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn start_context_replay(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<ReplayRequest>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.start_replay(request).await {
+        Ok(run) => Ok(Json(json!({
+            "success": true,
+            "data": run
+        }))),
+        Err(e) => {
+            error!("Failed to start context replay: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/admin/replay/{run_id}",
+    tag = "admin",
+    params(("run_id" = String, Path, description = "Replay run ID")),
+    responses(
+        (status = 200, description = "Replay run progress and results", body = Object),
+        (status = 404, description = "Unknown run"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_context_replay(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.metacognitive_orchestrator.get_replay(&run_id) {
+        Some(run) => Ok(Json(json!({
+            "success": true,
+            "data": run
+        }))),
+        None => Err(ApiError::not_found(format!("Replay run {} not found", run_id))),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/orchestrator/admin/replay/{run_id}",
+    tag = "admin",
+    params(("run_id" = String, Path, description = "Replay run ID")),
+    responses(
+        (status = 200, description = "Run cancelled", body = Object),
+        (status = 404, description = "Unknown or finished run"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn cancel_context_replay(
+    State(state): State<AppState>,
+    Path(run_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    if !state.metacognitive_orchestrator.cancel_replay(&run_id) {
+        return Err(ApiError::not_found(format!("No running replay {}", run_id)));
+    }
+    
+    info!("Admin cancelled replay run {}", run_id);
+    Ok(Json(json!({
+        "success": true,
+        "data": { "run_id": run_id, "cancelled": true }
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/overview",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Streams, betting exposure, queues and dependency health", body = AdminOverview),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_admin_overview(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    let streams = state.stream_manager.get_active_streams().await.map_err(|e| {
+        error!("Failed to list active streams: {}", e);
+        ApiError::internal()
+    })?;
+    let books = state.betting_engine.open_books();
+    let queues = state.metacognitive_orchestrator.queue_summaries().await;
+
+    let (redis, database) = tokio::join!(
+        check_dependency(state.state_manager.ping()),
+        check_dependency(async {
+            sqlx::query("SELECT 1").execute(&state.db_pool).await.map(|_| ())
+        }),
+    );
+
+    let mut active_streams: Vec<StreamOverview> = streams.into_iter()
+        .map(|stream| {
+            let book = books.get(&stream.id).cloned().unwrap_or_default();
+            StreamOverview {
+                queued: queues.iter()
+                    .find(|queue| queue.stream_id == stream.id)
+                    .map(|queue| queue.queued)
+                    .unwrap_or(0),
+                stream_id: stream.id,
+                title: stream.title,
+                viewer_count: stream.viewer_count,
+                open_bets: book.open_bets,
+                open_stake: book.open_stake,
+                exposure: book.exposure,
+            }
+        })
+        .collect();
+    active_streams.sort_by(|a, b| b.viewer_count.cmp(&a.viewer_count));
+
+    let overview = AdminOverview {
+        generated_at: chrono::Utc::now(),
+        active_streams,
+        open_exposure: books.values().map(|book| book.exposure).sum(),
+        pending_settlements: books.values().map(|book| book.open_bets).sum(),
+        orchestrator_queue_depth: queues.iter().map(|queue| queue.queued).sum(),
+        paused_streams: queues.iter().filter(|queue| queue.paused).count(),
+        redis,
+        database,
+        exclusion_zones_count: state.geolocation_service.exclusion_zone_count().await,
+    };
+
+    Ok(Json(json!({
+        "success": true,
+        "data": overview
+    })))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FinancialSnapshotQuery {
+    // Only ledger entries from this instant on; the whole ledger if omitted
+    ledger_since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/financial-snapshot",
+    tag = "admin",
+    params(FinancialSnapshotQuery),
+    responses(
+        (status = 200, description = "Balances, open bets and ledger entries as of one watermark, with their totals", body = Object),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn export_financial_snapshot(
+    State(state): State<AppState>,
+    Query(query): Query<FinancialSnapshotQuery>,
+) -> Result<Json<Value>, ApiError> {
+    match state.betting_engine.financial_snapshot(query.ledger_since).await {
+        Ok(snapshot) => {
+            info!(
+                "Exported a financial snapshot at {}: {} balances, {} open bets, {} ledger entries",
+                snapshot.watermark, snapshot.balances.len(), snapshot.open_bets.len(), snapshot.ledger.len()
+            );
+            Ok(Json(json!({
+                "success": true,
+                "data": snapshot
+            })))
+        }
+        Err(e) => {
+            error!("Failed to export a financial snapshot: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+// Recorded once the change is made; failing to record it is logged and
+// reported rather than undoing a change that already took effect
+async fn record_audit(state: &AppState, record: AuditRecord<'_>) {
+    let action = record.action;
+    let target = format!("{} {}", record.target_type, record.target_id);
+    if let Err(e) = state.audit_log.record(record).await {
+        error!("Failed to record {} of {} in the audit log: {}", action.as_str(), target, e);
+    }
+}
+
+// A manual settlement, with the bet as it stood open before it
+async fn record_settlement(state: &AppState, bet: &Bet) {
+    let mut before = bet.clone();
+    before.status = betting::BetStatus::Active;
+    before.resolution_result = None;
+    record_audit(state, AuditRecord {
+        action: AuditAction::BetSettledManually,
+        target_type: "bet",
+        target_id: &bet.id,
+        before: serde_json::to_value(&before).ok(),
+        after: serde_json::to_value(bet).ok(),
+    }).await;
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/audit",
+    tag = "admin",
+    params(AuditQuery, PageParams),
+    responses(
+        (status = 200, description = "A page of the audit log, newest first", body = Object),
+        (status = 422, description = "Invalid page parameters"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_audit_entries(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+    page: PageRequest,
+) -> Result<Paginated<AuditEntry>, ApiError> {
+    page.check_sort(&["occurred_at"])?;
+    state.audit_log.list(&query, &page).await.map_err(|e| {
+        error!("Failed to list audit log entries: {}", e);
+        ApiError::internal()
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/audit/verify",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Whether the audit log's hash chain is intact, and where it breaks if not", body = ChainVerification),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn verify_audit_log(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    match state.audit_log.verify().await {
+        Ok(verification) => {
+            if !verification.intact {
+                error!(
+                    "Audit log hash chain broken at entry {:?} after {} intact entries",
+                    verification.first_broken_seq, verification.entries
+                );
+            }
+            Ok(Json(json!({
+                "success": true,
+                "data": verification
+            })))
+        }
+        Err(e) => {
+            error!("Failed to verify the audit log: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/risk/alerts",
+    tag = "admin",
+    params(RiskAlertQuery, PageParams),
+    responses(
+        (status = 200, description = "A page of risk alerts, newest first", body = Object),
+        (status = 422, description = "Invalid page parameters"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_risk_alerts(
+    State(state): State<AppState>,
+    Query(query): Query<RiskAlertQuery>,
+    page: PageRequest,
+) -> Result<Paginated<RiskAlert>, ApiError> {
+    page.check_sort(&["raised_at"])?;
+    state.risk.list_alerts(&query, &page).await.map_err(|e| {
+        error!("Failed to list risk alerts: {}", e);
+        ApiError::internal()
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/risk/alerts/{alert_id}/review",
+    tag = "admin",
+    params(("alert_id" = String, Path, description = "Risk alert ID")),
+    request_body = AlertReview,
+    responses(
+        (status = 200, description = "The alert as reviewed, and the accounts released", body = Object),
+        (status = 404, description = "Unknown alert"),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn review_risk_alert(
+    State(state): State<AppState>,
+    Path(alert_id): Path<String>,
+    ValidJson(review): ValidJson<AlertReview>,
+) -> Result<Json<Value>, ApiError> {
+    match state.risk.review(&alert_id, &review).await {
+        Ok(Some((alert, released))) => {
+            info!("Admin marked risk alert {} {}", alert_id, alert.status.as_str());
+            record_audit(&state, AuditRecord {
+                action: AuditAction::RiskAlertReviewed,
+                target_type: "risk_alert",
+                target_id: &alert_id,
+                before: None,
+                after: Some(json!({ "status": alert.status, "note": alert.review_note })),
+            }).await;
+            for user_id in &released {
+                record_audit(&state, AuditRecord {
+                    action: AuditAction::AccountUnfrozen,
+                    target_type: "user",
+                    target_id: user_id,
+                    before: Some(json!({ "frozen": true, "alert_id": alert_id })),
+                    after: Some(json!({ "frozen": false })),
+                }).await;
+            }
+            Ok(Json(json!({
+                "success": true,
+                "data": { "alert": alert, "released_user_ids": released }
+            })))
+        }
+        Ok(None) => Err(ApiError::not_found(format!("Risk alert {} not found", alert_id))),
+        Err(e) => {
+            error!("Failed to review risk alert {}: {}", alert_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/risk/freezes/{user_id}",
+    tag = "admin",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The account may bet again", body = Object),
+        (status = 404, description = "The account isn't frozen"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn release_account_freeze(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.risk.release(&user_id).await {
+        Ok(true) => {
+            info!("Admin released the freeze on {}", user_id);
+            record_audit(&state, AuditRecord {
+                action: AuditAction::AccountUnfrozen,
+                target_type: "user",
+                target_id: &user_id,
+                before: Some(json!({ "frozen": true })),
+                after: Some(json!({ "frozen": false })),
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": { "user_id": user_id, "frozen": false }
+            })))
+        }
+        Ok(false) => Err(ApiError::not_found(format!("User {} is not frozen", user_id))),
+        Err(e) => {
+            error!("Failed to release the freeze on {}: {}", user_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/exports",
+    tag = "admin",
+    request_body = ExportRequest,
+    responses(
+        (status = 200, description = "The export, queued; poll it until it completes", body = ExportJob),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn request_export(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<ExportRequest>,
+) -> Result<Json<Value>, ApiError> {
+    match state.exports.request(&request).await {
+        Ok(export) => {
+            info!(
+                "Admin requested export {} of {:?} from {} to {}",
+                export.export_id, export.datasets, export.from, export.to
+            );
+            Ok(Json(json!({
+                "success": true,
+                "data": export
+            })))
+        }
+        Err(e) => match e.downcast::<ValidationErrors>() {
+            Ok(errors) => Err((*errors).into()),
+            Err(e) => {
+                error!("Failed to request an export: {}", e);
+                Err(ApiError::internal())
+            }
+        },
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/exports",
+    tag = "admin",
+    params(PageParams),
+    responses(
+        (status = 200, description = "A page of exports, newest first", body = Object),
+        (status = 422, description = "Invalid page parameters"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_exports(
+    State(state): State<AppState>,
+    page: PageRequest,
+) -> Result<Paginated<ExportJob>, ApiError> {
+    page.check_sort(&["created_at"])?;
+    state.exports.list(&page).await.map_err(|e| {
+        error!("Failed to list exports: {}", e);
+        ApiError::internal()
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/exports/{export_id}",
+    tag = "admin",
+    params(("export_id" = String, Path, description = "Export ID")),
+    responses(
+        (status = 200, description = "The export, with freshly signed download URLs once completed", body = ExportJob),
+        (status = 404, description = "Unknown export"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_export(
+    State(state): State<AppState>,
+    Path(export_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.exports.get(&export_id).await {
+        Ok(Some(export)) => Ok(Json(json!({
+            "success": true,
+            "data": export
+        }))),
+        Ok(None) => Err(ApiError::not_found(format!("No export {}", export_id))),
+        Err(e) => {
+            error!("Failed to get export {}: {}", export_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/reports/fairness",
+    tag = "admin",
+    params(FairnessReportQuery),
+    responses(
+        (status = 200, description = "Realized margin and implied-vs-actual win rates of the bets settled in the range, per market type", body = FairnessReport),
+        (status = 400, description = "The range ends before it starts"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_fairness_report(
+    State(state): State<AppState>,
+    Query(query): Query<FairnessReportQuery>,
+) -> Result<Json<Value>, ApiError> {
+    if query.from >= query.to {
+        return Err(ApiError::bad_request("to must be after from"));
+    }
+    match state.betting_engine.fairness_report(query.from, query.to).await {
+        Ok(report) => Ok(Json(json!({
+            "success": true,
+            "data": report
+        }))),
+        Err(e) => {
+            error!("Failed to build the fairness report from {} to {}: {}", query.from, query.to, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ExportDownloadQuery {
+    expires: i64,
+    signature: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/exports/download/{export_id}/{file}",
+    tag = "admin",
+    params(
+        ("export_id" = String, Path, description = "Export ID"),
+        ("file" = String, Path, description = "The dataset's file, e.g. `bets.csv`"),
+        ExportDownloadQuery,
+    ),
+    responses(
+        (status = 200, description = "The file, as CSV or Parquet"),
+        (status = 404, description = "Unknown file, or an invalid or expired signature"),
+    ),
+)]
+async fn download_export(
+    State(state): State<AppState>,
+    Path((export_id, file)): Path<(String, String)>,
+    Query(query): Query<ExportDownloadQuery>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    match state.exports.download(&export_id, &file, query.expires, &query.signature).await {
+        Ok(Some((body, content_type))) => Ok((
+            [
+                (header::CONTENT_TYPE, content_type.to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}-{}\"", export_id, file)),
+            ],
+            body,
+        )),
+        // Also for a bad signature, so URLs can't be probed for which exports exist
+        Ok(None) => Err(ApiError::not_found("No such export file, or the link has expired")),
+        Err(e) => {
+            error!("Failed to read export file {}/{}: {}", export_id, file, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct StateAsOfQuery {
+    as_of: chrono::DateTime<chrono::Utc>,
+    // Only this bettor's bets and balances, and the streams they're on
+    user_id: Option<String>,
+    // Only this stream and the bets and balances on it
+    stream_id: Option<String>,
+    // At most this many entities; 10000 if omitted
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/timeline",
+    tag = "admin",
+    params(StateAsOfQuery),
+    responses(
+        (status = 200, description = "Streams, bets and balances as they stood at the instant", body = StateAsOf),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_state_as_of(
+    State(state): State<AppState>,
+    Query(query): Query<StateAsOfQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let filter = TimelineFilter { user_id: query.user_id, stream_id: query.stream_id };
+    match state.timeline.state_as_of(query.as_of, &filter, query.limit.unwrap_or(timeline::MAX_EVENTS)).await {
+        Ok(state_as_of) => Ok(Json(json!({
+            "success": true,
+            "data": state_as_of
+        }))),
+        Err(e) => {
+            error!("Failed to reconstruct state as of {}: {}", query.as_of, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct EntityHistoryQuery {
+    from: Option<chrono::DateTime<chrono::Utc>>,
+    to: Option<chrono::DateTime<chrono::Utc>>,
+    // At most this many changes; 10000 if omitted
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/timeline/{entity_type}/{entity_id}",
+    tag = "admin",
+    params(
+        ("entity_type" = String, Path, description = "stream, bet or balance"),
+        ("entity_id" = String, Path, description = "Stream or bet ID, or `user_id:stream_id` for a balance"),
+        EntityHistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "The entity's changes, oldest first", body = [StateEvent]),
+        (status = 400, description = "Unknown entity type"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_entity_history(
+    State(state): State<AppState>,
+    Path((entity_type, entity_id)): Path<(String, String)>,
+    Query(query): Query<EntityHistoryQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let entity_type: EntityType = entity_type.parse().map_err(|e: anyhow::Error| ApiError::bad_request(e.to_string()))?;
+    let limit = query.limit.unwrap_or(timeline::MAX_EVENTS);
+    match state.timeline.history(entity_type, &entity_id, query.from, query.to, limit).await {
+        Ok(events) => Ok(Json(json!({
+            "success": true,
+            "data": events
+        }))),
+        Err(e) => {
+            error!("Failed to read the history of {} {}: {}", entity_type.as_str(), entity_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct VerifyFrameHashRequest {
+    frame_hash: String,
+    // Only match frames from this stream
+    stream_id: Option<String>,
+}
+
+impl Validate for VerifyFrameHashRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("frame_hash", &self.frame_hash);
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/analytics/verify-frame",
+    tag = "admin",
+    request_body = VerifyFrameHashRequest,
+    responses(
+        (status = 200, description = "Whether the analytics service processed a frame with the hash", body = FrameHashVerification),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+        (status = 503, description = "The analytics service could not be reached or answered nonsense"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn verify_frame_hash(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<VerifyFrameHashRequest>,
+) -> Result<Json<Value>, ApiError> {
+    match state.analytics_client.verify_frame_hash(request.stream_id.as_deref(), &request.frame_hash).await {
+        Ok(verification) => Ok(Json(json!({
+            "success": true,
+            "data": verification
+        }))),
+        Err(e) => {
+            error!("Failed to verify frame hash {} with the analytics service: {}", request.frame_hash, e);
+            Err(ApiError::unavailable("The analytics service could not verify the frame"))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/analytics/{stream_id}/rerun-detection",
+    tag = "admin",
+    params(("stream_id" = String, Path, description = "Stream ID")),
+    request_body = Clip,
+    responses(
+        (status = 200, description = "What detection found in the clip this time", body = DetectionRerun),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+        (status = 503, description = "The analytics service could not be reached or answered nonsense"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn rerun_detection(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+    ValidJson(clip): ValidJson<Clip>,
+) -> Result<Json<Value>, ApiError> {
+    match state.analytics_client.rerun_detection(&stream_id, &clip).await {
+        Ok(rerun) => {
+            info!(
+                "Re-ran detection over {}s of stream {}: {} objects in {} frames",
+                clip.end - clip.start, stream_id, rerun.detections.len(), rerun.frames_analyzed
+            );
+            Ok(Json(json!({
+                "success": true,
+                "data": rerun
+            })))
+        }
+        Err(e) => match e.downcast::<ValidationErrors>() {
+            // Clips over the configured length are only caught by the client
+            Ok(errors) => Err(ApiError::from(*errors)),
+            Err(e) => {
+                error!("Failed to re-run detection for stream {}: {}", stream_id, e);
+                Err(ApiError::unavailable("The analytics service could not re-run detection"))
+            }
+        },
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/config/reload",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Sections applied, and changed sections that still need a restart", body = ReloadReport),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+        (status = 422, description = "The configuration file or environment is invalid; nothing was applied"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn reload_config(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    match state.config_reloader.reload() {
+        Ok(report) => Ok(Json(json!({
+            "success": true,
+            "data": report
+        }))),
+        Err(e) => {
+            warn!("Configuration reload failed; keeping the running configuration: {}", e);
+            Err(ApiError::new(ErrorCode::ValidationFailed, format!("Configuration not reloaded: {}", e)))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/debug/heap-profile",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Where on the instance the jemalloc heap profile was written", body = Object),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+        (status = 503, description = "This build can't write heap profiles, or profiling wasn't enabled at startup"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn dump_heap_profile() -> Result<Json<Value>, ApiError> {
+    let path = std::env::temp_dir().join(format!("morphine-heap-{}.prof", chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f")));
+    match allocator::dump_heap_profile(&path) {
+        Ok(()) => {
+            info!("Wrote a heap profile to {}", path.display());
+            Ok(Json(json!({
+                "success": true,
+                "data": { "path": path, "allocator": allocator::NAME }
+            })))
+        }
+        Err(e) => {
+            warn!("Failed to write a heap profile: {}", e);
+            Err(ApiError::unavailable(format!("Heap profile not written: {}", e)))
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FeatureFlagQuery {
+    // Also evaluate flags targeted at this stream
+    stream_id: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/features",
+    tag = "features",
+    params(FeatureFlagQuery),
+    responses(
+        (status = 200, description = "Every feature flag, evaluated for the caller", body = Object),
+    ),
+)]
+async fn get_feature_flags(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Query(query): Query<FeatureFlagQuery>,
+) -> Json<Value> {
+    // Only user IDs are targeted; API keys and the admin token get the untargeted answer
+    let user_id = principal.as_ref()
+        .filter(|Extension(principal)| matches!(principal.credential, Credential::UserToken))
+        .map(|Extension(principal)| principal.subject.as_str());
+    let context = FlagContext { user_id, stream_id: query.stream_id.as_deref() };
+
+    Json(json!({
+        "success": true,
+        "data": state.feature_flags.evaluate_all(&context)
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/features",
+    tag = "features",
+    responses(
+        (status = 200, description = "Stored feature flags; flags not listed use their defaults", body = [FeatureFlag]),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_feature_flags(
+    State(state): State<AppState>,
+) -> Json<Value> {
+    Json(json!({
+        "success": true,
+        "data": state.feature_flags.list()
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/features/{name}",
+    tag = "features",
+    params(("name" = String, Path, description = "Flag name, such as betting.pattern_bets")),
+    request_body = FeatureFlagUpdate,
+    responses(
+        (status = 200, description = "The flag as stored; other instances pick it up within the refresh interval", body = FeatureFlag),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn set_feature_flag(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    ValidJson(update): ValidJson<FeatureFlagUpdate>,
+) -> Result<Json<Value>, ApiError> {
+    let previous = state.feature_flags.list().into_iter().find(|flag| flag.name == name);
+    match state.feature_flags.upsert(&name, &update).await {
+        Ok(flag) => {
+            info!(
+                "Admin set feature flag {}: enabled={} rollout={}% users={} streams={}",
+                flag.name, flag.enabled, flag.rollout_percentage, flag.user_ids.len(), flag.stream_ids.len()
+            );
+            record_audit(&state, AuditRecord {
+                action: AuditAction::FeatureFlagSet,
+                target_type: "feature_flag",
+                target_id: &flag.name,
+                before: previous.and_then(|previous| serde_json::to_value(previous).ok()),
+                after: serde_json::to_value(&flag).ok(),
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": flag
+            })))
+        }
+        Err(e) => match e.downcast::<ValidationErrors>() {
+            Ok(errors) => Err((*errors).into()),
+            Err(e) => {
+                error!("Failed to set feature flag {}: {}", name, e);
+                Err(ApiError::internal())
+            }
+        },
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/features/{name}",
+    tag = "features",
+    params(("name" = String, Path, description = "Flag name")),
+    responses(
+        (status = 200, description = "Flag deleted; it evaluates to its default again", body = Object),
+        (status = 404, description = "No stored flag with this name"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn delete_feature_flag(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let previous = state.feature_flags.list().into_iter().find(|flag| flag.name == name);
+    match state.feature_flags.delete(&name).await {
+        Ok(true) => {
+            info!("Admin deleted feature flag {}", name);
+            record_audit(&state, AuditRecord {
+                action: AuditAction::FeatureFlagDeleted,
+                target_type: "feature_flag",
+                target_id: &name,
+                before: previous.and_then(|previous| serde_json::to_value(previous).ok()),
+                after: None,
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": { "name": name, "deleted": true }
+            })))
+        }
+        Ok(false) => Err(ApiError::not_found(format!("Feature flag {} not found", name))),
+        Err(e) => {
+            error!("Failed to delete feature flag {}: {}", name, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/stream-quotas",
+    tag = "admin",
+    responses(
+        (status = 200, description = "The tenant's stream usage against its quota, and the overrides of `[stream_quotas]` in force", body = Object),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_stream_quotas(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    let quotas = state.stream_manager.quotas();
+    let listed = async {
+        let tenant = quotas.usage(QuotaScope::Tenant, &quotas.current_tenant()).await?;
+        let overrides = quotas.list_overrides().await?;
+        anyhow::Ok((tenant, overrides))
+    };
+    match listed.await {
+        Ok((tenant, overrides)) => Ok(Json(json!({
+            "success": true,
+            "data": { "tenant": tenant, "overrides": overrides }
+        }))),
+        Err(e) => {
+            error!("Failed to list stream quotas: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/stream-quotas/tenant",
+    tag = "admin",
+    request_body = StreamQuotaRequest,
+    responses(
+        (status = 200, description = "The tenant's quota as stored; streams already active over it keep running", body = QuotaOverride),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn set_tenant_stream_quota(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<StreamQuotaRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let tenant_id = state.stream_manager.quotas().current_tenant();
+    set_stream_quota(&state, QuotaScope::Tenant, &tenant_id, request.max_active).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/stream-quotas/tenant",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Override removed; `[stream_quotas] per_tenant` applies again", body = Object),
+        (status = 404, description = "The tenant's quota isn't overridden"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn clear_tenant_stream_quota(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    let tenant_id = state.stream_manager.quotas().current_tenant();
+    clear_stream_quota(&state, QuotaScope::Tenant, &tenant_id).await
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/stream-quotas/creators/{creator_id}",
+    tag = "admin",
+    params(("creator_id" = String, Path, description = "Creator's user ID")),
+    request_body = StreamQuotaRequest,
+    responses(
+        (status = 200, description = "The creator's quota as stored; streams already active over it keep running", body = QuotaOverride),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn set_creator_stream_quota(
+    State(state): State<AppState>,
+    Path(creator_id): Path<String>,
+    ValidJson(request): ValidJson<StreamQuotaRequest>,
+) -> Result<Json<Value>, ApiError> {
+    set_stream_quota(&state, QuotaScope::Creator, &creator_id, request.max_active).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/stream-quotas/creators/{creator_id}",
+    tag = "admin",
+    params(("creator_id" = String, Path, description = "Creator's user ID")),
+    responses(
+        (status = 200, description = "Override removed; `[stream_quotas] per_creator` applies again", body = Object),
+        (status = 404, description = "The creator's quota isn't overridden"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn clear_creator_stream_quota(
+    State(state): State<AppState>,
+    Path(creator_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    clear_stream_quota(&state, QuotaScope::Creator, &creator_id).await
+}
+
+async fn set_stream_quota(
+    state: &AppState,
+    scope: QuotaScope,
+    subject_id: &str,
+    max_active: u32,
+) -> Result<Json<Value>, ApiError> {
+    let quotas = state.stream_manager.quotas();
+    let previous = match quotas.find_override(scope, subject_id).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            error!("Failed to look up the {} stream quota of {}: {}", scope.as_str(), subject_id, e);
+            return Err(ApiError::internal());
+        }
+    };
+    match quotas.set_override(scope, subject_id, max_active).await {
+        Ok(quota) => {
+            info!("Admin set the {} stream quota of {} to {}", scope.as_str(), subject_id, max_active);
+            record_audit(state, AuditRecord {
+                action: AuditAction::StreamQuotaSet,
+                target_type: "stream_quota",
+                target_id: &format!("{}:{}", scope.as_str(), subject_id),
+                before: previous.and_then(|previous| serde_json::to_value(previous).ok()),
+                after: serde_json::to_value(&quota).ok(),
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": quota
+            })))
+        }
+        Err(e) => {
+            error!("Failed to set the {} stream quota of {}: {}", scope.as_str(), subject_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+async fn clear_stream_quota(state: &AppState, scope: QuotaScope, subject_id: &str) -> Result<Json<Value>, ApiError> {
+    match state.stream_manager.quotas().clear_override(scope, subject_id).await {
+        Ok(Some(previous)) => {
+            info!("Admin cleared the {} stream quota override of {}", scope.as_str(), subject_id);
+            record_audit(state, AuditRecord {
+                action: AuditAction::StreamQuotaCleared,
+                target_type: "stream_quota",
+                target_id: &format!("{}:{}", scope.as_str(), subject_id),
+                before: serde_json::to_value(&previous).ok(),
+                after: None,
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": { "scope": scope, "subject_id": subject_id, "cleared": true }
+            })))
+        }
+        Ok(None) => Err(ApiError::not_found(format!("No {} stream quota override for {}", scope.as_str(), subject_id))),
+        Err(e) => {
+            error!("Failed to clear the {} stream quota of {}: {}", scope.as_str(), subject_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/streams/{stream_id}/emergency-stop",
+    tag = "admin",
+    params(("stream_id" = String, Path, description = "Stream identifier")),
+    request_body = EmergencyStopRequest,
+    responses(
+        (status = 200, description = "Betting and settlement on the stream stopped; open bets voided or frozen as asked", body = EmergencyStop),
+        (status = 404, description = "Stream not found"),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn emergency_stop_stream(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+    ValidJson(request): ValidJson<EmergencyStopRequest>,
+) -> Result<Json<Value>, ApiError> {
+    match state.stream_manager.get_stream(&stream_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(ApiError::not_found(format!("Stream {} not found", stream_id))),
+        Err(e) => {
+            error!("Failed to get stream {}: {}", stream_id, e);
+            return Err(ApiError::internal());
+        }
+    }
+    let previous = match state.market_suspensions.emergency(&stream_id).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            error!("Failed to look up the emergency stop on stream {}: {}", stream_id, e);
+            return Err(ApiError::internal());
+        }
+    };
+    let stop = match state.market_suspensions.emergency_stop(&stream_id, request.reason.trim(), request.open_bets).await {
+        Ok(stop) => stop,
+        Err(e) => {
+            error!("Failed to stop betting on stream {}: {}", stream_id, e);
+            return Err(ApiError::internal());
+        }
+    };
+    record_audit(&state, AuditRecord {
+        action: AuditAction::StreamEmergencyStopped,
+        target_type: "stream",
+        target_id: &stream_id,
+        before: previous.and_then(|previous| serde_json::to_value(previous).ok()),
+        after: serde_json::to_value(&stop).ok(),
+    }).await;
+
+    // Placements still under way finish before the void reads the open bets, and are voided with them
+    let voided = match request.open_bets {
+        OpenBetPolicy::Void => match state.betting_engine.void_stream_bets(&stream_id).await {
+            Ok(voided) => voided,
+            Err(e) => {
+                // The stop stands and holds back settlement; stopping again with `void` retries
+                error!("Failed to void open bets on stream {}: {}", stream_id, e);
+                return Err(ApiError::internal());
+            }
+        },
+        OpenBetPolicy::Freeze => Vec::new(),
+    };
+
+    let mut refunded: Vec<&str> = voided.iter().map(|bet| bet.user_id.as_str()).collect();
+    refunded.sort_unstable();
+    refunded.dedup();
+    for user_id in refunded {
+        match state.betting_engine.get_user_balance(user_id, &stream_id).await {
+            Ok(balance) => state.websocket_manager.broadcast(WebSocketMessage::BalanceUpdate {
+                user_id: user_id.to_string(),
+                stream_id: stream_id.clone(),
+                balance: balance.available_balance(),
+            }),
+            Err(e) => warn!("Failed to load the refunded balance of user {} on stream {}: {}", user_id, stream_id, e),
+        }
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "data": {
+            "stop": stop,
+            "voided_bets": voided.len(),
+            "refunded": voided.iter().map(|bet| bet.stake_amount).sum::<f64>()
+        }
+    })))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/streams/{stream_id}/emergency-stop",
+    tag = "admin",
+    params(("stream_id" = String, Path, description = "Stream identifier")),
+    responses(
+        (status = 200, description = "Stop lifted; the stream takes bets again and frozen bets can be settled", body = EmergencyStop),
+        (status = 404, description = "The stream isn't under an emergency stop"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn lift_emergency_stop(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.market_suspensions.lift_emergency(&stream_id).await {
+        Ok(Some(stop)) => {
+            info!("Admin lifted the emergency stop on stream {}", stream_id);
+            record_audit(&state, AuditRecord {
+                action: AuditAction::StreamEmergencyLifted,
+                target_type: "stream",
+                target_id: &stream_id,
+                before: serde_json::to_value(&stop).ok(),
+                after: None,
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": stop
+            })))
+        }
+        Ok(None) => Err(ApiError::not_found(format!("Stream {} isn't under an emergency stop", stream_id))),
+        Err(e) => {
+            error!("Failed to lift the emergency stop on stream {}: {}", stream_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/liquidity-caps",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Streams whose cap overrides `[betting] market_liquidity`", body = [LiquidityCap]),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn list_liquidity_caps(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    match state.betting_engine.list_liquidity_caps().await {
+        Ok(caps) => Ok(Json(json!({
+            "success": true,
+            "data": caps
+        }))),
+        Err(e) => {
+            error!("Failed to list liquidity caps: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/streams/{stream_id}/liquidity",
+    tag = "admin",
+    params(("stream_id" = String, Path, description = "Stream identifier")),
+    responses(
+        (status = 200, description = "The stream's cap, what its open bets owe and what's left", body = MarketLiquidity),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_market_liquidity(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.betting_engine.market_liquidity(&stream_id).await {
+        Ok(market) => Ok(Json(json!({
+            "success": true,
+            "data": market
+        }))),
+        Err(e) => {
+            error!("Failed to load the liquidity of stream {}: {}", stream_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/streams/{stream_id}/liquidity",
+    tag = "admin",
+    params(("stream_id" = String, Path, description = "Stream identifier")),
+    request_body = LiquidityCapRequest,
+    responses(
+        (status = 200, description = "The stream's cap as stored; open bets over it stay open", body = LiquidityCap),
+        (status = 404, description = "Stream not found"),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn set_liquidity_cap(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+    ValidJson(request): ValidJson<LiquidityCapRequest>,
+) -> Result<Json<Value>, ApiError> {
+    match state.stream_manager.get_stream(&stream_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(ApiError::not_found(format!("Stream {} not found", stream_id))),
+        Err(e) => {
+            error!("Failed to get stream {}: {}", stream_id, e);
+            return Err(ApiError::internal());
+        }
+    }
+    let previous = match state.betting_engine.find_liquidity_cap(&stream_id).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            error!("Failed to look up the liquidity cap of stream {}: {}", stream_id, e);
+            return Err(ApiError::internal());
+        }
+    };
+    match state.betting_engine.set_liquidity_cap(&stream_id, request.max_liability).await {
+        Ok(cap) => {
+            info!("Admin set the liquidity cap of stream {} to {:.2}", stream_id, request.max_liability);
+            record_audit(&state, AuditRecord {
+                action: AuditAction::LiquidityCapSet,
+                target_type: "stream",
+                target_id: &stream_id,
+                before: previous.and_then(|previous| serde_json::to_value(previous).ok()),
+                after: serde_json::to_value(&cap).ok(),
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": cap
+            })))
+        }
+        Err(e) => {
+            error!("Failed to set the liquidity cap of stream {}: {}", stream_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/streams/{stream_id}/liquidity",
+    tag = "admin",
+    params(("stream_id" = String, Path, description = "Stream identifier")),
+    responses(
+        (status = 200, description = "Override removed; `[betting] market_liquidity` applies again", body = LiquidityCap),
+        (status = 404, description = "The stream's cap isn't overridden"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn clear_liquidity_cap(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.betting_engine.clear_liquidity_cap(&stream_id).await {
+        Ok(Some(previous)) => {
+            info!("Admin cleared the liquidity cap override of stream {}", stream_id);
+            record_audit(&state, AuditRecord {
+                action: AuditAction::LiquidityCapCleared,
+                target_type: "stream",
+                target_id: &stream_id,
+                before: serde_json::to_value(&previous).ok(),
+                after: None,
+            }).await;
+            Ok(Json(json!({
+                "success": true,
+                "data": previous
+            })))
+        }
+        Ok(None) => Err(ApiError::not_found(format!("No liquidity cap override for stream {}", stream_id))),
+        Err(e) => {
+            error!("Failed to clear the liquidity cap of stream {}: {}", stream_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/creators/me/quota",
+    tag = "streams",
+    responses(
+        (status = 200, description = "The caller's active streams against their quota, and the tenant's against its own", body = Object),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the creator role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_creator_quota(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<Value>, ApiError> {
+    let quotas = state.stream_manager.quotas();
+    let usage = async {
+        let creator = quotas.usage(QuotaScope::Creator, &principal.subject).await?;
+        let tenant = quotas.usage(QuotaScope::Tenant, &quotas.current_tenant()).await?;
+        anyhow::Ok((creator, tenant))
+    };
+    match usage.await {
+        Ok((creator, tenant)) => Ok(Json(json!({
+            "success": true,
+            "data": { "creator": creator, "tenant": tenant }
+        }))),
+        Err(e) => {
+            error!("Failed to load the stream quota of creator {}: {}", principal.subject, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/admin/queues",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Per-stream queue depth and lag", body = Object),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_queue_summaries(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    let queues = state.metacognitive_orchestrator.queue_summaries().await;
+    
+    Ok(Json(json!({
+        "success": true,
+        "data": queues
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/orchestrator/admin/events",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Server-sent admin events", body = String),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn stream_admin_events(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let admin_events = state.metacognitive_orchestrator.subscribe_admin_events();
+    
+    let events = futures::stream::unfold(admin_events, |mut admin_events| async move {
+        let event = match admin_events.recv().await {
+            Ok(admin_event) => Event::default()
+                .event("admin")
+                .json_data(&admin_event)
+                .unwrap_or_else(|_| Event::default().comment("failed to serialize admin event")),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => Event::default()
+                .event("lagged")
+                .data(skipped.to_string()),
+            Err(broadcast::error::RecvError::Closed) => return None,
+        };
+        Some((Ok(event), admin_events))
+    });
+    
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/analytics/{stream_id}/history",
+    tag = "analytics",
+    params(
+        ("stream_id" = String, Path, description = "Stream ID"),
+        ("range" = Option<String>, Query, description = "How far back to look, e.g. 30s, 5min (the default) or 1h"),
+        PageParams,
+    ),
+    responses(
+        (status = 200, description = "A page of stored analytics for the stream, as JSON or, with `Accept: application/msgpack`, MessagePack", body = Object),
+        (status = 422, description = "Invalid range or page parameters"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "API key is not scoped for this endpoint"),
+    ),
+    security(("api_key" = [])),
+)]
+async fn get_analytics_history(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    page: PageRequest,
+    encoding: Encoding,
+) -> Result<Negotiated<Value>, ApiError> {
+    let range = params.get("range").map(String::as_str).unwrap_or("5min");
+    let Some(window) = history_window(range) else {
+        let mut errors = ValidationErrors::new();
+        errors.add("range", "must be a duration such as 30s, 5min or 1h");
+        return Err(errors.into());
+    };
+    let end = chrono::Utc::now();
+    let start = end - window;
+    
+    match state.state_manager.get_analytics_history(&stream_id, start.timestamp_millis(), end.timestamp_millis()).await {
+        Ok(frames) => Ok(page.paginate(frames, &["timestamp", "processing_time"])?.negotiated(encoding)),
+        Err(e) => {
+            error!("Failed to get analytics history: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+// `30s`, `5min`, `1h`: how far back the analytics history reaches
+fn history_window(range: &str) -> Option<chrono::Duration> {
+    let (amount, unit) = range.split_at(range.find(|c: char| !c.is_ascii_digit())?);
+    let amount: i64 = amount.parse().ok()?;
+    match unit {
+        "s" => Some(chrono::Duration::seconds(amount)),
+        "min" => Some(chrono::Duration::minutes(amount)),
+        "h" => Some(chrono::Duration::hours(amount)),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AnalyticsArchiveQuery {
+    from: chrono::DateTime<chrono::Utc>,
+    to: chrono::DateTime<chrono::Utc>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/analytics/{stream_id}/archive",
+    tag = "analytics",
+    params(("stream_id" = String, Path, description = "Stream ID"), AnalyticsArchiveQuery, PageParams),
+    responses(
+        (status = 200, description = "A page of the analytics archived for the stream between from and to, oldest first", body = Object),
+        (status = 422, description = "Invalid range or page parameters"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "API key is not scoped for this endpoint"),
+    ),
+    security(("api_key" = [])),
+)]
+async fn get_analytics_archive(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+    Query(query): Query<AnalyticsArchiveQuery>,
+    page: PageRequest,
+    encoding: Encoding,
+) -> Result<Negotiated<Value>, ApiError> {
+    page.check_sort(&["timestamp"])?;
+    if query.to <= query.from {
+        let mut errors = ValidationErrors::new();
+        errors.add("to", "must be after from");
+        return Err(errors.into());
+    }
+
+    match state.metacognitive_orchestrator.archived_analytics(&stream_id, query.from, query.to, &page).await {
+        Ok(frames) => Ok(frames.negotiated(encoding)),
+        Err(e) => {
+            error!("Failed to load archived analytics for stream {}: {}", stream_id, e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/geolocation/verify",
+    tag = "geolocation",
+    request_body = LocationVerificationRequest,
+    responses(
+        (status = 200, description = "Verification result", body = Object),
+        (status = 403, description = "Location session has been revoked or belongs to another user"),
+        (status = 404, description = "No such location session"),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn verify_location(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    ValidJson(request): ValidJson<LocationVerificationRequest>,
+) -> Result<Json<Value>, ApiError> {
+    // The instance that revoked it has already ended it; any other still holds it
+    match state.sessions.is_revoked(&request.session_id).await {
+        Ok(false) => {}
+        Ok(true) => return Err(ApiError::new(ErrorCode::Forbidden, "Location session has been revoked")),
+        Err(e) => {
+            error!("Failed to check location session {}: {}", request.session_id, e);
+            return Err(ApiError::unavailable("Unable to check the location session"));
+        }
+    }
+    // A verification clears its session's user to bet, so only they may send one
+    match state.geolocation_service.session_user(&request.session_id).await {
+        Some(user_id) if user_id == principal.subject => {}
+        Some(_) => return Err(ApiError::new(ErrorCode::Forbidden, "Location session belongs to another user")),
+        None => return Err(ApiError::not_found(format!("No location session {}", request.session_id))),
+    }
+    let was_excluded = state.geolocation_service.is_session_excluded(&request.session_id).await;
+    let verification = state.geolocation_service.update_location_multi_source(
+        &request.session_id,
+        request.gps_data,
+        request.cell_towers,
+        request.wifi_points,
+        request.video_frame_hash,
+    ).await;
+    match verification {
+        Ok(verification) => {
+            // Only entering an exclusion zone is an event, not every check while inside one
+            if verification.is_excluded && !was_excluded {
+                let event = json!({
+                    "user_id": verification.user_id,
+                    "session_id": verification.session_id,
+                    "verification_id": verification.verification_id,
+                    "zone_ids": verification.exclusion_zones.iter().map(|zone| &zone.zone_id).collect::<Vec<_>>(),
+                });
+                if let Err(e) = outbox::enqueue(&state.db_pool, WebhookEventType::UserExcluded.as_str(), &event).await {
+                    error!("Failed to record exclusion of user {}: {}", verification.user_id, e);
+                }
+            }
+            // Without it their bets would be refused, so the client should verify again
+            if let Err(e) = state.location_clearances.record(&verification).await {
+                error!("Failed to record location verification {} for user {}: {}", verification.verification_id, verification.user_id, e);
+                return Err(ApiError::unavailable("Unable to record the location verification"));
+            }
+            Ok(Json(json!({
+                "success": true,
+                "verification": verification
+            })))
+        }
+        Err(e) => {
+            error!("Failed to verify location: {}", e);
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/geolocation/exclusion-zones",
+    tag = "geolocation",
+    request_body = ExclusionZoneRequest,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key replay the first response")),
+    responses(
+        (status = 200, description = "Zone added"),
+        (status = 422, description = "Request validation failed"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller lacks the admin role"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn add_exclusion_zone(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<ExclusionZoneRequest>,
+) -> Result<Json<Value>, ApiError> {
+    // A zone saved with an existing ID replaces it; the audit log keeps what it replaced
+    let previous = match state.exclusion_zones.load_current().await {
+        Ok(zones) => zones.into_iter().find(|zone| zone.zone_id == request.zone.zone_id),
+        Err(e) => {
+            error!("Failed to load exclusion zones: {}", e);
+            return Err(ApiError::internal());
+        }
+    };
+    // Saved first, so a zone in force here survives a restart
+    if let Err(e) = state.exclusion_zones.save(&request.zone).await {
+        error!("Failed to save exclusion zone {}: {}", request.zone.zone_id, e);
+        return Err(ApiError::internal());
+    }
+    record_audit(&state, AuditRecord {
+        action: AuditAction::ExclusionZoneSaved,
+        target_type: "exclusion_zone",
+        target_id: &request.zone.zone_id,
+        before: previous.and_then(|previous| serde_json::to_value(previous).ok()),
+        after: serde_json::to_value(&request.zone).ok(),
+    }).await;
+    state.geolocation_service.add_exclusion_zone(request.zone).await;
+    info!("Admin added a geolocation exclusion zone");
+    
+    Ok(Json(json!({"success": true})))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/geolocation/session/start/{user_id}",
+    tag = "geolocation",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Session started", body = Object),
+        (status = 404, description = "No such active user"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn start_location_session(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.users.get(&user_id).await {
+        Ok(Some(user)) if user.is_active => {}
+        Ok(_) => return Err(ApiError::not_found(format!("No active user {}", user_id))),
+        Err(e) => {
+            error!("Failed to load user {}: {}", user_id, e);
+            return Err(ApiError::internal());
+        }
+    }
+    
+    let session_id = state.geolocation_service.start_location_session(user_id.clone()).await;
+    // Listed with the user's devices, under the token that started it when that's the user's own
+    let parent_id = principal.session_id.filter(|_| principal.subject == user_id);
+    let origin = SessionOrigin::from_headers(&headers);
+    if let Err(e) = state.sessions.open(session_id.clone(), &user_id, SessionKind::Geolocation, parent_id, origin).await {
+        error!("Failed to record location session for user {}: {}", user_id, e);
+        state.geolocation_service.end_location_session(&session_id).await;
+        return Err(ApiError::internal());
+    }
+
+    Ok(Json(json!({
+        "success": true,
+        "session_id": session_id
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/geolocation/history/{user_id}",
+    tag = "geolocation",
+    params(("user_id" = String, Path, description = "User ID"), PageParams),
+    responses(
+        (status = 200, description = "A page of the user's location verifications", body = Object),
+        (status = 422, description = "Invalid page parameters"),
+        (status = 401, description = "Missing or invalid credentials"),
+        (status = 403, description = "Caller may not act on this resource"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn get_location_history(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    page: PageRequest,
+) -> Result<Paginated<LocationVerification>, ApiError> {
+    let history = state.geolocation_service.get_location_history(&user_id).await;
+    page.paginate(history, &["timestamp_ns", "confidence_score"])
+}
+
+#[utoipa::path(
+    get,
+    path = "/ws/{stream_id}",
+    tag = "websocket",
+    params(
+        ("stream_id" = String, Path, description = "Stream ID"),
+        WebSocketAuthQuery,
+    ),
+    responses(
+        (status = 101, description = "Switching to the WebSocket protocol"),
+        (status = 401, description = "Missing or invalid user token"),
+    ),
+    security(("bearer" = [])),
+)]
+async fn websocket_handler(
+    State(state): State<AppState>,
+    Path(stream_id): Path<String>,
+    principal: Option<Extension<Principal>>,
+    Query(query): Query<WebSocketAuthQuery>,
+    headers: HeaderMap,
+    ws: axum::extract::WebSocketUpgrade,
+) -> Result<axum::response::Response, ApiError> {
+    let principal = match (principal, query.token) {
+        (Some(Extension(principal)), _) => principal,
+        (None, Some(token)) => state.authenticator.verify_user_session(&token).await?,
+        (None, None) => return Err(ApiError::unauthorized()),
+    };
+    
+    // Sockets act for a real, active account
+    let user = match state.users.get(&principal.subject).await {
+        Ok(Some(user)) if user.is_active => user,
+        Ok(_) => return Err(ApiError::unauthorized()),
+        Err(e) => {
+            error!("Failed to load user {} for WebSocket: {}", principal.subject, e);
+            return Err(ApiError::unavailable("Unable to verify the account"));
+        }
+    };
+    
+    info!("User {} opening WebSocket for stream {}", user.user_id, stream_id);
+    let origin = SessionOrigin::from_headers(&headers);
+    Ok(ws.on_upgrade(move |socket| websocket::handle_socket(socket, state, user, stream_id, principal.session_id, origin)))
+} 
\ No newline at end of file
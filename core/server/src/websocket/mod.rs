@@ -0,0 +1,479 @@
+use axum::extract::ws::{close_code, CloseFrame, WebSocket, Message};
+use tokio::sync::{broadcast, oneshot};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn, error};
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::analytics::AnalyticsEvent;
+use crate::api::WebSocketMessage;
+use crate::auth::{SessionKind, SessionOrigin, User, UserSession};
+use crate::latency::Stage;
+use crate::replay::ReplayError;
+use crate::runtime;
+
+// Messages a slow connection may fall behind by on one stream before it skips ahead
+const TOPIC_CAPACITY: usize = 256;
+// Streams one connection may follow at once
+const MAX_JOINED_STREAMS: usize = 32;
+
+/// Fans server messages out to the connections on this instance that follow
+/// the stream they're about. Each stream with followers has its own channel,
+/// dropped once the last of them leaves; messages about no stream in
+/// particular go to every connection.
+pub struct WebSocketManager {
+    broadcast_tx: broadcast::Sender<WebSocketMessage>,
+    topics: Mutex<HashMap<String, broadcast::Sender<WebSocketMessage>>>,
+}
+
+impl WebSocketManager {
+    pub fn new() -> Self {
+        let (broadcast_tx, _) = broadcast::channel(1000);
+        
+        Self {
+            broadcast_tx,
+            topics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sends `message` to the connections following its stream, or to every
+    /// connection if it isn't about one. A stream nobody follows here drops it.
+    pub fn broadcast(&self, message: WebSocketMessage) {
+        let Some(stream_id) = message.stream_id().map(str::to_string) else {
+            // Nobody connected is the usual reason, and not worth a warning
+            if self.broadcast_tx.send(message).is_err() {
+                debug!("No WebSocket connections for a broadcast message");
+            }
+            return;
+        };
+        let mut topics = self.topics.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(topic) = topics.get(&stream_id) {
+            if topic.send(message).is_err() {
+                // Its last follower has gone
+                topics.remove(&stream_id);
+            }
+        }
+    }
+
+    /// Every message about no stream in particular.
+    pub fn subscribe(&self) -> broadcast::Receiver<WebSocketMessage> {
+        self.broadcast_tx.subscribe()
+    }
+
+    /// Messages about `stream_id` from now on.
+    pub fn subscribe_stream(&self, stream_id: &str) -> broadcast::Receiver<WebSocketMessage> {
+        let mut topics = self.topics.lock().unwrap_or_else(|e| e.into_inner());
+        topics.entry(stream_id.to_string())
+            .or_insert_with(|| broadcast::channel(TOPIC_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Drops `stream_id`'s channel if nobody follows it any more.
+    fn release_stream(&self, stream_id: &str) {
+        let mut topics = self.topics.lock().unwrap_or_else(|e| e.into_inner());
+        if topics.get(stream_id).is_some_and(|topic| topic.receiver_count() == 0) {
+            topics.remove(stream_id);
+        }
+    }
+}
+
+/// The streams one connection follows, each forwarded into its outgoing
+/// queue by a task of its own. Dropping it stops them all.
+struct Subscriptions {
+    user_id: String,
+    manager: Arc<WebSocketManager>,
+    tx: tokio::sync::mpsc::UnboundedSender<WebSocketMessage>,
+    // Dropping a stream's sender stops its forwarder
+    forwarders: HashMap<String, oneshot::Sender<()>>,
+}
+
+impl Subscriptions {
+    fn new(user_id: String, manager: Arc<WebSocketManager>, tx: tokio::sync::mpsc::UnboundedSender<WebSocketMessage>) -> Self {
+        Self { user_id, manager, tx, forwarders: HashMap::new() }
+    }
+
+    /// Follows `stream_id`. Returns false if the connection already follows
+    /// as many streams as it may; following one twice changes nothing.
+    fn join(&mut self, stream_id: &str) -> bool {
+        if self.forwarders.contains_key(stream_id) {
+            return true;
+        }
+        if self.forwarders.len() >= MAX_JOINED_STREAMS {
+            return false;
+        }
+        let mut messages = self.manager.subscribe_stream(stream_id);
+        let (stop, mut stopped) = oneshot::channel();
+        let manager = self.manager.clone();
+        let tx = self.tx.clone();
+        let user_id = self.user_id.clone();
+        let watched_stream = stream_id.to_string();
+        runtime::spawn("websocket:stream", async move {
+            loop {
+                let received = tokio::select! {
+                    _ = &mut stopped => break,
+                    received = messages.recv() => received,
+                };
+                match received {
+                    Ok(message) => {
+                        if !is_for_user(&message, &user_id) {
+                            continue;
+                        }
+                        if tx.send(message).is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("WebSocket of user {} skipped {} messages on stream {}", user_id, skipped, watched_stream);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            // Only once this receiver is gone can the channel be seen to have no followers
+            drop(messages);
+            manager.release_stream(&watched_stream);
+        });
+        self.forwarders.insert(stream_id.to_string(), stop);
+        true
+    }
+
+    /// Stops following `stream_id`. Returns false if it wasn't followed.
+    fn leave(&mut self, stream_id: &str) -> bool {
+        self.forwarders.remove(stream_id).is_some()
+    }
+}
+
+// Balances are private to their user; everything else on a stream goes to all its followers
+fn is_for_user(message: &WebSocketMessage, user_id: &str) -> bool {
+    match message {
+        WebSocketMessage::BalanceUpdate { user_id: recipient, .. } => recipient == user_id,
+        _ => true,
+    }
+}
+
+// The connection has been authenticated as `user`, with the token session
+// `parent_id` if the token had one; messages can only act for them. It
+// follows `stream_id`, the one in its path, until it leaves it
+pub async fn handle_socket(
+    socket: WebSocket,
+    state: AppState,
+    user: User,
+    stream_id: String,
+    parent_id: Option<String>,
+    origin: SessionOrigin,
+) {
+    let (mut sender, mut receiver) = socket.split();
+    let session_id = Uuid::new_v4().to_string();
+
+    // Listed with the user's devices so it can be revoked like them
+    let session = match state.sessions.open(session_id.clone(), &user.user_id, SessionKind::WebSocket, parent_id, origin).await {
+        Ok(session) => session,
+        Err(e) => {
+            error!("Failed to record WebSocket session for user {}: {}", user.user_id, e);
+            let _ = sender.send(Message::Close(Some(CloseFrame {
+                code: close_code::ERROR,
+                reason: "Unable to start a session".into(),
+            }))).await;
+            return;
+        }
+    };
+
+    info!("New WebSocket connection: {} (user {})", session_id, user.user_id);
+
+    // Create a channel for this specific connection
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+    // Spawn a task to handle outgoing messages; on shutdown it closes the
+    // connection so clients know to reconnect elsewhere
+    let shutdown = state.shutdown.clone();
+    let latency = state.metacognitive_orchestrator.latency().clone();
+    // Revocations on this instance close the connection at once; others are
+    // caught when the connection next tries to move money
+    let mut revoked = state.sessions.subscribe();
+    let watched_session = session_id.clone();
+    let mut broadcasts = state.websocket_manager.subscribe();
+    let send_task = runtime::spawn("websocket:send", async move {
+        loop {
+            let msg = tokio::select! {
+                msg = rx.recv() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                msg = broadcasts.recv() => match msg {
+                    Ok(msg) => msg,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = shutdown.triggered() => {
+                    let _ = sender.send(Message::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "Server shutting down".into(),
+                    }))).await;
+                    break;
+                }
+                revocation = revoked.recv() => match revocation {
+                    Ok(revoked_id) if revoked_id == watched_session => {
+                        let _ = sender.send(Message::Close(Some(CloseFrame {
+                            code: close_code::POLICY,
+                            reason: "Session revoked".into(),
+                        }))).await;
+                        break;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                    _ => continue,
+                }
+            };
+            if let Ok(json) = serde_json::to_string(&msg) {
+                if sender.send(Message::Text(json)).await.is_err() {
+                    break;
+                }
+            }
+            if let WebSocketMessage::AnalyticsUpdate { stamp: Some(mut stamp), .. } = msg {
+                latency.observe(Stage::Delivery, stamp.lap());
+            }
+        }
+    });
+
+    // Handle incoming messages
+    let state_clone = state.clone();
+    let tx_clone = tx.clone();
+    let socket_session = session.clone();
+    let receive_task = runtime::spawn("websocket:receive", async move {
+        // Dropped with this task, so the connection stops following its streams when it ends
+        let mut subscriptions = Subscriptions::new(user.user_id.clone(), state_clone.websocket_manager.clone(), tx_clone.clone());
+        subscriptions.join(&stream_id);
+        while let Some(msg) = receiver.recv().await {
+            if let Ok(msg) = msg {
+                match msg {
+                    Message::Text(text) => {
+                        if let Err(e) = handle_text_message(text, &state_clone, &user, &socket_session, &mut subscriptions, &tx_clone).await {
+                            error!("Error handling WebSocket message: {}", e);
+                            let error_msg = WebSocketMessage::ErrorMessage {
+                                error: "Internal server error".to_string(),
+                            };
+                            let _ = tx_clone.send(error_msg);
+                        }
+                    }
+                    Message::Close(_) => {
+                        info!("WebSocket connection closed: {}", session_id);
+                        break;
+                    }
+                    _ => {}
+                }
+            } else {
+                break;
+            }
+        }
+    });
+
+    // Wait for either task to complete
+    tokio::select! {
+        _ = send_task => {},
+        _ = receive_task => {},
+    }
+
+    state.sessions.close(&session).await;
+    info!("WebSocket connection ended: {}", session_id);
+}
+
+async fn handle_text_message(
+    text: String,
+    state: &AppState,
+    user: &User,
+    session: &UserSession,
+    subscriptions: &mut Subscriptions,
+    tx: &tokio::sync::mpsc::UnboundedSender<WebSocketMessage>,
+) -> anyhow::Result<()> {
+    let message: WebSocketMessage = serde_json::from_str(&text)?;
+
+    match message {
+        WebSocketMessage::JoinStream { user_id, .. } | WebSocketMessage::PlaceBet { bet_request: crate::betting::BetRequest { user_id, .. }, .. }
+            if user_id != user.user_id =>
+        {
+            warn!("WebSocket user {} tried to act as {}", user.user_id, user_id);
+            tx.send(WebSocketMessage::ErrorMessage {
+                error: "Messages can only act for the signed-in user".to_string(),
+            })?;
+        }
+
+        WebSocketMessage::JoinStream { stream_id, .. } => {
+            if !subscriptions.join(&stream_id) {
+                tx.send(WebSocketMessage::ErrorMessage {
+                    error: format!("A connection can follow at most {} streams; leave one first", MAX_JOINED_STREAMS),
+                })?;
+                return Ok(());
+            }
+            // Get current stream status and send to client
+            if let Ok(Some(status)) = state.stream_manager.get_stream_status(&stream_id).await {
+                let response = WebSocketMessage::StreamUpdate {
+                    stream_id: stream_id.clone(),
+                    status,
+                };
+                tx.send(response)?;
+            }
+            match state.market_suspensions.status(&stream_id).await {
+                Ok(market) => tx.send(WebSocketMessage::MarketStatus {
+                    stream_id: market.stream_id,
+                    bettable: market.bettable,
+                    reason: market.reason,
+                })?,
+                Err(e) => warn!("Failed to look up market status for stream {}: {}", stream_id, e),
+            }
+        }
+
+        WebSocketMessage::LeaveStream { stream_id } => {
+            if !subscriptions.leave(&stream_id) {
+                tx.send(WebSocketMessage::ErrorMessage {
+                    error: format!("Not following stream {}", stream_id),
+                })?;
+            }
+        }
+
+        WebSocketMessage::PlaceBet { bet_request, nonce, timestamp } => {
+            if !session_active(state, session, tx).await? || !consume_nonce(state, user, nonce, timestamp, tx).await? {
+                return Ok(());
+            }
+            match state.betting_engine.place_bet(bet_request.clone()).await {
+                Ok(result) => {
+                    let response = WebSocketMessage::BetUpdate {
+                        bet_id: result.bet_id.clone(),
+                        result,
+                    };
+                    tx.send(response)?;
+
+                    // Send balance update
+                    let balance_update = WebSocketMessage::BalanceUpdate {
+                        user_id: bet_request.user_id.clone(),
+                        stream_id: bet_request.stream_id.clone(),
+                        balance: 0.0, // Would get actual balance from state
+                    };
+                    tx.send(balance_update)?;
+                }
+                Err(e) => {
+                    let error_msg = WebSocketMessage::ErrorMessage {
+                        error: format!("Failed to place bet: {}", e),
+                    };
+                    tx.send(error_msg)?;
+                }
+            }
+        }
+
+        WebSocketMessage::PledgeToStream { stream_id, amount, nonce, timestamp } => {
+            if !session_active(state, session, tx).await? || !consume_nonce(state, user, nonce, timestamp, tx).await? {
+                return Ok(());
+            }
+            // Handle stream pledge
+            // This would integrate with payment processing
+            info!("Received pledge of ${:.2} for stream {}", amount, stream_id);
+            
+            // For now, just acknowledge
+            let response = WebSocketMessage::StreamUpdate {
+                stream_id: stream_id.clone(),
+                status: crate::stream::StreamStatus {
+                    stream_id,
+                    state: crate::stream::StreamState::Pledging,
+                    current_pledges: amount,
+                    activation_threshold: 100.0,
+                    pledger_count: 1,
+                    active_viewers: 0,
+                    time_until_timeout: Some(300),
+                    activation_percentage: amount / 100.0 * 100.0,
+                    recent_activity: vec![],
+                },
+            };
+            tx.send(response)?;
+        }
+
+        WebSocketMessage::Ping => {
+            tx.send(WebSocketMessage::Pong)?;
+        }
+
+        _ => {
+            // Handle other message types
+        }
+    }
+
+    Ok(())
+}
+
+// Whether the connection's session, or the token it was opened with, is
+// still good; revoked on another instance, it's only caught here
+async fn session_active(
+    state: &AppState,
+    session: &UserSession,
+    tx: &tokio::sync::mpsc::UnboundedSender<WebSocketMessage>,
+) -> anyhow::Result<bool> {
+    for session_id in std::iter::once(&session.session_id).chain(session.parent_id.as_ref()) {
+        match state.sessions.is_revoked(session_id).await {
+            Ok(false) => {}
+            Ok(true) => {
+                warn!("WebSocket message from user {} refused: session {} revoked", session.user_id, session_id);
+                tx.send(WebSocketMessage::ErrorMessage {
+                    error: "Session has been revoked; sign in again".to_string(),
+                })?;
+                return Ok(false);
+            }
+            Err(e) => {
+                error!("Failed to check session {} of user {}: {}", session_id, session.user_id, e);
+                tx.send(WebSocketMessage::ErrorMessage {
+                    error: "Unable to verify the session; retry shortly".to_string(),
+                })?;
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+// Spends the nonce of a message that moves money, telling the client why
+// not if it can't be; false means the message must be dropped
+async fn consume_nonce(
+    state: &AppState,
+    user: &User,
+    nonce: String,
+    timestamp: i64,
+    tx: &tokio::sync::mpsc::UnboundedSender<WebSocketMessage>,
+) -> anyhow::Result<bool> {
+    match state.replay_guard.consume(&user.user_id, &nonce, timestamp).await {
+        Ok(()) => Ok(true),
+        Err(ReplayError::Rejected(reason)) => {
+            warn!("WebSocket message from user {} refused: {}", user.user_id, reason);
+            tx.send(WebSocketMessage::ReplayRejected { nonce, reason })?;
+            Ok(false)
+        }
+        Err(e) => {
+            error!("Failed to check WebSocket nonce for user {}: {}", user.user_id, e);
+            tx.send(WebSocketMessage::ErrorMessage {
+                error: "Unable to verify the message; retry with a new nonce".to_string(),
+            })?;
+            Ok(false)
+        }
+    }
+}
+
+// Helper function to broadcast analytics updates
+pub async fn broadcast_analytics_update(
+    stream_id: String,
+    analytics_data: AnalyticsEvent,
+    ws_manager: &WebSocketManager,
+) {
+    let message = WebSocketMessage::AnalyticsUpdate {
+        stream_id,
+        data: analytics_data,
+        stamp: None,
+    };
+    ws_manager.broadcast(message);
+}
+
+// Helper function to broadcast stream updates
+pub async fn broadcast_stream_update(
+    stream_id: String,
+    status: crate::stream::StreamStatus,
+    ws_manager: &WebSocketManager,
+) {
+    let message = WebSocketMessage::StreamUpdate {
+        stream_id,
+        status,
+    };
+    ws_manager.broadcast(message);
+} 
\ No newline at end of file
@@ -0,0 +1,142 @@
+use std::path::Path;
+use prometheus::{Encoder, IntGaugeVec, Opts, Registry, TextEncoder};
+
+type MetricsError = Box<dyn std::error::Error + Send + Sync>;
+pub type AllocatorError = Box<dyn std::error::Error + Send + Sync>;
+
+// Long-lived caches and per-connection buffers make the allocator the biggest
+// factor in memory under load, so it can be chosen at build time: the
+// `jemalloc` or `mimalloc` feature replaces the system allocator, and
+// `dhat-heap` builds a diagnostic binary that records every allocation.
+
+#[cfg(all(feature = "jemalloc", feature = "mimalloc"))]
+compile_error!("the jemalloc and mimalloc features each replace the allocator; enable at most one");
+#[cfg(all(feature = "dhat-heap", any(feature = "jemalloc", feature = "mimalloc")))]
+compile_error!("dhat-heap records allocations through its own allocator; build it without jemalloc or mimalloc");
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+#[cfg(feature = "mimalloc")]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+#[cfg(feature = "dhat-heap")]
+#[global_allocator]
+static GLOBAL: dhat::Alloc = dhat::Alloc;
+
+/// The allocator this build uses.
+pub const NAME: &str = if cfg!(feature = "jemalloc") {
+    "jemalloc"
+} else if cfg!(feature = "mimalloc") {
+    "mimalloc"
+} else if cfg!(feature = "dhat-heap") {
+    "dhat"
+} else {
+    "system"
+};
+
+/// Records allocations for as long as it's held, in a `dhat-heap` build, and
+/// writes them to `dhat-heap.json` in the working directory when dropped;
+/// hold it for the life of `main` and shut down cleanly to get the file.
+/// Does nothing in any other build.
+pub struct Profiling {
+    #[cfg(feature = "dhat-heap")]
+    _profiler: dhat::Profiler,
+}
+
+pub fn start_profiling() -> Profiling {
+    Profiling {
+        #[cfg(feature = "dhat-heap")]
+        _profiler: dhat::Profiler::new_heap(),
+    }
+}
+
+/// Writes a jemalloc heap profile to `path`, for `jeprof`. Needs a build with
+/// the `jemalloc-profiling` feature, started with profiling enabled in
+/// `_RJEM_MALLOC_CONF` (e.g. `prof:true`).
+pub fn dump_heap_profile(path: &Path) -> Result<(), AllocatorError> {
+    #[cfg(feature = "jemalloc-profiling")]
+    {
+        let path = std::ffi::CString::new(path.to_str().ok_or("heap profile path must be UTF-8")?)?;
+        // `prof.dump` takes a C string; `path` outlives the call
+        unsafe { tikv_jemalloc_ctl::raw::write(b"prof.dump\0", path.as_ptr()) }?;
+        Ok(())
+    }
+    #[cfg(not(feature = "jemalloc-profiling"))]
+    {
+        let _ = path;
+        Err("heap profiles need a build with the jemalloc-profiling feature".into())
+    }
+}
+
+/// Prometheus view of the allocator, read from it when scraped. Only
+/// jemalloc and dhat builds report byte counts.
+pub struct AllocatorMetrics {
+    registry: Registry,
+    bytes: IntGaugeVec,
+}
+
+impl AllocatorMetrics {
+    pub fn new() -> Result<Self, MetricsError> {
+        let registry = Registry::new_custom(Some("morphine".to_string()), None)?;
+
+        let info = IntGaugeVec::new(
+            Opts::new("allocator_info", "The allocator this build uses, as a label; always 1"),
+            &["allocator"],
+        )?;
+        let bytes = IntGaugeVec::new(
+            Opts::new("allocator_bytes", "Heap memory by what the allocator counts it as"),
+            &["kind"],
+        )?;
+
+        registry.register(Box::new(info.clone()))?;
+        registry.register(Box::new(bytes.clone()))?;
+        info.with_label_values(&[NAME]).set(1);
+
+        Ok(Self { registry, bytes })
+    }
+
+    /// Encodes the allocator's current figures in the Prometheus text exposition format.
+    pub fn encode(&self) -> Result<String, MetricsError> {
+        for (kind, value) in heap_stats()? {
+            self.bytes.with_label_values(&[kind]).set(value as i64);
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+#[cfg(feature = "jemalloc")]
+fn heap_stats() -> Result<Vec<(&'static str, u64)>, MetricsError> {
+    use tikv_jemalloc_ctl::{epoch, stats};
+
+    // jemalloc only refreshes its statistics when the epoch advances
+    epoch::advance()?;
+    Ok(vec![
+        ("allocated", stats::allocated::read()? as u64),
+        ("active", stats::active::read()? as u64),
+        ("resident", stats::resident::read()? as u64),
+        ("mapped", stats::mapped::read()? as u64),
+        ("retained", stats::retained::read()? as u64),
+    ])
+}
+
+// Only called while `start_profiling`'s guard is held; dhat panics otherwise
+#[cfg(feature = "dhat-heap")]
+fn heap_stats() -> Result<Vec<(&'static str, u64)>, MetricsError> {
+    let stats = dhat::HeapStats::get();
+    Ok(vec![
+        ("allocated", stats.curr_bytes as u64),
+        ("peak", stats.max_bytes as u64),
+        ("total", stats.total_bytes),
+    ])
+}
+
+#[cfg(not(any(feature = "jemalloc", feature = "dhat-heap")))]
+fn heap_stats() -> Result<Vec<(&'static str, u64)>, MetricsError> {
+    Ok(Vec::new())
+}
@@ -0,0 +1,336 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+use crate::orchestrator::priority_queue::ContextPriority;
+use crate::validation::{Validate, ValidationErrors};
+
+// What a frame that doesn't name its event type is archived and reported as
+pub const UNTYPED_EVENT: &str = "analytics_frame";
+
+/// One frame from the analytics service, typed by the `event_type` it names.
+/// Frames of any other type, or none, are kept whole as `Custom`, so older
+/// producers and new event types pass through untouched.
+#[derive(Debug, Clone)]
+pub enum AnalyticsEvent {
+    Detections(DetectionsEvent),
+    Pose(PoseEvent),
+    Motion(MotionEvent),
+    Speed(SpeedEvent),
+    Custom(CustomEvent),
+}
+
+/// What any typed frame may say about itself.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct FrameInfo {
+    // Seconds since the epoch; when it arrived if left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub priority: Option<ContextPriority>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DetectionsEvent {
+    #[serde(flatten)]
+    pub frame: FrameInfo,
+    pub detected_objects: Vec<DetectedObject>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DetectedObject {
+    pub object_type: String,
+    pub confidence: f64,
+    pub bounding_box: BoundingBox,
+    // km/h, when the object is being tracked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+}
+
+// Pixels from the frame's top left corner
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BoundingBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PoseEvent {
+    #[serde(flatten)]
+    pub frame: FrameInfo,
+    pub poses: Vec<Pose>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Pose {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject_id: Option<String>,
+    pub keypoints: Vec<Keypoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Keypoint {
+    // e.g. `left_wrist`
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MotionEvent {
+    #[serde(flatten)]
+    pub frame: FrameInfo,
+    pub optical_flow_magnitude: f64,
+    pub motion_energy: f64,
+    // Radians, clockwise from the frame's x axis
+    pub dominant_direction: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SpeedEvent {
+    #[serde(flatten)]
+    pub frame: FrameInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject_id: Option<String>,
+    // km/h
+    pub speed: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acceleration: Option<f64>,
+}
+
+/// A frame this version has no type for, exactly as it was sent.
+#[derive(Debug, Clone, Default)]
+pub struct CustomEvent {
+    pub data: Map<String, Value>,
+}
+
+// The typed events as they appear on the wire, told apart by `event_type`
+#[derive(Deserialize)]
+#[serde(tag = "event_type", rename_all = "snake_case")]
+enum Typed {
+    Detections(DetectionsEvent),
+    Pose(PoseEvent),
+    Motion(MotionEvent),
+    Speed(SpeedEvent),
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event_type", rename_all = "snake_case")]
+enum TypedRef<'a> {
+    Detections(&'a DetectionsEvent),
+    Pose(&'a PoseEvent),
+    Motion(&'a MotionEvent),
+    Speed(&'a SpeedEvent),
+}
+
+const TYPED_EVENTS: [&str; 4] = ["detections", "pose", "motion", "speed"];
+
+impl AnalyticsEvent {
+    pub fn event_type(&self) -> &str {
+        match self {
+            Self::Detections(_) => "detections",
+            Self::Pose(_) => "pose",
+            Self::Motion(_) => "motion",
+            Self::Speed(_) => "speed",
+            Self::Custom(event) => event.data.get("event_type").and_then(Value::as_str).unwrap_or(UNTYPED_EVENT),
+        }
+    }
+
+    pub fn timestamp(&self) -> Option<f64> {
+        match self.frame() {
+            Some(frame) => frame.timestamp,
+            None => self.custom_field("timestamp").and_then(Value::as_f64),
+        }
+    }
+
+    pub fn confidence(&self) -> Option<f64> {
+        match self.frame() {
+            Some(frame) => frame.confidence,
+            None => self.custom_field("confidence").and_then(Value::as_f64),
+        }
+    }
+
+    pub fn priority(&self) -> Option<ContextPriority> {
+        match self.frame() {
+            Some(frame) => frame.priority,
+            None => self.custom_field("priority").and_then(|v| ContextPriority::deserialize(v).ok()),
+        }
+    }
+
+    /// The frame's fields as sent, `event_type` included, for code that
+    /// works over any frame such as the orchestrator's context.
+    pub fn into_fields(self) -> Map<String, Value> {
+        match self {
+            Self::Custom(event) => event.data,
+            typed => match serde_json::to_value(&typed) {
+                Ok(Value::Object(fields)) => fields,
+                _ => Map::new(),
+            },
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Custom(event) => Value::Object(event.data.clone()),
+            typed => serde_json::to_value(typed).unwrap_or_default(),
+        }
+    }
+
+    fn frame(&self) -> Option<&FrameInfo> {
+        match self {
+            Self::Detections(event) => Some(&event.frame),
+            Self::Pose(event) => Some(&event.frame),
+            Self::Motion(event) => Some(&event.frame),
+            Self::Speed(event) => Some(&event.frame),
+            Self::Custom(_) => None,
+        }
+    }
+
+    fn custom_field(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Custom(event) => event.data.get(key),
+            _ => None,
+        }
+    }
+}
+
+// A frame that isn't an object is kept under `analytics`; only stored frames
+// from before frames had to be objects look like that
+fn into_map(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(fields) => fields,
+        other => Map::from_iter([("analytics".to_string(), other)]),
+    }
+}
+
+/// Never fails: a frame that doesn't match the type it names becomes
+/// `Custom`, which validation then rejects on ingest.
+impl From<Value> for AnalyticsEvent {
+    fn from(value: Value) -> Self {
+        let value = Value::Object(into_map(value));
+        match Typed::deserialize(&value) {
+            Ok(Typed::Detections(event)) => Self::Detections(event),
+            Ok(Typed::Pose(event)) => Self::Pose(event),
+            Ok(Typed::Motion(event)) => Self::Motion(event),
+            Ok(Typed::Speed(event)) => Self::Speed(event),
+            Err(_) => Self::Custom(CustomEvent { data: into_map(value) }),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AnalyticsEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            value @ Value::Object(_) => Ok(Self::from(value)),
+            _ => Err(de::Error::custom("an analytics frame must be a JSON object")),
+        }
+    }
+}
+
+impl Serialize for AnalyticsEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Detections(event) => TypedRef::Detections(event).serialize(serializer),
+            Self::Pose(event) => TypedRef::Pose(event).serialize(serializer),
+            Self::Motion(event) => TypedRef::Motion(event).serialize(serializer),
+            Self::Speed(event) => TypedRef::Speed(event).serialize(serializer),
+            Self::Custom(event) => event.data.serialize(serializer),
+        }
+    }
+}
+
+impl Validate for AnalyticsEvent {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        match self {
+            Self::Detections(event) => {
+                // Frame fields sit at the top level, not under a `frame` key
+                event.frame.validate_fields(errors);
+                for (index, object) in event.detected_objects.iter().enumerate() {
+                    errors.nested(&format!("detected_objects[{}]", index), object);
+                }
+            }
+            Self::Pose(event) => {
+                event.frame.validate_fields(errors);
+                for (index, pose) in event.poses.iter().enumerate() {
+                    for (point, keypoint) in pose.keypoints.iter().enumerate() {
+                        errors.nested(&format!("poses[{}].keypoints[{}]", index, point), keypoint);
+                    }
+                }
+            }
+            Self::Motion(event) => {
+                event.frame.validate_fields(errors);
+                for (field, value) in [
+                    ("optical_flow_magnitude", event.optical_flow_magnitude),
+                    ("motion_energy", event.motion_energy),
+                    ("dominant_direction", event.dominant_direction),
+                ] {
+                    if !value.is_finite() {
+                        errors.add(field, "must be a finite number");
+                    }
+                }
+            }
+            Self::Speed(event) => {
+                event.frame.validate_fields(errors);
+                errors.require_range("speed", event.speed, 0.0, f64::MAX);
+            }
+            Self::Custom(event) => event.validate_fields(errors),
+        }
+    }
+}
+
+impl Validate for CustomEvent {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        match self.data.get("event_type") {
+            Some(Value::String(event_type)) if TYPED_EVENTS.contains(&event_type.as_str()) => {
+                // Only a frame that didn't fit its type gets here; serde says why
+                if let Err(e) = Typed::deserialize(&Value::Object(self.data.clone())) {
+                    errors.add("body", format!("is not a valid {} event: {}", event_type, e));
+                }
+            }
+            Some(Value::String(_)) | None => {}
+            Some(_) => errors.add("event_type", "must be a string"),
+        }
+        if let Some(confidence) = self.data.get("confidence") {
+            match confidence.as_f64() {
+                Some(confidence) => errors.require_range("confidence", confidence, 0.0, 1.0),
+                None => errors.add("confidence", "must be a number"),
+            }
+        }
+    }
+}
+
+impl Validate for FrameInfo {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        if let Some(confidence) = self.confidence {
+            errors.require_range("confidence", confidence, 0.0, 1.0);
+        }
+        if self.timestamp.map(|timestamp| !timestamp.is_finite()).unwrap_or(false) {
+            errors.add("timestamp", "must be a finite number");
+        }
+    }
+}
+
+impl Validate for DetectedObject {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("object_type", &self.object_type);
+        errors.require_range("confidence", self.confidence, 0.0, 1.0);
+        errors.require_range("bounding_box.width", self.bounding_box.width, 0.0, f64::MAX);
+        errors.require_range("bounding_box.height", self.bounding_box.height, 0.0, f64::MAX);
+        if let Some(speed) = self.speed {
+            errors.require_range("speed", speed, 0.0, f64::MAX);
+        }
+    }
+}
+
+impl Validate for Keypoint {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("name", &self.name);
+        errors.require_range("confidence", self.confidence, 0.0, 1.0);
+    }
+}
@@ -0,0 +1,215 @@
+use std::time::Duration;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::analytics::DetectedObject;
+use crate::config::AnalyticsClientConfig;
+use crate::validation::{Validate, ValidationErrors};
+
+pub type AnalyticsClientError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Asks the analytics service to look at footage again, for dispute
+/// resolution and transaction verification rather than the live frame stream:
+/// whether a frame hash is one it actually processed, and what detection finds
+/// in a clip when run over it again. Failed calls are retried with backoff;
+/// replies that don't match the expected shape are rejected, not retried.
+pub struct AnalyticsClient {
+    base_url: String,
+    client: reqwest::Client,
+    config: AnalyticsClientConfig,
+}
+
+#[derive(Debug, Serialize)]
+struct VerifyFrameRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_id: Option<&'a str>,
+    frame_hash: &'a str,
+}
+
+/// Whether the analytics service saw a frame with the hash.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FrameHashVerification {
+    pub frame_hash: String,
+    pub verified: bool,
+    // Set when verified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_id: Option<String>,
+    // Seconds since the epoch the frame was captured; set when verified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub captured_at: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_idx: Option<i64>,
+}
+
+/// A span of a stream's footage, in seconds since the epoch.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Clip {
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct RerunDetectionRequest<'a> {
+    stream_id: &'a str,
+    start: f64,
+    end: f64,
+}
+
+/// What detection found in a clip when run over it again.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DetectionRerun {
+    pub stream_id: String,
+    pub start: f64,
+    pub end: f64,
+    pub frames_analyzed: u64,
+    pub detections: Vec<ClipDetection>,
+    // Seconds the service spent on it
+    pub processing_time: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ClipDetection {
+    // Seconds since the epoch of the frame the object was found in
+    pub frame_timestamp: f64,
+    #[serde(flatten)]
+    pub object: DetectedObject,
+}
+
+impl AnalyticsClient {
+    pub fn new(base_url: &str, config: AnalyticsClientConfig) -> Result<Self, AnalyticsClientError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()?;
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client,
+            config,
+        })
+    }
+
+    /// Whether the service processed a frame with `frame_hash`, in `stream_id`
+    /// when given or in any stream otherwise.
+    pub async fn verify_frame_hash(
+        &self,
+        stream_id: Option<&str>,
+        frame_hash: &str,
+    ) -> Result<FrameHashVerification, AnalyticsClientError> {
+        let request = VerifyFrameRequest { stream_id, frame_hash };
+        let verification: FrameHashVerification = self.post("/analytics/verify_frame", &request).await?;
+
+        // A reply about some other frame or stream is as good as no reply
+        if verification.frame_hash != frame_hash {
+            return Err(invalid_response("frame_hash does not match the one asked about"));
+        }
+        if let (Some(asked), Some(answered)) = (stream_id, &verification.stream_id) {
+            if asked != answered {
+                return Err(invalid_response("stream_id does not match the one asked about"));
+            }
+        }
+        Ok(verification)
+    }
+
+    /// Runs object detection over `clip` of the stream's footage again.
+    pub async fn rerun_detection(&self, stream_id: &str, clip: &Clip) -> Result<DetectionRerun, AnalyticsClientError> {
+        let errors = clip.validate_for(self.config.max_clip_secs);
+        if !errors.is_empty() {
+            return Err(errors.into());
+        }
+
+        let request = RerunDetectionRequest { stream_id, start: clip.start, end: clip.end };
+        let rerun: DetectionRerun = self.post("/analytics/rerun_detection", &request).await?;
+
+        if rerun.stream_id != stream_id || rerun.start != clip.start || rerun.end != clip.end {
+            return Err(invalid_response("does not cover the clip asked about"));
+        }
+        Ok(rerun)
+    }
+
+    async fn post<B, R>(&self, path: &str, body: &B) -> Result<R, AnalyticsClientError>
+    where
+        B: Serialize,
+        R: DeserializeOwned + Validate,
+    {
+        let url = format!("{}{}", self.base_url, path);
+        let mut attempt = 0;
+        let mut backoff = Duration::from_millis(self.config.initial_backoff_ms);
+
+        let response = loop {
+            match self.client.post(&url).json(body).send().await.and_then(|r| r.error_for_status()) {
+                Ok(response) => break response,
+                Err(e) if attempt < self.config.max_retries && is_retryable(&e) => {
+                    attempt += 1;
+                    warn!("Analytics service call to {} failed (attempt {}): {}", path, attempt, e);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(Duration::from_millis(self.config.max_backoff_ms));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        let reply: R = serde_json::from_slice(&response.bytes().await?)
+            .map_err(|e| invalid_response(&e.to_string()))?;
+        ValidationErrors::collect(&reply).map_err(|errors| invalid_response(&errors.to_string()))?;
+        Ok(reply)
+    }
+}
+
+fn is_retryable(error: &reqwest::Error) -> bool {
+    error.is_timeout()
+        || error.is_connect()
+        || error.status().map(|s| s.is_server_error() || s == StatusCode::TOO_MANY_REQUESTS).unwrap_or(false)
+}
+
+fn invalid_response(reason: &str) -> AnalyticsClientError {
+    format!("analytics service returned an invalid response: {}", reason).into()
+}
+
+impl Clip {
+    fn validate_for(&self, max_clip_secs: f64) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        self.validate_fields(&mut errors);
+        if errors.is_empty() && self.end - self.start > max_clip_secs {
+            errors.add("end", format!("clip must be at most {} seconds long", max_clip_secs));
+        }
+        errors
+    }
+}
+
+impl Validate for Clip {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_positive("start", self.start);
+        if !self.end.is_finite() || self.end <= self.start {
+            errors.add("end", "must be after start");
+        }
+    }
+}
+
+impl Validate for FrameHashVerification {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("frame_hash", &self.frame_hash);
+        if self.verified && self.captured_at.is_none() {
+            errors.add("captured_at", "is required when the frame is verified");
+        }
+        if self.captured_at.map(|at| !at.is_finite()).unwrap_or(false) {
+            errors.add("captured_at", "must be a finite number");
+        }
+    }
+}
+
+impl Validate for DetectionRerun {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("stream_id", &self.stream_id);
+        errors.require_range("processing_time", self.processing_time, 0.0, f64::MAX);
+        for (index, detection) in self.detections.iter().enumerate() {
+            if detection.frame_timestamp < self.start || detection.frame_timestamp > self.end {
+                errors.add(format!("detections[{}].frame_timestamp", index), "must fall within the clip");
+            }
+            errors.nested(&format!("detections[{}]", index), &detection.object);
+        }
+    }
+}
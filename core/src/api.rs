@@ -0,0 +1,322 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::analytics::AnalyticsEvent;
+use crate::betting::{self, ActualResult, BetRequest, BetResult, SlipProblem, UserBalance};
+use crate::geolocation::{CellTowerData, GeolocationPoint, WiFiAccessPoint};
+use crate::latency::Stamp;
+use crate::orchestrator::opportunities::BettingOpportunity;
+use crate::replay::ReplayRejection;
+use crate::stream::StreamStatus;
+use crate::validation::{Validate, ValidationErrors};
+
+// Request and response bodies of the HTTP and WebSocket APIs. The server
+// serves these and `morphine-client` sends them, so the two can't drift.
+
+// Longest prediction window a bet may be placed for
+pub const MAX_BET_WINDOW_SECS: u32 = 3600;
+// Most bets one slip may carry
+pub const MAX_SLIP_LINES: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreateStreamRequest {
+    pub title: String,
+    pub source_type: String,
+    pub source_url: String,
+    pub settings: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PlaceBetRequest {
+    pub user_id: String,
+    pub stream_id: String,
+    pub bet_type: String,
+    pub stake_amount: f64,
+    pub prediction: Value,
+    pub time_window_seconds: u32,
+}
+
+/// A draft of several bets, checked together before any is placed.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BetSlipRequest {
+    pub lines: Vec<BetSlipLine>,
+}
+
+/// One bet on a slip, placed as the caller.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BetSlipLine {
+    pub stream_id: String,
+    pub bet_type: String,
+    pub stake_amount: f64,
+    pub prediction: Value,
+    pub time_window_seconds: u32,
+}
+
+/// How one line of a slip would fare if placed now.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SlipLineDiagnostics {
+    // Position on the slip, from 0
+    pub index: usize,
+    pub ok: bool,
+    // None for a line too malformed to quote
+    pub odds: Option<f64>,
+    // Less than the stake when the market could only take part of it
+    pub matched_stake: Option<f64>,
+    pub potential_payout: Option<f64>,
+    pub problems: Vec<SlipProblem>,
+}
+
+/// What `POST /api/betting/slip/validate` returns.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BetSlipValidation {
+    // Every line would be placed
+    pub valid: bool,
+    pub lines: Vec<SlipLineDiagnostics>,
+    pub total_stake: f64,
+    // Paid out if every line that can be placed is, and wins
+    pub total_potential_payout: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BetResponse {
+    pub success: bool,
+    pub bet_id: String,
+    pub message: String,
+    pub remaining_balance: f64,
+    pub bet_details: Option<Value>,
+    // The stake placed, and what the market couldn't take and stayed in the balance
+    #[serde(default)]
+    pub matched_stake: f64,
+    #[serde(default)]
+    pub unmatched_stake: f64,
+}
+
+/// A bettor's balance on one stream, as `GET /api/betting/balance/{stream_id}` returns it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BalanceSummary {
+    pub available: f64,
+    pub total_deposited: f64,
+    // Staked on bets still open
+    pub active_bets: f64,
+    pub total_winnings: f64,
+    pub bet_count: u32,
+}
+
+impl From<&UserBalance> for BalanceSummary {
+    fn from(balance: &UserBalance) -> Self {
+        Self {
+            available: balance.available_balance(),
+            total_deposited: balance.total_deposited,
+            active_bets: balance.active_bets_total,
+            total_winnings: balance.total_winnings,
+            bet_count: balance.bet_count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ResolveBetRequest {
+    #[schema(value_type = Object)]
+    pub actual_result: ActualResult,
+    pub confidence_score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LocationVerificationRequest {
+    // From POST /api/geolocation/session/start/:user_id
+    pub session_id: String,
+    #[schema(value_type = Option<Object>)]
+    pub gps_data: Option<GeolocationPoint>,
+    #[serde(default)]
+    #[schema(value_type = Vec<Object>)]
+    pub cell_towers: Vec<CellTowerData>,
+    #[serde(default)]
+    #[schema(value_type = Vec<Object>)]
+    pub wifi_points: Vec<WiFiAccessPoint>,
+    pub video_frame_hash: Option<String>,
+}
+
+/// A message on a stream's WebSocket, `/ws/{stream_id}`, as JSON text frames.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WebSocketMessage {
+    // Client -> Server
+    // Start or stop following a stream's updates; a socket starts out following the one in its path
+    JoinStream { stream_id: String, user_id: String },
+    LeaveStream { stream_id: String },
+    // Messages that move money carry a nonce used once and the Unix
+    // milliseconds they were sent at; build them with `place_bet` and
+    // `pledge_to_stream`
+    PlaceBet { bet_request: BetRequest, nonce: String, timestamp: i64 },
+    PledgeToStream { stream_id: String, amount: f64, nonce: String, timestamp: i64 },
+
+    // Server -> Client
+    StreamUpdate { stream_id: String, status: StreamStatus },
+    BetUpdate { bet_id: String, result: BetResult },
+    AnalyticsUpdate {
+        stream_id: String,
+        data: AnalyticsEvent,
+        // Where the frame is in the pipeline, for the delivery latency metric
+        #[serde(skip)]
+        stamp: Option<Stamp>,
+    },
+    BalanceUpdate { user_id: String, stream_id: String, balance: f64 },
+    ErrorMessage { error: String },
+    // A `PlaceBet` or `PledgeToStream` refused as a possible replay; nothing was done
+    ReplayRejected { nonce: String, reason: ReplayRejection },
+    // A stream's markets stopped or started taking bets; sent on joining, then on every change
+    MarketStatus { stream_id: String, bettable: bool, reason: Option<String> },
+    // A market opened from a confident prediction, taking bets until it closes
+    BettingOpportunity { opportunity: BettingOpportunity },
+
+    // Bidirectional
+    Ping,
+    Pong,
+}
+
+impl WebSocketMessage {
+    /// The stream a server message is about, which decides who gets it; None
+    /// for messages meant for every connection.
+    pub fn stream_id(&self) -> Option<&str> {
+        match self {
+            WebSocketMessage::StreamUpdate { stream_id, .. }
+            | WebSocketMessage::AnalyticsUpdate { stream_id, .. }
+            | WebSocketMessage::BalanceUpdate { stream_id, .. }
+            | WebSocketMessage::MarketStatus { stream_id, .. } => Some(stream_id),
+            WebSocketMessage::BetUpdate { result, .. } => result.bet_details.as_ref().map(|bet| bet.stream_id.as_str()),
+            WebSocketMessage::BettingOpportunity { opportunity } => Some(&opportunity.stream_id),
+            _ => None,
+        }
+    }
+
+    /// A `PlaceBet` with a fresh nonce, stamped now.
+    pub fn place_bet(bet_request: BetRequest) -> Self {
+        WebSocketMessage::PlaceBet {
+            bet_request,
+            nonce: Uuid::new_v4().to_string(),
+            timestamp: Utc::now().timestamp_millis(),
+        }
+    }
+
+    /// A `PledgeToStream` with a fresh nonce, stamped now.
+    pub fn pledge_to_stream(stream_id: String, amount: f64) -> Self {
+        WebSocketMessage::PledgeToStream {
+            stream_id,
+            amount,
+            nonce: Uuid::new_v4().to_string(),
+            timestamp: Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+impl Validate for PlaceBetRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("user_id", &self.user_id);
+        validate_bet(errors, &self.stream_id, &self.bet_type, self.stake_amount, &self.prediction, self.time_window_seconds);
+    }
+}
+
+impl Validate for BetSlipLine {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        validate_bet(errors, &self.stream_id, &self.bet_type, self.stake_amount, &self.prediction, self.time_window_seconds);
+    }
+}
+
+// A malformed line is one of the slip's diagnostics, not a reason to reject it
+impl Validate for BetSlipRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        if self.lines.is_empty() || self.lines.len() > MAX_SLIP_LINES {
+            errors.add("lines", format!("must have between 1 and {} bets", MAX_SLIP_LINES));
+        }
+    }
+}
+
+// The fields every bet has, however it's placed
+fn validate_bet(
+    errors: &mut ValidationErrors,
+    stream_id: &str,
+    bet_type: &str,
+    stake_amount: f64,
+    prediction: &Value,
+    time_window_seconds: u32,
+) {
+    errors.require_non_empty("stream_id", stream_id);
+    errors.require_positive("stake_amount", stake_amount);
+    if time_window_seconds == 0 || time_window_seconds > MAX_BET_WINDOW_SECS {
+        errors.add("time_window_seconds", format!("must be between 1 and {}", MAX_BET_WINDOW_SECS));
+    }
+
+    let bet_type = match bet_type.parse::<betting::BetType>() {
+        Ok(bet_type) => Some(bet_type),
+        Err(e) => {
+            errors.add("bet_type", e);
+            None
+        }
+    };
+    match serde_json::from_value::<betting::Prediction>(prediction.clone()) {
+        Ok(prediction) => {
+            if bet_type.map(|bet_type| bet_type != prediction.bet_type()).unwrap_or(false) {
+                errors.add("prediction", "does not match bet_type");
+            }
+            errors.nested("prediction", &prediction);
+        }
+        Err(e) => errors.add("prediction", format!("is not a valid prediction: {}", e)),
+    }
+}
+
+impl Validate for ResolveBetRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.nested("actual_result", &self.actual_result);
+        errors.require_range("confidence_score", self.confidence_score, 0.0, 1.0);
+    }
+}
+
+impl Validate for LocationVerificationRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("session_id", &self.session_id);
+        if self.gps_data.is_none() && self.cell_towers.is_empty() && self.wifi_points.is_empty() {
+            errors.add("gps_data", "at least one of gps_data, cell_towers or wifi_points is required");
+        }
+        if let Some(gps_data) = &self.gps_data {
+            errors.nested("gps_data", gps_data);
+        }
+        for (index, tower) in self.cell_towers.iter().enumerate() {
+            errors.nested(&format!("cell_towers[{}]", index), tower);
+        }
+        for (index, point) in self.wifi_points.iter().enumerate() {
+            errors.nested(&format!("wifi_points[{}]", index), point);
+        }
+    }
+}
+
+impl Validate for LoginRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("email", &self.email);
+        errors.require_non_empty("password", &self.password);
+    }
+}
+
+impl Validate for CreateStreamRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("title", &self.title);
+        errors.require_non_empty("source_type", &self.source_type);
+        if !self.source_url.contains("://") {
+            errors.add("source_url", "must be an absolute URL");
+        }
+    }
+}
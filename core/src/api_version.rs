@@ -0,0 +1,248 @@
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use axum::extract::Request;
+use axum::http::{header, HeaderValue, Uri};
+use axum::response::{IntoResponse, Response};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use tower::{Layer, Service};
+
+use crate::config::ApiVersionConfig;
+use crate::error::{ApiError, ErrorCode};
+
+pub const API_VERSION_HEADER: &str = "api-version";
+
+// Served unversioned; the document describes every version
+const UNVERSIONED_PREFIXES: [&str; 1] = ["/api/docs"];
+
+/// A published version of the HTTP API. Routes are registered once under their
+/// unversioned `/api/...` path and serve every version, unless a version
+/// overrides them (see [`ApiVersionLayer::overridden`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum ApiVersion {
+    V1,
+}
+
+impl ApiVersion {
+    pub const CURRENT: ApiVersion = ApiVersion::V1;
+    pub const SUPPORTED: [ApiVersion; 1] = [ApiVersion::V1];
+
+    pub fn parse(version: &str) -> Option<Self> {
+        match version.trim().trim_start_matches(['v', 'V']) {
+            "1" => Some(ApiVersion::V1),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+        }
+    }
+
+    /// Where a version-specific handler for `path` (an unversioned `/api/...`
+    /// path) is mounted in the router, e.g. `/api/v2/streams`.
+    pub fn internal_path(&self, path: &str) -> String {
+        format!("/api/{}{}", self.as_str(), path.trim_start_matches("/api"))
+    }
+}
+
+// Route pattern with `:param` segments, as axum writes them
+#[derive(Debug, Clone)]
+struct RoutePattern(Vec<String>);
+
+impl RoutePattern {
+    fn new(path: &str) -> Self {
+        Self(path.split('/').map(str::to_string).collect())
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let segments: Vec<&str> = path.split('/').collect();
+        segments.len() == self.0.len()
+            && self.0.iter().zip(&segments).all(|(pattern, segment)| pattern.starts_with(':') || pattern == segment)
+    }
+}
+
+/// Resolves the API version of each request and routes it:
+/// - `/api/v1/streams` is served by the `/api/streams` route as v1;
+/// - unversioned `/api/streams` is served as the version named in the
+///   `Api-Version` header, or v1 when absent, and marked deprecated (or
+///   rejected once legacy paths are switched off);
+/// - unknown versions are rejected with the list of supported ones.
+///
+/// The version is available to handlers as `Extension<ApiVersion>` and echoed
+/// in the `Api-Version` response header. Must wrap the router, not be layered
+/// inside it, since it rewrites the path before routing.
+#[derive(Clone)]
+pub struct ApiVersionLayer {
+    config: Arc<ApiVersionConfig>,
+    overrides: Arc<Vec<(ApiVersion, RoutePattern)>>,
+}
+
+impl ApiVersionLayer {
+    pub fn new(config: ApiVersionConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            overrides: Arc::new(Vec::new()),
+        }
+    }
+
+    /// Declares that `version` has its own handler for the unversioned route
+    /// `path`, mounted at [`ApiVersion::internal_path`]. Other versions keep
+    /// being served by the shared route.
+    pub fn overridden(mut self, version: ApiVersion, path: &str) -> Self {
+        Arc::make_mut(&mut self.overrides).push((version, RoutePattern::new(path)));
+        self
+    }
+
+    fn is_overridden(&self, version: ApiVersion, path: &str) -> bool {
+        self.overrides.iter().any(|(v, pattern)| *v == version && pattern.matches(path))
+    }
+}
+
+impl<S> Layer<S> for ApiVersionLayer {
+    type Service = VersionedApi<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        VersionedApi { inner, versions: self.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub struct VersionedApi<S> {
+    inner: S,
+    versions: ApiVersionLayer,
+}
+
+enum Resolution {
+    // Not an API path; passed through untouched
+    Unversioned,
+    Versioned { version: ApiVersion, path: String, legacy: bool },
+    Unsupported(String),
+    LegacyDisabled,
+}
+
+fn resolve(versions: &ApiVersionLayer, request: &Request) -> Resolution {
+    let path = request.uri().path();
+    if !path.starts_with("/api/") || UNVERSIONED_PREFIXES.iter().any(|prefix| path.starts_with(prefix)) {
+        return Resolution::Unversioned;
+    }
+
+    let rest = &path["/api".len()..];
+    let (first, remainder) = match rest[1..].find('/') {
+        Some(index) => (&rest[1..index + 1], &rest[index + 1..]),
+        None => (&rest[1..], ""),
+    };
+
+    // An explicit path prefix wins over any header
+    let looks_versioned = first.len() > 1
+        && first.starts_with('v')
+        && first[1..].chars().all(|c| c.is_ascii_digit());
+    if looks_versioned {
+        return match ApiVersion::parse(first) {
+            Some(version) => Resolution::Versioned {
+                version,
+                path: format!("/api{}", remainder),
+                legacy: false,
+            },
+            None => Resolution::Unsupported(first.to_string()),
+        };
+    }
+
+    if !versions.config.legacy_paths_enabled {
+        return Resolution::LegacyDisabled;
+    }
+    let requested = request.headers()
+        .get(API_VERSION_HEADER)
+        .and_then(|value| value.to_str().ok());
+    match requested {
+        Some(requested) => match ApiVersion::parse(requested) {
+            Some(version) => Resolution::Versioned { version, path: path.to_string(), legacy: true },
+            None => Resolution::Unsupported(requested.to_string()),
+        },
+        // Unversioned clients were written against the first version
+        None => Resolution::Versioned { version: ApiVersion::V1, path: path.to_string(), legacy: true },
+    }
+}
+
+fn legacy_path_disabled(path: &str) -> Response {
+    ApiError::new(
+        ErrorCode::UnsupportedApiVersion,
+        format!("Unversioned API paths are no longer served; use {}", ApiVersion::CURRENT.internal_path(path)),
+    ).into_response()
+}
+
+fn rewrite_path(uri: &Uri, path: &str) -> Option<Uri> {
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{}?{}", path, query),
+        None => path.to_string(),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+fn unsupported_version(requested: &str) -> Response {
+    let supported: Vec<&str> = ApiVersion::SUPPORTED.iter().map(|v| v.as_str()).collect();
+    ApiError::new(
+        ErrorCode::UnsupportedApiVersion,
+        format!("API version '{}' is not supported; supported versions are {}", requested, supported.join(", ")),
+    ).into_response()
+}
+
+impl<S> Service<Request> for VersionedApi<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request) -> Self::Future {
+        let versions = self.versions.clone();
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let (version, path, legacy) = match resolve(&versions, &request) {
+                Resolution::Unversioned => return inner.call(request).await,
+                Resolution::Unsupported(requested) => return Ok(unsupported_version(&requested)),
+                Resolution::LegacyDisabled => return Ok(legacy_path_disabled(request.uri().path())),
+                Resolution::Versioned { version, path, legacy } => (version, path, legacy),
+            };
+
+            let routed_path = if versions.is_overridden(version, &path) {
+                version.internal_path(&path)
+            } else {
+                path.clone()
+            };
+            if let Some(uri) = rewrite_path(request.uri(), &routed_path) {
+                *request.uri_mut() = uri;
+            }
+            request.extensions_mut().insert(version);
+
+            let mut response = inner.call(request).await?;
+            let headers = response.headers_mut();
+            headers.insert(API_VERSION_HEADER, HeaderValue::from_static(version.as_str()));
+
+            // RFC 8594 style hints pointing legacy clients at the versioned path
+            if legacy {
+                headers.insert("deprecation", HeaderValue::from_static("true"));
+                if let Some(sunset) = versions.config.legacy_sunset.as_deref().and_then(|s| HeaderValue::from_str(s).ok()) {
+                    headers.insert("sunset", sunset);
+                }
+                let successor = format!("<{}>; rel=\"successor-version\"", ApiVersion::CURRENT.internal_path(&path));
+                if let Ok(link) = HeaderValue::from_str(&successor) {
+                    headers.insert(header::LINK, link);
+                }
+            }
+
+            Ok(response)
+        })
+    }
+}
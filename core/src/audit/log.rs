@@ -0,0 +1,338 @@
+use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres, Row};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::error::current_correlation_id;
+use crate::pagination::{PageRequest, Paginated};
+
+pub type AuditError = Box<dyn std::error::Error + Send + Sync>;
+
+// The `prev_hash` of each tenant's first entry
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// A privileged or financial change the audit log records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    // Added, or replaced by one with the same ID
+    ExclusionZoneSaved,
+    // Resolved by an operator rather than the settlement monitor
+    BetSettledManually,
+    SystemWeightChanged,
+    FeatureFlagSet,
+    FeatureFlagDeleted,
+    ApiKeyCreated,
+    ApiKeyRotated,
+    ApiKeyRevoked,
+    StreamOwnerAssigned,
+    // An admin override of a creator's or tenant's concurrent stream quota
+    StreamQuotaSet,
+    StreamQuotaCleared,
+    // An admin stopping all betting and settlement on a stream, and lifting it
+    StreamEmergencyStopped,
+    StreamEmergencyLifted,
+    // An admin override of how much a stream's open bets may owe
+    LiquidityCapSet,
+    LiquidityCapCleared,
+    // A user signing out one of their devices or connections
+    SessionRevoked,
+    // Frozen by the risk monitor, pending review of the alert that named the account
+    AccountFrozen,
+    AccountUnfrozen,
+    RiskAlertReviewed,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::ExclusionZoneSaved => "exclusion_zone_saved",
+            AuditAction::BetSettledManually => "bet_settled_manually",
+            AuditAction::SystemWeightChanged => "system_weight_changed",
+            AuditAction::FeatureFlagSet => "feature_flag_set",
+            AuditAction::FeatureFlagDeleted => "feature_flag_deleted",
+            AuditAction::ApiKeyCreated => "api_key_created",
+            AuditAction::ApiKeyRotated => "api_key_rotated",
+            AuditAction::ApiKeyRevoked => "api_key_revoked",
+            AuditAction::StreamOwnerAssigned => "stream_owner_assigned",
+            AuditAction::StreamQuotaSet => "stream_quota_set",
+            AuditAction::StreamQuotaCleared => "stream_quota_cleared",
+            AuditAction::StreamEmergencyStopped => "stream_emergency_stopped",
+            AuditAction::StreamEmergencyLifted => "stream_emergency_lifted",
+            AuditAction::LiquidityCapSet => "liquidity_cap_set",
+            AuditAction::LiquidityCapCleared => "liquidity_cap_cleared",
+            AuditAction::SessionRevoked => "session_revoked",
+            AuditAction::AccountFrozen => "account_frozen",
+            AuditAction::AccountUnfrozen => "account_unfrozen",
+            AuditAction::RiskAlertReviewed => "risk_alert_reviewed",
+        }
+    }
+}
+
+/// One change to record: what was done to what, and how it looked before
+/// and after. The actor and correlation ID are the current request's.
+pub struct AuditRecord<'a> {
+    pub action: AuditAction,
+    pub target_type: &'a str,
+    pub target_id: &'a str,
+    // None for something created
+    pub before: Option<Value>,
+    // None for something removed
+    pub after: Option<Value>,
+}
+
+/// An entry in the audit log. `hash` covers the entry and the `hash` of the
+/// one before it in the tenant's log, so a changed or removed entry breaks
+/// every hash after it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AuditEntry {
+    pub seq: i64,
+    pub entry_id: String,
+    pub actor: String,
+    #[schema(value_type = String)]
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    #[schema(value_type = Option<Object>)]
+    pub before: Option<Value>,
+    #[schema(value_type = Option<Object>)]
+    pub after: Option<Value>,
+    pub correlation_id: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    // As recorded, e.g. `user:<id>`, `api_key:<id>` or `admin`
+    pub actor: Option<String>,
+    #[param(value_type = Option<String>)]
+    pub action: Option<AuditAction>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// The outcome of rehashing a tenant's log from its first entry.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ChainVerification {
+    pub entries: u64,
+    pub intact: bool,
+    // The first entry whose hash or link doesn't match; everything before it checks out
+    pub first_broken_seq: Option<i64>,
+    pub head_hash: Option<String>,
+}
+
+/// An append-only, hash-chained record of who changed what. The table
+/// refuses updates and deletes; each tenant's entries form one chain, and
+/// appends to it are serialized so the chain never forks.
+pub struct AuditLog {
+    db_pool: Pool<Postgres>,
+}
+
+impl AuditLog {
+    pub fn new(db_pool: Pool<Postgres>) -> Self {
+        Self { db_pool }
+    }
+
+    /// Appends `record` to the current tenant's log, as the current actor.
+    pub async fn record(&self, record: AuditRecord<'_>) -> Result<AuditEntry, AuditError> {
+        let mut tx = self.db_pool.begin().await?;
+        let tenant_id: String = sqlx::query_scalar("SELECT COALESCE(morphine_current_tenant(), 'default')")
+            .fetch_one(&mut *tx)
+            .await?;
+        // Held to commit: the next append for this tenant waits to link to this one
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('morphine.audit_log:' || $1))")
+            .bind(&tenant_id)
+            .execute(&mut *tx)
+            .await?;
+        let prev_hash: Option<String> = sqlx::query_scalar(
+            "SELECT hash FROM audit_log WHERE tenant_id = $1 ORDER BY seq DESC LIMIT 1"
+        )
+        .bind(&tenant_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let mut entry = AuditEntry {
+            seq: 0,
+            entry_id: Uuid::new_v4().to_string(),
+            actor: super::current().unwrap_or_else(|| "system".to_string()),
+            action: record.action.as_str().to_string(),
+            target_type: record.target_type.to_string(),
+            target_id: record.target_id.to_string(),
+            before: record.before,
+            after: record.after,
+            correlation_id: current_correlation_id(),
+            // Postgres keeps microseconds; hashed as it will read back
+            occurred_at: Utc::now().trunc_subsecs(6),
+            prev_hash: prev_hash.unwrap_or_else(|| GENESIS_HASH.to_string()),
+            hash: String::new(),
+        };
+        entry.hash = entry_hash(&tenant_id, &entry);
+
+        entry.seq = sqlx::query_scalar(
+            r#"
+            INSERT INTO audit_log (entry_id, tenant_id, actor, action, target_type, target_id, before, after,
+                correlation_id, occurred_at, prev_hash, hash)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            RETURNING seq
+            "#
+        )
+        .bind(&entry.entry_id)
+        .bind(&tenant_id)
+        .bind(&entry.actor)
+        .bind(&entry.action)
+        .bind(&entry.target_type)
+        .bind(&entry.target_id)
+        .bind(&entry.before)
+        .bind(&entry.after)
+        .bind(&entry.correlation_id)
+        .bind(entry.occurred_at)
+        .bind(&entry.prev_hash)
+        .bind(&entry.hash)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(entry)
+    }
+
+    /// The current tenant's entries matching `query`, newest first unless
+    /// sorted by `occurred_at`.
+    pub async fn list(&self, query: &AuditQuery, page: &PageRequest) -> Result<Paginated<AuditEntry>, AuditError> {
+        let ascending = page.sort.as_ref().map(|sort| !sort.descending).unwrap_or(false);
+        let filter = "tenant_id = COALESCE(morphine_current_tenant(), 'default') \
+            AND ($1::text IS NULL OR actor = $1) AND ($2::text IS NULL OR action = $2) \
+            AND ($3::text IS NULL OR target_type = $3) AND ($4::text IS NULL OR target_id = $4) \
+            AND ($5::timestamptz IS NULL OR occurred_at >= $5) AND ($6::timestamptz IS NULL OR occurred_at < $6)";
+        let action = query.action.map(|action| action.as_str());
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM audit_log WHERE {}", filter))
+            .bind(&query.actor)
+            .bind(action)
+            .bind(&query.target_type)
+            .bind(&query.target_id)
+            .bind(query.from)
+            .bind(query.to)
+            .fetch_one(&self.db_pool)
+            .await?;
+
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM audit_log WHERE {} ORDER BY seq {} LIMIT $7 OFFSET $8",
+            ENTRY_COLUMNS,
+            filter,
+            if ascending { "ASC" } else { "DESC" }
+        ))
+        .bind(&query.actor)
+        .bind(action)
+        .bind(&query.target_type)
+        .bind(&query.target_id)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(page.limit as i64)
+        .bind(page.offset as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(page.page_of(rows.iter().map(entry_from_row).collect(), total as usize))
+    }
+
+    /// Rehashes the current tenant's log from its first entry, stopping at
+    /// the first that doesn't match.
+    pub async fn verify(&self) -> Result<ChainVerification, AuditError> {
+        // Streamed, as the log only grows
+        let sql = format!(
+            "SELECT tenant_id, {} FROM audit_log WHERE tenant_id = COALESCE(morphine_current_tenant(), 'default') ORDER BY seq",
+            ENTRY_COLUMNS
+        );
+        let mut rows = sqlx::query(&sql).fetch(&self.db_pool);
+
+        let mut entries = 0;
+        let mut expected_prev = GENESIS_HASH.to_string();
+        while let Some(row) = rows.try_next().await? {
+            let tenant_id: String = row.get("tenant_id");
+            let entry = entry_from_row(&row);
+            if entry.prev_hash != expected_prev || entry_hash(&tenant_id, &entry) != entry.hash {
+                return Ok(ChainVerification {
+                    entries,
+                    intact: false,
+                    first_broken_seq: Some(entry.seq),
+                    head_hash: None,
+                });
+            }
+            entries += 1;
+            expected_prev = entry.hash;
+        }
+
+        Ok(ChainVerification {
+            entries,
+            intact: true,
+            first_broken_seq: None,
+            head_hash: (entries > 0).then_some(expected_prev),
+        })
+    }
+}
+
+const ENTRY_COLUMNS: &str = "seq, entry_id, actor, action, target_type, target_id, before, after, correlation_id, \
+    occurred_at, prev_hash, hash";
+
+fn entry_from_row(row: &sqlx::postgres::PgRow) -> AuditEntry {
+    AuditEntry {
+        seq: row.get("seq"),
+        entry_id: row.get("entry_id"),
+        actor: row.get("actor"),
+        action: row.get("action"),
+        target_type: row.get("target_type"),
+        target_id: row.get("target_id"),
+        before: row.get("before"),
+        after: row.get("after"),
+        correlation_id: row.get("correlation_id"),
+        occurred_at: row.get("occurred_at"),
+        prev_hash: row.get("prev_hash"),
+        hash: row.get("hash"),
+    }
+}
+
+// SHA-256 over the fields as a JSON array, with snapshots as canonical JSON
+// so Postgres reordering JSONB keys doesn't change the hash
+fn entry_hash(tenant_id: &str, entry: &AuditEntry) -> String {
+    let snapshot = |value: &Option<Value>| value.as_ref().map(canonical_json).unwrap_or_else(|| "null".to_string());
+    let fields = [
+        entry.prev_hash.clone(),
+        entry.entry_id.clone(),
+        tenant_id.to_string(),
+        entry.actor.clone(),
+        entry.action.clone(),
+        entry.target_type.clone(),
+        entry.target_id.clone(),
+        snapshot(&entry.before),
+        snapshot(&entry.after),
+        entry.correlation_id.clone().unwrap_or_default(),
+        entry.occurred_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+    ];
+    let encoded = serde_json::to_string(&fields).expect("strings always serialize");
+    hex::encode(Sha256::digest(encoded.as_bytes()))
+}
+
+// Object keys sorted at every level, whatever order the map keeps them in
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(fields) => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_by(|a, b| a.0.cmp(b.0));
+            let fields: Vec<String> = fields.iter()
+                .map(|(name, value)| format!("{}:{}", Value::String((*name).clone()), canonical_json(value)))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => format!("[{}]", items.iter().map(canonical_json).collect::<Vec<_>>().join(",")),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,44 @@
+mod log;
+
+pub use log::{AuditAction, AuditEntry, AuditError, AuditLog, AuditQuery, AuditRecord, ChainVerification};
+
+use std::future::Future;
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::auth::Principal;
+use crate::auth::principal::Credential;
+
+tokio::task_local! {
+    static CURRENT_ACTOR: String;
+}
+
+/// Who the current request acts as, for the `created_by` and `updated_by`
+/// columns. None outside a request; such writes are recorded as `system`.
+pub fn current() -> Option<String> {
+    CURRENT_ACTOR.try_with(|actor| actor.clone()).ok()
+}
+
+/// Runs `future` as `actor`, for work a request hands off to another task.
+pub async fn scope<F: Future>(actor: String, future: F) -> F::Output {
+    CURRENT_ACTOR.scope(actor, future).await
+}
+
+// Prefixed by credential, so a user and an API key with the same ID can't be confused in a dispute
+fn actor(principal: &Principal) -> String {
+    match &principal.credential {
+        Credential::AdminToken => "admin".to_string(),
+        Credential::ApiKey { key_id } => format!("api_key:{}", key_id),
+        Credential::UserToken => format!("user:{}", principal.subject),
+    }
+}
+
+/// Runs the rest of the request as its caller, so rows it writes record who
+/// wrote them. Must run inside the authentication layer.
+pub async fn attribute(request: Request, next: Next) -> Response {
+    match request.extensions().get::<Principal>().map(actor) {
+        Some(actor) => scope(actor, next.run(request)).await,
+        None => next.run(request).await,
+    }
+}
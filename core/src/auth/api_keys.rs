@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use sqlx::{Pool, Postgres, Row};
+use tokio::sync::watch;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::config::ApiKeyConfig;
+use crate::runtime;
+use crate::validation::{Validate, ValidationErrors};
+
+type ApiKeyError = Box<dyn std::error::Error + Send + Sync>;
+
+// Every issued key starts with this, which makes leaked keys easy to spot
+pub const KEY_PREFIX: &str = "mk_";
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// An endpoint pattern a key may call: an exact path, a path ending in `/*`,
+/// or `*` for everything, optionally preceded by a method (`POST /api/analytics/*`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+#[schema(value_type = String, example = "POST /api/analytics/*")]
+pub struct ApiKeyScope(String);
+
+impl ApiKeyScope {
+    pub fn parse(scope: &str) -> Result<Self, String> {
+        let scope = scope.trim();
+        let (method, pattern) = split_scope(scope);
+
+        if let Some(method) = method {
+            if method.is_empty() || !method.chars().all(|c| c.is_ascii_uppercase()) {
+                return Err(format!("Invalid method in scope '{}'", scope));
+            }
+        }
+        if pattern != "*" {
+            if !pattern.starts_with('/') {
+                return Err(format!("Scope '{}' must be a path starting with '/'", scope));
+            }
+            let wildcards = pattern.matches('*').count();
+            if wildcards > 1 || (wildcards == 1 && !pattern.ends_with("/*")) {
+                return Err(format!("Scope '{}' may only use a trailing '/*' wildcard", scope));
+            }
+        }
+
+        Ok(Self(scope.to_string()))
+    }
+
+    pub fn allows(&self, method: &str, path: &str) -> bool {
+        let (scope_method, pattern) = split_scope(&self.0);
+        if scope_method.map(|m| !m.eq_ignore_ascii_case(method)).unwrap_or(false) {
+            return false;
+        }
+        if pattern == "*" {
+            return true;
+        }
+        match pattern.strip_suffix("/*") {
+            Some(prefix) => path == prefix || path.starts_with(&format!("{}/", prefix)),
+            None => path == pattern,
+        }
+    }
+}
+
+fn split_scope(scope: &str) -> (Option<&str>, &str) {
+    match scope.split_once(' ') {
+        Some((method, pattern)) => (Some(method), pattern.trim()),
+        None => (None, scope),
+    }
+}
+
+impl TryFrom<String> for ApiKeyScope {
+    type Error = String;
+
+    fn try_from(scope: String) -> Result<Self, Self::Error> {
+        Self::parse(&scope)
+    }
+}
+
+impl From<ApiKeyScope> for String {
+    fn from(scope: ApiKeyScope) -> Self {
+        scope.0
+    }
+}
+
+/// A stored key, without its secret.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKey {
+    pub key_id: String,
+    // Keys belong to the tenant they were created in
+    pub tenant_id: String,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub rotated_from: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiKey {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.map(|expires_at| expires_at > now).unwrap_or(true)
+    }
+
+    pub fn allows(&self, method: &str, path: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.allows(method, path))
+    }
+}
+
+/// A freshly created key. The secret is only ever returned here.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct IssuedApiKey {
+    pub key: ApiKey,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct NewApiKey {
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl NewApiKey {
+    pub fn validate(&self) -> Result<(), String> {
+        ValidationErrors::collect(self).map_err(|e| e.to_string())
+    }
+}
+
+impl Validate for NewApiKey {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("name", &self.name);
+        if self.scopes.is_empty() {
+            errors.add("scopes", "needs at least one scope");
+        }
+        if self.expires_at.map(|expires_at| expires_at <= Utc::now()).unwrap_or(false) {
+            errors.add("expires_at", "must be in the future");
+        }
+    }
+}
+
+/// Postgres-backed API keys. Keys are looked up by their SHA-256 hash, and
+/// recently authenticated keys are cached for `cache_ttl_secs`.
+pub struct ApiKeyStore {
+    db_pool: Pool<Postgres>,
+    config: watch::Receiver<ApiKeyConfig>,
+    cache: RwLock<HashMap<String, (ApiKey, Instant)>>,
+}
+
+impl ApiKeyStore {
+    pub fn new(db_pool: Pool<Postgres>, config: watch::Receiver<ApiKeyConfig>) -> Self {
+        Self {
+            db_pool,
+            config,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn create(&self, request: &NewApiKey) -> Result<IssuedApiKey, ApiKeyError> {
+        request.validate()?;
+        let (secret, key_hash) = generate_secret();
+
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO api_keys (key_id, name, key_prefix, key_hash, scopes, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING {}
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(Uuid::new_v4().to_string())
+        .bind(request.name.trim())
+        .bind(display_prefix(&secret))
+        .bind(&key_hash)
+        .bind(scope_strings(&request.scopes))
+        .bind(request.expires_at)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(IssuedApiKey { key: key_from_row(&row), secret })
+    }
+
+    // Newest first, revoked keys included
+    pub async fn list(&self) -> Result<Vec<ApiKey>, ApiKeyError> {
+        let rows = sqlx::query(&format!("SELECT {} FROM api_keys ORDER BY created_at DESC", KEY_COLUMNS))
+            .fetch_all(&self.db_pool)
+            .await?;
+
+        Ok(rows.iter().map(key_from_row).collect())
+    }
+
+    /// Issues a replacement with the same name and scopes. The old key is
+    /// revoked immediately, or left valid for `grace` so callers can switch over.
+    /// Returns None if the key doesn't exist or was already revoked.
+    pub async fn rotate(&self, key_id: &str, grace: Option<Duration>) -> Result<Option<IssuedApiKey>, ApiKeyError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM api_keys WHERE key_id = $1 AND revoked_at IS NULL FOR UPDATE",
+            KEY_COLUMNS
+        ))
+        .bind(key_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let current = match row {
+            Some(row) => key_from_row(&row),
+            None => return Ok(None),
+        };
+
+        let (secret, key_hash) = generate_secret();
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO api_keys (key_id, name, key_prefix, key_hash, scopes, rotated_from, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING {}
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(Uuid::new_v4().to_string())
+        .bind(&current.name)
+        .bind(display_prefix(&secret))
+        .bind(&key_hash)
+        .bind(scope_strings(&current.scopes))
+        .bind(&current.key_id)
+        .bind(current.expires_at)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        match grace {
+            Some(grace) => {
+                let grace_ends = Utc::now() + chrono::Duration::from_std(grace)?;
+                sqlx::query("UPDATE api_keys SET expires_at = LEAST(COALESCE(expires_at, $2), $2) WHERE key_id = $1")
+                    .bind(key_id)
+                    .bind(grace_ends)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            None => {
+                sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE key_id = $1")
+                    .bind(key_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        tx.commit().await?;
+        self.evict(key_id);
+
+        Ok(Some(IssuedApiKey { key: key_from_row(&row), secret }))
+    }
+
+    /// Returns false if the key doesn't exist or was already revoked.
+    pub async fn revoke(&self, key_id: &str) -> Result<bool, ApiKeyError> {
+        let result = sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE key_id = $1 AND revoked_at IS NULL")
+            .bind(key_id)
+            .execute(&self.db_pool)
+            .await?;
+
+        self.evict(key_id);
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Resolves a presented key to an active stored key.
+    pub async fn authenticate(&self, presented: &str) -> Result<Option<ApiKey>, ApiKeyError> {
+        if !presented.starts_with(KEY_PREFIX) {
+            return Ok(None);
+        }
+        let key_hash = sha256::digest(presented);
+        let now = Utc::now();
+
+        let ttl = Duration::from_secs(self.config.borrow().cache_ttl_secs);
+        let cached = self.cache.read().get(&key_hash)
+            .filter(|(_, cached_at)| cached_at.elapsed() < ttl)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = cached {
+            return Ok(Some(key).filter(|key| key.is_active(now)));
+        }
+
+        let row = sqlx::query(&format!("SELECT {} FROM api_keys WHERE key_hash = $1", KEY_COLUMNS))
+            .bind(&key_hash)
+            .fetch_optional(&self.db_pool)
+            .await?;
+        let key = match row {
+            Some(row) => key_from_row(&row),
+            None => return Ok(None),
+        };
+
+        // Usage is recorded on cache misses, so at most once per TTL per key
+        if key.is_active(now) {
+            let db_pool = self.db_pool.clone();
+            let key_id = key.key_id.clone();
+            runtime::spawn("auth:api_key_last_used", async move {
+                let touched = sqlx::query("UPDATE api_keys SET last_used_at = NOW() WHERE key_id = $1")
+                    .bind(&key_id)
+                    .execute(&db_pool)
+                    .await;
+                if let Err(e) = touched {
+                    warn!("Failed to record use of API key {}: {}", key_id, e);
+                }
+            });
+        }
+
+        self.cache.write().insert(key_hash, (key.clone(), Instant::now()));
+        Ok(Some(key).filter(|key| key.is_active(now)))
+    }
+
+    // Only clears this instance's cache; other instances see the change within the TTL
+    fn evict(&self, key_id: &str) {
+        self.cache.write().retain(|_, (key, _)| key.key_id != key_id);
+    }
+}
+
+// Two v4 UUIDs give 244 random bits, plenty for an unsalted hash
+fn generate_secret() -> (String, String) {
+    let secret = format!("{}{}{}", KEY_PREFIX, Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let key_hash = sha256::digest(secret.as_str());
+    (secret, key_hash)
+}
+
+fn display_prefix(secret: &str) -> String {
+    secret.chars().take(KEY_PREFIX.len() + 8).collect()
+}
+
+fn scope_strings(scopes: &[ApiKeyScope]) -> Vec<String> {
+    scopes.iter().cloned().map(String::from).collect()
+}
+
+const KEY_COLUMNS: &str = "key_id, tenant_id, name, key_prefix, scopes, rotated_from, expires_at, revoked_at, \
+    last_used_at, created_at";
+
+fn key_from_row(row: &sqlx::postgres::PgRow) -> ApiKey {
+    let scopes: Vec<String> = row.get("scopes");
+    ApiKey {
+        key_id: row.get("key_id"),
+        tenant_id: row.get("tenant_id"),
+        name: row.get("name"),
+        key_prefix: row.get("key_prefix"),
+        // Scopes were validated on the way in
+        scopes: scopes.into_iter().map(ApiKeyScope).collect(),
+        rotated_from: row.get("rotated_from"),
+        expires_at: row.get("expires_at"),
+        revoked_at: row.get("revoked_at"),
+        last_used_at: row.get("last_used_at"),
+        created_at: row.get("created_at"),
+    }
+}
@@ -0,0 +1,11 @@
+pub mod api_keys;
+pub mod principal;
+pub mod rbac;
+pub mod sessions;
+pub mod users;
+
+pub use api_keys::{ApiKey, ApiKeyStore};
+pub use principal::{Authenticator, Principal, Role};
+pub use rbac::{Access, StreamOwnership};
+pub use sessions::{SessionKind, SessionOrigin, SessionRegistry, UserSession};
+pub use users::{User, UserStore};
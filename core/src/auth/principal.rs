@@ -0,0 +1,252 @@
+use std::sync::Arc;
+use axum::http::{header, HeaderMap, StatusCode};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use tracing::{error, warn};
+
+use super::api_keys::{ApiKey, ApiKeyStore, API_KEY_HEADER};
+use super::sessions::SessionRegistry;
+use super::users::User;
+use crate::config::UserAuthConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    Creator,
+    Bettor,
+    Service,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Credential {
+    AdminToken,
+    ApiKey { key_id: String },
+    UserToken,
+}
+
+/// Who is making a request, as established by the authentication layer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Principal {
+    // User ID for user tokens, key ID for API keys
+    pub subject: String,
+    pub roles: Vec<Role>,
+    pub credential: Credential,
+    // The tenant the credential was issued in; None for the admin token, which works on every tenant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    // The session a user token was issued for, if it was issued with one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+}
+
+impl Principal {
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.contains(&role)
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.has_role(Role::Admin)
+    }
+
+    /// The user a request acts for: the caller themselves, or anyone for admins.
+    pub fn acting_as(&self, user_id: &str) -> Result<String, StatusCode> {
+        if self.subject == user_id || self.is_admin() {
+            Ok(user_id.to_string())
+        } else {
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+// Claims in user tokens; matches the tokens the API gateway issues
+#[derive(Debug, Serialize, Deserialize)]
+struct UserClaims {
+    #[serde(rename = "userId")]
+    user_id: String,
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(default)]
+    roles: Option<Vec<Role>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tenant: Option<String>,
+    // Session ID, for revoking the token before it expires; gateway tokens have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sid: Option<String>,
+    exp: i64,
+}
+
+struct UserTokenKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    // The key before the last rotation, still accepted so signed-in users stay signed in
+    previous_decoding: Option<DecodingKey>,
+    validation: Validation,
+}
+
+impl UserTokenKeys {
+    fn new(secret: &str, previous_decoding: Option<DecodingKey>) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+            previous_decoding,
+            validation: Validation::new(Algorithm::HS256),
+        }
+    }
+}
+
+/// Resolves the admin token, service API keys and user tokens into principals.
+pub struct Authenticator {
+    admin_token_digest: Option<String>,
+    api_keys: Arc<ApiKeyStore>,
+    sessions: Arc<SessionRegistry>,
+    user_tokens: RwLock<Option<UserTokenKeys>>,
+    default_user_roles: Vec<Role>,
+    user_token_ttl: chrono::Duration,
+}
+
+impl Authenticator {
+    pub fn new(
+        admin_token: Option<&str>,
+        user_auth: &UserAuthConfig,
+        api_keys: Arc<ApiKeyStore>,
+        sessions: Arc<SessionRegistry>,
+    ) -> Self {
+        Self {
+            admin_token_digest: admin_token.map(sha256::digest),
+            api_keys,
+            sessions,
+            user_tokens: RwLock::new(user_auth.jwt_secret.as_deref().map(|secret| UserTokenKeys::new(secret, None))),
+            default_user_roles: user_auth.default_roles.clone(),
+            user_token_ttl: chrono::Duration::seconds(user_auth.token_ttl_secs as i64),
+        }
+    }
+
+    /// Signs new tokens with `secret` from now on. Tokens signed with the
+    /// outgoing secret are accepted until the next rotation.
+    pub fn rotate_user_token_secret(&self, secret: &str) {
+        let mut user_tokens = self.user_tokens.write();
+        let previous = user_tokens.as_ref().map(|keys| keys.decoding.clone());
+        *user_tokens = Some(UserTokenKeys::new(secret, previous));
+    }
+
+    /// Signs a token for a user who just logged in, good until `session_id` is revoked.
+    pub fn issue_user_token(&self, user: &User, session_id: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let user_tokens = self.user_tokens.read();
+        let keys = user_tokens.as_ref().ok_or("User tokens are disabled without JWT_SECRET")?;
+        let claims = UserClaims {
+            user_id: user.user_id.clone(),
+            email: Some(user.email.clone()),
+            roles: Some(user.roles.clone()),
+            // Users sign in on their tenant's host, so the request's tenant is theirs
+            tenant: crate::tenant::current().map(|tenant| tenant.id),
+            sid: Some(session_id.to_string()),
+            exp: (chrono::Utc::now() + self.user_token_ttl).timestamp(),
+        };
+        Ok(jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &keys.encoding)?)
+    }
+
+    pub fn verify_user_token(&self, token: &str) -> Result<Principal, StatusCode> {
+        let user_tokens = self.user_tokens.read();
+        let keys = user_tokens.as_ref().ok_or(StatusCode::UNAUTHORIZED)?;
+        let claims = jsonwebtoken::decode::<UserClaims>(token, &keys.decoding, &keys.validation)
+            .or_else(|e| match &keys.previous_decoding {
+                Some(previous) => jsonwebtoken::decode::<UserClaims>(token, previous, &keys.validation),
+                None => Err(e),
+            })
+            .map_err(|e| {
+                warn!("Rejected user token: {}", e);
+                StatusCode::UNAUTHORIZED
+            })?
+            .claims;
+
+        Ok(Principal {
+            subject: claims.user_id,
+            roles: claims.roles.unwrap_or_else(|| self.default_user_roles.clone()),
+            credential: Credential::UserToken,
+            tenant: claims.tenant,
+            session_id: claims.sid,
+        })
+    }
+
+    /// Like `verify_user_token`, and also refuses tokens whose session was revoked.
+    pub async fn verify_user_session(&self, token: &str) -> Result<Principal, StatusCode> {
+        let principal = self.verify_user_token(token)?;
+        let Some(session_id) = &principal.session_id else {
+            return Ok(principal);
+        };
+        match self.sessions.is_revoked(session_id).await {
+            Ok(false) => Ok(principal),
+            Ok(true) => {
+                warn!("Rejected token for revoked session {} of user {}", session_id, principal.subject);
+                Err(StatusCode::UNAUTHORIZED)
+            }
+            Err(e) => {
+                error!("Failed to check session {}: {}", session_id, e);
+                Err(StatusCode::SERVICE_UNAVAILABLE)
+            }
+        }
+    }
+
+    /// Ok(None) for anonymous requests. Credentials that are presented but
+    /// invalid are rejected here rather than treated as anonymous. API keys
+    /// are also checked against their scopes for `method` and `path`.
+    pub async fn resolve(
+        &self,
+        headers: &HeaderMap,
+        method: &str,
+        path: &str,
+    ) -> Result<Option<(Principal, Option<ApiKey>)>, StatusCode> {
+        if let Some(presented) = headers.get(API_KEY_HEADER) {
+            let presented = presented.to_str().map_err(|_| StatusCode::UNAUTHORIZED)?;
+            let key = match self.api_keys.authenticate(presented).await {
+                Ok(Some(key)) => key,
+                Ok(None) => {
+                    warn!("Rejected unknown or inactive API key for {}", path);
+                    return Err(StatusCode::UNAUTHORIZED);
+                }
+                Err(e) => {
+                    error!("Failed to look up API key: {}", e);
+                    return Err(StatusCode::SERVICE_UNAVAILABLE);
+                }
+            };
+            if !key.allows(method, path) {
+                warn!("API key {} is not scoped for {} {}", key.key_id, method, path);
+                return Err(StatusCode::FORBIDDEN);
+            }
+
+            let principal = Principal {
+                subject: key.key_id.clone(),
+                roles: vec![Role::Service],
+                credential: Credential::ApiKey { key_id: key.key_id.clone() },
+                tenant: Some(key.tenant_id.clone()),
+                session_id: None,
+            };
+            return Ok(Some((principal, Some(key))));
+        }
+
+        let token = match headers.get(header::AUTHORIZATION) {
+            Some(value) => value.to_str().ok()
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .ok_or(StatusCode::UNAUTHORIZED)?,
+            None => return Ok(None),
+        };
+
+        // Compare digests so the check doesn't leak how much of the token matched
+        if self.admin_token_digest.as_deref() == Some(sha256::digest(token).as_str()) {
+            let principal = Principal {
+                subject: "admin".to_string(),
+                roles: vec![Role::Admin],
+                credential: Credential::AdminToken,
+                tenant: None,
+                session_id: None,
+            };
+            return Ok(Some((principal, None)));
+        }
+
+        Ok(Some((self.verify_user_session(token).await?, None)))
+    }
+}
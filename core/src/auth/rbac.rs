@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use axum::http::StatusCode;
+use sqlx::{Pool, Postgres};
+
+use super::principal::{Principal, Role};
+
+type OwnershipError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone)]
+enum Subject {
+    // The named path parameter must be the caller's own user ID
+    User(&'static str),
+    // The named path parameter must be a stream the caller owns
+    StreamOwner(&'static str),
+}
+
+/// Who may call a group of routes. Admins pass every policy.
+#[derive(Clone)]
+pub struct Access {
+    roles: &'static [Role],
+    subject: Option<Subject>,
+    ownership: Option<Arc<StreamOwnership>>,
+}
+
+impl Access {
+    pub fn roles(roles: &'static [Role]) -> Self {
+        Self { roles, subject: None, ownership: None }
+    }
+
+    pub fn own_user(param: &'static str, roles: &'static [Role]) -> Self {
+        Self { roles, subject: Some(Subject::User(param)), ownership: None }
+    }
+
+    pub fn stream_owner(param: &'static str, ownership: Arc<StreamOwnership>) -> Self {
+        Self {
+            roles: &[Role::Creator],
+            subject: Some(Subject::StreamOwner(param)),
+            ownership: Some(ownership),
+        }
+    }
+
+    pub async fn check(
+        &self,
+        principal: Option<&Principal>,
+        params: &HashMap<String, String>,
+    ) -> Result<(), StatusCode> {
+        let principal = principal.ok_or(StatusCode::UNAUTHORIZED)?;
+        if principal.is_admin() {
+            return Ok(());
+        }
+        if !self.roles.iter().any(|role| principal.has_role(*role)) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+
+        match &self.subject {
+            None => Ok(()),
+            Some(Subject::User(param)) => {
+                let user_id = params.get(*param).ok_or(StatusCode::FORBIDDEN)?;
+                principal.acting_as(user_id).map(|_| ())
+            }
+            Some(Subject::StreamOwner(param)) => {
+                let stream_id = params.get(*param).ok_or(StatusCode::FORBIDDEN)?;
+                let ownership = self.ownership.as_ref().ok_or(StatusCode::FORBIDDEN)?;
+                match ownership.owner_of(stream_id).await {
+                    Ok(Some(owner)) if owner == principal.subject => Ok(()),
+                    Ok(_) => Err(StatusCode::FORBIDDEN),
+                    Err(e) => {
+                        tracing::error!("Failed to look up owner of stream {}: {}", stream_id, e);
+                        Err(StatusCode::SERVICE_UNAVAILABLE)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Which creator manages each stream, in `stream_owners`.
+pub struct StreamOwnership {
+    db_pool: Pool<Postgres>,
+}
+
+impl StreamOwnership {
+    pub fn new(db_pool: Pool<Postgres>) -> Self {
+        Self { db_pool }
+    }
+
+    pub async fn owner_of(&self, stream_id: &str) -> Result<Option<String>, OwnershipError> {
+        let owner = sqlx::query_scalar("SELECT creator_id FROM stream_owners WHERE stream_id = $1")
+            .bind(stream_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+        Ok(owner)
+    }
+
+    pub async fn assign(&self, stream_id: &str, creator_id: &str) -> Result<(), OwnershipError> {
+        sqlx::query(
+            r#"
+            INSERT INTO stream_owners (stream_id, creator_id) VALUES ($1, $2)
+            ON CONFLICT (stream_id) DO UPDATE SET creator_id = EXCLUDED.creator_id, assigned_at = NOW()
+            "#
+        )
+        .bind(stream_id)
+        .bind(creator_id)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use std::sync::Arc;
+use axum::http::{header, HeaderMap};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::rate_limit::client_ip;
+use crate::state::StateManager;
+
+type SessionError = Box<dyn std::error::Error + Send + Sync>;
+
+// Longer User-Agents are cut short; they only help the user recognise a device
+const MAX_DEVICE_LENGTH: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionKind {
+    // A signed-in token, for the API and for opening WebSockets
+    AuthToken,
+    WebSocket,
+    // Location checks clearing the device to bet
+    Geolocation,
+}
+
+/// One of a user's signed-in devices or open connections.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserSession {
+    pub session_id: String,
+    pub user_id: String,
+    pub kind: SessionKind,
+    // The token session this was opened with; revoking that revokes this too
+    pub parent_id: Option<String>,
+    // The client's User-Agent
+    pub device: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Where a session was opened from, as the request describes it.
+#[derive(Debug, Clone, Default)]
+pub struct SessionOrigin {
+    pub device: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl SessionOrigin {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let device = headers.get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|device| device.chars().take(MAX_DEVICE_LENGTH).collect());
+        Self { device, ip_address: client_ip(headers) }
+    }
+}
+
+/// Each user's tokens, WebSockets and location sessions, kept in the Redis
+/// session store so any instance can list or revoke them. A revoked session
+/// is refused wherever it's next used; connections on this instance are
+/// also told through `subscribe` so they can close at once. Tokens issued
+/// without a session, such as the gateway's, aren't tracked.
+pub struct SessionRegistry {
+    state: Arc<StateManager>,
+    // How long a session is listed, and a revocation remembered: the user token lifetime
+    ttl: Duration,
+    revoked: broadcast::Sender<String>,
+}
+
+impl SessionRegistry {
+    pub fn new(state: Arc<StateManager>, ttl: std::time::Duration) -> Self {
+        Self {
+            state,
+            ttl: Duration::from_std(ttl).unwrap_or_else(|_| Duration::days(1)),
+            revoked: broadcast::channel(256).0,
+        }
+    }
+
+    /// IDs of sessions revoked by requests to this instance, as it happens.
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.revoked.subscribe()
+    }
+
+    /// Records a session opened under `session_id`, listed until it's closed,
+    /// revoked or expires.
+    pub async fn open(
+        &self,
+        session_id: String,
+        user_id: &str,
+        kind: SessionKind,
+        parent_id: Option<String>,
+        origin: SessionOrigin,
+    ) -> Result<UserSession, SessionError> {
+        let now = Utc::now();
+        let session = UserSession {
+            session_id,
+            user_id: user_id.to_string(),
+            kind,
+            parent_id,
+            device: origin.device,
+            ip_address: origin.ip_address,
+            created_at: now,
+            expires_at: now + self.ttl,
+        };
+        self.state.set_session(&session.session_id, &serde_json::to_string(&session)?, self.ttl_secs()).await?;
+        self.state.add_user_session(user_id, &session.session_id, self.ttl_secs()).await?;
+        Ok(session)
+    }
+
+    /// Stops listing a session that ended on its own, such as a closed WebSocket.
+    pub async fn close(&self, session: &UserSession) {
+        let closed = async {
+            self.state.delete_session(&session.session_id).await?;
+            self.state.remove_user_session(&session.user_id, &session.session_id).await
+        };
+        if let Err(e) = closed.await {
+            warn!("Failed to close session {} of user {}: {}", session.session_id, session.user_id, e);
+        }
+    }
+
+    /// `user_id`'s sessions, newest first.
+    pub async fn list(&self, user_id: &str) -> Result<Vec<UserSession>, SessionError> {
+        let mut sessions = Vec::new();
+        for session_id in self.state.get_user_sessions(user_id).await? {
+            match self.state.get_session(&session_id).await? {
+                Some(stored) => sessions.push(serde_json::from_str::<UserSession>(&stored)?),
+                // Expired; the index is only trimmed here
+                None => self.state.remove_user_session(user_id, &session_id).await?,
+            }
+        }
+        sessions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(sessions)
+    }
+
+    /// Revokes `user_id`'s session `session_id` and the sessions opened with
+    /// it. Returns them, the one asked for first, or None if the user has no
+    /// such session.
+    pub async fn revoke(&self, user_id: &str, session_id: &str) -> Result<Option<Vec<UserSession>>, SessionError> {
+        let sessions = self.list(user_id).await?;
+        let Some(target) = sessions.iter().find(|session| session.session_id == session_id) else {
+            return Ok(None);
+        };
+
+        let mut revoked = vec![target.clone()];
+        revoked.extend(sessions.iter().filter(|session| session.parent_id.as_deref() == Some(session_id)).cloned());
+        for session in &revoked {
+            // Marked before the record goes, so there's no moment it's neither listed nor refused
+            self.state.revoke_session(&session.session_id, self.ttl_secs()).await?;
+            self.state.delete_session(&session.session_id).await?;
+            self.state.remove_user_session(user_id, &session.session_id).await?;
+            let _ = self.revoked.send(session.session_id.clone());
+        }
+        info!("Revoked {} sessions of user {} starting from {}", revoked.len(), user_id, session_id);
+        Ok(Some(revoked))
+    }
+
+    pub async fn is_revoked(&self, session_id: &str) -> Result<bool, SessionError> {
+        Ok(self.state.is_session_revoked(session_id).await?)
+    }
+
+    fn ttl_secs(&self) -> usize {
+        self.ttl.num_seconds().max(1) as usize
+    }
+}
@@ -0,0 +1,485 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use sqlx::{Pool, Postgres, Row};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::principal::Role;
+use crate::validation::{Validate, ValidationErrors};
+
+type UserError = Box<dyn std::error::Error + Send + Sync>;
+
+const MIN_PASSWORD_LENGTH: usize = 10;
+const MAX_DISPLAY_NAME_LENGTH: usize = 64;
+const MAX_BIO_LENGTH: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct User {
+    pub user_id: String,
+    pub email: String,
+    pub username: String,
+    pub roles: Vec<Role>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub is_active: bool,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserProfile {
+    pub user_id: String,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub preferences: serde_json::Value,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct Registration {
+    pub email: String,
+    pub username: String,
+    pub password: String,
+    pub display_name: Option<String>,
+}
+
+impl Registration {
+    pub fn validate(&self) -> Result<(), String> {
+        ValidationErrors::collect(self).map_err(|e| e.to_string())
+    }
+}
+
+impl Validate for Registration {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        if !is_valid_email(&self.email) {
+            errors.add("email", "must be a valid email address");
+        }
+        let valid_chars = self.username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !(3..=32).contains(&self.username.len()) || !valid_chars {
+            errors.add("username", "must be 3-32 letters, digits, '_' or '-'");
+        }
+        if self.password.chars().count() < MIN_PASSWORD_LENGTH {
+            errors.add("password", format!("must be at least {} characters", MIN_PASSWORD_LENGTH));
+        }
+        if let Some(display_name) = &self.display_name {
+            validate_display_name(errors, display_name);
+        }
+    }
+}
+
+fn is_valid_email(email: &str) -> bool {
+    let email = email.trim();
+    email.len() <= 254
+        && email.split_once('@').map(|(local, domain)| !local.is_empty() && domain.contains('.')).unwrap_or(false)
+}
+
+fn validate_display_name(errors: &mut ValidationErrors, display_name: &str) {
+    if display_name.trim().is_empty() || display_name.chars().count() > MAX_DISPLAY_NAME_LENGTH {
+        errors.add("display_name", format!("must be 1-{} characters", MAX_DISPLAY_NAME_LENGTH));
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct ProfileUpdate {
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub preferences: Option<serde_json::Value>,
+}
+
+impl Validate for ProfileUpdate {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        if let Some(display_name) = &self.display_name {
+            validate_display_name(errors, display_name);
+        }
+        if let Some(avatar_url) = &self.avatar_url {
+            if !avatar_url.starts_with("https://") {
+                errors.add("avatar_url", "must be an https URL");
+            }
+        }
+        if self.bio.as_ref().map(|bio| bio.chars().count() > MAX_BIO_LENGTH).unwrap_or(false) {
+            errors.add("bio", format!("must be at most {} characters", MAX_BIO_LENGTH));
+        }
+        if self.preferences.as_ref().map(|p| !p.is_object()).unwrap_or(false) {
+            errors.add("preferences", "must be a JSON object");
+        }
+    }
+}
+
+/// An identity the API gateway has already verified with an OAuth provider.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct OAuthIdentity {
+    pub provider: String,
+    pub provider_subject: String,
+    pub email: String,
+    pub display_name: Option<String>,
+}
+
+impl Validate for OAuthIdentity {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("provider", &self.provider);
+        errors.require_non_empty("provider_subject", &self.provider_subject);
+        if !is_valid_email(&self.email) {
+            errors.add("email", "must be a valid email address");
+        }
+    }
+}
+
+/// Called when a user needs to confirm their email address, with the token
+/// they must send back to `/api/auth/verify-email`.
+#[async_trait::async_trait]
+pub trait VerificationHook: Send + Sync {
+    async fn verification_requested(&self, user: &User, token: &str);
+}
+
+/// Posts verification requests to a webhook that sends the actual email.
+pub struct WebhookVerificationHook {
+    url: String,
+    http_client: reqwest::Client,
+}
+
+impl WebhookVerificationHook {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            http_client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl VerificationHook for WebhookVerificationHook {
+    async fn verification_requested(&self, user: &User, token: &str) {
+        let payload = serde_json::json!({
+            "user_id": user.user_id,
+            "email": user.email,
+            "username": user.username,
+            "token": token,
+        });
+        let result = self.http_client.post(&self.url)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        if let Err(e) = result {
+            warn!("Failed to deliver email verification for user {}: {}", user.user_id, e);
+        }
+    }
+}
+
+// Used when no webhook is configured; the token is deliberately not logged
+pub struct LogVerificationHook;
+
+#[async_trait::async_trait]
+impl VerificationHook for LogVerificationHook {
+    async fn verification_requested(&self, user: &User, _token: &str) {
+        info!("Email verification requested for user {} but no delivery hook is configured", user.user_id);
+    }
+}
+
+/// Postgres-backed user accounts, profiles and sign-in credentials.
+pub struct UserStore {
+    db_pool: Pool<Postgres>,
+    default_roles: Vec<Role>,
+    verification_ttl: Duration,
+}
+
+impl UserStore {
+    pub fn new(db_pool: Pool<Postgres>, default_roles: Vec<Role>, verification_ttl: Duration) -> Self {
+        Self {
+            db_pool,
+            default_roles,
+            verification_ttl,
+        }
+    }
+
+    /// Creates an account with a password credential. Returns None if the
+    /// email or username is already taken.
+    pub async fn register(&self, registration: &Registration) -> Result<Option<User>, UserError> {
+        registration.validate()?;
+        let password_hash = hash_password(registration.password.clone()).await?;
+
+        let mut tx = self.db_pool.begin().await?;
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO users (user_id, email, username, roles)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT DO NOTHING
+            RETURNING {}
+            "#,
+            USER_COLUMNS
+        ))
+        .bind(Uuid::new_v4().to_string())
+        .bind(registration.email.trim().to_lowercase())
+        .bind(registration.username.trim())
+        .bind(role_strings(&self.default_roles))
+        .fetch_optional(&mut *tx)
+        .await?;
+        let user = match row {
+            Some(row) => user_from_row(&row)?,
+            None => return Ok(None),
+        };
+
+        sqlx::query("INSERT INTO user_credentials (credential_id, user_id, kind, password_hash) VALUES ($1, $2, 'password', $3)")
+            .bind(Uuid::new_v4().to_string())
+            .bind(&user.user_id)
+            .bind(&password_hash)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("INSERT INTO user_profiles (user_id, display_name) VALUES ($1, $2)")
+            .bind(&user.user_id)
+            .bind(&registration.display_name)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(Some(user))
+    }
+
+    /// Checks an email and password, recording the login on success.
+    pub async fn login(&self, email: &str, password: &str) -> Result<Option<User>, UserError> {
+        let row = sqlx::query(
+            r#"
+            SELECT u.user_id, c.password_hash
+            FROM users u JOIN user_credentials c ON c.user_id = u.user_id AND c.kind = 'password'
+            WHERE u.email = $1 AND u.is_active
+            "#
+        )
+        .bind(email.trim().to_lowercase())
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        let (user_id, password_hash): (String, String) = match row {
+            Some(row) => (row.get("user_id"), row.get("password_hash")),
+            None => return Ok(None),
+        };
+        if !verify_password(password.to_string(), password_hash).await? {
+            return Ok(None);
+        }
+
+        self.record_login(&user_id).await
+    }
+
+    /// Signs in through an OAuth identity, creating the account on first use.
+    /// Returns None if the email already belongs to an account that isn't
+    /// linked to this identity.
+    pub async fn sign_in_oauth(&self, identity: &OAuthIdentity) -> Result<Option<User>, UserError> {
+        let linked: Option<String> = sqlx::query_scalar(
+            "SELECT user_id FROM user_credentials WHERE kind = 'oauth' AND provider = $1 AND provider_subject = $2"
+        )
+        .bind(&identity.provider)
+        .bind(&identity.provider_subject)
+        .fetch_optional(&self.db_pool)
+        .await?;
+        if let Some(user_id) = linked {
+            return self.record_login(&user_id).await;
+        }
+
+        // The provider verified the email, so the account starts out verified
+        let email = identity.email.trim().to_lowercase();
+        let local_part = email.split('@').next().unwrap_or("user");
+        let username: String = local_part.chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+            .take(23)
+            .collect();
+        let username = format!("{}-{}", username, &Uuid::new_v4().simple().to_string()[..8]);
+
+        let mut tx = self.db_pool.begin().await?;
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO users (user_id, email, username, roles, email_verified_at, last_login_at)
+            VALUES ($1, $2, $3, $4, NOW(), NOW())
+            ON CONFLICT DO NOTHING
+            RETURNING {}
+            "#,
+            USER_COLUMNS
+        ))
+        .bind(Uuid::new_v4().to_string())
+        .bind(&email)
+        .bind(&username)
+        .bind(role_strings(&self.default_roles))
+        .fetch_optional(&mut *tx)
+        .await?;
+        let user = match row {
+            Some(row) => user_from_row(&row)?,
+            None => return Ok(None),
+        };
+
+        sqlx::query(
+            "INSERT INTO user_credentials (credential_id, user_id, kind, provider, provider_subject) VALUES ($1, $2, 'oauth', $3, $4)"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&user.user_id)
+        .bind(&identity.provider)
+        .bind(&identity.provider_subject)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT INTO user_profiles (user_id, display_name) VALUES ($1, $2)")
+            .bind(&user.user_id)
+            .bind(&identity.display_name)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(Some(user))
+    }
+
+    pub async fn get(&self, user_id: &str) -> Result<Option<User>, UserError> {
+        let row = sqlx::query(&format!("SELECT {} FROM users WHERE user_id = $1", USER_COLUMNS))
+            .bind(user_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+
+        row.as_ref().map(user_from_row).transpose()
+    }
+
+    pub async fn profile(&self, user_id: &str) -> Result<Option<UserProfile>, UserError> {
+        let row = sqlx::query(&format!("SELECT {} FROM user_profiles WHERE user_id = $1", PROFILE_COLUMNS))
+            .bind(user_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+
+        Ok(row.as_ref().map(profile_from_row))
+    }
+
+    // Fields left out of the update keep their current value
+    pub async fn update_profile(&self, user_id: &str, update: &ProfileUpdate) -> Result<UserProfile, UserError> {
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO user_profiles (user_id, display_name, avatar_url, bio, preferences)
+            VALUES ($1, $2, $3, $4, COALESCE($5, '{{}}'::jsonb))
+            ON CONFLICT (user_id) DO UPDATE SET
+                display_name = COALESCE(EXCLUDED.display_name, user_profiles.display_name),
+                avatar_url = COALESCE(EXCLUDED.avatar_url, user_profiles.avatar_url),
+                bio = COALESCE(EXCLUDED.bio, user_profiles.bio),
+                preferences = COALESCE($5, user_profiles.preferences),
+                updated_at = NOW()
+            RETURNING {}
+            "#,
+            PROFILE_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(&update.display_name)
+        .bind(&update.avatar_url)
+        .bind(&update.bio)
+        .bind(&update.preferences)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(profile_from_row(&row))
+    }
+
+    /// Issues a single-use token confirming the user's current email address.
+    pub async fn create_email_verification(&self, user: &User) -> Result<String, UserError> {
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let expires_at = Utc::now() + chrono::Duration::from_std(self.verification_ttl)?;
+
+        sqlx::query("INSERT INTO email_verifications (token_hash, user_id, email, expires_at) VALUES ($1, $2, $3, $4)")
+            .bind(sha256::digest(token.as_str()))
+            .bind(&user.user_id)
+            .bind(&user.email)
+            .bind(expires_at)
+            .execute(&self.db_pool)
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Consumes a verification token. Returns None if it is unknown, used,
+    /// expired, or for an email the user no longer has.
+    pub async fn verify_email(&self, token: &str) -> Result<Option<User>, UserError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        let user_id: Option<String> = sqlx::query_scalar(
+            r#"
+            UPDATE email_verifications v SET consumed_at = NOW()
+            FROM users u
+            WHERE v.token_hash = $1 AND v.consumed_at IS NULL AND v.expires_at > NOW()
+              AND u.user_id = v.user_id AND u.email = v.email
+            RETURNING v.user_id
+            "#
+        )
+        .bind(sha256::digest(token))
+        .fetch_optional(&mut *tx)
+        .await?;
+        let user_id = match user_id {
+            Some(user_id) => user_id,
+            None => return Ok(None),
+        };
+
+        let row = sqlx::query(&format!(
+            "UPDATE users SET email_verified_at = COALESCE(email_verified_at, NOW()), updated_at = NOW() \
+             WHERE user_id = $1 RETURNING {}",
+            USER_COLUMNS
+        ))
+        .bind(&user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(user_from_row(&row)?))
+    }
+
+    async fn record_login(&self, user_id: &str) -> Result<Option<User>, UserError> {
+        let row = sqlx::query(&format!(
+            "UPDATE users SET last_login_at = NOW() WHERE user_id = $1 AND is_active RETURNING {}",
+            USER_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        row.as_ref().map(user_from_row).transpose()
+    }
+}
+
+// bcrypt is deliberately slow, so keep it off the async workers
+async fn hash_password(password: String) -> Result<String, UserError> {
+    Ok(tokio::task::spawn_blocking(move || bcrypt::hash(password, bcrypt::DEFAULT_COST)).await??)
+}
+
+async fn verify_password(password: String, password_hash: String) -> Result<bool, UserError> {
+    Ok(tokio::task::spawn_blocking(move || bcrypt::verify(password, &password_hash)).await??)
+}
+
+fn role_strings(roles: &[Role]) -> Vec<String> {
+    roles.iter()
+        .filter_map(|role| serde_json::to_value(role).ok())
+        .filter_map(|value| value.as_str().map(str::to_string))
+        .collect()
+}
+
+const USER_COLUMNS: &str = "user_id, email, username, roles, email_verified_at, is_active, last_login_at, created_at";
+
+const PROFILE_COLUMNS: &str = "user_id, display_name, avatar_url, bio, preferences, updated_at";
+
+fn user_from_row(row: &sqlx::postgres::PgRow) -> Result<User, UserError> {
+    let roles: Vec<String> = row.get("roles");
+    Ok(User {
+        user_id: row.get("user_id"),
+        email: row.get("email"),
+        username: row.get("username"),
+        roles: roles.into_iter()
+            .map(|role| serde_json::from_value(serde_json::Value::String(role)))
+            .collect::<Result<_, _>>()?,
+        email_verified_at: row.get("email_verified_at"),
+        is_active: row.get("is_active"),
+        last_login_at: row.get("last_login_at"),
+        created_at: row.get("created_at"),
+    })
+}
+
+fn profile_from_row(row: &sqlx::postgres::PgRow) -> UserProfile {
+    UserProfile {
+        user_id: row.get("user_id"),
+        display_name: row.get("display_name"),
+        avatar_url: row.get("avatar_url"),
+        bio: row.get("bio"),
+        preferences: row.get("preferences"),
+        updated_at: row.get("updated_at"),
+    }
+}
@@ -1,55 +1,221 @@
+use super::fairness::FairnessReport;
+use super::liquidity::{LiquidityCap, MarketLiquidity};
+use super::repository::{BettingRepository, Placement};
+use super::shards::{shard_of, SettlementJob, ShardedBalances};
 use super::types::*;
+use crate::config::{BetArchiveConfig, BettingConfig, DatabasePoolConfig, SettlementConfig};
+use crate::features::{self, FeatureFlags, FlagContext};
+use crate::geolocation::GeolocationService;
+use crate::geolocation::clearance::{Clearance, LocationClearances};
 use crate::state::StateManager;
+use crate::stream::{StreamActivity, StreamManager, StreamStatus};
+use crate::pagination::{PageRequest, Paginated};
+use crate::replica::ReadRouter;
+use crate::shutdown::Shutdown;
+use crate::tenant::{self, Tenant};
+use crate::audit;
+use crate::error_reporting::{self, ReportContext};
 use anyhow::{Result, Context};
 use dashmap::DashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio::time::{Duration, interval};
 use tracing::{info, warn, error};
-use sqlx::{Pool, Postgres, Row};
+use sqlx::{Pool, Postgres};
 use chrono::Utc;
 
 pub struct BettingEngine {
     state_manager: Arc<StateManager>,
-    db_pool: Pool<Postgres>,
-    active_bets: DashMap<String, Bet>, // bet_id -> Bet
-    user_balances: DashMap<String, UserBalance>, // user_id:stream_id -> UserBalance
+    repository: Arc<BettingRepository>,
+    // bet_id -> Bet, open bets only; a bet leaves once it's settled or expires
+    active_bets: Arc<DashMap<String, Bet>>,
+    user_balances: Arc<ShardedBalances>,
+    // One worker per balance shard; a bet is settled by its bettor's shard
+    settlement_queues: Vec<mpsc::Sender<SettlementJob>>,
+    // Pricing; replaced in place when the configuration is reloaded
+    config: watch::Receiver<BettingConfig>,
+    flags: Arc<FeatureFlags>,
+    // Where bettors last verified they were
+    location_clearances: Arc<LocationClearances>,
+    // Whether a stream is live and open to bets
+    stream_manager: Arc<StreamManager>,
+    // Which bettors' location sessions are inside an exclusion zone
+    geolocation_service: Arc<GeolocationService>,
+    shutdown: Shutdown,
 }
 
 impl BettingEngine {
     pub async fn new(
         state_manager: Arc<StateManager>,
         database_url: &str,
+        pool: &DatabasePoolConfig,
+        config: watch::Receiver<BettingConfig>,
+        flags: Arc<FeatureFlags>,
+        archive: BetArchiveConfig,
+        settlement: SettlementConfig,
+        reads: Arc<ReadRouter>,
+        location_clearances: Arc<LocationClearances>,
+        stream_manager: Arc<StreamManager>,
+        geolocation_service: Arc<GeolocationService>,
+        shutdown: Shutdown,
     ) -> Result<Self> {
-        let db_pool = sqlx::postgres::PgPool::connect(database_url).await
+        let db_pool = tenant::connect(database_url, pool).await
             .context("Failed to connect to PostgreSQL")?;
 
-        // Run migrations
-        sqlx::migrate!("./migrations").run(&db_pool).await
-            .context("Failed to run database migrations")?;
+        let settler = Settler {
+            state_manager,
+            repository: Arc::new(BettingRepository::new(db_pool, reads)),
+            active_bets: Arc::new(DashMap::new()),
+            user_balances: Arc::new(ShardedBalances::new(settlement.shards)),
+            lock_ttl: Duration::from_millis(settlement.lock_ttl_ms),
+        };
+        let settlement_queues = (0..settlement.shards)
+            .map(|_| {
+                let (queue, jobs) = mpsc::channel(settlement.queue_capacity);
+                spawn_settlement_worker(settler.clone(), jobs, settlement.batch_size, &shutdown);
+                queue
+            })
+            .collect();
+        info!("Settling bets across {} shards", settlement.shards);
 
         let engine = Self {
-            state_manager,
-            db_pool,
-            active_bets: DashMap::new(),
-            user_balances: DashMap::new(),
+            state_manager: settler.state_manager,
+            repository: settler.repository,
+            active_bets: settler.active_bets,
+            user_balances: settler.user_balances,
+            settlement_queues,
+            config,
+            flags,
+            location_clearances,
+            stream_manager,
+            geolocation_service,
+            shutdown,
         };
 
         // Start background tasks
         engine.start_bet_resolution_monitor().await;
-        engine.start_balance_sync_task().await;
+        engine.start_balance_write_behind(&settlement);
+        if archive.enabled {
+            engine.start_archival_task(archive);
+        }
 
         Ok(engine)
     }
 
+    /// How much more `stream_id`'s market can take on, against its own cap or
+    /// `[betting] market_liquidity`.
+    pub async fn market_liquidity(&self, stream_id: &str) -> Result<MarketLiquidity> {
+        let default_liquidity = self.config.borrow().market_liquidity;
+        self.repository.market_liquidity(stream_id, default_liquidity).await
+    }
+
+    /// The streams whose liquidity cap an admin has overridden, by stream ID.
+    pub async fn list_liquidity_caps(&self) -> Result<Vec<LiquidityCap>> {
+        self.repository.list_liquidity_caps().await
+    }
+
+    pub async fn find_liquidity_cap(&self, stream_id: &str) -> Result<Option<LiquidityCap>> {
+        self.repository.find_liquidity_cap(stream_id).await
+    }
+
+    /// Overrides `[betting] market_liquidity` for `stream_id`.
+    pub async fn set_liquidity_cap(&self, stream_id: &str, max_liability: f64) -> Result<LiquidityCap> {
+        self.repository.set_liquidity_cap(stream_id, max_liability).await
+    }
+
+    /// Removes `stream_id`'s override, returning it, or None if there wasn't one.
+    pub async fn clear_liquidity_cap(&self, stream_id: &str) -> Result<Option<LiquidityCap>> {
+        self.repository.clear_liquidity_cap(stream_id).await
+    }
+
+    /// Realized margin and implied-vs-actual win rates for bets settled from
+    /// `from` up to `to`, per market type.
+    pub async fn fairness_report(&self, from: chrono::DateTime<Utc>, to: chrono::DateTime<Utc>) -> Result<FairnessReport> {
+        self.repository.fairness_report(from, to).await
+    }
+
+    /// The engine's own pool, separate from the service's.
+    pub fn db_pool(&self) -> &Pool<Postgres> {
+        self.repository.db_pool()
+    }
+
     pub async fn place_bet(&self, bet_request: BetRequest) -> Result<BetResult> {
-        let balance_key = format!("{}:{}", bet_request.user_id, bet_request.stream_id);
+        let flag_context = FlagContext {
+            user_id: Some(&bet_request.user_id),
+            stream_id: Some(&bet_request.stream_id),
+        };
+
+        // Checked before a balance is opened, so none is opened on a stream that doesn't exist
+        if let Some(reason) = self.market_closed_reason(&bet_request.stream_id).await? {
+            return Ok(BetResult {
+                bet_id: String::new(),
+                success: false,
+                message: reason,
+                remaining_balance: self.peek_balance(&bet_request.user_id, &bet_request.stream_id).await?.available_balance(),
+                bet_details: None,
+                matched_stake: 0.0,
+                unmatched_stake: 0.0,
+            });
+        }
 
         // Get or create user balance
-        let mut user_balance = self.get_or_create_user_balance(
+        let user_balance = self.get_or_create_user_balance(
             &bet_request.user_id,
             &bet_request.stream_id,
         ).await?;
 
+        if self.repository.is_frozen(&bet_request.user_id).await? {
+            return Ok(BetResult {
+                bet_id: String::new(),
+                success: false,
+                message: "This account is frozen pending review".to_string(),
+                remaining_balance: user_balance.available_balance(),
+                bet_details: None,
+                matched_stake: 0.0,
+                unmatched_stake: 0.0,
+            });
+        }
+
+        let clearance = self.location_clearances.check(&bet_request.user_id).await
+            .map_err(|e| anyhow::anyhow!(e))
+            .context("Failed to check the bettor's location")?;
+        if let Some(message) = self.jurisdiction_problem(&bet_request.user_id, &clearance).await {
+            return Ok(BetResult {
+                bet_id: String::new(),
+                success: false,
+                message,
+                remaining_balance: user_balance.available_balance(),
+                bet_details: None,
+                matched_stake: 0.0,
+                unmatched_stake: 0.0,
+            });
+        }
+
+        if bet_request.bet_type == BetType::Pattern && !self.flags.is_enabled(features::PATTERN_BETS, &flag_context) {
+            return Ok(BetResult {
+                bet_id: String::new(),
+                success: false,
+                message: "Pattern bets are not available right now".to_string(),
+                remaining_balance: user_balance.available_balance(),
+                bet_details: None,
+                matched_stake: 0.0,
+                unmatched_stake: 0.0,
+            });
+        }
+
+        if let Some(message) = self.stake_limit_problem(bet_request.stake_amount) {
+            return Ok(BetResult {
+                bet_id: String::new(),
+                success: false,
+                message,
+                remaining_balance: user_balance.available_balance(),
+                bet_details: None,
+                matched_stake: 0.0,
+                unmatched_stake: 0.0,
+            });
+        }
+
         // Check if user can place the bet
         if !user_balance.can_place_bet(bet_request.stake_amount) {
             return Ok(BetResult {
@@ -62,14 +228,16 @@ impl BettingEngine {
                 ),
                 remaining_balance: user_balance.available_balance(),
                 bet_details: None,
+                matched_stake: 0.0,
+                unmatched_stake: 0.0,
             });
         }
 
         // Calculate odds based on bet type and current market
-        let odds = self.calculate_odds(&bet_request).await?;
+        let odds = self.calculate_odds(&bet_request, &flag_context).await?;
 
         // Create the bet
-        let bet = Bet::new(
+        let mut bet = Bet::new(
             bet_request.user_id.clone(),
             bet_request.stream_id.clone(),
             bet_request.bet_type,
@@ -78,38 +246,88 @@ impl BettingEngine {
             bet_request.time_window_seconds,
             odds,
         );
+        bet.location_verification_id = clearance.verification_id().map(str::to_string);
 
-        // Deduct from balance
-        if !user_balance.place_bet(bet_request.stake_amount) {
-            return Ok(BetResult {
-                bet_id: String::new(),
-                success: false,
-                message: "Failed to place bet due to balance issue".to_string(),
-                remaining_balance: user_balance.available_balance(),
-                bet_details: None,
-            });
-        }
-
-        // Store bet in database
-        self.store_bet_in_db(&bet).await?;
+        let (default_liquidity, min_stake) = {
+            let config = self.config.borrow();
+            (config.market_liquidity, config.min_stake)
+        };
+        // The deduction, the bet and its ledger entry commit together or not at all
+        let user_balance = match self.repository.place_bet(&mut bet, default_liquidity, min_stake).await? {
+            Placement::Placed(user_balance) => user_balance,
+            Placement::NoLiquidity => {
+                return Ok(BetResult {
+                    bet_id: String::new(),
+                    success: false,
+                    message: "This market can't take any more bets right now".to_string(),
+                    remaining_balance: user_balance.available_balance(),
+                    bet_details: None,
+                    matched_stake: 0.0,
+                    unmatched_stake: 0.0,
+                });
+            }
+            Placement::EmergencyStopped(reason) => {
+                return Ok(BetResult {
+                    bet_id: String::new(),
+                    success: false,
+                    message: format!("Betting on this stream is suspended: {}", reason),
+                    remaining_balance: user_balance.available_balance(),
+                    bet_details: None,
+                    matched_stake: 0.0,
+                    unmatched_stake: 0.0,
+                });
+            }
+            Placement::InsufficientBalance => {
+                // The cached balance was stale; the stored one is what counts
+                let stored = self.repository.find_balance(&bet.user_id, &bet.stream_id).await?;
+                let available = stored.as_ref().map(UserBalance::available_balance).unwrap_or(0.0);
+                if let Some(stored) = stored {
+                    self.user_balances.insert(stored);
+                }
+                return Ok(BetResult {
+                    bet_id: String::new(),
+                    success: false,
+                    message: format!(
+                        "Insufficient balance: ${:.2} available, ${:.2} required",
+                        available,
+                        bet_request.stake_amount
+                    ),
+                    remaining_balance: available,
+                    bet_details: None,
+                    matched_stake: 0.0,
+                    unmatched_stake: 0.0,
+                });
+            }
+        };
 
-        // Store bet in active bets
+        // Only committed state reaches the caches; Redis is written behind
         self.active_bets.insert(bet.id.clone(), bet.clone());
-
-        // Update user balance in cache and Redis
-        self.user_balances.insert(balance_key.clone(), user_balance.clone());
-        self.sync_balance_to_redis(&user_balance).await?;
+        self.user_balances.insert(user_balance.clone());
 
         info!(
             "Placed bet {} for user {} on stream {} (${:.2})",
             bet.id, bet.user_id, bet.stream_id, bet.stake_amount
         );
 
+        // Scaled down to the market's liquidity; the rest never left the balance
+        let unmatched_stake = bet_request.stake_amount - bet.stake_amount;
+        let message = if unmatched_stake > 0.0 {
+            format!(
+                "Bet partly placed: this market could only take ${:.2} of your ${:.2} stake, and the other ${:.2} stays in your balance",
+                bet.stake_amount,
+                bet_request.stake_amount,
+                unmatched_stake
+            )
+        } else {
+            "Bet placed successfully".to_string()
+        };
         Ok(BetResult {
             bet_id: bet.id.clone(),
             success: true,
-            message: "Bet placed successfully".to_string(),
+            message,
             remaining_balance: user_balance.available_balance(),
+            matched_stake: bet.stake_amount,
+            unmatched_stake,
             bet_details: Some(bet),
         })
     }
@@ -119,284 +337,675 @@ impl BettingEngine {
         user_id: &str,
         stream_id: &str,
     ) -> Result<UserBalance> {
-        let balance_key = format!("{}:{}", user_id, stream_id);
-
         // Check cache first
-        if let Some(balance) = self.user_balances.get(&balance_key) {
-            return Ok(balance.clone());
+        if let Some(balance) = self.user_balances.get(user_id, stream_id) {
+            return Ok(balance);
         }
 
         // Check database
-        let row = sqlx::query(
-            "SELECT * FROM user_balances WHERE user_id = $1 AND stream_id = $2"
-        )
-        .bind(user_id)
-        .bind(stream_id)
-        .fetch_optional(&self.db_pool)
-        .await?;
-
-        if let Some(row) = row {
-            let balance = UserBalance {
-                user_id: row.get("user_id"),
-                stream_id: row.get("stream_id"),
-                total_deposited: row.get("total_deposited"),
-                activation_cost: row.get("activation_cost"),
-                betting_balance: row.get("betting_balance"),
-                active_bets_total: row.get("active_bets_total"),
-                total_winnings: row.get("total_winnings"),
-                total_losses: row.get("total_losses"),
-                bet_count: row.get("bet_count"),
-                created_at: row.get("created_at"),
-                last_updated: row.get("last_updated"),
-            };
-
-            self.user_balances.insert(balance_key, balance.clone());
+        if let Some(balance) = self.repository.find_balance(user_id, stream_id).await? {
+            self.user_balances.insert(balance.clone());
             Ok(balance)
         } else {
             // Create new balance (this would happen when user first joins a stream)
-            let default_deposit = 50.0; // Default for development
-            let activation_cost = 10.0;
-            
-            let mut balance = UserBalance::new(
+            let balance = UserBalance::new(
                 user_id.to_string(),
                 stream_id.to_string(),
-                default_deposit,
-                activation_cost,
+                DEFAULT_DEPOSIT,
+                ACTIVATION_COST,
             );
 
-            self.store_balance_in_db(&balance).await?;
-            self.user_balances.insert(balance_key, balance.clone());
+            let balance = self.repository.create_balance(&balance).await?;
+            self.user_balances.insert(balance.clone());
             
             Ok(balance)
         }
     }
 
-    async fn calculate_odds(&self, bet_request: &BetRequest) -> Result<f64> {
+    /// What would stop each of `requests`, all `user_id`'s, being placed now,
+    /// with the odds it would get. The requests are taken as one slip: stakes
+    /// on the same stream draw on the same balance, in order. Nothing is placed
+    /// or reserved, and no balance is opened.
+    pub async fn check_slip(&self, user_id: &str, requests: &[BetRequest]) -> Result<Vec<SlipLineCheck>> {
+        let frozen = self.repository.is_frozen(user_id).await?;
+        let clearance = self.location_clearances.check(user_id).await
+            .map_err(|e| anyhow::anyhow!(e))
+            .context("Failed to check the bettor's location")?;
+        let jurisdiction = self.jurisdiction_problem(user_id, &clearance).await;
+        // What's left on each stream after the lines before
+        let mut remaining: HashMap<String, f64> = HashMap::new();
+        // Why each stream can't take bets, if it can't; looked up once however many lines it has
+        let mut closed: HashMap<String, Option<String>> = HashMap::new();
+        // Each stream's liquidity, less what the lines before would take
+        let mut markets: HashMap<String, MarketLiquidity> = HashMap::new();
+        let (default_liquidity, min_stake) = {
+            let config = self.config.borrow();
+            (config.market_liquidity, config.min_stake)
+        };
+        let mut checks = Vec::with_capacity(requests.len());
+
+        for request in requests {
+            let flag_context = FlagContext {
+                user_id: Some(user_id),
+                stream_id: Some(&request.stream_id),
+            };
+            let mut problems = Vec::new();
+            if frozen {
+                problems.push(SlipProblem {
+                    check: SlipCheck::Account,
+                    message: "This account is frozen pending review".to_string(),
+                });
+            }
+            if let Some(message) = &jurisdiction {
+                problems.push(SlipProblem {
+                    check: SlipCheck::Jurisdiction,
+                    message: message.clone(),
+                });
+            }
+            if request.bet_type == BetType::Pattern && !self.flags.is_enabled(features::PATTERN_BETS, &flag_context) {
+                problems.push(SlipProblem {
+                    check: SlipCheck::Market,
+                    message: "Pattern bets are not available right now".to_string(),
+                });
+            }
+            if !closed.contains_key(&request.stream_id) {
+                let reason = self.market_closed_reason(&request.stream_id).await?;
+                closed.insert(request.stream_id.clone(), reason);
+            }
+            if let Some(Some(reason)) = closed.get(&request.stream_id) {
+                problems.push(SlipProblem {
+                    check: SlipCheck::Market,
+                    message: reason.clone(),
+                });
+            }
+            if let Some(message) = self.stake_limit_problem(request.stake_amount) {
+                problems.push(SlipProblem { check: SlipCheck::StakeLimit, message });
+            }
+
+            let available = match remaining.get(&request.stream_id) {
+                Some(available) => *available,
+                None => self.peek_balance(user_id, &request.stream_id).await?.available_balance(),
+            };
+            if request.stake_amount > available {
+                problems.push(SlipProblem {
+                    check: SlipCheck::Balance,
+                    message: format!(
+                        "Insufficient balance: ${:.2} available after the lines before, ${:.2} required",
+                        available,
+                        request.stake_amount
+                    ),
+                });
+            }
+            remaining.insert(request.stream_id.clone(), available - request.stake_amount);
+
+            let odds = self.calculate_odds(request, &flag_context).await?;
+            if !markets.contains_key(&request.stream_id) {
+                let market = self.repository.market_liquidity(&request.stream_id, default_liquidity).await?;
+                markets.insert(request.stream_id.clone(), market);
+            }
+            let mut matched_stake = request.stake_amount;
+            if let Some(market) = markets.get_mut(&request.stream_id) {
+                match market.matchable_stake(odds) {
+                    Some(matchable) if matchable < min_stake => problems.push(SlipProblem {
+                        check: SlipCheck::Market,
+                        message: "This market can't take any more bets right now".to_string(),
+                    }),
+                    // Placed in part; not a problem
+                    Some(matchable) if matchable < request.stake_amount => matched_stake = matchable,
+                    _ => {}
+                }
+                if let Some(available) = market.available.as_mut() {
+                    *available = (*available - matched_stake * odds).max(0.0);
+                }
+            }
+            checks.push(SlipLineCheck {
+                odds,
+                matched_stake,
+                potential_payout: matched_stake * odds,
+                problems,
+            });
+        }
+        Ok(checks)
+    }
+
+    // The balance a bet would draw on, without opening one if there isn't one yet
+    async fn peek_balance(&self, user_id: &str, stream_id: &str) -> Result<UserBalance> {
+        if let Some(balance) = self.user_balances.get(user_id, stream_id) {
+            return Ok(balance);
+        }
+        Ok(match self.repository.find_balance(user_id, stream_id).await? {
+            Some(balance) => balance,
+            None => UserBalance::new(user_id.to_string(), stream_id.to_string(), DEFAULT_DEPOSIT, ACTIVATION_COST),
+        })
+    }
+
+    // Why bets on the stream can't be taken right now, if they can't
+    async fn market_closed_reason(&self, stream_id: &str) -> Result<Option<String>> {
+        let Some(stream) = self.stream_manager.get_stream(stream_id).await? else {
+            return Ok(Some(format!("Stream {} not found", stream_id)));
+        };
+        if !stream.settings.enable_betting {
+            return Ok(Some("Betting is closed on this stream".to_string()));
+        }
+        if !matches!(stream.status, StreamStatus::Active) {
+            return Ok(Some("The stream isn't live".to_string()));
+        }
+        Ok(self.repository.market_suspension(stream_id).await?
+            .map(|reason| format!("Betting on this stream is suspended: {}", reason)))
+    }
+
+    // Why the bettor's location keeps them from betting, if it does. A location
+    // session on this instance inside an exclusion zone counts even when
+    // verification isn't required
+    async fn jurisdiction_problem(&self, user_id: &str, clearance: &Clearance) -> Option<String> {
+        if let Clearance::Refused(message) = clearance {
+            return Some(message.clone());
+        }
+        if self.geolocation_service.is_user_excluded(user_id).await {
+            return Some("Betting isn't allowed from your current location".to_string());
+        }
+        None
+    }
+
+    fn stake_limit_problem(&self, stake_amount: f64) -> Option<String> {
+        let config = self.config.borrow();
+        if stake_amount < config.min_stake {
+            Some(format!("Stake must be at least ${:.2}", config.min_stake))
+        } else if stake_amount > config.max_stake {
+            Some(format!("Stake must be at most ${:.2}", config.max_stake))
+        } else {
+            None
+        }
+    }
+
+    async fn calculate_odds(&self, bet_request: &BetRequest, flag_context: &FlagContext<'_>) -> Result<f64> {
+        Ok(self.quote_odds(bet_request.bet_type, bet_request.time_window_seconds, flag_context))
+    }
+
+    /// The odds a bet of `bet_type` over `time_window_seconds` would be placed
+    /// at now, for showing before it's placed.
+    pub fn quote_odds(&self, bet_type: BetType, time_window_seconds: u64, flag_context: &FlagContext<'_>) -> f64 {
         // Simple odds calculation - in a real system this would be more sophisticated
-        let base_odds = match bet_request.bet_type {
-            BetType::Binary => 1.9,      // Slightly house favored
-            BetType::Quantity => 2.1,    // Harder to predict exactly
-            BetType::Timing => 2.5,      // Time-sensitive, harder
-            BetType::Pattern => 3.0,     // Most complex
+        let config = self.config.borrow().clone();
+        let base_odds = match bet_type {
+            BetType::Binary => config.binary_odds,
+            BetType::Quantity => config.quantity_odds,
+            BetType::Timing => config.timing_odds,
+            BetType::Pattern => config.pattern_odds,
         };
 
         // Adjust based on time window (shorter = higher odds)
-        let time_factor = if bet_request.time_window_seconds < 30 {
+        let time_factor = if self.flags.is_enabled(features::CONTINUOUS_TIME_ODDS, flag_context) {
+            // The same 1.2 to 0.9 range without the jumps at 30s and 120s
+            let window = time_window_seconds.clamp(10, 300) as f64;
+            1.2 - 0.3 * (window / 10.0).ln() / 30f64.ln()
+        } else if time_window_seconds < 30 {
             1.2
-        } else if bet_request.time_window_seconds < 120 {
+        } else if time_window_seconds < 120 {
             1.0
         } else {
             0.9
         };
 
-        Ok(base_odds * time_factor)
-    }
-
-    async fn store_bet_in_db(&self, bet: &Bet) -> Result<()> {
-        let prediction_json = serde_json::to_string(&bet.prediction)?;
-        
-        sqlx::query(
-            r#"
-            INSERT INTO bets (
-                id, user_id, stream_id, bet_type, stake_amount, prediction,
-                status, created_at, resolution_deadline, potential_payout, odds
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-            "#
-        )
-        .bind(&bet.id)
-        .bind(&bet.user_id)
-        .bind(&bet.stream_id)
-        .bind(serde_json::to_string(&bet.bet_type)?)
-        .bind(bet.stake_amount)
-        .bind(prediction_json)
-        .bind(serde_json::to_string(&bet.status)?)
-        .bind(bet.created_at)
-        .bind(bet.resolution_deadline)
-        .bind(bet.potential_payout)
-        .bind(bet.odds)
-        .execute(&self.db_pool)
-        .await?;
-
-        Ok(())
-    }
-
-    async fn store_balance_in_db(&self, balance: &UserBalance) -> Result<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO user_balances (
-                user_id, stream_id, total_deposited, activation_cost, betting_balance,
-                active_bets_total, total_winnings, total_losses, bet_count, created_at, last_updated
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-            ON CONFLICT (user_id, stream_id) DO UPDATE SET
-                betting_balance = EXCLUDED.betting_balance,
-                active_bets_total = EXCLUDED.active_bets_total,
-                total_winnings = EXCLUDED.total_winnings,
-                total_losses = EXCLUDED.total_losses,
-                bet_count = EXCLUDED.bet_count,
-                last_updated = EXCLUDED.last_updated
-            "#
-        )
-        .bind(&balance.user_id)
-        .bind(&balance.stream_id)
-        .bind(balance.total_deposited)
-        .bind(balance.activation_cost)
-        .bind(balance.betting_balance)
-        .bind(balance.active_bets_total)
-        .bind(balance.total_winnings)
-        .bind(balance.total_losses)
-        .bind(balance.bet_count as i32)
-        .bind(balance.created_at)
-        .bind(balance.last_updated)
-        .execute(&self.db_pool)
-        .await?;
-
-        Ok(())
-    }
-
-    async fn sync_balance_to_redis(&self, balance: &UserBalance) -> Result<()> {
-        let balance_json = serde_json::to_string(balance)?;
-        let key = format!("balance:{}:{}", balance.user_id, balance.stream_id);
-        
-        self.state_manager.set_key_with_expiry(&key, &balance_json, 3600).await?;
-        Ok(())
+        base_odds * time_factor * (1.0 - config.odds_margin)
     }
 
+    /// Settles an active bet on its bettor's shard. Returns the settled bet,
+    /// or None if there's no such bet or it can no longer be resolved.
     pub async fn resolve_bet(
         &self,
         bet_id: &str,
         actual_result: ActualResult,
         confidence_score: f64,
-    ) -> Result<bool> {
-        if let Some(mut bet_entry) = self.active_bets.get_mut(bet_id) {
-            let bet = bet_entry.value_mut();
+    ) -> Result<Option<Bet>> {
+        let Some(settled) = self.queue_settlement(bet_id, actual_result, confidence_score).await? else {
+            return Ok(None);
+        };
+        settled.await.context("Settlement worker stopped before settling the bet")?
+    }
+
+    /// Settles a batch of bets, each shard working through its share in
+    /// parallel with the others. Results are in the order given.
+    pub async fn resolve_bets(&self, resolutions: Vec<(String, ActualResult, f64)>) -> Vec<Result<Option<Bet>>> {
+        // Everything is queued before anything is awaited, so the shards overlap
+        let mut pending = Vec::with_capacity(resolutions.len());
+        for (bet_id, actual_result, confidence_score) in resolutions {
+            pending.push(self.queue_settlement(&bet_id, actual_result, confidence_score).await);
+        }
 
-            if !bet.can_resolve() {
-                return Ok(false);
+        futures::future::join_all(pending.into_iter().map(|queued| async move {
+            match queued? {
+                Some(settled) => settled.await.context("Settlement worker stopped before settling the bet")?,
+                None => Ok(None),
             }
+        }))
+        .await
+    }
 
-            // Determine if bet won
-            let won = self.check_bet_outcome(&bet.prediction, &actual_result);
-            let payout_amount = if won { bet.potential_payout } else { 0.0 };
-
-            // Create resolution
-            let resolution = BetResolution {
-                actual_result,
-                won,
-                payout_amount,
-                resolved_at: Utc::now(),
-                confidence_score,
-            };
+    // None if there's no such active bet, so no shard to send it to
+    async fn queue_settlement(
+        &self,
+        bet_id: &str,
+        actual_result: ActualResult,
+        confidence_score: f64,
+    ) -> Result<Option<oneshot::Receiver<Result<Option<Bet>>>>> {
+        let cached = self.active_bets.get(bet_id).map(|bet| bet.user_id.clone());
+        let user_id = match cached {
+            Some(user_id) => user_id,
+            // Placed through another instance; its settlement lock keeps the two from racing
+            None => match self.repository.find_active_bet(bet_id).await? {
+                Some(bet) => {
+                    let user_id = bet.user_id.clone();
+                    self.active_bets.insert(bet.id.clone(), bet);
+                    user_id
+                }
+                None => return Ok(None),
+            },
+        };
 
-            bet.resolution_result = Some(resolution);
-            bet.status = BetStatus::Resolved;
+        let (reply, settled) = oneshot::channel();
+        let job = SettlementJob {
+            tenant: tenant::current(),
+            actor: audit::current(),
+            bet_id: bet_id.to_string(),
+            actual_result,
+            confidence_score,
+            reply,
+        };
+        self.settlement_queues[shard_of(&user_id, self.settlement_queues.len())]
+            .send(job)
+            .await
+            .map_err(|_| anyhow::anyhow!("Settlement workers have stopped"))?;
+        Ok(Some(settled))
+    }
 
-            // Update user balance
-            let balance_key = format!("{}:{}", bet.user_id, bet.stream_id);
-            if let Some(mut balance_entry) = self.user_balances.get_mut(&balance_key) {
-                let balance = balance_entry.value_mut();
-                balance.resolve_bet(bet.stake_amount, payout_amount);
-                
-                // Update database
-                self.store_balance_in_db(balance).await?;
-                self.sync_balance_to_redis(balance).await?;
+    /// Cancels every open bet on `stream_id` and refunds the stakes, for an
+    /// emergency stop; bets placed through other instances are voided too.
+    /// Returns the voided bets.
+    pub async fn void_stream_bets(&self, stream_id: &str) -> Result<Vec<Bet>> {
+        let voided = self.repository.void_stream_bets(stream_id).await?;
+
+        let mut refunded = HashSet::new();
+        for bet in &voided {
+            self.active_bets.remove(&bet.id);
+            if refunded.insert(bet.user_id.clone()) {
+                if let Some(balance) = self.repository.find_balance(&bet.user_id, stream_id).await? {
+                    self.user_balances.insert(balance);
+                }
             }
+        }
+        info!("Voided {} open bets on stream {} and refunded {} bettors", voided.len(), stream_id, refunded.len());
+        Ok(voided)
+    }
 
-            // Update bet in database
-            self.update_bet_in_db(bet).await?;
+    /// A user's bets, newest first unless sorted by `created_at`, including
+    /// those already moved to the archive.
+    pub async fn bet_history(
+        &self,
+        user_id: &str,
+        stream_id: Option<&str>,
+        page: &PageRequest,
+    ) -> Result<Paginated<Bet>> {
+        let ascending = page.sort.as_ref().map(|sort| !sort.descending).unwrap_or(false);
+        let (bets, total) = self.repository
+            .bet_history(user_id, stream_id, ascending, page.limit, page.offset)
+            .await?;
+        Ok(page.page_of(bets, total))
+    }
 
-            info!("Resolved bet {} - Won: {}, Payout: ${:.2}", bet_id, won, payout_amount);
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+    /// A user's betting totals across every stream, or None if they've never bet.
+    pub async fn user_stats(&self, user_id: &str) -> Result<Option<UserBettingStats>> {
+        self.repository.user_stats(user_id).await
     }
 
-    fn check_bet_outcome(&self, prediction: &Prediction, actual: &ActualResult) -> bool {
-        match (prediction, actual) {
-            (Prediction::Binary { will_occur }, ActualResult::Binary { occurred }) => {
-                will_occur == occurred
-            }
-            (
-                Prediction::Quantity { predicted_value, tolerance },
-                ActualResult::Quantity { actual_value }
-            ) => {
-                (predicted_value - actual_value).abs() <= *tolerance
-            }
-            (
-                Prediction::Timing { predicted_seconds, tolerance },
-                ActualResult::Timing { actual_seconds }
-            ) => {
-                (predicted_seconds - actual_seconds).abs() <= *tolerance
-            }
-            (
-                Prediction::Pattern { sequence },
-                ActualResult::Pattern { actual_sequence }
-            ) => {
-                sequence == actual_sequence
-            }
-            _ => false,
-        }
+    /// A user's balance on a stream, opened with the starting deposit if
+    /// they don't have one yet.
+    pub async fn get_user_balance(&self, user_id: &str, stream_id: &str) -> Result<UserBalance> {
+        self.get_or_create_user_balance(user_id, stream_id).await
     }
 
-    async fn update_bet_in_db(&self, bet: &Bet) -> Result<()> {
-        let resolution_json = bet.resolution_result.as_ref()
-            .map(|r| serde_json::to_string(r))
-            .transpose()?;
+    /// The last 100 things that happened on a stream, newest first.
+    pub async fn get_stream_activity(&self, stream_id: &str) -> Result<Vec<StreamActivity>> {
+        self.state_manager.get_stream_activity(stream_id).await
+    }
 
-        sqlx::query(
-            "UPDATE bets SET status = $1, resolution_result = $2 WHERE id = $3"
-        )
-        .bind(serde_json::to_string(&bet.status)?)
-        .bind(resolution_json)
-        .bind(&bet.id)
-        .execute(&self.db_pool)
-        .await?;
+    /// Bettors ranked by `metric`, highest first. Win rate and ROI rank only
+    /// bettors with at least the configured number of settled bets.
+    pub async fn leaderboard(&self, metric: LeaderboardMetric, page: &PageRequest) -> Result<Paginated<UserBettingStats>> {
+        let min_settled = if metric.needs_track_record() {
+            self.config.borrow().leaderboard_min_settled_bets
+        } else {
+            0
+        };
+        let (ranked, total) = self.repository.leaderboard(metric, min_settled, page.limit, page.offset).await?;
+        Ok(page.page_of(ranked, total))
+    }
 
-        Ok(())
+    /// A consistent export of balances, open bets and the ledger since
+    /// `ledger_since`, for reconciliation and recovery drills.
+    pub async fn financial_snapshot(&self, ledger_since: Option<chrono::DateTime<Utc>>) -> Result<FinancialSnapshot> {
+        self.repository.financial_snapshot(ledger_since).await
+    }
+
+    /// Open bets by stream, for operator dashboards.
+    pub fn open_books(&self) -> HashMap<String, OpenBook> {
+        let mut books: HashMap<String, OpenBook> = HashMap::new();
+        for entry in self.active_bets.iter() {
+            let bet = entry.value();
+            if matches!(bet.status, BetStatus::Active) {
+                let book = books.entry(bet.stream_id.clone()).or_default();
+                book.open_bets += 1;
+                book.open_stake += bet.stake_amount;
+                book.exposure += bet.potential_payout;
+            }
+        }
+        books
     }
 
     async fn start_bet_resolution_monitor(&self) {
         let active_bets = self.active_bets.clone();
         
-        tokio::spawn(async move {
+        self.shutdown.spawn_loop("betting:settlement_monitor", async move {
             let mut interval = interval(Duration::from_secs(1));
             
             loop {
                 interval.tick().await;
                 
-                // Check for expired bets
+                // Drop expired bets; past its deadline a bet can't be settled, here or when reloaded
                 let now = Utc::now();
-                for mut entry in active_bets.iter_mut() {
-                    let bet = entry.value_mut();
-                    
-                    if bet.status == BetStatus::Active && bet.resolution_deadline < now {
-                        bet.status = BetStatus::Expired;
+                active_bets.retain(|_, bet| {
+                    let expired = bet.resolution_deadline < now;
+                    if expired {
                         warn!("Bet {} expired without resolution", bet.id);
                     }
+                    !expired
+                });
+            }
+        });
+    }
+
+    fn start_archival_task(&self, config: BetArchiveConfig) {
+        // Its own repository on the same pool, so the loop doesn't borrow the engine
+        let repository = BettingRepository::new(self.repository.db_pool().clone(), self.repository.reads().clone());
+
+        self.shutdown.spawn_loop("betting:archival", async move {
+            let mut interval = interval(Duration::from_secs(config.interval_secs));
+
+            loop {
+                interval.tick().await;
+
+                // Work through the backlog one batch at a time
+                let mut archived = 0;
+                loop {
+                    match repository.archive_settled_bets(config.archive_after_days, config.batch_size).await {
+                        Ok(moved) => {
+                            archived += moved;
+                            if moved < config.batch_size as u64 {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            error!("Failed to archive settled bets: {}", e);
+                            break;
+                        }
+                    }
+                }
+                if archived > 0 {
+                    info!("Archived {} bets settled over {} days ago", archived, config.archive_after_days);
                 }
             }
         });
     }
 
-    async fn start_balance_sync_task(&self) {
+    // Writes changed balances to Redis shortly after they change, and whatever
+    // is left on shutdown; a balance nobody touches isn't written again
+    fn start_balance_write_behind(&self, config: &SettlementConfig) {
         let user_balances = self.user_balances.clone();
         let state_manager = self.state_manager.clone();
-        
-        tokio::spawn(async move {
-            let mut interval = interval(Duration::from_secs(10));
-            
+        let shutdown = self.shutdown.clone();
+        let delay = Duration::from_millis(config.balance_flush_delay_ms);
+        let batch_size = config.balance_flush_batch_size;
+
+        // Tracked so shutdown waits for the final flush
+        self.shutdown.spawn_tracked("betting:balance_write_behind", async move {
             loop {
-                interval.tick().await;
-                
-                // Sync balances to Redis periodically
-                for entry in user_balances.iter() {
-                    let balance = entry.value();
-                    if let Ok(balance_json) = serde_json::to_string(balance) {
-                        let key = format!("balance:{}:{}", balance.user_id, balance.stream_id);
-                        let _ = state_manager.set_key_with_expiry(&key, &balance_json, 3600).await;
+                tokio::select! {
+                    _ = user_balances.changed() => {
+                        // Let a burst of changes gather into one write
+                        tokio::select! {
+                            _ = tokio::time::sleep(delay) => {}
+                            _ = shutdown.triggered() => {}
+                        }
+                    }
+                    _ = shutdown.triggered() => {}
+                }
+
+                let (flushed, failed) = flush_balances(&user_balances, &state_manager, batch_size).await;
+                if shutdown.is_triggered() {
+                    info!("Flushed {} changed balances to Redis before shutdown", flushed);
+                    return;
+                }
+                if failed > 0 {
+                    // Redis is failing; don't retry the same writes in a tight loop
+                    tokio::select! {
+                        _ = tokio::time::sleep(BALANCE_FLUSH_RETRY) => {}
+                        _ = shutdown.triggered() => {}
                     }
                 }
             }
         });
     }
-} 
\ No newline at end of file
+}
+
+// A bettor's first balance on a stream: the deposit, less what activating the stream costs
+const DEFAULT_DEPOSIT: f64 = 50.0;
+const ACTIVATION_COST: f64 = 10.0;
+
+// How long failed balance writes wait before they're tried again
+const BALANCE_FLUSH_RETRY: Duration = Duration::from_secs(1);
+
+/// What settling a bet touches, shared by the engine and its shard workers.
+#[derive(Clone)]
+struct Settler {
+    state_manager: Arc<StateManager>,
+    repository: Arc<BettingRepository>,
+    active_bets: Arc<DashMap<String, Bet>>,
+    user_balances: Arc<ShardedBalances>,
+    lock_ttl: Duration,
+}
+
+impl Settler {
+    /// Settles the jobs, all from one tenant and actor, in one transaction,
+    /// each bet under its cross-instance lock. A bet another instance is
+    /// settling right now is left to that instance.
+    async fn settle_batch(&self, jobs: Vec<SettlementJob>) {
+        let mut locked = Vec::with_capacity(jobs.len());
+        for job in jobs {
+            let lock = format!("bet-settlement:{}", job.bet_id);
+            let token = uuid::Uuid::new_v4().to_string();
+            match self.state_manager.try_lock(&lock, &token, self.lock_ttl).await {
+                Ok(true) => locked.push((job, lock, token)),
+                Ok(false) => {
+                    info!("Bet {} is being settled by another instance", job.bet_id);
+                    let _ = job.reply.send(Ok(None));
+                }
+                Err(e) => {
+                    let _ = job.reply.send(Err(e));
+                }
+            }
+        }
+
+        let requests: Vec<(&str, ActualResult, f64)> = locked.iter()
+            .map(|(job, _, _)| (job.bet_id.as_str(), job.actual_result.clone(), job.confidence_score))
+            .collect();
+        let outcomes = self.settle_locked(requests).await;
+
+        for ((job, lock, token), outcome) in locked.into_iter().zip(outcomes) {
+            // An expired lock did no harm: the settlement transaction only pays out an active bet
+            match self.state_manager.unlock(&lock, &token).await {
+                Ok(true) => {}
+                Ok(false) => warn!("Settlement lock for bet {} expired before settlement finished", job.bet_id),
+                Err(e) => warn!("Failed to release the settlement lock for bet {}: {}", job.bet_id, e),
+            }
+            let _ = job.reply.send(outcome);
+        }
+    }
+
+    // One outcome per request, in order: the settled bet, or None if it can't be settled here
+    async fn settle_locked(&self, requests: Vec<(&str, ActualResult, f64)>) -> Vec<Result<Option<Bet>>> {
+        let mut outcomes: Vec<Result<Option<Bet>>> = requests.iter().map(|_| Ok(None)).collect();
+        let mut settlements: Vec<(Bet, f64)> = Vec::new();
+        let mut positions = Vec::new();
+
+        // Bets on a stream under an emergency stop wait for it to be lifted
+        let stream_ids: Vec<String> = requests.iter()
+            .filter_map(|(bet_id, _, _)| self.active_bets.get(*bet_id).map(|bet| bet.stream_id.clone()))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let stopped = match self.repository.emergency_stopped(&stream_ids).await {
+            Ok(stopped) => stopped,
+            Err(e) => {
+                let reason = format!("{:#}", e);
+                return outcomes.into_iter().map(|_| Err(anyhow::anyhow!("{}", reason))).collect();
+            }
+        };
+
+        for (position, (bet_id, actual_result, confidence_score)) in requests.into_iter().enumerate() {
+            // Copied out, so no map lock is held while the settlements commit
+            let Some(bet) = self.active_bets.get(bet_id).map(|bet| bet.clone()) else {
+                continue;
+            };
+            if stopped.contains(&bet.stream_id) {
+                info!("Bet {} not settled: stream {} is under an emergency stop", bet.id, bet.stream_id);
+                continue;
+            }
+            // A bet queued twice in one batch is settled the first time only
+            if !bet.can_resolve() || settlements.iter().any(|(queued, _)| queued.id == bet.id) {
+                continue;
+            }
+
+            let mut settled = bet;
+            let won = settled.settle(actual_result, confidence_score);
+            let payout_amount = if won { settled.potential_payout } else { 0.0 };
+            settlements.push((settled, payout_amount));
+            positions.push(position);
+        }
+        if settlements.is_empty() {
+            return outcomes;
+        }
+
+        // The bets and the balances they pay into are settled in one transaction
+        let balances = match self.repository.settle_bets(&settlements).await {
+            Ok(balances) => balances,
+            Err(e) => {
+                // Nothing was committed, so every bet in the batch is still open
+                let reason = format!("{:#}", e);
+                for (position, (bet, _)) in positions.into_iter().zip(&settlements) {
+                    error_reporting::report(&e, ReportContext {
+                        stream_id: Some(&bet.stream_id),
+                        bet_id: Some(&bet.id),
+                        user_id: Some(&bet.user_id),
+                    });
+                    outcomes[position] = Err(anyhow::anyhow!("{}", reason));
+                }
+                return outcomes;
+            }
+        };
+
+        for ((position, (settled, payout_amount)), balance) in positions.into_iter().zip(settlements).zip(balances) {
+            self.active_bets.remove(&settled.id);
+            // None means settled, cancelled or deleted elsewhere; the copy here was stale
+            let Some(balance) = balance else {
+                continue;
+            };
+            self.user_balances.insert(balance);
+
+            let won = settled.resolution_result.as_ref().map(|resolution| resolution.won).unwrap_or(false);
+            info!("Resolved bet {} - Won: {}, Payout: ${:.2}", settled.id, won, payout_amount);
+            outcomes[position] = Ok(Some(settled));
+        }
+        outcomes
+    }
+}
+
+// Settles one shard's bets in the order they were queued; on shutdown, those already queued are settled first.
+// Whatever is waiting when the worker gets to it is settled together, so a burst costs a few commits, not one each
+fn spawn_settlement_worker(settler: Settler, mut jobs: mpsc::Receiver<SettlementJob>, batch_size: usize, shutdown: &Shutdown) {
+    let stopping = shutdown.clone();
+    shutdown.spawn_tracked("betting:settlement_worker", async move {
+        loop {
+            let job = tokio::select! {
+                job = jobs.recv() => job,
+                _ = stopping.triggered() => {
+                    jobs.close();
+                    jobs.recv().await
+                }
+            };
+            let Some(job) = job else {
+                return;
+            };
+            let mut batch = vec![job];
+            while batch.len() < batch_size {
+                match jobs.try_recv() {
+                    Ok(job) => batch.push(job),
+                    Err(_) => break,
+                }
+            }
+
+            // A transaction writes as one tenant and actor, so a batch splits where they change
+            while !batch.is_empty() {
+                let split = batch.iter()
+                    .position(|job| job.tenant != batch[0].tenant || job.actor != batch[0].actor)
+                    .unwrap_or(batch.len());
+                let rest = batch.split_off(split);
+                let (tenant, actor) = (batch[0].tenant.clone(), batch[0].actor.clone());
+                let settle = settler.settle_batch(std::mem::replace(&mut batch, rest));
+                match (tenant, actor) {
+                    (Some(tenant), Some(actor)) => tenant::scope(tenant, audit::scope(actor, settle)).await,
+                    (Some(tenant), None) => tenant::scope(tenant, settle).await,
+                    (None, Some(actor)) => audit::scope(actor, settle).await,
+                    (None, None) => settle.await,
+                }
+            }
+        }
+    });
+}
+
+// Writes out the balances changed since the last flush, one pipeline per tenant and
+// batch; those whose write fails are marked changed again. Returns how many were written and how many failed
+async fn flush_balances(user_balances: &ShardedBalances, state_manager: &StateManager, batch_size: usize) -> (usize, usize) {
+    let mut by_tenant: HashMap<Option<String>, Vec<(Option<Tenant>, UserBalance)>> = HashMap::new();
+    for (tenant, balance) in user_balances.take_dirty() {
+        by_tenant.entry(tenant.as_ref().map(|tenant| tenant.id.clone())).or_default().push((tenant, balance));
+    }
+
+    let (mut flushed, mut failed) = (0, 0);
+    for balances in by_tenant.into_values() {
+        for batch in balances.chunks(batch_size) {
+            let write = write_balances(state_manager, batch);
+            let written = match batch[0].0.clone() {
+                Some(tenant) => tenant::scope(tenant, write).await,
+                None => write.await,
+            };
+            match written {
+                Ok(()) => flushed += batch.len(),
+                Err(e) => {
+                    warn!("Failed to write {} balances to Redis: {}", batch.len(), e);
+                    user_balances.restore_dirty(batch.to_vec());
+                    failed += batch.len();
+                }
+            }
+        }
+    }
+    (flushed, failed)
+}
+
+async fn write_balances(state_manager: &StateManager, balances: &[(Option<Tenant>, UserBalance)]) -> Result<()> {
+    let mut entries = Vec::with_capacity(balances.len());
+    for (_, balance) in balances {
+        entries.push((format!("balance:{}:{}", balance.user_id, balance.stream_id), serde_json::to_string(balance)?));
+    }
+    // An hour after its last change a balance drops out of Redis; Postgres still has it
+    state_manager.set_keys_with_expiry(&entries, 3600).await
+}
@@ -0,0 +1,121 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// Implied probabilities are grouped into this many equal bands, 0-10% up to 90-100%
+pub const PROBABILITY_BANDS: i32 = 10;
+
+/// Bets whose odds implied a chance of winning within one band, and how
+/// often they actually won.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProbabilityBand {
+    pub lower: f64,
+    pub upper: f64,
+    pub settled_bets: u64,
+    pub stakes: f64,
+    pub payouts: f64,
+    // Mean of 1 / odds
+    pub implied_probability: f64,
+    pub actual_win_rate: f64,
+}
+
+/// How one market type's settled bets paid out against the odds they were
+/// placed at.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MarketFairness {
+    // As `bets.bet_type` stores it
+    pub bet_type: String,
+    pub settled_bets: u64,
+    pub stakes: f64,
+    pub payouts: f64,
+    // (stakes - payouts) / stakes
+    pub realized_margin: f64,
+    pub implied_probability: f64,
+    pub actual_win_rate: f64,
+    // Only bands that had bets, lowest first
+    pub bands: Vec<ProbabilityBand>,
+}
+
+/// Realized margin and implied-vs-actual win probability for bets settled
+/// in a range, per market type.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FairnessReport {
+    // Bets settled from `from` up to, not including, `to`
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub generated_at: DateTime<Utc>,
+    pub markets: Vec<MarketFairness>,
+}
+
+/// One market type's settled bets within one probability band, as
+/// `BettingRepository::fairness_report` totals them.
+pub struct SettledBand {
+    pub bet_type: String,
+    // 0 for the lowest band
+    pub band: i32,
+    pub settled_bets: i64,
+    pub wins: i64,
+    pub stakes: f64,
+    pub payouts: f64,
+    // Sum of 1 / odds
+    pub implied: f64,
+}
+
+/// Builds the fairness report from its bands, ordered by market type then band.
+pub fn report(from: DateTime<Utc>, to: DateTime<Utc>, bands: Vec<SettledBand>) -> FairnessReport {
+    let mut markets: Vec<MarketFairness> = Vec::new();
+    // Running sums of 1 / odds and wins per market, turned into rates at the end
+    let mut totals: Vec<(f64, i64)> = Vec::new();
+    for SettledBand { bet_type, band, settled_bets: settled, wins, stakes, payouts, implied } in bands {
+        if markets.last().map(|market| market.bet_type != bet_type).unwrap_or(true) {
+            markets.push(MarketFairness {
+                bet_type,
+                settled_bets: 0,
+                stakes: 0.0,
+                payouts: 0.0,
+                realized_margin: 0.0,
+                implied_probability: 0.0,
+                actual_win_rate: 0.0,
+                bands: Vec::new(),
+            });
+            totals.push((0.0, 0));
+        }
+        let (Some(market), Some(total)) = (markets.last_mut(), totals.last_mut()) else {
+            continue;
+        };
+        market.settled_bets += settled as u64;
+        market.stakes += stakes;
+        market.payouts += payouts;
+        total.0 += implied;
+        total.1 += wins;
+
+        let band = band.clamp(0, PROBABILITY_BANDS - 1) as f64;
+        market.bands.push(ProbabilityBand {
+            lower: band / PROBABILITY_BANDS as f64,
+            upper: (band + 1.0) / PROBABILITY_BANDS as f64,
+            settled_bets: settled as u64,
+            stakes,
+            payouts,
+            implied_probability: implied / settled as f64,
+            actual_win_rate: wins as f64 / settled as f64,
+        });
+    }
+
+    for (market, (implied, wins)) in markets.iter_mut().zip(totals) {
+        let settled = market.settled_bets as f64;
+        market.implied_probability = implied / settled;
+        market.actual_win_rate = wins as f64 / settled;
+        market.realized_margin = realized_margin(market.stakes, market.payouts);
+    }
+
+    FairnessReport { from, to, generated_at: Utc::now(), markets }
+}
+
+/// What the house kept of every unit staked; 0 with nothing staked.
+pub fn realized_margin(stakes: f64, payouts: f64) -> f64 {
+    if stakes > 0.0 {
+        (stakes - payouts) / stakes
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Executor, Postgres};
+use utoipa::ToSchema;
+
+use super::BetStatus;
+
+/// An admin's override of `[betting] market_liquidity` for one stream.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LiquidityCap {
+    pub stream_id: String,
+    // 0 means no limit
+    pub max_liability: f64,
+    pub updated_by: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// How much more a stream's markets can take on.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MarketLiquidity {
+    pub stream_id: String,
+    // The most the house will owe if every open bet wins; None for no limit
+    pub max_liability: Option<f64>,
+    // Owed if every open bet wins, now
+    pub open_liability: f64,
+    // What's left under the cap; None for no limit
+    pub available: Option<f64>,
+    // Whether `max_liability` is the stream's own rather than the default
+    pub overridden: bool,
+}
+
+impl MarketLiquidity {
+    /// The largest stake at `odds` the market can still take, rounded down
+    /// to the cent; None for no limit.
+    pub fn matchable_stake(&self, odds: f64) -> Option<f64> {
+        self.available.map(|available| (available / odds * 100.0).floor().max(0.0) / 100.0)
+    }
+}
+
+/// `stream_id`'s liquidity, against its own cap if it has one and
+/// `default_cap` otherwise; a cap of 0 means no limit. Only a cap set in the
+/// stream's own tenant counts. Reads the current tenant's bets.
+pub async fn market_liquidity<'e, E>(executor: E, stream_id: &str, default_cap: f64) -> Result<MarketLiquidity, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            (SELECT market_liquidity_caps.max_liability::float8 FROM market_liquidity_caps
+             JOIN streams ON streams.id = market_liquidity_caps.stream_id
+                AND streams.tenant_id = market_liquidity_caps.tenant_id
+             WHERE market_liquidity_caps.stream_id = $1) AS cap,
+            (SELECT COALESCE(SUM(potential_payout), 0)::float8 FROM bets
+             WHERE stream_id = $1 AND status = $2 AND deleted_at IS NULL) AS "open_liability!"
+        "#,
+        stream_id,
+        BetStatus::Active.as_str(),
+    )
+    .fetch_one(executor)
+    .await?;
+
+    let max_liability = Some(row.cap.unwrap_or(default_cap)).filter(|cap| *cap > 0.0);
+    Ok(MarketLiquidity {
+        stream_id: stream_id.to_string(),
+        max_liability,
+        open_liability: row.open_liability,
+        available: max_liability.map(|cap| (cap - row.open_liability).max(0.0)),
+        overridden: row.cap.is_some(),
+    })
+}
@@ -1,4 +1,9 @@
 pub mod engine;
+pub mod fairness;
+pub mod liquidity;
+pub mod repository;
+pub mod shards;
+pub mod suspension;
 pub mod types;
 
 pub use engine::BettingEngine;
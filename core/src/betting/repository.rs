@@ -0,0 +1,1119 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::sync::Arc;
+use sqlx::{Pool, Postgres, Transaction};
+
+use super::fairness;
+use super::liquidity;
+use super::suspension::{EmergencyStop, OpenBetPolicy};
+use super::types::*;
+use crate::outbox;
+use crate::replica::ReadRouter;
+use crate::webhooks::WebhookEventType;
+
+// Money columns are NUMERIC; the engine works in f64, so every query converts at the boundary.
+// Bets and balances are deleted by setting `deleted_at`, and every query here skips deleted rows.
+// The query macros check this SQL against the schema when compiling, from `.sqlx/` when
+// there's no database to ask; run `cargo sqlx prepare` after changing a query.
+
+/// A `user_balances` row as the queries below select it.
+struct BalanceRow {
+    user_id: String,
+    stream_id: String,
+    total_deposited: f64,
+    activation_cost: f64,
+    betting_balance: f64,
+    active_bets_total: f64,
+    total_winnings: f64,
+    total_losses: f64,
+    bet_count: i32,
+    created_at: DateTime<Utc>,
+    last_updated: DateTime<Utc>,
+}
+
+impl From<BalanceRow> for UserBalance {
+    fn from(row: BalanceRow) -> Self {
+        UserBalance {
+            user_id: row.user_id,
+            stream_id: row.stream_id,
+            total_deposited: row.total_deposited,
+            activation_cost: row.activation_cost,
+            betting_balance: row.betting_balance,
+            active_bets_total: row.active_bets_total,
+            total_winnings: row.total_winnings,
+            total_losses: row.total_losses,
+            bet_count: row.bet_count.max(0) as u32,
+            created_at: row.created_at,
+            last_updated: row.last_updated,
+        }
+    }
+}
+
+/// A `user_betting_stats` row as the queries below select it.
+struct StatsRow {
+    user_id: String,
+    bets_placed: i32,
+    total_staked: f64,
+    bets_settled: i32,
+    bets_won: i32,
+    settled_stake: f64,
+    total_returned: f64,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<StatsRow> for UserBettingStats {
+    fn from(row: StatsRow) -> Self {
+        let net_profit = row.total_returned - row.settled_stake;
+        UserBettingStats {
+            user_id: row.user_id,
+            bets_placed: row.bets_placed.max(0) as u32,
+            total_staked: row.total_staked,
+            bets_settled: row.bets_settled.max(0) as u32,
+            bets_won: row.bets_won.max(0) as u32,
+            settled_stake: row.settled_stake,
+            total_returned: row.total_returned,
+            net_profit,
+            win_rate: if row.bets_settled > 0 { row.bets_won as f64 / row.bets_settled as f64 } else { 0.0 },
+            roi: if row.settled_stake > 0.0 { net_profit / row.settled_stake } else { 0.0 },
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// A bet as the history query selects it, from either tier.
+struct BetRow {
+    id: String,
+    user_id: String,
+    stream_id: String,
+    bet_type: String,
+    stake_amount: f64,
+    prediction: serde_json::Value,
+    status: String,
+    created_at: DateTime<Utc>,
+    resolution_deadline: DateTime<Utc>,
+    resolution_result: Option<serde_json::Value>,
+    potential_payout: f64,
+    odds: f64,
+    location_verification_id: Option<String>,
+}
+
+impl TryFrom<BetRow> for Bet {
+    type Error = anyhow::Error;
+
+    fn try_from(row: BetRow) -> Result<Self> {
+        Ok(Bet {
+            bet_type: row.bet_type.parse().map_err(anyhow::Error::msg)?,
+            status: row.status.parse().map_err(anyhow::Error::msg)?,
+            prediction: serde_json::from_value(row.prediction)?,
+            resolution_result: row.resolution_result.map(serde_json::from_value).transpose()?,
+            id: row.id,
+            user_id: row.user_id,
+            stream_id: row.stream_id,
+            stake_amount: row.stake_amount,
+            created_at: row.created_at,
+            resolution_deadline: row.resolution_deadline,
+            potential_payout: row.potential_payout,
+            odds: row.odds,
+            location_verification_id: row.location_verification_id,
+        })
+    }
+}
+
+/// An emergency stop as market_suspensions stores it.
+struct EmergencyStopRow {
+    stream_id: String,
+    reason: String,
+    open_bets: Option<String>,
+    suspended_by: String,
+    suspended_at: DateTime<Utc>,
+}
+
+impl From<EmergencyStopRow> for EmergencyStop {
+    fn from(row: EmergencyStopRow) -> Self {
+        EmergencyStop {
+            // Only emergency rows are read here, and they always have a policy
+            open_bets: row.open_bets.and_then(|policy| policy.parse().ok()).unwrap_or(OpenBetPolicy::Freeze),
+            stream_id: row.stream_id,
+            reason: row.reason,
+            suspended_by: row.suspended_by,
+            suspended_at: row.suspended_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum LedgerEntryType {
+    BetPlaced,
+    BetSettled,
+    BetVoided,
+}
+
+impl LedgerEntryType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LedgerEntryType::BetPlaced => "bet_placed",
+            LedgerEntryType::BetSettled => "bet_settled",
+            LedgerEntryType::BetVoided => "bet_voided",
+        }
+    }
+}
+
+/// What became of a placement.
+pub enum Placement {
+    // The balance as committed
+    Placed(UserBalance),
+    // The stored balance can't cover the stake
+    InsufficientBalance,
+    // The stream's market can't take even the smallest stake
+    NoLiquidity,
+    // An emergency stop came down after the checks before placement, for this reason
+    EmergencyStopped(String),
+}
+
+/// All of the betting engine's SQL.
+pub struct BettingRepository {
+    db_pool: Pool<Postgres>,
+    // History reads, which may lag a little behind db_pool
+    reads: Arc<ReadRouter>,
+}
+
+impl BettingRepository {
+    pub fn new(db_pool: Pool<Postgres>, reads: Arc<ReadRouter>) -> Self {
+        Self { db_pool, reads }
+    }
+
+    pub fn db_pool(&self) -> &Pool<Postgres> {
+        &self.db_pool
+    }
+
+    pub fn reads(&self) -> &Arc<ReadRouter> {
+        &self.reads
+    }
+
+    /// Takes the stake from the balance, records the bet, its ledger entry and
+    /// the placement event, all in one transaction. On a stream whose open
+    /// bets are near its liquidity cap (`default_liquidity` unless an admin
+    /// set its own), `bet`'s stake and payout are first scaled down to what
+    /// the market can still take; only that much leaves the balance. Unless
+    /// the bet is placed, nothing is written.
+    pub async fn place_bet(&self, bet: &mut Bet, default_liquidity: f64, min_stake: f64) -> Result<Placement> {
+        let mut tx = self.db_pool.begin().await?;
+
+        // Shared among placements; void_stream_bets takes it alone, so a bet is either refused
+        // here or committed before the void reads the stream's open bets
+        let stop_lock = format!("emergency:{}", bet.stream_id);
+        sqlx::query!("SELECT pg_advisory_xact_lock_shared(hashtextextended($1, 0))", stop_lock)
+            .execute(&mut *tx)
+            .await?;
+        let stopped = sqlx::query_scalar!(
+            "SELECT reason FROM market_suspensions WHERE stream_id = $1 AND emergency",
+            bet.stream_id,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(reason) = stopped {
+            return Ok(Placement::EmergencyStopped(reason));
+        }
+
+        let mut market = liquidity::market_liquidity(&mut *tx, &bet.stream_id, default_liquidity).await?;
+        if market.max_liability.is_some() {
+            // Placements on a capped stream queue here, so two can't both take its last liquidity
+            let market_lock = format!("liquidity:{}", bet.stream_id);
+            sqlx::query!("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))", market_lock)
+                .execute(&mut *tx)
+                .await?;
+            market = liquidity::market_liquidity(&mut *tx, &bet.stream_id, default_liquidity).await?;
+        }
+        if let Some(matchable) = market.matchable_stake(bet.odds) {
+            if matchable < bet.stake_amount {
+                if matchable < min_stake {
+                    return Ok(Placement::NoLiquidity);
+                }
+                bet.stake_amount = matchable;
+                bet.potential_payout = matchable * bet.odds;
+            }
+        }
+
+        // The balance check and deduction are one statement, so concurrent placements can't overdraw
+        let balance = sqlx::query_as!(
+            BalanceRow,
+            r#"
+            UPDATE user_balances SET
+                betting_balance = betting_balance - $3::float8,
+                active_bets_total = active_bets_total + $3::float8,
+                bet_count = bet_count + 1,
+                last_updated = NOW()
+            WHERE user_id = $1 AND stream_id = $2 AND betting_balance >= $3::float8 AND deleted_at IS NULL
+            RETURNING
+                user_id, stream_id,
+                total_deposited::float8 AS "total_deposited!",
+                activation_cost::float8 AS "activation_cost!",
+                betting_balance::float8 AS "betting_balance!",
+                active_bets_total::float8 AS "active_bets_total!",
+                total_winnings::float8 AS "total_winnings!",
+                total_losses::float8 AS "total_losses!",
+                bet_count, created_at, last_updated
+            "#,
+            bet.user_id,
+            bet.stream_id,
+            bet.stake_amount,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(balance) = balance else {
+            return Ok(Placement::InsufficientBalance);
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO bets (
+                id, user_id, stream_id, bet_type, stake_amount, prediction,
+                status, created_at, resolution_deadline, potential_payout, odds, location_verification_id
+            ) VALUES ($1, $2, $3, $4, $5::float8, $6, $7, $8, $9, $10::float8, $11::float8, $12)
+            "#,
+            bet.id,
+            bet.user_id,
+            bet.stream_id,
+            bet.bet_type.as_str(),
+            bet.stake_amount,
+            serde_json::to_value(&bet.prediction)?,
+            bet.status.as_str(),
+            bet.created_at,
+            bet.resolution_deadline,
+            bet.potential_payout,
+            bet.odds,
+            bet.location_verification_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        insert_ledger_entry(&mut tx, bet, LedgerEntryType::BetPlaced, -bet.stake_amount, balance.betting_balance).await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO user_betting_stats (user_id, bets_placed, total_staked)
+            VALUES ($1, 1, $2::float8)
+            ON CONFLICT (tenant_id, user_id) DO UPDATE SET
+                bets_placed = user_betting_stats.bets_placed + 1,
+                total_staked = user_betting_stats.total_staked + EXCLUDED.total_staked,
+                updated_at = NOW()
+            "#,
+            bet.user_id,
+            bet.stake_amount,
+        )
+        .execute(&mut *tx)
+        .await?;
+        outbox::enqueue(&mut *tx, WebhookEventType::BetPlaced.as_str(), &serde_json::to_value(bet)?)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?;
+
+        tx.commit().await?;
+        Ok(Placement::Placed(balance.into()))
+    }
+
+    /// Marks an active bet settled and pays `payout` into its balance, with the
+    /// ledger entry and the settlement event, in one transaction. Returns the
+    /// balance as committed, or None if the bet was no longer active, in which
+    /// case nothing is written.
+    pub async fn settle_bet(&self, bet: &Bet, payout: f64) -> Result<Option<UserBalance>> {
+        let mut settled = self.settle_bets(&[(bet.clone(), payout)]).await?;
+        Ok(settled.pop().flatten())
+    }
+
+    /// Settles each bet as `settle_bet` does, all in one transaction, so a
+    /// burst of settlements costs one commit. Results are in the order given;
+    /// if any write fails, none of them are committed.
+    pub async fn settle_bets(&self, settlements: &[(Bet, f64)]) -> Result<Vec<Option<UserBalance>>> {
+        let mut tx = self.db_pool.begin().await?;
+
+        // Settlements of one bet from any instance queue here; whoever is second finds it no longer active.
+        // Taken in ID order up front, so two instances settling overlapping batches can't deadlock
+        let mut bet_ids: Vec<&str> = settlements.iter().map(|(bet, _)| bet.id.as_str()).collect();
+        bet_ids.sort_unstable();
+        bet_ids.dedup();
+        for bet_id in bet_ids {
+            sqlx::query!("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))", bet_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let mut balances = Vec::with_capacity(settlements.len());
+        for (bet, payout) in settlements {
+            balances.push(Self::settle_in(&mut tx, bet, *payout).await?);
+        }
+
+        tx.commit().await?;
+        Ok(balances)
+    }
+
+    // One bet's share of `settle_bets`, its advisory lock already held
+    async fn settle_in(tx: &mut Transaction<'_, Postgres>, bet: &Bet, payout: f64) -> Result<Option<UserBalance>> {
+        let resolution = bet.resolution_result.as_ref().map(serde_json::to_value).transpose()?;
+
+        let settled = sqlx::query!(
+            "UPDATE bets SET status = $1, resolution_result = $2 WHERE id = $3 AND status = $4 AND deleted_at IS NULL",
+            bet.status.as_str(),
+            resolution,
+            bet.id,
+            BetStatus::Active.as_str(),
+        )
+        .execute(&mut **tx)
+        .await?;
+        if settled.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        // Mirrors UserBalance::resolve_bet: a winning payout returns the stake with the winnings
+        let balance = sqlx::query_as!(
+            BalanceRow,
+            r#"
+            UPDATE user_balances SET
+                active_bets_total = active_bets_total - $3::float8,
+                betting_balance = betting_balance + CASE WHEN $4::float8 > $3::float8 THEN $4::float8 ELSE 0 END,
+                total_winnings = total_winnings + GREATEST($4::float8 - $3::float8, 0),
+                total_losses = total_losses + CASE WHEN $4::float8 > $3::float8 THEN 0 ELSE $3::float8 END,
+                last_updated = NOW()
+            WHERE user_id = $1 AND stream_id = $2 AND deleted_at IS NULL
+            RETURNING
+                user_id, stream_id,
+                total_deposited::float8 AS "total_deposited!",
+                activation_cost::float8 AS "activation_cost!",
+                betting_balance::float8 AS "betting_balance!",
+                active_bets_total::float8 AS "active_bets_total!",
+                total_winnings::float8 AS "total_winnings!",
+                total_losses::float8 AS "total_losses!",
+                bet_count, created_at, last_updated
+            "#,
+            bet.user_id,
+            bet.stream_id,
+            bet.stake_amount,
+            payout,
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        let credited = if payout > bet.stake_amount { payout } else { 0.0 };
+        insert_ledger_entry(tx, bet, LedgerEntryType::BetSettled, credited, balance.betting_balance).await?;
+        let won = bet.resolution_result.as_ref().map(|resolution| resolution.won).unwrap_or(false);
+        sqlx::query!(
+            r#"
+            INSERT INTO user_betting_stats (user_id, bets_settled, bets_won, settled_stake, total_returned)
+            VALUES ($1, 1, CASE WHEN $2 THEN 1 ELSE 0 END, $3::float8, $4::float8)
+            ON CONFLICT (tenant_id, user_id) DO UPDATE SET
+                bets_settled = user_betting_stats.bets_settled + 1,
+                bets_won = user_betting_stats.bets_won + EXCLUDED.bets_won,
+                settled_stake = user_betting_stats.settled_stake + EXCLUDED.settled_stake,
+                total_returned = user_betting_stats.total_returned + EXCLUDED.total_returned,
+                updated_at = NOW()
+            "#,
+            bet.user_id,
+            won,
+            bet.stake_amount,
+            credited,
+        )
+        .execute(&mut **tx)
+        .await?;
+        outbox::enqueue(&mut **tx, WebhookEventType::BetSettled.as_str(), &serde_json::to_value(bet)?)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?;
+
+        Ok(Some(balance.into()))
+    }
+
+    /// A bet still awaiting settlement, from the primary, whichever instance placed it.
+    pub async fn find_active_bet(&self, bet_id: &str) -> Result<Option<Bet>> {
+        let row = sqlx::query_as!(
+            BetRow,
+            r#"
+            SELECT
+                id, user_id, stream_id, bet_type, stake_amount::float8 AS "stake_amount!", prediction, status,
+                created_at, resolution_deadline, resolution_result,
+                potential_payout::float8 AS "potential_payout!", odds::float8 AS "odds!", location_verification_id
+            FROM bets
+            WHERE id = $1 AND status = $2 AND deleted_at IS NULL
+            "#,
+            bet_id,
+            BetStatus::Active.as_str(),
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+        row.map(Bet::try_from).transpose()
+    }
+
+    /// Whether the risk monitor froze the user pending review.
+    pub async fn is_frozen(&self, user_id: &str) -> Result<bool> {
+        let frozen = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM account_freezes WHERE user_id = $1 AND released_at IS NULL) AS "frozen!""#,
+            user_id,
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+        Ok(frozen)
+    }
+
+    /// Why betting on `stream_id` is suspended, if it is.
+    pub async fn market_suspension(&self, stream_id: &str) -> Result<Option<String>> {
+        let reason = sqlx::query_scalar!("SELECT reason FROM market_suspensions WHERE stream_id = $1", stream_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+        Ok(reason)
+    }
+
+    /// Suspends `stream_id`'s markets for `reason`, or updates the reason
+    /// given if they already are. Returns false, leaving it be, if an
+    /// emergency stop stands there instead. A stream that isn't stored is
+    /// recorded under `fallback_tenant`.
+    pub async fn suspend_market(
+        &self,
+        stream_id: &str,
+        reason: &str,
+        confidence: Option<f64>,
+        fallback_tenant: &str,
+    ) -> Result<bool> {
+        let stored = sqlx::query!(
+            r#"
+            INSERT INTO market_suspensions (stream_id, tenant_id, reason, confidence)
+            SELECT $1, COALESCE((SELECT tenant_id FROM streams WHERE id = $1), $4), $2, $3::float8
+            ON CONFLICT (tenant_id, stream_id) DO UPDATE SET reason = EXCLUDED.reason, confidence = EXCLUDED.confidence
+            WHERE NOT market_suspensions.emergency
+            "#,
+            stream_id,
+            reason,
+            confidence,
+            fallback_tenant,
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(stored.rows_affected() > 0)
+    }
+
+    /// Lifts the automatic suspension on `stream_id`. Returns false if there
+    /// wasn't one; an emergency stop is left for an admin.
+    pub async fn resume_market(&self, stream_id: &str) -> Result<bool> {
+        let lifted = sqlx::query!("DELETE FROM market_suspensions WHERE stream_id = $1 AND NOT emergency", stream_id)
+            .execute(&self.db_pool)
+            .await?;
+        Ok(lifted.rows_affected() > 0)
+    }
+
+    /// How much more `stream_id`'s market can take on, against its own cap
+    /// or `default_liquidity`.
+    pub async fn market_liquidity(&self, stream_id: &str, default_liquidity: f64) -> Result<liquidity::MarketLiquidity> {
+        Ok(liquidity::market_liquidity(&self.db_pool, stream_id, default_liquidity).await?)
+    }
+
+    /// The streams whose liquidity cap an admin has overridden, by stream ID.
+    pub async fn list_liquidity_caps(&self) -> Result<Vec<liquidity::LiquidityCap>> {
+        let caps = sqlx::query_as!(
+            liquidity::LiquidityCap,
+            r#"
+            SELECT stream_id, max_liability::float8 AS "max_liability!", updated_by, updated_at
+            FROM market_liquidity_caps ORDER BY stream_id
+            "#
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+        Ok(caps)
+    }
+
+    pub async fn find_liquidity_cap(&self, stream_id: &str) -> Result<Option<liquidity::LiquidityCap>> {
+        let cap = sqlx::query_as!(
+            liquidity::LiquidityCap,
+            r#"
+            SELECT stream_id, max_liability::float8 AS "max_liability!", updated_by, updated_at
+            FROM market_liquidity_caps WHERE stream_id = $1
+            "#,
+            stream_id,
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+        Ok(cap)
+    }
+
+    /// Overrides `stream_id`'s liquidity cap. Bets already open over it stay
+    /// open; new ones are scaled down until the market is back under it.
+    pub async fn set_liquidity_cap(&self, stream_id: &str, max_liability: f64) -> Result<liquidity::LiquidityCap> {
+        let cap = sqlx::query_as!(
+            liquidity::LiquidityCap,
+            r#"
+            INSERT INTO market_liquidity_caps (stream_id, max_liability)
+            VALUES ($1, $2::float8)
+            ON CONFLICT (tenant_id, stream_id) DO UPDATE SET
+                max_liability = EXCLUDED.max_liability,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = NOW()
+            RETURNING stream_id, max_liability::float8 AS "max_liability!", updated_by, updated_at
+            "#,
+            stream_id,
+            max_liability,
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+        Ok(cap)
+    }
+
+    /// Removes `stream_id`'s liquidity cap override, so the default applies
+    /// again. Returns the override removed, or None if there wasn't one.
+    pub async fn clear_liquidity_cap(&self, stream_id: &str) -> Result<Option<liquidity::LiquidityCap>> {
+        let cap = sqlx::query_as!(
+            liquidity::LiquidityCap,
+            r#"
+            DELETE FROM market_liquidity_caps WHERE stream_id = $1
+            RETURNING stream_id, max_liability::float8 AS "max_liability!", updated_by, updated_at
+            "#,
+            stream_id,
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+        Ok(cap)
+    }
+
+    /// Stops all betting on `stream_id`, replacing any suspension already in
+    /// force. A stream that isn't stored is recorded under `fallback_tenant`.
+    pub async fn emergency_stop(
+        &self,
+        stream_id: &str,
+        reason: &str,
+        open_bets: OpenBetPolicy,
+        fallback_tenant: &str,
+    ) -> Result<EmergencyStop> {
+        let stop = sqlx::query_as!(
+            EmergencyStopRow,
+            r#"
+            INSERT INTO market_suspensions (stream_id, tenant_id, reason, emergency, open_bets)
+            SELECT $1, COALESCE((SELECT tenant_id FROM streams WHERE id = $1), $4), $2, TRUE, $3
+            ON CONFLICT (tenant_id, stream_id) DO UPDATE SET
+                reason = EXCLUDED.reason,
+                confidence = NULL,
+                emergency = TRUE,
+                open_bets = EXCLUDED.open_bets,
+                suspended_by = COALESCE(morphine_current_actor(), 'system'),
+                suspended_at = NOW()
+            RETURNING stream_id, reason, open_bets, suspended_by, suspended_at
+            "#,
+            stream_id,
+            reason,
+            open_bets.as_str(),
+            fallback_tenant,
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+        Ok(stop.into())
+    }
+
+    /// The emergency stop on `stream_id`, if there is one.
+    pub async fn find_emergency_stop(&self, stream_id: &str) -> Result<Option<EmergencyStop>> {
+        let stop = sqlx::query_as!(
+            EmergencyStopRow,
+            r#"
+            SELECT stream_id, reason, open_bets, suspended_by, suspended_at
+            FROM market_suspensions WHERE stream_id = $1 AND emergency
+            "#,
+            stream_id,
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+        Ok(stop.map(EmergencyStop::from))
+    }
+
+    /// Lifts the emergency stop on `stream_id`. Returns the stop lifted, or
+    /// None if there wasn't one.
+    pub async fn lift_emergency_stop(&self, stream_id: &str) -> Result<Option<EmergencyStop>> {
+        let stop = sqlx::query_as!(
+            EmergencyStopRow,
+            r#"
+            DELETE FROM market_suspensions WHERE stream_id = $1 AND emergency
+            RETURNING stream_id, reason, open_bets, suspended_by, suspended_at
+            "#,
+            stream_id,
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+        Ok(stop.map(EmergencyStop::from))
+    }
+
+    /// Which of `stream_ids` are under an emergency stop, when none of their bets may be settled.
+    pub async fn emergency_stopped(&self, stream_ids: &[String]) -> Result<HashSet<String>> {
+        if stream_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let stopped = sqlx::query_scalar!(
+            "SELECT stream_id FROM market_suspensions WHERE emergency AND stream_id = ANY($1)",
+            stream_ids,
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+        Ok(stopped.into_iter().collect())
+    }
+
+    /// Cancels every active bet on `stream_id` and returns its stake to the
+    /// balance, with the ledger entry and the void event, in one
+    /// transaction. Returns the bets voided, now cancelled; one settled
+    /// meanwhile by another instance is left as it was. Placements on the
+    /// stream already under way finish first, and are voided with the rest.
+    pub async fn void_stream_bets(&self, stream_id: &str) -> Result<Vec<Bet>> {
+        let mut tx = self.db_pool.begin().await?;
+
+        // Waits out placements already past their emergency check; any after it see the stop
+        let stop_lock = format!("emergency:{}", stream_id);
+        sqlx::query!("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))", stop_lock)
+            .execute(&mut *tx)
+            .await?;
+
+        // In ID order, so the settlement locks below are taken in the order settle_bets takes them
+        let rows = sqlx::query_as!(
+            BetRow,
+            r#"
+            SELECT
+                id, user_id, stream_id, bet_type, stake_amount::float8 AS "stake_amount!", prediction, status,
+                created_at, resolution_deadline, resolution_result,
+                potential_payout::float8 AS "potential_payout!", odds::float8 AS "odds!", location_verification_id
+            FROM bets
+            WHERE stream_id = $1 AND status = $2 AND deleted_at IS NULL
+            ORDER BY id
+            "#,
+            stream_id,
+            BetStatus::Active.as_str(),
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut voided = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut bet = Bet::try_from(row)?;
+
+            // The lock settlement takes, so a bet is either settled or voided
+            sqlx::query!("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))", bet.id)
+                .execute(&mut *tx)
+                .await?;
+            let cancelled = sqlx::query!(
+                "UPDATE bets SET status = $1 WHERE id = $2 AND status = $3 AND deleted_at IS NULL",
+                BetStatus::Cancelled.as_str(),
+                bet.id,
+                BetStatus::Active.as_str(),
+            )
+            .execute(&mut *tx)
+            .await?;
+            if cancelled.rows_affected() == 0 {
+                continue;
+            }
+
+            let balance_after = sqlx::query_scalar!(
+                r#"
+                UPDATE user_balances SET
+                    betting_balance = betting_balance + $3::float8,
+                    active_bets_total = active_bets_total - $3::float8,
+                    last_updated = NOW()
+                WHERE user_id = $1 AND stream_id = $2 AND deleted_at IS NULL
+                RETURNING betting_balance::float8 AS "betting_balance!"
+                "#,
+                bet.user_id,
+                bet.stream_id,
+                bet.stake_amount,
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+
+            bet.status = BetStatus::Cancelled;
+            insert_ledger_entry(&mut tx, &bet, LedgerEntryType::BetVoided, bet.stake_amount, balance_after).await?;
+            outbox::enqueue(&mut *tx, WebhookEventType::BetVoided.as_str(), &serde_json::to_value(&bet)?)
+                .await
+                .map_err(|e| anyhow::anyhow!(e))?;
+            voided.push(bet);
+        }
+
+        tx.commit().await?;
+        Ok(voided)
+    }
+
+    pub async fn find_balance(&self, user_id: &str, stream_id: &str) -> Result<Option<UserBalance>> {
+        let row = sqlx::query_as!(
+            BalanceRow,
+            r#"
+            SELECT
+                user_id, stream_id,
+                total_deposited::float8 AS "total_deposited!",
+                activation_cost::float8 AS "activation_cost!",
+                betting_balance::float8 AS "betting_balance!",
+                active_bets_total::float8 AS "active_bets_total!",
+                total_winnings::float8 AS "total_winnings!",
+                total_losses::float8 AS "total_losses!",
+                bet_count, created_at, last_updated
+            FROM user_balances
+            WHERE user_id = $1 AND stream_id = $2 AND deleted_at IS NULL
+            "#,
+            user_id,
+            stream_id,
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+        Ok(row.map(UserBalance::from))
+    }
+
+    /// Stores a new balance and returns the stored one, which is another
+    /// instance's if that got there first.
+    pub async fn create_balance(&self, balance: &UserBalance) -> Result<UserBalance> {
+        sqlx::query!(
+            r#"
+            INSERT INTO user_balances (
+                user_id, stream_id, total_deposited, activation_cost, betting_balance,
+                active_bets_total, total_winnings, total_losses, bet_count, created_at, last_updated
+            ) VALUES ($1, $2, $3::float8, $4::float8, $5::float8, $6::float8, $7::float8, $8::float8, $9, $10, $11)
+            ON CONFLICT (user_id, stream_id) DO NOTHING
+            "#,
+            balance.user_id,
+            balance.stream_id,
+            balance.total_deposited,
+            balance.activation_cost,
+            balance.betting_balance,
+            balance.active_bets_total,
+            balance.total_winnings,
+            balance.total_losses,
+            balance.bet_count as i32,
+            balance.created_at,
+            balance.last_updated,
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        self.find_balance(&balance.user_id, &balance.stream_id).await?
+            .context("Balance missing right after it was stored")
+    }
+
+    /// Moves up to `batch_size` bets settled more than `older_than_days` ago
+    /// into `bets_archive`. Returns how many moved.
+    pub async fn archive_settled_bets(&self, older_than_days: u32, batch_size: u32) -> Result<u64> {
+        let moved = sqlx::query!(
+            r#"
+            WITH moved AS (
+                DELETE FROM bets
+                WHERE id IN (
+                    SELECT id FROM bets
+                    WHERE status <> $1 AND created_at < NOW() - make_interval(days => $2)
+                    ORDER BY created_at
+                    LIMIT $3
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING *
+            )
+            INSERT INTO bets_archive (
+                id, user_id, stream_id, bet_type, stake_amount, prediction, status,
+                created_at, resolution_deadline, resolution_result, potential_payout, odds, tenant_id,
+                deleted_at, created_by, updated_by, row_version, location_verification_id
+            )
+            SELECT
+                id, user_id, stream_id, bet_type, stake_amount, prediction, status,
+                created_at, resolution_deadline, resolution_result, potential_payout, odds, tenant_id,
+                deleted_at, created_by, updated_by, row_version, location_verification_id
+            FROM moved
+            "#,
+            BetStatus::Active.as_str(),
+            older_than_days as i32,
+            batch_size as i64,
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(moved.rows_affected())
+    }
+
+    /// A user's bets, live and archived alike, newest first unless `ascending`.
+    /// Returns the page and the total across both tiers. Read from a replica,
+    /// so a bet placed moments ago may be missing.
+    pub async fn bet_history(
+        &self,
+        user_id: &str,
+        stream_id: Option<&str>,
+        ascending: bool,
+        limit: usize,
+        offset: usize,
+    ) -> Result<(Vec<Bet>, usize)> {
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM bets
+                 WHERE user_id = $1 AND ($2::text IS NULL OR stream_id = $2) AND deleted_at IS NULL)
+                + (SELECT COUNT(*) FROM bets_archive
+                   WHERE user_id = $1 AND ($2::text IS NULL OR stream_id = $2) AND deleted_at IS NULL)
+                AS "total!"
+            "#,
+            user_id,
+            stream_id,
+        )
+        .fetch_one(self.reads.reader())
+        .await?;
+
+        let rows = sqlx::query_as!(
+            BetRow,
+            r#"
+            SELECT
+                id AS "id!", user_id AS "user_id!", stream_id AS "stream_id!", bet_type AS "bet_type!",
+                stake_amount AS "stake_amount!", prediction AS "prediction!", status AS "status!",
+                created_at AS "created_at!", resolution_deadline AS "resolution_deadline!",
+                resolution_result AS "resolution_result?", potential_payout AS "potential_payout!", odds AS "odds!",
+                location_verification_id AS "location_verification_id?"
+            FROM (
+                SELECT
+                    id, user_id, stream_id, bet_type, stake_amount::float8, prediction, status,
+                    created_at, resolution_deadline, resolution_result, potential_payout::float8, odds::float8,
+                    location_verification_id
+                FROM bets
+                WHERE user_id = $1 AND ($2::text IS NULL OR stream_id = $2) AND deleted_at IS NULL
+                UNION ALL
+                SELECT
+                    id, user_id, stream_id, bet_type, stake_amount::float8, prediction, status,
+                    created_at, resolution_deadline, resolution_result, potential_payout::float8, odds::float8,
+                    location_verification_id
+                FROM bets_archive
+                WHERE user_id = $1 AND ($2::text IS NULL OR stream_id = $2) AND deleted_at IS NULL
+            ) AS history
+            ORDER BY CASE WHEN $3 THEN created_at END ASC, created_at DESC
+            LIMIT $4 OFFSET $5
+            "#,
+            user_id,
+            stream_id,
+            ascending,
+            limit as i64,
+            offset as i64,
+        )
+        .fetch_all(self.reads.reader())
+        .await?;
+
+        let bets = rows.into_iter().map(Bet::try_from).collect::<Result<Vec<_>>>()?;
+        Ok((bets, total as usize))
+    }
+
+    /// Realized margin and implied-vs-actual win rates for bets settled from
+    /// `from` up to `to`, archived bets included. Cancelled and expired bets
+    /// were refunded, so they aren't counted. Reads the current tenant's bets.
+    pub async fn fairness_report(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<fairness::FairnessReport> {
+        // A bet is resolved after it's placed, so created_at narrows each scan to the index
+        let bands = sqlx::query_as!(
+            fairness::SettledBand,
+            r#"
+            WITH settled AS (
+                SELECT
+                    bet_type, stake_amount::float8 AS stake, odds::float8 AS odds,
+                    (resolution_result->>'won')::boolean AS won,
+                    COALESCE((resolution_result->>'payout_amount')::float8, 0) AS payout
+                FROM bets
+                WHERE resolution_result IS NOT NULL AND created_at < $2
+                    AND (resolution_result->>'resolved_at')::timestamptz >= $1
+                    AND (resolution_result->>'resolved_at')::timestamptz < $2
+                UNION ALL
+                SELECT
+                    bet_type, stake_amount::float8 AS stake, odds::float8 AS odds,
+                    (resolution_result->>'won')::boolean AS won,
+                    COALESCE((resolution_result->>'payout_amount')::float8, 0) AS payout
+                FROM bets_archive
+                WHERE resolution_result IS NOT NULL AND created_at < $2
+                    AND (resolution_result->>'resolved_at')::timestamptz >= $1
+                    AND (resolution_result->>'resolved_at')::timestamptz < $2
+            )
+            SELECT
+                bet_type AS "bet_type!",
+                LEAST(FLOOR($3::float8 / odds), $3::float8 - 1)::int4 AS "band!",
+                COUNT(*) AS "settled_bets!",
+                COUNT(*) FILTER (WHERE won) AS "wins!",
+                SUM(stake) AS "stakes!",
+                SUM(CASE WHEN won THEN payout ELSE 0 END) AS "payouts!",
+                SUM(1 / odds) AS "implied!"
+            FROM settled
+            WHERE won IS NOT NULL AND odds > 0
+            GROUP BY 1, 2
+            ORDER BY 1, 2
+            "#,
+            from,
+            to,
+            fairness::PROBABILITY_BANDS as f64,
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+        Ok(fairness::report(from, to, bands))
+    }
+
+    /// Every balance, every open bet and the ledger since `ledger_since` (all
+    /// of it if None), read from the primary in one repeatable-read
+    /// transaction so the three agree.
+    pub async fn financial_snapshot(&self, ledger_since: Option<DateTime<Utc>>) -> Result<FinancialSnapshot> {
+        let mut tx = self.db_pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(&mut *tx)
+            .await?;
+
+        // The first statement fixes the snapshot; NOW() is when the transaction began
+        let watermark = sqlx::query!(
+            r#"SELECT NOW() AS "taken_at!", (SELECT MAX(entry_id) FROM balance_ledger) AS last_entry_id"#
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let balances = sqlx::query_as!(
+            BalanceRow,
+            r#"
+            SELECT
+                user_id, stream_id,
+                total_deposited::float8 AS "total_deposited!",
+                activation_cost::float8 AS "activation_cost!",
+                betting_balance::float8 AS "betting_balance!",
+                active_bets_total::float8 AS "active_bets_total!",
+                total_winnings::float8 AS "total_winnings!",
+                total_losses::float8 AS "total_losses!",
+                bet_count, created_at, last_updated
+            FROM user_balances
+            WHERE deleted_at IS NULL
+            ORDER BY user_id, stream_id
+            "#
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let open_bets = sqlx::query_as!(
+            BetRow,
+            r#"
+            SELECT
+                id, user_id, stream_id, bet_type, stake_amount::float8 AS "stake_amount!", prediction, status,
+                created_at, resolution_deadline, resolution_result,
+                potential_payout::float8 AS "potential_payout!", odds::float8 AS "odds!", location_verification_id
+            FROM bets
+            WHERE status = $1 AND deleted_at IS NULL
+            ORDER BY created_at, id
+            "#,
+            BetStatus::Active.as_str(),
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let ledger = sqlx::query_as!(
+            LedgerEntry,
+            r#"
+            SELECT
+                entry_id, user_id, stream_id, bet_id, entry_type,
+                amount::float8 AS "amount!", balance_after::float8 AS "balance_after!", created_at
+            FROM balance_ledger
+            WHERE $1::timestamptz IS NULL OR created_at >= $1
+            ORDER BY entry_id
+            "#,
+            ledger_since,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        let balances: Vec<UserBalance> = balances.into_iter().map(UserBalance::from).collect();
+        let open_bets = open_bets.into_iter().map(Bet::try_from).collect::<Result<Vec<_>>>()?;
+        let totals = SnapshotTotals {
+            total_deposited: balances.iter().map(|b| b.total_deposited).sum(),
+            betting_balance: balances.iter().map(|b| b.betting_balance).sum(),
+            active_bets_total: balances.iter().map(|b| b.active_bets_total).sum(),
+            open_bets: open_bets.len(),
+            open_stake: open_bets.iter().map(|b| b.stake_amount).sum(),
+            open_exposure: open_bets.iter().map(|b| b.potential_payout).sum(),
+            ledger_entries: ledger.len(),
+            ledger_net: ledger.iter().map(|e| e.amount).sum(),
+        };
+
+        Ok(FinancialSnapshot {
+            tenant_id: crate::tenant::current().map(|tenant| tenant.id),
+            watermark: watermark.taken_at,
+            last_ledger_entry_id: watermark.last_entry_id,
+            ledger_since,
+            totals,
+            balances,
+            open_bets,
+            ledger,
+        })
+    }
+
+    /// A user's running totals, or None if they've never placed a bet. Read
+    /// from a replica, so the latest settlement may be missing.
+    pub async fn user_stats(&self, user_id: &str) -> Result<Option<UserBettingStats>> {
+        let row = sqlx::query_as!(
+            StatsRow,
+            r#"
+            SELECT
+                user_id, bets_placed, bets_settled, bets_won, updated_at,
+                total_staked::float8 AS "total_staked!",
+                settled_stake::float8 AS "settled_stake!",
+                total_returned::float8 AS "total_returned!"
+            FROM user_betting_stats
+            WHERE user_id = $1
+            "#,
+            user_id,
+        )
+        .fetch_optional(self.reads.reader())
+        .await?;
+        Ok(row.map(UserBettingStats::from))
+    }
+
+    /// Bettors ranked by `metric`, highest first, skipping those with fewer
+    /// than `min_settled` settled bets. Returns the page and the number ranked.
+    pub async fn leaderboard(
+        &self,
+        metric: LeaderboardMetric,
+        min_settled: u32,
+        limit: usize,
+        offset: usize,
+    ) -> Result<(Vec<UserBettingStats>, usize)> {
+        let total = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "total!" FROM user_betting_stats WHERE bets_settled >= $1"#,
+            min_settled as i32,
+        )
+        .fetch_one(self.reads.reader())
+        .await?;
+
+        // Ties are broken by user so pages stay stable between requests
+        let rows = sqlx::query_as!(
+            StatsRow,
+            r#"
+            SELECT
+                user_id, bets_placed, bets_settled, bets_won, updated_at,
+                total_staked::float8 AS "total_staked!",
+                settled_stake::float8 AS "settled_stake!",
+                total_returned::float8 AS "total_returned!"
+            FROM user_betting_stats
+            WHERE bets_settled >= $2
+            ORDER BY
+                CASE $1::text
+                    WHEN 'volume' THEN total_staked
+                    WHEN 'net_profit' THEN total_returned - settled_stake
+                    WHEN 'win_rate' THEN bets_won::numeric / NULLIF(bets_settled, 0)
+                    WHEN 'roi' THEN (total_returned - settled_stake) / NULLIF(settled_stake, 0)
+                END DESC NULLS LAST,
+                user_id
+            LIMIT $3 OFFSET $4
+            "#,
+            metric.as_str(),
+            min_settled as i32,
+            limit as i64,
+            offset as i64,
+        )
+        .fetch_all(self.reads.reader())
+        .await?;
+
+        Ok((rows.into_iter().map(UserBettingStats::from).collect(), total as usize))
+    }
+}
+
+async fn insert_ledger_entry(
+    tx: &mut Transaction<'_, Postgres>,
+    bet: &Bet,
+    entry_type: LedgerEntryType,
+    amount: f64,
+    balance_after: f64,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO balance_ledger (user_id, stream_id, bet_id, entry_type, amount, balance_after)
+        VALUES ($1, $2, $3, $4, $5::float8, $6::float8)
+        "#,
+        bet.user_id,
+        bet.stream_id,
+        bet.id,
+        entry_type.as_str(),
+        amount,
+        balance_after,
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use dashmap::DashMap;
+use tokio::sync::{oneshot, Notify};
+
+use super::types::{ActualResult, Bet, UserBalance};
+use crate::tenant::{self, Tenant};
+
+/// The shard a user's balances and settlements belong to. FNV-1a rather
+/// than the std hasher, so the mapping doesn't change between releases.
+pub fn shard_of(user_id: &str, shards: usize) -> usize {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in user_id.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    (hash % shards.max(1) as u64) as usize
+}
+
+/// Cached balances split by user, so a burst of settlements for one event
+/// spreads its locking over every shard instead of a single map. Each
+/// shard remembers which of its balances changed since they were last
+/// written to Redis, so only those are written.
+pub struct ShardedBalances {
+    // Each keyed by user_id:stream_id
+    shards: Vec<DashMap<String, UserBalance>>,
+    // Keyed like the shards, with the tenant each changed balance belongs to
+    dirty: Vec<DashMap<String, Option<Tenant>>>,
+    changed: Notify,
+}
+
+impl ShardedBalances {
+    pub fn new(shards: usize) -> Self {
+        let shards = shards.max(1);
+        Self {
+            shards: (0..shards).map(|_| DashMap::new()).collect(),
+            dirty: (0..shards).map(|_| DashMap::new()).collect(),
+            changed: Notify::new(),
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn shard(&self, index: usize) -> &DashMap<String, UserBalance> {
+        &self.shards[index]
+    }
+
+    fn shard_for(&self, user_id: &str) -> &DashMap<String, UserBalance> {
+        &self.shards[shard_of(user_id, self.shards.len())]
+    }
+
+    pub fn get(&self, user_id: &str, stream_id: &str) -> Option<UserBalance> {
+        self.shard_for(user_id).get(&balance_key(user_id, stream_id)).map(|entry| entry.clone())
+    }
+
+    /// Caches the balance and marks it for the next write to Redis, as the
+    /// current request's tenant.
+    pub fn insert(&self, balance: UserBalance) {
+        let index = shard_of(&balance.user_id, self.shards.len());
+        let key = balance_key(&balance.user_id, &balance.stream_id);
+        self.shards[index].insert(key.clone(), balance);
+        self.dirty[index].insert(key, tenant::current());
+        self.changed.notify_one();
+    }
+
+    /// Resolves once a balance has changed since the last call.
+    pub async fn changed(&self) {
+        self.changed.notified().await
+    }
+
+    /// The balances changed since they were last taken, each with its
+    /// tenant, clearing their marks.
+    pub fn take_dirty(&self) -> Vec<(Option<Tenant>, UserBalance)> {
+        let mut taken = Vec::new();
+        for (shard, dirty) in self.shards.iter().zip(&self.dirty) {
+            // Collected first so no shard lock is held while removing;
+            // unmarked before the balance is read, so a change in between is marked again
+            let keys: Vec<String> = dirty.iter().map(|entry| entry.key().clone()).collect();
+            for key in keys {
+                let Some((_, tenant)) = dirty.remove(&key) else {
+                    continue;
+                };
+                if let Some(balance) = shard.get(&key) {
+                    taken.push((tenant, balance.clone()));
+                }
+            }
+        }
+        taken
+    }
+
+    /// Marks balances taken for a write that failed, unless they've changed again since.
+    pub fn restore_dirty(&self, balances: Vec<(Option<Tenant>, UserBalance)>) {
+        for (tenant, balance) in balances {
+            let index = shard_of(&balance.user_id, self.shards.len());
+            self.dirty[index].entry(balance_key(&balance.user_id, &balance.stream_id)).or_insert(tenant);
+        }
+        self.changed.notify_one();
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(DashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(DashMap::is_empty)
+    }
+}
+
+fn balance_key(user_id: &str, stream_id: &str) -> String {
+    format!("{}:{}", user_id, stream_id)
+}
+
+/// A settlement queued for the worker of the bettor's shard.
+pub(super) struct SettlementJob {
+    // Who asked, so the worker writes as that tenant and actor
+    pub tenant: Option<Tenant>,
+    pub actor: Option<String>,
+    pub bet_id: String,
+    pub actual_result: ActualResult,
+    pub confidence_score: f64,
+    pub reply: oneshot::Sender<anyhow::Result<Option<Bet>>>,
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, Mutex};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use super::repository::BettingRepository;
+use crate::config::MarketSuspensionConfig;
+use crate::orchestrator::{DecisionType, MetacognitiveDecision};
+use crate::shutdown::Shutdown;
+use crate::tenant;
+
+/// Whether a stream's markets are taking bets, and why not if they aren't.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketStatus {
+    pub stream_id: String,
+    pub bettable: bool,
+    pub reason: Option<String>,
+}
+
+/// What an emergency stop does with the bets already open on the stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenBetPolicy {
+    // Cancelled, stakes refunded
+    Void,
+    // Left open, but not settled until the stop is lifted
+    Freeze,
+}
+
+impl OpenBetPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OpenBetPolicy::Void => "void",
+            OpenBetPolicy::Freeze => "freeze",
+        }
+    }
+
+}
+
+impl FromStr for OpenBetPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "void" => Ok(OpenBetPolicy::Void),
+            "freeze" => Ok(OpenBetPolicy::Freeze),
+            _ => Err(format!("unknown open bet policy '{}'; expected void or freeze", s)),
+        }
+    }
+}
+
+/// An admin's stop on a stream: no bets are taken or settled on it until
+/// an admin lifts it, whatever the analytics say.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct EmergencyStop {
+    pub stream_id: String,
+    pub reason: String,
+    pub open_bets: OpenBetPolicy,
+    pub suspended_by: String,
+    pub suspended_at: DateTime<Utc>,
+}
+
+struct Watch {
+    last_decision: Instant,
+    suspended: bool,
+    // Confident decisions in a row while suspended
+    confident_run: u32,
+}
+
+/// Suspends a stream's markets when the orchestrator's decisions on it lose
+/// confidence or stop arriving, as when the camera is blocked or detection
+/// fails, and resumes them once confident decisions are back. Suspensions
+/// live in Postgres, where placement checks them; changes go out to
+/// `subscribe`rs. Streams are watched on the instance that processes them.
+/// Emergency stops share the table but are left alone here: an automatic
+/// resume never lifts one.
+pub struct MarketSuspensions {
+    repository: BettingRepository,
+    config: MarketSuspensionConfig,
+    default_tenant: String,
+    watched: Mutex<HashMap<String, Watch>>,
+    changes: broadcast::Sender<MarketStatus>,
+    shutdown: Shutdown,
+}
+
+impl MarketSuspensions {
+    pub fn new(repository: BettingRepository, config: MarketSuspensionConfig, default_tenant: String, shutdown: Shutdown) -> Self {
+        Self {
+            repository,
+            config,
+            default_tenant,
+            watched: Mutex::new(HashMap::new()),
+            changes: broadcast::channel(256).0,
+            shutdown,
+        }
+    }
+
+    /// Streams whose markets were suspended or resumed, as it happens.
+    pub fn subscribe(&self) -> broadcast::Receiver<MarketStatus> {
+        self.changes.subscribe()
+    }
+
+    /// Whether `stream_id`'s markets are taking bets now.
+    pub async fn status(&self, stream_id: &str) -> anyhow::Result<MarketStatus> {
+        let reason = self.repository.market_suspension(stream_id).await?;
+        Ok(MarketStatus { stream_id: stream_id.to_string(), bettable: reason.is_none(), reason })
+    }
+
+    /// Follows `decisions` until shutdown, and sweeps for streams that have
+    /// gone quiet if that's configured.
+    pub fn start(self: &Arc<Self>, mut decisions: broadcast::Receiver<MetacognitiveDecision>) {
+        let suspensions = self.clone();
+        self.shutdown.spawn_loop("markets:suspension", async move {
+            loop {
+                match decisions.recv().await {
+                    Ok(decision) => suspensions.observe(&decision).await,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("Market suspension fell behind and skipped {} decisions", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
+        if self.config.stale_after_secs > 0 {
+            let suspensions = self.clone();
+            let stale_after = Duration::from_secs(self.config.stale_after_secs);
+            self.shutdown.spawn_loop("markets:stale_sweep", async move {
+                let mut ticks = tokio::time::interval((stale_after / 3).max(Duration::from_secs(1)));
+                loop {
+                    ticks.tick().await;
+                    suspensions.sweep(stale_after).await;
+                }
+            });
+        }
+        info!(
+            "Suspending markets below {:.2} confidence, resuming after {} decisions at {:.2} or above",
+            self.config.suspend_below_confidence, self.config.resume_after_decisions, self.config.resume_above_confidence
+        );
+    }
+
+    /// Stops all betting on `stream_id` at once, replacing any suspension
+    /// already in force. Open bets are voided or frozen by the betting
+    /// engine; this only records the stop and tells subscribers.
+    pub async fn emergency_stop(
+        &self,
+        stream_id: &str,
+        reason: &str,
+        open_bets: OpenBetPolicy,
+    ) -> anyhow::Result<EmergencyStop> {
+        let stop = self.repository.emergency_stop(stream_id, reason, open_bets, &self.fallback_tenant()).await?;
+
+        warn!("Emergency stop on stream {} by {}: {}", stream_id, stop.suspended_by, reason);
+        let _ = self.changes.send(MarketStatus {
+            stream_id: stream_id.to_string(),
+            bettable: false,
+            reason: Some(reason.to_string()),
+        });
+        Ok(stop)
+    }
+
+    /// The emergency stop on `stream_id`, if there is one.
+    pub async fn emergency(&self, stream_id: &str) -> anyhow::Result<Option<EmergencyStop>> {
+        self.repository.find_emergency_stop(stream_id).await
+    }
+
+    /// Lifts the emergency stop on `stream_id`, so it takes bets again and
+    /// frozen bets can be settled. Returns the stop lifted, or None if there
+    /// wasn't one. If the analytics are still poor, the stream is suspended
+    /// again on its next decision.
+    pub async fn lift_emergency(&self, stream_id: &str) -> anyhow::Result<Option<EmergencyStop>> {
+        let Some(stop) = self.repository.lift_emergency_stop(stream_id).await? else {
+            return Ok(None);
+        };
+
+        // Watched afresh from the next decision
+        self.watched.lock().await.remove(stream_id);
+        info!("Emergency stop on stream {} lifted", stream_id);
+        let _ = self.changes.send(MarketStatus { stream_id: stream_id.to_string(), bettable: true, reason: None });
+        Ok(Some(stop))
+    }
+
+    // The tenant a stream that isn't stored is recorded under: the caller's,
+    // outside a request the default one
+    fn fallback_tenant(&self) -> String {
+        tenant::current().map(|tenant| tenant.id).unwrap_or_else(|| self.default_tenant.clone())
+    }
+
+    async fn observe(&self, decision: &MetacognitiveDecision) {
+        // These are about a user's location or payment, not the stream
+        if matches!(decision.decision_type, DecisionType::LocationVerification | DecisionType::TransactionValidation) {
+            return;
+        }
+
+        let mut watched = self.watched.lock().await;
+        if !watched.contains_key(&decision.stream_id) {
+            // A suspension from before this instance took the stream on still stands
+            let suspended = match self.repository.market_suspension(&decision.stream_id).await {
+                Ok(reason) => reason.is_some(),
+                Err(e) => {
+                    warn!("Failed to look up the market suspension for stream {}: {}", decision.stream_id, e);
+                    return;
+                }
+            };
+            watched.insert(decision.stream_id.clone(), Watch { last_decision: Instant::now(), suspended, confident_run: 0 });
+        }
+        let Some(watch) = watched.get_mut(&decision.stream_id) else {
+            return;
+        };
+        watch.last_decision = Instant::now();
+
+        if decision.confidence < self.config.suspend_below_confidence {
+            watch.confident_run = 0;
+            if !watch.suspended {
+                let reason = format!(
+                    "analytics confidence {:.2} is below {:.2}",
+                    decision.confidence, self.config.suspend_below_confidence
+                );
+                watch.suspended = self.suspend(&decision.stream_id, &reason, Some(decision.confidence)).await;
+            }
+        } else if decision.confidence >= self.config.resume_above_confidence {
+            if watch.suspended {
+                watch.confident_run += 1;
+                if watch.confident_run >= self.config.resume_after_decisions {
+                    watch.suspended = !self.resume(&decision.stream_id).await;
+                    watch.confident_run = 0;
+                }
+            }
+        } else {
+            watch.confident_run = 0;
+        }
+    }
+
+    async fn sweep(&self, stale_after: Duration) {
+        let mut watched = self.watched.lock().await;
+        let stale: Vec<String> = watched.iter()
+            .filter(|(_, watch)| watch.last_decision.elapsed() >= stale_after)
+            .map(|(stream_id, _)| stream_id.clone())
+            .collect();
+
+        for stream_id in stale {
+            let already = watched.get(&stream_id).map(|watch| watch.suspended).unwrap_or(false);
+            let reason = format!("no analytics for {}s", stale_after.as_secs());
+            // Forgotten once suspended; decisions coming back start a new watch from the stored suspension
+            if already || self.suspend(&stream_id, &reason, None).await {
+                watched.remove(&stream_id);
+            }
+        }
+    }
+
+    // Whether the stream is suspended now; an emergency stop stands in for the suspension
+    async fn suspend(&self, stream_id: &str, reason: &str, confidence: Option<f64>) -> bool {
+        match self.repository.suspend_market(stream_id, reason, confidence, &self.fallback_tenant()).await {
+            Ok(false) => return true,
+            Ok(true) => {}
+            Err(e) => {
+                warn!("Failed to suspend markets on stream {}: {}", stream_id, e);
+                return false;
+            }
+        }
+        info!("Suspended markets on stream {}: {}", stream_id, reason);
+        let _ = self.changes.send(MarketStatus {
+            stream_id: stream_id.to_string(),
+            bettable: false,
+            reason: Some(reason.to_string()),
+        });
+        true
+    }
+
+    // Whether the suspension was lifted; an emergency stop is left for an admin
+    async fn resume(&self, stream_id: &str) -> bool {
+        match self.repository.resume_market(stream_id).await {
+            Ok(false) => return false,
+            Ok(true) => {}
+            Err(e) => {
+                warn!("Failed to resume markets on stream {}: {}", stream_id, e);
+                return false;
+            }
+        }
+        info!("Resumed markets on stream {}", stream_id);
+        let _ = self.changes.send(MarketStatus { stream_id: stream_id.to_string(), bettable: true, reason: None });
+        true
+    }
+}
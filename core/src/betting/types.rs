@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+use crate::validation::{Validate, ValidationErrors};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BetRequest {
@@ -12,7 +16,7 @@ pub struct BetRequest {
     pub time_window_seconds: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BetType {
     Binary,      // Yes/No predictions
     Quantity,    // Numeric predictions
@@ -28,6 +32,66 @@ pub enum Prediction {
     Pattern { sequence: Vec<String> },
 }
 
+impl BetType {
+    /// The name stored in `bets.bet_type`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BetType::Binary => "binary",
+            BetType::Quantity => "quantity",
+            BetType::Timing => "timing",
+            BetType::Pattern => "pattern",
+        }
+    }
+}
+
+impl FromStr for BetType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "binary" => Ok(BetType::Binary),
+            "quantity" => Ok(BetType::Quantity),
+            "timing" => Ok(BetType::Timing),
+            "pattern" => Ok(BetType::Pattern),
+            _ => Err(format!("unknown bet type '{}'; expected binary, quantity, timing or pattern", s)),
+        }
+    }
+}
+
+impl Prediction {
+    pub fn bet_type(&self) -> BetType {
+        match self {
+            Prediction::Binary { .. } => BetType::Binary,
+            Prediction::Quantity { .. } => BetType::Quantity,
+            Prediction::Timing { .. } => BetType::Timing,
+            Prediction::Pattern { .. } => BetType::Pattern,
+        }
+    }
+}
+
+impl Validate for Prediction {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        match self {
+            Prediction::Binary { .. } => {}
+            Prediction::Quantity { predicted_value, tolerance } => {
+                if !predicted_value.is_finite() {
+                    errors.add("predicted_value", "must be a finite number");
+                }
+                errors.require_range("tolerance", *tolerance, 0.0, f64::MAX);
+            }
+            Prediction::Timing { predicted_seconds, tolerance } => {
+                errors.require_range("predicted_seconds", *predicted_seconds, 0.0, f64::MAX);
+                errors.require_range("tolerance", *tolerance, 0.0, f64::MAX);
+            }
+            Prediction::Pattern { sequence } => {
+                if sequence.is_empty() || sequence.iter().any(|event| event.trim().is_empty()) {
+                    errors.add("sequence", "must be a non-empty list of event names");
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BetResult {
     pub bet_id: String,
@@ -35,6 +99,50 @@ pub struct BetResult {
     pub message: String,
     pub remaining_balance: f64,
     pub bet_details: Option<Bet>,
+    // Of the stake asked for, what was placed and what the market couldn't take and stayed in the balance
+    #[serde(default)]
+    pub matched_stake: f64,
+    #[serde(default)]
+    pub unmatched_stake: f64,
+}
+
+/// Which rule a bet on a slip would fall foul of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SlipCheck {
+    // The line itself is malformed
+    Invalid,
+    // The account can't bet at all, e.g. it's frozen
+    Account,
+    Balance,
+    StakeLimit,
+    Jurisdiction,
+    Market,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SlipProblem {
+    pub check: SlipCheck,
+    pub message: String,
+}
+
+/// How one line of a slip would fare if placed now, as the engine sees it.
+#[derive(Debug, Clone)]
+pub struct SlipLineCheck {
+    pub odds: f64,
+    // Less than the stake asked for when the market is near its liquidity cap
+    pub matched_stake: f64,
+    pub potential_payout: f64,
+    pub problems: Vec<SlipProblem>,
+}
+
+/// Bets on one stream still awaiting settlement.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpenBook {
+    pub open_bets: usize,
+    pub open_stake: f64,
+    // Total owed if every open bet wins
+    pub exposure: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,6 +159,9 @@ pub struct Bet {
     pub resolution_result: Option<BetResolution>,
     pub potential_payout: f64,
     pub odds: f64,
+    // The location verification that cleared the bettor; None when none was required
+    #[serde(default)]
+    pub location_verification_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +172,32 @@ pub enum BetStatus {
     Expired,
 }
 
+impl BetStatus {
+    /// The name stored in `bets.status`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BetStatus::Active => "active",
+            BetStatus::Resolved => "resolved",
+            BetStatus::Cancelled => "cancelled",
+            BetStatus::Expired => "expired",
+        }
+    }
+}
+
+impl FromStr for BetStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(BetStatus::Active),
+            "resolved" => Ok(BetStatus::Resolved),
+            "cancelled" => Ok(BetStatus::Cancelled),
+            "expired" => Ok(BetStatus::Expired),
+            _ => Err(format!("unknown bet status '{}'", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BetResolution {
     pub actual_result: ActualResult,
@@ -78,6 +215,22 @@ pub enum ActualResult {
     Pattern { actual_sequence: Vec<String> },
 }
 
+impl Validate for ActualResult {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        match self {
+            ActualResult::Binary { .. } | ActualResult::Pattern { .. } => {}
+            ActualResult::Quantity { actual_value } => {
+                if !actual_value.is_finite() {
+                    errors.add("actual_value", "must be a finite number");
+                }
+            }
+            ActualResult::Timing { actual_seconds } => {
+                errors.require_range("actual_seconds", *actual_seconds, 0.0, f64::MAX);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserBalance {
     pub user_id: String,
@@ -93,6 +246,108 @@ pub struct UserBalance {
     pub last_updated: DateTime<Utc>,
 }
 
+/// A `balance_ledger` row: one change to a betting balance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub entry_id: i64,
+    pub user_id: String,
+    pub stream_id: String,
+    pub bet_id: String,
+    pub entry_type: String,
+    pub amount: f64,
+    pub balance_after: f64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Balances, open bets and ledger entries as of one instant, read in a
+/// single transaction so they agree with each other.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FinancialSnapshot {
+    pub tenant_id: Option<String>,
+    // The snapshot holds everything committed before this, and nothing after
+    pub watermark: DateTime<Utc>,
+    // The newest ledger entry included; the next export can start after it
+    pub last_ledger_entry_id: Option<i64>,
+    // Ledger entries are those from here on; None means the whole ledger
+    pub ledger_since: Option<DateTime<Utc>>,
+    pub totals: SnapshotTotals,
+    pub balances: Vec<UserBalance>,
+    pub open_bets: Vec<Bet>,
+    pub ledger: Vec<LedgerEntry>,
+}
+
+/// Sums over a snapshot, to reconcile against without adding up every row.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotTotals {
+    pub total_deposited: f64,
+    pub betting_balance: f64,
+    pub active_bets_total: f64,
+    pub open_bets: usize,
+    pub open_stake: f64,
+    // Owed if every open bet wins
+    pub open_exposure: f64,
+    pub ledger_entries: usize,
+    pub ledger_net: f64,
+}
+
+/// A bettor's running totals across every stream, kept by placement and settlement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserBettingStats {
+    pub user_id: String,
+    pub bets_placed: u32,
+    pub total_staked: f64,
+    pub bets_settled: u32,
+    pub bets_won: u32,
+    // Stake on settled bets, which profit and ROI are measured against
+    pub settled_stake: f64,
+    pub total_returned: f64,
+    pub net_profit: f64,
+    // Won over settled bets, and net profit over settled stake; 0 until something settles
+    pub win_rate: f64,
+    pub roi: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What a leaderboard ranks bettors by, highest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardMetric {
+    Volume,
+    NetProfit,
+    WinRate,
+    Roi,
+}
+
+impl LeaderboardMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LeaderboardMetric::Volume => "volume",
+            LeaderboardMetric::NetProfit => "net_profit",
+            LeaderboardMetric::WinRate => "win_rate",
+            LeaderboardMetric::Roi => "roi",
+        }
+    }
+
+    /// Rates over a handful of bets say little, so they rank only bettors with a track record.
+    pub fn needs_track_record(&self) -> bool {
+        matches!(self, LeaderboardMetric::WinRate | LeaderboardMetric::Roi)
+    }
+}
+
+impl FromStr for LeaderboardMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "volume" => Ok(LeaderboardMetric::Volume),
+            "net_profit" => Ok(LeaderboardMetric::NetProfit),
+            "win_rate" => Ok(LeaderboardMetric::WinRate),
+            "roi" => Ok(LeaderboardMetric::Roi),
+            _ => Err(format!("unknown leaderboard metric '{}'", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BetEvent {
     pub id: String,
@@ -141,6 +396,7 @@ impl Bet {
             resolution_result: None,
             potential_payout: stake_amount * odds,
             odds,
+            location_verification_id: None,
         }
     }
 
@@ -151,6 +407,48 @@ impl Bet {
     pub fn can_resolve(&self) -> bool {
         matches!(self.status, BetStatus::Active) && !self.is_expired()
     }
+
+    /// Resolves the bet against what actually happened and returns whether
+    /// it won. Only the decision; paying it out is the repository's job.
+    pub fn settle(&mut self, actual_result: ActualResult, confidence_score: f64) -> bool {
+        let won = bet_won(&self.prediction, &actual_result);
+        self.resolution_result = Some(BetResolution {
+            actual_result,
+            won,
+            payout_amount: if won { self.potential_payout } else { 0.0 },
+            resolved_at: Utc::now(),
+            confidence_score,
+        });
+        self.status = BetStatus::Resolved;
+        won
+    }
+}
+
+fn bet_won(prediction: &Prediction, actual: &ActualResult) -> bool {
+    match (prediction, actual) {
+        (Prediction::Binary { will_occur }, ActualResult::Binary { occurred }) => {
+            will_occur == occurred
+        }
+        (
+            Prediction::Quantity { predicted_value, tolerance },
+            ActualResult::Quantity { actual_value }
+        ) => {
+            (predicted_value - actual_value).abs() <= *tolerance
+        }
+        (
+            Prediction::Timing { predicted_seconds, tolerance },
+            ActualResult::Timing { actual_seconds }
+        ) => {
+            (predicted_seconds - actual_seconds).abs() <= *tolerance
+        }
+        (
+            Prediction::Pattern { sequence },
+            ActualResult::Pattern { actual_sequence }
+        ) => {
+            sequence == actual_sequence
+        }
+        _ => false,
+    }
 }
 
 impl UserBalance {
@@ -0,0 +1,227 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use anyhow::Result;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::analytics_client::AnalyticsClient;
+use crate::betting::BettingEngine;
+use crate::cli;
+use crate::config::Config;
+use crate::features::FeatureFlags;
+use crate::geolocation::{clearance::LocationClearances, zones::ExclusionZoneStore, GeolocationService};
+use crate::orchestrator::MetacognitiveOrchestrator;
+use crate::reasoning::HybridReasoningEngine;
+use crate::reload::ConfigReloader;
+use crate::replica::ReadRouter;
+use crate::secrets::SecretStore;
+use crate::shutdown::Shutdown;
+use crate::state::StateManager;
+use crate::stream::{quotas::StreamQuotas, StreamManager};
+use crate::tenant;
+
+/// Connects and starts the domain services from a [`Config`]: state,
+/// streams, betting, geolocation and the orchestrator, in the order they
+/// depend on each other. The `morphine` server builds on what this returns;
+/// an embedder can use the services directly.
+///
+/// ```no_run
+/// # async fn run() -> anyhow::Result<()> {
+/// use morphine_core::{config::Config, MorphineBuilder};
+///
+/// let morphine = MorphineBuilder::new(Config::load(None)?).build().await?;
+/// let balance = morphine.betting_engine.get_user_balance("user_123", "stream_456").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MorphineBuilder {
+    config: Config,
+    config_path: Option<PathBuf>,
+    shutdown: Option<Shutdown>,
+    migrate: bool,
+    require_migrated: bool,
+}
+
+/// The services [`MorphineBuilder`] started. Their background work stops
+/// when `shutdown` is triggered.
+pub struct Morphine {
+    // With secrets resolved
+    pub config: Config,
+    pub shutdown: Shutdown,
+    pub config_reloader: Arc<ConfigReloader>,
+    pub secrets: Arc<SecretStore>,
+    // Scoped to the current tenant, see `tenant::connect`
+    pub db_pool: PgPool,
+    pub reads: Arc<ReadRouter>,
+    pub state_manager: Arc<StateManager>,
+    pub stream_manager: Arc<StreamManager>,
+    pub feature_flags: Arc<FeatureFlags>,
+    pub betting_engine: Arc<BettingEngine>,
+    pub metacognitive_orchestrator: Arc<MetacognitiveOrchestrator>,
+    pub analytics_client: Arc<AnalyticsClient>,
+    pub geolocation_service: Arc<GeolocationService>,
+    // Recorded as locations are verified, required by the betting engine
+    pub location_clearances: Arc<LocationClearances>,
+    pub exclusion_zones: Arc<ExclusionZoneStore>,
+    pub reasoning_engine: Arc<HybridReasoningEngine>,
+}
+
+impl MorphineBuilder {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            config_path: None,
+            shutdown: None,
+            migrate: false,
+            require_migrated: false,
+        }
+    }
+
+    /// The file `config` was loaded from, re-read when the configuration is reloaded.
+    pub fn config_path(mut self, path: Option<PathBuf>) -> Self {
+        self.config_path = path;
+        self
+    }
+
+    /// Ties the services' background work to `shutdown` rather than a signal of their own.
+    pub fn shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Applies pending migrations before connecting.
+    pub fn migrate(mut self, migrate: bool) -> Self {
+        self.migrate = migrate;
+        self
+    }
+
+    /// Refuses to start with pending migrations instead of warning.
+    pub fn require_migrated(mut self, require_migrated: bool) -> Self {
+        self.require_migrated = require_migrated;
+        self
+    }
+
+    pub async fn build(self) -> Result<Morphine> {
+        let mut config = self.config;
+        let shutdown = self.shutdown.unwrap_or_else(Shutdown::new);
+
+        // Safe-to-change settings reach their modules through watch channels
+        let config_reloader = Arc::new(ConfigReloader::new(self.config_path, &config));
+
+        // Settings that name a secret are resolved before anything connects
+        let secrets = Arc::new(SecretStore::new(&config.secrets).map_err(|e| anyhow::anyhow!(e))?);
+        secrets.resolve_config(&mut config).await.map_err(|e| anyhow::anyhow!(e))?;
+        if secrets.is_enabled() {
+            info!("Resolved secrets from the {:?} provider", config.secrets.provider);
+        }
+
+        // Each request's queries see only its tenant's rows
+        if self.migrate {
+            cli::migrate(&config, false).await?;
+        }
+        let db_pool = tenant::connect(&config.database_url, &config.database_pool).await?;
+        cli::check_schema(&db_pool, self.require_migrated).await?;
+
+        // Heavy reads go to replicas when there are any fresh enough
+        let reads = Arc::new(
+            ReadRouter::connect(db_pool.clone(), config.read_replicas.clone(), &config.database_pool)
+                .await
+                .map_err(|e| anyhow::anyhow!(e))?
+        );
+        reads.start(&shutdown);
+
+        let state_manager = Arc::new(StateManager::new(&config.redis_url, &config.redis_pool).await?);
+        info!("Connected to Redis state store");
+
+        let stream_manager = Arc::new(StreamManager::new(
+            state_manager.clone(),
+            db_pool.clone(),
+            config.tenancy.default_tenant.clone(),
+            config.region.id.clone(),
+            Arc::new(StreamQuotas::new(
+                db_pool.clone(),
+                config.stream_quotas.clone(),
+                config.max_concurrent_streams,
+                config.tenancy.default_tenant.clone(),
+            )),
+        ).await?);
+        info!("Stream manager initialized");
+
+        // Consulted by the engines below, so loaded before them
+        let feature_flags = Arc::new(FeatureFlags::new(
+            db_pool.clone(),
+            config.feature_flags.clone(),
+            shutdown.clone(),
+        ));
+        feature_flags.start().await.map_err(|e| anyhow::anyhow!(e))?;
+
+        // The betting engine refuses bettors inside these zones, so it needs the service
+        let analytics_client = Arc::new(
+            AnalyticsClient::new(&config.analytics_service_url, config.analytics_client.clone()).map_err(|e| anyhow::anyhow!(e))?,
+        );
+        let geolocation_service = Arc::new(
+            GeolocationService::new(config.precision_timing_enabled, &config.geolocation, analytics_client.clone()).await,
+        );
+        let exclusion_zones = Arc::new(ExclusionZoneStore::new(db_pool.clone()));
+        for zone in exclusion_zones.load_current().await.map_err(|e| anyhow::anyhow!(e))? {
+            geolocation_service.add_exclusion_zone(zone).await;
+        }
+        info!("Loaded {} geolocation exclusion zones", geolocation_service.exclusion_zone_count().await);
+
+        let location_clearances = Arc::new(LocationClearances::new(
+            state_manager.clone(),
+            config.geolocation.bet_verification_max_age_secs,
+        ));
+        let betting_engine = Arc::new(BettingEngine::new(
+            state_manager.clone(),
+            &config.database_url,
+            &config.database_pool,
+            config_reloader.betting(),
+            feature_flags.clone(),
+            config.bet_archive.clone(),
+            config.settlement.clone(),
+            reads.clone(),
+            location_clearances.clone(),
+            stream_manager.clone(),
+            geolocation_service.clone(),
+            shutdown.clone(),
+        ).await?);
+        info!("Betting engine initialized");
+
+        let metacognitive_orchestrator = Arc::new(MetacognitiveOrchestrator::new(
+            config.orchestrator.clone(),
+            config_reloader.dreaming(),
+            db_pool.clone(),
+            reads.clone(),
+            shutdown.clone(),
+        ).await);
+        info!("Metacognitive orchestrator started");
+
+        let reasoning_engine = Arc::new(
+            HybridReasoningEngine::new(config_reloader.reasoning(), db_pool.clone())
+                .await
+                .map_err(|e| anyhow::anyhow!(e))?
+        );
+        reasoning_engine.watch_config(&shutdown);
+        info!("Hybrid reasoning engine started");
+
+        Ok(Morphine {
+            config,
+            shutdown,
+            config_reloader,
+            secrets,
+            db_pool,
+            reads,
+            state_manager,
+            stream_manager,
+            feature_flags,
+            betting_engine,
+            metacognitive_orchestrator,
+            analytics_client,
+            geolocation_service,
+            location_clearances,
+            exclusion_zones,
+            reasoning_engine,
+        })
+    }
+}
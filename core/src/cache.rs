@@ -0,0 +1,297 @@
+use parking_lot::Mutex;
+use prometheus::{Encoder, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+type MetricsError = Box<dyn std::error::Error + Send + Sync>;
+
+/// An in-memory map that can't outgrow its limits: past `max_entries` the
+/// least recently used entry is evicted, and with a TTL an entry expires that
+/// long after it was last written. Safe to share; each call takes a short
+/// lock, so nothing is held across an await.
+pub struct BoundedCache<K, V> {
+    name: &'static str,
+    entries: Mutex<Entries<K, V>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evicted_full: AtomicU64,
+    evicted_expired: AtomicU64,
+}
+
+struct Entries<K, V> {
+    map: HashMap<K, Entry<V>>,
+    // Recency order: the lowest tick is the next to be evicted
+    order: BTreeMap<u64, K>,
+    tick: u64,
+    max_entries: usize,
+    ttl: Option<Duration>,
+}
+
+struct Entry<V> {
+    value: V,
+    tick: u64,
+    written_at: Instant,
+}
+
+/// A cache's size and counters as of when it was read, for `/metrics`.
+#[derive(Debug, Clone)]
+pub struct CacheStats {
+    pub name: &'static str,
+    pub entries: usize,
+    pub max_entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evicted_full: u64,
+    pub evicted_expired: u64,
+}
+
+impl<K, V> BoundedCache<K, V>
+where
+    K: Eq + Hash + Clone,
+{
+    /// `name` labels the cache's metrics. No TTL means entries only leave to make room.
+    pub fn new(name: &'static str, max_entries: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            name,
+            entries: Mutex::new(Entries {
+                map: HashMap::new(),
+                order: BTreeMap::new(),
+                tick: 0,
+                max_entries: max_entries.max(1),
+                ttl,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evicted_full: AtomicU64::new(0),
+            evicted_expired: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.update(key, |value| value.clone())
+    }
+
+    /// Runs `f` on the entry if there is a live one, counting as a use.
+    pub fn update<R>(&self, key: &K, f: impl FnOnce(&mut V) -> R) -> Option<R> {
+        let mut entries = self.entries.lock();
+        if entries.is_expired(key) {
+            entries.remove(key);
+            self.evicted_expired.fetch_add(1, Ordering::Relaxed);
+        }
+        let tick = entries.next_tick();
+        let Some(entry) = entries.map.get_mut(key) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        let previous = std::mem::replace(&mut entry.tick, tick);
+        let result = f(&mut entry.value);
+        entries.order.remove(&previous);
+        entries.order.insert(tick, key.clone());
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(result)
+    }
+
+    /// Runs `f` on the entry, first inserting `default()` if there's no live one.
+    /// Either way the entry counts as freshly written.
+    pub fn upsert<R>(&self, key: K, default: impl FnOnce() -> V, f: impl FnOnce(&mut V) -> R) -> R {
+        let mut entries = self.entries.lock();
+        if entries.is_expired(&key) {
+            entries.remove(&key);
+            self.evicted_expired.fetch_add(1, Ordering::Relaxed);
+        }
+        let (mut value, hit) = match entries.remove(&key) {
+            Some(value) => (value, true),
+            None => (default(), false),
+        };
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        let result = f(&mut value);
+        entries.push(key, value);
+        self.evict(&mut entries);
+        result
+    }
+
+    /// Returns the value it replaced, if that was still live.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let mut entries = self.entries.lock();
+        let expired = entries.is_expired(&key);
+        let previous = entries.remove(&key).filter(|_| !expired);
+        if expired {
+            self.evicted_expired.fetch_add(1, Ordering::Relaxed);
+        }
+        entries.push(key, value);
+        self.evict(&mut entries);
+        previous
+    }
+
+    pub fn remove(&self, key: &K) -> Option<V> {
+        self.entries.lock().remove(key)
+    }
+
+    /// Keeps the entries `keep` returns true for. Entries it drops aren't counted as evictions.
+    pub fn retain(&self, mut keep: impl FnMut(&K, &mut V) -> bool) {
+        let mut entries = self.entries.lock();
+        let dropped: Vec<K> = entries.map.iter_mut()
+            .filter_map(|(key, entry)| (!keep(key, &mut entry.value)).then(|| key.clone()))
+            .collect();
+        for key in dropped {
+            entries.remove(&key);
+        }
+    }
+
+    /// A copy of every live entry, without counting as a use.
+    pub fn values(&self) -> Vec<V>
+    where
+        V: Clone,
+    {
+        let entries = self.entries.lock();
+        let now = Instant::now();
+        entries.map.values()
+            .filter(|entry| !entries.expired_at(entry, now))
+            .map(|entry| entry.value.clone())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Changes the limit, evicting straight away if the cache is now over it.
+    pub fn set_max_entries(&self, max_entries: usize) {
+        let mut entries = self.entries.lock();
+        if entries.max_entries != max_entries.max(1) {
+            entries.max_entries = max_entries.max(1);
+            self.evict(&mut entries);
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.entries.lock();
+        CacheStats {
+            name: self.name,
+            entries: entries.map.len(),
+            max_entries: entries.max_entries,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evicted_full: self.evicted_full.load(Ordering::Relaxed),
+            evicted_expired: self.evicted_expired.load(Ordering::Relaxed),
+        }
+    }
+
+    // Expired entries at the cold end go first, then whatever is least recently used
+    fn evict(&self, entries: &mut Entries<K, V>) {
+        let now = Instant::now();
+        while let Some(key) = entries.order.values().next().cloned() {
+            let expired = entries.map.get(&key).map(|entry| entries.expired_at(entry, now)).unwrap_or(false);
+            if expired {
+                self.evicted_expired.fetch_add(1, Ordering::Relaxed);
+            } else if entries.map.len() > entries.max_entries {
+                self.evicted_full.fetch_add(1, Ordering::Relaxed);
+            } else {
+                break;
+            }
+            entries.remove(&key);
+        }
+    }
+}
+
+impl<K, V> Entries<K, V>
+where
+    K: Eq + Hash + Clone,
+{
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn push(&mut self, key: K, value: V) {
+        let tick = self.next_tick();
+        self.order.insert(tick, key.clone());
+        self.map.insert(key, Entry { value, tick, written_at: Instant::now() });
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        let entry = self.map.remove(key)?;
+        self.order.remove(&entry.tick);
+        Some(entry.value)
+    }
+
+    fn is_expired(&self, key: &K) -> bool {
+        self.map.get(key).map(|entry| self.expired_at(entry, Instant::now())).unwrap_or(false)
+    }
+
+    fn expired_at(&self, entry: &Entry<V>, now: Instant) -> bool {
+        self.ttl.map(|ttl| now.duration_since(entry.written_at) >= ttl).unwrap_or(false)
+    }
+}
+
+/// Prometheus view of the bounded caches, read from the caches when scraped.
+/// A cache evicting for room while its hit rate falls is too small for the load.
+pub struct CacheMetrics {
+    registry: Registry,
+    entries: IntGaugeVec,
+    max_entries: IntGaugeVec,
+    lookups: IntCounterVec,
+    evictions: IntCounterVec,
+}
+
+impl CacheMetrics {
+    pub fn new() -> Result<Self, MetricsError> {
+        let registry = Registry::new_custom(Some("morphine".to_string()), None)?;
+
+        let entries = IntGaugeVec::new(
+            Opts::new("cache_entries", "Entries held by an in-memory cache"),
+            &["cache"],
+        )?;
+        let max_entries = IntGaugeVec::new(
+            Opts::new("cache_max_entries", "Most entries the cache holds before evicting"),
+            &["cache"],
+        )?;
+        let lookups = IntCounterVec::new(
+            Opts::new("cache_lookups_total", "Cache lookups by result"),
+            &["cache", "result"],
+        )?;
+        let evictions = IntCounterVec::new(
+            Opts::new("cache_evictions_total", "Entries evicted to make room or because they expired"),
+            &["cache", "reason"],
+        )?;
+
+        registry.register(Box::new(entries.clone()))?;
+        registry.register(Box::new(max_entries.clone()))?;
+        registry.register(Box::new(lookups.clone()))?;
+        registry.register(Box::new(evictions.clone()))?;
+
+        Ok(Self { registry, entries, max_entries, lookups, evictions })
+    }
+
+    /// Encodes `caches` in the Prometheus text exposition format.
+    pub fn encode(&self, caches: &[CacheStats]) -> Result<String, MetricsError> {
+        for cache in caches {
+            self.entries.with_label_values(&[cache.name]).set(cache.entries as i64);
+            self.max_entries.with_label_values(&[cache.name]).set(cache.max_entries as i64);
+            // The caches keep running totals; the counters catch up to them
+            for (counter, labels, total) in [
+                (&self.lookups, [cache.name, "hit"], cache.hits),
+                (&self.lookups, [cache.name, "miss"], cache.misses),
+                (&self.evictions, [cache.name, "full"], cache.evicted_full),
+                (&self.evictions, [cache.name, "expired"], cache.evicted_expired),
+            ] {
+                let counter = counter.with_label_values(&labels);
+                counter.inc_by(total.saturating_sub(counter.get()));
+            }
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
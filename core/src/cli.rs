@@ -0,0 +1,330 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::migrate::Migrator;
+use tracing::{info, warn};
+
+use crate::auth::{Role, UserStore};
+use crate::auth::users::Registration;
+use crate::betting::repository::BettingRepository;
+use crate::config::{Config, DatabasePoolConfig, ReplicaConfig};
+use crate::geolocation::{ExclusionType, ExclusionZone};
+use crate::geolocation::zones::ExclusionZoneStore;
+use crate::replica::ReadRouter;
+use crate::secrets::SecretStore;
+use crate::state::StateManager;
+use crate::stream::{quotas::StreamQuotas, StreamManager, StreamSettings};
+use crate::tenant::{self, Tenant};
+use crate::timeline::{self, Timeline, TimelineFilter};
+
+/// The schema migrations built into the binary.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+// Shared by every seeded account; printed when seeding so nobody has to look it up
+pub(crate) const DEMO_PASSWORD: &str = "morphine-demo-password";
+
+/// Replaces settings that name a secret with the secret, as serving does.
+pub async fn resolve_secrets(config: &mut Config) -> Result<()> {
+    let secrets = SecretStore::new(&config.secrets).map_err(|e| anyhow::anyhow!(e))?;
+    secrets.resolve_config(config).await.map_err(|e| anyhow::anyhow!(e))
+}
+
+/// How the database's applied migrations compare with those built into the binary.
+#[derive(Debug, Default)]
+pub struct MigrationStatus {
+    pub applied: usize,
+    // Version and description, in the order they would run
+    pub pending: Vec<(i64, String)>,
+    // Applied, but the file built in has changed since
+    pub drifted: Vec<(i64, String)>,
+    // Applied, but not built into this binary, usually because a newer release ran
+    pub unknown: Vec<i64>,
+    // Started but never finished; the schema may be half-changed
+    pub failed: Vec<i64>,
+}
+
+impl MigrationStatus {
+    pub fn is_consistent(&self) -> bool {
+        self.drifted.is_empty() && self.failed.is_empty()
+    }
+
+    fn log(&self) {
+        info!(
+            "{} of {} migrations applied, {} pending",
+            self.applied,
+            MIGRATOR.iter().filter(|m| !m.migration_type.is_down_migration()).count(),
+            self.pending.len()
+        );
+        for (version, description) in &self.pending {
+            info!("Pending: {} {}", version, description);
+        }
+        for (version, description) in &self.drifted {
+            warn!("Drifted: {} {} was changed after it was applied", version, description);
+        }
+        for version in &self.failed {
+            warn!("Failed: {} was left partly applied", version);
+        }
+        for version in &self.unknown {
+            warn!("Unknown: {} is applied but not built into this binary", version);
+        }
+    }
+}
+
+/// Compares the `_sqlx_migrations` table with the migrations built in.
+pub async fn migration_status(db_pool: &sqlx::PgPool) -> Result<MigrationStatus> {
+    let tracked: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(db_pool)
+        .await?;
+    let applied: Vec<(i64, Vec<u8>, bool)> = if tracked {
+        sqlx::query_as("SELECT version, checksum, success FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(db_pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    let mut status = MigrationStatus::default();
+    for migration in MIGRATOR.iter().filter(|m| !m.migration_type.is_down_migration()) {
+        match applied.iter().find(|(version, _, _)| *version == migration.version) {
+            None => status.pending.push((migration.version, migration.description.to_string())),
+            Some((version, _, false)) => status.failed.push(*version),
+            Some((_, checksum, true)) if checksum.as_slice() != &*migration.checksum => {
+                status.drifted.push((migration.version, migration.description.to_string()));
+            }
+            Some(_) => status.applied += 1,
+        }
+    }
+    status.unknown = applied.iter()
+        .map(|(version, _, _)| *version)
+        .filter(|version| MIGRATOR.iter().all(|m| m.version != *version))
+        .collect();
+    Ok(status)
+}
+
+/// `morphine migrate`: applies pending migrations, or with `dry_run` only
+/// reports what would run. Refuses to touch a schema that has drifted from
+/// the migrations built in.
+pub async fn migrate(config: &Config, dry_run: bool) -> Result<()> {
+    // Index builds and backfills may run past the statement timeout meant for requests
+    let pool_config = DatabasePoolConfig { statement_timeout_ms: 0, ..config.database_pool.clone() };
+    let db_pool = tenant::connect(&config.database_url, &pool_config).await
+        .context("Failed to connect to PostgreSQL")?;
+
+    let status = migration_status(&db_pool).await?;
+    status.log();
+    if !status.is_consistent() {
+        bail!("The database schema has drifted from the migrations built in; repair it before migrating");
+    }
+    if dry_run || status.pending.is_empty() {
+        return Ok(());
+    }
+    if !status.unknown.is_empty() {
+        bail!("The database has migrations this binary doesn't know; migrate with the release that applied them");
+    }
+
+    MIGRATOR.run(&db_pool).await
+        .context("Failed to run database migrations")?;
+    info!("Applied {} migrations; the schema is up to date", status.pending.len());
+    Ok(())
+}
+
+/// Checks the schema before serving. Pending migrations and drift are
+/// logged, and with `require_migrated` they stop the service from starting.
+pub async fn check_schema(db_pool: &sqlx::PgPool, require_migrated: bool) -> Result<()> {
+    let status = migration_status(db_pool).await?;
+    if status.pending.is_empty() && status.is_consistent() {
+        return Ok(());
+    }
+
+    status.log();
+    if require_migrated {
+        bail!("The database schema isn't up to date; run `morphine migrate` first");
+    }
+    warn!("Serving with a schema that isn't up to date; run `morphine migrate`");
+    Ok(())
+}
+
+/// `morphine seed`: demo data for local development, written as `tenant` or
+/// the default tenant. Running it again only fills in what is missing.
+pub async fn seed(config: &Config, tenant: Option<String>) -> Result<()> {
+    let db_pool = tenant::connect(&config.database_url, &config.database_pool).await
+        .context("Failed to connect to PostgreSQL")?;
+    let tenant_id = tenant.unwrap_or_else(|| config.tenancy.default_tenant.clone());
+    let tenant = Tenant { is_default: tenant_id == config.tenancy.default_tenant, id: tenant_id };
+    info!("Seeding demo data for tenant {}", tenant.id);
+
+    tenant::scope(tenant, async {
+        seed_users(config, &db_pool).await?;
+        seed_streams(config, &db_pool).await?;
+        seed_exclusion_zones(&db_pool).await
+    }).await?;
+
+    info!("Demo accounts sign in with the password {:?}", DEMO_PASSWORD);
+    Ok(())
+}
+
+/// `morphine export-snapshot`: writes the tenant's balances, open bets and
+/// ledger as of one instant to `output`, or stdout for `-`.
+pub async fn export_snapshot(
+    config: &Config,
+    output: &Path,
+    tenant: Option<String>,
+    ledger_since: Option<DateTime<Utc>>,
+) -> Result<()> {
+    // A large ledger may take longer to read than a request is allowed
+    let pool_config = DatabasePoolConfig { statement_timeout_ms: 0, ..config.database_pool.clone() };
+    let db_pool = tenant::connect(&config.database_url, &pool_config).await
+        .context("Failed to connect to PostgreSQL")?;
+    // Read from the primary only; a replica could be behind the ledger
+    let reads = ReadRouter::connect(db_pool.clone(), ReplicaConfig::default(), &pool_config).await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    let repository = BettingRepository::new(db_pool, Arc::new(reads));
+
+    let tenant_id = tenant.unwrap_or_else(|| config.tenancy.default_tenant.clone());
+    let tenant = Tenant { is_default: tenant_id == config.tenancy.default_tenant, id: tenant_id };
+    let snapshot = tenant::scope(tenant, repository.financial_snapshot(ledger_since)).await?;
+
+    let json = serde_json::to_string_pretty(&snapshot)?;
+    if output == Path::new("-") {
+        println!("{}", json);
+    } else {
+        std::fs::write(output, json)
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+    }
+    info!(
+        "Exported the financial snapshot at {}: {} balances, {} open bets, {} ledger entries (through entry {:?})",
+        snapshot.watermark,
+        snapshot.balances.len(),
+        snapshot.open_bets.len(),
+        snapshot.ledger.len(),
+        snapshot.last_ledger_entry_id,
+    );
+    Ok(())
+}
+
+/// Writes state as of `as_of`, rebuilt from the state event log, as `GET /api/admin/timeline` returns it.
+pub async fn state_as_of(
+    config: &Config,
+    output: &Path,
+    tenant: Option<String>,
+    as_of: DateTime<Utc>,
+    filter: TimelineFilter,
+) -> Result<()> {
+    let pool_config = DatabasePoolConfig { statement_timeout_ms: 0, ..config.database_pool.clone() };
+    let db_pool = tenant::connect(&config.database_url, &pool_config).await
+        .context("Failed to connect to PostgreSQL")?;
+    let timeline = Timeline::new(db_pool);
+
+    let tenant_id = tenant.unwrap_or_else(|| config.tenancy.default_tenant.clone());
+    let tenant = Tenant { is_default: tenant_id == config.tenancy.default_tenant, id: tenant_id };
+    let state = tenant::scope(tenant, timeline.state_as_of(as_of, &filter, timeline::MAX_EVENTS)).await?;
+
+    let json = serde_json::to_string_pretty(&state)?;
+    if output == Path::new("-") {
+        println!("{}", json);
+    } else {
+        std::fs::write(output, json)
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+    }
+    info!(
+        "Rebuilt state as of {}: {} streams, {} bets, {} balances",
+        as_of, state.streams.len(), state.bets.len(), state.balances.len(),
+    );
+    if state.truncated {
+        warn!("Only the first {} entities were written; narrow with --user-id or --stream-id", timeline::MAX_EVENTS);
+    }
+    Ok(())
+}
+
+async fn seed_users(config: &Config, db_pool: &sqlx::PgPool) -> Result<()> {
+    let verification_ttl = Duration::from_secs(config.user_auth.email_verification_ttl_secs);
+    let accounts = [
+        ("creator@demo.morphine.local", "demo_creator", "Demo Creator", vec![Role::Creator, Role::Bettor]),
+        ("bettor@demo.morphine.local", "demo_bettor", "Demo Bettor", vec![Role::Bettor]),
+        ("viewer@demo.morphine.local", "demo_viewer", "Demo Viewer", vec![Role::Bettor]),
+    ];
+
+    for (email, username, display_name, roles) in accounts {
+        let users = UserStore::new(db_pool.clone(), roles, verification_ttl);
+        let registration = Registration {
+            email: email.to_string(),
+            username: username.to_string(),
+            password: DEMO_PASSWORD.to_string(),
+            display_name: Some(display_name.to_string()),
+        };
+        match users.register(&registration).await.map_err(|e| anyhow::anyhow!(e))? {
+            Some(user) => info!("Created demo user {} ({})", email, user.user_id),
+            None => info!("Demo user {} already exists", email),
+        }
+    }
+    Ok(())
+}
+
+async fn seed_streams(config: &Config, db_pool: &sqlx::PgPool) -> Result<()> {
+    let state_manager = Arc::new(StateManager::new(&config.redis_url, &config.redis_pool).await?);
+    let streams = StreamManager::new(
+        state_manager,
+        db_pool.clone(),
+        config.tenancy.default_tenant.clone(),
+        config.region.id.clone(),
+        Arc::new(StreamQuotas::new(
+            db_pool.clone(),
+            config.stream_quotas.clone(),
+            config.max_concurrent_streams,
+            config.tenancy.default_tenant.clone(),
+        )),
+    ).await?;
+    let existing: Vec<String> = streams.list_streams().await?
+        .into_iter()
+        .map(|stream| stream.title)
+        .collect();
+
+    let demo_streams = [
+        ("Demo: Sunday League Final", "1080p", 30, true),
+        ("Demo: Street Basketball Pickup", "720p", 60, true),
+        ("Demo: Training Session (no betting)", "720p", 30, false),
+    ];
+    for (title, quality, frame_rate, enable_betting) in demo_streams {
+        if existing.iter().any(|existing| existing == title) {
+            info!("Demo stream {:?} already exists", title);
+            continue;
+        }
+        let settings = StreamSettings {
+            quality: quality.to_string(),
+            frame_rate,
+            enable_cv: true,
+            enable_betting,
+            max_viewers: Some(500),
+        };
+        let stream = streams.create_stream(title.to_string(), settings).await?;
+        info!("Created demo stream {:?} ({})", title, stream.id);
+    }
+    Ok(())
+}
+
+async fn seed_exclusion_zones(db_pool: &sqlx::PgPool) -> Result<()> {
+    let zones = ExclusionZoneStore::new(db_pool.clone());
+    let demo_zones = [
+        ("demo-venue", 51.5560, -0.2796, 500.0, ExclusionType::VenueProximity),
+        ("demo-event-location", 51.5560, -0.2796, 150.0, ExclusionType::EventLocation),
+        ("demo-restricted-region", 40.7128, -74.0060, 5000.0, ExclusionType::RestrictedRegion),
+    ];
+
+    // Fixed IDs, so seeding again updates these zones instead of adding copies
+    for (zone_id, center_lat, center_lon, radius_meters, zone_type) in demo_zones {
+        let zone = ExclusionZone {
+            zone_id: zone_id.to_string(),
+            center_lat,
+            center_lon,
+            radius_meters,
+            zone_type,
+            active_from: Utc::now(),
+            active_until: None,
+        };
+        zones.save(&zone).await.map_err(|e| anyhow::anyhow!(e))?;
+        info!("Seeded exclusion zone {}", zone_id);
+    }
+    Ok(())
+}
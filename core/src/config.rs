@@ -1,7 +1,26 @@
-use anyhow::{Result, Context};
-use serde::Deserialize;
+use anyhow::{Result, Context, bail};
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-#[derive(Debug, Deserialize)]
+use MAX_BET_WINDOW_SECS;
+use crate::auth::Role;
+use crate::email::EmailProviderKind;
+use crate::error_reporting::UserIdReporting;
+use crate::events::EventBusKind;
+use crate::exports::ExportStorageKind;
+use crate::loadtest::LoadTestOptions;
+use crate::orchestrator::accelerator::{AcceleratorDevice, AcceleratorRequirement};
+use crate::orchestrator::backpressure::OverflowPolicy;
+use crate::orchestrator::priority_queue::ShedPolicy;
+use crate::orchestrator::windowing::{WindowKind, WindowPolicy};
+use crate::region::RegionEndpoints;
+use crate::secrets::SecretProviderKind;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub bind_address: String,
     pub redis_url: String,
@@ -10,37 +29,3279 @@ pub struct Config {
     pub stream_storage_path: String,
     pub max_concurrent_streams: usize,
     pub stream_activation_timeout_seconds: u64,
+    // Nanosecond timestamps for location verification; the system clock otherwise
+    pub precision_timing_enabled: bool,
+    pub ai_systems_manifest_path: Option<String>,
+    pub reasoning_config: ReasoningConfig,
+    pub analytics_adapter: AnalyticsAdapterConfig,
+    pub analytics_client: AnalyticsClientConfig,
+    pub orchestrator: OrchestratorConfig,
+    pub admin_api: AdminApiConfig,
+    pub api_keys: ApiKeyConfig,
+    pub user_auth: UserAuthConfig,
+    pub rate_limit: RateLimitConfig,
+    pub shutdown: ShutdownConfig,
+    pub api_version: ApiVersionConfig,
+    pub webhooks: WebhookConfig,
+    pub grpc: GrpcConfig,
+    pub datagram_ingest: DatagramIngestConfig,
+    pub betting: BettingConfig,
+    pub secrets: SecretsConfig,
+    pub cors: CorsConfig,
+    pub tls: TlsConfig,
+    pub idempotency: IdempotencyConfig,
+    pub limits: LimitsConfig,
+    pub tenancy: TenancyConfig,
+    pub feature_flags: FeatureFlagConfig,
+    pub bet_archive: BetArchiveConfig,
+    pub read_replicas: ReplicaConfig,
+    pub outbox: OutboxConfig,
+    pub database_pool: DatabasePoolConfig,
+    pub redis_pool: RedisPoolConfig,
+    pub settlement: SettlementConfig,
+    pub event_bus: EventBusConfig,
+    pub email: EmailConfig,
+    pub push: PushConfig,
+    pub geolocation: GeolocationConfig,
+    pub error_reporting: ErrorReportingConfig,
+    pub exports: ExportConfig,
+    pub risk: RiskConfig,
+    pub replay_protection: ReplayProtectionConfig,
+    pub region: RegionConfig,
+    pub market_suspension: MarketSuspensionConfig,
+    pub stream_quotas: StreamQuotaConfig,
+}
+
+/// Tunables for the hybrid reasoning engine and its paradigm sub-engines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReasoningConfig {
+    // Paradigm weights used when synthesizing a hybrid outcome
+    pub imperative_weight: f64,
+    pub logical_weight: f64,
+    pub fuzzy_weight: f64,
+
+    // Engine toggles; a disabled paradigm contributes nothing to the outcome
+    pub imperative_enabled: bool,
+    pub logical_enabled: bool,
+    pub fuzzy_enabled: bool,
+
+    // Maximum number of evaluated outcomes kept for trace lookups
+    pub reasoning_cache_size: usize,
+    // Largest event, context or trace stored whole with an evaluation; larger ones keep a preview
+    pub trace_max_payload_bytes: usize,
+
+    // Outcome classification thresholds
+    pub win_score_threshold: f64,
+    pub loss_score_threshold: f64,
+    pub partial_win_score_threshold: f64,
+    pub high_confidence_threshold: f64,
+    pub partial_win_confidence_threshold: f64,
+    pub uncertain_confidence_threshold: f64,
+
+    // Stake used for settlement until bet amounts are wired in
+    pub default_settlement_base: f64,
+}
+
+impl Default for ReasoningConfig {
+    fn default() -> Self {
+        Self {
+            imperative_weight: 0.4,
+            logical_weight: 0.3,
+            fuzzy_weight: 0.3,
+            imperative_enabled: true,
+            logical_enabled: true,
+            fuzzy_enabled: true,
+            reasoning_cache_size: 10_000,
+            trace_max_payload_bytes: 65_536,
+            win_score_threshold: 0.8,
+            loss_score_threshold: 0.2,
+            partial_win_score_threshold: 0.4,
+            high_confidence_threshold: 0.9,
+            partial_win_confidence_threshold: 0.7,
+            uncertain_confidence_threshold: 0.5,
+            default_settlement_base: 100.0,
+        }
+    }
+}
+
+impl ReasoningConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = ReasoningConfig {
+            imperative_weight: env_or("REASONING_IMPERATIVE_WEIGHT", base.imperative_weight)?,
+            logical_weight: env_or("REASONING_LOGICAL_WEIGHT", base.logical_weight)?,
+            fuzzy_weight: env_or("REASONING_FUZZY_WEIGHT", base.fuzzy_weight)?,
+
+            imperative_enabled: env_or("REASONING_IMPERATIVE_ENABLED", base.imperative_enabled)?,
+            logical_enabled: env_or("REASONING_LOGICAL_ENABLED", base.logical_enabled)?,
+            fuzzy_enabled: env_or("REASONING_FUZZY_ENABLED", base.fuzzy_enabled)?,
+
+            reasoning_cache_size: env_or("REASONING_CACHE_SIZE", base.reasoning_cache_size)?,
+            trace_max_payload_bytes: env_or("REASONING_TRACE_MAX_PAYLOAD_BYTES", base.trace_max_payload_bytes)?,
+
+            win_score_threshold: env_or("REASONING_WIN_SCORE_THRESHOLD", base.win_score_threshold)?,
+            loss_score_threshold: env_or("REASONING_LOSS_SCORE_THRESHOLD", base.loss_score_threshold)?,
+            partial_win_score_threshold: env_or(
+                "REASONING_PARTIAL_WIN_SCORE_THRESHOLD",
+                base.partial_win_score_threshold,
+            )?,
+            high_confidence_threshold: env_or(
+                "REASONING_HIGH_CONFIDENCE_THRESHOLD",
+                base.high_confidence_threshold,
+            )?,
+            partial_win_confidence_threshold: env_or(
+                "REASONING_PARTIAL_WIN_CONFIDENCE_THRESHOLD",
+                base.partial_win_confidence_threshold,
+            )?,
+            uncertain_confidence_threshold: env_or(
+                "REASONING_UNCERTAIN_CONFIDENCE_THRESHOLD",
+                base.uncertain_confidence_threshold,
+            )?,
+
+            default_settlement_base: env_or("REASONING_SETTLEMENT_BASE", base.default_settlement_base)?,
+        };
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let weights = [self.imperative_weight, self.logical_weight, self.fuzzy_weight];
+        if weights.iter().any(|w| *w < 0.0) {
+            bail!("Reasoning paradigm weights must be non-negative");
+        }
+
+        if !(self.imperative_enabled || self.logical_enabled || self.fuzzy_enabled) {
+            bail!("At least one reasoning paradigm must be enabled");
+        }
+
+        if self.reasoning_cache_size == 0 {
+            bail!("REASONING_CACHE_SIZE must be greater than zero");
+        }
+
+        if self.trace_max_payload_bytes < 1024 {
+            bail!("REASONING_TRACE_MAX_PAYLOAD_BYTES must be at least 1024");
+        }
+
+        if self.loss_score_threshold >= self.win_score_threshold {
+            bail!("Loss score threshold must be below the win score threshold");
+        }
+
+        Ok(())
+    }
+}
+
+/// Connection settings for the Python analytics sidecar adapter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalyticsAdapterConfig {
+    pub enabled: bool,
+    pub weight: f64,
+    pub timeout_ms: u64,
+    pub max_retries: u32,
+    pub retry_backoff_ms: u64,
+    pub pool_size: usize,
+    // VRAM each frame-processing call needs on a GPU; 0 means CPU-only
+    pub accelerator_vram_mb: u64,
+}
+
+impl AnalyticsAdapterConfig {
+    /// The longest one orchestrator call to the adapter can take: every
+    /// attempt timing out, with the backoff between them.
+    pub fn retry_budget_ms(&self) -> u64 {
+        let attempts = self.max_retries as u64 + 1;
+        let backoff = self.retry_backoff_ms.saturating_mul((1u64 << self.max_retries.min(32)) - 1);
+        self.timeout_ms.saturating_mul(attempts).saturating_add(backoff)
+    }
+
+    pub fn accelerator_requirement(&self) -> Option<AcceleratorRequirement> {
+        (self.accelerator_vram_mb > 0).then_some(AcceleratorRequirement {
+            slots: 1,
+            vram_mb: self.accelerator_vram_mb,
+        })
+    }
+}
+
+impl Default for AnalyticsAdapterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            weight: 1.0,
+            // Two attempts and the backoff fit under the breaker's default call timeout
+            timeout_ms: 200,
+            max_retries: 1,
+            retry_backoff_ms: 50,
+            pool_size: 16,
+            accelerator_vram_mb: 0,
+        }
+    }
+}
+
+impl AnalyticsAdapterConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        Ok(AnalyticsAdapterConfig {
+            enabled: env_or("ANALYTICS_ADAPTER_ENABLED", base.enabled)?,
+            weight: env_or("ANALYTICS_ADAPTER_WEIGHT", base.weight)?,
+            timeout_ms: env_or("ANALYTICS_ADAPTER_TIMEOUT_MS", base.timeout_ms)?,
+            max_retries: env_or("ANALYTICS_ADAPTER_MAX_RETRIES", base.max_retries)?,
+            retry_backoff_ms: env_or("ANALYTICS_ADAPTER_RETRY_BACKOFF_MS", base.retry_backoff_ms)?,
+            pool_size: env_or("ANALYTICS_ADAPTER_POOL_SIZE", base.pool_size)?,
+            accelerator_vram_mb: env_or("ANALYTICS_ADAPTER_ACCELERATOR_VRAM_MB", base.accelerator_vram_mb)?,
+        })
+    }
+}
+
+/// On-demand requests to the analytics service, such as checking a frame
+/// hash for a disputed bet. Unlike the adapter these aren't on the live path,
+/// so they wait longer and retry more.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalyticsClientConfig {
+    // Per attempt; re-running detection over a clip can take a while
+    pub timeout_ms: u64,
+    pub max_retries: u32,
+    // Doubles after each failed attempt, up to max_backoff_ms
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    // Longest clip detection may be re-run over
+    pub max_clip_secs: f64,
+}
+
+impl Default for AnalyticsClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 30000,
+            max_retries: 3,
+            initial_backoff_ms: 250,
+            max_backoff_ms: 5000,
+            max_clip_secs: 300.0,
+        }
+    }
+}
+
+impl AnalyticsClientConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = AnalyticsClientConfig {
+            timeout_ms: env_or("ANALYTICS_CLIENT_TIMEOUT_MS", base.timeout_ms)?,
+            max_retries: env_or("ANALYTICS_CLIENT_MAX_RETRIES", base.max_retries)?,
+            initial_backoff_ms: env_or("ANALYTICS_CLIENT_INITIAL_BACKOFF_MS", base.initial_backoff_ms)?,
+            max_backoff_ms: env_or("ANALYTICS_CLIENT_MAX_BACKOFF_MS", base.max_backoff_ms)?,
+            max_clip_secs: env_or("ANALYTICS_CLIENT_MAX_CLIP_SECS", base.max_clip_secs)?,
+        };
+
+        if config.timeout_ms == 0 || config.initial_backoff_ms == 0 || config.max_clip_secs <= 0.0 {
+            bail!("ANALYTICS_CLIENT_TIMEOUT_MS, ANALYTICS_CLIENT_INITIAL_BACKOFF_MS and ANALYTICS_CLIENT_MAX_CLIP_SECS must be greater than zero");
+        }
+        if config.initial_backoff_ms > config.max_backoff_ms {
+            bail!("ANALYTICS_CLIENT_INITIAL_BACKOFF_MS must not exceed ANALYTICS_CLIENT_MAX_BACKOFF_MS");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Runtime settings for the metacognitive orchestrator.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OrchestratorConfig {
+    pub circuit_breaker: CircuitBreakerConfig,
+    pub context_queue: ContextQueueConfig,
+    pub input_streams: InputStreamConfig,
+    pub dreaming: DreamingConfig,
+    pub lactate: LactateConfig,
+    pub health_probe: HealthProbeConfig,
+    pub feedback: FeedbackConfig,
+    pub accelerators: AcceleratorConfig,
+    pub knowledge_graph: KnowledgeGraphConfig,
+    pub executor: ExecutorConfig,
+    pub windows: WindowConfig,
+    pub alerts: AlertConfig,
+    pub replay: ReplayConfig,
+    pub onnx: OnnxConfig,
+    pub opportunities: OpportunityConfig,
+}
+
+impl OrchestratorConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        Ok(OrchestratorConfig {
+            circuit_breaker: CircuitBreakerConfig::from_env(base.circuit_breaker)?,
+            context_queue: ContextQueueConfig::from_env(base.context_queue)?,
+            input_streams: InputStreamConfig::from_env(base.input_streams)?,
+            dreaming: DreamingConfig::from_env(base.dreaming)?,
+            lactate: LactateConfig::from_env(base.lactate)?,
+            health_probe: HealthProbeConfig::from_env(base.health_probe)?,
+            feedback: FeedbackConfig::from_env(base.feedback)?,
+            accelerators: AcceleratorConfig::from_env(base.accelerators)?,
+            knowledge_graph: KnowledgeGraphConfig::from_env(base.knowledge_graph)?,
+            executor: ExecutorConfig::from_env(base.executor)?,
+            windows: WindowConfig::from_env(base.windows)?,
+            alerts: AlertConfig::from_env(base.alerts)?,
+            replay: ReplayConfig::from_env(base.replay)?,
+            onnx: OnnxConfig::from_env(base.onnx)?,
+            opportunities: OpportunityConfig::from_env(base.opportunities)?,
+        })
+    }
+}
+
+/// Bounded input channel settings for orchestrator streams.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputStreamConfig {
+    pub channel_capacity: usize,
+    pub overflow_policy: OverflowPolicy,
+    pub block_timeout_ms: u64,
+    // Routine contexts older than this when dequeued are dropped as stale
+    pub max_context_age_ms: u64,
+}
+
+impl Default for InputStreamConfig {
+    fn default() -> Self {
+        Self {
+            channel_capacity: 256,
+            overflow_policy: OverflowPolicy::Merge,
+            block_timeout_ms: 50,
+            max_context_age_ms: 5_000,
+        }
+    }
+}
+
+impl InputStreamConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let overflow_policy = match std::env::var("ORCHESTRATOR_OVERFLOW_POLICY").as_deref() {
+            Ok("drop") => OverflowPolicy::Drop,
+            Ok("block") => OverflowPolicy::Block,
+            Ok("merge") => OverflowPolicy::Merge,
+            Err(_) => base.overflow_policy,
+            Ok(other) => bail!(
+                "ORCHESTRATOR_OVERFLOW_POLICY must be 'drop', 'merge' or 'block', got '{}'",
+                other
+            ),
+        };
+
+        let config = InputStreamConfig {
+            channel_capacity: env_or("ORCHESTRATOR_CHANNEL_CAPACITY", base.channel_capacity)?,
+            overflow_policy,
+            block_timeout_ms: env_or("ORCHESTRATOR_BLOCK_TIMEOUT_MS", base.block_timeout_ms)?,
+            max_context_age_ms: env_or("ORCHESTRATOR_MAX_CONTEXT_AGE_MS", base.max_context_age_ms)?,
+        };
+
+        if config.channel_capacity == 0 {
+            bail!("ORCHESTRATOR_CHANNEL_CAPACITY must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Priority queue limits and load-shedding behaviour for per-stream context processing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContextQueueConfig {
+    // Glycolytic load at or above which routine contexts are shed
+    pub shed_load_threshold: f64,
+    pub shed_policy: ShedPolicy,
+    // Queue depth at which routine contexts are shed regardless of load
+    pub max_queue_depth: usize,
+}
+
+impl Default for ContextQueueConfig {
+    fn default() -> Self {
+        Self {
+            shed_load_threshold: 0.85,
+            shed_policy: ShedPolicy::Summarize,
+            max_queue_depth: 500,
+        }
+    }
+}
+
+impl ContextQueueConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let shed_policy = match std::env::var("CONTEXT_SHED_POLICY").as_deref() {
+            Ok("drop") => ShedPolicy::Drop,
+            Ok("summarize") => ShedPolicy::Summarize,
+            Err(_) => base.shed_policy,
+            Ok(other) => bail!("CONTEXT_SHED_POLICY must be 'drop' or 'summarize', got '{}'", other),
+        };
+
+        Ok(ContextQueueConfig {
+            shed_load_threshold: env_or("CONTEXT_SHED_LOAD_THRESHOLD", base.shed_load_threshold)?,
+            shed_policy,
+            max_queue_depth: env_or("CONTEXT_MAX_QUEUE_DEPTH", base.max_queue_depth)?,
+        })
+    }
+}
+
+/// Per-AI-system call guarding: timeouts, breaker thresholds and fallback penalty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CircuitBreakerConfig {
+    pub call_timeout_ms: u64,
+    pub window_size: usize,
+    pub min_calls: usize,
+    pub failure_rate_threshold: f64,
+    pub open_duration_ms: u64,
+    // Fraction of context-layer confidence lost per skipped or failed system
+    pub fallback_confidence_penalty: f64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            call_timeout_ms: 500,
+            window_size: 20,
+            min_calls: 5,
+            failure_rate_threshold: 0.5,
+            open_duration_ms: 30_000,
+            fallback_confidence_penalty: 0.15,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = CircuitBreakerConfig {
+            call_timeout_ms: env_or("AI_SYSTEM_TIMEOUT_MS", base.call_timeout_ms)?,
+            window_size: env_or("AI_BREAKER_WINDOW_SIZE", base.window_size)?,
+            min_calls: env_or("AI_BREAKER_MIN_CALLS", base.min_calls)?,
+            failure_rate_threshold: env_or("AI_BREAKER_FAILURE_RATE", base.failure_rate_threshold)?,
+            open_duration_ms: env_or("AI_BREAKER_OPEN_DURATION_MS", base.open_duration_ms)?,
+            fallback_confidence_penalty: env_or(
+                "AI_BREAKER_FALLBACK_PENALTY",
+                base.fallback_confidence_penalty,
+            )?,
+        };
+
+        if config.window_size == 0 || config.min_calls > config.window_size {
+            bail!("AI_BREAKER_MIN_CALLS must be between 1 and AI_BREAKER_WINDOW_SIZE");
+        }
+
+        if !(0.0..=1.0).contains(&config.failure_rate_threshold) {
+            bail!("AI_BREAKER_FAILURE_RATE must be between 0 and 1");
+        }
+
+        Ok(config)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind_address: "0.0.0.0:3001".to_string(),
+            redis_url: "redis://localhost:6379".to_string(),
+            // Required; there is no sensible default
+            database_url: String::new(),
+            analytics_service_url: "http://localhost:8000".to_string(),
+            stream_storage_path: "./storage/streams".to_string(),
+            max_concurrent_streams: 10,
+            stream_activation_timeout_seconds: 300,
+            precision_timing_enabled: true,
+            ai_systems_manifest_path: None,
+            reasoning_config: ReasoningConfig::default(),
+            analytics_adapter: AnalyticsAdapterConfig::default(),
+            analytics_client: AnalyticsClientConfig::default(),
+            orchestrator: OrchestratorConfig::default(),
+            admin_api: AdminApiConfig::default(),
+            api_keys: ApiKeyConfig::default(),
+            user_auth: UserAuthConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            shutdown: ShutdownConfig::default(),
+            api_version: ApiVersionConfig::default(),
+            webhooks: WebhookConfig::default(),
+            grpc: GrpcConfig::default(),
+            datagram_ingest: DatagramIngestConfig::default(),
+            betting: BettingConfig::default(),
+            secrets: SecretsConfig::default(),
+            cors: CorsConfig::default(),
+            tls: TlsConfig::default(),
+            idempotency: IdempotencyConfig::default(),
+            limits: LimitsConfig::default(),
+            tenancy: TenancyConfig::default(),
+            feature_flags: FeatureFlagConfig::default(),
+            bet_archive: BetArchiveConfig::default(),
+            read_replicas: ReplicaConfig::default(),
+            outbox: OutboxConfig::default(),
+            database_pool: DatabasePoolConfig::default(),
+            redis_pool: RedisPoolConfig::default(),
+            settlement: SettlementConfig::default(),
+            event_bus: EventBusConfig::default(),
+            email: EmailConfig::default(),
+            push: PushConfig::default(),
+            geolocation: GeolocationConfig::default(),
+            error_reporting: ErrorReportingConfig::default(),
+            exports: ExportConfig::default(),
+            risk: RiskConfig::default(),
+            replay_protection: ReplayProtectionConfig::default(),
+            region: RegionConfig::default(),
+            market_suspension: MarketSuspensionConfig::default(),
+            stream_quotas: StreamQuotaConfig::default(),
+        }
+    }
 }
 
 impl Config {
-    pub fn from_env() -> Result<Self> {
+    /// Defaults, overridden by the TOML file at `path` if one is given, overridden
+    /// in turn by environment variables. Validation runs on the merged result.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let base = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        Self::from_env(base)
+    }
+
+    /// Parses a config file without validating it; sections and keys left out keep their defaults.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    pub fn from_env(base: Self) -> Result<Self> {
         let config = Config {
-            bind_address: std::env::var("BIND_ADDRESS")
-                .unwrap_or_else(|_| "0.0.0.0:3001".to_string()),
-            
-            redis_url: std::env::var("REDIS_URL")
-                .unwrap_or_else(|_| "redis://localhost:6379".to_string()),
-            
-            database_url: std::env::var("DATABASE_URL")
-                .context("DATABASE_URL must be set")?,
-            
-            analytics_service_url: std::env::var("ANALYTICS_SERVICE_URL")
-                .unwrap_or_else(|_| "http://localhost:8000".to_string()),
-            
-            stream_storage_path: std::env::var("STREAM_STORAGE_PATH")
-                .unwrap_or_else(|_| "./storage/streams".to_string()),
-            
-            max_concurrent_streams: std::env::var("MAX_CONCURRENT_STREAMS")
-                .unwrap_or_else(|_| "10".to_string())
-                .parse()
-                .context("MAX_CONCURRENT_STREAMS must be a valid number")?,
-            
-            stream_activation_timeout_seconds: std::env::var("STREAM_ACTIVATION_TIMEOUT")
-                .unwrap_or_else(|_| "300".to_string())
-                .parse()
-                .context("STREAM_ACTIVATION_TIMEOUT must be a valid number")?,
-        };
-
-        Ok(config)
-    }
-} 
\ No newline at end of file
+            bind_address: env_or("BIND_ADDRESS", base.bind_address)?,
+
+            redis_url: env_or("REDIS_URL", base.redis_url)?,
+
+            database_url: env_or("DATABASE_URL", base.database_url)?,
+
+            analytics_service_url: env_or("ANALYTICS_SERVICE_URL", base.analytics_service_url)?,
+
+            stream_storage_path: env_or("STREAM_STORAGE_PATH", base.stream_storage_path)?,
+
+            max_concurrent_streams: env_or("MAX_CONCURRENT_STREAMS", base.max_concurrent_streams)?,
+
+            stream_activation_timeout_seconds: env_or(
+                "STREAM_ACTIVATION_TIMEOUT",
+                base.stream_activation_timeout_seconds,
+            )?,
+
+            precision_timing_enabled: env_or("PRECISION_TIMING_ENABLED", base.precision_timing_enabled)?,
+
+            ai_systems_manifest_path: env_opt("AI_SYSTEMS_MANIFEST").or(base.ai_systems_manifest_path),
+
+            reasoning_config: ReasoningConfig::from_env(base.reasoning_config)?,
+
+            analytics_adapter: AnalyticsAdapterConfig::from_env(base.analytics_adapter)?,
+            analytics_client: AnalyticsClientConfig::from_env(base.analytics_client)?,
+
+            orchestrator: OrchestratorConfig::from_env(base.orchestrator)?,
+
+            admin_api: AdminApiConfig::from_env(base.admin_api)?,
+
+            api_keys: ApiKeyConfig::from_env(base.api_keys)?,
+
+            user_auth: UserAuthConfig::from_env(base.user_auth)?,
+
+            rate_limit: RateLimitConfig::from_env(base.rate_limit)?,
+
+            shutdown: ShutdownConfig::from_env(base.shutdown)?,
+
+            api_version: ApiVersionConfig::from_env(base.api_version)?,
+
+            webhooks: WebhookConfig::from_env(base.webhooks)?,
+
+            grpc: GrpcConfig::from_env(base.grpc)?,
+            datagram_ingest: DatagramIngestConfig::from_env(base.datagram_ingest)?,
+
+            betting: BettingConfig::from_env(base.betting)?,
+
+            secrets: SecretsConfig::from_env(base.secrets)?,
+
+            cors: CorsConfig::from_env(base.cors)?,
+
+            tls: TlsConfig::from_env(base.tls)?,
+
+            idempotency: IdempotencyConfig::from_env(base.idempotency)?,
+
+            limits: LimitsConfig::from_env(base.limits)?,
+
+            tenancy: TenancyConfig::from_env(base.tenancy)?,
+
+            feature_flags: FeatureFlagConfig::from_env(base.feature_flags)?,
+
+            bet_archive: BetArchiveConfig::from_env(base.bet_archive)?,
+
+            read_replicas: ReplicaConfig::from_env(base.read_replicas)?,
+
+            outbox: OutboxConfig::from_env(base.outbox)?,
+
+            database_pool: DatabasePoolConfig::from_env(base.database_pool)?,
+
+            redis_pool: RedisPoolConfig::from_env(base.redis_pool)?,
+
+            settlement: SettlementConfig::from_env(base.settlement)?,
+
+            event_bus: EventBusConfig::from_env(base.event_bus)?,
+
+            email: EmailConfig::from_env(base.email)?,
+
+            push: PushConfig::from_env(base.push)?,
+
+            geolocation: GeolocationConfig::from_env(base.geolocation)?,
+
+            error_reporting: ErrorReportingConfig::from_env(base.error_reporting)?,
+
+            exports: ExportConfig::from_env(base.exports)?,
+
+            risk: RiskConfig::from_env(base.risk)?,
+            replay_protection: ReplayProtectionConfig::from_env(base.replay_protection)?,
+            region: RegionConfig::from_env(base.region)?,
+            market_suspension: MarketSuspensionConfig::from_env(base.market_suspension)?,
+            stream_quotas: StreamQuotaConfig::from_env(base.stream_quotas)?,
+        };
+
+        if config.database_url.is_empty() && config.secrets.database_url.is_none() {
+            bail!("DATABASE_URL must be set, in the environment, as database_url in the config file or as a secret");
+        }
+        if config.bind_address.parse::<std::net::SocketAddr>().is_err() {
+            bail!("BIND_ADDRESS must be a socket address such as 0.0.0.0:3001");
+        }
+        if config.max_concurrent_streams == 0 {
+            bail!("MAX_CONCURRENT_STREAMS must be greater than zero");
+        }
+        if config.region.replicate_events && config.event_bus.backend == EventBusKind::None {
+            bail!("REGION_REPLICATE_EVENTS needs an event bus; set EVENT_BUS_BACKEND");
+        }
+        // The breaker cancels the adapter's call at its timeout, retries and all
+        if config.analytics_adapter.enabled
+            && config.analytics_adapter.retry_budget_ms() > config.orchestrator.circuit_breaker.call_timeout_ms
+        {
+            bail!(
+                "The analytics adapter can take {} ms with retries, longer than AI_SYSTEM_TIMEOUT_MS ({} ms); \
+                 lower ANALYTICS_ADAPTER_TIMEOUT_MS or ANALYTICS_ADAPTER_MAX_RETRIES, or raise AI_SYSTEM_TIMEOUT_MS",
+                config.analytics_adapter.retry_budget_ms(),
+                config.orchestrator.circuit_breaker.call_timeout_ms,
+            );
+        }
+
+        Ok(config)
+    }
+
+    /// The effective configuration as TOML, with secrets and URL passwords masked.
+    pub fn to_redacted_toml(&self) -> Result<String> {
+        let mut config = self.clone();
+        config.database_url = redact_url_password(&config.database_url);
+        config.redis_url = redact_url_password(&config.redis_url);
+        for url in &mut config.read_replicas.urls {
+            *url = redact_url_password(url);
+        }
+        for secret in [
+            &mut config.admin_api.token,
+            &mut config.user_auth.jwt_secret,
+            &mut config.orchestrator.alerts.pagerduty_routing_key,
+            &mut config.secrets.vault_token,
+            &mut config.email.smtp_password,
+            &mut config.datagram_ingest.secret,
+            &mut config.error_reporting.dsn,
+            &mut config.error_reporting.user_id_key,
+            &mut config.exports.local_signing_key,
+        ] {
+            if secret.is_some() {
+                *secret = Some(REDACTED.to_string());
+            }
+        }
+
+        toml::to_string_pretty(&config).context("Failed to render configuration as TOML")
+    }
+}
+
+const REDACTED: &str = "redacted";
+
+pub(crate) fn redact_url_password(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut parsed) if parsed.password().is_some() => {
+            let _ = parsed.set_password(Some(REDACTED));
+            parsed.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// How the process was asked to start: `morphine [--config <path>] <command>`.
+#[derive(Debug, Clone, Parser)]
+#[command(name = "morphine", version, about = "Morphine core service")]
+pub struct LaunchOptions {
+    // With neither the flag nor MORPHINE_CONFIG, only defaults and the environment apply
+    #[arg(long = "config", global = true, env = "MORPHINE_CONFIG", value_name = "PATH")]
+    pub config_path: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Serve the REST, WebSocket and gRPC APIs (the default)
+    Serve {
+        /// Apply pending migrations before serving, for local development
+        #[arg(long)]
+        migrate: bool,
+        /// Refuse to start while migrations are pending or the schema has drifted
+        #[arg(long, env = "MORPHINE_REQUIRE_MIGRATED")]
+        require_migrated: bool,
+    },
+    /// Apply pending database migrations and exit
+    Migrate {
+        /// Report applied, pending and drifted migrations without applying any
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Load demo streams, users and exclusion zones; already-seeded data is left alone
+    Seed {
+        /// Tenant to seed; the default tenant if omitted
+        #[arg(long)]
+        tenant: Option<String>,
+    },
+    /// Write balances, open bets and the ledger as of one instant to a JSON file
+    ExportSnapshot {
+        /// File to write; `-` writes to stdout
+        #[arg(long, value_name = "PATH")]
+        output: PathBuf,
+        /// Tenant to export; the default tenant if omitted
+        #[arg(long)]
+        tenant: Option<String>,
+        /// Only ledger entries from this RFC 3339 instant on; the whole ledger if omitted
+        #[arg(long, value_name = "TIMESTAMP")]
+        ledger_since: Option<chrono::DateTime<chrono::Utc>>,
+    },
+    /// Write streams, bets and balances as they stood at one instant, rebuilt from the
+    /// state event log, to a JSON file
+    StateAsOf {
+        /// RFC 3339 instant to rebuild state as of
+        #[arg(long, value_name = "TIMESTAMP")]
+        as_of: chrono::DateTime<chrono::Utc>,
+        /// File to write; `-` writes to stdout
+        #[arg(long, value_name = "PATH", default_value = "-")]
+        output: PathBuf,
+        /// Tenant to read; the default tenant if omitted
+        #[arg(long)]
+        tenant: Option<String>,
+        /// Only this bettor's bets and balances, and the streams they're on
+        #[arg(long)]
+        user_id: Option<String>,
+        /// Only this stream and the bets and balances on it
+        #[arg(long)]
+        stream_id: Option<String>,
+    },
+    /// Drive a running instance with simulated viewers, bettors and analytics pushes and
+    /// report latency percentiles per operation
+    Loadtest(LoadTestOptions),
+    /// Validate the configuration and exit non-zero if it is invalid
+    CheckConfig {
+        /// Also print the effective configuration, with secrets masked
+        #[arg(long)]
+        print: bool,
+    },
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Command::Serve { migrate: false, require_migrated: false }
+    }
+}
+
+/// When the dreaming module runs its background synthesis cycles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DreamingConfig {
+    // Disables scheduled cycles; manual triggers still work
+    pub schedule_enabled: bool,
+    pub tick_interval_secs: u64,
+    // Cycles may only start in the first `window_length_secs` of every `window_period_secs`
+    pub window_period_secs: u64,
+    pub window_length_secs: u64,
+    // Idle detection: load below this and no new experience for `idle_secs`
+    pub idle_load_threshold: f64,
+    pub idle_secs: u64,
+    pub min_experiences: usize,
+    pub experience_buffer_size: usize,
+    // Past this, the patterns least recently reinforced are forgotten first
+    pub max_patterns: usize,
+    pub max_dream_duration_ms: u64,
+}
+
+impl Default for DreamingConfig {
+    fn default() -> Self {
+        Self {
+            schedule_enabled: true,
+            tick_interval_secs: 300,
+            window_period_secs: 3600,
+            window_length_secs: 300,
+            idle_load_threshold: 0.3,
+            idle_secs: 60,
+            min_experiences: 10,
+            experience_buffer_size: 1000,
+            max_patterns: 10_000,
+            max_dream_duration_ms: 30_000,
+        }
+    }
+}
+
+impl DreamingConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = DreamingConfig {
+            schedule_enabled: env_or("DREAMING_SCHEDULE_ENABLED", base.schedule_enabled)?,
+            tick_interval_secs: env_or("DREAMING_TICK_INTERVAL_SECS", base.tick_interval_secs)?,
+            window_period_secs: env_or("DREAMING_WINDOW_PERIOD_SECS", base.window_period_secs)?,
+            window_length_secs: env_or("DREAMING_WINDOW_LENGTH_SECS", base.window_length_secs)?,
+            idle_load_threshold: env_or("DREAMING_IDLE_LOAD_THRESHOLD", base.idle_load_threshold)?,
+            idle_secs: env_or("DREAMING_IDLE_SECS", base.idle_secs)?,
+            min_experiences: env_or("DREAMING_MIN_EXPERIENCES", base.min_experiences)?,
+            experience_buffer_size: env_or("DREAMING_EXPERIENCE_BUFFER_SIZE", base.experience_buffer_size)?,
+            max_patterns: env_or("DREAMING_MAX_PATTERNS", base.max_patterns)?,
+            max_dream_duration_ms: env_or("DREAMING_MAX_DURATION_MS", base.max_dream_duration_ms)?,
+        };
+
+        if config.tick_interval_secs == 0 {
+            bail!("DREAMING_TICK_INTERVAL_SECS must be greater than zero");
+        }
+        if config.window_period_secs == 0 || config.window_length_secs > config.window_period_secs {
+            bail!("DREAMING_WINDOW_LENGTH_SECS must not exceed a non-zero DREAMING_WINDOW_PERIOD_SECS");
+        }
+        if config.experience_buffer_size == 0 {
+            bail!("DREAMING_EXPERIENCE_BUFFER_SIZE must be greater than zero");
+        }
+        if config.max_patterns == 0 {
+            bail!("DREAMING_MAX_PATTERNS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Partial-result retention and recovery for low-confidence decisions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LactateConfig {
+    // Decisions below this confidence are kept as partial results
+    pub partial_confidence_threshold: f64,
+    pub partial_ttl_secs: u64,
+    pub recovery_interval_secs: u64,
+    // Stored partials are re-queued only while glycolytic load is below this
+    pub recovery_load_threshold: f64,
+    pub max_recovery_attempts: u32,
+    // Fold stored partials into the next context that arrives for the same stream
+    pub merge_on_new_evidence: bool,
+}
+
+impl Default for LactateConfig {
+    fn default() -> Self {
+        Self {
+            partial_confidence_threshold: 0.8,
+            partial_ttl_secs: 3600,
+            recovery_interval_secs: 15,
+            recovery_load_threshold: 0.5,
+            max_recovery_attempts: 3,
+            merge_on_new_evidence: true,
+        }
+    }
+}
+
+impl LactateConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = LactateConfig {
+            partial_confidence_threshold: env_or("LACTATE_PARTIAL_CONFIDENCE_THRESHOLD", base.partial_confidence_threshold)?,
+            partial_ttl_secs: env_or("LACTATE_PARTIAL_TTL_SECS", base.partial_ttl_secs)?,
+            recovery_interval_secs: env_or("LACTATE_RECOVERY_INTERVAL_SECS", base.recovery_interval_secs)?,
+            recovery_load_threshold: env_or("LACTATE_RECOVERY_LOAD_THRESHOLD", base.recovery_load_threshold)?,
+            max_recovery_attempts: env_or("LACTATE_MAX_RECOVERY_ATTEMPTS", base.max_recovery_attempts)?,
+            merge_on_new_evidence: env_or("LACTATE_MERGE_ON_NEW_EVIDENCE", base.merge_on_new_evidence)?,
+        };
+
+        if config.recovery_interval_secs == 0 {
+            bail!("LACTATE_RECOVERY_INTERVAL_SECS must be greater than zero");
+        }
+        if !(0.0..=1.0).contains(&config.partial_confidence_threshold) {
+            bail!("LACTATE_PARTIAL_CONFIDENCE_THRESHOLD must be between 0 and 1");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Periodic AI system health probes and quarantine backoff.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthProbeConfig {
+    pub probe_interval_secs: u64,
+    pub probe_timeout_ms: u64,
+    pub quarantine_after_failures: u32,
+    // Quarantine doubles from the base on each consecutive quarantine, up to the max
+    pub quarantine_base_secs: u64,
+    pub quarantine_max_secs: u64,
+    // Consecutive probe failures after which a system is removed entirely
+    pub deregister_after_failures: u32,
+}
+
+impl Default for HealthProbeConfig {
+    fn default() -> Self {
+        Self {
+            probe_interval_secs: 30,
+            probe_timeout_ms: 2000,
+            quarantine_after_failures: 2,
+            quarantine_base_secs: 30,
+            quarantine_max_secs: 1800,
+            deregister_after_failures: 10,
+        }
+    }
+}
+
+impl HealthProbeConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = HealthProbeConfig {
+            probe_interval_secs: env_or("AI_HEALTH_PROBE_INTERVAL_SECS", base.probe_interval_secs)?,
+            probe_timeout_ms: env_or("AI_HEALTH_PROBE_TIMEOUT_MS", base.probe_timeout_ms)?,
+            quarantine_after_failures: env_or("AI_QUARANTINE_AFTER_FAILURES", base.quarantine_after_failures)?,
+            quarantine_base_secs: env_or("AI_QUARANTINE_BASE_SECS", base.quarantine_base_secs)?,
+            quarantine_max_secs: env_or("AI_QUARANTINE_MAX_SECS", base.quarantine_max_secs)?,
+            deregister_after_failures: env_or("AI_DEREGISTER_AFTER_FAILURES", base.deregister_after_failures)?,
+        };
+
+        if config.probe_interval_secs == 0 {
+            bail!("AI_HEALTH_PROBE_INTERVAL_SECS must be greater than zero");
+        }
+        if config.deregister_after_failures <= config.quarantine_after_failures {
+            bail!("AI_DEREGISTER_AFTER_FAILURES must be greater than AI_QUARANTINE_AFTER_FAILURES");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Step sizes and bounds for outcome-feedback weight learning.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeedbackConfig {
+    pub learning_rate: f64,
+    // Largest relative change a single outcome can make to a weight
+    pub max_step: f64,
+    pub min_weight: f64,
+    pub max_weight: f64,
+    pub history_size: usize,
+    // Recent decisions whose signals are kept in memory for feedback
+    pub signal_cache_size: usize,
+}
+
+impl Default for FeedbackConfig {
+    fn default() -> Self {
+        Self {
+            learning_rate: 0.05,
+            max_step: 0.1,
+            min_weight: 0.05,
+            max_weight: 5.0,
+            history_size: 1000,
+            signal_cache_size: 10_000,
+        }
+    }
+}
+
+impl FeedbackConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = FeedbackConfig {
+            learning_rate: env_or("FEEDBACK_LEARNING_RATE", base.learning_rate)?,
+            max_step: env_or("FEEDBACK_MAX_STEP", base.max_step)?,
+            min_weight: env_or("FEEDBACK_MIN_WEIGHT", base.min_weight)?,
+            max_weight: env_or("FEEDBACK_MAX_WEIGHT", base.max_weight)?,
+            history_size: env_or("FEEDBACK_HISTORY_SIZE", base.history_size)?,
+            signal_cache_size: env_or("FEEDBACK_SIGNAL_CACHE_SIZE", base.signal_cache_size)?,
+        };
+
+        if !(0.0..1.0).contains(&config.max_step) {
+            bail!("FEEDBACK_MAX_STEP must be in [0, 1)");
+        }
+        if config.min_weight <= 0.0 || config.min_weight > config.max_weight {
+            bail!("FEEDBACK_MIN_WEIGHT must be positive and not exceed FEEDBACK_MAX_WEIGHT");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Accelerator devices available to CV-heavy AI systems.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AcceleratorConfig {
+    // How long a call waits for device capacity before the system is skipped
+    pub reservation_timeout_ms: u64,
+    pub devices: Vec<AcceleratorDevice>,
+}
+
+impl Default for AcceleratorConfig {
+    fn default() -> Self {
+        Self {
+            devices: Vec::new(),
+            reservation_timeout_ms: 200,
+        }
+    }
+}
+
+impl AcceleratorConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        // ACCELERATOR_DEVICES="gpu0:4:24576,gpu1:2:16384" (id:slots:vram_mb)
+        let devices = match std::env::var("ACCELERATOR_DEVICES") {
+            Ok(spec) if !spec.trim().is_empty() => spec
+                .split(',')
+                .map(|device| {
+                    let parts: Vec<&str> = device.trim().split(':').collect();
+                    if parts.len() != 3 {
+                        bail!("ACCELERATOR_DEVICES entries must be id:slots:vram_mb, got '{}'", device);
+                    }
+                    Ok(AcceleratorDevice {
+                        device_id: parts[0].to_string(),
+                        slots: parts[1].parse()
+                            .with_context(|| format!("Invalid slot count in '{}'", device))?,
+                        vram_mb: parts[2].parse()
+                            .with_context(|| format!("Invalid VRAM size in '{}'", device))?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            _ => base.devices,
+        };
+
+        Ok(AcceleratorConfig {
+            devices,
+            reservation_timeout_ms: env_or("ACCELERATOR_RESERVATION_TIMEOUT_MS", base.reservation_timeout_ms)?,
+        })
+    }
+}
+
+/// Retention for the orchestrator's knowledge graph.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KnowledgeGraphConfig {
+    pub max_facts: usize,
+    // How long a fact derived from analytics stays valid
+    pub fact_ttl_secs: u64,
+}
+
+impl Default for KnowledgeGraphConfig {
+    fn default() -> Self {
+        Self {
+            max_facts: 50_000,
+            fact_ttl_secs: 600,
+        }
+    }
+}
+
+impl KnowledgeGraphConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        Ok(KnowledgeGraphConfig {
+            max_facts: env_or("KNOWLEDGE_MAX_FACTS", base.max_facts)?,
+            fact_ttl_secs: env_or("KNOWLEDGE_FACT_TTL_SECS", base.fact_ttl_secs)?,
+        })
+    }
+}
+
+/// Sizing and autoscaling for the glycolytic work-stealing executor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecutorConfig {
+    // 0 means one worker per CPU
+    pub min_workers: usize,
+    pub max_workers: usize,
+    pub scale_up_load: f64,
+    pub scale_down_load: f64,
+    // Weight of the newest sample in the latency and throughput averages
+    pub metrics_smoothing: f64,
+}
+
+impl Default for ExecutorConfig {
+    fn default() -> Self {
+        Self {
+            min_workers: 0,
+            max_workers: 32,
+            scale_up_load: 0.8,
+            scale_down_load: 0.3,
+            metrics_smoothing: 0.1,
+        }
+    }
+}
+
+impl ExecutorConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = ExecutorConfig {
+            min_workers: env_or("EXECUTOR_MIN_WORKERS", base.min_workers)?,
+            max_workers: env_or("EXECUTOR_MAX_WORKERS", base.max_workers)?,
+            scale_up_load: env_or("EXECUTOR_SCALE_UP_LOAD", base.scale_up_load)?,
+            scale_down_load: env_or("EXECUTOR_SCALE_DOWN_LOAD", base.scale_down_load)?,
+            metrics_smoothing: env_or("EXECUTOR_METRICS_SMOOTHING", base.metrics_smoothing)?,
+        };
+
+        if config.max_workers == 0 {
+            bail!("EXECUTOR_MAX_WORKERS must be at least 1");
+        }
+        if config.scale_down_load >= config.scale_up_load {
+            bail!("EXECUTOR_SCALE_DOWN_LOAD must be below EXECUTOR_SCALE_UP_LOAD");
+        }
+        if !(0.0..=1.0).contains(&config.metrics_smoothing) || config.metrics_smoothing == 0.0 {
+            bail!("EXECUTOR_METRICS_SMOOTHING must be in (0, 1]");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Default batching of analytics frames into contexts; streams can override it at runtime.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub kind: WindowKind,
+    pub window_ms: u64,
+    pub slide_ms: u64,
+    pub max_frames: usize,
+    pub min_load: f64,
+    // How often time-based windows are checked for closing
+    pub flush_interval_ms: u64,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            kind: WindowKind::None,
+            window_ms: 500,
+            slide_ms: 250,
+            max_frames: 50,
+            min_load: 0.0,
+            flush_interval_ms: 50,
+        }
+    }
+}
+
+impl WindowConfig {
+    pub fn policy(&self) -> WindowPolicy {
+        WindowPolicy {
+            kind: self.kind,
+            window_ms: self.window_ms,
+            slide_ms: self.slide_ms,
+            max_frames: self.max_frames,
+            min_load: self.min_load,
+        }
+    }
+
+    pub fn from_env(base: Self) -> Result<Self> {
+        let kind = match std::env::var("ORCHESTRATOR_WINDOW_KIND").as_deref() {
+            Ok("none") => WindowKind::None,
+            Err(_) => base.kind,
+            Ok("tumbling") => WindowKind::Tumbling,
+            Ok("sliding") => WindowKind::Sliding,
+            Ok(other) => bail!(
+                "ORCHESTRATOR_WINDOW_KIND must be 'none', 'tumbling' or 'sliding', got '{}'",
+                other
+            ),
+        };
+
+        let config = WindowConfig {
+            kind,
+            window_ms: env_or("ORCHESTRATOR_WINDOW_MS", base.window_ms)?,
+            slide_ms: env_or("ORCHESTRATOR_WINDOW_SLIDE_MS", base.slide_ms)?,
+            max_frames: env_or("ORCHESTRATOR_WINDOW_MAX_FRAMES", base.max_frames)?,
+            min_load: env_or("ORCHESTRATOR_WINDOW_MIN_LOAD", base.min_load)?,
+            flush_interval_ms: env_or("ORCHESTRATOR_WINDOW_FLUSH_INTERVAL_MS", base.flush_interval_ms)?,
+        };
+
+        config.policy().validate().map_err(|e| anyhow::anyhow!(e))?;
+        if config.flush_interval_ms == 0 {
+            bail!("ORCHESTRATOR_WINDOW_FLUSH_INTERVAL_MS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Which decisions raise alerts and where the alerts are delivered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertConfig {
+    // TransactionValidation decisions below this confidence raise an alert
+    pub transaction_confidence_threshold: f64,
+    // AlertGeneration decisions at or above this confidence are critical
+    pub critical_confidence: f64,
+    // Repeats of the same alert within this window are counted, not re-sent
+    pub dedup_window_secs: u64,
+    pub webhook_urls: Vec<String>,
+    pub pagerduty_routing_key: Option<String>,
+    pub delivery_timeout_ms: u64,
+    pub history_size: usize,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            transaction_confidence_threshold: 0.4,
+            critical_confidence: 0.8,
+            dedup_window_secs: 300,
+            webhook_urls: Vec::new(),
+            pagerduty_routing_key: None,
+            delivery_timeout_ms: 3_000,
+            history_size: 200,
+        }
+    }
+}
+
+impl AlertConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        // ALERT_WEBHOOK_URLS="https://ops.example.com/hook,https://chat.example.com/hook"
+        let webhook_urls = match std::env::var("ALERT_WEBHOOK_URLS") {
+            Ok(urls) => urls.split(',')
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+                .collect(),
+            Err(_) => base.webhook_urls,
+        };
+
+        let config = AlertConfig {
+            transaction_confidence_threshold: env_or(
+                "ALERT_TRANSACTION_CONFIDENCE_THRESHOLD",
+                base.transaction_confidence_threshold,
+            )?,
+            critical_confidence: env_or("ALERT_CRITICAL_CONFIDENCE", base.critical_confidence)?,
+            dedup_window_secs: env_or("ALERT_DEDUP_WINDOW_SECS", base.dedup_window_secs)?,
+            webhook_urls,
+            pagerduty_routing_key: env_opt("PAGERDUTY_ROUTING_KEY").or(base.pagerduty_routing_key),
+            delivery_timeout_ms: env_or("ALERT_DELIVERY_TIMEOUT_MS", base.delivery_timeout_ms)?,
+            history_size: env_or("ALERT_HISTORY_SIZE", base.history_size)?,
+        };
+
+        if !(0.0..=1.0).contains(&config.transaction_confidence_threshold)
+            || !(0.0..=1.0).contains(&config.critical_confidence)
+        {
+            bail!("Alert confidence thresholds must be between 0 and 1");
+        }
+
+        Ok(config)
+    }
+}
+
+/// ONNX models the intuition layer runs for pattern model versions that
+/// name one, e.g. event-probability predictors trained offline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OnnxConfig {
+    // Model paths in pattern model versions are relative to this
+    pub model_dir: String,
+    // How long one inference may take, queueing on the glycolytic executor included,
+    // for models that don't set their own
+    pub default_budget_ms: u64,
+    // Inferences run on zeroed input before a model goes live; the slowest must be within budget
+    pub warmup_runs: u32,
+    pub max_model_bytes: u64,
+}
+
+impl Default for OnnxConfig {
+    fn default() -> Self {
+        Self {
+            model_dir: "./models".to_string(),
+            default_budget_ms: 20,
+            warmup_runs: 5,
+            max_model_bytes: 256 * 1024 * 1024,
+        }
+    }
+}
+
+impl OnnxConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = OnnxConfig {
+            model_dir: env_or("ONNX_MODEL_DIR", base.model_dir)?,
+            default_budget_ms: env_or("ONNX_DEFAULT_BUDGET_MS", base.default_budget_ms)?,
+            warmup_runs: env_or("ONNX_WARMUP_RUNS", base.warmup_runs)?,
+            max_model_bytes: env_or("ONNX_MAX_MODEL_BYTES", base.max_model_bytes)?,
+        };
+
+        if config.default_budget_ms == 0 || config.warmup_runs == 0 || config.max_model_bytes == 0 {
+            bail!("ONNX_DEFAULT_BUDGET_MS, ONNX_WARMUP_RUNS and ONNX_MAX_MODEL_BYTES must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Markets opened from confident decisions whose ONNX model predicts an
+/// event, taking bets on it happening within a short window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpportunityConfig {
+    pub enabled: bool,
+    // Decisions less confident than this open nothing
+    pub min_confidence: f64,
+    // Predictions outside this range make markets not worth offering: near-certain or long shots
+    pub min_probability: f64,
+    pub max_probability: f64,
+    // How long a market takes bets, and the window its bets predict over
+    pub window_secs: u64,
+    // Taken off fair odds for the suggested price
+    pub margin: f64,
+}
+
+impl Default for OpportunityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_confidence: 0.75,
+            min_probability: 0.05,
+            max_probability: 0.95,
+            window_secs: 60,
+            margin: 0.05,
+        }
+    }
+}
+
+impl OpportunityConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = OpportunityConfig {
+            enabled: env_or("OPPORTUNITIES_ENABLED", base.enabled)?,
+            min_confidence: env_or("OPPORTUNITIES_MIN_CONFIDENCE", base.min_confidence)?,
+            min_probability: env_or("OPPORTUNITIES_MIN_PROBABILITY", base.min_probability)?,
+            max_probability: env_or("OPPORTUNITIES_MAX_PROBABILITY", base.max_probability)?,
+            window_secs: env_or("OPPORTUNITIES_WINDOW_SECS", base.window_secs)?,
+            margin: env_or("OPPORTUNITIES_MARGIN", base.margin)?,
+        };
+
+        if !(0.0..=1.0).contains(&config.min_confidence) {
+            bail!("OPPORTUNITIES_MIN_CONFIDENCE must be between 0 and 1");
+        }
+        if config.min_probability <= 0.0 || config.max_probability >= 1.0 || config.min_probability > config.max_probability {
+            bail!("OPPORTUNITIES_MIN_PROBABILITY and OPPORTUNITIES_MAX_PROBABILITY must be strictly between 0 and 1, minimum first");
+        }
+        if config.window_secs == 0 || config.window_secs > MAX_BET_WINDOW_SECS as u64 {
+            bail!("OPPORTUNITIES_WINDOW_SECS must be between 1 and {}", MAX_BET_WINDOW_SECS);
+        }
+        if !(0.0..1.0).contains(&config.margin) {
+            bail!("OPPORTUNITIES_MARGIN must be at least 0 and less than 1");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Archiving of incoming analytics and replay of them through the pipeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReplayConfig {
+    // Persist every analytics frame to analytics_events so it can be replayed
+    pub archive_analytics: bool,
+    // Frames are written in batches of up to this many, at least every flush interval
+    pub archive_batch_size: usize,
+    pub archive_flush_interval_ms: u64,
+    // Frames waiting to be written; more are dropped until the writer catches up
+    pub archive_queue_capacity: usize,
+    // Upper bound on frames loaded for one replay run
+    pub max_frames: i64,
+    // Finished runs kept for inspection before the oldest are dropped
+    pub max_runs_retained: usize,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            archive_analytics: true,
+            archive_batch_size: 500,
+            archive_flush_interval_ms: 1000,
+            archive_queue_capacity: 20_000,
+            max_frames: 10_000,
+            max_runs_retained: 20,
+        }
+    }
+}
+
+impl ReplayConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = ReplayConfig {
+            archive_analytics: env_or("REPLAY_ARCHIVE_ANALYTICS", base.archive_analytics)?,
+            archive_batch_size: env_or("REPLAY_ARCHIVE_BATCH_SIZE", base.archive_batch_size)?,
+            archive_flush_interval_ms: env_or("REPLAY_ARCHIVE_FLUSH_MS", base.archive_flush_interval_ms)?,
+            archive_queue_capacity: env_or("REPLAY_ARCHIVE_QUEUE_CAPACITY", base.archive_queue_capacity)?,
+            max_frames: env_or("REPLAY_MAX_FRAMES", base.max_frames)?,
+            max_runs_retained: env_or("REPLAY_MAX_RUNS", base.max_runs_retained)?,
+        };
+
+        if config.max_frames < 1 || config.max_runs_retained == 0 {
+            bail!("REPLAY_MAX_FRAMES and REPLAY_MAX_RUNS must be greater than zero");
+        }
+        if config.archive_batch_size == 0 || config.archive_flush_interval_ms == 0 {
+            bail!("REPLAY_ARCHIVE_BATCH_SIZE and REPLAY_ARCHIVE_FLUSH_MS must be greater than zero");
+        }
+        if config.archive_queue_capacity < config.archive_batch_size {
+            bail!("REPLAY_ARCHIVE_QUEUE_CAPACITY must be at least REPLAY_ARCHIVE_BATCH_SIZE");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Access control for the orchestrator admin endpoints.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminApiConfig {
+    // Bearer token operators must present; admin endpoints are disabled without one
+    pub token: Option<String>,
+}
+
+impl AdminApiConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let token = env_opt("ADMIN_API_TOKEN").or(base.token);
+
+        if let Some(token) = &token {
+            if token.len() < 16 {
+                bail!("ADMIN_API_TOKEN must be at least 16 characters");
+            }
+        }
+
+        Ok(AdminApiConfig { token })
+    }
+}
+
+/// Service-to-service API key authentication.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    // How long an authenticated key is trusted before it's re-read from the
+    // database; bounds how long a revocation takes to reach other instances
+    pub cache_ttl_secs: u64,
+}
+
+impl Default for ApiKeyConfig {
+    fn default() -> Self {
+        Self { cache_ttl_secs: 30 }
+    }
+}
+
+impl ApiKeyConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        Ok(ApiKeyConfig {
+            cache_ttl_secs: env_or("API_KEY_CACHE_TTL_SECS", base.cache_ttl_secs)?,
+        })
+    }
+}
+
+/// User accounts and the tokens they sign in with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UserAuthConfig {
+    // HS256 secret shared with the API gateway; user tokens are rejected without one
+    pub jwt_secret: Option<String>,
+    // Roles given to new accounts and to tokens that carry no `roles` claim
+    pub default_roles: Vec<Role>,
+    pub token_ttl_secs: u64,
+    pub email_verification_ttl_secs: u64,
+    // Receives verification requests and sends the email; logged only when unset
+    pub email_verification_webhook_url: Option<String>,
+}
+
+impl Default for UserAuthConfig {
+    fn default() -> Self {
+        Self {
+            jwt_secret: None,
+            default_roles: vec![Role::Bettor],
+            token_ttl_secs: 86_400,
+            email_verification_ttl_secs: 86_400,
+            email_verification_webhook_url: None,
+        }
+    }
+}
+
+impl UserAuthConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let jwt_secret = env_opt("JWT_SECRET").or(base.jwt_secret);
+
+        if let Some(secret) = &jwt_secret {
+            if secret.len() < 16 {
+                bail!("JWT_SECRET must be at least 16 characters");
+            }
+        }
+
+        // USER_DEFAULT_ROLES="bettor,creator"
+        let default_roles = match std::env::var("USER_DEFAULT_ROLES") {
+            Ok(roles) => roles.split(',')
+                .map(|role| role.trim())
+                .filter(|role| !role.is_empty())
+                .map(|role| match role {
+                    "admin" => Ok(Role::Admin),
+                    "creator" => Ok(Role::Creator),
+                    "bettor" => Ok(Role::Bettor),
+                    "service" => Ok(Role::Service),
+                    other => Err(anyhow::anyhow!("USER_DEFAULT_ROLES contains unknown role '{}'", other)),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Err(_) => base.default_roles,
+        };
+
+        let config = UserAuthConfig {
+            jwt_secret,
+            default_roles,
+            token_ttl_secs: env_or("USER_TOKEN_TTL_SECS", base.token_ttl_secs)?,
+            email_verification_ttl_secs: env_or(
+                "EMAIL_VERIFICATION_TTL_SECS",
+                base.email_verification_ttl_secs,
+            )?,
+            email_verification_webhook_url: env_opt("EMAIL_VERIFICATION_WEBHOOK_URL")
+                .or(base.email_verification_webhook_url),
+        };
+
+        if config.token_ttl_secs == 0 || config.email_verification_ttl_secs == 0 {
+            bail!("USER_TOKEN_TTL_SECS and EMAIL_VERIFICATION_TTL_SECS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Request rate limits, counted in Redis so they hold across instances.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    // Fixed window the limits below are counted over
+    pub window_secs: u64,
+    // Requests per window for ordinary endpoints
+    pub default_limit: u64,
+    // Requests per window for expensive ones: placing bets, verifying locations
+    pub expensive_limit: u64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_secs: 60,
+            default_limit: 300,
+            expensive_limit: 20,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = RateLimitConfig {
+            enabled: env_or("RATE_LIMIT_ENABLED", base.enabled)?,
+            window_secs: env_or("RATE_LIMIT_WINDOW_SECS", base.window_secs)?,
+            default_limit: env_or("RATE_LIMIT_DEFAULT", base.default_limit)?,
+            expensive_limit: env_or("RATE_LIMIT_EXPENSIVE", base.expensive_limit)?,
+        };
+
+        if config.window_secs == 0 || config.default_limit == 0 || config.expensive_limit == 0 {
+            bail!("RATE_LIMIT_WINDOW_SECS, RATE_LIMIT_DEFAULT and RATE_LIMIT_EXPENSIVE must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Graceful shutdown behaviour.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShutdownConfig {
+    // How long to wait for background writes after the server stops serving
+    pub drain_timeout_secs: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self { drain_timeout_secs: 30 }
+    }
+}
+
+impl ShutdownConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        Ok(ShutdownConfig {
+            drain_timeout_secs: env_or("SHUTDOWN_DRAIN_TIMEOUT_SECS", base.drain_timeout_secs)?,
+        })
+    }
+}
+
+/// Handling of requests to the unversioned `/api/...` paths.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiVersionConfig {
+    // Keep serving unversioned paths as v1, marked deprecated
+    pub legacy_paths_enabled: bool,
+    // HTTP-date sent as `Sunset` on unversioned responses, once one is announced
+    pub legacy_sunset: Option<String>,
+}
+
+impl Default for ApiVersionConfig {
+    fn default() -> Self {
+        Self {
+            legacy_paths_enabled: true,
+            legacy_sunset: None,
+        }
+    }
+}
+
+impl ApiVersionConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let legacy_sunset = env_opt("API_LEGACY_SUNSET").or(base.legacy_sunset);
+
+        // e.g. "Sat, 01 Nov 2025 00:00:00 GMT"
+        if let Some(sunset) = &legacy_sunset {
+            if chrono::DateTime::parse_from_rfc2822(sunset).is_err() {
+                bail!("API_LEGACY_SUNSET must be an HTTP date");
+            }
+        }
+
+        Ok(ApiVersionConfig {
+            legacy_paths_enabled: env_or("API_LEGACY_PATHS_ENABLED", base.legacy_paths_enabled)?,
+            legacy_sunset,
+        })
+    }
+}
+
+/// Outbound webhook delivery to integrators.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub enabled: bool,
+    // Attempts before a delivery is dead-lettered
+    pub max_attempts: u32,
+    // Backoff after the first failure; doubles with each further attempt
+    pub initial_backoff_secs: u64,
+    pub max_backoff_secs: u64,
+    pub delivery_timeout_ms: u64,
+    // How often the dispatcher looks for due deliveries, and how many it takes at once
+    pub poll_interval_ms: u64,
+    pub batch_size: i64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_attempts: 8,
+            initial_backoff_secs: 10,
+            max_backoff_secs: 3600,
+            delivery_timeout_ms: 5000,
+            poll_interval_ms: 1000,
+            batch_size: 50,
+        }
+    }
+}
+
+impl WebhookConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = WebhookConfig {
+            enabled: env_or("WEBHOOKS_ENABLED", base.enabled)?,
+            max_attempts: env_or("WEBHOOK_MAX_ATTEMPTS", base.max_attempts)?,
+            initial_backoff_secs: env_or("WEBHOOK_INITIAL_BACKOFF_SECS", base.initial_backoff_secs)?,
+            max_backoff_secs: env_or("WEBHOOK_MAX_BACKOFF_SECS", base.max_backoff_secs)?,
+            delivery_timeout_ms: env_or("WEBHOOK_DELIVERY_TIMEOUT_MS", base.delivery_timeout_ms)?,
+            poll_interval_ms: env_or("WEBHOOK_POLL_INTERVAL_MS", base.poll_interval_ms)?,
+            batch_size: env_or("WEBHOOK_BATCH_SIZE", base.batch_size)?,
+        };
+
+        if config.max_attempts == 0 || config.poll_interval_ms == 0 || config.batch_size <= 0 {
+            bail!("WEBHOOK_MAX_ATTEMPTS, WEBHOOK_POLL_INTERVAL_MS and WEBHOOK_BATCH_SIZE must be greater than zero");
+        }
+        if config.initial_backoff_secs > config.max_backoff_secs {
+            bail!("WEBHOOK_INITIAL_BACKOFF_SECS must not exceed WEBHOOK_MAX_BACKOFF_SECS");
+        }
+
+        Ok(config)
+    }
+}
+
+/// The gRPC API served alongside REST for internal services.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GrpcConfig {
+    pub enabled: bool,
+    pub bind_address: String,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bind_address: "0.0.0.0:50051".to_string(),
+        }
+    }
+}
+
+impl GrpcConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = GrpcConfig {
+            enabled: env_or("GRPC_ENABLED", base.enabled)?,
+            bind_address: env_or("GRPC_BIND_ADDRESS", base.bind_address)?,
+        };
+
+        if config.bind_address.parse::<std::net::SocketAddr>().is_err() {
+            bail!("GRPC_BIND_ADDRESS must be a socket address such as 0.0.0.0:50051");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Analytics frames over UDP, one protobuf `AnalyticsDatagram` each, for the
+/// analytics service when an HTTP or gRPC round trip per frame is too slow.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatagramIngestConfig {
+    pub enabled: bool,
+    pub bind_address: String,
+    // Shared with the analytics service to sign datagrams; required when enabled
+    pub secret: Option<String>,
+    // A frame this far or further behind the highest sequence seen means the
+    // producer restarted its count rather than that the frame arrived late
+    pub reorder_window: u64,
+    // Streams whose last sequence is remembered; the least recently heard from is forgotten first
+    pub max_streams: usize,
+    // Streams silent this long start their count afresh
+    pub stream_idle_secs: u64,
+}
+
+impl Default for DatagramIngestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "0.0.0.0:50052".to_string(),
+            secret: None,
+            reorder_window: 1024,
+            max_streams: 10000,
+            stream_idle_secs: 300,
+        }
+    }
+}
+
+impl DatagramIngestConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = DatagramIngestConfig {
+            enabled: env_or("DATAGRAM_INGEST_ENABLED", base.enabled)?,
+            bind_address: env_or("DATAGRAM_INGEST_BIND_ADDRESS", base.bind_address)?,
+            secret: env_opt("DATAGRAM_INGEST_SECRET").or(base.secret),
+            reorder_window: env_or("DATAGRAM_INGEST_REORDER_WINDOW", base.reorder_window)?,
+            max_streams: env_or("DATAGRAM_INGEST_MAX_STREAMS", base.max_streams)?,
+            stream_idle_secs: env_or("DATAGRAM_INGEST_STREAM_IDLE_SECS", base.stream_idle_secs)?,
+        };
+
+        if config.bind_address.parse::<std::net::SocketAddr>().is_err() {
+            bail!("DATAGRAM_INGEST_BIND_ADDRESS must be a socket address such as 0.0.0.0:50052");
+        }
+        if config.enabled && config.secret.as_ref().map(|secret| secret.len() < 16).unwrap_or(true) {
+            bail!("DATAGRAM_INGEST_SECRET of at least 16 characters is required when DATAGRAM_INGEST_ENABLED is on");
+        }
+        if config.reorder_window == 0 || config.max_streams == 0 || config.stream_idle_secs == 0 {
+            bail!("DATAGRAM_INGEST_REORDER_WINDOW, DATAGRAM_INGEST_MAX_STREAMS and DATAGRAM_INGEST_STREAM_IDLE_SECS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// How the betting engine prices bets. Reloadable without a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BettingConfig {
+    // Decimal odds per bet type, before the time-window adjustment
+    pub binary_odds: f64,
+    pub quantity_odds: f64,
+    pub timing_odds: f64,
+    pub pattern_odds: f64,
+    // Share of the quoted odds the house keeps, between 0 and 0.5
+    pub odds_margin: f64,
+    // Settled bets a bettor needs before ranking on win rate or ROI
+    pub leaderboard_min_settled_bets: u32,
+    // Smallest and largest stake one bet may carry
+    pub min_stake: f64,
+    pub max_stake: f64,
+    // Most a stream's open bets may owe if they all win; bets past it are scaled down. 0 means no limit
+    pub market_liquidity: f64,
+}
+
+impl Default for BettingConfig {
+    fn default() -> Self {
+        Self {
+            binary_odds: 1.9,
+            quantity_odds: 2.1,
+            timing_odds: 2.5,
+            pattern_odds: 3.0,
+            odds_margin: 0.0,
+            leaderboard_min_settled_bets: 10,
+            min_stake: 0.01,
+            max_stake: 1000.0,
+            market_liquidity: 0.0,
+        }
+    }
+}
+
+impl BettingConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = BettingConfig {
+            binary_odds: env_or("BETTING_BINARY_ODDS", base.binary_odds)?,
+            quantity_odds: env_or("BETTING_QUANTITY_ODDS", base.quantity_odds)?,
+            timing_odds: env_or("BETTING_TIMING_ODDS", base.timing_odds)?,
+            pattern_odds: env_or("BETTING_PATTERN_ODDS", base.pattern_odds)?,
+            odds_margin: env_or("BETTING_ODDS_MARGIN", base.odds_margin)?,
+            leaderboard_min_settled_bets: env_or("BETTING_LEADERBOARD_MIN_SETTLED_BETS", base.leaderboard_min_settled_bets)?,
+            min_stake: env_or("BETTING_MIN_STAKE", base.min_stake)?,
+            max_stake: env_or("BETTING_MAX_STAKE", base.max_stake)?,
+            market_liquidity: env_or("BETTING_MARKET_LIQUIDITY", base.market_liquidity)?,
+        };
+
+        for odds in [config.binary_odds, config.quantity_odds, config.timing_odds, config.pattern_odds] {
+            if !odds.is_finite() || odds <= 1.0 {
+                bail!("BETTING_*_ODDS must be greater than 1.0");
+            }
+        }
+        if !(0.0..0.5).contains(&config.odds_margin) {
+            bail!("BETTING_ODDS_MARGIN must be at least 0 and below 0.5");
+        }
+        if !(config.min_stake > 0.0 && config.min_stake <= config.max_stake && config.max_stake.is_finite()) {
+            bail!("BETTING_MIN_STAKE must be positive and no more than BETTING_MAX_STAKE");
+        }
+        if !(config.market_liquidity >= 0.0 && config.market_liquidity.is_finite()) {
+            bail!("BETTING_MARKET_LIQUIDITY must be 0 or more");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Where database and Redis URLs and the JWT secret come from when they are
+/// kept out of the file and environment. Webhook subscriptions can name
+/// secrets from the same provider to sign with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecretsConfig {
+    pub provider: SecretProviderKind,
+    // How often secrets are fetched again to pick up rotations; 0 fetches each one once
+    pub refresh_interval_secs: u64,
+    // file: one file per secret, named after it, as Docker and Kubernetes mount them
+    pub file_dir: String,
+    // vault: a KV version 2 engine
+    pub vault_address: Option<String>,
+    pub vault_mount: String,
+    pub vault_token: Option<String>,
+    // aws: credentials come from AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and AWS_SESSION_TOKEN
+    pub aws_region: Option<String>,
+    // Secret names, `path#key` to pick one key of a structured secret; each replaces the inline setting
+    pub database_url: Option<String>,
+    pub redis_url: Option<String>,
+    pub jwt_secret: Option<String>,
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            provider: SecretProviderKind::None,
+            refresh_interval_secs: 300,
+            file_dir: "/run/secrets".to_string(),
+            vault_address: None,
+            vault_mount: "secret".to_string(),
+            vault_token: None,
+            aws_region: None,
+            database_url: None,
+            redis_url: None,
+            jwt_secret: None,
+        }
+    }
+}
+
+impl SecretsConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let provider = match std::env::var("SECRETS_PROVIDER").as_deref() {
+            Ok("none") => SecretProviderKind::None,
+            Ok("file") => SecretProviderKind::File,
+            Ok("vault") => SecretProviderKind::Vault,
+            Ok("aws") => SecretProviderKind::Aws,
+            Err(_) => base.provider,
+            Ok(other) => bail!("SECRETS_PROVIDER must be 'none', 'file', 'vault' or 'aws', got '{}'", other),
+        };
+
+        let config = SecretsConfig {
+            provider,
+            refresh_interval_secs: env_or("SECRETS_REFRESH_INTERVAL_SECS", base.refresh_interval_secs)?,
+            file_dir: env_or("SECRETS_FILE_DIR", base.file_dir)?,
+            vault_address: env_opt("VAULT_ADDR").or(base.vault_address),
+            vault_mount: env_or("VAULT_KV_MOUNT", base.vault_mount)?,
+            vault_token: env_opt("VAULT_TOKEN").or(base.vault_token),
+            aws_region: env_opt("AWS_REGION").or(base.aws_region),
+            database_url: env_opt("DATABASE_URL_SECRET_NAME").or(base.database_url),
+            redis_url: env_opt("REDIS_URL_SECRET_NAME").or(base.redis_url),
+            jwt_secret: env_opt("JWT_SECRET_NAME").or(base.jwt_secret),
+        };
+
+        match config.provider {
+            SecretProviderKind::None => {
+                if config.database_url.is_some() || config.redis_url.is_some() || config.jwt_secret.is_some() {
+                    bail!("Secret names are set but SECRETS_PROVIDER is 'none'");
+                }
+            }
+            SecretProviderKind::File => {}
+            SecretProviderKind::Vault => {
+                if config.vault_address.is_none() || config.vault_token.is_none() {
+                    bail!("VAULT_ADDR and VAULT_TOKEN must be set for the vault secrets provider");
+                }
+            }
+            SecretProviderKind::Aws => {
+                if config.aws_region.is_none() {
+                    bail!("AWS_REGION must be set for the aws secrets provider");
+                }
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// Which browser origins may call the API. Nothing cross-origin is allowed
+/// until origins are listed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    // Exact origins such as https://app.morphine.example; no wildcards
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    // Lets browsers send cookies and HTTP auth; bearer tokens don't need it
+    pub allow_credentials: bool,
+    // How long browsers may cache a preflight response
+    pub max_age_secs: u64,
+    // Development only: any origin, method and header. Ignores everything above
+    pub permissive: bool,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: ["GET", "POST", "PUT", "PATCH", "DELETE"].map(String::from).to_vec(),
+            allowed_headers: ["authorization", "content-type", "x-api-key", "x-correlation-id", "api-version"]
+                .map(String::from)
+                .to_vec(),
+            allow_credentials: false,
+            max_age_secs: 600,
+            permissive: false,
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        // CORS_ALLOWED_ORIGINS="https://app.morphine.example,https://admin.morphine.example"
+        let config = CorsConfig {
+            allowed_origins: env_list("CORS_ALLOWED_ORIGINS").unwrap_or(base.allowed_origins),
+            allowed_methods: env_list("CORS_ALLOWED_METHODS").unwrap_or(base.allowed_methods),
+            allowed_headers: env_list("CORS_ALLOWED_HEADERS").unwrap_or(base.allowed_headers),
+            allow_credentials: env_or("CORS_ALLOW_CREDENTIALS", base.allow_credentials)?,
+            max_age_secs: env_or("CORS_MAX_AGE_SECS", base.max_age_secs)?,
+            permissive: env_or("CORS_PERMISSIVE", base.permissive)?,
+        };
+
+        for origin in &config.allowed_origins {
+            let valid = reqwest::Url::parse(origin)
+                .map(|url| {
+                    matches!(url.scheme(), "http" | "https")
+                        && url.origin().ascii_serialization() == origin.trim_end_matches('/')
+                })
+                .unwrap_or(false);
+            if !valid {
+                bail!("CORS_ALLOWED_ORIGINS entry '{}' must be a scheme, host and optional port, e.g. https://app.example.com", origin);
+            }
+        }
+        for method in &config.allowed_methods {
+            if method.parse::<axum::http::Method>().is_err() {
+                bail!("CORS_ALLOWED_METHODS entry '{}' is not an HTTP method", method);
+            }
+        }
+        for header in &config.allowed_headers {
+            if header.parse::<axum::http::HeaderName>().is_err() {
+                bail!("CORS_ALLOWED_HEADERS entry '{}' is not a valid header name", header);
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// TLS termination on the HTTP listener itself, for deployments without a
+/// fronting proxy. When enabled, nothing is served over plaintext.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub enabled: bool,
+    // PEM certificate chain and private key
+    pub cert_path: Option<String>,
+    pub key_path: Option<String>,
+    // How often the files are re-read to pick up renewals; 0 reads them once
+    pub cert_reload_interval_secs: u64,
+    // Order certificates for these domains over ACME instead of reading files. Challenges
+    // are answered on the listener, so it must be reachable on port 443
+    pub acme_domains: Vec<String>,
+    pub acme_contact_email: Option<String>,
+    // Issued certificates and the account key survive restarts here
+    pub acme_cache_dir: String,
+    // Let's Encrypt staging, for trying things out; its certificates aren't trusted
+    pub acme_staging: bool,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cert_path: None,
+            key_path: None,
+            cert_reload_interval_secs: 3600,
+            acme_domains: Vec::new(),
+            acme_contact_email: None,
+            acme_cache_dir: "./storage/acme".to_string(),
+            acme_staging: false,
+        }
+    }
+}
+
+impl TlsConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = TlsConfig {
+            enabled: env_or("TLS_ENABLED", base.enabled)?,
+            cert_path: env_opt("TLS_CERT_PATH").or(base.cert_path),
+            key_path: env_opt("TLS_KEY_PATH").or(base.key_path),
+            cert_reload_interval_secs: env_or("TLS_CERT_RELOAD_INTERVAL_SECS", base.cert_reload_interval_secs)?,
+            acme_domains: env_list("TLS_ACME_DOMAINS").unwrap_or(base.acme_domains),
+            acme_contact_email: env_opt("TLS_ACME_CONTACT_EMAIL").or(base.acme_contact_email),
+            acme_cache_dir: env_or("TLS_ACME_CACHE_DIR", base.acme_cache_dir)?,
+            acme_staging: env_or("TLS_ACME_STAGING", base.acme_staging)?,
+        };
+
+        if config.enabled {
+            let files = config.cert_path.is_some() || config.key_path.is_some();
+            let acme = !config.acme_domains.is_empty();
+            if files == acme {
+                bail!("TLS needs either TLS_CERT_PATH and TLS_KEY_PATH or TLS_ACME_DOMAINS, not both");
+            }
+            if files && (config.cert_path.is_none() || config.key_path.is_none()) {
+                bail!("TLS_CERT_PATH and TLS_KEY_PATH must be set together");
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// Replay of first responses to retried requests carrying an `Idempotency-Key`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdempotencyConfig {
+    pub enabled: bool,
+    // How long a completed response is replayed for
+    pub ttl_secs: u64,
+    // How long a key stays claimed by a request still running, in case its instance dies
+    pub in_flight_ttl_secs: u64,
+    // Largest request or response body that is fingerprinted or stored
+    pub max_body_bytes: usize,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_secs: 86400,
+            in_flight_ttl_secs: 60,
+            max_body_bytes: 1024 * 1024,
+        }
+    }
+}
+
+impl IdempotencyConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = IdempotencyConfig {
+            enabled: env_or("IDEMPOTENCY_ENABLED", base.enabled)?,
+            ttl_secs: env_or("IDEMPOTENCY_TTL_SECS", base.ttl_secs)?,
+            in_flight_ttl_secs: env_or("IDEMPOTENCY_IN_FLIGHT_TTL_SECS", base.in_flight_ttl_secs)?,
+            max_body_bytes: env_or("IDEMPOTENCY_MAX_BODY_BYTES", base.max_body_bytes)?,
+        };
+
+        if config.ttl_secs == 0 || config.in_flight_ttl_secs == 0 || config.max_body_bytes == 0 {
+            bail!("IDEMPOTENCY_TTL_SECS, IDEMPOTENCY_IN_FLIGHT_TTL_SECS and IDEMPOTENCY_MAX_BODY_BYTES must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Bounds on what one request may cost the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub request_timeout_secs: u64,
+    // For backtests, context replays and dream cycles
+    pub slow_request_timeout_secs: u64,
+    // Largest request body most endpoints accept
+    pub max_body_bytes: usize,
+    // Largest analytics frame the analytics service may push
+    pub max_analytics_body_bytes: usize,
+    // Requests handled at once before the rest are shed with 503
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout_secs: 30,
+            slow_request_timeout_secs: 300,
+            max_body_bytes: 256 * 1024,
+            max_analytics_body_bytes: 1024 * 1024,
+            max_concurrent_requests: 1024,
+        }
+    }
+}
+
+impl LimitsConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = LimitsConfig {
+            request_timeout_secs: env_or("REQUEST_TIMEOUT_SECS", base.request_timeout_secs)?,
+            slow_request_timeout_secs: env_or("SLOW_REQUEST_TIMEOUT_SECS", base.slow_request_timeout_secs)?,
+            max_body_bytes: env_or("MAX_BODY_BYTES", base.max_body_bytes)?,
+            max_analytics_body_bytes: env_or("MAX_ANALYTICS_BODY_BYTES", base.max_analytics_body_bytes)?,
+            max_concurrent_requests: env_or("MAX_CONCURRENT_REQUESTS", base.max_concurrent_requests)?,
+        };
+
+        if config.request_timeout_secs == 0 || config.slow_request_timeout_secs == 0 {
+            bail!("REQUEST_TIMEOUT_SECS and SLOW_REQUEST_TIMEOUT_SECS must be greater than zero");
+        }
+        if config.max_body_bytes == 0 || config.max_analytics_body_bytes == 0 || config.max_concurrent_requests == 0 {
+            bail!("MAX_BODY_BYTES, MAX_ANALYTICS_BODY_BYTES and MAX_CONCURRENT_REQUESTS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// White-label deployments sharing one cluster. Each request belongs to the
+/// tenant its host maps to, or else the one its credential was issued in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenancyConfig {
+    pub enabled: bool,
+    // Requests no host or credential places elsewhere; also every request while disabled
+    pub default_tenant: String,
+    // Request host, without port, to tenant ID
+    pub hosts: BTreeMap<String, String>,
+}
+
+impl Default for TenancyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_tenant: "default".to_string(),
+            hosts: BTreeMap::new(),
+        }
+    }
+}
+
+impl TenancyConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let hosts = match env_list("TENANCY_HOSTS") {
+            Some(entries) => entries.iter()
+                .map(|entry| match entry.split_once('=') {
+                    Some((host, tenant)) => Ok((host.trim().to_string(), tenant.trim().to_string())),
+                    None => bail!("TENANCY_HOSTS entries must look like host=tenant, got {:?}", entry),
+                })
+                .collect::<Result<_>>()?,
+            None => base.hosts,
+        };
+        let config = TenancyConfig {
+            enabled: env_or("TENANCY_ENABLED", base.enabled)?,
+            default_tenant: env_or("TENANCY_DEFAULT_TENANT", base.default_tenant)?,
+            hosts: hosts.into_iter().map(|(host, tenant)| (host.to_ascii_lowercase(), tenant)).collect(),
+        };
+
+        // Tenant IDs end up in Redis keys and Postgres rows
+        let valid = |tenant: &str| {
+            !tenant.is_empty() && tenant.len() <= 64
+                && tenant.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        };
+        if !valid(&config.default_tenant) {
+            bail!("TENANCY_DEFAULT_TENANT must be 1 to 64 lowercase letters, digits, '-' or '_'");
+        }
+        if let Some((host, tenant)) = config.hosts.iter().find(|(host, tenant)| host.is_empty() || !valid(tenant)) {
+            bail!("TENANCY_HOSTS maps {:?} to {:?}; tenant IDs must be 1 to 64 lowercase letters, digits, '-' or '_'", host, tenant);
+        }
+
+        Ok(config)
+    }
+}
+
+/// Runtime feature flags, stored in Postgres and cached by every instance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureFlagConfig {
+    // How stale another instance's change to a flag may be here
+    pub refresh_interval_ms: u64,
+}
+
+impl Default for FeatureFlagConfig {
+    fn default() -> Self {
+        Self { refresh_interval_ms: 2000 }
+    }
+}
+
+impl FeatureFlagConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = FeatureFlagConfig {
+            refresh_interval_ms: env_or("FEATURE_FLAGS_REFRESH_MS", base.refresh_interval_ms)?,
+        };
+
+        if config.refresh_interval_ms == 0 {
+            bail!("FEATURE_FLAGS_REFRESH_MS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Moving settled bets from `bets` to `bets_archive`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BetArchiveConfig {
+    pub enabled: bool,
+    // Settled bets older than this are archived
+    pub archive_after_days: u32,
+    pub interval_secs: u64,
+    // Bets moved per transaction, so no run holds locks for long
+    pub batch_size: u32,
+}
+
+impl Default for BetArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            archive_after_days: 30,
+            interval_secs: 3600,
+            batch_size: 5000,
+        }
+    }
+}
+
+impl BetArchiveConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = BetArchiveConfig {
+            enabled: env_or("BET_ARCHIVE_ENABLED", base.enabled)?,
+            archive_after_days: env_or("BET_ARCHIVE_AFTER_DAYS", base.archive_after_days)?,
+            interval_secs: env_or("BET_ARCHIVE_INTERVAL_SECS", base.interval_secs)?,
+            batch_size: env_or("BET_ARCHIVE_BATCH_SIZE", base.batch_size)?,
+        };
+
+        if config.interval_secs == 0 || config.batch_size == 0 {
+            bail!("BET_ARCHIVE_INTERVAL_SECS and BET_ARCHIVE_BATCH_SIZE must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Postgres read replicas for history and replay queries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReplicaConfig {
+    // None configured means every read goes to the primary
+    pub urls: Vec<String>,
+    // Replicas further behind than this are skipped until they catch up
+    pub max_lag_ms: u64,
+    pub check_interval_ms: u64,
+}
+
+impl Default for ReplicaConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            max_lag_ms: 5000,
+            check_interval_ms: 1000,
+        }
+    }
+}
+
+impl ReplicaConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = ReplicaConfig {
+            urls: env_list("DATABASE_REPLICA_URLS").unwrap_or(base.urls),
+            max_lag_ms: env_or("DATABASE_REPLICA_MAX_LAG_MS", base.max_lag_ms)?,
+            check_interval_ms: env_or("DATABASE_REPLICA_CHECK_MS", base.check_interval_ms)?,
+        };
+
+        if config.check_interval_ms == 0 {
+            bail!("DATABASE_REPLICA_CHECK_MS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Relaying of domain events from the outbox table to webhooks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutboxConfig {
+    // How often the relay looks for committed events, and how many it takes at once
+    pub poll_interval_ms: u64,
+    pub batch_size: i64,
+    // How long a claimed event is left to one instance before another may relay it
+    pub lease_secs: u64,
+    // Backoff after a handler fails; doubles with each further attempt
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: 500,
+            batch_size: 100,
+            lease_secs: 30,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 300_000,
+        }
+    }
+}
+
+impl OutboxConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = OutboxConfig {
+            poll_interval_ms: env_or("OUTBOX_POLL_INTERVAL_MS", base.poll_interval_ms)?,
+            batch_size: env_or("OUTBOX_BATCH_SIZE", base.batch_size)?,
+            lease_secs: env_or("OUTBOX_LEASE_SECS", base.lease_secs)?,
+            initial_backoff_ms: env_or("OUTBOX_INITIAL_BACKOFF_MS", base.initial_backoff_ms)?,
+            max_backoff_ms: env_or("OUTBOX_MAX_BACKOFF_MS", base.max_backoff_ms)?,
+        };
+
+        if config.poll_interval_ms == 0 {
+            bail!("OUTBOX_POLL_INTERVAL_MS must be greater than zero");
+        }
+        if config.batch_size <= 0 {
+            bail!("OUTBOX_BATCH_SIZE must be greater than zero");
+        }
+        if config.lease_secs == 0 {
+            bail!("OUTBOX_LEASE_SECS must be greater than zero");
+        }
+        if config.initial_backoff_ms > config.max_backoff_ms {
+            bail!("OUTBOX_INITIAL_BACKOFF_MS must not exceed OUTBOX_MAX_BACKOFF_MS");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Sizing and timeouts of every Postgres pool: the primary's, the betting
+/// engine's and each read replica's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabasePoolConfig {
+    // Per pool; Postgres's max_connections has to cover every pool of every instance
+    pub max_connections: u32,
+    pub min_connections: u32,
+    // How long a query waits for a free connection before failing
+    pub acquire_timeout_ms: u64,
+    // Statements running longer are cancelled; 0 means no limit
+    pub statement_timeout_ms: u64,
+    // Idle connections beyond min_connections are closed after this; 0 keeps them
+    pub idle_timeout_secs: u64,
+    // Connections are replaced after this, so credential rotations and failovers take hold; 0 keeps them
+    pub max_lifetime_secs: u64,
+}
+
+impl Default for DatabasePoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 20,
+            min_connections: 0,
+            acquire_timeout_ms: 5000,
+            statement_timeout_ms: 30_000,
+            idle_timeout_secs: 600,
+            max_lifetime_secs: 1800,
+        }
+    }
+}
+
+impl DatabasePoolConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = DatabasePoolConfig {
+            max_connections: env_or("DATABASE_POOL_MAX_CONNECTIONS", base.max_connections)?,
+            min_connections: env_or("DATABASE_POOL_MIN_CONNECTIONS", base.min_connections)?,
+            acquire_timeout_ms: env_or("DATABASE_ACQUIRE_TIMEOUT_MS", base.acquire_timeout_ms)?,
+            statement_timeout_ms: env_or("DATABASE_STATEMENT_TIMEOUT_MS", base.statement_timeout_ms)?,
+            idle_timeout_secs: env_or("DATABASE_IDLE_TIMEOUT_SECS", base.idle_timeout_secs)?,
+            max_lifetime_secs: env_or("DATABASE_MAX_LIFETIME_SECS", base.max_lifetime_secs)?,
+        };
+
+        if config.max_connections == 0 {
+            bail!("DATABASE_POOL_MAX_CONNECTIONS must be greater than zero");
+        }
+        if config.min_connections > config.max_connections {
+            bail!("DATABASE_POOL_MIN_CONNECTIONS must not exceed DATABASE_POOL_MAX_CONNECTIONS");
+        }
+        if config.acquire_timeout_ms == 0 {
+            bail!("DATABASE_ACQUIRE_TIMEOUT_MS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Sizing and timeouts of the pool `StateManager` takes Redis connections from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedisPoolConfig {
+    // Per instance; Redis's maxclients has to cover every instance's pool
+    pub max_size: usize,
+    // How long a command waits for a free connection before failing
+    pub acquire_timeout_ms: u64,
+    // How long opening a new connection may take
+    pub connect_timeout_ms: u64,
+    // Idle connections are pinged before reuse; one that doesn't answer in time is replaced
+    pub health_check_timeout_ms: u64,
+}
+
+impl Default for RedisPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 32,
+            acquire_timeout_ms: 2000,
+            connect_timeout_ms: 2000,
+            health_check_timeout_ms: 500,
+        }
+    }
+}
+
+impl RedisPoolConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = RedisPoolConfig {
+            max_size: env_or("REDIS_POOL_MAX_SIZE", base.max_size)?,
+            acquire_timeout_ms: env_or("REDIS_ACQUIRE_TIMEOUT_MS", base.acquire_timeout_ms)?,
+            connect_timeout_ms: env_or("REDIS_CONNECT_TIMEOUT_MS", base.connect_timeout_ms)?,
+            health_check_timeout_ms: env_or("REDIS_HEALTH_CHECK_TIMEOUT_MS", base.health_check_timeout_ms)?,
+        };
+
+        if config.max_size == 0 {
+            bail!("REDIS_POOL_MAX_SIZE must be greater than zero");
+        }
+        if config.acquire_timeout_ms == 0 {
+            bail!("REDIS_ACQUIRE_TIMEOUT_MS must be greater than zero");
+        }
+        if config.connect_timeout_ms == 0 {
+            bail!("REDIS_CONNECT_TIMEOUT_MS must be greater than zero");
+        }
+        if config.health_check_timeout_ms == 0 {
+            bail!("REDIS_HEALTH_CHECK_TIMEOUT_MS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// How the betting engine spreads balances and settlement across shards.
+/// A user's balances and settlements always land on the same shard, so
+/// shards never wait on each other.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SettlementConfig {
+    // Balance cache shards, each with its own settlement worker
+    pub shards: usize,
+    // Settlements waiting per shard before resolving callers wait for room
+    pub queue_capacity: usize,
+    // How long a bet's settlement lock holds if its instance dies mid-settlement
+    pub lock_ttl_ms: u64,
+    // Most queued settlements a shard commits in one transaction
+    pub batch_size: usize,
+    // How long changed balances gather before they're written to Redis together
+    pub balance_flush_delay_ms: u64,
+    // Most balances per Redis pipeline
+    pub balance_flush_batch_size: usize,
+}
+
+impl Default for SettlementConfig {
+    fn default() -> Self {
+        Self {
+            shards: 16,
+            queue_capacity: 1000,
+            lock_ttl_ms: 30_000,
+            batch_size: 64,
+            balance_flush_delay_ms: 50,
+            balance_flush_batch_size: 500,
+        }
+    }
+}
+
+impl SettlementConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = SettlementConfig {
+            shards: env_or("SETTLEMENT_SHARDS", base.shards)?,
+            queue_capacity: env_or("SETTLEMENT_QUEUE_CAPACITY", base.queue_capacity)?,
+            lock_ttl_ms: env_or("SETTLEMENT_LOCK_TTL_MS", base.lock_ttl_ms)?,
+            batch_size: env_or("SETTLEMENT_BATCH_SIZE", base.batch_size)?,
+            balance_flush_delay_ms: env_or("BALANCE_FLUSH_DELAY_MS", base.balance_flush_delay_ms)?,
+            balance_flush_batch_size: env_or("BALANCE_FLUSH_BATCH_SIZE", base.balance_flush_batch_size)?,
+        };
+
+        if config.shards == 0 || config.queue_capacity == 0 || config.batch_size == 0 || config.balance_flush_batch_size == 0 {
+            bail!("SETTLEMENT_SHARDS, SETTLEMENT_QUEUE_CAPACITY, SETTLEMENT_BATCH_SIZE and BALANCE_FLUSH_BATCH_SIZE must be greater than zero");
+        }
+        if config.lock_ttl_ms < 1000 {
+            bail!("SETTLEMENT_LOCK_TTL_MS must be at least 1000");
+        }
+
+        Ok(config)
+    }
+}
+
+/// The message bus domain events are published to, for downstream risk, BI
+/// and notification systems. Events go through the outbox, so each one is
+/// published at least once, keyed by the user or stream it concerns.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventBusConfig {
+    pub backend: EventBusKind,
+    // nats: a server URL such as nats://nats:4222
+    pub nats_url: Option<String>,
+    // kafka: bootstrap brokers as host:port
+    pub kafka_brokers: Vec<String>,
+    // Events go to `<prefix>.<event type>`, a NATS subject or Kafka topic
+    pub subject_prefix: String,
+    // How long a publish waits for the bus before the outbox retries it
+    pub publish_timeout_ms: u64,
+}
+
+impl Default for EventBusConfig {
+    fn default() -> Self {
+        Self {
+            backend: EventBusKind::None,
+            nats_url: None,
+            kafka_brokers: Vec::new(),
+            subject_prefix: "morphine".to_string(),
+            publish_timeout_ms: 5000,
+        }
+    }
+}
+
+impl EventBusConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let backend = match std::env::var("EVENT_BUS_BACKEND").as_deref() {
+            Ok("none") => EventBusKind::None,
+            Ok("nats") => EventBusKind::Nats,
+            Ok("kafka") => EventBusKind::Kafka,
+            Err(_) => base.backend,
+            Ok(other) => bail!("EVENT_BUS_BACKEND must be 'none', 'nats' or 'kafka', got '{}'", other),
+        };
+
+        let config = EventBusConfig {
+            backend,
+            nats_url: env_opt("EVENT_BUS_NATS_URL").or(base.nats_url),
+            kafka_brokers: env_list("EVENT_BUS_KAFKA_BROKERS").unwrap_or(base.kafka_brokers),
+            subject_prefix: env_or("EVENT_BUS_SUBJECT_PREFIX", base.subject_prefix)?,
+            publish_timeout_ms: env_or("EVENT_BUS_PUBLISH_TIMEOUT_MS", base.publish_timeout_ms)?,
+        };
+
+        match config.backend {
+            EventBusKind::None => {}
+            EventBusKind::Nats => {
+                if config.nats_url.is_none() {
+                    bail!("EVENT_BUS_NATS_URL must be set for the nats event bus");
+                }
+            }
+            EventBusKind::Kafka => {
+                if config.kafka_brokers.is_empty() {
+                    bail!("EVENT_BUS_KAFKA_BROKERS must be set for the kafka event bus");
+                }
+            }
+        }
+        // Kafka topics allow only these, and NATS treats anything else in a subject specially
+        if config.subject_prefix.is_empty()
+            || !config.subject_prefix.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        {
+            bail!("EVENT_BUS_SUBJECT_PREFIX must be non-empty and use only letters, digits, '.', '_' and '-'");
+        }
+        if config.publish_timeout_ms == 0 {
+            bail!("EVENT_BUS_PUBLISH_TIMEOUT_MS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Notification emails rendered from outbox events: settlement summaries,
+/// activation notices to pledgers, limit-breach warnings and dispute updates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailConfig {
+    pub provider: EmailProviderKind,
+    // Sender address, optionally with a name: `Morphine <notifications@morphine.example>`
+    pub from_address: String,
+    // smtp: a relay; STARTTLS unless turned off for a local mail catcher
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub smtp_starttls: bool,
+    // ses: credentials come from AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and AWS_SESSION_TOKEN
+    pub ses_region: Option<String>,
+    // `<template>.txt` here replaces the built-in template of that name
+    pub templates_dir: Option<String>,
+    // Attempts before a delivery is dead-lettered
+    pub max_attempts: u32,
+    // Backoff after the first failure; doubles with each further attempt
+    pub initial_backoff_secs: u64,
+    pub max_backoff_secs: u64,
+    pub send_timeout_ms: u64,
+    // How often the dispatcher looks for due deliveries, and how many it takes at once
+    pub poll_interval_ms: u64,
+    pub batch_size: i64,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            provider: EmailProviderKind::None,
+            from_address: "Morphine <notifications@morphine.local>".to_string(),
+            smtp_host: None,
+            smtp_port: 587,
+            smtp_username: None,
+            smtp_password: None,
+            smtp_starttls: true,
+            ses_region: None,
+            templates_dir: None,
+            max_attempts: 5,
+            initial_backoff_secs: 30,
+            max_backoff_secs: 3600,
+            send_timeout_ms: 10_000,
+            poll_interval_ms: 1000,
+            batch_size: 50,
+        }
+    }
+}
+
+impl EmailConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let provider = match std::env::var("EMAIL_PROVIDER").as_deref() {
+            Ok("none") => EmailProviderKind::None,
+            Ok("smtp") => EmailProviderKind::Smtp,
+            Ok("ses") => EmailProviderKind::Ses,
+            Err(_) => base.provider,
+            Ok(other) => bail!("EMAIL_PROVIDER must be 'none', 'smtp' or 'ses', got '{}'", other),
+        };
+
+        let config = EmailConfig {
+            provider,
+            from_address: env_or("EMAIL_FROM", base.from_address)?,
+            smtp_host: env_opt("EMAIL_SMTP_HOST").or(base.smtp_host),
+            smtp_port: env_or("EMAIL_SMTP_PORT", base.smtp_port)?,
+            smtp_username: env_opt("EMAIL_SMTP_USERNAME").or(base.smtp_username),
+            smtp_password: env_opt("EMAIL_SMTP_PASSWORD").or(base.smtp_password),
+            smtp_starttls: env_or("EMAIL_SMTP_STARTTLS", base.smtp_starttls)?,
+            ses_region: env_opt("EMAIL_SES_REGION").or(base.ses_region),
+            templates_dir: env_opt("EMAIL_TEMPLATES_DIR").or(base.templates_dir),
+            max_attempts: env_or("EMAIL_MAX_ATTEMPTS", base.max_attempts)?,
+            initial_backoff_secs: env_or("EMAIL_INITIAL_BACKOFF_SECS", base.initial_backoff_secs)?,
+            max_backoff_secs: env_or("EMAIL_MAX_BACKOFF_SECS", base.max_backoff_secs)?,
+            send_timeout_ms: env_or("EMAIL_SEND_TIMEOUT_MS", base.send_timeout_ms)?,
+            poll_interval_ms: env_or("EMAIL_POLL_INTERVAL_MS", base.poll_interval_ms)?,
+            batch_size: env_or("EMAIL_BATCH_SIZE", base.batch_size)?,
+        };
+
+        match config.provider {
+            EmailProviderKind::None => {}
+            EmailProviderKind::Smtp => {
+                if config.smtp_host.is_none() {
+                    bail!("EMAIL_SMTP_HOST must be set for the smtp email provider");
+                }
+                if config.smtp_username.is_some() != config.smtp_password.is_some() {
+                    bail!("EMAIL_SMTP_USERNAME and EMAIL_SMTP_PASSWORD must be set together");
+                }
+            }
+            EmailProviderKind::Ses => {
+                if config.ses_region.is_none() {
+                    bail!("EMAIL_SES_REGION must be set for the ses email provider");
+                }
+            }
+        }
+        if config.max_attempts == 0 {
+            bail!("EMAIL_MAX_ATTEMPTS must be at least 1");
+        }
+        if config.initial_backoff_secs > config.max_backoff_secs {
+            bail!("EMAIL_INITIAL_BACKOFF_SECS must not exceed EMAIL_MAX_BACKOFF_SECS");
+        }
+        if config.send_timeout_ms == 0 || config.poll_interval_ms == 0 || config.batch_size <= 0 {
+            bail!("EMAIL_SEND_TIMEOUT_MS, EMAIL_POLL_INTERVAL_MS and EMAIL_BATCH_SIZE must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Mobile push for settled bets and streams going live. Each platform is
+/// turned on by configuring its credentials; devices can only register for
+/// a platform that is turned on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PushConfig {
+    // fcm: a Google service account key file with the Firebase messaging role
+    pub fcm_project_id: Option<String>,
+    pub fcm_credentials_path: Option<String>,
+    // apns: a .p8 token-signing key from the Apple developer account
+    pub apns_key_path: Option<String>,
+    pub apns_key_id: Option<String>,
+    pub apns_team_id: Option<String>,
+    // The app's bundle ID
+    pub apns_topic: Option<String>,
+    // Development builds of the app get tokens for the sandbox gateway
+    pub apns_sandbox: bool,
+    pub send_timeout_ms: u64,
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        Self {
+            fcm_project_id: None,
+            fcm_credentials_path: None,
+            apns_key_path: None,
+            apns_key_id: None,
+            apns_team_id: None,
+            apns_topic: None,
+            apns_sandbox: false,
+            send_timeout_ms: 5000,
+        }
+    }
+}
+
+impl PushConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = PushConfig {
+            fcm_project_id: env_opt("PUSH_FCM_PROJECT_ID").or(base.fcm_project_id),
+            fcm_credentials_path: env_opt("PUSH_FCM_CREDENTIALS_PATH").or(base.fcm_credentials_path),
+            apns_key_path: env_opt("PUSH_APNS_KEY_PATH").or(base.apns_key_path),
+            apns_key_id: env_opt("PUSH_APNS_KEY_ID").or(base.apns_key_id),
+            apns_team_id: env_opt("PUSH_APNS_TEAM_ID").or(base.apns_team_id),
+            apns_topic: env_opt("PUSH_APNS_TOPIC").or(base.apns_topic),
+            apns_sandbox: env_or("PUSH_APNS_SANDBOX", base.apns_sandbox)?,
+            send_timeout_ms: env_or("PUSH_SEND_TIMEOUT_MS", base.send_timeout_ms)?,
+        };
+
+        if config.fcm_project_id.is_some() != config.fcm_credentials_path.is_some() {
+            bail!("PUSH_FCM_PROJECT_ID and PUSH_FCM_CREDENTIALS_PATH must be set together");
+        }
+        let apns = [&config.apns_key_path, &config.apns_key_id, &config.apns_team_id, &config.apns_topic];
+        if apns.iter().any(|value| value.is_some()) && !apns.iter().all(|value| value.is_some()) {
+            bail!("PUSH_APNS_KEY_PATH, PUSH_APNS_KEY_ID, PUSH_APNS_TEAM_ID and PUSH_APNS_TOPIC must be set together");
+        }
+        if config.send_timeout_ms == 0 {
+            bail!("PUSH_SEND_TIMEOUT_MS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// How much location evidence is kept in memory, and how recent a bettor's
+/// location must be. Frames and histories past these limits, or older than
+/// their TTL, are dropped least recently used first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeolocationConfig {
+    // Video frames a transaction can still be correlated with
+    pub frame_locations_max_entries: usize,
+    pub frame_location_ttl_secs: u64,
+    // Users whose verifications are kept, and how many of each user's most recent
+    pub history_max_users: usize,
+    pub history_per_user: usize,
+    pub history_ttl_secs: u64,
+    // How old a bettor's last location verification may be for a bet to be placed; 0 doesn't require one
+    pub bet_verification_max_age_secs: u64,
+}
+
+impl Default for GeolocationConfig {
+    fn default() -> Self {
+        Self {
+            frame_locations_max_entries: 100_000,
+            frame_location_ttl_secs: 3600,
+            history_max_users: 50_000,
+            history_per_user: 100,
+            history_ttl_secs: 86_400,
+            bet_verification_max_age_secs: 300,
+        }
+    }
+}
+
+impl GeolocationConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = GeolocationConfig {
+            frame_locations_max_entries: env_or("GEOLOCATION_FRAME_LOCATIONS_MAX_ENTRIES", base.frame_locations_max_entries)?,
+            frame_location_ttl_secs: env_or("GEOLOCATION_FRAME_LOCATION_TTL_SECS", base.frame_location_ttl_secs)?,
+            history_max_users: env_or("GEOLOCATION_HISTORY_MAX_USERS", base.history_max_users)?,
+            history_per_user: env_or("GEOLOCATION_HISTORY_PER_USER", base.history_per_user)?,
+            history_ttl_secs: env_or("GEOLOCATION_HISTORY_TTL_SECS", base.history_ttl_secs)?,
+            bet_verification_max_age_secs: env_or("GEOLOCATION_BET_VERIFICATION_MAX_AGE_SECS", base.bet_verification_max_age_secs)?,
+        };
+
+        if config.frame_locations_max_entries == 0 || config.history_max_users == 0 || config.history_per_user == 0 {
+            bail!("GEOLOCATION_FRAME_LOCATIONS_MAX_ENTRIES, GEOLOCATION_HISTORY_MAX_USERS and GEOLOCATION_HISTORY_PER_USER must be greater than zero");
+        }
+        if config.frame_location_ttl_secs == 0 || config.history_ttl_secs == 0 {
+            bail!("GEOLOCATION_FRAME_LOCATION_TTL_SECS and GEOLOCATION_HISTORY_TTL_SECS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Panics, handler errors and background-task failures reported to Sentry,
+/// or a service that accepts its protocol. Off until a DSN is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ErrorReportingConfig {
+    pub dsn: Option<String>,
+    // Groups events by deployment, e.g. `production`
+    pub environment: Option<String>,
+    // Share of events sent, from 0 to 1
+    pub sample_rate: f32,
+    // How events name the user involved: `omit`, `pseudonymize` (with user_id_key) or `raw`
+    pub user_ids: UserIdReporting,
+    // HMAC key for pseudonyms; a user keeps theirs until the key changes
+    pub user_id_key: Option<String>,
+    // Log lines at INFO and WARN are attached to the next event as breadcrumbs
+    pub breadcrumbs: bool,
+}
+
+impl Default for ErrorReportingConfig {
+    fn default() -> Self {
+        Self {
+            dsn: None,
+            environment: None,
+            sample_rate: 1.0,
+            user_ids: UserIdReporting::Omit,
+            user_id_key: None,
+            breadcrumbs: true,
+        }
+    }
+}
+
+impl ErrorReportingConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let user_ids = match std::env::var("ERROR_REPORTING_USER_IDS").as_deref() {
+            Ok("omit") => UserIdReporting::Omit,
+            Ok("pseudonymize") => UserIdReporting::Pseudonymize,
+            Ok("raw") => UserIdReporting::Raw,
+            Err(_) => base.user_ids,
+            Ok(other) => bail!("ERROR_REPORTING_USER_IDS must be 'omit', 'pseudonymize' or 'raw', got '{}'", other),
+        };
+
+        let config = ErrorReportingConfig {
+            // SENTRY_DSN is what the Sentry tooling sets
+            dsn: env_opt("ERROR_REPORTING_DSN").or_else(|| env_opt("SENTRY_DSN")).or(base.dsn),
+            environment: env_opt("ERROR_REPORTING_ENVIRONMENT").or(base.environment),
+            sample_rate: env_or("ERROR_REPORTING_SAMPLE_RATE", base.sample_rate)?,
+            user_ids,
+            user_id_key: env_opt("ERROR_REPORTING_USER_ID_KEY").or(base.user_id_key),
+            breadcrumbs: env_or("ERROR_REPORTING_BREADCRUMBS", base.breadcrumbs)?,
+        };
+
+        if let Some(dsn) = &config.dsn {
+            // The client panics on a malformed one, so it is caught here
+            dsn.parse::<sentry::types::Dsn>().context("ERROR_REPORTING_DSN must be a valid DSN")?;
+        }
+        if !(0.0..=1.0).contains(&config.sample_rate) {
+            bail!("ERROR_REPORTING_SAMPLE_RATE must be between 0 and 1");
+        }
+        if config.user_ids == UserIdReporting::Pseudonymize {
+            match &config.user_id_key {
+                Some(key) if key.len() >= 16 => {}
+                _ => bail!("ERROR_REPORTING_USER_ID_KEY must be at least 16 characters to pseudonymize user IDs"),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// Bulk exports of bets, settlements, ledger entries and location
+/// verifications, generated in the background and stored for download.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportConfig {
+    // local: files under local_dir, downloaded through this service; s3: a bucket, downloaded from it
+    pub storage: ExportStorageKind,
+    pub local_dir: String,
+    // Signs local download URLs; a random key per process if unset, so URLs don't survive a restart
+    pub local_signing_key: Option<String>,
+    // Where local download URLs point, e.g. `https://morphine.example.com`
+    pub public_url: String,
+    // s3: credentials come from AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and AWS_SESSION_TOKEN
+    pub s3_bucket: Option<String>,
+    pub s3_region: Option<String>,
+    // For S3-compatible stores such as MinIO; AWS otherwise
+    pub s3_endpoint: Option<String>,
+    // Prepended to every object key, e.g. `exports/`
+    pub s3_prefix: String,
+    // How long a download URL works once issued
+    pub url_ttl_secs: u64,
+    // Widest date range one export may cover
+    pub max_range_days: u32,
+    // An export with more rows in one dataset fails; narrow its range
+    pub max_rows_per_dataset: usize,
+    // How often the worker looks for requested exports
+    pub poll_interval_ms: u64,
+    // An export unfinished this long after it was claimed is taken over by another instance
+    pub lease_secs: u64,
+    pub max_attempts: u32,
+    pub upload_timeout_ms: u64,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            storage: ExportStorageKind::Local,
+            local_dir: "./exports".to_string(),
+            local_signing_key: None,
+            public_url: "http://localhost:8080".to_string(),
+            s3_bucket: None,
+            s3_region: None,
+            s3_endpoint: None,
+            s3_prefix: String::new(),
+            url_ttl_secs: 3600,
+            max_range_days: 366,
+            max_rows_per_dataset: 2_000_000,
+            poll_interval_ms: 5000,
+            lease_secs: 1800,
+            max_attempts: 3,
+            upload_timeout_ms: 300_000,
+        }
+    }
+}
+
+impl ExportConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let storage = match std::env::var("EXPORTS_STORAGE").as_deref() {
+            Ok("local") => ExportStorageKind::Local,
+            Ok("s3") => ExportStorageKind::S3,
+            Err(_) => base.storage,
+            Ok(other) => bail!("EXPORTS_STORAGE must be 'local' or 's3', got '{}'", other),
+        };
+
+        let config = ExportConfig {
+            storage,
+            local_dir: env_or("EXPORTS_LOCAL_DIR", base.local_dir)?,
+            local_signing_key: env_opt("EXPORTS_LOCAL_SIGNING_KEY").or(base.local_signing_key),
+            public_url: env_or("EXPORTS_PUBLIC_URL", base.public_url)?,
+            s3_bucket: env_opt("EXPORTS_S3_BUCKET").or(base.s3_bucket),
+            s3_region: env_opt("EXPORTS_S3_REGION").or(base.s3_region),
+            s3_endpoint: env_opt("EXPORTS_S3_ENDPOINT").or(base.s3_endpoint),
+            s3_prefix: env_or("EXPORTS_S3_PREFIX", base.s3_prefix)?,
+            url_ttl_secs: env_or("EXPORTS_URL_TTL_SECS", base.url_ttl_secs)?,
+            max_range_days: env_or("EXPORTS_MAX_RANGE_DAYS", base.max_range_days)?,
+            max_rows_per_dataset: env_or("EXPORTS_MAX_ROWS_PER_DATASET", base.max_rows_per_dataset)?,
+            poll_interval_ms: env_or("EXPORTS_POLL_INTERVAL_MS", base.poll_interval_ms)?,
+            lease_secs: env_or("EXPORTS_LEASE_SECS", base.lease_secs)?,
+            max_attempts: env_or("EXPORTS_MAX_ATTEMPTS", base.max_attempts)?,
+            upload_timeout_ms: env_or("EXPORTS_UPLOAD_TIMEOUT_MS", base.upload_timeout_ms)?,
+        };
+
+        match config.storage {
+            ExportStorageKind::Local => {
+                if reqwest::Url::parse(&config.public_url).is_err() {
+                    bail!("EXPORTS_PUBLIC_URL must be an absolute URL");
+                }
+                if config.local_signing_key.as_ref().map(|key| key.len() < 16).unwrap_or(false) {
+                    bail!("EXPORTS_LOCAL_SIGNING_KEY must be at least 16 characters");
+                }
+            }
+            ExportStorageKind::S3 => {
+                if config.s3_bucket.is_none() || config.s3_region.is_none() {
+                    bail!("EXPORTS_S3_BUCKET and EXPORTS_S3_REGION must be set for s3 export storage");
+                }
+            }
+        }
+        // SigV4 presigned URLs are valid for at most a week
+        if config.url_ttl_secs == 0 || config.url_ttl_secs > 604_800 {
+            bail!("EXPORTS_URL_TTL_SECS must be between 1 and 604800");
+        }
+        if config.max_range_days == 0 || config.max_rows_per_dataset == 0 || config.max_attempts == 0 {
+            bail!("EXPORTS_MAX_RANGE_DAYS, EXPORTS_MAX_ROWS_PER_DATASET and EXPORTS_MAX_ATTEMPTS must be greater than zero");
+        }
+        if config.poll_interval_ms == 0 || config.lease_secs == 0 || config.upload_timeout_ms == 0 {
+            bail!("EXPORTS_POLL_INTERVAL_MS, EXPORTS_LEASE_SECS and EXPORTS_UPLOAD_TIMEOUT_MS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Detection of anomalous betting, from placements and settlements as they
+/// are relayed from the outbox.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiskConfig {
+    pub enabled: bool,
+    // Settlements a bettor needs before their win rate is judged against their odds
+    pub min_settled_bets: u32,
+    // Weight each older settlement keeps relative to the next, so a sudden run stands out
+    pub decay: f64,
+    // Standard deviations of wins above what the odds imply that count as exploitation
+    pub exploitation_z_score: f64,
+    pub streak_min_length: u32,
+    // A winning streak at least this unlikely, given its odds, is raised
+    pub streak_max_probability: f64,
+    // Bets by different accounts on the same outcome this close together are compared
+    pub coordination_window_secs: u64,
+    pub coordination_min_accounts: u32,
+    // Stakes vary by at most this much (standard deviation over mean) to look coordinated
+    pub coordination_max_stake_cv: f64,
+    // The same anomaly isn't raised again for this long
+    pub alert_cooldown_secs: u64,
+    // Stop the accounts named in an alert from betting until it's reviewed
+    pub freeze_accounts: bool,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_settled_bets: 20,
+            decay: 0.97,
+            exploitation_z_score: 4.0,
+            streak_min_length: 5,
+            streak_max_probability: 0.0001,
+            coordination_window_secs: 60,
+            coordination_min_accounts: 5,
+            coordination_max_stake_cv: 0.1,
+            alert_cooldown_secs: 3600,
+            freeze_accounts: false,
+        }
+    }
+}
+
+impl RiskConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = RiskConfig {
+            enabled: env_or("RISK_ENABLED", base.enabled)?,
+            min_settled_bets: env_or("RISK_MIN_SETTLED_BETS", base.min_settled_bets)?,
+            decay: env_or("RISK_DECAY", base.decay)?,
+            exploitation_z_score: env_or("RISK_EXPLOITATION_Z_SCORE", base.exploitation_z_score)?,
+            streak_min_length: env_or("RISK_STREAK_MIN_LENGTH", base.streak_min_length)?,
+            streak_max_probability: env_or("RISK_STREAK_MAX_PROBABILITY", base.streak_max_probability)?,
+            coordination_window_secs: env_or("RISK_COORDINATION_WINDOW_SECS", base.coordination_window_secs)?,
+            coordination_min_accounts: env_or("RISK_COORDINATION_MIN_ACCOUNTS", base.coordination_min_accounts)?,
+            coordination_max_stake_cv: env_or("RISK_COORDINATION_MAX_STAKE_CV", base.coordination_max_stake_cv)?,
+            alert_cooldown_secs: env_or("RISK_ALERT_COOLDOWN_SECS", base.alert_cooldown_secs)?,
+            freeze_accounts: env_or("RISK_FREEZE_ACCOUNTS", base.freeze_accounts)?,
+        };
+
+        if !(config.decay > 0.0 && config.decay <= 1.0) {
+            bail!("RISK_DECAY must be greater than 0 and at most 1");
+        }
+        if !(config.streak_max_probability > 0.0 && config.streak_max_probability < 1.0) {
+            bail!("RISK_STREAK_MAX_PROBABILITY must be between 0 and 1");
+        }
+        if config.exploitation_z_score <= 0.0 || config.coordination_max_stake_cv < 0.0 {
+            bail!("RISK_EXPLOITATION_Z_SCORE must be positive and RISK_COORDINATION_MAX_STAKE_CV not negative");
+        }
+        if config.streak_min_length < 2 || config.coordination_min_accounts < 2 {
+            bail!("RISK_STREAK_MIN_LENGTH and RISK_COORDINATION_MIN_ACCOUNTS must be at least 2");
+        }
+        if config.coordination_window_secs == 0 {
+            bail!("RISK_COORDINATION_WINDOW_SECS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Nonces and timestamps required on WebSocket messages that move money, so
+/// a captured frame can't be sent again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReplayProtectionConfig {
+    pub enabled: bool,
+    // How far a message's timestamp may be from the server clock, either way
+    pub max_clock_skew_secs: u64,
+    pub max_nonce_length: usize,
+}
+
+impl Default for ReplayProtectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_clock_skew_secs: 30,
+            max_nonce_length: 128,
+        }
+    }
+}
+
+impl ReplayProtectionConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = ReplayProtectionConfig {
+            enabled: env_or("REPLAY_PROTECTION_ENABLED", base.enabled)?,
+            max_clock_skew_secs: env_or("REPLAY_PROTECTION_MAX_CLOCK_SKEW_SECS", base.max_clock_skew_secs)?,
+            max_nonce_length: env_or("REPLAY_PROTECTION_MAX_NONCE_LENGTH", base.max_nonce_length)?,
+        };
+
+        if config.max_clock_skew_secs == 0 || config.max_nonce_length < 16 {
+            bail!("REPLAY_PROTECTION_MAX_CLOCK_SKEW_SECS must be greater than zero and REPLAY_PROTECTION_MAX_NONCE_LENGTH at least 16");
+        }
+
+        Ok(config)
+    }
+
+    /// How long a consumed nonce is remembered: as long as a message carrying
+    /// it could still pass the timestamp check.
+    pub fn nonce_ttl_secs(&self) -> u64 {
+        self.max_clock_skew_secs * 2 + 1
+    }
+}
+
+/// Which region this instance serves, and the public endpoints of every
+/// region clients may be routed to. One region, unnamed, by default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegionConfig {
+    // Streams activated here are tagged with it, and events published from here carry it
+    pub id: String,
+    // This region and its peers; empty routes every client to whichever instance it asked
+    pub regions: Vec<RegionEndpoints>,
+    // Apply domain events other regions publish on the event bus here too
+    pub replicate_events: bool,
+}
+
+impl Default for RegionConfig {
+    fn default() -> Self {
+        Self {
+            id: "default".to_string(),
+            regions: Vec::new(),
+            replicate_events: false,
+        }
+    }
+}
+
+impl RegionConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = RegionConfig {
+            id: env_or("REGION_ID", base.id)?,
+            regions: base.regions,
+            replicate_events: env_or("REGION_REPLICATE_EVENTS", base.replicate_events)?,
+        };
+
+        // It names a Kafka consumer group and appears in event envelopes
+        if config.id.is_empty() || !config.id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-')) {
+            bail!("REGION_ID must be non-empty and use only letters, digits, '_' and '-'");
+        }
+        let mut seen = std::collections::HashSet::new();
+        for region in &config.regions {
+            if !seen.insert(region.id.as_str()) {
+                bail!("Region '{}' is listed twice in [[region.regions]]", region.id);
+            }
+            if region.websocket_url.is_empty() || region.hls_url.is_empty() {
+                bail!("Region '{}' needs a websocket_url and an hls_url", region.id);
+            }
+            if !(-90.0..=90.0).contains(&region.latitude) || !(-180.0..=180.0).contains(&region.longitude) {
+                bail!("Region '{}' has coordinates out of range", region.id);
+            }
+        }
+        if !config.regions.is_empty() && !seen.contains(config.id.as_str()) {
+            bail!("[[region.regions]] must include this instance's region '{}'", config.id);
+        }
+
+        Ok(config)
+    }
+}
+
+/// When a stream's markets stop taking bets because its analytics have
+/// become unreliable, and when they open again. The gap between the two
+/// thresholds keeps a market near the line from flapping.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarketSuspensionConfig {
+    pub enabled: bool,
+    // A decision less confident than this suspends the stream's markets
+    pub suspend_below_confidence: f64,
+    // Decisions at least this confident count towards resuming
+    pub resume_above_confidence: f64,
+    // How many of them in a row it takes
+    pub resume_after_decisions: u32,
+    // No decision for this long suspends too, e.g. when detection has stopped; 0 disables
+    pub stale_after_secs: u64,
+}
+
+impl Default for MarketSuspensionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            suspend_below_confidence: 0.35,
+            resume_above_confidence: 0.6,
+            resume_after_decisions: 3,
+            stale_after_secs: 15,
+        }
+    }
+}
+
+impl MarketSuspensionConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        let config = MarketSuspensionConfig {
+            enabled: env_or("MARKET_SUSPENSION_ENABLED", base.enabled)?,
+            suspend_below_confidence: env_or("MARKET_SUSPENSION_SUSPEND_BELOW_CONFIDENCE", base.suspend_below_confidence)?,
+            resume_above_confidence: env_or("MARKET_SUSPENSION_RESUME_ABOVE_CONFIDENCE", base.resume_above_confidence)?,
+            resume_after_decisions: env_or("MARKET_SUSPENSION_RESUME_AFTER_DECISIONS", base.resume_after_decisions)?,
+            stale_after_secs: env_or("MARKET_SUSPENSION_STALE_AFTER_SECS", base.stale_after_secs)?,
+        };
+
+        if !(0.0..=1.0).contains(&config.suspend_below_confidence)
+            || !(0.0..=1.0).contains(&config.resume_above_confidence)
+            || config.resume_above_confidence < config.suspend_below_confidence
+        {
+            bail!("MARKET_SUSPENSION_SUSPEND_BELOW_CONFIDENCE and MARKET_SUSPENSION_RESUME_ABOVE_CONFIDENCE must be between 0 and 1, suspending at or below resuming");
+        }
+        if config.resume_after_decisions == 0 {
+            bail!("MARKET_SUSPENSION_RESUME_AFTER_DECISIONS must be greater than zero");
+        }
+
+        Ok(config)
+    }
+}
+
+/// How many streams each creator and each tenant may have active or
+/// activating at once, unless an admin has overridden it for them. 0 means
+/// no limit. `max_concurrent_streams` still caps each instance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StreamQuotaConfig {
+    pub per_creator: u32,
+    pub per_tenant: u32,
+}
+
+impl Default for StreamQuotaConfig {
+    fn default() -> Self {
+        Self {
+            per_creator: 3,
+            per_tenant: 0,
+        }
+    }
+}
+
+impl StreamQuotaConfig {
+    pub fn from_env(base: Self) -> Result<Self> {
+        Ok(StreamQuotaConfig {
+            per_creator: env_or("STREAM_QUOTAS_PER_CREATOR", base.per_creator)?,
+            per_tenant: env_or("STREAM_QUOTAS_PER_TENANT", base.per_tenant)?,
+        })
+    }
+}
+
+// Unset and empty both mean "not configured here"
+fn env_opt(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+// Comma-separated; set but empty means an empty list
+fn env_list(key: &str) -> Option<Vec<String>> {
+    std::env::var(key).ok().map(|values| {
+        values.split(',')
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect()
+    })
+}
+
+fn env_or<T>(key: &str, default: T) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match std::env::var(key) {
+        Ok(value) => value
+            .parse()
+            .with_context(|| format!("{} must be a valid value", key)),
+        Err(_) => Ok(default),
+    }
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::api_version::API_VERSION_HEADER;
+use crate::config::CorsConfig;
+use crate::error::CORRELATION_ID_HEADER;
+
+/// The CORS policy described by `config`. Entries were validated when the
+/// configuration loaded, so any that still fail to parse are skipped.
+pub fn layer(config: &CorsConfig) -> CorsLayer {
+    if config.permissive {
+        return CorsLayer::permissive();
+    }
+
+    let origins: Vec<HeaderValue> = config.allowed_origins.iter()
+        .filter_map(|origin| HeaderValue::from_str(origin.trim_end_matches('/')).ok())
+        .collect();
+    let methods: Vec<Method> = config.allowed_methods.iter()
+        .filter_map(|method| method.parse().ok())
+        .collect();
+    let headers: Vec<HeaderName> = config.allowed_headers.iter()
+        .filter_map(|name| name.parse().ok())
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .allow_credentials(config.allow_credentials)
+        .max_age(Duration::from_secs(config.max_age_secs))
+        // Scripts may read these; browsers hide the rest of the response headers
+        .expose_headers([
+            header::RETRY_AFTER,
+            HeaderName::from_static(CORRELATION_ID_HEADER),
+            HeaderName::from_static(API_VERSION_HEADER),
+            HeaderName::from_static("deprecation"),
+            HeaderName::from_static("sunset"),
+        ])
+}
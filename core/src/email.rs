@@ -0,0 +1,649 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres, Row};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::betting::Bet;
+use crate::config::EmailConfig;
+use crate::outbox::{OutboxError, OutboxEvent, OutboxHandler};
+use crate::secrets::hmac_sha256;
+use crate::shutdown::Shutdown;
+
+pub type EmailError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailProviderKind {
+    // No notification emails are queued or sent
+    None,
+    Smtp,
+    Ses,
+}
+
+/// One rendered email, to one recipient.
+pub struct EmailMessage<'a> {
+    pub to: &'a str,
+    pub subject: &'a str,
+    pub body: &'a str,
+}
+
+/// A service that actually sends mail.
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    /// Returns once the provider has accepted the message.
+    async fn send(&self, message: EmailMessage<'_>) -> Result<(), EmailError>;
+}
+
+/// Builds the configured sender, or returns None with email turned off.
+pub fn sender(config: &EmailConfig) -> Result<Option<Box<dyn EmailSender>>, EmailError> {
+    let timeout = Duration::from_millis(config.send_timeout_ms);
+    match config.provider {
+        EmailProviderKind::None => Ok(None),
+        EmailProviderKind::Smtp => Ok(Some(Box::new(SmtpSender::new(config, timeout)?))),
+        EmailProviderKind::Ses => Ok(Some(Box::new(SesSender::from_env(config, timeout)?))),
+    }
+}
+
+struct SmtpSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: lettre::message::Mailbox,
+}
+
+impl SmtpSender {
+    fn new(config: &EmailConfig, timeout: Duration) -> Result<Self, EmailError> {
+        let host = config.smtp_host.as_deref().ok_or("EMAIL_SMTP_HOST is required for SMTP email")?;
+        let mut builder = if config.smtp_starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?
+        } else {
+            // Local relays and mail catchers only; credentials would go in the clear
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        };
+        builder = builder.port(config.smtp_port).timeout(Some(timeout));
+        if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: config.from_address.parse()?,
+        })
+    }
+}
+
+#[async_trait]
+impl EmailSender for SmtpSender {
+    async fn send(&self, message: EmailMessage<'_>) -> Result<(), EmailError> {
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(message.to.parse()?)
+            .subject(message.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body.to_string())?;
+        self.transport.send(email).await?;
+        Ok(())
+    }
+}
+
+/// Amazon SES, through the v2 SendEmail API with SigV4-signed requests.
+struct SesSender {
+    region: String,
+    from: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    http_client: reqwest::Client,
+}
+
+const SES_PATH: &str = "/v2/email/outbound-emails";
+
+impl SesSender {
+    /// Credentials come from the standard AWS environment variables.
+    fn from_env(config: &EmailConfig, timeout: Duration) -> Result<Self, EmailError> {
+        let region = config.ses_region.clone().ok_or("EMAIL_SES_REGION is required for SES email")?;
+        let access_key_id = std::env::var("AWS_ACCESS_KEY_ID")
+            .map_err(|_| "AWS_ACCESS_KEY_ID must be set for SES email")?;
+        let secret_access_key = std::env::var("AWS_SECRET_ACCESS_KEY")
+            .map_err(|_| "AWS_SECRET_ACCESS_KEY must be set for SES email")?;
+
+        Ok(Self {
+            region,
+            from: config.from_address.clone(),
+            access_key_id,
+            secret_access_key,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok().filter(|token| !token.is_empty()),
+            http_client: reqwest::Client::builder().timeout(timeout).build()?,
+        })
+    }
+
+    // Signature Version 4 `Authorization` header for a SendEmail call
+    fn authorization(&self, host: &str, amz_date: &str, body: &str) -> String {
+        let date = &amz_date[..8];
+        let mut headers = vec![
+            ("content-type", "application/json".to_string()),
+            ("host", host.to_string()),
+            ("x-amz-date", amz_date.to_string()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        headers.sort_by(|a, b| a.0.cmp(b.0));
+
+        let canonical_headers: String = headers.iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect();
+        let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+        let canonical_request = format!(
+            "POST\n{}\n\n{}\n{}\n{}",
+            SES_PATH,
+            canonical_headers,
+            signed_headers,
+            hex::encode(Sha256::digest(body.as_bytes())),
+        );
+
+        let scope = format!("{}/{}/ses/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes())),
+        );
+
+        let mut key = hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), date);
+        for part in [self.region.as_str(), "ses", "aws4_request"] {
+            key = hmac_sha256(&key, part);
+        }
+        let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        )
+    }
+}
+
+#[async_trait]
+impl EmailSender for SesSender {
+    async fn send(&self, message: EmailMessage<'_>) -> Result<(), EmailError> {
+        let host = format!("email.{}.amazonaws.com", self.region);
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let body = json!({
+            "FromEmailAddress": self.from,
+            "Destination": { "ToAddresses": [message.to] },
+            "Content": {
+                "Simple": {
+                    "Subject": { "Data": message.subject, "Charset": "UTF-8" },
+                    "Body": { "Text": { "Data": message.body, "Charset": "UTF-8" } },
+                }
+            },
+        }).to_string();
+
+        let mut request = self.http_client.post(format!("https://{}{}", host, SES_PATH))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Amz-Date", &amz_date)
+            .header(reqwest::header::AUTHORIZATION, self.authorization(&host, &amz_date, &body));
+        if let Some(token) = &self.session_token {
+            request = request.header("X-Amz-Security-Token", token);
+        }
+
+        let response = request.body(body).send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let detail = response.text().await.unwrap_or_default();
+            return Err(format!("SES responded {}: {}", status, detail).into());
+        }
+        Ok(())
+    }
+}
+
+/// The notifications there are templates for, named after the outbox event
+/// each is rendered from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EmailTemplate {
+    BetSettled,
+    StreamActivated,
+    LimitBreached,
+    DisputeUpdated,
+}
+
+impl EmailTemplate {
+    const ALL: [EmailTemplate; 4] = [
+        EmailTemplate::BetSettled,
+        EmailTemplate::StreamActivated,
+        EmailTemplate::LimitBreached,
+        EmailTemplate::DisputeUpdated,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailTemplate::BetSettled => "bet_settled",
+            EmailTemplate::StreamActivated => "stream_activated",
+            EmailTemplate::LimitBreached => "limit_breached",
+            EmailTemplate::DisputeUpdated => "dispute_updated",
+        }
+    }
+
+    pub fn for_event(event_type: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|template| template.as_str() == event_type)
+    }
+
+    // A `Subject:` line, a blank line, then the body; `{name}` is replaced with the variable
+    fn builtin(&self) -> &'static str {
+        match self {
+            EmailTemplate::BetSettled => "\
+Subject: Your bet on {stream_id} {outcome}
+
+Hi {username},
+
+Your {bet_type} bet {bet_id} on stream {stream_id} has settled.
+
+Stake:   ${stake}
+Odds:    {odds}
+Result:  {outcome}
+Payout:  ${payout}
+
+Settled at {settled_at}.
+",
+            EmailTemplate::StreamActivated => "\
+Subject: {stream_title} is live
+
+Hi {username},
+
+{stream_title}, which you pledged to, has reached its target and is now live.
+Your betting balance for the stream is ready to use.
+",
+            EmailTemplate::LimitBreached => "\
+Subject: You've reached your {limit_type} limit
+
+Hi {username},
+
+You've reached your {limit_type} limit of ${limit} (currently ${current}).
+Further activity under this limit is blocked until it resets or you change it.
+",
+            EmailTemplate::DisputeUpdated => "\
+Subject: Update on dispute {dispute_id}
+
+Hi {username},
+
+Your dispute {dispute_id} is now {status}.
+
+{message}
+",
+        }
+    }
+}
+
+struct Template {
+    subject: String,
+    body: String,
+}
+
+impl Template {
+    fn parse(name: &str, text: &str) -> Result<Self, EmailError> {
+        let (first, body) = text.split_once('\n').unwrap_or((text, ""));
+        let subject = first.strip_prefix("Subject:")
+            .ok_or_else(|| format!("Email template {} must start with a Subject: line", name))?;
+        Ok(Self {
+            subject: subject.trim().to_string(),
+            body: body.trim_start_matches('\n').to_string(),
+        })
+    }
+}
+
+// Unknown placeholders are left as they are, so a typo shows in the email rather than vanishing
+fn render(text: &str, variables: &BTreeMap<String, String>) -> String {
+    variables.iter().fold(text.to_string(), |rendered, (name, value)| {
+        rendered.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+/// Renders outbox events into notification emails and sends them. Each
+/// recipient gets their own queued delivery, so one bad address doesn't hold
+/// up the rest; a dispatcher sends due deliveries with exponential backoff
+/// and dead-letters those that keep failing. Only active users with a
+/// verified address are mailed, unless their profile preferences set
+/// `email_notifications` to false.
+pub struct EmailService {
+    db_pool: Pool<Postgres>,
+    config: EmailConfig,
+    sender: Box<dyn EmailSender>,
+    templates: HashMap<EmailTemplate, Template>,
+    shutdown: Shutdown,
+}
+
+struct Recipient {
+    user_id: String,
+    email: String,
+    username: String,
+}
+
+struct ClaimedDelivery {
+    delivery_id: String,
+    recipient: String,
+    subject: String,
+    body: String,
+    attempts: i32,
+}
+
+impl EmailService {
+    /// Loads templates, preferring `<templates_dir>/<template>.txt` over the
+    /// built-in one for each.
+    pub fn new(
+        db_pool: Pool<Postgres>,
+        config: EmailConfig,
+        sender: Box<dyn EmailSender>,
+        shutdown: Shutdown,
+    ) -> Result<Self, EmailError> {
+        let mut templates = HashMap::new();
+        for template in EmailTemplate::ALL {
+            let custom = config.templates_dir.as_ref()
+                .map(|dir| Path::new(dir).join(format!("{}.txt", template.as_str())))
+                .filter(|path| path.exists());
+            let text = match &custom {
+                Some(path) => std::fs::read_to_string(path)?,
+                None => template.builtin().to_string(),
+            };
+            templates.insert(template, Template::parse(template.as_str(), &text)?);
+        }
+
+        Ok(Self { db_pool, config, sender, templates, shutdown })
+    }
+
+    /// Starts the dispatcher. A send cut off by shutdown is retried once its
+    /// lease runs out, so a recipient may get an email twice.
+    pub fn start(self: &Arc<Self>) {
+        let service = self.clone();
+        self.shutdown.spawn_loop("email:dispatcher", async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(service.config.poll_interval_ms));
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.dispatch_due().await {
+                    warn!("Email dispatch failed: {}", e);
+                }
+            }
+        });
+        info!("Email dispatcher started");
+    }
+
+    /// Queues one delivery of `template` per recipient, rendered with the
+    /// event's variables and the recipient's username. Returns how many
+    /// were queued; recipients who already have this event are skipped.
+    async fn queue(
+        &self,
+        event: &OutboxEvent,
+        template: EmailTemplate,
+        user_ids: &[String],
+        variables: BTreeMap<String, String>,
+    ) -> Result<u64, EmailError> {
+        if user_ids.is_empty() {
+            return Ok(0);
+        }
+        let text = &self.templates[&template];
+
+        let mut delivery_ids = Vec::new();
+        let mut recipients = Vec::new();
+        let mut emails = Vec::new();
+        let mut subjects = Vec::new();
+        let mut bodies = Vec::new();
+        for recipient in self.recipients(user_ids).await? {
+            let mut variables = variables.clone();
+            variables.insert("username".to_string(), recipient.username);
+            delivery_ids.push(Uuid::new_v4().to_string());
+            subjects.push(render(&text.subject, &variables));
+            bodies.push(render(&text.body, &variables));
+            recipients.push(recipient.user_id);
+            emails.push(recipient.email);
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO email_deliveries (delivery_id, event_id, template, user_id, recipient, subject, body)
+            SELECT delivery_id, $1, $2, user_id, recipient, subject, body
+            FROM UNNEST($3::text[], $4::text[], $5::text[], $6::text[], $7::text[])
+                AS d(delivery_id, user_id, recipient, subject, body)
+            ON CONFLICT (event_id, user_id) DO NOTHING
+            "#
+        )
+        .bind(&event.event_id)
+        .bind(template.as_str())
+        .bind(&delivery_ids)
+        .bind(&recipients)
+        .bind(&emails)
+        .bind(&subjects)
+        .bind(&bodies)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn recipients(&self, user_ids: &[String]) -> Result<Vec<Recipient>, EmailError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT u.user_id, u.email, u.username
+            FROM users u
+            LEFT JOIN user_profiles p USING (user_id)
+            WHERE u.user_id = ANY($1) AND u.is_active AND u.email_verified_at IS NOT NULL
+                AND (p.preferences -> 'email_notifications') IS DISTINCT FROM 'false'::jsonb
+            "#
+        )
+        .bind(user_ids)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows.iter()
+            .map(|row| Recipient {
+                user_id: row.get("user_id"),
+                email: row.get("email"),
+                username: row.get("username"),
+            })
+            .collect())
+    }
+
+    // Everyone who put money into the stream's activation
+    async fn pledgers(&self, stream_id: &str) -> Result<Vec<String>, EmailError> {
+        let user_ids = sqlx::query_scalar(
+            "SELECT user_id FROM user_balances WHERE stream_id = $1 AND total_deposited > 0 AND deleted_at IS NULL"
+        )
+        .bind(stream_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+        Ok(user_ids)
+    }
+
+    async fn stream_title(&self, stream_id: &str) -> Result<Option<String>, EmailError> {
+        let title = sqlx::query_scalar("SELECT title FROM streams WHERE id = $1")
+            .bind(stream_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+        Ok(title)
+    }
+
+    async fn dispatch_due(&self) -> Result<(), EmailError> {
+        // Leased past the send timeout so no other instance picks them up while they're in flight
+        let lease_secs = (self.config.send_timeout_ms / 1000 + 1) as f64 * 2.0;
+        let rows = sqlx::query(
+            r#"
+            UPDATE email_deliveries d
+            SET next_attempt_at = NOW() + make_interval(secs => $2)
+            FROM (
+                SELECT delivery_id
+                FROM email_deliveries
+                WHERE status = 'pending' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            ) due
+            WHERE d.delivery_id = due.delivery_id
+            RETURNING d.delivery_id, d.recipient, d.subject, d.body, d.attempts
+            "#
+        )
+        .bind(self.config.batch_size)
+        .bind(lease_secs)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let claimed: Vec<ClaimedDelivery> = rows.iter()
+            .map(|row| ClaimedDelivery {
+                delivery_id: row.get("delivery_id"),
+                recipient: row.get("recipient"),
+                subject: row.get("subject"),
+                body: row.get("body"),
+                attempts: row.get("attempts"),
+            })
+            .collect();
+
+        futures::future::join_all(claimed.into_iter().map(|delivery| self.attempt(delivery))).await;
+        Ok(())
+    }
+
+    async fn attempt(&self, delivery: ClaimedDelivery) {
+        let sent = self.sender.send(EmailMessage {
+            to: &delivery.recipient,
+            subject: &delivery.subject,
+            body: &delivery.body,
+        }).await;
+
+        let recorded = match sent {
+            Ok(()) => self.record_success(&delivery).await,
+            Err(e) => self.record_failure(&delivery, &e.to_string()).await,
+        };
+        if let Err(e) = recorded {
+            warn!("Failed to record email delivery {}: {}", delivery.delivery_id, e);
+        }
+    }
+
+    async fn record_success(&self, delivery: &ClaimedDelivery) -> Result<(), EmailError> {
+        sqlx::query(
+            r#"
+            UPDATE email_deliveries
+            SET status = 'sent', attempts = attempts + 1, sent_at = NOW(), last_error = NULL
+            WHERE delivery_id = $1
+            "#
+        )
+        .bind(&delivery.delivery_id)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn record_failure(&self, delivery: &ClaimedDelivery, error: &str) -> Result<(), EmailError> {
+        let attempts = delivery.attempts + 1;
+        let dead_lettered = attempts as u32 >= self.config.max_attempts;
+        if dead_lettered {
+            warn!("Dead-lettered email delivery {} after {} attempts: {}", delivery.delivery_id, attempts, error);
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE email_deliveries
+            SET status = $2, attempts = $3, next_attempt_at = NOW() + make_interval(secs => $4), last_error = $5
+            WHERE delivery_id = $1
+            "#
+        )
+        .bind(&delivery.delivery_id)
+        .bind(if dead_lettered { "dead_lettered" } else { "pending" })
+        .bind(attempts)
+        .bind(self.backoff(attempts).as_secs_f64())
+        .bind(error)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    // Doubles from the initial backoff with each failed attempt, up to the max
+    fn backoff(&self, attempts: i32) -> Duration {
+        let exponent = (attempts.max(1) - 1).min(31) as u32;
+        let secs = self.config.initial_backoff_secs.saturating_mul(1u64 << exponent);
+        Duration::from_secs(secs.min(self.config.max_backoff_secs))
+    }
+}
+
+// The event's top-level fields, as text, for templates to pick from
+fn event_variables(data: &Value) -> BTreeMap<String, String> {
+    data.as_object()
+        .map(|fields| {
+            fields.iter()
+                .filter_map(|(name, value)| {
+                    let text = match value {
+                        Value::String(text) => text.clone(),
+                        Value::Number(number) => match number.as_f64() {
+                            Some(amount) if number.is_f64() => format!("{:.2}", amount),
+                            _ => number.to_string(),
+                        },
+                        Value::Bool(flag) => flag.to_string(),
+                        _ => return None,
+                    };
+                    Some((name.clone(), text))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn bet_variables(bet: &Bet) -> BTreeMap<String, String> {
+    let resolution = bet.resolution_result.as_ref();
+    let won = resolution.map(|resolution| resolution.won).unwrap_or(false);
+    BTreeMap::from([
+        ("bet_id".to_string(), bet.id.clone()),
+        ("stream_id".to_string(), bet.stream_id.clone()),
+        ("bet_type".to_string(), bet.bet_type.as_str().to_string()),
+        ("stake".to_string(), format!("{:.2}", bet.stake_amount)),
+        ("odds".to_string(), format!("{:.2}", bet.odds)),
+        ("outcome".to_string(), if won { "won" } else { "lost" }.to_string()),
+        ("payout".to_string(), format!("{:.2}", resolution.map(|resolution| resolution.payout_amount).unwrap_or(0.0))),
+        ("settled_at".to_string(), resolution
+            .map(|resolution| resolution.resolved_at.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_default()),
+    ])
+}
+
+/// Settlement summaries go to the bettor and activation notices to every
+/// pledger. Limit-breach warnings and dispute updates go to the `user_id`
+/// in the event, with its other fields as template variables.
+#[async_trait]
+impl OutboxHandler for EmailService {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    async fn handle(&self, event: &OutboxEvent) -> Result<(), OutboxError> {
+        let Some(template) = EmailTemplate::for_event(&event.event_type) else {
+            return Ok(());
+        };
+
+        let (user_ids, variables) = match template {
+            EmailTemplate::BetSettled => {
+                let bet: Bet = serde_json::from_value(event.data.clone())?;
+                (vec![bet.user_id.clone()], bet_variables(&bet))
+            }
+            EmailTemplate::StreamActivated => {
+                let stream_id = event.data.get("stream_id").and_then(Value::as_str)
+                    .ok_or("Stream activation event has no stream_id")?;
+                let mut variables = event_variables(&event.data);
+                let title = self.stream_title(stream_id).await?.unwrap_or_else(|| stream_id.to_string());
+                variables.insert("stream_title".to_string(), title);
+                (self.pledgers(stream_id).await?, variables)
+            }
+            EmailTemplate::LimitBreached | EmailTemplate::DisputeUpdated => {
+                let user_id = event.data.get("user_id").and_then(Value::as_str)
+                    .ok_or_else(|| format!("{} event has no user_id", event.event_type))?;
+                (vec![user_id.to_string()], event_variables(&event.data))
+            }
+        };
+
+        self.queue(event, template, &user_ids, variables).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use std::convert::Infallible;
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::{header, HeaderMap, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::error::ApiError;
+
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+// Names MessagePack went by before it was registered; clients still send them
+const MSGPACK_MEDIA_TYPES: &[&str] = &[MSGPACK_CONTENT_TYPE, "application/x-msgpack", "application/vnd.msgpack"];
+
+/// How a body is encoded on the wire. JSON unless the client asks otherwise;
+/// errors are always JSON so every client can read them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl Encoding {
+    /// The encoding the client prefers, by `Accept` quality. Ties go to the
+    /// range listed first; with no usable preference the response is JSON.
+    pub fn from_accept(headers: &HeaderMap) -> Self {
+        let Some(accept) = headers.get(header::ACCEPT).and_then(|value| value.to_str().ok()) else {
+            return Encoding::Json;
+        };
+
+        let mut best: Option<(Encoding, f32)> = None;
+        for range in accept.split(',') {
+            let mut params = range.split(';');
+            let media_type = params.next().unwrap_or("").trim().to_ascii_lowercase();
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            let encoding = match media_type.as_str() {
+                media_type if MSGPACK_MEDIA_TYPES.contains(&media_type) => Encoding::MessagePack,
+                "application/json" | "application/*" | "*/*" => Encoding::Json,
+                _ => continue,
+            };
+            if quality > 0.0 && best.map_or(true, |(_, best_quality)| quality > best_quality) {
+                best = Some((encoding, quality));
+            }
+        }
+        best.map(|(encoding, _)| encoding).unwrap_or_default()
+    }
+
+    /// The encoding of a request body, by its `Content-Type`.
+    pub fn from_content_type(headers: &HeaderMap) -> Self {
+        let media_type = headers.get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|media_type| media_type.trim().to_ascii_lowercase());
+        match media_type {
+            Some(media_type) if MSGPACK_MEDIA_TYPES.contains(&media_type.as_str()) => Encoding::MessagePack,
+            _ => Encoding::Json,
+        }
+    }
+
+    pub fn respond<T: Serialize>(self, value: T) -> Negotiated<T> {
+        Negotiated { encoding: self, value }
+    }
+}
+
+/// Extracts the response encoding the client asked for in `Accept`.
+#[async_trait]
+impl<S> FromRequestParts<S> for Encoding
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Encoding::from_accept(&parts.headers))
+    }
+}
+
+/// A response body in the encoding the client negotiated.
+#[derive(Debug, Clone)]
+pub struct Negotiated<T> {
+    pub encoding: Encoding,
+    pub value: T,
+}
+
+impl<T: Serialize> IntoResponse for Negotiated<T> {
+    fn into_response(self) -> Response {
+        let mut response = match self.encoding {
+            Encoding::Json => Json(self.value).into_response(),
+            // Named fields, so MessagePack maps carry the same keys as the JSON objects
+            Encoding::MessagePack => match rmp_serde::to_vec_named(&self.value) {
+                Ok(body) => ([(header::CONTENT_TYPE, HeaderValue::from_static(MSGPACK_CONTENT_TYPE))], body).into_response(),
+                Err(e) => {
+                    warn!("Failed to encode response as MessagePack: {}", e);
+                    ApiError::internal().into_response()
+                }
+            },
+        };
+        // Caches must not serve one client's encoding to another
+        response.headers_mut().append(header::VARY, HeaderValue::from_static("accept"));
+        response
+    }
+}
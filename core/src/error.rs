@@ -0,0 +1,210 @@
+use std::fmt;
+use axum::extract::Request;
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::validation::{FieldError, ValidationErrors};
+
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+/// Stable, machine-readable error codes. Clients should branch on these, not
+/// on messages or status codes, which may be refined over time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    MalformedBody,
+    ValidationFailed,
+    BadRequest,
+    PayloadTooLarge,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    BetRejected,
+    RateLimited,
+    UnsupportedApiVersion,
+    Unavailable,
+    Timeout,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::MalformedBody | ErrorCode::BadRequest => StatusCode::BAD_REQUEST,
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::ValidationFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound | ErrorCode::UnsupportedApiVersion => StatusCode::NOT_FOUND,
+            ErrorCode::Conflict | ErrorCode::BetRejected => StatusCode::CONFLICT,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::Timeout => StatusCode::REQUEST_TIMEOUT,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// The error every handler and middleware returns. Renders as
+/// `{"success": false, "error": {"code", "message", "correlation_id", "fields"?}}`.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub fields: Vec<FieldError>,
+    pub retry_after_secs: Option<u64>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            fields: Vec::new(),
+            retry_after_secs: None,
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::BadRequest, message)
+    }
+
+    pub fn unauthorized() -> Self {
+        Self::new(ErrorCode::Unauthorized, "Missing or invalid credentials")
+    }
+
+    pub fn forbidden() -> Self {
+        Self::new(ErrorCode::Forbidden, "Not allowed to perform this action")
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unavailable, message)
+    }
+
+    // Details belong in the log line the caller writes, not in the response
+    pub fn internal() -> Self {
+        Self::new(ErrorCode::Internal, "Internal server error")
+    }
+
+    pub fn rate_limited(retry_after_secs: u64) -> Self {
+        Self {
+            retry_after_secs: Some(retry_after_secs),
+            ..Self::new(ErrorCode::RateLimited, "Rate limit exceeded")
+        }
+    }
+
+    pub fn malformed_body(message: impl Into<String>) -> Self {
+        Self {
+            fields: vec![FieldError { field: "body".to_string(), message: message.into() }],
+            ..Self::new(ErrorCode::MalformedBody, "Malformed request body")
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+// Auth and access checks report bare status codes
+impl From<StatusCode> for ApiError {
+    fn from(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => Self::unauthorized(),
+            StatusCode::FORBIDDEN => Self::forbidden(),
+            StatusCode::NOT_FOUND => Self::not_found("Not found"),
+            StatusCode::CONFLICT => Self::conflict("Conflict"),
+            StatusCode::SERVICE_UNAVAILABLE => Self::unavailable("Service temporarily unavailable"),
+            StatusCode::TOO_MANY_REQUESTS => Self::new(ErrorCode::RateLimited, "Rate limit exceeded"),
+            status if status.is_client_error() => {
+                Self::bad_request(status.canonical_reason().unwrap_or("Bad request"))
+            }
+            _ => Self::internal(),
+        }
+    }
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        Self {
+            fields: errors.into_fields(),
+            ..Self::new(ErrorCode::ValidationFailed, "Request validation failed")
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let correlation_id = current_correlation_id().unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let mut error = json!({
+            "code": self.code,
+            "message": self.message,
+            "correlation_id": correlation_id,
+        });
+        if !self.fields.is_empty() {
+            error["fields"] = json!(self.fields);
+        }
+
+        let mut response = (self.code.status(), Json(json!({ "success": false, "error": error }))).into_response();
+        let headers = response.headers_mut();
+        if let Ok(value) = HeaderValue::from_str(&correlation_id) {
+            headers.insert(CORRELATION_ID_HEADER, value);
+        }
+        if let Some(retry_after_secs) = self.retry_after_secs {
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+        response
+    }
+}
+
+/// The correlation ID of the request being handled, if inside one.
+pub fn current_correlation_id() -> Option<String> {
+    CORRELATION_ID.try_with(|id| id.clone()).ok()
+}
+
+// Accept the caller's ID (e.g. from the API gateway) if it looks like one
+fn incoming_correlation_id(request: &Request) -> Option<String> {
+    [CORRELATION_ID_HEADER, "x-request-id"].iter()
+        .filter_map(|name| request.headers().get(*name))
+        .filter_map(|value| value.to_str().ok())
+        .find(|id| !id.is_empty() && id.len() <= 128 && id.chars().all(|c| c.is_ascii_graphic()))
+        .map(str::to_string)
+}
+
+/// Tags each request with a correlation ID: in its log lines, in any error
+/// body, and in the `X-Correlation-Id` response header.
+pub async fn correlate(request: Request, next: Next) -> Response {
+    let correlation_id = incoming_correlation_id(&request).unwrap_or_else(|| Uuid::new_v4().to_string());
+    let span = tracing::info_span!("request", correlation_id = %correlation_id);
+
+    let mut response = CORRELATION_ID
+        .scope(correlation_id.clone(), next.run(request).instrument(span))
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&correlation_id) {
+        response.headers_mut().insert(CORRELATION_ID_HEADER, value);
+    }
+    response
+}
@@ -0,0 +1,193 @@
+use std::borrow::Cow;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use axum::extract::{MatchedPath, RawPathParams, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use hmac::{Hmac, Mac};
+use sentry::protocol::{Context as EventContext, Event, Value};
+use sentry::{ClientInitGuard, Hub, Scope, SentryFutureExt};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::info;
+
+use crate::auth::principal::Credential;
+use crate::auth::Principal;
+use crate::config::ErrorReportingConfig;
+use crate::error::current_correlation_id;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserIdReporting {
+    // Events name no user
+    Omit,
+    // An HMAC of the ID: one user's events group together without naming them
+    Pseudonymize,
+    Raw,
+}
+
+// The event field, from a log line or a tag, that holds a user ID
+const USER_ID_FIELD: &str = "user_id";
+
+// Set by `init`; until then no user ID is reported
+static USER_IDS: OnceLock<UserIds> = OnceLock::new();
+
+struct UserIds {
+    reporting: UserIdReporting,
+    key: Vec<u8>,
+}
+
+/// What an event is about. Set on the current request or task with
+/// [`set_context`], or passed to [`report`] for one error.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ReportContext<'a> {
+    pub stream_id: Option<&'a str>,
+    pub bet_id: Option<&'a str>,
+    // Reported as `error_reporting.user_ids` says
+    pub user_id: Option<&'a str>,
+}
+
+impl ReportContext<'_> {
+    fn apply(&self, scope: &mut Scope) {
+        if let Some(stream_id) = self.stream_id {
+            scope.set_tag("stream_id", stream_id);
+        }
+        if let Some(bet_id) = self.bet_id {
+            scope.set_tag("bet_id", bet_id);
+        }
+        if let Some(user_id) = self.user_id.and_then(reported_user_id) {
+            scope.set_user(Some(sentry::User {
+                id: Some(user_id),
+                ..Default::default()
+            }));
+        }
+    }
+}
+
+/// Starts sending panics, ERROR log lines and [`report`]ed errors to the
+/// configured DSN, if there is one. Queued events are flushed when the
+/// returned guard is dropped, so hold it until the process exits.
+pub fn init(config: &ErrorReportingConfig) -> Option<ClientInitGuard> {
+    let dsn = config.dsn.as_deref()?;
+    let _ = USER_IDS.set(UserIds {
+        reporting: config.user_ids,
+        key: config.user_id_key.clone().unwrap_or_default().into_bytes(),
+    });
+
+    let guard = sentry::init((dsn, sentry::ClientOptions {
+        release: sentry::release_name!(),
+        environment: config.environment.clone().map(Cow::Owned),
+        sample_rate: config.sample_rate,
+        // No IP addresses, and no request headers or bodies
+        send_default_pii: false,
+        max_breadcrumbs: if config.breadcrumbs { 100 } else { 0 },
+        before_send: Some(Arc::new(scrub_user_ids)),
+        ..Default::default()
+    }));
+    info!("Reporting errors to {}", guard.dsn().map(|dsn| dsn.host().to_string()).unwrap_or_default());
+    Some(guard)
+}
+
+/// Reports `error` with `context`, for a failure that a log line alone
+/// would leave without the IDs needed to follow it up.
+pub fn report(error: &anyhow::Error, context: ReportContext<'_>) {
+    sentry::with_scope(
+        |scope| context.apply(scope),
+        || sentry::integrations::anyhow::capture_anyhow(error),
+    );
+}
+
+/// Adds `context` to everything the current request or task reports from here on.
+pub fn set_context(context: ReportContext<'_>) {
+    sentry::configure_scope(|scope| context.apply(scope));
+}
+
+/// Gives `future` a scope of its own, inheriting the spawner's, so context
+/// one task sets doesn't leak into another's events. Its events and panics
+/// are tagged with `task`.
+pub fn isolate<F: Future>(task: &str, future: F) -> impl Future<Output = F::Output> {
+    let hub = Arc::new(Hub::new_from_top(Hub::current()));
+    hub.configure_scope(|scope| scope.set_tag("task", task));
+    future.bind_hub(hub)
+}
+
+/// Tags what the rest of the request reports with its route, correlation ID,
+/// caller and the stream, bet or user in its path. Must run inside the
+/// authentication layer.
+pub async fn attribute(params: Option<RawPathParams>, request: Request, next: Next) -> Response {
+    let hub = Arc::new(Hub::new_from_top(Hub::current()));
+    let route = request.extensions().get::<MatchedPath>().map(|path| path.as_str().to_string());
+    hub.configure_scope(|scope| {
+        scope.set_tag("http.method", request.method().as_str());
+        scope.set_transaction(route.as_deref());
+        if let Some(correlation_id) = current_correlation_id() {
+            scope.set_tag("correlation_id", correlation_id);
+        }
+        if let Some(principal) = request.extensions().get::<Principal>() {
+            if let Credential::UserToken = principal.credential {
+                ReportContext { user_id: Some(principal.subject.as_str()), ..Default::default() }.apply(scope);
+            }
+        }
+
+        let mut context = ReportContext::default();
+        for (name, value) in params.iter().flat_map(|params| params.iter()) {
+            match name {
+                "stream_id" => context.stream_id = Some(value),
+                // `/api/streams/:id` and the routes under it
+                "id" if route.as_deref().map(|route| route.starts_with("/api/streams/")).unwrap_or(false) => {
+                    context.stream_id = Some(value)
+                }
+                "bet_id" => context.bet_id = Some(value),
+                "user_id" => context.user_id = Some(value),
+                _ => {}
+            }
+        }
+        context.apply(scope);
+    });
+
+    next.run(request).bind_hub(hub).await
+}
+
+fn reported_user_id(user_id: &str) -> Option<String> {
+    let user_ids = USER_IDS.get()?;
+    match user_ids.reporting {
+        UserIdReporting::Omit => None,
+        UserIdReporting::Pseudonymize => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(&user_ids.key).ok()?;
+            mac.update(user_id.as_bytes());
+            let digest = hex::encode(mac.finalize().into_bytes());
+            Some(format!("u_{}", &digest[..24]))
+        }
+        UserIdReporting::Raw => Some(user_id.to_string()),
+    }
+}
+
+// Log lines name users in their fields, e.g. `error!(user_id = %id, ...)`;
+// those are reported the same way as the event's user
+fn scrub_user_ids(mut event: Event<'static>) -> Option<Event<'static>> {
+    if let Some(user_id) = event.tags.remove(USER_ID_FIELD) {
+        if let Some(reported) = reported_user_id(&user_id) {
+            event.tags.insert(USER_ID_FIELD.to_string(), reported);
+        }
+    }
+    scrub_field(&mut event.extra);
+    for context in event.contexts.values_mut() {
+        if let EventContext::Other(fields) = context {
+            scrub_field(fields);
+        }
+    }
+    Some(event)
+}
+
+fn scrub_field(fields: &mut sentry::protocol::Map<String, Value>) {
+    let Some(value) = fields.remove(USER_ID_FIELD) else {
+        return;
+    };
+    let user_id = match &value {
+        Value::String(user_id) => user_id.clone(),
+        other => other.to_string(),
+    };
+    if let Some(reported) = reported_user_id(&user_id) {
+        fields.insert(USER_ID_FIELD.to_string(), Value::String(reported));
+    }
+}
@@ -0,0 +1,223 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use futures::StreamExt;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::message::{Header, Message, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::ClientConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::config::EventBusConfig;
+use crate::outbox::{OutboxError, OutboxEvent, OutboxHandler};
+
+pub type EventError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventBusKind {
+    // Domain events only reach webhooks
+    None,
+    Nats,
+    Kafka,
+}
+
+/// One event as it goes onto the bus.
+pub struct BusMessage<'a> {
+    // NATS subject or Kafka topic
+    pub subject: &'a str,
+    // Events with the same key stay in order where the bus partitions
+    pub key: &'a str,
+    // Stays the same when the event is published again, so consumers can drop duplicates
+    pub event_id: &'a str,
+    pub payload: &'a [u8],
+}
+
+/// A message bus downstream systems consume domain events from.
+#[async_trait]
+pub trait EventPublisher: Send + Sync {
+    /// Returns once the bus has the message, so a failure can be retried.
+    async fn publish(&self, message: BusMessage<'_>) -> Result<(), EventError>;
+}
+
+/// Events other instances published to the bus, as they arrive.
+#[async_trait]
+pub trait EventSubscriber: Send {
+    /// The next message's payload, or None once the subscription has ended.
+    async fn next(&mut self) -> Option<Result<Vec<u8>, EventError>>;
+}
+
+/// Connects to the configured bus, or returns None with none configured.
+pub async fn connect(config: &EventBusConfig) -> Result<Option<Box<dyn EventPublisher>>, EventError> {
+    let timeout = Duration::from_millis(config.publish_timeout_ms);
+    match config.backend {
+        EventBusKind::None => Ok(None),
+        EventBusKind::Nats => {
+            let url = config.nats_url.as_deref().ok_or("EVENT_BUS_NATS_URL is required for the nats event bus")?;
+            let client = async_nats::connect(url).await?;
+            info!("Publishing domain events to NATS at {}", url);
+            Ok(Some(Box::new(NatsPublisher { client, timeout })))
+        }
+        EventBusKind::Kafka => {
+            let producer: FutureProducer = ClientConfig::new()
+                .set("bootstrap.servers", config.kafka_brokers.join(","))
+                // Retries inside the producer can't duplicate or reorder a partition's events
+                .set("enable.idempotence", "true")
+                .set("message.timeout.ms", config.publish_timeout_ms.to_string())
+                .create()?;
+            info!("Publishing domain events to Kafka at {}", config.kafka_brokers.join(","));
+            Ok(Some(Box::new(KafkaPublisher { producer, timeout })))
+        }
+    }
+}
+
+/// Subscribes to every event under the configured prefix, or returns None
+/// with no bus configured. Subscribers sharing a `group` split the events
+/// between them on Kafka; on NATS each gets them all.
+pub async fn subscribe(config: &EventBusConfig, group: &str) -> Result<Option<Box<dyn EventSubscriber>>, EventError> {
+    match config.backend {
+        EventBusKind::None => Ok(None),
+        EventBusKind::Nats => {
+            let url = config.nats_url.as_deref().ok_or("EVENT_BUS_NATS_URL is required for the nats event bus")?;
+            let client = async_nats::connect(url).await?;
+            let subscriber = client.subscribe(format!("{}.>", config.subject_prefix)).await?;
+            info!("Subscribed to {}.> on NATS at {}", config.subject_prefix, url);
+            Ok(Some(Box::new(NatsSubscriber { subscriber })))
+        }
+        EventBusKind::Kafka => {
+            let consumer: StreamConsumer = ClientConfig::new()
+                .set("bootstrap.servers", config.kafka_brokers.join(","))
+                .set("group.id", group)
+                // Replication is about what happens from now on, not the topics' history
+                .set("auto.offset.reset", "latest")
+                .create()?;
+            // A leading '^' makes the subscription a pattern, covering event types added later
+            let pattern = format!("^{}\\..*", config.subject_prefix.replace('.', "\\."));
+            consumer.subscribe(&[&pattern])?;
+            info!("Subscribed to {} on Kafka as {}", pattern, group);
+            Ok(Some(Box::new(KafkaSubscriber { consumer })))
+        }
+    }
+}
+
+/// Core NATS publishing. The event ID goes in `Nats-Msg-Id`, so a JetStream
+/// stream capturing the subjects drops republished events.
+struct NatsPublisher {
+    client: async_nats::Client,
+    timeout: Duration,
+}
+
+#[async_trait]
+impl EventPublisher for NatsPublisher {
+    async fn publish(&self, message: BusMessage<'_>) -> Result<(), EventError> {
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert("Nats-Msg-Id", message.event_id);
+        headers.insert("Morphine-Key", message.key);
+
+        self.client
+            .publish_with_headers(message.subject.to_string(), headers, message.payload.to_vec().into())
+            .await?;
+        // Publishing only buffers; flushing is what tells us the server has it
+        tokio::time::timeout(self.timeout, self.client.flush()).await
+            .map_err(|_| "Timed out flushing to NATS")??;
+        Ok(())
+    }
+}
+
+struct KafkaPublisher {
+    producer: FutureProducer,
+    timeout: Duration,
+}
+
+#[async_trait]
+impl EventPublisher for KafkaPublisher {
+    async fn publish(&self, message: BusMessage<'_>) -> Result<(), EventError> {
+        let headers = OwnedHeaders::new().insert(Header { key: "event_id", value: Some(message.event_id) });
+        let record = FutureRecord::to(message.subject)
+            .key(message.key)
+            .payload(message.payload)
+            .headers(headers);
+
+        self.producer.send(record, self.timeout).await
+            .map_err(|(e, _)| e)?;
+        Ok(())
+    }
+}
+
+struct NatsSubscriber {
+    subscriber: async_nats::Subscriber,
+}
+
+#[async_trait]
+impl EventSubscriber for NatsSubscriber {
+    async fn next(&mut self) -> Option<Result<Vec<u8>, EventError>> {
+        self.subscriber.next().await.map(|message| Ok(message.payload.to_vec()))
+    }
+}
+
+struct KafkaSubscriber {
+    consumer: StreamConsumer,
+}
+
+#[async_trait]
+impl EventSubscriber for KafkaSubscriber {
+    async fn next(&mut self) -> Option<Result<Vec<u8>, EventError>> {
+        Some(match self.consumer.recv().await {
+            Ok(message) => Ok(message.payload().unwrap_or_default().to_vec()),
+            Err(e) => Err(e.into()),
+        })
+    }
+}
+
+/// Relays outbox events to the bus, on `<prefix>.<event type>`, as an
+/// envelope carrying the event ID, type, time, tenant and the region it was
+/// published from around its data.
+pub struct EventBusRelay {
+    publisher: Box<dyn EventPublisher>,
+    subject_prefix: String,
+    region: String,
+}
+
+impl EventBusRelay {
+    pub fn new(publisher: Box<dyn EventPublisher>, subject_prefix: String, region: String) -> Self {
+        Self { publisher, subject_prefix, region }
+    }
+}
+
+#[async_trait]
+impl OutboxHandler for EventBusRelay {
+    fn name(&self) -> &'static str {
+        "event_bus"
+    }
+
+    async fn handle(&self, event: &OutboxEvent) -> Result<(), OutboxError> {
+        let envelope = json!({
+            "event_id": event.event_id,
+            "event_type": event.event_type,
+            "occurred_at": event.occurred_at,
+            "tenant_id": event.tenant_id,
+            "region": self.region,
+            "data": event.data,
+        });
+        let payload = serde_json::to_vec(&envelope)?;
+        let subject = format!("{}.{}", self.subject_prefix, event.event_type);
+
+        self.publisher.publish(BusMessage {
+            subject: &subject,
+            key: &partition_key(event),
+            event_id: &event.event_id,
+            payload: &payload,
+        }).await
+    }
+}
+
+// A user's events stay in order, as do a stream's; anything else is keyed by itself
+fn partition_key(event: &OutboxEvent) -> String {
+    let entity = ["user_id", "stream_id"].iter()
+        .find_map(|field| event.data.get(*field).and_then(Value::as_str));
+    match entity {
+        Some(id) => format!("{}:{}", event.tenant_id, id),
+        None => event.event_id.clone(),
+    }
+}
@@ -0,0 +1,764 @@
+mod storage;
+mod tables;
+
+pub use storage::{LocalStore, ObjectStore, S3Store};
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres, Row};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::betting::{fairness, BettingEngine};
+use crate::config::ExportConfig;
+use crate::geolocation::GeolocationService;
+use crate::pagination::{PageRequest, Paginated};
+use crate::shutdown::Shutdown;
+use crate::tenant::{self, Tenant};
+use crate::validation::{Validate, ValidationErrors};
+
+use tables::{Cell, ColumnType, Table};
+
+pub type ExportError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStorageKind {
+    Local,
+    S3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+
+    fn parse(format: &str) -> Self {
+        match format {
+            "parquet" => ExportFormat::Parquet,
+            _ => ExportFormat::Csv,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+}
+
+/// What an export can contain, one file each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportDataset {
+    // Placed in the range, archived or not
+    Bets,
+    // Resolved in the range, with their outcome and payout
+    Settlements,
+    // Balance changes recorded in the range
+    Ledger,
+    // Location checks in the range, of those still held in memory
+    LocationVerifications,
+    // Realized margin and implied-vs-actual win rates of bets settled in the
+    // range, per market type and implied probability band
+    Fairness,
+}
+
+impl ExportDataset {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportDataset::Bets => "bets",
+            ExportDataset::Settlements => "settlements",
+            ExportDataset::Ledger => "ledger",
+            ExportDataset::LocationVerifications => "location_verifications",
+            ExportDataset::Fairness => "fairness",
+        }
+    }
+
+    fn parse(dataset: &str) -> Option<Self> {
+        match dataset {
+            "bets" => Some(ExportDataset::Bets),
+            "settlements" => Some(ExportDataset::Settlements),
+            "ledger" => Some(ExportDataset::Ledger),
+            "location_verifications" => Some(ExportDataset::LocationVerifications),
+            "fairness" => Some(ExportDataset::Fairness),
+            _ => None,
+        }
+    }
+}
+
+/// The body of `POST /api/admin/exports`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ExportRequest {
+    pub datasets: Vec<ExportDataset>,
+    pub format: ExportFormat,
+    // Rows from `from` up to, not including, `to`
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl Validate for ExportRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        if self.datasets.is_empty() {
+            errors.add("datasets", "needs at least one dataset");
+        }
+        if self.datasets.iter().enumerate().any(|(index, dataset)| self.datasets[..index].contains(dataset)) {
+            errors.add("datasets", "must not repeat a dataset");
+        }
+        if self.from >= self.to {
+            errors.add("to", "must be after from");
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStatus {
+    Pending,
+    Running,
+    Completed,
+    // Gave up after the configured number of attempts, or the range held too many rows
+    Failed,
+}
+
+impl ExportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportStatus::Pending => "pending",
+            ExportStatus::Running => "running",
+            ExportStatus::Completed => "completed",
+            ExportStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(status: &str) -> Self {
+        match status {
+            "running" => ExportStatus::Running,
+            "completed" => ExportStatus::Completed,
+            "failed" => ExportStatus::Failed,
+            _ => ExportStatus::Pending,
+        }
+    }
+}
+
+/// One dataset's file in a completed export.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExportFile {
+    pub dataset: ExportDataset,
+    pub key: String,
+    pub rows: u64,
+    pub bytes: u64,
+    // Signed afresh each time the export is fetched; never stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ExportJob {
+    pub export_id: String,
+    pub datasets: Vec<ExportDataset>,
+    pub format: ExportFormat,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub status: ExportStatus,
+    pub files: Vec<ExportFile>,
+    pub error: Option<String>,
+    pub attempts: i32,
+    pub requested_by: String,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+// A dataset with more rows than `max_rows_per_dataset`; retrying won't help
+#[derive(Debug)]
+struct RowLimitExceeded {
+    dataset: ExportDataset,
+    limit: usize,
+}
+
+impl fmt::Display for RowLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} has more than {} rows in the range; export a narrower range", self.dataset.as_str(), self.limit)
+    }
+}
+
+impl std::error::Error for RowLimitExceeded {}
+
+struct ClaimedExport {
+    export_id: String,
+    tenant_id: String,
+    datasets: Vec<ExportDataset>,
+    format: ExportFormat,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    attempts: i32,
+}
+
+/// Generates requested exports in the background. Any instance may claim a
+/// requested export; each dataset is read under the requesting tenant, so
+/// row-level security limits it to that tenant's rows, then encoded and
+/// uploaded as one file. A completed export's files are downloaded through
+/// URLs signed each time the export is fetched.
+pub struct ExportService {
+    db_pool: Pool<Postgres>,
+    config: ExportConfig,
+    store: Arc<dyn ObjectStore>,
+    // Set with local storage, which this service serves downloads for
+    local: Option<Arc<LocalStore>>,
+    geolocation: Arc<GeolocationService>,
+    betting: Arc<BettingEngine>,
+    default_tenant: String,
+    shutdown: Shutdown,
+}
+
+impl ExportService {
+    pub fn new(
+        db_pool: Pool<Postgres>,
+        config: ExportConfig,
+        geolocation: Arc<GeolocationService>,
+        betting: Arc<BettingEngine>,
+        default_tenant: String,
+        shutdown: Shutdown,
+    ) -> Result<Self, ExportError> {
+        let (store, local): (Arc<dyn ObjectStore>, _) = match config.storage {
+            ExportStorageKind::Local => {
+                let local = Arc::new(LocalStore::new(&config));
+                (local.clone(), Some(local))
+            }
+            ExportStorageKind::S3 => (Arc::new(S3Store::from_env(&config)?), None),
+        };
+
+        Ok(Self { db_pool, config, store, local, geolocation, betting, default_tenant, shutdown })
+    }
+
+    /// Starts the worker. An export cut off by shutdown is taken over by
+    /// another instance once its lease runs out.
+    pub fn start(self: &Arc<Self>) {
+        let service = self.clone();
+        self.shutdown.spawn_loop("exports:worker", async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(service.config.poll_interval_ms));
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.run_due().await {
+                    warn!("Export worker failed: {}", e);
+                }
+            }
+        });
+        info!("Export worker started, storing to {:?}", self.config.storage);
+    }
+
+    /// Queues an export for the current tenant.
+    pub async fn request(&self, request: &ExportRequest) -> Result<ExportJob, ExportError> {
+        ValidationErrors::collect(request)?;
+        let mut errors = ValidationErrors::new();
+        if request.to - request.from > chrono::Duration::days(self.config.max_range_days as i64) {
+            errors.add("to", format!("must be at most {} days after from", self.config.max_range_days));
+        }
+        // Location history is held in memory without a tenant
+        let default_tenant = tenant::current().map(|tenant| tenant.is_default).unwrap_or(true);
+        if !default_tenant && request.datasets.contains(&ExportDataset::LocationVerifications) {
+            errors.add("datasets", "location_verifications can only be exported by the default tenant");
+        }
+        if !errors.is_empty() {
+            return Err(errors.into());
+        }
+
+        let datasets: Vec<&str> = request.datasets.iter().map(ExportDataset::as_str).collect();
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO data_exports (export_id, datasets, format, range_start, range_end)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING {}
+            "#,
+            EXPORT_COLUMNS
+        ))
+        .bind(Uuid::new_v4().to_string())
+        .bind(&datasets)
+        .bind(request.format.as_str())
+        .bind(request.from)
+        .bind(request.to)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(export_from_row(&row))
+    }
+
+    /// The export, with download URLs once it has completed.
+    pub async fn get(&self, export_id: &str) -> Result<Option<ExportJob>, ExportError> {
+        let row = sqlx::query(&format!("SELECT {} FROM data_exports WHERE export_id = $1", EXPORT_COLUMNS))
+            .bind(export_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+
+        row.as_ref().map(|row| self.with_download_urls(export_from_row(row))).transpose()
+    }
+
+    pub async fn list(&self, page: &PageRequest) -> Result<Paginated<ExportJob>, ExportError> {
+        let ascending = page.sort.as_ref().map(|sort| !sort.descending).unwrap_or(false);
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM data_exports")
+            .fetch_one(&self.db_pool)
+            .await?;
+
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM data_exports ORDER BY created_at {} LIMIT $1 OFFSET $2",
+            EXPORT_COLUMNS,
+            if ascending { "ASC" } else { "DESC" }
+        ))
+        .bind(page.limit as i64)
+        .bind(page.offset as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let exports = rows.iter()
+            .map(|row| self.with_download_urls(export_from_row(row)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(page.page_of(exports, total as usize))
+    }
+
+    /// A locally stored export file and its content type, if the signature
+    /// from its download URL holds. None with any other storage.
+    pub async fn download(
+        &self,
+        export_id: &str,
+        file: &str,
+        expires: i64,
+        signature: &str,
+    ) -> Result<Option<(Vec<u8>, &'static str)>, ExportError> {
+        let Some(local) = &self.local else {
+            return Ok(None);
+        };
+        let format = match file.rsplit_once('.') {
+            Some((_, "parquet")) => ExportFormat::Parquet,
+            Some((_, "csv")) => ExportFormat::Csv,
+            _ => return Ok(None),
+        };
+        let body = local.open(&format!("{}/{}", export_id, file), expires, signature).await?;
+        Ok(body.map(|body| (body, format.content_type())))
+    }
+
+    fn with_download_urls(&self, mut export: ExportJob) -> Result<ExportJob, ExportError> {
+        if export.status == ExportStatus::Completed {
+            let ttl = Duration::from_secs(self.config.url_ttl_secs);
+            for file in &mut export.files {
+                file.download_url = Some(self.store.signed_url(&file.key, ttl)?);
+            }
+        }
+        Ok(export)
+    }
+
+    async fn run_due(&self) -> Result<(), ExportError> {
+        // A running export whose lease ran out was cut off, e.g. by a restart
+        let row = sqlx::query(
+            r#"
+            UPDATE data_exports e
+            SET status = 'running', attempts = e.attempts + 1,
+                lease_until = NOW() + make_interval(secs => $1), started_at = COALESCE(e.started_at, NOW())
+            FROM (
+                SELECT export_id
+                FROM data_exports
+                WHERE status = 'pending' OR (status = 'running' AND lease_until < NOW())
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            ) due
+            WHERE e.export_id = due.export_id
+            RETURNING e.export_id, e.tenant_id, e.datasets, e.format, e.range_start, e.range_end, e.attempts
+            "#
+        )
+        .bind(self.config.lease_secs as f64)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(());
+        };
+        let datasets: Vec<String> = row.get("datasets");
+        let format: String = row.get("format");
+        let export = ClaimedExport {
+            export_id: row.get("export_id"),
+            tenant_id: row.get("tenant_id"),
+            datasets: datasets.iter().filter_map(|dataset| ExportDataset::parse(dataset)).collect(),
+            format: ExportFormat::parse(&format),
+            from: row.get("range_start"),
+            to: row.get("range_end"),
+            attempts: row.get("attempts"),
+        };
+
+        if export.attempts as u32 > self.config.max_attempts {
+            return self.record_failure(&export, "Gave up after the export was cut off too many times", true).await;
+        }
+
+        let tenant = Tenant {
+            is_default: export.tenant_id == self.default_tenant,
+            id: export.tenant_id.clone(),
+        };
+        match tenant::scope(tenant, self.generate(&export)).await {
+            Ok(files) => {
+                info!(
+                    "Completed export {} of {} rows in {} files",
+                    export.export_id,
+                    files.iter().map(|file| file.rows).sum::<u64>(),
+                    files.len()
+                );
+                self.record_success(&export, &files).await
+            }
+            Err(e) => {
+                let permanent = e.is::<RowLimitExceeded>() || export.attempts as u32 >= self.config.max_attempts;
+                self.record_failure(&export, &e.to_string(), permanent).await
+            }
+        }
+    }
+
+    async fn generate(&self, export: &ClaimedExport) -> Result<Vec<ExportFile>, ExportError> {
+        let mut files = Vec::new();
+        for dataset in &export.datasets {
+            let table = match dataset {
+                ExportDataset::Bets => self.bets(export).await?,
+                ExportDataset::Settlements => self.settlements(export).await?,
+                ExportDataset::Ledger => self.ledger(export).await?,
+                ExportDataset::LocationVerifications => self.location_verifications(export)?,
+                ExportDataset::Fairness => self.fairness(export).await?,
+            };
+            if table.len() > self.config.max_rows_per_dataset {
+                return Err(Box::new(RowLimitExceeded { dataset: *dataset, limit: self.config.max_rows_per_dataset }));
+            }
+
+            let rows = table.len() as u64;
+            let format = export.format;
+            // Encoding a large table is CPU-bound; keep it off the runtime's workers
+            let body = tokio::task::spawn_blocking(move || table.encode(format)).await??;
+            let bytes = body.len() as u64;
+            let key = format!("{}/{}.{}", export.export_id, dataset.as_str(), format.as_str());
+            self.store.put(&key, body, format.content_type()).await?;
+            files.push(ExportFile { dataset: *dataset, key, rows, bytes, download_url: None });
+        }
+        Ok(files)
+    }
+
+    // Reads one past the row limit, so an oversized range is caught without loading all of it
+    fn fetch_limit(&self) -> i64 {
+        self.config.max_rows_per_dataset as i64 + 1
+    }
+
+    async fn bets(&self, export: &ClaimedExport) -> Result<Table, ExportError> {
+        const COLUMNS: &[(&str, ColumnType)] = &[
+            ("bet_id", ColumnType::Text),
+            ("user_id", ColumnType::Text),
+            ("stream_id", ColumnType::Text),
+            ("bet_type", ColumnType::Text),
+            ("stake_amount", ColumnType::Float),
+            ("odds", ColumnType::Float),
+            ("potential_payout", ColumnType::Float),
+            ("status", ColumnType::Text),
+            ("prediction", ColumnType::Text),
+            ("created_at", ColumnType::Timestamp),
+            ("resolution_deadline", ColumnType::Timestamp),
+            ("archived", ColumnType::Boolean),
+        ];
+        let select = "SELECT id, user_id, stream_id, bet_type, stake_amount::float8 AS stake_amount, \
+            odds::float8 AS odds, potential_payout::float8 AS potential_payout, status, \
+            prediction::text AS prediction, created_at, resolution_deadline";
+        let rows = sqlx::query(&format!(
+            r#"
+            {select}, FALSE AS archived FROM bets WHERE created_at >= $1 AND created_at < $2
+            UNION ALL
+            {select}, TRUE AS archived FROM bets_archive WHERE created_at >= $1 AND created_at < $2
+            ORDER BY created_at, id
+            LIMIT $3
+            "#
+        ))
+        .bind(export.from)
+        .bind(export.to)
+        .bind(self.fetch_limit())
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let mut table = Table::new(COLUMNS);
+        for row in &rows {
+            table.push(vec![
+                Cell::from(row.get::<String, _>("id")),
+                Cell::from(row.get::<String, _>("user_id")),
+                Cell::from(row.get::<String, _>("stream_id")),
+                Cell::from(row.get::<String, _>("bet_type")),
+                Cell::from(row.get::<f64, _>("stake_amount")),
+                Cell::from(row.get::<f64, _>("odds")),
+                Cell::from(row.get::<f64, _>("potential_payout")),
+                Cell::from(row.get::<String, _>("status")),
+                Cell::from(row.get::<String, _>("prediction")),
+                Cell::from(row.get::<DateTime<Utc>, _>("created_at")),
+                Cell::from(row.get::<DateTime<Utc>, _>("resolution_deadline")),
+                Cell::from(row.get::<bool, _>("archived")),
+            ]);
+        }
+        Ok(table)
+    }
+
+    async fn settlements(&self, export: &ClaimedExport) -> Result<Table, ExportError> {
+        const COLUMNS: &[(&str, ColumnType)] = &[
+            ("bet_id", ColumnType::Text),
+            ("user_id", ColumnType::Text),
+            ("stream_id", ColumnType::Text),
+            ("bet_type", ColumnType::Text),
+            ("stake_amount", ColumnType::Float),
+            ("odds", ColumnType::Float),
+            ("won", ColumnType::Boolean),
+            ("payout_amount", ColumnType::Float),
+            ("confidence_score", ColumnType::Float),
+            ("actual_result", ColumnType::Text),
+            ("resolved_at", ColumnType::Timestamp),
+        ];
+        let select = "SELECT id, user_id, stream_id, bet_type, stake_amount::float8 AS stake_amount, \
+            odds::float8 AS odds, (resolution_result->>'won')::boolean AS won, \
+            (resolution_result->>'payout_amount')::float8 AS payout_amount, \
+            (resolution_result->>'confidence_score')::float8 AS confidence_score, \
+            (resolution_result->'actual_result')::text AS actual_result, \
+            (resolution_result->>'resolved_at')::timestamptz AS resolved_at";
+        // A bet is resolved after it's placed, so created_at narrows the scan to the index
+        let filter = "resolution_result IS NOT NULL AND created_at < $2 \
+            AND (resolution_result->>'resolved_at')::timestamptz >= $1 \
+            AND (resolution_result->>'resolved_at')::timestamptz < $2";
+        let rows = sqlx::query(&format!(
+            r#"
+            {select} FROM bets WHERE {filter}
+            UNION ALL
+            {select} FROM bets_archive WHERE {filter}
+            ORDER BY resolved_at, id
+            LIMIT $3
+            "#
+        ))
+        .bind(export.from)
+        .bind(export.to)
+        .bind(self.fetch_limit())
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let mut table = Table::new(COLUMNS);
+        for row in &rows {
+            table.push(vec![
+                Cell::from(row.get::<String, _>("id")),
+                Cell::from(row.get::<String, _>("user_id")),
+                Cell::from(row.get::<String, _>("stream_id")),
+                Cell::from(row.get::<String, _>("bet_type")),
+                Cell::from(row.get::<f64, _>("stake_amount")),
+                Cell::from(row.get::<f64, _>("odds")),
+                Cell::from(row.get::<Option<bool>, _>("won")),
+                Cell::from(row.get::<Option<f64>, _>("payout_amount")),
+                Cell::from(row.get::<Option<f64>, _>("confidence_score")),
+                Cell::from(row.get::<Option<String>, _>("actual_result")),
+                Cell::from(row.get::<DateTime<Utc>, _>("resolved_at")),
+            ]);
+        }
+        Ok(table)
+    }
+
+    async fn ledger(&self, export: &ClaimedExport) -> Result<Table, ExportError> {
+        const COLUMNS: &[(&str, ColumnType)] = &[
+            ("entry_id", ColumnType::Integer),
+            ("user_id", ColumnType::Text),
+            ("stream_id", ColumnType::Text),
+            ("bet_id", ColumnType::Text),
+            ("entry_type", ColumnType::Text),
+            ("amount", ColumnType::Float),
+            ("balance_after", ColumnType::Float),
+            ("created_at", ColumnType::Timestamp),
+        ];
+        let rows = sqlx::query(
+            r#"
+            SELECT entry_id, user_id, stream_id, bet_id, entry_type,
+                amount::float8 AS amount, balance_after::float8 AS balance_after, created_at
+            FROM balance_ledger
+            WHERE created_at >= $1 AND created_at < $2
+            ORDER BY entry_id
+            LIMIT $3
+            "#
+        )
+        .bind(export.from)
+        .bind(export.to)
+        .bind(self.fetch_limit())
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let mut table = Table::new(COLUMNS);
+        for row in &rows {
+            table.push(vec![
+                Cell::from(row.get::<i64, _>("entry_id")),
+                Cell::from(row.get::<String, _>("user_id")),
+                Cell::from(row.get::<String, _>("stream_id")),
+                Cell::from(row.get::<String, _>("bet_id")),
+                Cell::from(row.get::<String, _>("entry_type")),
+                Cell::from(row.get::<f64, _>("amount")),
+                Cell::from(row.get::<f64, _>("balance_after")),
+                Cell::from(row.get::<DateTime<Utc>, _>("created_at")),
+            ]);
+        }
+        Ok(table)
+    }
+
+    fn location_verifications(&self, export: &ClaimedExport) -> Result<Table, ExportError> {
+        const COLUMNS: &[(&str, ColumnType)] = &[
+            ("verification_id", ColumnType::Text),
+            ("user_id", ColumnType::Text),
+            ("session_id", ColumnType::Text),
+            ("verified_at", ColumnType::Timestamp),
+            ("method", ColumnType::Text),
+            ("latitude", ColumnType::Float),
+            ("longitude", ColumnType::Float),
+            ("accuracy", ColumnType::Float),
+            ("confidence_score", ColumnType::Float),
+            ("is_excluded", ColumnType::Boolean),
+            ("exclusion_zone_ids", ColumnType::Text),
+            ("video_frame_hash", ColumnType::Text),
+        ];
+        // Only the default tenant may request these; see `request`
+        let mut table = Table::new(COLUMNS);
+        for verification in self.geolocation.verifications_between(export.from, export.to) {
+            let zone_ids: Vec<&str> = verification.exclusion_zones.iter().map(|zone| zone.zone_id.as_str()).collect();
+            table.push(vec![
+                Cell::from(verification.verification_id),
+                Cell::from(verification.user_id),
+                Cell::from(verification.session_id),
+                Cell::from(Utc.timestamp_nanos(verification.timestamp_ns as i64)),
+                Cell::from(format!("{:?}", verification.verification_method)),
+                Cell::from(verification.location.latitude),
+                Cell::from(verification.location.longitude),
+                Cell::from(verification.location.accuracy),
+                Cell::from(verification.confidence_score),
+                Cell::from(verification.is_excluded),
+                Cell::from(zone_ids.join(",")),
+                Cell::from(verification.video_frame_hash),
+            ]);
+            // In memory already, but the file would still be too large to be useful
+            if table.len() > self.config.max_rows_per_dataset {
+                break;
+            }
+        }
+        Ok(table)
+    }
+
+    async fn fairness(&self, export: &ClaimedExport) -> Result<Table, ExportError> {
+        const COLUMNS: &[(&str, ColumnType)] = &[
+            ("bet_type", ColumnType::Text),
+            // Both null on the row totalling the market type
+            ("band_lower", ColumnType::Float),
+            ("band_upper", ColumnType::Float),
+            ("settled_bets", ColumnType::Integer),
+            ("stakes", ColumnType::Float),
+            ("payouts", ColumnType::Float),
+            ("realized_margin", ColumnType::Float),
+            ("implied_probability", ColumnType::Float),
+            ("actual_win_rate", ColumnType::Float),
+        ];
+        let report = self.betting.fairness_report(export.from, export.to).await?;
+
+        let mut table = Table::new(COLUMNS);
+        for market in report.markets {
+            table.push(vec![
+                Cell::from(market.bet_type.clone()),
+                Cell::Null,
+                Cell::Null,
+                Cell::from(market.settled_bets as i64),
+                Cell::from(market.stakes),
+                Cell::from(market.payouts),
+                Cell::from(market.realized_margin),
+                Cell::from(market.implied_probability),
+                Cell::from(market.actual_win_rate),
+            ]);
+            for band in market.bands {
+                table.push(vec![
+                    Cell::from(market.bet_type.clone()),
+                    Cell::from(band.lower),
+                    Cell::from(band.upper),
+                    Cell::from(band.settled_bets as i64),
+                    Cell::from(band.stakes),
+                    Cell::from(band.payouts),
+                    Cell::from(fairness::realized_margin(band.stakes, band.payouts)),
+                    Cell::from(band.implied_probability),
+                    Cell::from(band.actual_win_rate),
+                ]);
+            }
+        }
+        Ok(table)
+    }
+
+    async fn record_success(&self, export: &ClaimedExport, files: &[ExportFile]) -> Result<(), ExportError> {
+        sqlx::query(
+            r#"
+            UPDATE data_exports
+            SET status = 'completed', files = $2, error = NULL, lease_until = NULL, finished_at = NOW()
+            WHERE export_id = $1
+            "#
+        )
+        .bind(&export.export_id)
+        .bind(serde_json::to_value(files)?)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    // A failure that isn't permanent is retried from the start at the next poll
+    async fn record_failure(&self, export: &ClaimedExport, error: &str, permanent: bool) -> Result<(), ExportError> {
+        if permanent {
+            warn!("Export {} failed after {} attempts: {}", export.export_id, export.attempts, error);
+        } else {
+            warn!("Export {} failed, will retry: {}", export.export_id, error);
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE data_exports
+            SET status = $2, error = $3, lease_until = NULL, finished_at = CASE WHEN $2 = 'failed' THEN NOW() END
+            WHERE export_id = $1
+            "#
+        )
+        .bind(&export.export_id)
+        .bind(if permanent { ExportStatus::Failed } else { ExportStatus::Pending }.as_str())
+        .bind(error)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+}
+
+const EXPORT_COLUMNS: &str = "export_id, datasets, format, range_start, range_end, status, files, error, attempts, \
+    requested_by, created_at, started_at, finished_at";
+
+fn export_from_row(row: &sqlx::postgres::PgRow) -> ExportJob {
+    let datasets: Vec<String> = row.get("datasets");
+    let format: String = row.get("format");
+    let status: String = row.get("status");
+    let files: serde_json::Value = row.get("files");
+    ExportJob {
+        export_id: row.get("export_id"),
+        // Datasets were validated on the way in
+        datasets: datasets.iter().filter_map(|dataset| ExportDataset::parse(dataset)).collect(),
+        format: ExportFormat::parse(&format),
+        from: row.get("range_start"),
+        to: row.get("range_end"),
+        status: ExportStatus::parse(&status),
+        files: serde_json::from_value(files).unwrap_or_default(),
+        error: row.get("error"),
+        attempts: row.get("attempts"),
+        requested_by: row.get("requested_by"),
+        created_at: row.get("created_at"),
+        started_at: row.get("started_at"),
+        finished_at: row.get("finished_at"),
+    }
+}
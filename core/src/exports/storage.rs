@@ -0,0 +1,273 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::config::ExportConfig;
+use crate::secrets::hmac_sha256;
+
+use super::ExportError;
+
+/// Where finished export files are kept and downloaded from.
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    async fn put(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<(), ExportError>;
+
+    /// A URL anyone holding it can download `key` from until `ttl` has passed.
+    fn signed_url(&self, key: &str, ttl: Duration) -> Result<String, ExportError>;
+}
+
+/// Export files on local disk, downloaded through
+/// `GET /api/exports/download/{export_id}/{file}` with an HMAC-signed expiry.
+/// Only for a single instance or a shared volume.
+pub struct LocalStore {
+    dir: PathBuf,
+    public_url: String,
+    signing_key: Vec<u8>,
+}
+
+pub(crate) const DOWNLOAD_PATH: &str = "/api/exports/download";
+
+impl LocalStore {
+    pub fn new(config: &ExportConfig) -> Self {
+        let signing_key = match &config.local_signing_key {
+            Some(key) => key.clone(),
+            None => {
+                warn!("EXPORTS_LOCAL_SIGNING_KEY not set; export download URLs stop working on restart");
+                format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+            }
+        };
+        Self {
+            dir: PathBuf::from(&config.local_dir),
+            public_url: config.public_url.trim_end_matches('/').to_string(),
+            signing_key: signing_key.into_bytes(),
+        }
+    }
+
+    /// The file at `key`, if `signature` was issued for it and `expires`
+    /// hasn't passed. None for a bad or expired signature or a missing file.
+    pub async fn open(&self, key: &str, expires: i64, signature: &str) -> Result<Option<Vec<u8>>, ExportError> {
+        if expires < Utc::now().timestamp() || !self.verify(key, expires, signature) {
+            return Ok(None);
+        }
+        match tokio::fs::read(self.dir.join(key)).await {
+            Ok(body) => Ok(Some(body)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn mac(&self, key: &str, expires: i64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.signing_key).expect("HMAC takes any key length");
+        mac.update(format!("{}\n{}", key, expires).as_bytes());
+        mac
+    }
+
+    fn verify(&self, key: &str, expires: i64, signature: &str) -> bool {
+        match hex::decode(signature) {
+            // Constant time, so a signature can't be guessed byte by byte
+            Ok(signature) => self.mac(key, expires).verify_slice(&signature).is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for LocalStore {
+    async fn put(&self, key: &str, body: Vec<u8>, _content_type: &str) -> Result<(), ExportError> {
+        let path = self.dir.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Written aside and renamed, so a download never sees half a file
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, body).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        Ok(())
+    }
+
+    fn signed_url(&self, key: &str, ttl: Duration) -> Result<String, ExportError> {
+        let expires = Utc::now().timestamp() + ttl.as_secs() as i64;
+        let signature = hex::encode(self.mac(key, expires).finalize().into_bytes());
+        Ok(format!("{}{}/{}?expires={}&signature={}", self.public_url, DOWNLOAD_PATH, key, expires, signature))
+    }
+}
+
+/// An S3 bucket, or an S3-compatible store, addressed path-style with
+/// SigV4-signed uploads and presigned downloads.
+pub struct S3Store {
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    prefix: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    http_client: reqwest::Client,
+}
+
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+impl S3Store {
+    /// Credentials come from the standard AWS environment variables.
+    pub fn from_env(config: &ExportConfig) -> Result<Self, ExportError> {
+        let bucket = config.s3_bucket.clone().ok_or("EXPORTS_S3_BUCKET is required for s3 export storage")?;
+        let region = config.s3_region.clone().ok_or("EXPORTS_S3_REGION is required for s3 export storage")?;
+        let endpoint = match &config.s3_endpoint {
+            Some(endpoint) => Url::parse(endpoint)?,
+            None => Url::parse(&format!("https://s3.{}.amazonaws.com", region))?,
+        };
+        let access_key_id = std::env::var("AWS_ACCESS_KEY_ID")
+            .map_err(|_| "AWS_ACCESS_KEY_ID must be set for s3 export storage")?;
+        let secret_access_key = std::env::var("AWS_SECRET_ACCESS_KEY")
+            .map_err(|_| "AWS_SECRET_ACCESS_KEY must be set for s3 export storage")?;
+
+        Ok(Self {
+            endpoint,
+            bucket,
+            region,
+            prefix: config.s3_prefix.clone(),
+            access_key_id,
+            secret_access_key,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok().filter(|token| !token.is_empty()),
+            http_client: reqwest::Client::builder()
+                .timeout(Duration::from_millis(config.upload_timeout_ms))
+                .build()?,
+        })
+    }
+
+    fn host(&self) -> String {
+        let host = self.endpoint.host_str().unwrap_or_default();
+        match self.endpoint.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        }
+    }
+
+    // `/bucket/prefix/key`, each segment URI-encoded as SigV4 expects
+    fn path(&self, key: &str) -> String {
+        format!("/{}/{}", uri_encode(&self.bucket, true), uri_encode(&format!("{}{}", self.prefix, key), false))
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.endpoint.as_str().trim_end_matches('/'), path)
+    }
+
+    fn scope(&self, date: &str) -> String {
+        format!("{}/{}/s3/aws4_request", date, self.region)
+    }
+
+    fn signature(&self, amz_date: &str, canonical_request: &str) -> String {
+        let date = &amz_date[..8];
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            self.scope(date),
+            hex::encode(Sha256::digest(canonical_request.as_bytes())),
+        );
+
+        let mut key = hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), date);
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac_sha256(&key, part);
+        }
+        hex::encode(hmac_sha256(&key, &string_to_sign))
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3Store {
+    async fn put(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<(), ExportError> {
+        let path = self.path(key);
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let mut headers = vec![
+            ("content-type", content_type.to_string()),
+            ("host", self.host()),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        headers.sort_by(|a, b| a.0.cmp(b.0));
+
+        let canonical_headers: String = headers.iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect();
+        let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+        let canonical_request = format!("PUT\n{}\n\n{}\n{}\n{}", path, canonical_headers, signed_headers, payload_hash);
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id,
+            self.scope(&amz_date[..8]),
+            signed_headers,
+            self.signature(&amz_date, &canonical_request),
+        );
+
+        let mut request = self.http_client.put(self.url(&path))
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .header("X-Amz-Content-Sha256", &payload_hash)
+            .header("X-Amz-Date", &amz_date)
+            .header(reqwest::header::AUTHORIZATION, authorization);
+        if let Some(token) = &self.session_token {
+            request = request.header("X-Amz-Security-Token", token);
+        }
+
+        let response = request.body(body).send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let detail = response.text().await.unwrap_or_default();
+            return Err(format!("S3 responded {} to uploading {}: {}", status, key, detail).into());
+        }
+        Ok(())
+    }
+
+    // A presigned GET: the signature travels in the query, over the host header alone
+    fn signed_url(&self, key: &str, ttl: Duration) -> Result<String, ExportError> {
+        let path = self.path(key);
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let credential = format!("{}/{}", self.access_key_id, self.scope(&amz_date[..8]));
+
+        let mut query = vec![
+            ("X-Amz-Algorithm", "AWS4-HMAC-SHA256".to_string()),
+            ("X-Amz-Credential", credential),
+            ("X-Amz-Date", amz_date.clone()),
+            ("X-Amz-Expires", ttl.as_secs().to_string()),
+            ("X-Amz-SignedHeaders", "host".to_string()),
+        ];
+        if let Some(token) = &self.session_token {
+            query.push(("X-Amz-Security-Token", token.clone()));
+        }
+        query.sort_by(|a, b| a.0.cmp(b.0));
+        let canonical_query = query.iter()
+            .map(|(name, value)| format!("{}={}", name, uri_encode(value, true)))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let canonical_request = format!(
+            "GET\n{}\n{}\nhost:{}\n\nhost\n{}",
+            path, canonical_query, self.host(), UNSIGNED_PAYLOAD
+        );
+        let signature = self.signature(&amz_date, &canonical_request);
+        Ok(format!("{}?{}&X-Amz-Signature={}", self.url(&path), canonical_query, signature))
+    }
+}
+
+// RFC 3986 unreserved characters pass through; `/` too, within a key
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
@@ -0,0 +1,188 @@
+use std::sync::Arc;
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, SecondsFormat, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+use super::{ExportError, ExportFormat};
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ColumnType {
+    Text,
+    Float,
+    Integer,
+    Boolean,
+    Timestamp,
+}
+
+impl ColumnType {
+    fn data_type(&self) -> DataType {
+        match self {
+            ColumnType::Text => DataType::Utf8,
+            ColumnType::Float => DataType::Float64,
+            ColumnType::Integer => DataType::Int64,
+            ColumnType::Boolean => DataType::Boolean,
+            ColumnType::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Cell {
+    Null,
+    Text(String),
+    Float(f64),
+    Integer(i64),
+    Boolean(bool),
+    Timestamp(DateTime<Utc>),
+}
+
+impl From<String> for Cell {
+    fn from(value: String) -> Self {
+        Cell::Text(value)
+    }
+}
+
+impl From<f64> for Cell {
+    fn from(value: f64) -> Self {
+        Cell::Float(value)
+    }
+}
+
+impl From<i64> for Cell {
+    fn from(value: i64) -> Self {
+        Cell::Integer(value)
+    }
+}
+
+impl From<bool> for Cell {
+    fn from(value: bool) -> Self {
+        Cell::Boolean(value)
+    }
+}
+
+impl From<DateTime<Utc>> for Cell {
+    fn from(value: DateTime<Utc>) -> Self {
+        Cell::Timestamp(value)
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Cell::Null)
+    }
+}
+
+impl Cell {
+    // Nulls are empty fields; timestamps are RFC 3339 in UTC
+    fn to_csv(&self) -> String {
+        match self {
+            Cell::Null => String::new(),
+            Cell::Text(value) => value.clone(),
+            Cell::Float(value) => value.to_string(),
+            Cell::Integer(value) => value.to_string(),
+            Cell::Boolean(value) => value.to_string(),
+            Cell::Timestamp(value) => value.to_rfc3339_opts(SecondsFormat::Micros, true),
+        }
+    }
+}
+
+/// One dataset's rows, in the columns it declares. A cell of the wrong type
+/// for its column is written as null.
+pub(crate) struct Table {
+    columns: &'static [(&'static str, ColumnType)],
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub(crate) fn new(columns: &'static [(&'static str, ColumnType)]) -> Self {
+        Self { columns, rows: Vec::new() }
+    }
+
+    pub(crate) fn push(&mut self, row: Vec<Cell>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub(crate) fn encode(&self, format: ExportFormat) -> Result<Vec<u8>, ExportError> {
+        match format {
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Parquet => self.to_parquet(),
+        }
+    }
+
+    fn to_csv(&self) -> Result<Vec<u8>, ExportError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(self.columns.iter().map(|(name, _)| *name))?;
+        for row in &self.rows {
+            writer.write_record(row.iter().map(Cell::to_csv))?;
+        }
+        Ok(writer.into_inner().map_err(|e| e.into_error())?)
+    }
+
+    // One row group, Snappy-compressed; exports are capped well below where that matters
+    fn to_parquet(&self) -> Result<Vec<u8>, ExportError> {
+        let schema = Arc::new(Schema::new(
+            self.columns.iter()
+                .map(|(name, column_type)| Field::new(*name, column_type.data_type(), true))
+                .collect::<Vec<_>>(),
+        ));
+        let arrays = self.columns.iter()
+            .enumerate()
+            .map(|(index, (_, column_type))| self.array(index, *column_type))
+            .collect();
+        let batch = RecordBatch::try_new(schema.clone(), arrays)?;
+
+        let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let mut buffer = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut buffer, schema, Some(properties))?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(buffer)
+    }
+
+    fn array(&self, index: usize, column_type: ColumnType) -> ArrayRef {
+        let cells = self.rows.iter().map(|row| &row[index]);
+        match column_type {
+            ColumnType::Text => Arc::new(cells
+                .map(|cell| match cell {
+                    Cell::Text(value) => Some(value.as_str()),
+                    _ => None,
+                })
+                .collect::<StringArray>()),
+            ColumnType::Float => Arc::new(cells
+                .map(|cell| match cell {
+                    Cell::Float(value) => Some(*value),
+                    _ => None,
+                })
+                .collect::<Float64Array>()),
+            ColumnType::Integer => Arc::new(cells
+                .map(|cell| match cell {
+                    Cell::Integer(value) => Some(*value),
+                    _ => None,
+                })
+                .collect::<Int64Array>()),
+            ColumnType::Boolean => Arc::new(cells
+                .map(|cell| match cell {
+                    Cell::Boolean(value) => Some(*value),
+                    _ => None,
+                })
+                .collect::<BooleanArray>()),
+            ColumnType::Timestamp => Arc::new(cells
+                .map(|cell| match cell {
+                    Cell::Timestamp(value) => Some(value.timestamp_micros()),
+                    _ => None,
+                })
+                .collect::<TimestampMicrosecondArray>()
+                .with_timezone("UTC")),
+        }
+    }
+}
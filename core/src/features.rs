@@ -0,0 +1,267 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres, Row};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::config::FeatureFlagConfig;
+use crate::shutdown::Shutdown;
+use crate::tenant;
+use crate::validation::{Validate, ValidationErrors};
+
+type FeatureFlagError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A flag the code consults, and what it evaluates to until an operator
+/// stores a flag of that name.
+#[derive(Debug, Clone, Copy)]
+pub struct Flag {
+    pub name: &'static str,
+    pub default: bool,
+}
+
+/// Pattern bets, the riskiest to settle; off switches them off for everyone.
+pub const PATTERN_BETS: Flag = Flag { name: "betting.pattern_bets", default: true };
+/// Odds that scale smoothly with the bet's time window instead of in three steps.
+pub const CONTINUOUS_TIME_ODDS: Flag = Flag { name: "odds.continuous_time_factor", default: false };
+
+const KNOWN_FLAGS: &[Flag] = &[PATTERN_BETS, CONTINUOUS_TIME_ODDS];
+
+/// A stored flag. `enabled = false` turns it off for everyone, whatever the
+/// targeting; otherwise listed users and streams always get it, and the
+/// rest get it by rollout percentage.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeatureFlag {
+    pub name: String,
+    pub description: Option<String>,
+    pub enabled: bool,
+    pub rollout_percentage: f64,
+    pub user_ids: Vec<String>,
+    pub stream_ids: Vec<String>,
+    // Limits the flag to these tenants; empty means every tenant
+    pub tenants: Vec<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FeatureFlagUpdate {
+    pub description: Option<String>,
+    pub enabled: bool,
+    #[serde(default)]
+    pub rollout_percentage: f64,
+    #[serde(default)]
+    pub user_ids: Vec<String>,
+    #[serde(default)]
+    pub stream_ids: Vec<String>,
+    #[serde(default)]
+    pub tenants: Vec<String>,
+}
+
+impl Validate for FeatureFlagUpdate {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_range("rollout_percentage", self.rollout_percentage, 0.0, 100.0);
+        for (field, ids) in [("user_ids", &self.user_ids), ("stream_ids", &self.stream_ids), ("tenants", &self.tenants)] {
+            if ids.iter().any(|id| id.trim().is_empty()) {
+                errors.add(field, "must not contain empty IDs");
+            }
+        }
+    }
+}
+
+/// Who a flag is evaluated for. Percentage rollouts bucket by user, or by
+/// stream when there is no user, so each keeps the same answer across requests.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlagContext<'a> {
+    pub user_id: Option<&'a str>,
+    pub stream_id: Option<&'a str>,
+}
+
+impl FeatureFlag {
+    fn evaluate(&self, context: &FlagContext) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if !self.tenants.is_empty() {
+            let tenant = tenant::current().map(|tenant| tenant.id);
+            if !tenant.map(|tenant| self.tenants.contains(&tenant)).unwrap_or(false) {
+                return false;
+            }
+        }
+        if context.user_id.map(|id| self.user_ids.iter().any(|user_id| user_id == id)).unwrap_or(false)
+            || context.stream_id.map(|id| self.stream_ids.iter().any(|stream_id| stream_id == id)).unwrap_or(false)
+        {
+            return true;
+        }
+
+        if self.rollout_percentage >= 100.0 {
+            return true;
+        }
+        match context.user_id.or(context.stream_id) {
+            Some(key) => bucket(&self.name, key) < (self.rollout_percentage * 100.0) as u32,
+            None => false,
+        }
+    }
+}
+
+// 0..10000, stable per flag and key, so raising the percentage only ever adds callers
+fn bucket(flag: &str, key: &str) -> u32 {
+    let digest = Sha256::digest(format!("{}:{}", flag, key).as_bytes());
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) % 10_000
+}
+
+fn valid_flag_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= 100
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-'))
+}
+
+/// Feature flags stored in Postgres and cached in memory. Every instance
+/// re-reads them each refresh interval, so turning a flag off takes effect
+/// everywhere within that interval and at once on the instance that changed it.
+pub struct FeatureFlags {
+    db_pool: Pool<Postgres>,
+    config: FeatureFlagConfig,
+    flags: RwLock<HashMap<String, FeatureFlag>>,
+    shutdown: Shutdown,
+}
+
+impl FeatureFlags {
+    pub fn new(db_pool: Pool<Postgres>, config: FeatureFlagConfig, shutdown: Shutdown) -> Self {
+        Self {
+            db_pool,
+            config,
+            flags: RwLock::new(HashMap::new()),
+            shutdown,
+        }
+    }
+
+    /// Loads the flags, then keeps them fresh until shutdown.
+    pub async fn start(self: &Arc<Self>) -> Result<(), FeatureFlagError> {
+        let loaded = self.refresh().await?;
+        info!("Loaded {} feature flags", loaded);
+
+        let flags = self.clone();
+        self.shutdown.spawn_loop("features:refresh", async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(flags.config.refresh_interval_ms));
+            // The flags were just loaded
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                // Keep evaluating with the last flags read rather than falling back to defaults
+                if let Err(e) = flags.refresh().await {
+                    warn!("Failed to refresh feature flags: {}", e);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn refresh(&self) -> Result<usize, FeatureFlagError> {
+        let rows = sqlx::query(&format!("SELECT {} FROM feature_flags", FLAG_COLUMNS))
+            .fetch_all(&self.db_pool)
+            .await?;
+        let flags: HashMap<String, FeatureFlag> = rows.iter()
+            .map(flag_from_row)
+            .map(|flag| (flag.name.clone(), flag))
+            .collect();
+
+        let count = flags.len();
+        *self.flags.write() = flags;
+        Ok(count)
+    }
+
+    pub fn is_enabled(&self, flag: Flag, context: &FlagContext) -> bool {
+        match self.flags.read().get(flag.name) {
+            Some(stored) => stored.evaluate(context),
+            None => flag.default,
+        }
+    }
+
+    /// Every known and stored flag, evaluated for `context`.
+    pub fn evaluate_all(&self, context: &FlagContext) -> BTreeMap<String, bool> {
+        let flags = self.flags.read();
+        let mut evaluated: BTreeMap<String, bool> = KNOWN_FLAGS.iter()
+            .map(|flag| (flag.name.to_string(), flag.default))
+            .collect();
+        for (name, flag) in flags.iter() {
+            evaluated.insert(name.clone(), flag.evaluate(context));
+        }
+        evaluated
+    }
+
+    pub fn list(&self) -> Vec<FeatureFlag> {
+        let mut flags: Vec<FeatureFlag> = self.flags.read().values().cloned().collect();
+        flags.sort_by(|a, b| a.name.cmp(&b.name));
+        flags
+    }
+
+    /// Creates or replaces the flag `name`; in effect on this instance at once.
+    pub async fn upsert(&self, name: &str, update: &FeatureFlagUpdate) -> Result<FeatureFlag, FeatureFlagError> {
+        ValidationErrors::collect(update)?;
+        if !valid_flag_name(name) {
+            let mut errors = ValidationErrors::new();
+            errors.add("name", "must be 1 to 100 lowercase letters, digits, '.', '_' or '-'");
+            return Err(errors.into());
+        }
+
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO feature_flags (name, description, enabled, rollout_percentage, user_ids, stream_ids, tenants)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (name) DO UPDATE SET
+                description = EXCLUDED.description,
+                enabled = EXCLUDED.enabled,
+                rollout_percentage = EXCLUDED.rollout_percentage,
+                user_ids = EXCLUDED.user_ids,
+                stream_ids = EXCLUDED.stream_ids,
+                tenants = EXCLUDED.tenants,
+                updated_at = NOW()
+            RETURNING {}
+            "#,
+            FLAG_COLUMNS
+        ))
+        .bind(name)
+        .bind(&update.description)
+        .bind(update.enabled)
+        .bind(update.rollout_percentage)
+        .bind(&update.user_ids)
+        .bind(&update.stream_ids)
+        .bind(&update.tenants)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        let flag = flag_from_row(&row);
+        self.flags.write().insert(flag.name.clone(), flag.clone());
+        Ok(flag)
+    }
+
+    /// Deletes the flag, so it evaluates to its default again. Returns false if there was none.
+    pub async fn delete(&self, name: &str) -> Result<bool, FeatureFlagError> {
+        let result = sqlx::query("DELETE FROM feature_flags WHERE name = $1")
+            .bind(name)
+            .execute(&self.db_pool)
+            .await?;
+        self.flags.write().remove(name);
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn flag_from_row(row: &sqlx::postgres::PgRow) -> FeatureFlag {
+    FeatureFlag {
+        name: row.get("name"),
+        description: row.get("description"),
+        enabled: row.get("enabled"),
+        rollout_percentage: row.get("rollout_percentage"),
+        user_ids: row.get("user_ids"),
+        stream_ids: row.get("stream_ids"),
+        tenants: row.get("tenants"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+const FLAG_COLUMNS: &str = "name, description, enabled, rollout_percentage, user_ids, stream_ids, tenants, updated_at";
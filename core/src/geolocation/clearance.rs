@@ -0,0 +1,101 @@
+use std::sync::Arc;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::LocationVerification;
+use crate::state::StateManager;
+
+type ClearanceError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A user's latest location verification, kept so a bet can be placed on any
+/// instance, not only the one holding the location session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationClearance {
+    pub verification_id: String,
+    pub session_id: String,
+    pub is_excluded: bool,
+    pub confidence_score: f64,
+    pub verified_at: DateTime<Utc>,
+}
+
+/// Whether a user's location lets them bet right now.
+#[derive(Debug, Clone)]
+pub enum Clearance {
+    // Verification isn't required
+    NotRequired,
+    Cleared(LocationClearance),
+    // Why not, as the bettor is told
+    Refused(String),
+}
+
+impl Clearance {
+    /// The verification a placed bet records; None when none was required.
+    pub fn verification_id(&self) -> Option<&str> {
+        match self {
+            Clearance::Cleared(clearance) => Some(&clearance.verification_id),
+            _ => None,
+        }
+    }
+}
+
+/// Bets need a recent location verification, outside every exclusion zone,
+/// from the bettor's own location session, which mustn't have been revoked
+/// since. Each verification replaces the user's last one in Redis, which
+/// forgets it once it's too old to count.
+pub struct LocationClearances {
+    state: Arc<StateManager>,
+    // 0 when verification isn't required
+    max_age_secs: u64,
+}
+
+impl LocationClearances {
+    pub fn new(state: Arc<StateManager>, max_age_secs: u64) -> Self {
+        Self { state, max_age_secs }
+    }
+
+    pub fn is_enforced(&self) -> bool {
+        self.max_age_secs > 0
+    }
+
+    /// Makes `verification` the user's latest, whether it cleared them or not.
+    pub async fn record(&self, verification: &LocationVerification) -> Result<(), ClearanceError> {
+        if !self.is_enforced() {
+            return Ok(());
+        }
+        let clearance = LocationClearance {
+            verification_id: verification.verification_id.clone(),
+            session_id: verification.session_id.clone(),
+            is_excluded: verification.is_excluded,
+            confidence_score: verification.confidence_score,
+            verified_at: Utc::now(),
+        };
+        self.state.set_location_clearance(
+            &verification.user_id,
+            &serde_json::to_string(&clearance)?,
+            self.max_age_secs as usize,
+        ).await?;
+        Ok(())
+    }
+
+    pub async fn check(&self, user_id: &str) -> Result<Clearance, ClearanceError> {
+        if !self.is_enforced() {
+            return Ok(Clearance::NotRequired);
+        }
+        let Some(stored) = self.state.get_location_clearance(user_id).await? else {
+            return Ok(Clearance::Refused("Verify your location before placing a bet".to_string()));
+        };
+        let clearance: LocationClearance = serde_json::from_str(&stored)?;
+        // Redis expires it too, but not to the second
+        if Utc::now() - clearance.verified_at > Duration::seconds(self.max_age_secs as i64) {
+            return Ok(Clearance::Refused("Your location verification has expired; verify it again".to_string()));
+        }
+        if clearance.is_excluded {
+            return Ok(Clearance::Refused("Betting isn't allowed from your current location".to_string()));
+        }
+        // The mark `SessionRegistry::revoke` leaves, so revoking the session withdraws its clearance at once
+        if self.state.is_session_revoked(&clearance.session_id).await? {
+            return Ok(Clearance::Refused("Your location session was revoked; verify your location again".to_string()));
+        }
+        Ok(Clearance::Cleared(clearance))
+    }
+}
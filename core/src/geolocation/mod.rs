@@ -1,14 +1,22 @@
+pub mod clearance;
 pub mod kalman;
 pub mod triangulation;
 pub mod verification;
 pub mod precision_timing;
+pub mod zones;
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
+use crate::analytics_client::AnalyticsClient;
+use crate::cache::{BoundedCache, CacheStats};
+use crate::config::GeolocationConfig;
+use crate::validation::{Validate, ValidationErrors};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeolocationPoint {
     pub latitude: f64,
@@ -83,6 +91,49 @@ pub enum ExclusionType {
     CompetitorZone,   // Areas where competitors are located
 }
 
+impl Validate for GeolocationPoint {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_coordinates(self.latitude, self.longitude);
+        errors.require_range("accuracy", self.accuracy, 0.0, f64::MAX);
+        errors.require_range("confidence", self.confidence, 0.0, 1.0);
+        if self.altitude.map(|altitude| !altitude.is_finite()).unwrap_or(false) {
+            errors.add("altitude", "must be a finite number");
+        }
+    }
+}
+
+impl Validate for CellTowerData {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("tower_id", &self.tower_id);
+        errors.require_coordinates(self.latitude, self.longitude);
+        if !self.signal_strength.is_finite() {
+            errors.add("signal_strength", "must be a finite number");
+        }
+    }
+}
+
+impl Validate for WiFiAccessPoint {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("bssid", &self.bssid);
+        errors.require_coordinates(self.latitude, self.longitude);
+        if !self.signal_strength.is_finite() {
+            errors.add("signal_strength", "must be a finite number");
+        }
+    }
+}
+
+impl Validate for ExclusionZone {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("zone_id", &self.zone_id);
+        errors.require_range("center_lat", self.center_lat, -90.0, 90.0);
+        errors.require_range("center_lon", self.center_lon, -180.0, 180.0);
+        errors.require_positive("radius_meters", self.radius_meters);
+        if self.active_until.map(|until| until <= self.active_from).unwrap_or(false) {
+            errors.add("active_until", "must be after active_from");
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionVerification {
     pub transaction_id: String,
@@ -97,6 +148,9 @@ pub struct TransactionVerification {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VideoEvidence {
     pub frame_hash: String,
+    // The stream the frame is from, when known; narrows the analytics service's lookup
+    #[serde(default)]
+    pub stream_id: Option<String>,
     pub timestamp_ns: u128,
     pub frame_metadata: FrameMetadata,
     pub location_markers: Vec<LocationMarker>,
@@ -124,15 +178,20 @@ pub struct GeolocationService {
     triangulation_engine: Arc<triangulation::TriangulationEngine>,
     verification_engine: Arc<verification::VerificationEngine>,
     precision_timer: Arc<precision_timing::PrecisionTimer>,
+    precision_timing_enabled: bool,
     
     // Real-time location tracking
     active_sessions: Arc<RwLock<HashMap<String, LocationSession>>>,
     exclusion_zones: Arc<RwLock<Vec<ExclusionZone>>>,
-    verification_history: Arc<RwLock<HashMap<String, Vec<LocationVerification>>>>,
+    // Each user's most recent verifications, oldest first
+    verification_history: Arc<BoundedCache<String, Vec<LocationVerification>>>,
+    history_per_user: usize,
     
     // Video frame correlation
-    frame_location_map: Arc<RwLock<HashMap<String, GeolocationPoint>>>,
+    frame_location_map: Arc<BoundedCache<String, GeolocationPoint>>,
     transaction_evidence: Arc<RwLock<HashMap<String, TransactionVerification>>>,
+    // Confirms evidence frames with the service that processed them
+    analytics: Arc<AnalyticsClient>,
 }
 
 #[derive(Debug, Clone)]
@@ -146,25 +205,36 @@ struct LocationSession {
 }
 
 impl GeolocationService {
-    pub async fn new() -> Self {
+    pub async fn new(precision_timing_enabled: bool, config: &GeolocationConfig, analytics: Arc<AnalyticsClient>) -> Self {
         Self {
             kalman_filter: Arc::new(kalman::KalmanFilter::new()),
             triangulation_engine: Arc::new(triangulation::TriangulationEngine::new()),
             verification_engine: Arc::new(verification::VerificationEngine::new()),
             precision_timer: Arc::new(precision_timing::PrecisionTimer::new()),
+            precision_timing_enabled,
             
             active_sessions: Arc::new(RwLock::new(HashMap::new())),
             exclusion_zones: Arc::new(RwLock::new(Vec::new())),
-            verification_history: Arc::new(RwLock::new(HashMap::new())),
+            verification_history: Arc::new(BoundedCache::new(
+                "verification_history",
+                config.history_max_users,
+                Some(Duration::from_secs(config.history_ttl_secs)),
+            )),
+            history_per_user: config.history_per_user,
             
-            frame_location_map: Arc::new(RwLock::new(HashMap::new())),
+            frame_location_map: Arc::new(BoundedCache::new(
+                "frame_locations",
+                config.frame_locations_max_entries,
+                Some(Duration::from_secs(config.frame_location_ttl_secs)),
+            )),
             transaction_evidence: Arc::new(RwLock::new(HashMap::new())),
+            analytics,
         }
     }
     
     pub async fn start_location_session(&self, user_id: String) -> String {
         let session_id = uuid::Uuid::new_v4().to_string();
-        let timestamp_ns = self.precision_timer.get_nanosecond_timestamp().await;
+        let timestamp_ns = self.get_nanosecond_timestamp().await;
         
         let session = LocationSession {
             session_id: session_id.clone(),
@@ -180,6 +250,12 @@ impl GeolocationService {
         
         session_id
     }
+
+    /// Forgets a session, so later updates to it fail. Returns false if this
+    /// instance had no such session.
+    pub async fn end_location_session(&self, session_id: &str) -> bool {
+        self.active_sessions.write().await.remove(session_id).is_some()
+    }
     
     pub async fn update_location_multi_source(
         &self,
@@ -189,7 +265,7 @@ impl GeolocationService {
         wifi_points: Vec<WiFiAccessPoint>,
         video_frame_hash: Option<String>
     ) -> Result<LocationVerification, Box<dyn std::error::Error + Send + Sync>> {
-        let timestamp_ns = self.precision_timer.get_nanosecond_timestamp().await;
+        let timestamp_ns = self.get_nanosecond_timestamp().await;
         
         // Multi-source data fusion
         let fused_location = self.fuse_location_sources(
@@ -379,10 +455,11 @@ impl GeolocationService {
         }
         
         // Store in verification history
-        let mut history = self.verification_history.write().await;
-        history.entry(verification.user_id.clone())
-            .or_insert_with(Vec::new)
-            .push(verification.clone());
+        self.verification_history.upsert(verification.user_id.clone(), Vec::new, |history| {
+            history.push(verification.clone());
+            let excess = history.len().saturating_sub(self.history_per_user);
+            history.drain(..excess);
+        });
     }
     
     async fn get_session_history(&self, session_id: &str) -> Vec<GeolocationPoint> {
@@ -393,8 +470,7 @@ impl GeolocationService {
     }
     
     async fn correlate_frame_location(&self, frame_hash: String, location: GeolocationPoint) {
-        let mut frame_map = self.frame_location_map.write().await;
-        frame_map.insert(frame_hash, location);
+        self.frame_location_map.insert(frame_hash, location);
     }
     
     pub async fn create_transaction_verification(
@@ -403,11 +479,18 @@ impl GeolocationService {
         user_id: String,
         video_evidence: VideoEvidence
     ) -> Result<TransactionVerification, Box<dyn std::error::Error + Send + Sync>> {
-        let timestamp_ns = self.precision_timer.get_nanosecond_timestamp().await;
+        let timestamp_ns = self.get_nanosecond_timestamp().await;
+        
+        // A hash we were handed proves nothing until the analytics service confirms it saw the frame
+        let frame_check = self.analytics
+            .verify_frame_hash(video_evidence.stream_id.as_deref(), &video_evidence.frame_hash)
+            .await?;
+        if !frame_check.verified {
+            return Err("Frame hash not recognised by the analytics service".into());
+        }
         
         // Get location from frame correlation
-        let frame_map = self.frame_location_map.read().await;
-        let frame_location = frame_map.get(&video_evidence.frame_hash)
+        let frame_location = self.frame_location_map.get(&video_evidence.frame_hash)
             .ok_or("Frame location not found")?;
         
         // Create location verification for this transaction
@@ -419,7 +502,7 @@ impl GeolocationService {
             verification_method: VerificationMethod::VideoAnalysis,
             confidence_score: 0.95, // High confidence from video evidence
             exclusion_zones: self.exclusion_zones.read().await.clone(),
-            is_excluded: self.check_exclusion_zones(frame_location, &self.exclusion_zones.read().await).await,
+            is_excluded: self.check_exclusion_zones(&frame_location, &self.exclusion_zones.read().await).await,
             timestamp_ns,
             video_frame_hash: Some(video_evidence.frame_hash.clone()),
         };
@@ -475,6 +558,10 @@ impl GeolocationService {
         zones.push(zone);
     }
     
+    pub async fn exclusion_zone_count(&self) -> usize {
+        self.exclusion_zones.read().await.len()
+    }
+    
     pub async fn verify_transaction_location(&self, transaction_id: &str) -> Option<bool> {
         let evidence = self.transaction_evidence.read().await;
         evidence.get(transaction_id)
@@ -482,8 +569,26 @@ impl GeolocationService {
     }
     
     pub async fn get_location_history(&self, user_id: &str) -> Vec<LocationVerification> {
-        let history = self.verification_history.read().await;
-        history.get(user_id).cloned().unwrap_or_default()
+        self.verification_history.get(&user_id.to_string()).unwrap_or_default()
+    }
+    
+    /// Every retained verification, of any user, from `from` up to `to`,
+    /// oldest first. Only what `history_per_user` and `history_ttl_secs` keep.
+    pub fn verifications_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<LocationVerification> {
+        let nanos = |at: DateTime<Utc>| at.timestamp_nanos_opt().unwrap_or(0).max(0) as u128;
+        let (from, to) = (nanos(from), nanos(to));
+        let mut verifications: Vec<LocationVerification> = self.verification_history.values()
+            .into_iter()
+            .flatten()
+            .filter(|verification| verification.timestamp_ns >= from && verification.timestamp_ns < to)
+            .collect();
+        verifications.sort_by_key(|verification| verification.timestamp_ns);
+        verifications
+    }
+    
+    /// The location caches' sizes and counters, for `/metrics`.
+    pub fn cache_stats(&self) -> Vec<CacheStats> {
+        vec![self.frame_location_map.stats(), self.verification_history.stats()]
     }
     
     pub async fn is_user_excluded(&self, user_id: &str) -> bool {
@@ -492,7 +597,24 @@ impl GeolocationService {
             .any(|session| session.user_id == user_id && session.current_exclusion_status)
     }
     
+    /// The user a location session was started for, if this instance holds it.
+    pub async fn session_user(&self, session_id: &str) -> Option<String> {
+        let sessions = self.active_sessions.read().await;
+        sessions.get(session_id).map(|session| session.user_id.clone())
+    }
+    
+    pub async fn is_session_excluded(&self, session_id: &str) -> bool {
+        let sessions = self.active_sessions.read().await;
+        sessions.get(session_id)
+            .map(|session| session.current_exclusion_status)
+            .unwrap_or(false)
+    }
+    
     pub async fn get_nanosecond_timestamp(&self) -> u128 {
-        self.precision_timer.get_nanosecond_timestamp().await
+        if self.precision_timing_enabled {
+            self.precision_timer.get_nanosecond_timestamp().await
+        } else {
+            Utc::now().timestamp_nanos_opt().unwrap_or_default() as u128
+        }
     }
 } 
\ No newline at end of file
@@ -0,0 +1,83 @@
+use sqlx::{Pool, Postgres, Row};
+
+use super::{ExclusionType, ExclusionZone};
+
+type ZoneError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Exclusion zones in Postgres, so they outlive the process holding them in
+/// memory. Zones apply on every tenant, like the laws and venues behind them.
+/// A zone with `deleted_at` set no longer applies, and saving its ID again
+/// leaves it deleted.
+pub struct ExclusionZoneStore {
+    db_pool: Pool<Postgres>,
+}
+
+impl ExclusionZoneStore {
+    pub fn new(db_pool: Pool<Postgres>) -> Self {
+        Self { db_pool }
+    }
+
+    /// Saves `zone`, replacing any zone with the same ID.
+    pub async fn save(&self, zone: &ExclusionZone) -> Result<(), ZoneError> {
+        sqlx::query(
+            r#"
+            INSERT INTO exclusion_zones (zone_id, center_lat, center_lon, radius_meters, zone_type, active_from, active_until)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (zone_id) DO UPDATE SET
+                center_lat = EXCLUDED.center_lat,
+                center_lon = EXCLUDED.center_lon,
+                radius_meters = EXCLUDED.radius_meters,
+                zone_type = EXCLUDED.zone_type,
+                active_from = EXCLUDED.active_from,
+                active_until = EXCLUDED.active_until
+            WHERE exclusion_zones.deleted_at IS NULL
+            "#
+        )
+        .bind(&zone.zone_id)
+        .bind(zone.center_lat)
+        .bind(zone.center_lon)
+        .bind(zone.radius_meters)
+        .bind(zone_type_name(&zone.zone_type)?)
+        .bind(zone.active_from)
+        .bind(zone.active_until)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Zones that are in force now or will be later.
+    pub async fn load_current(&self) -> Result<Vec<ExclusionZone>, ZoneError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT zone_id, center_lat, center_lon, radius_meters, zone_type, active_from, active_until
+            FROM exclusion_zones
+            WHERE deleted_at IS NULL AND (active_until IS NULL OR active_until > NOW())
+            ORDER BY active_from
+            "#
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(ExclusionZone {
+                    zone_id: row.get("zone_id"),
+                    center_lat: row.get("center_lat"),
+                    center_lon: row.get("center_lon"),
+                    radius_meters: row.get("radius_meters"),
+                    zone_type: serde_json::from_value(serde_json::Value::String(row.get("zone_type")))?,
+                    active_from: row.get("active_from"),
+                    active_until: row.get("active_until"),
+                })
+            })
+            .collect()
+    }
+}
+
+// Stored by variant name, as the API spells it
+fn zone_type_name(zone_type: &ExclusionType) -> Result<String, ZoneError> {
+    match serde_json::to_value(zone_type)? {
+        serde_json::Value::String(name) => Ok(name),
+        other => Err(format!("Unexpected exclusion zone type {}", other).into()),
+    }
+}
@@ -0,0 +1,291 @@
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use axum::body::{to_bytes, Body};
+use axum::extract::Request;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use futures::future::BoxFuture;
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::auth::Principal;
+use crate::config::IdempotencyConfig;
+use crate::error::{ApiError, ErrorCode};
+use crate::tenant;
+
+type IdempotencyError = Box<dyn std::error::Error + Send + Sync>;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+// Longest key accepted; UUIDs and ULIDs fit with room to spare
+const MAX_KEY_LENGTH: usize = 255;
+
+/// What is kept under one key: a marker while the first request runs, then its response.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+enum StoredRequest {
+    InFlight {
+        fingerprint: String,
+    },
+    Completed {
+        fingerprint: String,
+        status: u16,
+        headers: Vec<(String, String)>,
+        // Hex, so the record stays a plain JSON string in Redis
+        body: String,
+    },
+}
+
+impl StoredRequest {
+    fn fingerprint(&self) -> &str {
+        match self {
+            StoredRequest::InFlight { fingerprint } | StoredRequest::Completed { fingerprint, .. } => fingerprint,
+        }
+    }
+}
+
+/// First responses to mutating requests, keyed by the caller and their
+/// `Idempotency-Key`, so a retried request is answered without running twice.
+pub struct IdempotencyStore {
+    config: IdempotencyConfig,
+    redis: ConnectionManager,
+}
+
+impl IdempotencyStore {
+    pub async fn connect(redis_url: &str, config: IdempotencyConfig) -> Result<Self, IdempotencyError> {
+        let client = redis::Client::open(redis_url)?;
+        let redis = ConnectionManager::new(client).await?;
+        Ok(Self { config, redis })
+    }
+
+    // Claims the key for this request, or returns what already holds it
+    async fn claim(&self, key: &str, fingerprint: &str) -> Result<Option<StoredRequest>, IdempotencyError> {
+        let marker = serde_json::to_string(&StoredRequest::InFlight { fingerprint: fingerprint.to_string() })?;
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(&marker)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.config.in_flight_ttl_secs)
+            .query_async(&mut self.redis.clone())
+            .await?;
+        if claimed.is_some() {
+            return Ok(None);
+        }
+
+        let existing: Option<String> = redis::cmd("GET").arg(key).query_async(&mut self.redis.clone()).await?;
+        match existing {
+            Some(existing) => Ok(Some(serde_json::from_str(&existing)?)),
+            // Expired between the two commands; treat it as claimed by someone else and let the client retry
+            None => Ok(Some(StoredRequest::InFlight { fingerprint: fingerprint.to_string() })),
+        }
+    }
+
+    async fn complete(&self, key: &str, stored: &StoredRequest) -> Result<(), IdempotencyError> {
+        let _: () = redis::cmd("SET")
+            .arg(key)
+            .arg(serde_json::to_string(stored)?)
+            .arg("EX")
+            .arg(self.config.ttl_secs)
+            .query_async(&mut self.redis.clone())
+            .await?;
+        Ok(())
+    }
+
+    // Frees the key so a retry runs the request again
+    async fn release(&self, key: &str) -> Result<(), IdempotencyError> {
+        let _: () = redis::cmd("DEL").arg(key).query_async(&mut self.redis.clone()).await?;
+        Ok(())
+    }
+
+    /// Opts a route into idempotency: add it with `post(handler).layer(store.layer())`.
+    pub fn layer(self: &Arc<Self>) -> IdempotencyLayer {
+        IdempotencyLayer { store: self.clone() }
+    }
+}
+
+// The same key on a different endpoint or with a different body is a client bug, not a retry
+fn fingerprint(request: &Request, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(request.method().as_str());
+    hasher.update(b" ");
+    hasher.update(request.uri().path());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+fn idempotency_key(request: &Request) -> Result<Option<String>, ApiError> {
+    let Some(value) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    match value.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => Ok(Some(key.to_string())),
+        _ => Err(ApiError::bad_request(format!(
+            "Idempotency-Key must be 1 to {} visible ASCII characters",
+            MAX_KEY_LENGTH
+        ))),
+    }
+}
+
+// Responses that depend on the moment rather than the request are worth retrying for real
+fn is_replayable(status: StatusCode) -> bool {
+    !(status.is_server_error()
+        || matches!(
+            status,
+            StatusCode::UNAUTHORIZED | StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS
+        ))
+}
+
+fn replay(stored: StoredRequest) -> Response {
+    let StoredRequest::Completed { status, headers, body, .. } = stored else {
+        return ApiError::internal().into_response();
+    };
+    let body = match hex::decode(body) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("Stored idempotent response is corrupt: {}", e);
+            return ApiError::internal().into_response();
+        }
+    };
+
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = StatusCode::from_u16(status).unwrap_or(StatusCode::OK);
+    let response_headers = response.headers_mut();
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            response_headers.append(name, value);
+        }
+    }
+    response_headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+/// Tower layer that replays the stored response when a request repeats an
+/// `Idempotency-Key`. Requests without the header pass straight through.
+/// Must run inside the authentication layer so keys are scoped per principal.
+#[derive(Clone)]
+pub struct IdempotencyLayer {
+    store: Arc<IdempotencyStore>,
+}
+
+impl<S> Layer<S> for IdempotencyLayer {
+    type Service = Idempotent<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Idempotent { inner, store: self.store.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub struct Idempotent<S> {
+    inner: S,
+    store: Arc<IdempotencyStore>,
+}
+
+impl<S> Service<Request> for Idempotent<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let store = self.store.clone();
+        // Keep the instance poll_ready was called on; leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            if !store.config.enabled {
+                return inner.call(request).await;
+            }
+            let key = match idempotency_key(&request) {
+                Ok(Some(key)) => key,
+                Ok(None) => return inner.call(request).await,
+                Err(e) => return Ok(e.into_response()),
+            };
+            let Some(subject) = request.extensions().get::<Principal>().map(|principal| principal.subject.clone()) else {
+                return inner.call(request).await;
+            };
+
+            let (parts, body) = request.into_parts();
+            let body = match to_bytes(body, store.config.max_body_bytes).await {
+                Ok(body) => body,
+                Err(_) => {
+                    return Ok(ApiError::bad_request(format!(
+                        "Request bodies sent with an Idempotency-Key are limited to {} bytes",
+                        store.config.max_body_bytes
+                    )).into_response());
+                }
+            };
+            let request = Request::from_parts(parts, Body::from(body.clone()));
+            let fingerprint = fingerprint(&request, &body);
+            let redis_key = tenant::redis_key(&format!("idempotency:{}:{}", subject, key));
+
+            match store.claim(&redis_key, &fingerprint).await {
+                Ok(None) => {}
+                Ok(Some(existing)) if existing.fingerprint() != fingerprint => {
+                    return Ok(ApiError::new(
+                        ErrorCode::ValidationFailed,
+                        "Idempotency-Key was already used for a different request",
+                    ).into_response());
+                }
+                Ok(Some(StoredRequest::InFlight { .. })) => {
+                    return Ok(ApiError {
+                        retry_after_secs: Some(1),
+                        ..ApiError::conflict("A request with this Idempotency-Key is still being processed")
+                    }.into_response());
+                }
+                Ok(Some(completed)) => return Ok(replay(completed)),
+                // Fail open: an unavailable Redis shouldn't take the API down with it
+                Err(e) => {
+                    warn!("Idempotency check failed for {}: {}", redis_key, e);
+                    return inner.call(request).await;
+                }
+            }
+
+            let response = inner.call(request).await?;
+            let status = response.status();
+            if !is_replayable(status) {
+                if let Err(e) = store.release(&redis_key).await {
+                    warn!("Failed to release idempotency key {}: {}", redis_key, e);
+                }
+                return Ok(response);
+            }
+
+            let (parts, body) = response.into_parts();
+            let body = match to_bytes(body, store.config.max_body_bytes).await {
+                Ok(body) => body,
+                Err(e) => {
+                    // The handler ran, so the key stays claimed until it expires rather than inviting a rerun
+                    warn!("Response for idempotency key {} could not be stored: {}", redis_key, e);
+                    return Ok(ApiError::internal().into_response());
+                }
+            };
+            let stored = StoredRequest::Completed {
+                fingerprint,
+                status: status.as_u16(),
+                headers: parts.headers.iter()
+                    .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                    .collect(),
+                body: hex::encode(&body),
+            };
+            if let Err(e) = store.complete(&redis_key, &stored).await {
+                warn!("Failed to store response for idempotency key {}: {}", redis_key, e);
+            }
+
+            Ok(Response::from_parts(parts, Body::from(body)))
+        })
+    }
+}
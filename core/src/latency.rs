@@ -0,0 +1,195 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use prometheus::{Encoder, GaugeVec, HistogramOpts, HistogramVec, Opts, Registry, TextEncoder};
+
+use crate::cache::{BoundedCache, CacheStats};
+
+type MetricsError = Box<dyn std::error::Error + Send + Sync>;
+
+// End-to-end samples kept per stream for its p99
+const STREAM_SAMPLES: usize = 512;
+// Streams tracked at once; one that sends nothing for STREAM_IDLE is forgotten
+const MAX_STREAMS: usize = 10_000;
+const STREAM_IDLE: Duration = Duration::from_secs(300);
+
+const BUCKETS: [f64; 14] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Where an analytics frame has got to, carried with it from ingest so each
+/// stage it passes through can be timed. A frame's age is counted from when
+/// it was captured, if it says, so producer and network delay count too.
+#[derive(Debug, Clone, Copy)]
+pub struct Stamp {
+    received: Instant,
+    // Seconds between capture and receipt; none if the frame had no timestamp
+    capture_lag: Option<f64>,
+    checkpoint: Instant,
+}
+
+/// A stage of the pipeline from ingest to viewers, the `stage` label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    // Capture to receipt: the producer and the network
+    Capture,
+    // Storing the frame for the history endpoint
+    Store,
+    // Receipt to the hand-off to WebSocket connections
+    Fanout,
+    // Hand-off to a connection writing it to its socket
+    Delivery,
+    // Waiting in the orchestrator's window and queue
+    Queue,
+    // The metacognitive layers reaching a decision
+    Orchestration,
+}
+
+impl Stage {
+    fn as_label(&self) -> &'static str {
+        match self {
+            Stage::Capture => "capture",
+            Stage::Store => "store",
+            Stage::Fanout => "fanout",
+            Stage::Delivery => "delivery",
+            Stage::Queue => "queue",
+            Stage::Orchestration => "orchestration",
+        }
+    }
+}
+
+impl Stamp {
+    /// Stamps a frame received now, captured at `captured_at` seconds since the epoch if known.
+    pub fn received(captured_at: Option<f64>) -> Self {
+        let now = Instant::now();
+        let epoch_now = chrono::Utc::now().timestamp_micros() as f64 / 1_000_000.0;
+        Self {
+            received: now,
+            // A producer clock running ahead reads as no lag rather than negative lag
+            capture_lag: captured_at.filter(|at| at.is_finite()).map(|at| (epoch_now - at).max(0.0)),
+            checkpoint: now,
+        }
+    }
+
+    /// Seconds since the last checkpoint, or receipt, and starts a new one.
+    pub fn lap(&mut self) -> f64 {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.checkpoint).as_secs_f64();
+        self.checkpoint = now;
+        elapsed
+    }
+
+    /// Seconds between capture and receipt, if the frame said when it was captured.
+    pub fn capture_lag(&self) -> Option<f64> {
+        self.capture_lag
+    }
+
+    pub fn since_received(&self) -> f64 {
+        self.received.elapsed().as_secs_f64()
+    }
+
+    /// Seconds since capture, or since receipt for a frame that didn't say when it was captured.
+    pub fn age(&self) -> f64 {
+        self.capture_lag.unwrap_or(0.0) + self.since_received()
+    }
+}
+
+/// Prometheus view of how long analytics frames take from capture to
+/// viewers and to orchestrator decisions: a histogram per stage, the whole
+/// trip per path, and each stream's recent p99 to viewers, since viewers
+/// seeing an event before its bet window closes is the guarantee that matters.
+pub struct PipelineLatency {
+    registry: Registry,
+    stages: HistogramVec,
+    end_to_end: HistogramVec,
+    stream_p99: GaugeVec,
+    samples: BoundedCache<String, StreamSamples>,
+}
+
+#[derive(Clone)]
+struct StreamSamples {
+    stream_id: String,
+    ages: VecDeque<f64>,
+}
+
+impl PipelineLatency {
+    pub fn new() -> Result<Self, MetricsError> {
+        let registry = Registry::new_custom(Some("morphine".to_string()), None)?;
+
+        let stages = HistogramVec::new(
+            HistogramOpts::new("pipeline_stage_seconds", "Time analytics frames spend in each pipeline stage")
+                .buckets(BUCKETS.to_vec()),
+            &["stage"],
+        )?;
+        let end_to_end = HistogramVec::new(
+            HistogramOpts::new(
+                "pipeline_end_to_end_seconds",
+                "Capture, or receipt, to reaching viewers or an orchestrator decision",
+            )
+            .buckets(BUCKETS.to_vec()),
+            &["path"],
+        )?;
+        let stream_p99 = GaugeVec::new(
+            Opts::new(
+                "stream_end_to_end_p99_seconds",
+                "p99 of capture to viewers over each stream's recent frames",
+            ),
+            &["stream_id"],
+        )?;
+
+        registry.register(Box::new(stages.clone()))?;
+        registry.register(Box::new(end_to_end.clone()))?;
+        registry.register(Box::new(stream_p99.clone()))?;
+
+        Ok(Self {
+            registry,
+            stages,
+            end_to_end,
+            stream_p99,
+            samples: BoundedCache::new("latency_streams", MAX_STREAMS, Some(STREAM_IDLE)),
+        })
+    }
+
+    pub fn observe(&self, stage: Stage, seconds: f64) {
+        self.stages.with_label_values(&[stage.as_label()]).observe(seconds);
+    }
+
+    /// Records that a frame of `stream_id` has been handed to viewers.
+    pub fn reached_viewers(&self, stream_id: &str, stamp: &Stamp) {
+        let age = stamp.age();
+        self.end_to_end.with_label_values(&["viewers"]).observe(age);
+        let default = || StreamSamples { stream_id: stream_id.to_string(), ages: VecDeque::with_capacity(STREAM_SAMPLES) };
+        self.samples.upsert(stream_id.to_string(), default, |samples| {
+            if samples.ages.len() == STREAM_SAMPLES {
+                samples.ages.pop_front();
+            }
+            samples.ages.push_back(age);
+        });
+    }
+
+    /// Records that the orchestrator reached a decision on a frame.
+    pub fn reached_decision(&self, stamp: &Stamp) {
+        self.end_to_end.with_label_values(&["decision"]).observe(stamp.age());
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        self.samples.stats()
+    }
+
+    /// Encodes the latency series in the Prometheus text exposition format.
+    pub fn encode(&self) -> Result<String, MetricsError> {
+        // Streams gone quiet drop out of the gauge along with their samples
+        self.stream_p99.reset();
+        for samples in self.samples.values() {
+            self.stream_p99.with_label_values(&[&samples.stream_id]).set(p99(&samples.ages));
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+fn p99(samples: &VecDeque<f64>) -> f64 {
+    let mut sorted: Vec<f64> = samples.iter().copied().collect();
+    sorted.sort_by(f64::total_cmp);
+    let rank = ((sorted.len() as f64 * 0.99).ceil() as usize).saturating_sub(1);
+    sorted.get(rank).copied().unwrap_or(0.0)
+}
@@ -0,0 +1,58 @@
+//! The service's domain and infrastructure, as a library so embedders,
+//! benchmarks and tools can drive the same code the `morphine` binary
+//! serves. The HTTP, WebSocket, gRPC and GraphQL front ends live in the
+//! `morphine-server` crate.
+//!
+//! [`MorphineBuilder`] connects and starts the domain services from a
+//! [`config::Config`]; see `CHANGELOG.md` for what this crate's version
+//! promises about its public API.
+
+pub mod builder;
+pub mod allocator;
+pub mod analytics;
+pub mod analytics_client;
+pub mod api;
+pub mod stream;
+pub mod state;
+pub mod betting;
+pub mod config;
+pub mod orchestrator;
+pub mod geolocation;
+pub mod reasoning;
+pub mod auth;
+pub mod api_version;
+pub mod rate_limit;
+pub mod shutdown;
+pub mod runtime;
+pub mod validation;
+pub mod error;
+pub mod error_reporting;
+pub mod pagination;
+pub mod webhooks;
+pub mod reload;
+pub mod secrets;
+pub mod cors;
+pub mod tls;
+pub mod idempotency;
+pub mod limits;
+pub mod encoding;
+pub mod tenant;
+pub mod cli;
+pub mod loadtest;
+pub mod features;
+pub mod replica;
+pub mod outbox;
+pub mod audit;
+pub mod pool_metrics;
+pub mod cache;
+pub mod latency;
+pub mod events;
+pub mod email;
+pub mod exports;
+pub mod push;
+pub mod timeline;
+pub mod risk;
+pub mod replay;
+pub mod region;
+
+pub use builder::{Morphine, MorphineBuilder};
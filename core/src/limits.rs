@@ -0,0 +1,90 @@
+use std::sync::Arc;
+use std::time::Duration;
+use axum::extract::{Request, State};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+use crate::config::LimitsConfig;
+use crate::error::{ApiError, ErrorCode};
+
+/// Which timeout a request runs under. Backtests, replays and dream cycles
+/// legitimately run long; everything else should answer quickly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deadline {
+    Default,
+    Slow,
+}
+
+impl Deadline {
+    pub fn for_request(method: &Method, path: &str) -> Self {
+        match (method, path) {
+            (&Method::POST, "/api/orchestrator/dreams/trigger")
+            | (&Method::POST, "/api/orchestrator/dreams/backtest")
+            | (&Method::GET, "/api/orchestrator/dreams/export")
+            | (&Method::POST, "/api/orchestrator/admin/replay") => Deadline::Slow,
+            _ => Deadline::Default,
+        }
+    }
+}
+
+// Probes and scrapes must answer even when the server is shedding load
+fn is_exempt(path: &str) -> bool {
+    matches!(path, "/health" | "/healthz" | "/readyz" | "/metrics")
+}
+
+/// Per-request timeouts and a cap on requests handled at once. Body sizes are
+/// capped separately, by `DefaultBodyLimit` on the router and on routes that
+/// accept larger payloads.
+pub struct RequestLimits {
+    config: LimitsConfig,
+    in_flight: Arc<Semaphore>,
+}
+
+impl RequestLimits {
+    pub fn new(config: LimitsConfig) -> Self {
+        Self {
+            in_flight: Arc::new(Semaphore::new(config.max_concurrent_requests)),
+            config,
+        }
+    }
+
+    fn timeout_for(&self, deadline: Deadline) -> Duration {
+        match deadline {
+            Deadline::Default => Duration::from_secs(self.config.request_timeout_secs),
+            Deadline::Slow => Duration::from_secs(self.config.slow_request_timeout_secs),
+        }
+    }
+}
+
+/// Sheds requests over the concurrency cap with `503` and `Retry-After`, and
+/// answers `408` for those that outrun their deadline. Streaming responses
+/// (SSE, WebSocket upgrades) only count until their headers are sent.
+pub async fn enforce(State(limits): State<Arc<RequestLimits>>, request: Request, next: Next) -> Response {
+    if is_exempt(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let Ok(_permit) = limits.in_flight.clone().try_acquire_owned() else {
+        return ApiError {
+            retry_after_secs: Some(1),
+            ..ApiError::unavailable("Server is at capacity; retry shortly")
+        }.into_response();
+    };
+
+    let deadline = Deadline::for_request(request.method(), request.uri().path());
+    let timeout = limits.timeout_for(deadline);
+    let route = format!("{} {}", request.method(), request.uri().path());
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            warn!("{} did not complete within {} seconds", route, timeout.as_secs());
+            ApiError::new(
+                ErrorCode::Timeout,
+                format!("Request did not complete within {} seconds", timeout.as_secs()),
+            ).into_response()
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use futures::{SinkExt, StreamExt};
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{info, warn};
+
+use crate::analytics::{AnalyticsEvent, BoundingBox, DetectedObject, DetectionsEvent, FrameInfo};
+use crate::auth::api_keys::API_KEY_HEADER;
+use crate::betting::Prediction;
+use crate::cli::DEMO_PASSWORD;
+
+// How often each viewer measures a round trip, and how long it waits for the answer
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `morphine loadtest`: simulated viewers, bettors and analytics pushes
+/// against a running instance.
+#[derive(Debug, Clone, Args)]
+pub struct LoadTestOptions {
+    /// Base URL of the instance under test
+    #[arg(long, default_value = "http://localhost:8080")]
+    pub target: String,
+    /// Stream to spread the load over; repeat for several
+    #[arg(long = "stream", value_name = "STREAM_ID", required = true)]
+    pub streams: Vec<String>,
+    /// WebSocket viewers held open for the run
+    #[arg(long, default_value_t = 1000)]
+    pub viewers: usize,
+    /// Viewers connected per second while ramping up
+    #[arg(long, default_value_t = 200)]
+    pub ramp_per_sec: usize,
+    /// Simulated bettors, each placing a bet every `--bet-interval-ms`
+    #[arg(long, default_value_t = 50)]
+    pub bettors: usize,
+    #[arg(long, default_value_t = 1000)]
+    pub bet_interval_ms: u64,
+    #[arg(long, default_value_t = 1.0)]
+    pub stake: f64,
+    /// Analytics frames pushed per second to each stream; needs `--api-key`
+    #[arg(long, default_value_t = 10)]
+    pub analytics_rate: u32,
+    /// Service API key scoped for analytics ingestion; without one no analytics are pushed
+    #[arg(long, env = "MORPHINE_LOADTEST_API_KEY")]
+    pub api_key: Option<String>,
+    /// Account the simulated viewers and bettors sign in as
+    #[arg(long, default_value = "bettor@demo.morphine.local")]
+    pub email: String,
+    #[arg(long, env = "MORPHINE_LOADTEST_PASSWORD", default_value = DEMO_PASSWORD)]
+    pub password: String,
+    /// How long to hold the load once ramped up
+    #[arg(long, default_value_t = 60)]
+    pub duration_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Outcome {
+    Ok,
+    // The server answered 4xx: turned away, not broken
+    Rejected,
+    // 5xx, a timeout or a dropped connection
+    Failed,
+}
+
+#[derive(Default)]
+struct OperationStats {
+    latencies_us: Vec<u64>,
+    ok: u64,
+    rejected: u64,
+    failed: u64,
+}
+
+/// Latencies and outcomes per operation, shared by every simulated client.
+#[derive(Default)]
+struct Recorder {
+    operations: Mutex<BTreeMap<&'static str, OperationStats>>,
+    ws_messages: AtomicU64,
+    // Viewers whose connection ended before the run did
+    ws_dropped: AtomicU64,
+}
+
+impl Recorder {
+    fn record(&self, operation: &'static str, started: Instant, outcome: Outcome) {
+        let elapsed = started.elapsed().as_micros() as u64;
+        let mut operations = self.operations.lock();
+        let stats = operations.entry(operation).or_default();
+        match outcome {
+            Outcome::Ok => {
+                stats.ok += 1;
+                stats.latencies_us.push(elapsed);
+            }
+            Outcome::Rejected => {
+                stats.rejected += 1;
+                stats.latencies_us.push(elapsed);
+            }
+            Outcome::Failed => stats.failed += 1,
+        }
+    }
+
+    fn report(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        println!();
+        println!(
+            "{:<18} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+            "operation", "count", "rejected", "failed", "per sec", "p50 ms", "p90 ms", "p99 ms", "max ms",
+        );
+        for (operation, stats) in self.operations.lock().iter_mut() {
+            stats.latencies_us.sort_unstable();
+            let total = stats.ok + stats.rejected + stats.failed;
+            println!(
+                "{:<18} {:>9} {:>9} {:>9} {:>9.1} {:>9.2} {:>9.2} {:>9.2} {:>9.2}",
+                operation,
+                total,
+                stats.rejected,
+                stats.failed,
+                total as f64 / secs,
+                percentile_ms(&stats.latencies_us, 0.50),
+                percentile_ms(&stats.latencies_us, 0.90),
+                percentile_ms(&stats.latencies_us, 0.99),
+                percentile_ms(&stats.latencies_us, 1.0),
+            );
+        }
+        println!();
+        println!(
+            "{} WebSocket broadcasts received ({:.1} per sec)",
+            self.ws_messages.load(Ordering::Relaxed),
+            self.ws_messages.load(Ordering::Relaxed) as f64 / secs,
+        );
+        println!("{} WebSocket connections dropped mid-run", self.ws_dropped.load(Ordering::Relaxed));
+    }
+}
+
+// Nearest-rank percentile of sorted latencies
+fn percentile_ms(sorted_us: &[u64], quantile: f64) -> f64 {
+    if sorted_us.is_empty() {
+        return 0.0;
+    }
+    let rank = ((quantile * sorted_us.len() as f64).ceil() as usize).clamp(1, sorted_us.len());
+    sorted_us[rank - 1] as f64 / 1000.0
+}
+
+fn http_outcome(status: reqwest::StatusCode) -> Outcome {
+    if status.is_success() {
+        Outcome::Ok
+    } else if status.is_client_error() {
+        Outcome::Rejected
+    } else {
+        Outcome::Failed
+    }
+}
+
+struct Session {
+    user_id: String,
+    token: String,
+}
+
+async fn sign_in(client: &reqwest::Client, options: &LoadTestOptions) -> Result<Session> {
+    let response = client.post(format!("{}/api/auth/login", options.target))
+        .json(&json!({ "email": options.email, "password": options.password }))
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach {}", options.target))?;
+    if !response.status().is_success() {
+        bail!("Signing in as {} failed with {}", options.email, response.status());
+    }
+
+    let body: Value = response.json().await?;
+    let data = &body["data"];
+    match (data["user"]["user_id"].as_str(), data["token"].as_str()) {
+        (Some(user_id), Some(token)) => Ok(Session { user_id: user_id.to_string(), token: token.to_string() }),
+        _ => bail!("Sign-in response has no user or token"),
+    }
+}
+
+/// Ramps up to the configured load, holds it for the duration, then prints
+/// latency percentiles per operation. Nothing is cleaned up afterwards: the
+/// bets placed are real, so point it at a staging instance.
+pub async fn run(options: LoadTestOptions) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .pool_max_idle_per_host(options.bettors.max(16))
+        .build()?;
+    let session = Arc::new(sign_in(&client, &options).await?);
+    let recorder = Arc::new(Recorder::default());
+    let options = Arc::new(options);
+
+    let ramp_secs = options.viewers as f64 / options.ramp_per_sec.max(1) as f64;
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs_f64(ramp_secs) + Duration::from_secs(options.duration_secs);
+    info!(
+        "Load testing {} with {} viewers, {} bettors and {} analytics frames/s per stream for {}s after a {:.0}s ramp-up",
+        options.target,
+        options.viewers,
+        options.bettors,
+        if options.api_key.is_some() { options.analytics_rate } else { 0 },
+        options.duration_secs,
+        ramp_secs,
+    );
+    if options.api_key.is_none() {
+        warn!("No --api-key given; analytics pushes are skipped");
+    }
+
+    let mut tasks = Vec::new();
+    for index in 0..options.bettors {
+        tasks.push(tokio::spawn(bettor(index, client.clone(), options.clone(), session.clone(), recorder.clone(), deadline)));
+    }
+    if let Some(api_key) = &options.api_key {
+        for stream_id in &options.streams {
+            tasks.push(tokio::spawn(analytics_pusher(
+                stream_id.clone(),
+                api_key.clone(),
+                client.clone(),
+                options.clone(),
+                recorder.clone(),
+                deadline,
+            )));
+        }
+    }
+
+    // Viewers join gradually, so the ramp-up doesn't measure a connection storm
+    let mut ramp = tokio::time::interval(Duration::from_secs_f64(1.0 / options.ramp_per_sec.max(1) as f64));
+    for index in 0..options.viewers {
+        ramp.tick().await;
+        tasks.push(tokio::spawn(viewer(index, options.clone(), session.clone(), recorder.clone(), deadline)));
+    }
+
+    futures::future::join_all(tasks).await;
+    recorder.report(started.elapsed());
+    Ok(())
+}
+
+async fn bettor(
+    index: usize,
+    client: reqwest::Client,
+    options: Arc<LoadTestOptions>,
+    session: Arc<Session>,
+    recorder: Arc<Recorder>,
+    deadline: Instant,
+) {
+    let stream_id = &options.streams[index % options.streams.len()];
+    let mut interval = tokio::time::interval(Duration::from_millis(options.bet_interval_ms));
+    let mut placed = 0usize;
+
+    while Instant::now() < deadline {
+        interval.tick().await;
+        let prediction = Prediction::Binary { will_occur: (index + placed) % 2 == 0 };
+        placed += 1;
+
+        let started = Instant::now();
+        let outcome = match client.post(format!("{}/api/betting/place", options.target))
+            .bearer_auth(&session.token)
+            .json(&json!({
+                "user_id": session.user_id,
+                "stream_id": stream_id,
+                "bet_type": "binary",
+                "stake_amount": options.stake,
+                "prediction": prediction,
+                "time_window_seconds": 60,
+            }))
+            .send()
+            .await
+        {
+            Ok(response) => http_outcome(response.status()),
+            Err(_) => Outcome::Failed,
+        };
+        recorder.record("place_bet", started, outcome);
+    }
+}
+
+async fn analytics_pusher(
+    stream_id: String,
+    api_key: String,
+    client: reqwest::Client,
+    options: Arc<LoadTestOptions>,
+    recorder: Arc<Recorder>,
+    deadline: Instant,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / options.analytics_rate.max(1) as f64));
+    let mut frame_number = 0u64;
+
+    while Instant::now() < deadline {
+        interval.tick().await;
+        frame_number += 1;
+        let frame = AnalyticsEvent::Detections(DetectionsEvent {
+            frame: FrameInfo {
+                timestamp: Some(chrono::Utc::now().timestamp_millis() as f64 / 1000.0),
+                confidence: Some(0.9),
+                priority: None,
+            },
+            detected_objects: vec![DetectedObject {
+                object_type: "person".to_string(),
+                confidence: 0.95,
+                bounding_box: BoundingBox { x: 100.0, y: 120.0, width: 48.0, height: 96.0 },
+                speed: Some(30.0 + (frame_number % 20) as f64),
+            }],
+        });
+
+        // Pushes are fire-and-forget for the vision service, so they run concurrently
+        let client = client.clone();
+        let recorder = recorder.clone();
+        let url = format!("{}/api/analytics/{}/notify", options.target, stream_id);
+        let api_key = api_key.clone();
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let outcome = match client.post(url).header(API_KEY_HEADER, api_key).json(&frame).send().await {
+                Ok(response) => http_outcome(response.status()),
+                Err(_) => Outcome::Failed,
+            };
+            recorder.record("analytics_push", started, outcome);
+        });
+    }
+}
+
+async fn viewer(
+    index: usize,
+    options: Arc<LoadTestOptions>,
+    session: Arc<Session>,
+    recorder: Arc<Recorder>,
+    deadline: Instant,
+) {
+    let stream_id = &options.streams[index % options.streams.len()];
+    let base = options.target.replacen("http", "ws", 1);
+    let url = format!("{}/ws/{}?token={}", base, stream_id, session.token);
+
+    let started = Instant::now();
+    let mut socket = match tokio_tungstenite::connect_async(url.as_str()).await {
+        Ok((socket, _)) => {
+            recorder.record("ws_connect", started, Outcome::Ok);
+            socket
+        }
+        Err(tokio_tungstenite::tungstenite::Error::Http(response)) if response.status().is_client_error() => {
+            recorder.record("ws_connect", started, Outcome::Rejected);
+            return;
+        }
+        Err(_) => {
+            recorder.record("ws_connect", started, Outcome::Failed);
+            return;
+        }
+    };
+
+    let join = json!({ "JoinStream": { "stream_id": stream_id, "user_id": session.user_id } });
+    if socket.send(Message::Text(join.to_string())).await.is_err() {
+        recorder.ws_dropped.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+
+    // Spread the pings out so a thousand viewers don't measure the same instant
+    let mut next_ping = Instant::now() + PING_INTERVAL.mul_f64((index % 100) as f64 / 100.0);
+    let mut ping_sent: Option<Instant> = None;
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        if ping_sent.is_none() && now >= next_ping {
+            if socket.send(Message::Text("\"Ping\"".to_string())).await.is_err() {
+                recorder.ws_dropped.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            ping_sent = Some(now);
+        }
+
+        let wake = match ping_sent {
+            Some(sent) => sent + PING_TIMEOUT,
+            None => next_ping,
+        }
+        .min(deadline);
+        match tokio::time::timeout(wake.saturating_duration_since(now), socket.next()).await {
+            Ok(Some(Ok(Message::Text(text)))) => {
+                if text == "\"Pong\"" {
+                    if let Some(sent) = ping_sent.take() {
+                        recorder.record("ws_ping", sent, Outcome::Ok);
+                        next_ping = Instant::now() + PING_INTERVAL;
+                    }
+                } else {
+                    recorder.ws_messages.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            Ok(Some(Ok(Message::Close(_)))) | Ok(Some(Err(_))) | Ok(None) => {
+                // Dropped before the run was over
+                recorder.ws_dropped.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            Ok(Some(Ok(_))) => {}
+            Err(_) => {
+                if let Some(sent) = ping_sent {
+                    if sent.elapsed() >= PING_TIMEOUT {
+                        recorder.record("ws_ping", sent, Outcome::Failed);
+                        ping_sent = None;
+                        next_ping = Instant::now() + PING_INTERVAL;
+                    }
+                }
+            }
+        }
+    }
+
+    let _ = socket.close(None).await;
+}
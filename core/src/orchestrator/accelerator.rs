@@ -0,0 +1,177 @@
+use std::sync::Arc;
+use std::time::Duration;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+/// A GPU (or other accelerator) the orchestrator may schedule AI systems onto.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceleratorDevice {
+    pub device_id: String,
+    // Concurrent inference slots the device can serve
+    pub slots: u32,
+    pub vram_mb: u64,
+}
+
+/// What one call to an AI system needs from a single device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcceleratorRequirement {
+    #[serde(default = "default_slots")]
+    pub slots: u32,
+    pub vram_mb: u64,
+}
+
+fn default_slots() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceUtilization {
+    pub device_id: String,
+    pub slots_used: u32,
+    pub slots_total: u32,
+    pub vram_used_mb: u64,
+    pub vram_total_mb: u64,
+    pub utilization: f64,
+}
+
+struct DeviceState {
+    device: AcceleratorDevice,
+    slots_used: u32,
+    vram_used_mb: u64,
+}
+
+impl DeviceState {
+    fn fits(&self, requirement: &AcceleratorRequirement) -> bool {
+        self.slots_used + requirement.slots <= self.device.slots
+            && self.vram_used_mb + requirement.vram_mb <= self.device.vram_mb
+    }
+
+    // Larger of slot and VRAM pressure
+    fn utilization(&self) -> f64 {
+        let slots = if self.device.slots > 0 {
+            self.slots_used as f64 / self.device.slots as f64
+        } else {
+            0.0
+        };
+        let vram = if self.device.vram_mb > 0 {
+            self.vram_used_mb as f64 / self.device.vram_mb as f64
+        } else {
+            0.0
+        };
+        slots.max(vram)
+    }
+}
+
+/// Tracks slot and VRAM reservations across the configured devices.
+pub struct AcceleratorPool {
+    devices: Mutex<Vec<DeviceState>>,
+    released: Notify,
+}
+
+/// A reservation on one device, released when dropped.
+pub struct AcceleratorLease {
+    pool: Arc<AcceleratorPool>,
+    device_index: usize,
+    requirement: AcceleratorRequirement,
+}
+
+impl AcceleratorLease {
+    pub fn device_id(&self) -> String {
+        self.pool.devices.lock()[self.device_index].device.device_id.clone()
+    }
+}
+
+impl Drop for AcceleratorLease {
+    fn drop(&mut self) {
+        {
+            let mut devices = self.pool.devices.lock();
+            let state = &mut devices[self.device_index];
+            state.slots_used = state.slots_used.saturating_sub(self.requirement.slots);
+            state.vram_used_mb = state.vram_used_mb.saturating_sub(self.requirement.vram_mb);
+        }
+        self.pool.released.notify_waiters();
+    }
+}
+
+impl AcceleratorPool {
+    pub fn new(devices: Vec<AcceleratorDevice>) -> Self {
+        Self {
+            devices: Mutex::new(devices.into_iter()
+                .map(|device| DeviceState { device, slots_used: 0, vram_used_mb: 0 })
+                .collect()),
+            released: Notify::new(),
+        }
+    }
+
+    pub fn has_devices(&self) -> bool {
+        !self.devices.lock().is_empty()
+    }
+
+    /// Reserves capacity on the least-utilized device that fits, if any.
+    pub fn try_reserve(self: &Arc<Self>, requirement: AcceleratorRequirement) -> Option<AcceleratorLease> {
+        let mut devices = self.devices.lock();
+
+        let device_index = devices.iter()
+            .enumerate()
+            .filter(|(_, state)| state.fits(&requirement))
+            .min_by(|(_, a), (_, b)| a.utilization().partial_cmp(&b.utilization()).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(index, _)| index)?;
+
+        let state = &mut devices[device_index];
+        state.slots_used += requirement.slots;
+        state.vram_used_mb += requirement.vram_mb;
+
+        Some(AcceleratorLease {
+            pool: self.clone(),
+            device_index,
+            requirement,
+        })
+    }
+
+    /// Waits up to `timeout` for capacity to free up. Returns None on timeout or
+    /// when no device could ever satisfy the requirement.
+    pub async fn reserve(self: &Arc<Self>, requirement: AcceleratorRequirement, timeout: Duration) -> Option<AcceleratorLease> {
+        let satisfiable = self.devices.lock().iter().any(|state| {
+            requirement.slots <= state.device.slots && requirement.vram_mb <= state.device.vram_mb
+        });
+        if !satisfiable {
+            return None;
+        }
+
+        tokio::time::timeout(timeout, async {
+            loop {
+                // Register interest before checking so a release in between isn't missed
+                let released = self.released.notified();
+                tokio::pin!(released);
+                released.as_mut().enable();
+                if let Some(lease) = self.try_reserve(requirement) {
+                    return lease;
+                }
+                released.await;
+            }
+        }).await.ok()
+    }
+
+    pub fn utilization(&self) -> Vec<DeviceUtilization> {
+        self.devices.lock().iter()
+            .map(|state| DeviceUtilization {
+                device_id: state.device.device_id.clone(),
+                slots_used: state.slots_used,
+                slots_total: state.device.slots,
+                vram_used_mb: state.vram_used_mb,
+                vram_total_mb: state.device.vram_mb,
+                utilization: state.utilization(),
+            })
+            .collect()
+    }
+
+    // Mean utilization across devices; 0 when no accelerators are configured
+    pub fn overall_utilization(&self) -> f64 {
+        let devices = self.devices.lock();
+        if devices.is_empty() {
+            return 0.0;
+        }
+        devices.iter().map(|state| state.utilization()).sum::<f64>() / devices.len() as f64
+    }
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+use super::accelerator::AcceleratorRequirement;
+use super::alerts::Alert;
+use super::backpressure::StreamLagMetrics;
+use super::circuit_breaker::BreakerSnapshot;
+use super::health::{SystemHealthState, SystemLifecycleEvent};
+use crate::risk::RiskAlert;
+
+/// Operator-facing events published on the orchestrator's admin channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum AdminEvent {
+    SystemLifecycle(SystemLifecycleEvent),
+    Alert(Alert),
+    // An intuition pattern model version went live for a stream category
+    PatternModelActivated {
+        stream_category: String,
+        model_id: String,
+        version: i32,
+        rolled_back_from: Option<i32>,
+        timestamp: f64,
+    },
+    // Anomalous betting raised by the risk monitor
+    RiskAlert(RiskAlert),
+}
+
+/// Runtime view of one registered AI system.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AISystemSummary {
+    pub system_id: String,
+    pub capabilities: Vec<String>,
+    // As registered or tuned; effective_weight is the share actually applied
+    pub weight: f64,
+    pub effective_weight: f64,
+    pub health: Option<SystemHealthState>,
+    pub circuit_breaker: Option<BreakerSnapshot>,
+    pub accelerator: Option<AcceleratorRequirement>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamQueueSummary {
+    pub stream_id: String,
+    pub queued: usize,
+    pub paused: bool,
+    pub lag: Option<StreamLagMetrics>,
+}
@@ -0,0 +1,184 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::{DecisionType, MetacognitiveDecision};
+use super::backpressure::now_seconds;
+use crate::config::AlertConfig;
+
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub alert_id: String,
+    // Stable across repeats; also used as the PagerDuty dedup key
+    pub dedup_key: String,
+    pub stream_id: String,
+    pub decision_id: String,
+    pub decision_type: DecisionType,
+    pub severity: AlertSeverity,
+    pub summary: String,
+    pub confidence: f64,
+    pub raised_at: f64,
+}
+
+struct DedupEntry {
+    last_sent_at: f64,
+    suppressed: u64,
+}
+
+/// Turns alert-worthy decisions into deduplicated alerts and delivers them to
+/// the configured webhooks and PagerDuty.
+pub struct AlertRouter {
+    config: AlertConfig,
+    http_client: reqwest::Client,
+    dedup: Mutex<HashMap<String, DedupEntry>>,
+    history: Mutex<VecDeque<Alert>>,
+}
+
+impl AlertRouter {
+    pub fn new(config: AlertConfig) -> Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.delivery_timeout_ms))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            config,
+            http_client,
+            dedup: Mutex::new(HashMap::new()),
+            history: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn severity_for(&self, decision: &MetacognitiveDecision) -> Option<AlertSeverity> {
+        match decision.decision_type {
+            DecisionType::AlertGeneration if decision.confidence >= self.config.critical_confidence => {
+                Some(AlertSeverity::Critical)
+            }
+            DecisionType::AlertGeneration => Some(AlertSeverity::Warning),
+            // The less sure we are a transaction is valid, the more urgent
+            DecisionType::TransactionValidation
+                if decision.confidence < self.config.transaction_confidence_threshold / 2.0 =>
+            {
+                Some(AlertSeverity::Critical)
+            }
+            DecisionType::TransactionValidation
+                if decision.confidence < self.config.transaction_confidence_threshold =>
+            {
+                Some(AlertSeverity::Warning)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the alert to deliver for a decision, or None if it isn't
+    /// alert-worthy or repeats one sent within the dedup window.
+    pub fn evaluate(&self, decision: &MetacognitiveDecision) -> Option<Alert> {
+        let severity = self.severity_for(decision)?;
+        let now = now_seconds();
+        let dedup_key = format!("{}:{:?}:{:?}", decision.stream_id, decision.decision_type, severity);
+
+        // Repeats inside the window are only counted; the next alert reports them
+        let suppressed = {
+            let mut dedup = self.dedup.lock();
+            let window = self.config.dedup_window_secs as f64;
+            dedup.retain(|_, entry| now - entry.last_sent_at < window * 2.0);
+
+            let entry = dedup.entry(dedup_key.clone())
+                .or_insert(DedupEntry { last_sent_at: f64::NEG_INFINITY, suppressed: 0 });
+            if now - entry.last_sent_at < window {
+                entry.suppressed += 1;
+                return None;
+            }
+            entry.last_sent_at = now;
+            std::mem::take(&mut entry.suppressed)
+        };
+
+        let summary = match decision.decision_type {
+            DecisionType::TransactionValidation => format!(
+                "Low-confidence transaction validation on stream {} ({:.2})",
+                decision.stream_id, decision.confidence
+            ),
+            _ => format!(
+                "Orchestrator raised an alert on stream {} ({:.2} confidence)",
+                decision.stream_id, decision.confidence
+            ),
+        };
+
+        let alert = Alert {
+            alert_id: Uuid::new_v4().to_string(),
+            dedup_key,
+            stream_id: decision.stream_id.clone(),
+            decision_id: decision.decision_id.clone(),
+            decision_type: decision.decision_type.clone(),
+            severity,
+            summary: if suppressed > 0 { format!("{} ({} repeats suppressed)", summary, suppressed) } else { summary },
+            confidence: decision.confidence,
+            raised_at: now,
+        };
+
+        let mut history = self.history.lock();
+        history.push_back(alert.clone());
+        while history.len() > self.config.history_size {
+            history.pop_front();
+        }
+
+        Some(alert)
+    }
+
+    /// Sends the alert to every webhook, and to PagerDuty when critical.
+    /// Delivery failures are logged; alerts are advisory.
+    pub async fn deliver(&self, alert: &Alert) {
+        let webhooks = self.config.webhook_urls.iter().map(|url| async move {
+            if let Err(e) = self.http_client.post(url).json(alert).send().await.and_then(|r| r.error_for_status()) {
+                warn!("Failed to deliver alert {} to {}: {}", alert.alert_id, url, e);
+            }
+        });
+        futures::future::join_all(webhooks).await;
+
+        if alert.severity == AlertSeverity::Critical {
+            if let Some(routing_key) = &self.config.pagerduty_routing_key {
+                self.page(routing_key, alert).await;
+            }
+        }
+    }
+
+    async fn page(&self, routing_key: &str, alert: &Alert) {
+        let event = serde_json::json!({
+            "routing_key": routing_key,
+            "event_action": "trigger",
+            "dedup_key": alert.dedup_key,
+            "payload": {
+                "summary": alert.summary,
+                "source": format!("morphine-orchestrator/{}", alert.stream_id),
+                "severity": "critical",
+                "custom_details": alert,
+            },
+        });
+
+        let result = self.http_client.post(PAGERDUTY_EVENTS_URL)
+            .json(&event)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        if let Err(e) = result {
+            warn!("Failed to page for alert {}: {}", alert.alert_id, e);
+        }
+    }
+
+    // Most recent first
+    pub fn recent(&self, limit: usize) -> Vec<Alert> {
+        self.history.lock().iter().rev().take(limit).cloned().collect()
+    }
+}
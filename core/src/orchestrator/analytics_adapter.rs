@@ -0,0 +1,243 @@
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::warn;
+
+use super::{AISystem, StreamingContext};
+use super::accelerator::AcceleratorRequirement;
+use crate::config::AnalyticsAdapterConfig;
+
+type AdapterError = Box<dyn std::error::Error + Send + Sync>;
+
+// Request body expected by the analytics service's /analytics/process_frame
+#[derive(Debug, Clone, Serialize)]
+struct FrameProcessRequest {
+    stream_id: String,
+    frame_data: String,
+    timestamp: f64,
+    frame_idx: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnalyticsResponse {
+    success: bool,
+    analytics: serde_json::Value,
+    processing_time: f64,
+    error: Option<String>,
+}
+
+/// Sidecar adapter for the Python analytics service (Vibrio + Moriarty).
+///
+/// Contexts carrying a base64 `frame_data` field are sent for full frame processing;
+/// otherwise the latest stored analytics for the stream are fetched.
+pub struct AnalyticsServiceAdapter {
+    base_url: String,
+    client: reqwest::Client,
+    config: AnalyticsAdapterConfig,
+    last_processing_time: RwLock<f64>,
+}
+
+impl AnalyticsServiceAdapter {
+    pub fn new(base_url: &str, config: AnalyticsAdapterConfig) -> Result<Self, AdapterError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .pool_max_idle_per_host(config.pool_size)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .build()?;
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client,
+            config,
+            last_processing_time: RwLock::new(0.0),
+        })
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.config.weight
+    }
+
+    async fn call_service(&self, context: &StreamingContext) -> Result<serde_json::Value, AdapterError> {
+        match context.partial_data.get("frame_data").and_then(|v| v.as_str()) {
+            Some(frame_data) => {
+                let request = FrameProcessRequest {
+                    stream_id: context.stream_id.clone(),
+                    frame_data: frame_data.to_string(),
+                    timestamp: context.timestamp,
+                    frame_idx: context.partial_data.get("frame_idx")
+                        .and_then(|v| v.as_i64())
+                        .unwrap_or(0),
+                };
+
+                let response: AnalyticsResponse = self.client
+                    .post(format!("{}/analytics/process_frame", self.base_url))
+                    .json(&request)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+
+                if !response.success {
+                    return Err(response.error
+                        .unwrap_or_else(|| "Analytics service reported failure".to_string())
+                        .into());
+                }
+
+                let mut analytics = response.analytics;
+                if let Some(obj) = analytics.as_object_mut() {
+                    obj.insert("processing_time".to_string(), json!(response.processing_time));
+                }
+                Ok(analytics)
+            }
+            None => {
+                let analytics = self.client
+                    .get(format!("{}/analytics/{}/latest", self.base_url, context.stream_id))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                Ok(analytics)
+            }
+        }
+    }
+
+    async fn call_with_retries(&self, context: &StreamingContext) -> Result<serde_json::Value, AdapterError> {
+        let mut attempt = 0;
+        let mut backoff = Duration::from_millis(self.config.retry_backoff_ms);
+
+        loop {
+            match self.call_service(context).await {
+                Ok(analytics) => return Ok(analytics),
+                Err(e) if attempt < self.config.max_retries && is_retryable(e.as_ref()) => {
+                    attempt += 1;
+                    warn!(
+                        "Analytics service call failed for stream {} (attempt {}): {}",
+                        context.stream_id, attempt, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // Maps the service's CV output into orchestrator evidence
+    fn to_evidence(analytics: serde_json::Value) -> serde_json::Value {
+        let vibrio = analytics.get("vibrio").filter(|v| !v.is_null());
+        let moriarty = analytics.get("moriarty").filter(|v| !v.is_null());
+
+        let detections = vibrio
+            .and_then(|v| v.get("detections"))
+            .and_then(|d| d.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let tracks = vibrio
+            .and_then(|v| v.get("tracks"))
+            .and_then(|t| t.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        let motion_energy = vibrio
+            .and_then(|v| v.get("motion_energy"))
+            .and_then(|m| m.get("motion_energy"))
+            .and_then(|m| m.as_f64());
+        let max_speed = tracks.iter()
+            .filter_map(|t| t.get("speed").and_then(|s| s.as_f64()))
+            .fold(0.0_f64, f64::max);
+
+        let pose_detected = moriarty
+            .and_then(|m| m.get("pose_detected"))
+            .and_then(|p| p.as_bool())
+            .unwrap_or(false);
+        let pose_quality = moriarty
+            .and_then(|m| m.get("pose_quality_score"))
+            .and_then(|p| p.as_f64());
+
+        // Confidence blends mean detection confidence with pose quality when present
+        let detection_confidences: Vec<f64> = detections.iter()
+            .filter_map(|d| d.get("confidence").and_then(|c| c.as_f64()))
+            .collect();
+        let mut signals = Vec::new();
+        if !detection_confidences.is_empty() {
+            signals.push(detection_confidences.iter().sum::<f64>() / detection_confidences.len() as f64);
+        }
+        if let Some(quality) = pose_quality {
+            signals.push(quality);
+        }
+        let confidence = if signals.is_empty() {
+            0.5
+        } else {
+            signals.iter().sum::<f64>() / signals.len() as f64
+        };
+
+        json!({
+            "confidence": confidence.clamp(0.0, 1.0),
+            "detection_count": detections.len(),
+            "track_count": tracks.len(),
+            "motion_energy": motion_energy,
+            "max_speed": max_speed,
+            "pose_detected": pose_detected,
+            "pose_quality": pose_quality,
+            "raw": analytics,
+        })
+    }
+}
+
+fn is_retryable(error: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+    match error.downcast_ref::<reqwest::Error>() {
+        Some(e) => e.is_timeout()
+            || e.is_connect()
+            || e.status().map(|s| s.is_server_error()).unwrap_or(false),
+        None => false,
+    }
+}
+
+#[async_trait::async_trait]
+impl AISystem for AnalyticsServiceAdapter {
+    async fn process(&self, context: &StreamingContext) -> Result<serde_json::Value, AdapterError> {
+        let started = Instant::now();
+        let result = self.call_with_retries(context).await.map(Self::to_evidence);
+        *self.last_processing_time.write() = started.elapsed().as_secs_f64() * 1000.0;
+        result
+    }
+
+    async fn health_check(&self) -> Result<(), AdapterError> {
+        self.client
+            .get(format!("{}/health", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn get_confidence(&self, input: &serde_json::Value) -> f64 {
+        input.get("confidence")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.5)
+    }
+
+    fn get_system_id(&self) -> String {
+        "analytics_service".to_string()
+    }
+
+    fn get_processing_time(&self) -> f64 {
+        *self.last_processing_time.read()
+    }
+
+    fn get_capabilities(&self) -> Vec<String> {
+        vec![
+            "object_detection".to_string(),
+            "tracking".to_string(),
+            "motion_analysis".to_string(),
+            "pose_estimation".to_string(),
+        ]
+    }
+
+    fn accelerator_requirement(&self) -> Option<AcceleratorRequirement> {
+        self.config.accelerator_requirement()
+    }
+}
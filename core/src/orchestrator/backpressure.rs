@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use super::StreamingContext;
+
+/// What to do with an incoming context when a stream's input channel is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    // Reject the incoming context
+    Drop,
+    // Fold the incoming context into a per-stream overflow slot, sent once capacity frees up
+    Merge,
+    // Wait up to the configured timeout for capacity, then drop
+    Block,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StreamLagMetrics {
+    pub ingested: u64,
+    pub processed: u64,
+    pub dropped_overflow: u64,
+    pub merged_overflow: u64,
+    pub dropped_stale: u64,
+    // Frames folded into windowed contexts rather than processed one by one
+    #[serde(default)]
+    pub batched_frames: u64,
+    // Seconds between a context's timestamp and when processing started
+    pub last_lag_seconds: f64,
+    pub max_lag_seconds: f64,
+    pub channel_depth: usize,
+    pub channel_capacity: usize,
+}
+
+impl StreamLagMetrics {
+    pub fn record_lag(&mut self, lag_seconds: f64) {
+        self.processed += 1;
+        self.last_lag_seconds = lag_seconds;
+        if lag_seconds > self.max_lag_seconds {
+            self.max_lag_seconds = lag_seconds;
+        }
+    }
+}
+
+/// Merges a newer context into an older pending one; newer values win and the
+/// merged context keeps the higher of the two priorities.
+pub fn merge_contexts(pending: &mut StreamingContext, incoming: StreamingContext) {
+    let merged_count = pending.partial_data.get("merged_count")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1);
+
+    for (key, value) in incoming.partial_data {
+        pending.partial_data.insert(key, value);
+    }
+    pending.partial_data.insert("merged_count".to_string(), serde_json::json!(merged_count + 1));
+
+    pending.timestamp = incoming.timestamp;
+    pending.confidence_level = incoming.confidence_level;
+    pending.priority = pending.priority.max(incoming.priority);
+}
+
+pub fn now_seconds() -> f64 {
+    chrono::Utc::now().timestamp_millis() as f64 / 1000.0
+}
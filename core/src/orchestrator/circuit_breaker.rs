@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::config::CircuitBreakerConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakerSnapshot {
+    pub state: BreakerState,
+    pub failure_rate: f64,
+    pub window_size: usize,
+    pub total_calls: u64,
+    pub total_failures: u64,
+    pub total_timeouts: u64,
+    pub total_skipped: u64,
+    pub open_for_ms: Option<u64>,
+}
+
+struct BreakerInner {
+    state: BreakerState,
+    outcomes: VecDeque<bool>, // true = success
+    opened_at: Option<Instant>,
+    half_open_in_flight: bool,
+    total_calls: u64,
+    total_failures: u64,
+    total_timeouts: u64,
+    total_skipped: u64,
+}
+
+/// Failure-rate circuit breaker guarding calls to a single AI system.
+///
+/// The breaker opens once the failure rate over the rolling window crosses the
+/// configured threshold, rejects calls for the cooldown period, then lets a single
+/// trial call through (half-open) to decide whether to close again.
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    inner: Mutex<BreakerInner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            inner: Mutex::new(BreakerInner {
+                state: BreakerState::Closed,
+                outcomes: VecDeque::with_capacity(config.window_size),
+                opened_at: None,
+                half_open_in_flight: false,
+                total_calls: 0,
+                total_failures: 0,
+                total_timeouts: 0,
+                total_skipped: 0,
+            }),
+            config,
+        }
+    }
+
+    pub fn call_timeout(&self) -> Duration {
+        Duration::from_millis(self.config.call_timeout_ms)
+    }
+
+    /// Returns false if the call should be skipped.
+    pub fn try_acquire(&self) -> bool {
+        let mut inner = self.inner.lock();
+
+        match inner.state {
+            BreakerState::Closed => true,
+            BreakerState::Open => {
+                let cooldown = Duration::from_millis(self.config.open_duration_ms);
+                let elapsed = inner.opened_at.map(|t| t.elapsed()).unwrap_or(cooldown);
+                if elapsed >= cooldown {
+                    inner.state = BreakerState::HalfOpen;
+                    inner.half_open_in_flight = true;
+                    true
+                } else {
+                    inner.total_skipped += 1;
+                    false
+                }
+            }
+            BreakerState::HalfOpen => {
+                if inner.half_open_in_flight {
+                    inner.total_skipped += 1;
+                    false
+                } else {
+                    inner.half_open_in_flight = true;
+                    true
+                }
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock();
+        inner.total_calls += 1;
+
+        if inner.state == BreakerState::HalfOpen {
+            inner.state = BreakerState::Closed;
+            inner.half_open_in_flight = false;
+            inner.opened_at = None;
+            inner.outcomes.clear();
+        }
+
+        self.push_outcome(&mut inner, true);
+    }
+
+    pub fn record_failure(&self, timed_out: bool) {
+        let mut inner = self.inner.lock();
+        inner.total_calls += 1;
+        inner.total_failures += 1;
+        if timed_out {
+            inner.total_timeouts += 1;
+        }
+
+        if inner.state == BreakerState::HalfOpen {
+            inner.state = BreakerState::Open;
+            inner.half_open_in_flight = false;
+            inner.opened_at = Some(Instant::now());
+            return;
+        }
+
+        self.push_outcome(&mut inner, false);
+
+        if inner.outcomes.len() >= self.config.min_calls
+            && Self::failure_rate(&inner) >= self.config.failure_rate_threshold
+        {
+            inner.state = BreakerState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.inner.lock().state
+    }
+
+    pub fn snapshot(&self) -> BreakerSnapshot {
+        let inner = self.inner.lock();
+        BreakerSnapshot {
+            state: inner.state,
+            failure_rate: Self::failure_rate(&inner),
+            window_size: inner.outcomes.len(),
+            total_calls: inner.total_calls,
+            total_failures: inner.total_failures,
+            total_timeouts: inner.total_timeouts,
+            total_skipped: inner.total_skipped,
+            open_for_ms: inner.opened_at.map(|t| t.elapsed().as_millis() as u64),
+        }
+    }
+
+    fn push_outcome(&self, inner: &mut BreakerInner, success: bool) {
+        inner.outcomes.push_back(success);
+        while inner.outcomes.len() > self.config.window_size {
+            inner.outcomes.pop_front();
+        }
+    }
+
+    fn failure_rate(inner: &BreakerInner) -> f64 {
+        if inner.outcomes.is_empty() {
+            return 0.0;
+        }
+        let failures = inner.outcomes.iter().filter(|ok| !**ok).count();
+        failures as f64 / inner.outcomes.len() as f64
+    }
+}
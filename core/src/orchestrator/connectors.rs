@@ -0,0 +1,287 @@
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use bytes::{Buf, BufMut};
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Channel, Endpoint};
+
+use super::{AISystem, StreamingContext};
+use super::accelerator::AcceleratorRequirement;
+
+type ConnectorError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectorTransport {
+    Http,
+    Grpc,
+}
+
+/// One AI system entry in the connector manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AISystemManifestEntry {
+    pub system_id: String,
+    pub transport: ConnectorTransport,
+    pub endpoint: String,
+    // gRPC method path (e.g. "/morphine.vision.Detector/Process"); unused for HTTP
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    // Field in the system's response that carries its confidence
+    #[serde(default = "default_confidence_field")]
+    pub confidence_field: String,
+    // HTTP URL probed with GET for health checks; when unset, probes always pass
+    #[serde(default)]
+    pub health_endpoint: Option<String>,
+    // Device capacity each call needs; CPU-only systems leave this unset
+    #[serde(default)]
+    pub accelerator: Option<AcceleratorRequirement>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AISystemManifest {
+    pub systems: Vec<AISystemManifestEntry>,
+}
+
+fn default_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn default_confidence_field() -> String {
+    "confidence".to_string()
+}
+
+impl AISystemManifest {
+    pub fn load(path: &str) -> Result<Self, ConnectorError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read AI system manifest {}: {}", path, e))?;
+        let manifest: AISystemManifest = serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid AI system manifest {}: {}", path, e))?;
+        Ok(manifest)
+    }
+
+    /// Builds a connector for every manifest entry, paired with its registration weight.
+    pub fn build_connectors(&self) -> Result<Vec<(Box<dyn AISystem + Send + Sync>, f64)>, ConnectorError> {
+        let mut connectors: Vec<(Box<dyn AISystem + Send + Sync>, f64)> = Vec::new();
+
+        for entry in &self.systems {
+            let connector: Box<dyn AISystem + Send + Sync> = match entry.transport {
+                ConnectorTransport::Http => Box::new(HttpAISystem::new(entry.clone())?),
+                ConnectorTransport::Grpc => Box::new(GrpcAISystem::new(entry.clone())?),
+            };
+            connectors.push((connector, entry.weight));
+        }
+
+        Ok(connectors)
+    }
+}
+
+fn confidence_from(entry: &AISystemManifestEntry, input: &serde_json::Value) -> f64 {
+    input.get(&entry.confidence_field)
+        .and_then(|v| v.as_f64())
+        .unwrap_or(0.5)
+        .clamp(0.0, 1.0)
+}
+
+// HTTP connector - POSTs the streaming context as JSON and expects a JSON result
+pub struct HttpAISystem {
+    entry: AISystemManifestEntry,
+    client: reqwest::Client,
+    last_processing_time: RwLock<f64>,
+}
+
+impl HttpAISystem {
+    pub fn new(entry: AISystemManifestEntry) -> Result<Self, ConnectorError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(entry.timeout_ms))
+            .build()?;
+
+        Ok(Self {
+            entry,
+            client,
+            last_processing_time: RwLock::new(0.0),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl AISystem for HttpAISystem {
+    async fn process(&self, context: &StreamingContext) -> Result<serde_json::Value, ConnectorError> {
+        let started = Instant::now();
+
+        let result = async {
+            let response = self.client
+                .post(&self.entry.endpoint)
+                .json(context)
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok::<_, ConnectorError>(response.json::<serde_json::Value>().await?)
+        }.await;
+
+        *self.last_processing_time.write() = started.elapsed().as_secs_f64() * 1000.0;
+        result
+    }
+
+    async fn health_check(&self) -> Result<(), ConnectorError> {
+        if let Some(health_endpoint) = &self.entry.health_endpoint {
+            self.client.get(health_endpoint).send().await?.error_for_status()?;
+        }
+        Ok(())
+    }
+
+    fn get_confidence(&self, input: &serde_json::Value) -> f64 {
+        confidence_from(&self.entry, input)
+    }
+
+    fn get_system_id(&self) -> String {
+        self.entry.system_id.clone()
+    }
+
+    fn get_processing_time(&self) -> f64 {
+        *self.last_processing_time.read()
+    }
+
+    fn get_capabilities(&self) -> Vec<String> {
+        self.entry.capabilities.clone()
+    }
+
+    fn accelerator_requirement(&self) -> Option<AcceleratorRequirement> {
+        self.entry.accelerator
+    }
+}
+
+// gRPC connector - unary call with JSON-encoded messages, so services don't need
+// to share generated protobuf types with the core
+pub struct GrpcAISystem {
+    entry: AISystemManifestEntry,
+    channel: Channel,
+    method: PathAndQuery,
+    last_processing_time: RwLock<f64>,
+}
+
+impl GrpcAISystem {
+    pub fn new(entry: AISystemManifestEntry) -> Result<Self, ConnectorError> {
+        let method_path = entry.method.clone()
+            .ok_or_else(|| format!("gRPC system {} requires a method path", entry.system_id))?;
+        let method = PathAndQuery::try_from(method_path)?;
+
+        // Lazy connection: the channel connects on first use and reconnects as needed
+        let channel = Endpoint::from_shared(entry.endpoint.clone())?
+            .timeout(Duration::from_millis(entry.timeout_ms))
+            .connect_lazy();
+
+        Ok(Self {
+            entry,
+            channel,
+            method,
+            last_processing_time: RwLock::new(0.0),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl AISystem for GrpcAISystem {
+    async fn process(&self, context: &StreamingContext) -> Result<serde_json::Value, ConnectorError> {
+        let started = Instant::now();
+
+        let result = async {
+            let payload = serde_json::to_value(context)?;
+            let mut client = tonic::client::Grpc::new(self.channel.clone());
+            client.ready().await?;
+
+            let response = client
+                .unary(tonic::Request::new(payload), self.method.clone(), JsonCodec)
+                .await?;
+            Ok::<_, ConnectorError>(response.into_inner())
+        }.await;
+
+        *self.last_processing_time.write() = started.elapsed().as_secs_f64() * 1000.0;
+        result
+    }
+
+    // A ready channel means the service is reachable
+    async fn health_check(&self) -> Result<(), ConnectorError> {
+        let mut client = tonic::client::Grpc::new(self.channel.clone());
+        client.ready().await?;
+        Ok(())
+    }
+
+    fn get_confidence(&self, input: &serde_json::Value) -> f64 {
+        confidence_from(&self.entry, input)
+    }
+
+    fn get_system_id(&self) -> String {
+        self.entry.system_id.clone()
+    }
+
+    fn get_processing_time(&self) -> f64 {
+        *self.last_processing_time.read()
+    }
+
+    fn get_capabilities(&self) -> Vec<String> {
+        self.entry.capabilities.clone()
+    }
+
+    fn accelerator_requirement(&self) -> Option<AcceleratorRequirement> {
+        self.entry.accelerator
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct JsonCodec;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct JsonEncoder;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct JsonDecoder;
+
+impl Codec for JsonCodec {
+    type Encode = serde_json::Value;
+    type Decode = serde_json::Value;
+    type Encoder = JsonEncoder;
+    type Decoder = JsonDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        JsonEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        JsonDecoder
+    }
+}
+
+impl Encoder for JsonEncoder {
+    type Item = serde_json::Value;
+    type Error = tonic::Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        let bytes = serde_json::to_vec(&item)
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        dst.put_slice(&bytes);
+        Ok(())
+    }
+}
+
+impl Decoder for JsonDecoder {
+    type Item = serde_json::Value;
+    type Error = tonic::Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        let bytes = src.copy_to_bytes(src.remaining());
+        let value = serde_json::from_slice(&bytes)
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        Ok(Some(value))
+    }
+}
@@ -0,0 +1,157 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+use std::sync::Arc;
+use sqlx::{Pool, Postgres, Row};
+
+use super::{MetacognitiveDecision, StreamingContext};
+use crate::replica::ReadRouter;
+
+type DecisionLogError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DecisionQuery {
+    // Unix timestamps (seconds), inclusive
+    pub from: Option<f64>,
+    pub to: Option<f64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecisionRecord {
+    pub inputs_hash: String,
+    pub decided_at: DateTime<Utc>,
+    pub decision: MetacognitiveDecision,
+}
+
+/// Persistent audit trail of every decision the orchestrator produces.
+pub struct DecisionLog {
+    db_pool: Pool<Postgres>,
+    // Decision history for dashboards; lookups by ID stay on db_pool so fresh decisions are found
+    reads: Arc<ReadRouter>,
+}
+
+impl DecisionLog {
+    pub fn new(db_pool: Pool<Postgres>, reads: Arc<ReadRouter>) -> Self {
+        Self { db_pool, reads }
+    }
+
+    /// The same for equal contexts on any run or instance: object keys,
+    /// `partial_data`'s included, are hashed in sorted order.
+    pub fn hash_inputs(context: &StreamingContext) -> String {
+        let serialized = serde_json::to_value(context)
+            .map(|value| canonical(value).to_string())
+            .unwrap_or_default();
+        sha256::digest(serialized)
+    }
+
+    pub async fn record(
+        &self,
+        decision: &MetacognitiveDecision,
+        inputs_hash: &str,
+    ) -> Result<(), DecisionLogError> {
+        sqlx::query(
+            r#"
+            INSERT INTO orchestrator_decisions (
+                decision_id, stream_id, decision_type, confidence, inputs_hash,
+                evidence, layer_weights, metabolic_state, decision, decided_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (decision_id) DO NOTHING
+            "#
+        )
+        .bind(&decision.decision_id)
+        .bind(&decision.stream_id)
+        .bind(format!("{:?}", decision.decision_type))
+        .bind(decision.confidence)
+        .bind(inputs_hash)
+        .bind(serde_json::to_value(&decision.evidence)?)
+        .bind(serde_json::json!({
+            "context": decision.layer_contributions.context_weight,
+            "reasoning": decision.layer_contributions.reasoning_weight,
+            "intuition": decision.layer_contributions.intuition_weight,
+        }))
+        .bind(serde_json::to_value(&decision.layer_contributions.metabolic_state)?)
+        .bind(serde_json::to_value(decision)?)
+        .bind(timestamp_to_datetime(decision.timestamp))
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get(&self, decision_id: &str) -> Result<Option<MetacognitiveDecision>, DecisionLogError> {
+        let row = sqlx::query("SELECT decision FROM orchestrator_decisions WHERE decision_id = $1")
+            .bind(decision_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+
+        match row {
+            Some(row) => {
+                let decision: serde_json::Value = row.get("decision");
+                Ok(Some(serde_json::from_value(decision)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn query(
+        &self,
+        stream_id: &str,
+        query: &DecisionQuery,
+    ) -> Result<Vec<DecisionRecord>, DecisionLogError> {
+        let from = query.from.map(timestamp_to_datetime);
+        let to = query.to.map(timestamp_to_datetime);
+        let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+
+        let rows = sqlx::query(
+            r#"
+            SELECT inputs_hash, decided_at, decision
+            FROM orchestrator_decisions
+            WHERE stream_id = $1
+              AND ($2::timestamptz IS NULL OR decided_at >= $2)
+              AND ($3::timestamptz IS NULL OR decided_at <= $3)
+            ORDER BY decided_at DESC
+            LIMIT $4
+            "#
+        )
+        .bind(stream_id)
+        .bind(from)
+        .bind(to)
+        .bind(limit)
+        .fetch_all(self.reads.reader())
+        .await?;
+
+        let mut records = Vec::with_capacity(rows.len());
+        for row in rows {
+            let decision: serde_json::Value = row.get("decision");
+            records.push(DecisionRecord {
+                inputs_hash: row.get("inputs_hash"),
+                decided_at: row.get("decided_at"),
+                decision: serde_json::from_value(decision)?,
+            });
+        }
+
+        Ok(records)
+    }
+}
+
+fn timestamp_to_datetime(timestamp: f64) -> DateTime<Utc> {
+    let secs = timestamp.trunc() as i64;
+    let nanos = (timestamp.fract() * 1e9) as u32;
+    Utc.timestamp_opt(secs, nanos).single().unwrap_or_else(Utc::now)
+}
+
+// `value` with every object's keys in sorted order, whatever order they were inserted in
+fn canonical(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let sorted: std::collections::BTreeMap<String, serde_json::Value> = map.into_iter()
+                .map(|(key, value)| (key, canonical(value)))
+                .collect();
+            serde_json::Value::Object(sorted.into_iter().collect())
+        }
+        serde_json::Value::Array(values) => serde_json::Value::Array(values.into_iter().map(canonical).collect()),
+        other => other,
+    }
+}
@@ -0,0 +1,401 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use futures::FutureExt;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{oneshot, Notify};
+use tokio_util::sync::{CancellationToken, DropGuard};
+use tracing::{error, info};
+
+use super::metabolic::Task;
+use crate::config::ExecutorConfig;
+use crate::runtime;
+
+pub type TaskError = Box<dyn std::error::Error + Send + Sync>;
+
+// Resolves to whether the work succeeded; the typed result travels over the task's oneshot
+type Work = Pin<Box<dyn Future<Output = bool> + Send>>;
+
+// Idle workers re-check for stealable work at least this often
+const IDLE_POLL: Duration = Duration::from_millis(50);
+
+struct Job {
+    task: Task,
+    cancel: CancellationToken,
+    enqueued_at: Instant,
+    work: Work,
+}
+
+impl Job {
+    // Same ordering the glycolytic balancer always used: priority per unit of complexity
+    fn score(&self) -> f64 {
+        self.task.priority / self.task.complexity.max(f64::EPSILON)
+    }
+}
+
+struct Worker {
+    worker_id: String,
+    // Highest score at the front; owners pop the front, thieves take the back
+    local: Mutex<VecDeque<Job>>,
+    busy: AtomicBool,
+    retiring: AtomicBool,
+    current_task: Mutex<Option<String>>,
+    performance_score: Mutex<f64>,
+}
+
+/// Handle to a submitted task. Dropping it cancels the task if it hasn't finished.
+pub struct TaskHandle<T> {
+    pub task_id: String,
+    receiver: oneshot::Receiver<Result<T, TaskError>>,
+    _cancel_on_drop: DropGuard,
+}
+
+impl<T> TaskHandle<T> {
+    pub async fn join(self) -> Result<T, TaskError> {
+        let TaskHandle { receiver, _cancel_on_drop, .. } = self;
+        match receiver.await {
+            Ok(result) => result,
+            Err(_) => Err("Task was cancelled before completing".into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkerSnapshot {
+    pub worker_id: String,
+    pub busy: bool,
+    pub current_task: Option<String>,
+    pub queued: usize,
+    pub performance_score: f64,
+}
+
+/// Measured executor performance; latencies are exponentially weighted averages.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutorMetrics {
+    pub workers: usize,
+    pub busy_workers: usize,
+    pub queued: usize,
+    pub completed: u64,
+    pub failed: u64,
+    pub cancelled: u64,
+    pub steals: u64,
+    // Completed tasks per second
+    pub throughput: f64,
+    pub average_latency_ms: f64,
+    pub average_queue_wait_ms: f64,
+    pub error_rate: f64,
+}
+
+#[derive(Default)]
+struct MetricsState {
+    throughput: f64,
+    average_latency_ms: f64,
+    average_queue_wait_ms: f64,
+    error_rate: f64,
+    last_sample: Option<(Instant, u64)>,
+}
+
+/// Work-stealing task executor backing the glycolytic cycle.
+///
+/// Each worker owns a local queue; submissions are spread across workers and
+/// idle workers steal from the back of the longest queue.
+pub struct WorkStealingExecutor {
+    config: ExecutorConfig,
+    workers: RwLock<Vec<Arc<Worker>>>,
+    work_available: Notify,
+    next_worker: AtomicUsize,
+    next_worker_id: AtomicUsize,
+    completed: AtomicU64,
+    failed: AtomicU64,
+    cancelled: AtomicU64,
+    steals: AtomicU64,
+    metrics: Mutex<MetricsState>,
+}
+
+impl WorkStealingExecutor {
+    pub fn new(config: ExecutorConfig) -> Arc<Self> {
+        let executor = Arc::new(Self {
+            config,
+            workers: RwLock::new(Vec::new()),
+            work_available: Notify::new(),
+            next_worker: AtomicUsize::new(0),
+            next_worker_id: AtomicUsize::new(0),
+            completed: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            cancelled: AtomicU64::new(0),
+            steals: AtomicU64::new(0),
+            metrics: Mutex::new(MetricsState::default()),
+        });
+
+        for _ in 0..executor.min_workers() {
+            executor.spawn_worker();
+        }
+
+        executor
+    }
+
+    fn min_workers(&self) -> usize {
+        match self.config.min_workers {
+            0 => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            configured => configured,
+        }
+    }
+
+    fn spawn_worker(self: &Arc<Self>) {
+        let id = self.next_worker_id.fetch_add(1, Ordering::Relaxed);
+        let worker = Arc::new(Worker {
+            worker_id: format!("worker_{}", id),
+            local: Mutex::new(VecDeque::new()),
+            busy: AtomicBool::new(false),
+            retiring: AtomicBool::new(false),
+            current_task: Mutex::new(None),
+            performance_score: Mutex::new(1.0),
+        });
+        self.workers.write().push(worker.clone());
+
+        let executor = self.clone();
+        runtime::spawn(&format!("orchestrator:executor:{}", worker.worker_id), async move {
+            executor.run_worker(worker).await;
+        });
+    }
+
+    /// Queues `work` for execution and returns a handle to its result.
+    pub fn spawn<T, F>(self: &Arc<Self>, task: Task, work: F) -> TaskHandle<T>
+    where
+        T: Send + 'static,
+        F: Future<Output = Result<T, TaskError>> + Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let cancel = CancellationToken::new();
+        let task_id = task.task_id.clone();
+
+        let job = Job {
+            task,
+            cancel: cancel.clone(),
+            enqueued_at: Instant::now(),
+            work: Box::pin(async move {
+                let result = work.await;
+                let succeeded = result.is_ok();
+                let _ = sender.send(result);
+                succeeded
+            }),
+        };
+        self.enqueue(job);
+
+        TaskHandle {
+            task_id,
+            receiver,
+            _cancel_on_drop: cancel.drop_guard(),
+        }
+    }
+
+    fn enqueue(&self, job: Job) {
+        {
+            let workers = self.workers.read();
+            let active: Vec<&Arc<Worker>> = workers.iter()
+                .filter(|w| !w.retiring.load(Ordering::Acquire))
+                .collect();
+            let target = match active.len() {
+                0 => &workers[0],
+                n => active[self.next_worker.fetch_add(1, Ordering::Relaxed) % n],
+            };
+
+            let mut local = target.local.lock();
+            let score = job.score();
+            let position = local.iter().position(|queued| queued.score() < score).unwrap_or(local.len());
+            local.insert(position, job);
+        }
+        self.work_available.notify_one();
+    }
+
+    fn next_job(&self, worker: &Worker) -> Option<Job> {
+        if let Some(job) = worker.local.lock().pop_front() {
+            return Some(job);
+        }
+        if worker.retiring.load(Ordering::Acquire) {
+            return None;
+        }
+
+        // Steal the lowest-scored job from whoever has the most waiting
+        let workers = self.workers.read();
+        let victim = workers.iter()
+            .filter(|other| other.worker_id != worker.worker_id)
+            .max_by_key(|other| other.local.lock().len())?;
+        let stolen = victim.local.lock().pop_back();
+        if stolen.is_some() {
+            self.steals.fetch_add(1, Ordering::Relaxed);
+        }
+        stolen
+    }
+
+    async fn run_worker(self: Arc<Self>, worker: Arc<Worker>) {
+        loop {
+            // Register interest before looking so a submission in between isn't missed
+            let notified = self.work_available.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            match self.next_job(&worker) {
+                Some(job) => self.run_job(&worker, job).await,
+                None if worker.retiring.load(Ordering::Acquire) => break,
+                None => {
+                    let _ = tokio::time::timeout(IDLE_POLL, notified).await;
+                }
+            }
+        }
+
+        self.workers.write().retain(|w| w.worker_id != worker.worker_id);
+
+        // Anything routed here while we were retiring goes back to the pool
+        let leftovers: Vec<Job> = worker.local.lock().drain(..).collect();
+        for job in leftovers {
+            self.enqueue(job);
+        }
+        info!("Retired glycolytic {}", worker.worker_id);
+    }
+
+    async fn run_job(&self, worker: &Worker, job: Job) {
+        let queue_wait = job.enqueued_at.elapsed();
+        if job.cancel.is_cancelled() {
+            self.cancelled.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        worker.busy.store(true, Ordering::Release);
+        *worker.current_task.lock() = Some(job.task.task_id.clone());
+
+        let started = Instant::now();
+        let outcome = tokio::select! {
+            biased;
+            _ = job.cancel.cancelled() => None,
+            result = AssertUnwindSafe(job.work).catch_unwind() => Some(result.unwrap_or_else(|_| {
+                error!("Task {} panicked on {}", job.task.task_id, worker.worker_id);
+                false
+            })),
+        };
+        let elapsed = started.elapsed();
+
+        *worker.current_task.lock() = None;
+        worker.busy.store(false, Ordering::Release);
+
+        match outcome {
+            Some(succeeded) => {
+                let counter = if succeeded { &self.completed } else { &self.failed };
+                counter.fetch_add(1, Ordering::Relaxed);
+                self.record_execution(queue_wait, elapsed, succeeded);
+
+                let mut score = worker.performance_score.lock();
+                *score = *score * 0.9 + 0.1 / elapsed.as_secs_f64().max(0.001);
+            }
+            None => {
+                self.cancelled.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn record_execution(&self, queue_wait: Duration, elapsed: Duration, succeeded: bool) {
+        let alpha = self.config.metrics_smoothing;
+        let mut metrics = self.metrics.lock();
+        metrics.average_latency_ms = ewma(metrics.average_latency_ms, elapsed.as_secs_f64() * 1000.0, alpha);
+        metrics.average_queue_wait_ms = ewma(metrics.average_queue_wait_ms, queue_wait.as_secs_f64() * 1000.0, alpha);
+        metrics.error_rate = ewma(metrics.error_rate, if succeeded { 0.0 } else { 1.0 }, alpha);
+    }
+
+    /// Folds completions since the last sample into the throughput average.
+    pub fn sample_throughput(&self) {
+        let now = Instant::now();
+        let finished = self.completed.load(Ordering::Relaxed) + self.failed.load(Ordering::Relaxed);
+        let alpha = self.config.metrics_smoothing;
+
+        let mut metrics = self.metrics.lock();
+        if let Some((last_at, last_finished)) = metrics.last_sample {
+            let seconds = now.duration_since(last_at).as_secs_f64();
+            if seconds > 0.0 {
+                let rate = finished.saturating_sub(last_finished) as f64 / seconds;
+                metrics.throughput = ewma(metrics.throughput, rate, alpha);
+            }
+        }
+        metrics.last_sample = Some((now, finished));
+    }
+
+    // Fraction of workers currently executing a task
+    pub fn load(&self) -> f64 {
+        let workers = self.workers.read();
+        if workers.is_empty() {
+            return 0.0;
+        }
+        let busy = workers.iter().filter(|w| w.busy.load(Ordering::Acquire)).count();
+        busy as f64 / workers.len() as f64
+    }
+
+    pub fn queued(&self) -> usize {
+        self.workers.read().iter().map(|w| w.local.lock().len()).sum()
+    }
+
+    /// Adds a worker under sustained load with work waiting, or retires an idle
+    /// one when load drops, within the configured bounds.
+    pub fn scale(self: &Arc<Self>) {
+        let load = self.load();
+        let (active, queued) = {
+            let workers = self.workers.read();
+            let active = workers.iter().filter(|w| !w.retiring.load(Ordering::Acquire)).count();
+            (active, workers.iter().map(|w| w.local.lock().len()).sum::<usize>())
+        };
+
+        if load > self.config.scale_up_load && queued > 0 && active < self.config.max_workers {
+            self.spawn_worker();
+        } else if load < self.config.scale_down_load && active > self.min_workers() {
+            let workers = self.workers.read();
+            if let Some(idle) = workers.iter().find(|w| {
+                !w.busy.load(Ordering::Acquire) && !w.retiring.load(Ordering::Acquire)
+            }) {
+                idle.retiring.store(true, Ordering::Release);
+                self.work_available.notify_waiters();
+            }
+        }
+    }
+
+    pub fn workers(&self) -> Vec<WorkerSnapshot> {
+        self.workers.read().iter()
+            .map(|w| WorkerSnapshot {
+                worker_id: w.worker_id.clone(),
+                busy: w.busy.load(Ordering::Acquire),
+                current_task: w.current_task.lock().clone(),
+                queued: w.local.lock().len(),
+                performance_score: *w.performance_score.lock(),
+            })
+            .collect()
+    }
+
+    pub fn metrics(&self) -> ExecutorMetrics {
+        let (workers, busy_workers) = {
+            let workers = self.workers.read();
+            let busy = workers.iter().filter(|w| w.busy.load(Ordering::Acquire)).count();
+            (workers.len(), busy)
+        };
+        let metrics = self.metrics.lock();
+
+        ExecutorMetrics {
+            workers,
+            busy_workers,
+            queued: self.queued(),
+            completed: self.completed.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            cancelled: self.cancelled.load(Ordering::Relaxed),
+            steals: self.steals.load(Ordering::Relaxed),
+            throughput: metrics.throughput,
+            average_latency_ms: metrics.average_latency_ms,
+            average_queue_wait_ms: metrics.average_queue_wait_ms,
+            error_rate: metrics.error_rate,
+        }
+    }
+}
+
+fn ewma(current: f64, sample: f64, alpha: f64) -> f64 {
+    current * (1.0 - alpha) + sample * alpha
+}
@@ -0,0 +1,208 @@
+use std::collections::{HashMap, VecDeque};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::MetacognitiveDecision;
+use crate::config::FeedbackConfig;
+use crate::validation::{Validate, ValidationErrors};
+
+pub const LAYERS: [&str; 3] = ["context", "reasoning", "intuition"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackSource {
+    BetSettlement,
+    LocationVerification,
+    Manual,
+}
+
+/// A confirmed outcome for a previously emitted decision.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OutcomeFeedback {
+    pub decision_id: String,
+    // Whether the decision turned out to be right
+    pub correct: bool,
+    pub source: FeedbackSource,
+}
+
+impl Validate for OutcomeFeedback {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("decision_id", &self.decision_id);
+    }
+}
+
+/// The per-layer and per-system confidences a decision was built from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DecisionSignals {
+    pub layer_confidences: HashMap<String, f64>,
+    pub system_confidences: HashMap<String, f64>,
+}
+
+impl DecisionSignals {
+    pub fn from_decision(decision: &MetacognitiveDecision) -> Self {
+        let mut layer_confidences = HashMap::new();
+        for layer in LAYERS {
+            if let Some(confidence) = decision.evidence.get(layer)
+                .and_then(|e| e.get("confidence"))
+                .and_then(|c| c.as_f64())
+            {
+                layer_confidences.insert(layer.to_string(), confidence);
+            }
+        }
+
+        let system_confidences = decision.evidence.get("context")
+            .and_then(|e| e.get("system_confidences"))
+            .cloned()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+
+        Self {
+            layer_confidences,
+            system_confidences,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightAdjustment {
+    pub adjusted_at: f64,
+    pub decision_id: String,
+    pub source: FeedbackSource,
+    // "layer:<name>" or "system:<id>"
+    pub target: String,
+    pub previous: f64,
+    pub updated: f64,
+    pub score: f64,
+}
+
+/// Online learner that nudges layer multipliers and AI system weights toward
+/// the sources that were confident about decisions that turned out correct.
+pub struct WeightLearner {
+    config: FeedbackConfig,
+    layer_multipliers: RwLock<HashMap<String, f64>>,
+    signals: RwLock<HashMap<String, DecisionSignals>>,
+    signal_order: RwLock<VecDeque<String>>,
+    history: RwLock<VecDeque<WeightAdjustment>>,
+}
+
+impl WeightLearner {
+    pub fn new(config: FeedbackConfig) -> Self {
+        Self {
+            layer_multipliers: RwLock::new(
+                LAYERS.iter().map(|layer| (layer.to_string(), 1.0)).collect()
+            ),
+            signals: RwLock::new(HashMap::new()),
+            signal_order: RwLock::new(VecDeque::new()),
+            history: RwLock::new(VecDeque::new()),
+            config,
+        }
+    }
+
+    pub fn layer_multiplier(&self, layer: &str) -> f64 {
+        self.layer_multipliers.read().get(layer).copied().unwrap_or(1.0)
+    }
+
+    pub fn layer_multipliers(&self) -> HashMap<String, f64> {
+        self.layer_multipliers.read().clone()
+    }
+
+    // Recent decisions are kept in memory so feedback rarely needs the audit log
+    pub fn record_signals(&self, decision: &MetacognitiveDecision) {
+        let mut signals = self.signals.write();
+        let mut order = self.signal_order.write();
+
+        if signals.insert(decision.decision_id.clone(), DecisionSignals::from_decision(decision)).is_none() {
+            order.push_back(decision.decision_id.clone());
+        }
+        while signals.len() > self.config.signal_cache_size {
+            match order.pop_front() {
+                Some(oldest) => { signals.remove(&oldest); }
+                None => break,
+            }
+        }
+    }
+
+    pub fn signals_for(&self, decision_id: &str) -> Option<DecisionSignals> {
+        self.signals.read().get(decision_id).cloned()
+    }
+
+    /// Applies one outcome to the layer multipliers and the given system weights,
+    /// returning the adjustments made.
+    pub fn apply_feedback(
+        &self,
+        feedback: &OutcomeFeedback,
+        signals: &DecisionSignals,
+        system_weights: &mut HashMap<String, f64>,
+    ) -> Vec<WeightAdjustment> {
+        let now = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
+        let mut adjustments = Vec::new();
+
+        {
+            let mut multipliers = self.layer_multipliers.write();
+            for (layer, confidence) in &signals.layer_confidences {
+                let previous = multipliers.get(layer).copied().unwrap_or(1.0);
+                let score = self.score(*confidence, feedback.correct);
+                let updated = self.step(previous, score);
+                multipliers.insert(layer.clone(), updated);
+                adjustments.push(self.adjustment(now, feedback, format!("layer:{}", layer), previous, updated, score));
+            }
+        }
+
+        for (system_id, confidence) in &signals.system_confidences {
+            // Systems deregistered since the decision are skipped
+            if let Some(weight) = system_weights.get_mut(system_id) {
+                let previous = *weight;
+                let score = self.score(*confidence, feedback.correct);
+                *weight = self.step(previous, score);
+                adjustments.push(self.adjustment(now, feedback, format!("system:{}", system_id), previous, *weight, score));
+            }
+        }
+
+        let mut history = self.history.write();
+        history.extend(adjustments.iter().cloned());
+        while history.len() > self.config.history_size {
+            history.pop_front();
+        }
+
+        adjustments
+    }
+
+    // Most recent adjustments first
+    pub fn history(&self, limit: usize) -> Vec<WeightAdjustment> {
+        self.history.read().iter().rev().take(limit).cloned().collect()
+    }
+
+    // How well a source's confidence matched the outcome, in [0, 1]
+    fn score(&self, confidence: f64, correct: bool) -> f64 {
+        let confidence = confidence.clamp(0.0, 1.0);
+        if correct { confidence } else { 1.0 - confidence }
+    }
+
+    // Multiplicative update centred on a neutral score of 0.5, with a bounded step
+    fn step(&self, weight: f64, score: f64) -> f64 {
+        let delta = (self.config.learning_rate * (2.0 * score - 1.0))
+            .clamp(-self.config.max_step, self.config.max_step);
+        (weight * (1.0 + delta)).clamp(self.config.min_weight, self.config.max_weight)
+    }
+
+    fn adjustment(
+        &self,
+        now: f64,
+        feedback: &OutcomeFeedback,
+        target: String,
+        previous: f64,
+        updated: f64,
+        score: f64,
+    ) -> WeightAdjustment {
+        WeightAdjustment {
+            adjusted_at: now,
+            decision_id: feedback.decision_id.clone(),
+            source: feedback.source,
+            target,
+            previous,
+            updated,
+            score,
+        }
+    }
+}
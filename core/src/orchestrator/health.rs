@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::HealthProbeConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemStatus {
+    Healthy,
+    Quarantined,
+}
+
+/// Probe history for one registered AI system.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemHealthState {
+    pub status: SystemStatus,
+    pub consecutive_failures: u32,
+    // Number of back-to-back quarantines; drives the exponential backoff
+    pub quarantine_count: u32,
+    pub quarantined_until: Option<f64>,
+    pub last_probe_at: Option<f64>,
+    pub last_error: Option<String>,
+}
+
+impl Default for SystemHealthState {
+    fn default() -> Self {
+        Self {
+            status: SystemStatus::Healthy,
+            consecutive_failures: 0,
+            quarantine_count: 0,
+            quarantined_until: None,
+            last_probe_at: None,
+            last_error: None,
+        }
+    }
+}
+
+/// What a probe result means for the system.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProbeVerdict {
+    Unchanged,
+    Quarantined { until: f64 },
+    Recovered,
+    Deregister,
+}
+
+impl SystemHealthState {
+    pub fn is_quarantined(&self) -> bool {
+        self.status == SystemStatus::Quarantined
+    }
+
+    // Quarantined systems are not probed again until their backoff expires
+    pub fn due_for_probe(&self, now: f64) -> bool {
+        match self.quarantined_until {
+            Some(until) => now >= until,
+            None => true,
+        }
+    }
+
+    pub fn record_success(&mut self, now: f64) -> ProbeVerdict {
+        let was_quarantined = self.is_quarantined();
+
+        self.last_probe_at = Some(now);
+        self.last_error = None;
+        self.consecutive_failures = 0;
+        self.quarantine_count = 0;
+        self.quarantined_until = None;
+        self.status = SystemStatus::Healthy;
+
+        if was_quarantined {
+            ProbeVerdict::Recovered
+        } else {
+            ProbeVerdict::Unchanged
+        }
+    }
+
+    pub fn record_failure(&mut self, now: f64, error: String, config: &HealthProbeConfig) -> ProbeVerdict {
+        self.last_probe_at = Some(now);
+        self.last_error = Some(error);
+        self.consecutive_failures += 1;
+
+        if self.consecutive_failures >= config.deregister_after_failures {
+            return ProbeVerdict::Deregister;
+        }
+        if self.consecutive_failures < config.quarantine_after_failures {
+            return ProbeVerdict::Unchanged;
+        }
+
+        let backoff = (config.quarantine_base_secs as f64 * 2f64.powi(self.quarantine_count as i32))
+            .min(config.quarantine_max_secs as f64);
+        let until = now + backoff;
+
+        self.status = SystemStatus::Quarantined;
+        self.quarantine_count += 1;
+        self.quarantined_until = Some(until);
+
+        ProbeVerdict::Quarantined { until }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LifecycleEventKind {
+    Registered { weight: f64 },
+    Quarantined { until: f64, consecutive_failures: u32, error: Option<String> },
+    Recovered,
+    Deregistered { reason: String },
+    WeightChanged { previous: f64, weight: f64 },
+}
+
+/// Emitted on the admin channel whenever an AI system changes state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemLifecycleEvent {
+    pub system_id: String,
+    pub timestamp: f64,
+    #[serde(flatten)]
+    pub kind: LifecycleEventKind,
+}
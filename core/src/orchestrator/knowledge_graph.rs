@@ -0,0 +1,244 @@
+use std::collections::{HashMap, VecDeque};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+use super::StreamingContext;
+use super::backpressure::now_seconds;
+use crate::config::KnowledgeGraphConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Stream,
+    User,
+    Event,
+    AiSystem,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entity {
+    pub id: String,
+    pub kind: EntityKind,
+    pub attributes: HashMap<String, serde_json::Value>,
+    pub updated_at: f64,
+}
+
+/// A directed edge between two entities, valid over a time interval.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Relation {
+    pub from: String,
+    pub relation: String,
+    pub to: String,
+    pub weight: f64,
+    pub valid_from: f64,
+    pub valid_to: Option<f64>,
+}
+
+impl Relation {
+    fn valid_at(&self, at: f64) -> bool {
+        self.valid_from <= at && self.valid_to.map(|to| at < to).unwrap_or(true)
+    }
+}
+
+/// A timestamped statement about an entity, e.g. a stream's latest motion energy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemporalFact {
+    pub subject: String,
+    pub predicate: String,
+    pub object: serde_json::Value,
+    pub observed_at: f64,
+    pub valid_until: Option<f64>,
+    pub source: String,
+    pub confidence: f64,
+}
+
+impl TemporalFact {
+    fn valid_at(&self, at: f64) -> bool {
+        self.observed_at <= at && self.valid_until.map(|until| at < until).unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct KnowledgeQuery {
+    pub entity: Option<String>,
+    #[param(value_type = Option<String>)]
+    pub kind: Option<EntityKind>,
+    pub relation: Option<String>,
+    pub predicate: Option<String>,
+    // Point-in-time view; defaults to now
+    pub at: Option<f64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KnowledgeQueryResult {
+    pub entities: Vec<Entity>,
+    pub relations: Vec<Relation>,
+    pub facts: Vec<TemporalFact>,
+}
+
+/// Typed, in-memory graph of what the orchestrator knows about streams, users,
+/// events and AI systems, used to ground incoming analytics.
+pub struct KnowledgeGraph {
+    config: KnowledgeGraphConfig,
+    entities: RwLock<HashMap<String, Entity>>,
+    relations: RwLock<Vec<Relation>>,
+    facts: RwLock<VecDeque<TemporalFact>>,
+}
+
+impl KnowledgeGraph {
+    pub fn new(config: KnowledgeGraphConfig) -> Self {
+        Self {
+            config,
+            entities: RwLock::new(HashMap::new()),
+            relations: RwLock::new(Vec::new()),
+            facts: RwLock::new(VecDeque::new()),
+        }
+    }
+
+    pub fn upsert_entity(&self, id: &str, kind: EntityKind, attributes: HashMap<String, serde_json::Value>) {
+        let now = now_seconds();
+        let mut entities = self.entities.write();
+        let entity = entities.entry(id.to_string()).or_insert_with(|| Entity {
+            id: id.to_string(),
+            kind,
+            attributes: HashMap::new(),
+            updated_at: now,
+        });
+        entity.attributes.extend(attributes);
+        entity.updated_at = now;
+    }
+
+    // Re-asserting an open relation refreshes its weight instead of duplicating it
+    pub fn relate(&self, from: &str, relation: &str, to: &str, weight: f64) {
+        let mut relations = self.relations.write();
+        match relations.iter_mut().find(|r| {
+            r.from == from && r.relation == relation && r.to == to && r.valid_to.is_none()
+        }) {
+            Some(existing) => existing.weight = weight,
+            None => relations.push(Relation {
+                from: from.to_string(),
+                relation: relation.to_string(),
+                to: to.to_string(),
+                weight,
+                valid_from: now_seconds(),
+                valid_to: None,
+            }),
+        }
+    }
+
+    pub fn end_relation(&self, from: &str, relation: &str, to: &str) {
+        let now = now_seconds();
+        for existing in self.relations.write().iter_mut() {
+            if existing.from == from && existing.relation == relation && existing.to == to && existing.valid_to.is_none() {
+                existing.valid_to = Some(now);
+            }
+        }
+    }
+
+    pub fn assert_fact(&self, fact: TemporalFact) {
+        let mut facts = self.facts.write();
+        facts.push_back(fact);
+        while facts.len() > self.config.max_facts {
+            facts.pop_front();
+        }
+    }
+
+    pub fn query(&self, query: &KnowledgeQuery) -> KnowledgeQueryResult {
+        let at = query.at.unwrap_or_else(now_seconds);
+        let limit = query.limit.unwrap_or(100);
+
+        let entities: Vec<Entity> = self.entities.read().values()
+            .filter(|e| query.entity.as_ref().map(|id| &e.id == id).unwrap_or(true))
+            .filter(|e| query.kind.map(|kind| e.kind == kind).unwrap_or(true))
+            .take(limit)
+            .cloned()
+            .collect();
+
+        let relations: Vec<Relation> = self.relations.read().iter()
+            .filter(|r| r.valid_at(at))
+            .filter(|r| query.entity.as_ref().map(|id| &r.from == id || &r.to == id).unwrap_or(true))
+            .filter(|r| query.relation.as_ref().map(|rel| &r.relation == rel).unwrap_or(true))
+            .take(limit)
+            .cloned()
+            .collect();
+
+        // Newest first
+        let facts: Vec<TemporalFact> = self.facts.read().iter().rev()
+            .filter(|f| f.valid_at(at))
+            .filter(|f| query.entity.as_ref().map(|id| &f.subject == id).unwrap_or(true))
+            .filter(|f| query.predicate.as_ref().map(|p| &f.predicate == p).unwrap_or(true))
+            .take(limit)
+            .cloned()
+            .collect();
+
+        KnowledgeQueryResult {
+            entities,
+            relations,
+            facts,
+        }
+    }
+
+    /// Latest valid value of each predicate for a subject.
+    pub fn current_facts(&self, subject: &str) -> HashMap<String, TemporalFact> {
+        let now = now_seconds();
+        let mut current = HashMap::new();
+        for fact in self.facts.read().iter().rev() {
+            if fact.subject == subject && fact.valid_at(now) && !current.contains_key(&fact.predicate) {
+                current.insert(fact.predicate.clone(), fact.clone());
+            }
+        }
+        current
+    }
+
+    /// Records what a context tells us about its stream: scalar analytics become
+    /// short-lived facts, and each AI system that contributed is linked to it.
+    pub fn observe_context(&self, context: &StreamingContext, system_confidences: &HashMap<String, f64>) {
+        self.upsert_entity(&context.stream_id, EntityKind::Stream, HashMap::from([
+            ("last_seen".to_string(), serde_json::json!(context.timestamp)),
+        ]));
+
+        let valid_until = context.timestamp + self.config.fact_ttl_secs as f64;
+        for (key, value) in &context.partial_data {
+            if value.is_number() || value.is_boolean() || value.is_string() {
+                // Raw frames and other large strings are not knowledge
+                if value.as_str().map(|s| s.len() > 256).unwrap_or(false) {
+                    continue;
+                }
+                self.assert_fact(TemporalFact {
+                    subject: context.stream_id.clone(),
+                    predicate: format!("analytics.{}", key),
+                    object: value.clone(),
+                    observed_at: context.timestamp,
+                    valid_until: Some(valid_until),
+                    source: "analytics".to_string(),
+                    confidence: context.confidence_level,
+                });
+            }
+        }
+
+        for (system_id, confidence) in system_confidences {
+            self.upsert_entity(system_id, EntityKind::AiSystem, HashMap::new());
+            self.relate(&context.stream_id, "observed_by", system_id, *confidence);
+        }
+    }
+
+    /// What the graph currently knows about the context's stream, for the context layer.
+    pub fn ground_context(&self, context: &StreamingContext) -> serde_json::Value {
+        let facts = self.current_facts(&context.stream_id);
+        let now = now_seconds();
+        let related: Vec<Relation> = self.relations.read().iter()
+            .filter(|r| r.valid_at(now) && (r.from == context.stream_id || r.to == context.stream_id))
+            .cloned()
+            .collect();
+        let entity = self.entities.read().get(&context.stream_id).cloned();
+
+        serde_json::json!({
+            "entity": entity,
+            "facts": facts,
+            "relations": related,
+        })
+    }
+}
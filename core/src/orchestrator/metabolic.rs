@@ -1,10 +1,17 @@
+use std::fmt;
 use std::sync::Arc;
 use std::collections::HashMap;
-use tokio::sync::{RwLock, Mutex};
+use tokio::sync::{watch, RwLock, Mutex};
 use serde::{Deserialize, Serialize};
-use tokio::time::{Duration, interval};
+use tokio::time::{Duration, Instant, interval};
 
 use super::{StreamingContext, MetacognitiveDecision, MetabolicState};
+use super::accelerator::{AcceleratorDevice, AcceleratorPool};
+use super::executor::{ExecutorMetrics, TaskError, TaskHandle, WorkStealingExecutor, WorkerSnapshot};
+use super::priority_queue::ContextPriority;
+use crate::cache::{BoundedCache, CacheStats};
+use crate::config::{DreamingConfig, ExecutorConfig, LactateConfig};
+use crate::runtime;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
@@ -17,10 +24,39 @@ pub struct Task {
     pub created_at: f64,
 }
 
+impl Task {
+    /// Describes one pass of a context through the metacognitive layers.
+    pub fn for_context(context: &StreamingContext, estimated_time: f64) -> Self {
+        let priority = match context.priority {
+            ContextPriority::Routine => 1.0,
+            ContextPriority::Elevated => 2.0,
+            ContextPriority::Settlement => 3.0,
+        };
+        
+        Self {
+            task_id: uuid::Uuid::new_v4().to_string(),
+            stream_id: context.stream_id.clone(),
+            // Larger payloads take longer through the layers
+            complexity: 1.0 + context.partial_data.len() as f64 / 10.0,
+            priority,
+            resource_requirement: 1.0,
+            estimated_time,
+            created_at: chrono::Utc::now().timestamp_millis() as f64 / 1000.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PartialResult {
     pub result_id: String,
     pub task_id: String,
+    #[serde(default)]
+    pub stream_id: String,
+    // The context inputs that produced the low-confidence decision
+    #[serde(default)]
+    pub inputs: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub attempts: u32,
     pub completion_percentage: f64,
     pub partial_data: serde_json::Value,
     pub confidence: f64,
@@ -38,170 +74,69 @@ pub struct DreamPattern {
     pub generated_scenarios: Vec<serde_json::Value>,
 }
 
-// Glycolytic Cycle - High-throughput resource management
-pub struct GlycolyticCycle {
-    worker_pool: Arc<RwLock<Vec<WorkerState>>>,
-    task_queue: Arc<Mutex<Vec<Task>>>,
-    resource_allocation: Arc<RwLock<HashMap<String, f64>>>,
-    current_load: Arc<RwLock<f64>>,
-    performance_metrics: Arc<RwLock<PerformanceMetrics>>,
+/// Snapshot of what the dreaming module has discovered, for offline backtesting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DreamExport {
+    pub exported_at: f64,
+    pub patterns: Vec<DreamPattern>,
+    pub scenarios: Vec<serde_json::Value>,
 }
 
-#[derive(Debug, Clone)]
-struct WorkerState {
-    worker_id: String,
-    is_busy: bool,
-    current_task: Option<String>,
-    performance_score: f64,
-    resource_usage: f64,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DreamCycleReport {
+    pub trigger: String,
+    pub started_at: f64,
+    pub duration_ms: u64,
+    // False when the cycle was cut short by the maximum dream duration
+    pub completed: bool,
+    pub patterns: usize,
+    pub new_scenarios: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct PerformanceMetrics {
-    throughput: f64,
-    average_latency: f64,
-    resource_efficiency: f64,
-    error_rate: f64,
+/// The error [`GlycolyticCycle::execute_within`] gives for work that didn't
+/// finish within its budget.
+#[derive(Debug, Clone, Copy)]
+pub struct BudgetExceeded(pub Duration);
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Task exceeded its {}ms budget", self.0.as_millis())
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+// Glycolytic Cycle - High-throughput resource management
+pub struct GlycolyticCycle {
+    executor: Arc<WorkStealingExecutor>,
+    resource_allocation: Arc<RwLock<HashMap<String, f64>>>,
+    accelerators: Arc<AcceleratorPool>,
 }
 
 impl GlycolyticCycle {
-    pub fn new() -> Self {
+    pub fn new(accelerator_devices: Vec<AcceleratorDevice>, executor_config: ExecutorConfig) -> Self {
         let glycolytic = Self {
-            worker_pool: Arc::new(RwLock::new(Vec::new())),
-            task_queue: Arc::new(Mutex::new(Vec::new())),
+            executor: WorkStealingExecutor::new(executor_config),
             resource_allocation: Arc::new(RwLock::new(HashMap::new())),
-            current_load: Arc::new(RwLock::new(0.0)),
-            performance_metrics: Arc::new(RwLock::new(PerformanceMetrics {
-                throughput: 0.0,
-                average_latency: 0.0,
-                resource_efficiency: 0.0,
-                error_rate: 0.0,
-            })),
+            accelerators: Arc::new(AcceleratorPool::new(accelerator_devices)),
         };
         
-        // Initialize worker pool
-        let glycolytic_clone = glycolytic.clone();
-        tokio::spawn(async move {
-            glycolytic_clone.initialize_workers().await;
-        });
-        
         // Start load balancing
         let glycolytic_clone = glycolytic.clone();
-        tokio::spawn(async move {
+        runtime::spawn("metabolic:load_balancer", async move {
             glycolytic_clone.run_load_balancer().await;
         });
         
         glycolytic
     }
     
-    async fn initialize_workers(&self) {
-        let mut workers = self.worker_pool.write().await;
-        for i in 0..num_cpus::get() {
-            workers.push(WorkerState {
-                worker_id: format!("worker_{}", i),
-                is_busy: false,
-                current_task: None,
-                performance_score: 1.0,
-                resource_usage: 0.0,
-            });
-        }
-    }
-    
     async fn run_load_balancer(&self) {
         let mut interval = interval(Duration::from_millis(100));
         
         loop {
             interval.tick().await;
-            self.balance_load().await;
-            self.update_metrics().await;
-            self.scale_workers().await;
-        }
-    }
-    
-    async fn balance_load(&self) {
-        let mut queue = self.task_queue.lock().await;
-        let mut workers = self.worker_pool.write().await;
-        
-        // Sort tasks by priority and complexity
-        queue.sort_by(|a, b| {
-            (b.priority / b.complexity).partial_cmp(&(a.priority / a.complexity))
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-        
-        // Assign tasks to available workers
-        for worker in workers.iter_mut() {
-            if !worker.is_busy && !queue.is_empty() {
-                if let Some(task) = queue.pop() {
-                    worker.is_busy = true;
-                    worker.current_task = Some(task.task_id.clone());
-                    worker.resource_usage = task.resource_requirement;
-                    
-                    // Start task processing
-                    let worker_id = worker.worker_id.clone();
-                    let task_clone = task.clone();
-                    let glycolytic = self.clone();
-                    
-                    tokio::spawn(async move {
-                        glycolytic.process_task(worker_id, task_clone).await;
-                    });
-                }
-            }
-        }
-    }
-    
-    async fn process_task(&self, worker_id: String, task: Task) {
-        // Simulate task processing with resource consumption
-        let processing_time = task.estimated_time * (1.0 + rand::random::<f64>() * 0.2);
-        tokio::time::sleep(Duration::from_millis((processing_time * 1000.0) as u64)).await;
-        
-        // Mark worker as available
-        let mut workers = self.worker_pool.write().await;
-        if let Some(worker) = workers.iter_mut().find(|w| w.worker_id == worker_id) {
-            worker.is_busy = false;
-            worker.current_task = None;
-            worker.resource_usage = 0.0;
-            worker.performance_score = (worker.performance_score * 0.9) + (0.1 / processing_time);
-        }
-    }
-    
-    async fn update_metrics(&self) {
-        let workers = self.worker_pool.read().await;
-        let busy_workers = workers.iter().filter(|w| w.is_busy).count() as f64;
-        let total_workers = workers.len() as f64;
-        
-        let current_load = busy_workers / total_workers;
-        *self.current_load.write().await = current_load;
-        
-        let mut metrics = self.performance_metrics.write().await;
-        metrics.throughput = self.calculate_throughput(&workers).await;
-        metrics.resource_efficiency = current_load;
-    }
-    
-    async fn calculate_throughput(&self, workers: &[WorkerState]) -> f64 {
-        workers.iter()
-            .map(|w| w.performance_score)
-            .sum::<f64>() / workers.len() as f64
-    }
-    
-    async fn scale_workers(&self) {
-        let current_load = *self.current_load.read().await;
-        let mut workers = self.worker_pool.write().await;
-        
-        // Auto-scaling logic based on load
-        if current_load > 0.8 && workers.len() < 32 {
-            // Add worker
-            workers.push(WorkerState {
-                worker_id: format!("worker_{}", workers.len()),
-                is_busy: false,
-                current_task: None,
-                performance_score: 1.0,
-                resource_usage: 0.0,
-            });
-        } else if current_load < 0.3 && workers.len() > num_cpus::get() {
-            // Remove worker
-            if let Some(pos) = workers.iter().position(|w| !w.is_busy) {
-                workers.remove(pos);
-            }
+            self.executor.sample_throughput();
+            self.executor.scale();
         }
     }
     
@@ -216,21 +151,58 @@ impl GlycolyticCycle {
         allocation.insert("memory".to_string(), base_allocation * 0.8);
         allocation.insert("io".to_string(), base_allocation * 0.6);
         
+        // Accelerator share is bounded by what the devices actually have free
+        if self.accelerators.has_devices() {
+            let gpu_headroom = 1.0 - self.accelerators.overall_utilization();
+            allocation.insert("gpu".to_string(), base_allocation * gpu_headroom);
+        }
+        
         *self.resource_allocation.write().await = allocation.clone();
         allocation
     }
     
     pub async fn get_current_load(&self) -> f64 {
-        *self.current_load.read().await
+        self.executor.load()
     }
     
     pub async fn get_resource_allocation(&self) -> HashMap<String, f64> {
         self.resource_allocation.read().await.clone()
     }
     
-    pub async fn submit_task(&self, task: Task) {
-        let mut queue = self.task_queue.lock().await;
-        queue.push(task);
+    pub fn accelerator_pool(&self) -> Arc<AcceleratorPool> {
+        self.accelerators.clone()
+    }
+    
+    /// Runs `work` on the executor. Dropping the returned handle cancels it.
+    pub fn execute<T, F>(&self, task: Task, work: F) -> TaskHandle<T>
+    where
+        T: Send + 'static,
+        F: std::future::Future<Output = Result<T, TaskError>> + Send + 'static,
+    {
+        self.executor.spawn(task, work)
+    }
+    
+    /// Runs `work` on the executor, cancelling it if it hasn't finished within
+    /// `budget` of being submitted, time spent queued included.
+    pub async fn execute_within<T, F>(&self, task: Task, budget: Duration, work: F) -> Result<T, TaskError>
+    where
+        T: Send + 'static,
+        F: std::future::Future<Output = Result<T, TaskError>> + Send + 'static,
+    {
+        let handle = self.executor.spawn(task, work);
+        // Dropping the handle on timeout cancels the task, queued or running
+        match tokio::time::timeout(budget, handle.join()).await {
+            Ok(result) => result,
+            Err(_) => Err(Box::new(BudgetExceeded(budget))),
+        }
+    }
+    
+    pub fn performance_metrics(&self) -> ExecutorMetrics {
+        self.executor.metrics()
+    }
+    
+    pub fn workers(&self) -> Vec<WorkerSnapshot> {
+        self.executor.workers()
     }
 }
 
@@ -239,19 +211,21 @@ pub struct LactateCycle {
     partial_results: Arc<RwLock<HashMap<String, PartialResult>>>,
     lactate_level: Arc<RwLock<f64>>,
     cleanup_scheduler: Arc<Mutex<Vec<String>>>,
+    config: LactateConfig,
 }
 
 impl LactateCycle {
-    pub fn new() -> Self {
+    pub fn new(config: LactateConfig) -> Self {
         let lactate = Self {
             partial_results: Arc::new(RwLock::new(HashMap::new())),
             lactate_level: Arc::new(RwLock::new(0.0)),
             cleanup_scheduler: Arc::new(Mutex::new(Vec::new())),
+            config,
         };
         
         // Start cleanup process
         let lactate_clone = lactate.clone();
-        tokio::spawn(async move {
+        runtime::spawn("metabolic:lactate_cleanup", async move {
             lactate_clone.run_cleanup_process().await;
         });
         
@@ -279,6 +253,11 @@ impl LactateCycle {
     
     async fn update_lactate_level(&self) {
         let results = self.partial_results.read().await;
+        if results.is_empty() {
+            *self.lactate_level.write().await = 0.0;
+            return;
+        }
+        
         let incomplete_count = results.len() as f64;
         let total_completion = results.values()
             .map(|r| r.completion_percentage)
@@ -288,21 +267,66 @@ impl LactateCycle {
         *self.lactate_level.write().await = lactate_level;
     }
     
-    pub async fn store_partial_result(&self, decision: &MetacognitiveDecision) {
+    pub fn should_store(&self, decision: &MetacognitiveDecision) -> bool {
+        decision.confidence < self.config.partial_confidence_threshold
+    }
+    
+    /// Keeps a low-confidence decision for later recovery. Results that have
+    /// already been retried `max_recovery_attempts` times are discarded.
+    pub async fn store_partial_result(
+        &self,
+        decision: &MetacognitiveDecision,
+        inputs: &HashMap<String, serde_json::Value>,
+        attempts: u32,
+    ) {
+        if attempts >= self.config.max_recovery_attempts {
+            return;
+        }
+        
         let partial_result = PartialResult {
             result_id: uuid::Uuid::new_v4().to_string(),
             task_id: decision.decision_id.clone(),
+            stream_id: decision.stream_id.clone(),
+            inputs: inputs.clone(),
+            attempts,
             completion_percentage: decision.confidence * 100.0,
-            partial_data: serde_json::to_value(&decision.evidence).unwrap(),
+            partial_data: serde_json::to_value(&decision.evidence).unwrap_or_default(),
             confidence: decision.confidence,
             created_at: chrono::Utc::now().timestamp() as f64,
-            ttl: 3600.0, // 1 hour TTL
+            ttl: self.config.partial_ttl_secs as f64,
         };
         
         let mut results = self.partial_results.write().await;
         results.insert(partial_result.result_id.clone(), partial_result);
     }
     
+    /// Removes and returns all stored partial results for a stream, oldest first.
+    pub async fn take_partial_results(&self, stream_id: &str) -> Vec<PartialResult> {
+        let mut results = self.partial_results.write().await;
+        let ids: Vec<String> = results.values()
+            .filter(|r| r.stream_id == stream_id)
+            .map(|r| r.result_id.clone())
+            .collect();
+        
+        let mut taken: Vec<PartialResult> = ids.iter()
+            .filter_map(|id| results.remove(id))
+            .collect();
+        taken.sort_by(|a, b| a.created_at.partial_cmp(&b.created_at).unwrap_or(std::cmp::Ordering::Equal));
+        taken
+    }
+    
+    pub async fn streams_with_partials(&self) -> Vec<String> {
+        let results = self.partial_results.read().await;
+        let mut streams: Vec<String> = results.values().map(|r| r.stream_id.clone()).collect();
+        streams.sort();
+        streams.dedup();
+        streams
+    }
+    
+    pub fn config(&self) -> &LactateConfig {
+        &self.config
+    }
+    
     pub async fn retrieve_partial_result(&self, task_id: &str) -> Option<PartialResult> {
         let results = self.partial_results.read().await;
         results.values()
@@ -317,7 +341,7 @@ impl LactateCycle {
     pub async fn recovery_from_incomplete(&self, stream_id: &str) -> Vec<PartialResult> {
         let results = self.partial_results.read().await;
         results.values()
-            .filter(|r| r.task_id.contains(stream_id))
+            .filter(|r| r.stream_id == stream_id)
             .cloned()
             .collect()
     }
@@ -325,24 +349,32 @@ impl LactateCycle {
 
 // Dreaming Module - Background pattern synthesis and discovery
 pub struct DreamingModule {
-    dream_patterns: Arc<RwLock<HashMap<String, DreamPattern>>>,
+    dream_patterns: Arc<BoundedCache<String, DreamPattern>>,
     is_active: Arc<RwLock<bool>>,
     experience_buffer: Arc<RwLock<Vec<MetacognitiveDecision>>>,
     discovery_log: Arc<RwLock<Vec<serde_json::Value>>>,
+    last_experience_at: Arc<RwLock<f64>>,
+    glycolytic_cycle: Arc<GlycolyticCycle>,
+    // The schedule follows configuration reloads without a restart
+    config: watch::Receiver<DreamingConfig>,
 }
 
 impl DreamingModule {
-    pub fn new() -> Self {
+    pub fn new(config: watch::Receiver<DreamingConfig>, glycolytic_cycle: Arc<GlycolyticCycle>) -> Self {
+        let max_patterns = config.borrow().max_patterns;
         let dreaming = Self {
-            dream_patterns: Arc::new(RwLock::new(HashMap::new())),
+            dream_patterns: Arc::new(BoundedCache::new("dream_patterns", max_patterns, None)),
             is_active: Arc::new(RwLock::new(false)),
             experience_buffer: Arc::new(RwLock::new(Vec::new())),
             discovery_log: Arc::new(RwLock::new(Vec::new())),
+            last_experience_at: Arc::new(RwLock::new(0.0)),
+            glycolytic_cycle,
+            config,
         };
         
-        // Start dreaming cycles
+        // Start dreaming cycles; the loop idles while the schedule is disabled
         let dreaming_clone = dreaming.clone();
-        tokio::spawn(async move {
+        runtime::spawn("metabolic:dreaming_cycles", async move {
             dreaming_clone.run_dreaming_cycles().await;
         });
         
@@ -350,24 +382,85 @@ impl DreamingModule {
     }
     
     async fn run_dreaming_cycles(&self) {
-        let mut interval = interval(Duration::from_secs(300)); // 5 minutes
+        let mut config = self.config.clone();
         
         loop {
-            interval.tick().await;
+            let (schedule_enabled, tick_interval_secs) = {
+                let cfg = config.borrow_and_update();
+                (cfg.schedule_enabled, cfg.tick_interval_secs)
+            };
+            let mut interval = interval(Duration::from_secs(tick_interval_secs));
             
-            // Activate during low activity periods
-            if self.should_activate_dreaming().await {
-                *self.is_active.write().await = true;
-                self.dream_cycle().await;
-                *self.is_active.write().await = false;
+            // Rebuild the interval whenever the schedule is reloaded
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        // Activate during low activity periods
+                        if schedule_enabled && self.should_activate_dreaming().await {
+                            self.run_cycle("scheduled").await;
+                        }
+                    }
+                    changed = config.changed() => {
+                        if changed.is_err() {
+                            return;
+                        }
+                        break;
+                    }
+                }
             }
         }
     }
     
     async fn should_activate_dreaming(&self) -> bool {
-        // Simple heuristic: dream during low activity
-        let experience_buffer = self.experience_buffer.read().await;
-        experience_buffer.len() > 10 && chrono::Utc::now().timestamp() % 3600 < 300
+        let cfg = self.config.borrow().clone();
+        
+        if self.experience_buffer.read().await.len() < cfg.min_experiences {
+            return false;
+        }
+        
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        let in_window = now % cfg.window_period_secs < cfg.window_length_secs;
+        
+        // Idle: low glycolytic load and no fresh experience for a while
+        let idle_for = now as f64 - *self.last_experience_at.read().await;
+        let load = self.glycolytic_cycle.get_current_load().await;
+        
+        in_window && load < cfg.idle_load_threshold && idle_for >= cfg.idle_secs as f64
+    }
+    
+    /// Runs a dream cycle immediately, e.g. for an offline analysis window.
+    /// Fails if a cycle is already in progress.
+    pub async fn trigger_dream(&self) -> Result<DreamCycleReport, Box<dyn std::error::Error + Send + Sync>> {
+        self.run_cycle("manual").await
+            .ok_or_else(|| "Dream cycle already in progress".into())
+    }
+    
+    async fn run_cycle(&self, trigger: &str) -> Option<DreamCycleReport> {
+        {
+            let mut is_active = self.is_active.write().await;
+            if *is_active {
+                return None;
+            }
+            *is_active = true;
+        }
+        
+        let started_at = chrono::Utc::now().timestamp() as f64;
+        let started = Instant::now();
+        let scenarios_before = self.discovery_log.read().await.len();
+        
+        let max_duration = Duration::from_millis(self.config.borrow().max_dream_duration_ms);
+        let completed = tokio::time::timeout(max_duration, self.dream_cycle()).await.is_ok();
+        
+        *self.is_active.write().await = false;
+        
+        Some(DreamCycleReport {
+            trigger: trigger.to_string(),
+            started_at,
+            duration_ms: started.elapsed().as_millis() as u64,
+            completed,
+            patterns: self.dream_patterns.len(),
+            new_scenarios: self.discovery_log.read().await.len().saturating_sub(scenarios_before),
+        })
     }
     
     async fn dream_cycle(&self) {
@@ -383,16 +476,18 @@ impl DreamingModule {
     
     async fn consolidate_patterns(&self) {
         let experiences = self.experience_buffer.read().await;
-        let mut patterns = self.dream_patterns.write().await;
+        // The limit follows configuration reloads
+        self.dream_patterns.set_max_entries(self.config.borrow().max_patterns);
         
         // Extract patterns from recent experiences
         for experience in experiences.iter() {
             let pattern_signature = self.extract_pattern_signature(experience);
             
-            if let Some(existing_pattern) = patterns.get_mut(&pattern_signature) {
+            let reinforced = self.dream_patterns.update(&pattern_signature, |existing_pattern| {
                 existing_pattern.frequency += 1.0;
                 existing_pattern.strength *= 1.1;
-            } else {
+            });
+            if reinforced.is_none() {
                 let new_pattern = DreamPattern {
                     pattern_id: pattern_signature.clone(),
                     pattern_type: format!("{:?}", experience.decision_type),
@@ -401,7 +496,7 @@ impl DreamingModule {
                     associations: HashMap::new(),
                     generated_scenarios: Vec::new(),
                 };
-                patterns.insert(pattern_signature, new_pattern);
+                self.dream_patterns.insert(pattern_signature, new_pattern);
             }
         }
     }
@@ -415,11 +510,11 @@ impl DreamingModule {
     }
     
     async fn generate_novel_scenarios(&self) {
-        let patterns = self.dream_patterns.read().await;
+        let patterns = self.dream_patterns.values();
         let mut discoveries = self.discovery_log.write().await;
         
         // Creative recombination of patterns
-        for (pattern_id, pattern) in patterns.iter() {
+        for pattern in patterns.iter() {
             if pattern.strength > 2.0 {
                 let novel_scenario = self.create_novel_scenario(pattern).await;
                 discoveries.push(novel_scenario);
@@ -466,20 +561,11 @@ impl DreamingModule {
     }
     
     async fn update_pattern_strengths(&self) {
-        let mut patterns = self.dream_patterns.write().await;
-        
-        // Decay pattern strengths over time
-        for pattern in patterns.values_mut() {
+        // Decay pattern strengths over time, removing patterns too weak to matter
+        self.dream_patterns.retain(|_, pattern| {
             pattern.strength *= 0.95;
-            
-            // Remove weak patterns
-            if pattern.strength < 0.1 {
-                pattern.strength = 0.0;
-            }
-        }
-        
-        // Remove inactive patterns
-        patterns.retain(|_, pattern| pattern.strength > 0.0);
+            pattern.strength >= 0.1
+        });
     }
     
     pub async fn incorporate_experience(&self, decision: &MetacognitiveDecision) {
@@ -487,9 +573,11 @@ impl DreamingModule {
         buffer.push(decision.clone());
         
         // Keep buffer size manageable
-        if buffer.len() > 1000 {
+        if buffer.len() > self.config.borrow().experience_buffer_size {
             buffer.remove(0);
         }
+        
+        *self.last_experience_at.write().await = chrono::Utc::now().timestamp() as f64;
     }
     
     pub async fn is_active(&self) -> bool {
@@ -497,24 +585,38 @@ impl DreamingModule {
     }
     
     pub async fn get_discovered_patterns(&self) -> Vec<DreamPattern> {
-        let patterns = self.dream_patterns.read().await;
-        patterns.values().cloned().collect()
+        self.dream_patterns.values()
+    }
+    
+    /// The pattern cache's size and counters, for `/metrics`.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.dream_patterns.stats()
     }
     
     pub async fn get_novel_discoveries(&self) -> Vec<serde_json::Value> {
         let discoveries = self.discovery_log.read().await;
         discoveries.clone()
     }
+    
+    // Most recent scenarios first; `limit` caps how many are returned
+    pub async fn export_scenarios(&self, limit: Option<usize>) -> DreamExport {
+        let discoveries = self.discovery_log.read().await;
+        let limit = limit.unwrap_or(discoveries.len());
+        
+        DreamExport {
+            exported_at: chrono::Utc::now().timestamp() as f64,
+            patterns: self.get_discovered_patterns().await,
+            scenarios: discoveries.iter().rev().take(limit).cloned().collect(),
+        }
+    }
 }
 
 impl Clone for GlycolyticCycle {
     fn clone(&self) -> Self {
         Self {
-            worker_pool: self.worker_pool.clone(),
-            task_queue: self.task_queue.clone(),
+            executor: self.executor.clone(),
             resource_allocation: self.resource_allocation.clone(),
-            current_load: self.current_load.clone(),
-            performance_metrics: self.performance_metrics.clone(),
+            accelerators: self.accelerators.clone(),
         }
     }
 }
@@ -525,6 +627,7 @@ impl Clone for LactateCycle {
             partial_results: self.partial_results.clone(),
             lactate_level: self.lactate_level.clone(),
             cleanup_scheduler: self.cleanup_scheduler.clone(),
+            config: self.config.clone(),
         }
     }
 }
@@ -536,6 +639,9 @@ impl Clone for DreamingModule {
             is_active: self.is_active.clone(),
             experience_buffer: self.experience_buffer.clone(),
             discovery_log: self.discovery_log.clone(),
+            last_experience_at: self.last_experience_at.clone(),
+            glycolytic_cycle: self.glycolytic_cycle.clone(),
+            config: self.config.clone(),
         }
     }
 } 
\ No newline at end of file
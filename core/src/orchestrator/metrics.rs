@@ -0,0 +1,180 @@
+use prometheus::{
+    Encoder, Gauge, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+
+use super::accelerator::DeviceUtilization;
+use super::circuit_breaker::BreakerSnapshot;
+
+type MetricsError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Outcome of a single AI system call, used as the `outcome` label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallOutcome {
+    Success,
+    Failure,
+    Timeout,
+    Skipped,
+}
+
+impl CallOutcome {
+    fn as_label(&self) -> &'static str {
+        match self {
+            CallOutcome::Success => "success",
+            CallOutcome::Failure => "failure",
+            CallOutcome::Timeout => "timeout",
+            CallOutcome::Skipped => "skipped",
+        }
+    }
+}
+
+/// Prometheus view of the orchestrator's metabolic and layer state.
+///
+/// Per-call series are recorded as work happens; the metabolic gauges are
+/// refreshed from the cycles when the registry is scraped.
+pub struct OrchestratorMetrics {
+    registry: Registry,
+    glycolytic_load: Gauge,
+    lactate_level: Gauge,
+    dreaming_active: IntGauge,
+    dream_patterns: IntGauge,
+    layer_latency: HistogramVec,
+    ai_system_calls: IntCounterVec,
+    ai_system_latency: HistogramVec,
+    ai_system_success_rate: GaugeVec,
+    accelerator_utilization: GaugeVec,
+    onnx_inferences: IntCounterVec,
+    onnx_inference_latency: HistogramVec,
+}
+
+impl OrchestratorMetrics {
+    pub fn new() -> Result<Self, MetricsError> {
+        let registry = Registry::new_custom(Some("morphine".to_string()), None)?;
+
+        let glycolytic_load = Gauge::new(
+            "glycolytic_load",
+            "Fraction of glycolytic workers currently busy",
+        )?;
+        let lactate_level = Gauge::new(
+            "lactate_level",
+            "Accumulated partial results awaiting recovery",
+        )?;
+        let dreaming_active = IntGauge::new(
+            "dreaming_active",
+            "1 while the dreaming module is running a cycle",
+        )?;
+        let dream_patterns = IntGauge::new(
+            "dream_patterns",
+            "Number of patterns held by the dreaming module",
+        )?;
+        let layer_latency = HistogramVec::new(
+            HistogramOpts::new("layer_processing_seconds", "Processing latency per metacognitive layer")
+                .buckets(vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5]),
+            &["layer"],
+        )?;
+        let ai_system_calls = IntCounterVec::new(
+            Opts::new("ai_system_calls_total", "AI system calls by outcome"),
+            &["system_id", "outcome"],
+        )?;
+        let ai_system_latency = HistogramVec::new(
+            HistogramOpts::new("ai_system_call_seconds", "AI system call latency, including failures")
+                .buckets(vec![0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]),
+            &["system_id"],
+        )?;
+        let ai_system_success_rate = GaugeVec::new(
+            Opts::new("ai_system_success_rate", "Success rate over the circuit breaker window"),
+            &["system_id"],
+        )?;
+        let accelerator_utilization = GaugeVec::new(
+            Opts::new("accelerator_utilization", "Larger of slot and VRAM utilization per device"),
+            &["device_id"],
+        )?;
+
+        let onnx_inferences = IntCounterVec::new(
+            Opts::new("onnx_inferences_total", "Intuition layer ONNX inferences by outcome; timeouts ran over budget"),
+            &["stream_category", "outcome"],
+        )?;
+        let onnx_inference_latency = HistogramVec::new(
+            HistogramOpts::new("onnx_inference_seconds", "ONNX inference latency, queueing on the glycolytic executor included")
+                .buckets(vec![0.001, 0.0025, 0.005, 0.01, 0.02, 0.05, 0.1, 0.25, 0.5]),
+            &["stream_category"],
+        )?;
+
+        registry.register(Box::new(glycolytic_load.clone()))?;
+        registry.register(Box::new(lactate_level.clone()))?;
+        registry.register(Box::new(dreaming_active.clone()))?;
+        registry.register(Box::new(dream_patterns.clone()))?;
+        registry.register(Box::new(layer_latency.clone()))?;
+        registry.register(Box::new(ai_system_calls.clone()))?;
+        registry.register(Box::new(ai_system_latency.clone()))?;
+        registry.register(Box::new(ai_system_success_rate.clone()))?;
+        registry.register(Box::new(accelerator_utilization.clone()))?;
+        registry.register(Box::new(onnx_inferences.clone()))?;
+        registry.register(Box::new(onnx_inference_latency.clone()))?;
+
+        Ok(Self {
+            registry,
+            glycolytic_load,
+            lactate_level,
+            dreaming_active,
+            dream_patterns,
+            layer_latency,
+            ai_system_calls,
+            ai_system_latency,
+            ai_system_success_rate,
+            accelerator_utilization,
+            onnx_inferences,
+            onnx_inference_latency,
+        })
+    }
+
+    pub fn observe_layer(&self, layer: &str, seconds: f64) {
+        self.layer_latency.with_label_values(&[layer]).observe(seconds);
+    }
+
+    pub fn observe_ai_call(&self, system_id: &str, outcome: CallOutcome, seconds: Option<f64>) {
+        self.ai_system_calls
+            .with_label_values(&[system_id, outcome.as_label()])
+            .inc();
+        if let Some(seconds) = seconds {
+            self.ai_system_latency.with_label_values(&[system_id]).observe(seconds);
+        }
+    }
+
+    pub fn observe_onnx_inference(&self, stream_category: &str, outcome: CallOutcome, seconds: Option<f64>) {
+        self.onnx_inferences
+            .with_label_values(&[stream_category, outcome.as_label()])
+            .inc();
+        if let Some(seconds) = seconds {
+            self.onnx_inference_latency.with_label_values(&[stream_category]).observe(seconds);
+        }
+    }
+
+    pub fn set_metabolic_state(&self, glycolytic_load: f64, lactate_level: f64, dreaming_active: bool, dream_patterns: usize) {
+        self.glycolytic_load.set(glycolytic_load);
+        self.lactate_level.set(lactate_level);
+        self.dreaming_active.set(dreaming_active as i64);
+        self.dream_patterns.set(dream_patterns as i64);
+    }
+
+    pub fn set_breaker_snapshot(&self, system_id: &str, snapshot: &BreakerSnapshot) {
+        self.ai_system_success_rate
+            .with_label_values(&[system_id])
+            .set(1.0 - snapshot.failure_rate);
+    }
+
+    pub fn set_accelerator_utilization(&self, devices: &[DeviceUtilization]) {
+        for device in devices {
+            self.accelerator_utilization
+                .with_label_values(&[&device.device_id])
+                .set(device.utilization);
+        }
+    }
+
+    /// Encodes every registered series in the Prometheus text exposition format.
+    pub fn encode(&self) -> Result<String, MetricsError> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
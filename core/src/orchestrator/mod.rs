@@ -3,12 +3,61 @@ pub mod reasoning;
 pub mod intuition;
 pub mod metabolic;
 pub mod knowledge;
+pub mod connectors;
+pub mod analytics_adapter;
+pub mod circuit_breaker;
+pub mod priority_queue;
+pub mod decision_log;
+pub mod backpressure;
+pub mod metrics;
+pub mod health;
+pub mod admin;
+pub mod feedback;
+pub mod accelerator;
+pub mod knowledge_graph;
+pub mod pattern_models;
+pub mod executor;
+pub mod windowing;
+pub mod alerts;
+pub mod replay;
+pub mod onnx;
+pub mod opportunities;
 
 use std::sync::Arc;
-use std::collections::HashMap;
-use tokio::sync::{mpsc, RwLock, Mutex};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::{broadcast, mpsc, watch, Notify, RwLock, Mutex};
+use tokio_util::sync::CancellationToken;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use tracing::{info, warn};
+
+use crate::analytics::AnalyticsEvent;
+use crate::cache::CacheStats;
+use crate::config::{DreamingConfig, OrchestratorConfig};
+use crate::pagination::{PageRequest, Paginated};
+use crate::error_reporting::{self, ReportContext};
+use crate::latency::{PipelineLatency, Stage, Stamp};
+use crate::replica::ReadRouter;
+use crate::runtime;
+use crate::shutdown::Shutdown;
+use circuit_breaker::CircuitBreaker;
+use priority_queue::{ContextPriority, ContextQueue};
+use decision_log::{DecisionLog, DecisionQuery, DecisionRecord};
+use backpressure::{OverflowPolicy, StreamLagMetrics};
+use metrics::{CallOutcome, OrchestratorMetrics};
+use health::{LifecycleEventKind, ProbeVerdict, SystemHealthState, SystemLifecycleEvent};
+use admin::{AISystemSummary, AdminEvent, StreamQueueSummary};
+use feedback::{DecisionSignals, OutcomeFeedback, WeightAdjustment, WeightLearner};
+use knowledge_graph::{KnowledgeGraph, KnowledgeQuery, KnowledgeQueryResult};
+use windowing::{StreamWindow, WindowPolicy};
+use alerts::{Alert, AlertRouter};
+use replay::{AnalyticsArchive, ArchivedFrame, ReplayComparison, ReplayRegistry, ReplayRequest, ReplayRun, ReplayStatus, ReplayedDecision, REPLAY_MARKER};
+use pattern_models::{NewPatternModel, PatternModelRegistry, PatternModelSnapshot, DEFAULT_CATEGORY};
+use onnx::{OnnxModel, OnnxModels};
+use opportunities::{BettingOpportunity, OpportunityGenerator};
+use executor::TaskError;
+use sqlx::{Pool, Postgres};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamingContext {
@@ -17,6 +66,11 @@ pub struct StreamingContext {
     pub partial_data: HashMap<String, serde_json::Value>,
     pub confidence_level: f64,
     pub processing_stage: ProcessingStage,
+    #[serde(default)]
+    pub priority: ContextPriority,
+    // Set on contexts made from live analytics, for the pipeline latency metrics
+    #[serde(skip)]
+    pub stamp: Option<Stamp>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +115,8 @@ pub struct MetabolicState {
     pub lactate_level: f64,
     pub dreaming_active: bool,
     pub resource_allocation: HashMap<String, f64>,
+    #[serde(default)]
+    pub accelerator_utilization: Vec<accelerator::DeviceUtilization>,
 }
 
 pub struct MetacognitiveOrchestrator {
@@ -76,10 +132,29 @@ pub struct MetacognitiveOrchestrator {
     
     // Knowledge management
     knowledge_base: Arc<knowledge::KnowledgeBase>,
+    knowledge_graph: Arc<KnowledgeGraph>,
+    // Versioned intuition patterns, selected per stream category
+    pattern_models: Arc<PatternModelRegistry>,
+    // ONNX models of the active versions that name one
+    onnx_models: Arc<OnnxModels>,
+    stream_categories: Arc<RwLock<HashMap<String, String>>>,
     
     // Streaming infrastructure
     input_streams: Arc<RwLock<HashMap<String, mpsc::Receiver<StreamingContext>>>>,
     output_streams: Arc<RwLock<HashMap<String, mpsc::Sender<MetacognitiveDecision>>>>,
+    input_senders: Arc<RwLock<HashMap<String, mpsc::Sender<StreamingContext>>>>,
+    overflow_contexts: Arc<Mutex<HashMap<String, StreamingContext>>>,
+    // Routine frames waiting to be batched into a context
+    stream_windows: Arc<Mutex<HashMap<String, StreamWindow>>>,
+    stream_lag: Arc<RwLock<HashMap<String, StreamLagMetrics>>>,
+    decision_topics: Arc<RwLock<HashMap<String, broadcast::Sender<MetacognitiveDecision>>>>,
+    // Paused streams stop draining their input; contexts back up under the overflow policy
+    paused_streams: Arc<RwLock<HashSet<String>>>,
+    stream_resumed: Arc<Notify>,
+    // Cancelling a stream's token aborts its processing loop and any in-flight layer work
+    stream_cancellations: Arc<RwLock<HashMap<String, CancellationToken>>>,
+    // Every stream's decisions, for consumers that watch them all
+    all_decisions: broadcast::Sender<MetacognitiveDecision>,
     
     // State management
     active_contexts: Arc<RwLock<HashMap<String, StreamingContext>>>,
@@ -89,6 +164,31 @@ pub struct MetacognitiveOrchestrator {
     // AI system integration
     ai_systems: Arc<RwLock<HashMap<String, Box<dyn AISystem + Send + Sync>>>>,
     system_weights: Arc<RwLock<HashMap<String, f64>>>,
+    // Registered weights normalized over the systems currently in service
+    effective_weights: Arc<RwLock<HashMap<String, f64>>>,
+    system_health: Arc<RwLock<HashMap<String, SystemHealthState>>>,
+    admin_events: broadcast::Sender<AdminEvent>,
+    alert_router: Arc<AlertRouter>,
+    opportunities: Arc<OpportunityGenerator>,
+    // Markets opened from decisions, once stored
+    opportunity_events: broadcast::Sender<BettingOpportunity>,
+    weight_learner: Arc<WeightLearner>,
+    circuit_breakers: Arc<RwLock<HashMap<String, Arc<CircuitBreaker>>>>,
+    queue_depths: Arc<RwLock<HashMap<String, usize>>>,
+    
+    // Persistent decision audit trail
+    decision_log: Arc<DecisionLog>,
+    // Incoming analytics history, replayable through the pipeline
+    analytics_archive: Arc<AnalyticsArchive>,
+    replays: Arc<ReplayRegistry>,
+    metrics: Arc<OrchestratorMetrics>,
+    latency: Arc<PipelineLatency>,
+    // Unix millis of the window flush loop's last tick, for readiness probes
+    heartbeat: Arc<AtomicI64>,
+    // Stops the background loops and stream processing on shutdown
+    shutdown: Shutdown,
+    
+    config: OrchestratorConfig,
 }
 
 #[async_trait::async_trait]
@@ -97,23 +197,62 @@ pub trait AISystem {
     fn get_confidence(&self, input: &serde_json::Value) -> f64;
     fn get_system_id(&self) -> String;
     fn get_processing_time(&self) -> f64;
+    fn get_capabilities(&self) -> Vec<String> {
+        Vec::new()
+    }
+    // Lightweight liveness probe; systems without a cheap check report healthy
+    async fn health_check(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+    // Device capacity each call needs; None means the system runs on CPU
+    fn accelerator_requirement(&self) -> Option<accelerator::AcceleratorRequirement> {
+        None
+    }
 }
 
 impl MetacognitiveOrchestrator {
-    pub async fn new() -> Self {
-        Self {
+    /// `dreaming` carries reloads of `config.dreaming`; the rest of `config` is fixed at startup.
+    pub async fn new(
+        config: OrchestratorConfig,
+        dreaming: watch::Receiver<DreamingConfig>,
+        db_pool: Pool<Postgres>,
+        reads: Arc<ReadRouter>,
+        shutdown: Shutdown,
+    ) -> Self {
+        let glycolytic_cycle = Arc::new(metabolic::GlycolyticCycle::new(
+            config.accelerators.devices.clone(),
+            config.executor.clone(),
+        ));
+        
+        let orchestrator = Self {
             context_layer: Arc::new(context::ContextLayer::new().await),
             reasoning_layer: Arc::new(reasoning::ReasoningLayer::new().await),
             intuition_layer: Arc::new(intuition::IntuitionLayer::new().await),
             
-            glycolytic_cycle: Arc::new(metabolic::GlycolyticCycle::new()),
-            lactate_cycle: Arc::new(metabolic::LactateCycle::new()),
-            dreaming_module: Arc::new(metabolic::DreamingModule::new()),
+            lactate_cycle: Arc::new(metabolic::LactateCycle::new(config.lactate.clone())),
+            dreaming_module: Arc::new(metabolic::DreamingModule::new(
+                dreaming,
+                glycolytic_cycle.clone(),
+            )),
+            glycolytic_cycle,
             
             knowledge_base: Arc::new(knowledge::KnowledgeBase::new().await),
+            knowledge_graph: Arc::new(KnowledgeGraph::new(config.knowledge_graph.clone())),
+            pattern_models: Arc::new(PatternModelRegistry::new(db_pool.clone())),
+            onnx_models: Arc::new(OnnxModels::new(config.onnx.clone())),
+            stream_categories: Arc::new(RwLock::new(HashMap::new())),
             
             input_streams: Arc::new(RwLock::new(HashMap::new())),
             output_streams: Arc::new(RwLock::new(HashMap::new())),
+            input_senders: Arc::new(RwLock::new(HashMap::new())),
+            overflow_contexts: Arc::new(Mutex::new(HashMap::new())),
+            stream_windows: Arc::new(Mutex::new(HashMap::new())),
+            stream_lag: Arc::new(RwLock::new(HashMap::new())),
+            decision_topics: Arc::new(RwLock::new(HashMap::new())),
+            paused_streams: Arc::new(RwLock::new(HashSet::new())),
+            stream_resumed: Arc::new(Notify::new()),
+            stream_cancellations: Arc::new(RwLock::new(HashMap::new())),
+            all_decisions: broadcast::channel(config.input_streams.channel_capacity).0,
             
             active_contexts: Arc::new(RwLock::new(HashMap::new())),
             pending_decisions: Arc::new(RwLock::new(HashMap::new())),
@@ -121,7 +260,54 @@ impl MetacognitiveOrchestrator {
             
             ai_systems: Arc::new(RwLock::new(HashMap::new())),
             system_weights: Arc::new(RwLock::new(HashMap::new())),
+            effective_weights: Arc::new(RwLock::new(HashMap::new())),
+            system_health: Arc::new(RwLock::new(HashMap::new())),
+            admin_events: broadcast::channel(256).0,
+            alert_router: Arc::new(AlertRouter::new(config.alerts.clone())),
+            opportunities: Arc::new(OpportunityGenerator::new(config.opportunities.clone(), db_pool.clone())),
+            opportunity_events: broadcast::channel(256).0,
+            weight_learner: Arc::new(WeightLearner::new(config.feedback.clone())),
+            circuit_breakers: Arc::new(RwLock::new(HashMap::new())),
+            queue_depths: Arc::new(RwLock::new(HashMap::new())),
+            
+            decision_log: Arc::new(DecisionLog::new(db_pool.clone(), reads.clone())),
+            analytics_archive: Arc::new(AnalyticsArchive::new(db_pool, reads, config.replay.clone())),
+            replays: Arc::new(ReplayRegistry::new(config.replay.clone())),
+            metrics: Arc::new(OrchestratorMetrics::new().expect("orchestrator metric definitions are valid")),
+            latency: Arc::new(PipelineLatency::new().expect("pipeline latency metric definitions are valid")),
+            heartbeat: Arc::new(AtomicI64::new(chrono::Utc::now().timestamp_millis())),
+            shutdown,
+            
+            config,
+        };
+        
+        match orchestrator.pattern_models.load_active().await {
+            Ok(loaded) => info!("Loaded {} active intuition pattern models", loaded),
+            Err(e) => warn!("Failed to load active pattern models: {}", e),
         }
+        orchestrator.load_active_onnx_models().await;
+        
+        orchestrator.analytics_archive.start(&orchestrator.shutdown);
+        
+        // Start lactate recovery loop
+        let recovery = orchestrator.clone();
+        orchestrator.shutdown.spawn_loop("orchestrator:lactate_recovery", async move {
+            recovery.run_lactate_recovery().await;
+        });
+        
+        // Close time-based analytics windows
+        let flusher = orchestrator.clone();
+        orchestrator.shutdown.spawn_loop("orchestrator:window_flush", async move {
+            flusher.run_window_flush().await;
+        });
+        
+        // Start AI system health probes
+        let prober = orchestrator.clone();
+        orchestrator.shutdown.spawn_loop("orchestrator:health_probes", async move {
+            prober.run_health_probes().await;
+        });
+        
+        orchestrator
     }
     
     pub async fn register_ai_system(
@@ -138,62 +324,818 @@ impl MetacognitiveOrchestrator {
         
         {
             let mut weights = self.system_weights.write().await;
-            weights.insert(system_id, weight);
+            weights.insert(system_id.clone(), weight);
         }
         
+        {
+            let mut breakers = self.circuit_breakers.write().await;
+            breakers.insert(
+                system_id.clone(),
+                Arc::new(CircuitBreaker::new(self.config.circuit_breaker.clone())),
+            );
+        }
+        
+        self.system_health.write().await.insert(system_id.clone(), SystemHealthState::default());
+        self.renormalize_weights().await;
+        self.publish_lifecycle(&system_id, LifecycleEventKind::Registered { weight });
+        
         Ok(())
     }
     
+    /// Removes an AI system and redistributes its weight over the remaining ones.
+    /// Returns false if the system was not registered.
+    pub async fn deregister_ai_system(&self, system_id: &str, reason: &str) -> bool {
+        let removed = self.ai_systems.write().await.remove(system_id).is_some();
+        if !removed {
+            return false;
+        }
+        
+        self.system_weights.write().await.remove(system_id);
+        self.circuit_breakers.write().await.remove(system_id);
+        self.system_health.write().await.remove(system_id);
+        self.renormalize_weights().await;
+        
+        warn!("Deregistered AI system {}: {}", system_id, reason);
+        self.publish_lifecycle(system_id, LifecycleEventKind::Deregistered { reason: reason.to_string() });
+        true
+    }
+    
+    /// Scores a decision's layers and AI systems against its confirmed outcome
+    /// and adjusts their weights.
+    pub async fn record_outcome(
+        &self,
+        feedback: OutcomeFeedback,
+    ) -> Result<Vec<WeightAdjustment>, Box<dyn std::error::Error + Send + Sync>> {
+        let signals = match self.weight_learner.signals_for(&feedback.decision_id) {
+            Some(signals) => signals,
+            None => {
+                let decision = self.decision_log.get(&feedback.decision_id).await?
+                    .ok_or("Decision not found")?;
+                DecisionSignals::from_decision(&decision)
+            }
+        };
+        
+        let adjustments = {
+            let mut weights = self.system_weights.write().await;
+            self.weight_learner.apply_feedback(&feedback, &signals, &mut weights)
+        };
+        self.renormalize_weights().await;
+        
+        Ok(adjustments)
+    }
+    
+    pub async fn list_ai_systems(&self) -> Vec<AISystemSummary> {
+        let ai_systems = self.ai_systems.read().await;
+        let weights = self.system_weights.read().await;
+        let effective_weights = self.effective_weights.read().await;
+        let health = self.system_health.read().await;
+        let breakers = self.circuit_breakers.read().await;
+        
+        let mut systems: Vec<AISystemSummary> = ai_systems.iter()
+            .map(|(system_id, system)| AISystemSummary {
+                system_id: system_id.clone(),
+                capabilities: system.get_capabilities(),
+                weight: weights.get(system_id).copied().unwrap_or(0.0),
+                effective_weight: effective_weights.get(system_id).copied().unwrap_or(0.0),
+                health: health.get(system_id).cloned(),
+                circuit_breaker: breakers.get(system_id).map(|breaker| breaker.snapshot()),
+                accelerator: system.accelerator_requirement(),
+            })
+            .collect();
+        systems.sort_by(|a, b| a.system_id.cmp(&b.system_id));
+        systems
+    }
+    
+    /// Overrides a system's registered weight at runtime. Returns the previous weight.
+    pub async fn set_system_weight(
+        &self,
+        system_id: &str,
+        weight: f64,
+    ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+        if !weight.is_finite() || weight < 0.0 {
+            return Err("Weight must be a non-negative number".into());
+        }
+        
+        let previous = {
+            let mut weights = self.system_weights.write().await;
+            let current = weights.get_mut(system_id).ok_or("AI system not registered")?;
+            std::mem::replace(current, weight)
+        };
+        self.renormalize_weights().await;
+        
+        self.publish_lifecycle(system_id, LifecycleEventKind::WeightChanged { previous, weight });
+        Ok(previous)
+    }
+    
+    /// Stops draining a stream's input until it is resumed. Returns false if
+    /// the stream was already paused.
+    pub async fn pause_stream(&self, stream_id: &str) -> bool {
+        self.paused_streams.write().await.insert(stream_id.to_string())
+    }
+    
+    pub async fn resume_stream(&self, stream_id: &str) -> bool {
+        let resumed = self.paused_streams.write().await.remove(stream_id);
+        if resumed {
+            self.stream_resumed.notify_waiters();
+        }
+        resumed
+    }
+    
+    pub async fn queue_summaries(&self) -> Vec<StreamQueueSummary> {
+        let queue_depths = self.queue_depths.read().await;
+        let stream_lag = self.stream_lag.read().await;
+        let paused = self.paused_streams.read().await;
+        
+        let mut stream_ids: Vec<&String> = stream_lag.keys().chain(queue_depths.keys()).collect();
+        stream_ids.sort();
+        stream_ids.dedup();
+        
+        stream_ids.into_iter()
+            .map(|stream_id| StreamQueueSummary {
+                stream_id: stream_id.clone(),
+                queued: queue_depths.get(stream_id).copied().unwrap_or(0),
+                paused: paused.contains(stream_id),
+                lag: stream_lag.get(stream_id).cloned(),
+            })
+            .collect()
+    }
+    
+    pub fn query_knowledge(&self, query: &KnowledgeQuery) -> KnowledgeQueryResult {
+        self.knowledge_graph.query(query)
+    }
+    
+    pub fn recent_alerts(&self, limit: usize) -> Vec<Alert> {
+        self.alert_router.recent(limit)
+    }
+    
+    pub fn weight_history(&self, limit: usize) -> Vec<WeightAdjustment> {
+        self.weight_learner.history(limit)
+    }
+    
+    /// Saves the intuition patterns for a stream category as a new model version.
+    /// Without explicit patterns, the dreaming module's current ones are captured.
+    pub async fn snapshot_pattern_model(
+        &self,
+        stream_category: &str,
+        request: NewPatternModel,
+    ) -> Result<PatternModelSnapshot, Box<dyn std::error::Error + Send + Sync>> {
+        let patterns = match &request.patterns {
+            Some(patterns) => patterns.clone(),
+            None => serde_json::to_value(self.dreaming_module.get_discovered_patterns().await)?,
+        };
+        
+        let snapshot = self.pattern_models.save_snapshot(stream_category, patterns, &request).await?;
+        if request.activate {
+            return self.activate_pattern_model(stream_category, snapshot.version).await;
+        }
+        Ok(snapshot)
+    }
+    
+    pub async fn list_pattern_models(
+        &self,
+        stream_category: &str,
+    ) -> Result<Vec<PatternModelSnapshot>, Box<dyn std::error::Error + Send + Sync>> {
+        self.pattern_models.list_versions(stream_category).await
+    }
+    
+    pub async fn get_pattern_model(
+        &self,
+        stream_category: &str,
+        version: i32,
+    ) -> Result<Option<PatternModelSnapshot>, Box<dyn std::error::Error + Send + Sync>> {
+        self.pattern_models.get_version(stream_category, version).await
+    }
+    
+    /// Switches the category's streams to a specific pattern model version.
+    /// A version naming an ONNX model is loaded and warmed up first, and
+    /// isn't activated if that fails.
+    pub async fn activate_pattern_model(
+        &self,
+        stream_category: &str,
+        version: i32,
+    ) -> Result<PatternModelSnapshot, Box<dyn std::error::Error + Send + Sync>> {
+        let candidate = self.pattern_models.get_version(stream_category, version).await?
+            .ok_or("Pattern model version not found")?;
+        let onnx_model = self.onnx_models.prepare(&candidate).await
+            .map_err(|e| format!("ONNX model failed to load: {}", e))?;
+        
+        let snapshot = self.pattern_models.activate(stream_category, version).await?;
+        self.onnx_models.install(stream_category, onnx_model);
+        self.publish_pattern_model_activation(&snapshot, None);
+        Ok(snapshot)
+    }
+    
+    pub async fn rollback_pattern_model(
+        &self,
+        stream_category: &str,
+    ) -> Result<PatternModelSnapshot, Box<dyn std::error::Error + Send + Sync>> {
+        let previous = self.pattern_models.active_for(stream_category).map(|model| model.version);
+        let snapshot = self.pattern_models.rollback(stream_category).await?;
+        // A rollback goes ahead regardless; the earlier version runs on its patterns alone if need be
+        let onnx_model = match self.onnx_models.prepare(&snapshot).await {
+            Ok(model) => model,
+            Err(e) => {
+                warn!("Rolled back {} to v{} without its ONNX model: {}", stream_category, snapshot.version, e);
+                None
+            }
+        };
+        self.onnx_models.install(stream_category, onnx_model);
+        self.publish_pattern_model_activation(&snapshot, previous);
+        Ok(snapshot)
+    }
+    
+    // At startup; a version whose model fails to load runs on its patterns alone
+    async fn load_active_onnx_models(&self) {
+        for snapshot in self.pattern_models.active() {
+            match self.onnx_models.prepare(&snapshot).await {
+                Ok(Some(model)) => {
+                    info!(
+                        "Loaded ONNX model {} for {} v{}, warm in {}ms",
+                        model.spec.path, snapshot.stream_category, snapshot.version, model.warmup_latency.as_millis()
+                    );
+                    self.onnx_models.install(&snapshot.stream_category, Some(model));
+                }
+                Ok(None) => {}
+                Err(e) => warn!(
+                    "Failed to load the ONNX model of {} v{}: {}",
+                    snapshot.stream_category, snapshot.version, e
+                ),
+            }
+        }
+    }
+    
+    fn publish_pattern_model_activation(&self, snapshot: &PatternModelSnapshot, rolled_back_from: Option<i32>) {
+        let _ = self.admin_events.send(AdminEvent::PatternModelActivated {
+            stream_category: snapshot.stream_category.clone(),
+            model_id: snapshot.model_id.clone(),
+            version: snapshot.version,
+            rolled_back_from,
+            timestamp: backpressure::now_seconds(),
+        });
+    }
+    
+    pub fn subscribe_admin_events(&self) -> broadcast::Receiver<AdminEvent> {
+        self.admin_events.subscribe()
+    }
+    
+    /// For services outside the orchestrator that publish on the admin channel.
+    pub fn admin_event_sender(&self) -> broadcast::Sender<AdminEvent> {
+        self.admin_events.clone()
+    }
+    
+    fn publish_lifecycle(&self, system_id: &str, kind: LifecycleEventKind) {
+        // No subscribers is fine; events are advisory
+        let _ = self.admin_events.send(AdminEvent::SystemLifecycle(SystemLifecycleEvent {
+            system_id: system_id.to_string(),
+            timestamp: backpressure::now_seconds(),
+            kind,
+        }));
+    }
+    
+    // Quarantined systems get no weight; everyone else shares the total proportionally
+    async fn renormalize_weights(&self) {
+        let weights = self.system_weights.read().await;
+        let health = self.system_health.read().await;
+        
+        let in_service: Vec<(&String, f64)> = weights.iter()
+            .filter(|(id, _)| !health.get(*id).map(|h| h.is_quarantined()).unwrap_or(false))
+            .map(|(id, weight)| (id, *weight))
+            .collect();
+        let total: f64 = in_service.iter().map(|(_, weight)| weight).sum();
+        
+        let mut effective = HashMap::new();
+        for (id, weight) in in_service {
+            let normalized = if total > 0.0 { weight / total } else { 0.0 };
+            effective.insert(id.clone(), normalized);
+        }
+        
+        *self.effective_weights.write().await = effective;
+    }
+    
+    async fn run_health_probes(&self) {
+        let probe_config = self.config.health_probe.clone();
+        let probe_timeout = tokio::time::Duration::from_millis(probe_config.probe_timeout_ms);
+        let mut interval = tokio::time::interval(
+            tokio::time::Duration::from_secs(probe_config.probe_interval_secs)
+        );
+        
+        loop {
+            interval.tick().await;
+            let now = backpressure::now_seconds();
+            
+            let due: Vec<String> = {
+                let health = self.system_health.read().await;
+                health.iter()
+                    .filter(|(_, state)| state.due_for_probe(now))
+                    .map(|(id, _)| id.clone())
+                    .collect()
+            };
+            
+            let probes = due.into_iter().map(|system_id| async move {
+                let ai_systems = self.ai_systems.read().await;
+                let result = match ai_systems.get(&system_id) {
+                    Some(system) => match tokio::time::timeout(probe_timeout, system.health_check()).await {
+                        Ok(result) => result.map_err(|e| e.to_string()),
+                        Err(_) => Err("health probe timed out".to_string()),
+                    },
+                    None => return None,
+                };
+                Some((system_id, result))
+            });
+            let results = futures::future::join_all(probes).await;
+            
+            let mut weights_changed = false;
+            for (system_id, result) in results.into_iter().flatten() {
+                let verdict = {
+                    let mut health = self.system_health.write().await;
+                    let state = match health.get_mut(&system_id) {
+                        Some(state) => state,
+                        None => continue,
+                    };
+                    match result {
+                        Ok(()) => state.record_success(now),
+                        Err(e) => state.record_failure(now, e, &probe_config),
+                    }
+                };
+                
+                match verdict {
+                    ProbeVerdict::Unchanged => {}
+                    ProbeVerdict::Quarantined { until } => {
+                        let (consecutive_failures, error) = self.system_health.read().await
+                            .get(&system_id)
+                            .map(|s| (s.consecutive_failures, s.last_error.clone()))
+                            .unwrap_or_default();
+                        warn!("Quarantined AI system {} until {:.0} after {} failed probes", system_id, until, consecutive_failures);
+                        self.publish_lifecycle(&system_id, LifecycleEventKind::Quarantined {
+                            until,
+                            consecutive_failures,
+                            error,
+                        });
+                        weights_changed = true;
+                    }
+                    ProbeVerdict::Recovered => {
+                        self.publish_lifecycle(&system_id, LifecycleEventKind::Recovered);
+                        weights_changed = true;
+                    }
+                    ProbeVerdict::Deregister => {
+                        self.deregister_ai_system(&system_id, "health probes failed repeatedly").await;
+                    }
+                }
+            }
+            
+            if weights_changed {
+                self.renormalize_weights().await;
+            }
+        }
+    }
+    
     pub async fn create_stream(&self, stream_id: String) -> (mpsc::Sender<StreamingContext>, mpsc::Receiver<MetacognitiveDecision>) {
-        let (input_tx, input_rx) = mpsc::channel(1000);
-        let (output_tx, output_rx) = mpsc::channel(1000);
+        let capacity = self.config.input_streams.channel_capacity;
+        let (input_tx, input_rx) = mpsc::channel(capacity);
+        let (output_tx, output_rx) = mpsc::channel(capacity);
         
         {
             let mut input_streams = self.input_streams.write().await;
             input_streams.insert(stream_id.clone(), input_rx);
         }
         
+        {
+            let mut input_senders = self.input_senders.write().await;
+            input_senders.insert(stream_id.clone(), input_tx.clone());
+        }
+        
+        {
+            let mut stream_lag = self.stream_lag.write().await;
+            stream_lag.insert(stream_id.clone(), StreamLagMetrics {
+                channel_capacity: capacity,
+                ..Default::default()
+            });
+        }
+        
         {
             let mut output_streams = self.output_streams.write().await;
             output_streams.insert(stream_id.clone(), output_tx);
         }
         
+        // Replacing a live stream cancels its old processing loop; shutdown cancels them all
+        let cancel = self.shutdown.child_token();
+        if let Some(previous) = self.stream_cancellations.write().await.insert(stream_id.clone(), cancel.clone()) {
+            previous.cancel();
+        }
+        
         // Start processing loop for this stream
         let orchestrator = self.clone();
-        tokio::spawn(async move {
-            orchestrator.process_stream(stream_id).await;
+        runtime::spawn(&format!("orchestrator:stream:{}", stream_id), async move {
+            error_reporting::set_context(ReportContext { stream_id: Some(&stream_id), ..Default::default() });
+            orchestrator.process_stream(stream_id, cancel).await;
         });
         
         (input_tx, output_rx)
     }
     
-    async fn process_stream(&self, stream_id: String) {
+    async fn wait_while_paused(&self, stream_id: &str) {
+        loop {
+            // Register interest before checking so a resume in between isn't missed
+            let resumed = self.stream_resumed.notified();
+            tokio::pin!(resumed);
+            resumed.as_mut().enable();
+            if !self.paused_streams.read().await.contains(stream_id) {
+                return;
+            }
+            resumed.await;
+        }
+    }
+    
+    /// Stops a stream's processing loop, abandoning whatever it is working on.
+    /// Returns false if the stream had no running loop.
+    pub async fn stop_stream(&self, stream_id: &str) -> bool {
+        match self.stream_cancellations.write().await.remove(stream_id) {
+            Some(cancel) => {
+                cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+    
+    async fn process_stream(&self, stream_id: String, cancel: CancellationToken) {
         let mut input_rx = {
             let mut input_streams = self.input_streams.write().await;
-            input_streams.remove(&stream_id).unwrap()
+            match input_streams.remove(&stream_id) {
+                Some(input_rx) => input_rx,
+                // Stopped before the loop got going
+                None => return,
+            }
         };
         
-        while let Some(context) = input_rx.recv().await {
+        let queue_config = self.config.context_queue.clone();
+        let mut queue = ContextQueue::new();
+        let mut input_open = true;
+        
+        loop {
+            tokio::select! {
+                biased;
+                _ = cancel.cancelled() => break,
+                _ = self.wait_while_paused(&stream_id) => {}
+            }
+            
+            // Block only when there is nothing queued
+            if queue.is_empty() {
+                if !input_open {
+                    break;
+                }
+                let received = tokio::select! {
+                    biased;
+                    _ = cancel.cancelled() => break,
+                    received = input_rx.recv() => received,
+                };
+                match received {
+                    Some(context) => queue.push(context),
+                    None => break,
+                }
+            }
+            
+            // Pull in everything already waiting so priorities can take effect
+            loop {
+                match input_rx.try_recv() {
+                    Ok(context) => queue.push(context),
+                    Err(mpsc::error::TryRecvError::Empty) => break,
+                    Err(mpsc::error::TryRecvError::Disconnected) => {
+                        input_open = false;
+                        break;
+                    }
+                }
+            }
+            
+            // Shed routine work instead of falling further behind under load
+            let load = self.glycolytic_cycle.get_current_load().await;
+            if load >= queue_config.shed_load_threshold || queue.len() > queue_config.max_queue_depth {
+                let shed = queue.shed(queue_config.shed_policy);
+                if shed > 0 {
+                    warn!(
+                        "Shed {} routine contexts for stream {} (load {:.2}, total shed {})",
+                        shed, stream_id, load, queue.shed_count()
+                    );
+                }
+            }
+            
+            self.queue_depths.write().await.insert(stream_id.clone(), queue.len());
+            
+            let context = match queue.pop() {
+                Some(context) => context,
+                None => continue,
+            };
+            
+            // Drop routine contexts that went stale while waiting
+            let lag_seconds = (backpressure::now_seconds() - context.timestamp).max(0.0);
+            {
+                let mut stream_lag = self.stream_lag.write().await;
+                let metrics = stream_lag.entry(stream_id.clone()).or_default();
+                metrics.channel_depth = metrics.channel_capacity.saturating_sub(input_rx.capacity());
+                
+                let max_age = self.config.input_streams.max_context_age_ms as f64 / 1000.0;
+                if context.priority == ContextPriority::Routine && lag_seconds > max_age {
+                    metrics.dropped_stale += 1;
+                    continue;
+                }
+                metrics.record_lag(lag_seconds);
+            }
+            
             // Store active context
             {
                 let mut active_contexts = self.active_contexts.write().await;
                 active_contexts.insert(context.stream_id.clone(), context.clone());
             }
             
-            // Process through metacognitive layers
-            let decision = self.process_context(context).await;
+            let mut stamp = context.stamp;
+            if let Some(stamp) = &mut stamp {
+                self.latency.observe(Stage::Queue, stamp.lap());
+            }
+            
+            // Process through metacognitive layers on the glycolytic executor; cancelling
+            // drops the task handle, which aborts the in-flight layer calls
+            let estimated_time = self.glycolytic_cycle.performance_metrics().average_latency_ms / 1000.0;
+            let task = metabolic::Task::for_context(&context, estimated_time);
+            let orchestrator = self.clone();
+            let handle = self.glycolytic_cycle.execute(task, async move {
+                Ok(orchestrator.process_context(context).await)
+            });
+            
+            let decision = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => break,
+                result = handle.join() => match result {
+                    Ok(decision) => decision,
+                    Err(e) => {
+                        warn!("Context processing for stream {} did not complete: {}", stream_id, e);
+                        continue;
+                    }
+                },
+            };
+            
+            if let Some(stamp) = &mut stamp {
+                self.latency.observe(Stage::Orchestration, stamp.lap());
+                self.latency.reached_decision(stamp);
+            }
+            
+            // Publish to live subscribers; no receivers is not an error
+            if let Some(topic) = self.decision_topics.read().await.get(&stream_id) {
+                let _ = topic.send(decision.clone());
+            }
+            let _ = self.all_decisions.send(decision.clone());
+            
+            // Alert-worthy decisions go to operators without holding up the stream
+            if let Some(alert) = self.alert_router.evaluate(&decision) {
+                let _ = self.admin_events.send(AdminEvent::Alert(alert.clone()));
+                let alert_router = self.alert_router.clone();
+                self.shutdown.spawn_tracked("orchestrator:alert_delivery", async move {
+                    alert_router.deliver(&alert).await;
+                });
+            }
+            
+            // Confident predictions open short-lived markets, announced once stored
+            for opportunity in self.opportunities.evaluate(&decision) {
+                let opportunities = self.opportunities.clone();
+                let opportunity_events = self.opportunity_events.clone();
+                self.shutdown.spawn_tracked("orchestrator:market_creation", async move {
+                    match opportunities.create_market(&opportunity).await {
+                        Ok(()) => {
+                            let _ = opportunity_events.send(opportunity);
+                        }
+                        Err(e) => warn!("Failed to open market on {} for stream {}: {}", opportunity.market.event, opportunity.stream_id, e),
+                    }
+                });
+            }
             
             // Send decision if we have an output stream
-            if let Ok(output_streams) = self.output_streams.read().await {
-                if let Some(output_tx) = output_streams.get(&stream_id) {
-                    let _ = output_tx.send(decision).await;
+            let output_streams = self.output_streams.read().await;
+            if let Some(output_tx) = output_streams.get(&stream_id) {
+                let _ = output_tx.send(decision).await;
+            }
+        }
+        
+        // A stopped stream may already have been recreated under the same id; leave its state alone
+        if cancel.is_cancelled() && self.stream_cancellations.read().await.contains_key(&stream_id) {
+            return;
+        }
+        self.stream_cancellations.write().await.remove(&stream_id);
+        
+        self.queue_depths.write().await.remove(&stream_id);
+        // Dropping the topic sender ends every live subscription for the stream
+        self.decision_topics.write().await.remove(&stream_id);
+        self.input_senders.write().await.remove(&stream_id);
+        self.output_streams.write().await.remove(&stream_id);
+        self.stream_lag.write().await.remove(&stream_id);
+        self.active_contexts.write().await.remove(&stream_id);
+        self.overflow_contexts.lock().await.remove(&stream_id);
+        self.stream_windows.lock().await.remove(&stream_id);
+        self.paused_streams.write().await.remove(&stream_id);
+        self.stream_categories.write().await.remove(&stream_id);
+        
+        // Recovery only feeds live streams, so a stopped stream's partials would just pile up
+        if cancel.is_cancelled() {
+            let discarded = self.lactate_cycle.take_partial_results(&stream_id).await.len();
+            info!("Stopped orchestration for stream {} ({} partial results discarded)", stream_id, discarded);
+        }
+    }
+    
+    /// Feeds an analytics payload into the stream's bounded input channel,
+    /// creating the stream's processing loop on first use.
+    pub async fn process_analytics(
+        &self,
+        stream_id: &str,
+        analytics: AnalyticsEvent,
+        stamp: Stamp,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if self.config.replay.archive_analytics {
+            self.analytics_archive.record(stream_id, &analytics);
+        }
+        
+        let context = Self::context_from_analytics(stream_id, analytics, Some(stamp));
+        
+        let existing = self.input_senders.read().await.get(stream_id).cloned();
+        let sender = match existing {
+            Some(sender) => sender,
+            None => {
+                let (sender, _decisions) = self.create_stream(stream_id.to_string()).await;
+                sender
+            }
+        };
+        
+        for context in self.window_context(stream_id, context).await {
+            self.submit_context(stream_id, &sender, context).await;
+        }
+        Ok(())
+    }
+    
+    // Routine frames are batched under the stream's window policy; anything more
+    // urgent flushes the open window and goes straight through
+    async fn window_context(&self, stream_id: &str, context: StreamingContext) -> Vec<StreamingContext> {
+        let load = self.glycolytic_cycle.get_current_load().await;
+        
+        let emitted: Vec<StreamingContext> = {
+            let mut windows = self.stream_windows.lock().await;
+            let window = windows.entry(stream_id.to_string())
+                .or_insert_with(|| StreamWindow::new(self.config.windows.policy()));
+            let policy = window.policy();
+            
+            if !policy.is_batching() {
+                return vec![context];
+            }
+            if context.priority > ContextPriority::Routine || load < policy.min_load {
+                let mut emitted: Vec<StreamingContext> = window.flush().into_iter().collect();
+                emitted.push(context);
+                return emitted;
+            }
+            window.push(context, backpressure::now_seconds()).into_iter().collect()
+        };
+        
+        self.stream_lag.write().await.entry(stream_id.to_string()).or_default().batched_frames += 1;
+        emitted
+    }
+    
+    async fn run_window_flush(&self) {
+        let mut interval = tokio::time::interval(
+            tokio::time::Duration::from_millis(self.config.windows.flush_interval_ms)
+        );
+        
+        loop {
+            interval.tick().await;
+            // This loop ticks every few milliseconds whatever the traffic, so it doubles as the heartbeat
+            self.heartbeat.store(chrono::Utc::now().timestamp_millis(), Ordering::Relaxed);
+            let now = backpressure::now_seconds();
+            
+            let due: Vec<(String, StreamingContext)> = {
+                let mut windows = self.stream_windows.lock().await;
+                windows.iter_mut()
+                    .filter_map(|(stream_id, window)| window.poll(now).map(|context| (stream_id.clone(), context)))
+                    .collect()
+            };
+            
+            for (stream_id, context) in due {
+                let sender = match self.input_senders.read().await.get(&stream_id).cloned() {
+                    Some(sender) => sender,
+                    None => continue,
+                };
+                self.submit_context(&stream_id, &sender, context).await;
+            }
+        }
+    }
+    
+    pub async fn get_stream_window(&self, stream_id: &str) -> WindowPolicy {
+        self.stream_windows.lock().await
+            .get(stream_id)
+            .map(|window| window.policy().clone())
+            .unwrap_or_else(|| self.config.windows.policy())
+    }
+    
+    /// Overrides a stream's window policy. Frames buffered under the old policy
+    /// are flushed as one context first.
+    pub async fn set_stream_window(
+        &self,
+        stream_id: &str,
+        policy: WindowPolicy,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        policy.validate()?;
+        
+        let flushed = self.stream_windows.lock().await
+            .insert(stream_id.to_string(), StreamWindow::new(policy))
+            .and_then(|mut previous| previous.flush());
+        
+        if let Some(context) = flushed {
+            if let Some(sender) = self.input_senders.read().await.get(stream_id).cloned() {
+                self.submit_context(stream_id, &sender, context).await;
+            }
+        }
+        Ok(())
+    }
+    
+    async fn submit_context(
+        &self,
+        stream_id: &str,
+        sender: &mpsc::Sender<StreamingContext>,
+        context: StreamingContext,
+    ) {
+        let policy = self.config.input_streams.overflow_policy;
+        
+        // Flush anything merged during a previous overflow first so ordering holds
+        let mut context = context;
+        if policy == OverflowPolicy::Merge {
+            let mut overflow = self.overflow_contexts.lock().await;
+            if let Some(mut pending) = overflow.remove(stream_id) {
+                backpressure::merge_contexts(&mut pending, context);
+                context = pending;
+            }
+        }
+        
+        let outcome = match sender.try_send(context) {
+            Ok(()) => Ok(()),
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                warn!("Input channel closed for stream {}", stream_id);
+                return;
+            }
+            Err(mpsc::error::TrySendError::Full(context)) => match policy {
+                OverflowPolicy::Drop => Err(false),
+                OverflowPolicy::Merge => {
+                    self.overflow_contexts.lock().await.insert(stream_id.to_string(), context);
+                    Err(true)
+                }
+                OverflowPolicy::Block => {
+                    let timeout = tokio::time::Duration::from_millis(self.config.input_streams.block_timeout_ms);
+                    match sender.send_timeout(context, timeout).await {
+                        Ok(()) => Ok(()),
+                        Err(_) => Err(false),
+                    }
                 }
+            },
+        };
+        
+        let mut stream_lag = self.stream_lag.write().await;
+        let metrics = stream_lag.entry(stream_id.to_string()).or_default();
+        metrics.ingested += 1;
+        match outcome {
+            Ok(()) => {}
+            Err(true) => metrics.merged_overflow += 1,
+            Err(false) => {
+                metrics.dropped_overflow += 1;
+                warn!("Dropped analytics context for stream {}: input channel full", stream_id);
             }
         }
     }
     
+    fn context_from_analytics(stream_id: &str, analytics: AnalyticsEvent, stamp: Option<Stamp>) -> StreamingContext {
+        let timestamp = analytics.timestamp().unwrap_or_else(backpressure::now_seconds);
+        let confidence_level = analytics.confidence().unwrap_or(0.5);
+        let priority = analytics.priority().unwrap_or_default();
+        let partial_data = analytics.into_fields().into_iter().collect();
+        
+        StreamingContext {
+            stream_id: stream_id.to_string(),
+            timestamp,
+            partial_data,
+            confidence_level,
+            processing_stage: ProcessingStage::Context,
+            priority,
+            stamp,
+        }
+    }
+    
     async fn process_context(&self, mut context: StreamingContext) -> MetacognitiveDecision {
         let decision_id = Uuid::new_v4().to_string();
+        let replaying = replay::is_replay(&context);
+        
+        // Fold in partial results left by earlier low-confidence decisions on this stream
+        let mut attempts = context.partial_data.get("lactate_attempts")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as u32;
+        if self.lactate_cycle.config().merge_on_new_evidence && !replaying {
+            let partials = self.lactate_cycle.take_partial_results(&context.stream_id).await;
+            if !partials.is_empty() {
+                attempts = attempts.max(merge_partial_results(&mut context, partials));
+            }
+        }
+        let inputs = context.partial_data.clone();
+        let inputs_hash = DecisionLog::hash_inputs(&context);
         
         // Check metabolic state and allocate resources
         let metabolic_state = self.assess_metabolic_state(&context).await;
@@ -201,9 +1143,9 @@ impl MetacognitiveOrchestrator {
         
         // Process through three layers concurrently with streaming
         let (context_result, reasoning_result, intuition_result) = tokio::join!(
-            self.process_context_layer(&context),
-            self.process_reasoning_layer(&context),
-            self.process_intuition_layer(&context)
+            self.timed_layer("context", self.process_context_layer(&context)),
+            self.timed_layer("reasoning", self.process_reasoning_layer(&context)),
+            self.timed_layer("intuition", self.process_intuition_layer(&context))
         );
         
         // Combine layer outputs with dynamic weighting
@@ -225,30 +1167,210 @@ impl MetacognitiveOrchestrator {
             metabolic_state
         ).await;
         
+        // Replayed decisions are reported to their replay run only; they must not
+        // feed learning, lactate storage or the audit trail
+        if replaying {
+            return decision;
+        }
+        
         // Store in lactate cycle if incomplete
-        if decision.confidence < 0.8 {
-            self.lactate_cycle.store_partial_result(&decision).await;
+        if self.lactate_cycle.should_store(&decision) {
+            self.lactate_cycle.store_partial_result(&decision, &inputs, attempts).await;
         }
         
         // Update dreaming module with new patterns
         self.dreaming_module.incorporate_experience(&decision).await;
         
+        // Keep the signals around so confirmed outcomes can be scored later
+        self.weight_learner.record_signals(&decision);
+        
+        // Persist to the audit log without holding up the stream
+        let decision_log = self.decision_log.clone();
+        let logged_decision = decision.clone();
+        self.shutdown.spawn_tracked("orchestrator:decision_log", async move {
+            if let Err(e) = decision_log.record(&logged_decision, &inputs_hash).await {
+                warn!("Failed to persist decision {}: {}", logged_decision.decision_id, e);
+            }
+        });
+        
         decision
     }
     
+    // Re-queues stored partial results onto their streams whenever load allows
+    async fn run_lactate_recovery(&self) {
+        let lactate_config = self.config.lactate.clone();
+        let mut interval = tokio::time::interval(
+            tokio::time::Duration::from_secs(lactate_config.recovery_interval_secs)
+        );
+        
+        loop {
+            interval.tick().await;
+            
+            let load = self.glycolytic_cycle.get_current_load().await;
+            if load >= lactate_config.recovery_load_threshold {
+                continue;
+            }
+            
+            for stream_id in self.lactate_cycle.streams_with_partials().await {
+                // Only streams with a live input channel can take recovered work
+                let sender = match self.input_senders.read().await.get(&stream_id).cloned() {
+                    Some(sender) => sender,
+                    None => continue,
+                };
+                
+                let partials = self.lactate_cycle.take_partial_results(&stream_id).await;
+                if partials.is_empty() {
+                    continue;
+                }
+                let confidence_level = partials.iter().map(|p| p.confidence).sum::<f64>() / partials.len() as f64;
+                
+                let mut context = StreamingContext {
+                    stream_id: stream_id.clone(),
+                    timestamp: backpressure::now_seconds(),
+                    partial_data: HashMap::new(),
+                    confidence_level,
+                    processing_stage: ProcessingStage::Context,
+                    priority: ContextPriority::Routine,
+                    stamp: None,
+                };
+                let attempts = merge_partial_results(&mut context, partials);
+                context.partial_data.insert("lactate_attempts".to_string(), serde_json::json!(attempts));
+                
+                self.submit_context(&stream_id, &sender, context).await;
+            }
+        }
+    }
+    
+    async fn timed_layer<F>(&self, layer: &str, layer_future: F) -> serde_json::Value
+    where
+        F: std::future::Future<Output = serde_json::Value>,
+    {
+        let started = std::time::Instant::now();
+        let result = layer_future.await;
+        self.metrics.observe_layer(layer, started.elapsed().as_secs_f64());
+        result
+    }
+    
     async fn process_context_layer(&self, context: &StreamingContext) -> serde_json::Value {
-        // Parallel processing of all AI systems for context understanding
+        // Parallel processing of all AI systems for context understanding, each guarded
+        // by its own timeout and circuit breaker so one slow system can't stall the rest
+        let quarantined: Vec<String> = self.system_health.read().await.iter()
+            .filter(|(_, state)| state.is_quarantined())
+            .map(|(id, _)| id.clone())
+            .collect();
         let ai_systems = self.ai_systems.read().await;
-        let mut context_results = HashMap::new();
+        let breakers = self.circuit_breakers.read().await;
+        let accelerators = self.glycolytic_cycle.accelerator_pool();
+        let reservation_timeout = tokio::time::Duration::from_millis(self.config.accelerators.reservation_timeout_ms);
+        
+        let calls = ai_systems.iter().map(|(system_id, system)| {
+            let breaker = breakers.get(system_id).cloned();
+            let quarantined = quarantined.contains(system_id);
+            let accelerators = accelerators.clone();
+            async move {
+                // Quarantined systems count as degraded without being called
+                if quarantined {
+                    self.metrics.observe_ai_call(system_id, CallOutcome::Skipped, None);
+                    return (system_id.clone(), None);
+                }
+                
+                // Accelerated systems only run once a device has capacity for them;
+                // the lease is held until the call finishes
+                let _lease = match system.accelerator_requirement() {
+                    Some(requirement) => match accelerators.reserve(requirement, reservation_timeout).await {
+                        Some(lease) => Some(lease),
+                        None => {
+                            warn!("No accelerator capacity for AI system {}", system_id);
+                            self.metrics.observe_ai_call(system_id, CallOutcome::Skipped, None);
+                            return (system_id.clone(), None);
+                        }
+                    },
+                    None => None,
+                };
+                
+                let started = std::time::Instant::now();
+                let breaker = match breaker {
+                    Some(breaker) => breaker,
+                    None => {
+                        let result = system.process(context).await.ok();
+                        let outcome = if result.is_some() { CallOutcome::Success } else { CallOutcome::Failure };
+                        self.metrics.observe_ai_call(system_id, outcome, Some(started.elapsed().as_secs_f64()));
+                        return (system_id.clone(), result);
+                    }
+                };
+                
+                if !breaker.try_acquire() {
+                    self.metrics.observe_ai_call(system_id, CallOutcome::Skipped, None);
+                    return (system_id.clone(), None);
+                }
+                
+                let (outcome, result) = match tokio::time::timeout(breaker.call_timeout(), system.process(context)).await {
+                    Ok(Ok(result)) => {
+                        breaker.record_success();
+                        (CallOutcome::Success, Some(result))
+                    }
+                    Ok(Err(e)) => {
+                        warn!("AI system {} failed: {}", system_id, e);
+                        breaker.record_failure(false);
+                        (CallOutcome::Failure, None)
+                    }
+                    Err(_) => {
+                        warn!("AI system {} timed out", system_id);
+                        breaker.record_failure(true);
+                        (CallOutcome::Timeout, None)
+                    }
+                };
+                self.metrics.observe_ai_call(system_id, outcome, Some(started.elapsed().as_secs_f64()));
+                (system_id.clone(), result)
+            }
+        });
+        
+        let outcomes = futures::future::join_all(calls).await;
         
-        for (system_id, system) in ai_systems.iter() {
-            if let Ok(result) = system.process(context).await {
-                context_results.insert(system_id.clone(), result);
+        let mut context_results = HashMap::new();
+        let mut system_confidences = HashMap::new();
+        let mut degraded_systems = Vec::new();
+        for (system_id, result) in outcomes {
+            match result {
+                Some(result) => {
+                    if let Some(system) = ai_systems.get(&system_id) {
+                        system_confidences.insert(system_id.clone(), system.get_confidence(&result));
+                    }
+                    context_results.insert(system_id, result);
+                }
+                None => degraded_systems.push(system_id),
             }
         }
         
+        // Ground the context in what the graph already knows about this stream,
+        // then record what this context adds
+        let mut grounded = context.clone();
+        grounded.partial_data.insert("knowledge_grounding".to_string(), self.knowledge_graph.ground_context(context));
+        if !replay::is_replay(context) {
+            self.knowledge_graph.observe_context(context, &system_confidences);
+        }
+        
         // Context layer processing with knowledge integration
-        self.context_layer.process(context, &context_results, &self.knowledge_base).await
+        let mut result = self.context_layer.process(&grounded, &context_results, &self.knowledge_base).await;
+        
+        // Per-system confidences let outcome feedback credit individual systems
+        if let Some(obj) = result.as_object_mut() {
+            obj.insert("system_confidences".to_string(), serde_json::json!(system_confidences));
+        }
+        
+        // Fallback: skipped systems reduce the layer's confidence instead of blocking it
+        if !degraded_systems.is_empty() {
+            let penalty = (self.config.circuit_breaker.fallback_confidence_penalty
+                * degraded_systems.len() as f64)
+                .min(1.0);
+            let confidence = self.extract_confidence(&result) * (1.0 - penalty);
+            if let Some(obj) = result.as_object_mut() {
+                obj.insert("confidence".to_string(), serde_json::json!(confidence));
+                obj.insert("degraded_systems".to_string(), serde_json::json!(degraded_systems));
+            }
+        }
+        
+        result
     }
     
     async fn process_reasoning_layer(&self, context: &StreamingContext) -> serde_json::Value {
@@ -257,8 +1379,91 @@ impl MetacognitiveOrchestrator {
     }
     
     async fn process_intuition_layer(&self, context: &StreamingContext) -> serde_json::Value {
-        // Pattern recognition and predictive modeling
-        self.intuition_layer.process(context, &self.knowledge_base).await
+        // Pattern recognition and predictive modeling, using the category's active model
+        let stream_category = self.resolve_stream_category(context).await;
+        let model = match self.pattern_models.active_for(&stream_category) {
+            Some(model) => model,
+            None => return self.intuition_layer.process(context, &self.knowledge_base).await,
+        };
+        
+        let mut modeled = context.clone();
+        modeled.partial_data.insert("pattern_model".to_string(), serde_json::json!({
+            "model_id": model.model_id,
+            "stream_category": model.stream_category,
+            "version": model.version,
+            "patterns": model.patterns,
+        }));
+        
+        // The version's ONNX predictions, when it has a model and it answers in budget
+        let onnx = match self.onnx_models.for_snapshot(&model) {
+            Some(onnx_model) => {
+                let started = std::time::Instant::now();
+                let model_path = onnx_model.spec.path.clone();
+                let predictions = self.run_onnx_model(onnx_model, context).await;
+                let outcome = match &predictions {
+                    Ok(_) => CallOutcome::Success,
+                    Err(e) if e.is::<metabolic::BudgetExceeded>() => CallOutcome::Timeout,
+                    Err(_) => CallOutcome::Failure,
+                };
+                let elapsed = started.elapsed();
+                self.metrics.observe_onnx_inference(&model.stream_category, outcome, Some(elapsed.as_secs_f64()));
+                Some(match predictions {
+                    Ok(predictions) => {
+                        modeled.partial_data.insert("onnx_predictions".to_string(), serde_json::json!(predictions));
+                        serde_json::json!({
+                            "model": model_path,
+                            "predictions": predictions,
+                            "latency_ms": elapsed.as_secs_f64() * 1000.0,
+                        })
+                    }
+                    Err(e) => {
+                        warn!("ONNX inference for {} on {} failed: {}", model.stream_category, context.stream_id, e);
+                        serde_json::json!({ "model": model_path, "error": e.to_string() })
+                    }
+                })
+            }
+            None => None,
+        };
+        
+        let mut result = self.intuition_layer.process(&modeled, &self.knowledge_base).await;
+        if let Some(fields) = result.as_object_mut() {
+            fields.insert("pattern_model_version".to_string(), serde_json::json!(model.version));
+            if let Some(onnx) = onnx {
+                fields.insert("onnx".to_string(), onnx);
+            }
+        }
+        result
+    }
+    
+    // On the glycolytic executor, under the model's latency budget; inference itself
+    // is CPU-bound, so it runs on a blocking thread that's left to finish if abandoned
+    async fn run_onnx_model(
+        &self,
+        model: Arc<OnnxModel>,
+        context: &StreamingContext,
+    ) -> Result<HashMap<String, f64>, TaskError> {
+        let features = model.features(&context.partial_data);
+        let budget = model.budget;
+        let task = metabolic::Task::for_context(context, budget.as_secs_f64());
+        self.glycolytic_cycle.execute_within(task, budget, async move {
+            tokio::task::spawn_blocking(move || model.run(features)).await?
+        }).await
+    }
+    
+    // Analytics carry the category occasionally; remember it for the frames that don't
+    async fn resolve_stream_category(&self, context: &StreamingContext) -> String {
+        if let Some(category) = context.partial_data.get("stream_category").and_then(|c| c.as_str()) {
+            let known = self.stream_categories.read().await.get(&context.stream_id).map(|c| c == category);
+            if known != Some(true) && !replay::is_replay(context) {
+                self.stream_categories.write().await.insert(context.stream_id.clone(), category.to_string());
+            }
+            return category.to_string();
+        }
+        
+        self.stream_categories.read().await
+            .get(&context.stream_id)
+            .cloned()
+            .unwrap_or_else(|| DEFAULT_CATEGORY.to_string())
     }
     
     async fn assess_metabolic_state(&self, context: &StreamingContext) -> MetabolicState {
@@ -266,12 +1471,14 @@ impl MetacognitiveOrchestrator {
         let lactate_level = self.lactate_cycle.get_lactate_level().await;
         let dreaming_active = self.dreaming_module.is_active().await;
         let resource_allocation = self.glycolytic_cycle.get_resource_allocation().await;
+        let accelerator_utilization = self.glycolytic_cycle.accelerator_pool().utilization();
         
         MetabolicState {
             glycolytic_load,
             lactate_level,
             dreaming_active,
             resource_allocation,
+            accelerator_utilization,
         }
     }
     
@@ -282,12 +1489,16 @@ impl MetacognitiveOrchestrator {
         intuition_result: &serde_json::Value,
         metabolic_state: &MetabolicState
     ) -> LayerContributions {
-        // Dynamic weight calculation based on confidence and metabolic state
-        let context_confidence = self.extract_confidence(context_result);
-        let reasoning_confidence = self.extract_confidence(reasoning_result);
-        let intuition_confidence = self.extract_confidence(intuition_result);
+        // Dynamic weight calculation based on confidence, learned reliability and metabolic state
+        let context_confidence = self.extract_confidence(context_result)
+            * self.weight_learner.layer_multiplier("context");
+        let reasoning_confidence = self.extract_confidence(reasoning_result)
+            * self.weight_learner.layer_multiplier("reasoning");
+        let intuition_confidence = self.extract_confidence(intuition_result)
+            * self.weight_learner.layer_multiplier("intuition");
         
-        let total_confidence = context_confidence + reasoning_confidence + intuition_confidence;
+        let total_confidence = (context_confidence + reasoning_confidence + intuition_confidence)
+            .max(f64::EPSILON);
         
         LayerContributions {
             context_weight: context_confidence / total_confidence,
@@ -361,18 +1572,241 @@ impl MetacognitiveOrchestrator {
             .collect()
     }
     
-    pub async fn get_system_health(&self) -> HashMap<String, serde_json::Value> {
-        let mut health = HashMap::new();
+    pub async fn query_decisions(
+        &self,
+        stream_id: &str,
+        query: &DecisionQuery,
+    ) -> Result<Vec<DecisionRecord>, Box<dyn std::error::Error + Send + Sync>> {
+        self.decision_log.query(stream_id, query).await
+    }
+    
+    /// A page of the stream's archived analytics between `from` and `to`,
+    /// however long ago, oldest first unless sorted by `-timestamp`.
+    pub async fn archived_analytics(
+        &self,
+        stream_id: &str,
+        from: chrono::DateTime<chrono::Utc>,
+        to: chrono::DateTime<chrono::Utc>,
+        page: &PageRequest,
+    ) -> Result<Paginated<ArchivedFrame>, Box<dyn std::error::Error + Send + Sync>> {
+        let descending = page.sort.as_ref().map(|sort| sort.descending).unwrap_or(false);
+        let (frames, total) = self.analytics_archive
+            .query(stream_id, from, to, descending, page.limit, page.offset)
+            .await?;
+        Ok(page.page_of(frames, total))
+    }
+    
+    /// Loads the stream's archived analytics for the window and replays them
+    /// through the three layers in the background.
+    pub async fn start_replay(
+        &self,
+        request: ReplayRequest,
+    ) -> Result<ReplayRun, Box<dyn std::error::Error + Send + Sync>> {
+        request.validate()?;
         
-        // Metabolic health
-        let metabolic_state = MetabolicState {
+        let max_frames = self.replays.config().max_frames;
+        let limit = request.limit.unwrap_or(max_frames).clamp(1, max_frames);
+        let frames = self.analytics_archive.load(&request.stream_id, request.from, request.to, limit).await?;
+        
+        let run = ReplayRun {
+            run_id: Uuid::new_v4().to_string(),
+            stream_id: request.stream_id.clone(),
+            from: request.from,
+            to: request.to,
+            speed: request.speed,
+            status: ReplayStatus::Running,
+            frames_total: frames.len(),
+            frames_replayed: 0,
+            decisions: Vec::new(),
+            comparison: None,
+            error: None,
+            started_at: backpressure::now_seconds(),
+            finished_at: None,
+        };
+        info!("Replaying {} analytics frames for stream {} as run {}", frames.len(), run.stream_id, run.run_id);
+        
+        let cancel = self.replays.start(run.clone());
+        let replayer = self.clone();
+        let run_id = run.run_id.clone();
+        runtime::spawn(&format!("orchestrator:replay:{}", run_id), async move {
+            replayer.run_replay(run_id, request, frames, cancel).await;
+        });
+        
+        Ok(run)
+    }
+    
+    pub fn get_replay(&self, run_id: &str) -> Option<ReplayRun> {
+        self.replays.get(run_id)
+    }
+    
+    pub fn cancel_replay(&self, run_id: &str) -> bool {
+        self.replays.cancel(run_id)
+    }
+    
+    async fn run_replay(
+        &self,
+        run_id: String,
+        request: ReplayRequest,
+        frames: Vec<ArchivedFrame>,
+        cancel: CancellationToken,
+    ) {
+        let mut previous_at: Option<chrono::DateTime<chrono::Utc>> = None;
+        for frame in frames {
+            // Keep the original spacing between frames, compressed by the speed factor
+            if let Some(previous_at) = previous_at.filter(|_| request.speed > 0.0) {
+                let gap = (frame.timestamp - previous_at).to_std().unwrap_or_default().div_f64(request.speed);
+                tokio::select! {
+                    biased;
+                    _ = cancel.cancelled() => break,
+                    _ = tokio::time::sleep(gap) => {}
+                }
+            }
+            previous_at = Some(frame.timestamp);
+            
+            // Frames without their own timestamp are replayed at the time they were archived
+            let has_timestamp = frame.analytics.timestamp().is_some();
+            let mut context = Self::context_from_analytics(&request.stream_id, frame.analytics, None);
+            if !has_timestamp {
+                context.timestamp = frame.timestamp.timestamp_millis() as f64 / 1000.0;
+            }
+            context.partial_data.insert(REPLAY_MARKER.to_string(), serde_json::json!(run_id));
+            
+            let decision = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => break,
+                decision = self.process_context(context) => decision,
+            };
+            let replayed = ReplayedDecision { frame_timestamp: frame.timestamp, decision };
+            self.replays.update(&run_id, |run| {
+                run.frames_replayed += 1;
+                run.decisions.push(replayed);
+            });
+        }
+        
+        if cancel.is_cancelled() {
+            info!("Replay run {} cancelled", run_id);
+            self.replays.finish(&run_id, ReplayStatus::Cancelled, None);
+            return;
+        }
+        
+        // Set the replayed decisions against what the live pipeline decided at the time
+        let query = DecisionQuery {
+            from: Some(request.from.timestamp_millis() as f64 / 1000.0),
+            to: Some(request.to.timestamp_millis() as f64 / 1000.0),
+            limit: Some(1000),
+        };
+        match self.decision_log.query(&request.stream_id, &query).await {
+            Ok(records) => {
+                let original: Vec<&MetacognitiveDecision> = records.iter().map(|r| &r.decision).collect();
+                self.replays.update(&run_id, |run| {
+                    let replayed: Vec<&MetacognitiveDecision> = run.decisions.iter().map(|d| &d.decision).collect();
+                    run.comparison = Some(ReplayComparison::new(&replayed, &original));
+                });
+                self.replays.finish(&run_id, ReplayStatus::Completed, None);
+            }
+            Err(e) => {
+                warn!("Replay run {} finished but original decisions could not be loaded: {}", run_id, e);
+                self.replays.finish(&run_id, ReplayStatus::Completed, Some(format!("Comparison unavailable: {}", e)));
+            }
+        }
+    }
+    
+    pub async fn trigger_dream_cycle(&self) -> Result<metabolic::DreamCycleReport, Box<dyn std::error::Error + Send + Sync>> {
+        self.dreaming_module.trigger_dream().await
+    }
+    
+    pub async fn export_dream_scenarios(&self, limit: Option<usize>) -> metabolic::DreamExport {
+        self.dreaming_module.export_scenarios(limit).await
+    }
+    
+    /// Subscribes to decisions for a stream as they are produced. Subscribing
+    /// before the stream exists is allowed; decisions flow once it starts.
+    pub async fn subscribe_decisions(&self, stream_id: &str) -> broadcast::Receiver<MetacognitiveDecision> {
+        let mut topics = self.decision_topics.write().await;
+        topics.entry(stream_id.to_string())
+            .or_insert_with(|| broadcast::channel(self.config.input_streams.channel_capacity).0)
+            .subscribe()
+    }
+    
+    /// Subscribes to decisions for every stream as they are produced.
+    pub fn subscribe_all_decisions(&self) -> broadcast::Receiver<MetacognitiveDecision> {
+        self.all_decisions.subscribe()
+    }
+    
+    /// Subscribes to markets opened from decisions, on every stream.
+    pub fn subscribe_opportunities(&self) -> broadcast::Receiver<BettingOpportunity> {
+        self.opportunity_events.subscribe()
+    }
+    
+    /// Markets opened on `stream_id` that are still taking bets.
+    pub async fn open_opportunities(&self, stream_id: &str) -> Result<Vec<BettingOpportunity>, sqlx::Error> {
+        self.opportunities.open_markets(stream_id).await
+    }
+    
+    /// The dream pattern cache's size and counters, for `/metrics`.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.dreaming_module.cache_stats()
+    }
+    
+    /// Timing of analytics frames from ingest through to viewers and decisions.
+    pub fn latency(&self) -> &Arc<PipelineLatency> {
+        &self.latency
+    }
+    
+    /// Refreshes the metabolic gauges and renders all metrics for a Prometheus scrape.
+    pub async fn render_metrics(&self) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        self.metrics.set_metabolic_state(
+            self.glycolytic_cycle.get_current_load().await,
+            self.lactate_cycle.get_lactate_level().await,
+            self.dreaming_module.is_active().await,
+            self.dreaming_module.get_discovered_patterns().await.len(),
+        );
+        
+        for (system_id, breaker) in self.circuit_breakers.read().await.iter() {
+            self.metrics.set_breaker_snapshot(system_id, &breaker.snapshot());
+        }
+        self.metrics.set_accelerator_utilization(&self.glycolytic_cycle.accelerator_pool().utilization());
+        
+        Ok(self.metrics.encode()? + &self.latency.encode()?)
+    }
+    
+    pub async fn metabolic_state(&self) -> MetabolicState {
+        MetabolicState {
             glycolytic_load: self.glycolytic_cycle.get_current_load().await,
             lactate_level: self.lactate_cycle.get_lactate_level().await,
             dreaming_active: self.dreaming_module.is_active().await,
             resource_allocation: self.glycolytic_cycle.get_resource_allocation().await,
-        };
+            accelerator_utilization: self.glycolytic_cycle.accelerator_pool().utilization(),
+        }
+    }
+    
+    /// How long since the background loops last made progress.
+    pub fn heartbeat_age(&self) -> std::time::Duration {
+        let last = self.heartbeat.load(Ordering::Relaxed);
+        std::time::Duration::from_millis((chrono::Utc::now().timestamp_millis() - last).max(0) as u64)
+    }
+    
+    /// A heartbeat older than this means the loops are stuck: ten flush
+    /// intervals, but never under five seconds.
+    pub fn heartbeat_stale_after(&self) -> std::time::Duration {
+        std::time::Duration::from_millis((self.config.windows.flush_interval_ms * 10).max(5000))
+    }
+    
+    pub async fn ai_system_count(&self) -> usize {
+        self.ai_systems.read().await.len()
+    }
+    
+    pub async fn get_system_health(&self) -> HashMap<String, serde_json::Value> {
+        let mut health = HashMap::new();
+        
+        // Metabolic health
+        let metabolic_state = self.metabolic_state().await;
         
         health.insert("metabolic_state".to_string(), serde_json::to_value(metabolic_state).unwrap());
+        health.insert("glycolytic_executor".to_string(), serde_json::json!({
+            "metrics": self.glycolytic_cycle.performance_metrics(),
+            "workers": self.glycolytic_cycle.workers(),
+        }));
         health.insert("active_streams".to_string(), serde_json::Value::Number(
             self.active_contexts.read().await.len().into()
         ));
@@ -383,10 +1817,53 @@ impl MetacognitiveOrchestrator {
             ai_systems.len().into()
         ));
         
+        // Circuit breaker state per AI system
+        let breakers = self.circuit_breakers.read().await;
+        let breaker_states: HashMap<String, circuit_breaker::BreakerSnapshot> = breakers.iter()
+            .map(|(system_id, breaker)| (system_id.clone(), breaker.snapshot()))
+            .collect();
+        health.insert("circuit_breakers".to_string(), serde_json::to_value(breaker_states).unwrap_or_default());
+        
+        let system_health = self.system_health.read().await.clone();
+        health.insert("ai_system_health".to_string(), serde_json::to_value(system_health).unwrap_or_default());
+        
+        health.insert("layer_multipliers".to_string(), serde_json::to_value(self.weight_learner.layer_multipliers()).unwrap_or_default());
+        
+        let effective_weights = self.effective_weights.read().await.clone();
+        health.insert("effective_weights".to_string(), serde_json::to_value(effective_weights).unwrap_or_default());
+        
+        let queue_depths = self.queue_depths.read().await.clone();
+        health.insert("queue_depths".to_string(), serde_json::to_value(queue_depths).unwrap_or_default());
+        
+        let stream_lag = self.stream_lag.read().await.clone();
+        health.insert("input_streams".to_string(), serde_json::to_value(stream_lag).unwrap_or_default());
+        
         health
     }
 }
 
+// Newer inputs win; stored evidence is attached so the layers can build on it.
+// Returns the retry attempt number the merged context represents.
+fn merge_partial_results(context: &mut StreamingContext, partials: Vec<metabolic::PartialResult>) -> u32 {
+    let mut attempts = 0;
+    let mut recovered = Vec::with_capacity(partials.len());
+    
+    for partial in partials {
+        for (key, value) in partial.inputs {
+            context.partial_data.entry(key).or_insert(value);
+        }
+        attempts = attempts.max(partial.attempts + 1);
+        recovered.push(serde_json::json!({
+            "decision_id": partial.task_id,
+            "confidence": partial.confidence,
+            "evidence": partial.partial_data,
+        }));
+    }
+    
+    context.partial_data.insert("recovered_partials".to_string(), serde_json::json!(recovered));
+    attempts
+}
+
 impl Clone for MetacognitiveOrchestrator {
     fn clone(&self) -> Self {
         Self {
@@ -397,13 +1874,42 @@ impl Clone for MetacognitiveOrchestrator {
             lactate_cycle: self.lactate_cycle.clone(),
             dreaming_module: self.dreaming_module.clone(),
             knowledge_base: self.knowledge_base.clone(),
+            knowledge_graph: self.knowledge_graph.clone(),
+            pattern_models: self.pattern_models.clone(),
+            onnx_models: self.onnx_models.clone(),
+            stream_categories: self.stream_categories.clone(),
             input_streams: self.input_streams.clone(),
             output_streams: self.output_streams.clone(),
+            input_senders: self.input_senders.clone(),
+            overflow_contexts: self.overflow_contexts.clone(),
+            stream_windows: self.stream_windows.clone(),
+            stream_lag: self.stream_lag.clone(),
+            decision_topics: self.decision_topics.clone(),
+            paused_streams: self.paused_streams.clone(),
+            stream_resumed: self.stream_resumed.clone(),
+            stream_cancellations: self.stream_cancellations.clone(),
+            all_decisions: self.all_decisions.clone(),
             active_contexts: self.active_contexts.clone(),
             pending_decisions: self.pending_decisions.clone(),
             processing_queue: self.processing_queue.clone(),
             ai_systems: self.ai_systems.clone(),
             system_weights: self.system_weights.clone(),
+            effective_weights: self.effective_weights.clone(),
+            system_health: self.system_health.clone(),
+            admin_events: self.admin_events.clone(),
+            alert_router: self.alert_router.clone(),
+            opportunities: self.opportunities.clone(),
+            opportunity_events: self.opportunity_events.clone(),
+            weight_learner: self.weight_learner.clone(),
+            circuit_breakers: self.circuit_breakers.clone(),
+            queue_depths: self.queue_depths.clone(),
+            decision_log: self.decision_log.clone(),
+            analytics_archive: self.analytics_archive.clone(),
+            replays: self.replays.clone(),
+            metrics: self.metrics.clone(),
+            heartbeat: self.heartbeat.clone(),
+            shutdown: self.shutdown.clone(),
+            config: self.config.clone(),
         }
     }
 } 
\ No newline at end of file
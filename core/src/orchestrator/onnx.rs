@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tract_onnx::prelude::*;
+use utoipa::ToSchema;
+
+use super::pattern_models::PatternModelSnapshot;
+use crate::config::OnnxConfig;
+use crate::validation::{Validate, ValidationErrors};
+
+pub type OnnxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The ONNX model a pattern model version runs, and how frame fields map
+/// onto its input and its output onto named predictions. The model takes one
+/// `[1, inputs]` float tensor and returns one value per output.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OnnxModelSpec {
+    // Relative to `[orchestrator.onnx] model_dir`
+    pub path: String,
+    // Hex SHA-256 of the file; a file that doesn't match isn't loaded
+    pub sha256: String,
+    // Numeric fields of the analytics frame, in the model's input order; missing ones are 0
+    pub inputs: Vec<String>,
+    // What each output value predicts, e.g. the event whose probability it is
+    pub outputs: Vec<String>,
+    // Overrides `default_budget_ms` for this model
+    pub latency_budget_ms: Option<u64>,
+}
+
+impl Validate for OnnxModelSpec {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("path", &self.path);
+        if Path::new(&self.path).components().any(|part| !matches!(part, Component::Normal(_))) {
+            errors.add("path", "must be a relative path within the model directory");
+        }
+        if self.sha256.len() != 64 || !self.sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+            errors.add("sha256", "must be a hex SHA-256 digest");
+        }
+        if self.inputs.is_empty() || self.inputs.iter().any(|input| input.trim().is_empty()) {
+            errors.add("inputs", "must be a non-empty list of field names");
+        }
+        if self.outputs.is_empty() || self.outputs.iter().any(|output| output.trim().is_empty()) {
+            errors.add("outputs", "must be a non-empty list of names");
+        }
+        if self.latency_budget_ms == Some(0) {
+            errors.add("latency_budget_ms", "must be greater than zero");
+        }
+    }
+}
+
+type Plan = TypedRunnableModel<TypedModel>;
+
+/// A loaded, warmed-up model, ready to run for its pattern model version.
+pub struct OnnxModel {
+    pub model_id: String,
+    pub spec: OnnxModelSpec,
+    pub budget: Duration,
+    // Slowest warmup inference
+    pub warmup_latency: Duration,
+    plan: Arc<Plan>,
+}
+
+impl OnnxModel {
+    /// The frame fields the model reads, in input order.
+    pub fn features(&self, fields: &HashMap<String, serde_json::Value>) -> Vec<f32> {
+        self.spec.inputs.iter()
+            .map(|input| fields.get(input).and_then(|value| value.as_f64()).unwrap_or(0.0) as f32)
+            .collect()
+    }
+
+    /// Runs one inference on the calling thread; callers schedule it.
+    pub fn run(&self, features: Vec<f32>) -> Result<HashMap<String, f64>, OnnxError> {
+        infer(&self.plan, features, &self.spec.outputs)
+    }
+}
+
+/// The ONNX models of the active pattern model versions, one per stream category.
+pub struct OnnxModels {
+    config: OnnxConfig,
+    loaded: RwLock<HashMap<String, Arc<OnnxModel>>>,
+}
+
+impl OnnxModels {
+    pub fn new(config: OnnxConfig) -> Self {
+        Self {
+            config,
+            loaded: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Loads, checks and warms up the model `snapshot` names, off the async
+    /// runtime. Fails if the file doesn't match its digest, the model doesn't
+    /// take and return what the spec says, or warmup runs over budget.
+    pub async fn prepare(&self, snapshot: &PatternModelSnapshot) -> Result<Option<Arc<OnnxModel>>, OnnxError> {
+        let Some(spec) = snapshot.onnx.clone() else {
+            return Ok(None);
+        };
+        if let Some(loaded) = self.loaded.read().get(&snapshot.stream_category) {
+            if loaded.model_id == snapshot.model_id {
+                return Ok(Some(loaded.clone()));
+            }
+        }
+
+        let path = PathBuf::from(&self.config.model_dir).join(&spec.path);
+        let max_bytes = self.config.max_model_bytes;
+        let warmup_runs = self.config.warmup_runs;
+        let budget = Duration::from_millis(spec.latency_budget_ms.unwrap_or(self.config.default_budget_ms));
+        let model_id = snapshot.model_id.clone();
+
+        tokio::task::spawn_blocking(move || -> Result<Arc<OnnxModel>, OnnxError> {
+            let size = std::fs::metadata(&path)?.len();
+            if size > max_bytes {
+                return Err(format!("{} is {} bytes, over the {} byte limit", path.display(), size, max_bytes).into());
+            }
+            let bytes = std::fs::read(&path)?;
+            let digest = hex::encode(Sha256::digest(&bytes));
+            if !digest.eq_ignore_ascii_case(&spec.sha256) {
+                return Err(format!("{} has SHA-256 {}, not {}", path.display(), digest, spec.sha256).into());
+            }
+
+            let plan = tract_onnx::onnx()
+                .model_for_read(&mut std::io::Cursor::new(bytes))?
+                .with_input_fact(0, f32::fact([1, spec.inputs.len()]).into())?
+                .into_optimized()?
+                .into_runnable()?;
+
+            let mut warmup_latency = Duration::ZERO;
+            for _ in 0..warmup_runs {
+                let started = Instant::now();
+                infer(&plan, vec![0.0; spec.inputs.len()], &spec.outputs)?;
+                warmup_latency = warmup_latency.max(started.elapsed());
+            }
+            if warmup_latency > budget {
+                return Err(format!(
+                    "Warmup inference took {}ms, over the {}ms budget",
+                    warmup_latency.as_millis(),
+                    budget.as_millis()
+                ).into());
+            }
+
+            Ok(Arc::new(OnnxModel { model_id, spec, budget, warmup_latency, plan: Arc::new(plan) }))
+        })
+        .await?
+        .map(Some)
+    }
+
+    /// Makes `model` the one run for `stream_category`, or stops running one
+    /// there if None.
+    pub fn install(&self, stream_category: &str, model: Option<Arc<OnnxModel>>) {
+        let mut loaded = self.loaded.write();
+        match model {
+            Some(model) => loaded.insert(stream_category.to_string(), model),
+            None => loaded.remove(stream_category),
+        };
+    }
+
+    /// The loaded model for `snapshot`, if it names one and it's the version loaded.
+    pub fn for_snapshot(&self, snapshot: &PatternModelSnapshot) -> Option<Arc<OnnxModel>> {
+        self.loaded.read()
+            .get(&snapshot.stream_category)
+            .filter(|model| model.model_id == snapshot.model_id)
+            .cloned()
+    }
+}
+
+fn infer(plan: &Plan, features: Vec<f32>, outputs: &[String]) -> Result<HashMap<String, f64>, OnnxError> {
+    let input = tract_ndarray::Array2::from_shape_vec((1, features.len()), features)?.into_tensor();
+    let result = plan.run(tvec!(input.into()))?;
+    let values = result.first().ok_or("Model produced no output")?.to_array_view::<f32>()?;
+    if values.len() != outputs.len() {
+        return Err(format!("Model produced {} values for {} outputs", values.len(), outputs.len()).into());
+    }
+    Ok(outputs.iter().cloned().zip(values.iter().map(|value| *value as f64)).collect())
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres, Row};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::MetacognitiveDecision;
+use crate::betting::BetType;
+use crate::config::OpportunityConfig;
+
+// Lowest odds worth offering; below this a winning bet barely returns its stake
+const MIN_ODDS: f64 = 1.01;
+
+/// What a market offers: a yes/no bet on an event happening within the window.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MarketTemplate {
+    // As the pattern model version's ONNX model names its output
+    pub event: String,
+    #[schema(value_type = String)]
+    pub bet_type: BetType,
+    pub time_window_seconds: u64,
+    pub title: String,
+}
+
+/// A market opened from a confident prediction, taking bets until `closes_at`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BettingOpportunity {
+    pub market_id: String,
+    pub stream_id: String,
+    // The decision whose prediction opened it
+    pub decision_id: String,
+    pub market: MarketTemplate,
+    // The model's probability that the event happens within the window
+    pub probability: f64,
+    // Fair odds less the configured margin; placement prices bets itself
+    pub suggested_odds: f64,
+    pub confidence: f64,
+    pub opens_at: DateTime<Utc>,
+    pub closes_at: DateTime<Utc>,
+}
+
+/// Turns confident decisions carrying event predictions into time-boxed
+/// markets, at most one open per stream and event at a time.
+pub struct OpportunityGenerator {
+    config: OpportunityConfig,
+    db_pool: Pool<Postgres>,
+    // When the open market for each stream and event closes
+    open: Mutex<HashMap<(String, String), DateTime<Utc>>>,
+}
+
+impl OpportunityGenerator {
+    pub fn new(config: OpportunityConfig, db_pool: Pool<Postgres>) -> Self {
+        Self {
+            config,
+            db_pool,
+            open: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The markets `decision` opens: one per predicted event whose
+    /// probability is in range, if the decision is confident enough. Events
+    /// with a market still open are skipped.
+    pub fn evaluate(&self, decision: &MetacognitiveDecision) -> Vec<BettingOpportunity> {
+        if !self.config.enabled || decision.confidence < self.config.min_confidence {
+            return Vec::new();
+        }
+        // The active pattern model version's ONNX outputs, event name to probability
+        let Some(predictions) = decision.evidence.get("intuition")
+            .and_then(|intuition| intuition.pointer("/onnx/predictions"))
+            .and_then(|predictions| predictions.as_object())
+        else {
+            return Vec::new();
+        };
+
+        let now = Utc::now();
+        let window = self.config.window_secs;
+        let mut open = self.open.lock();
+        open.retain(|_, closes_at| *closes_at > now);
+
+        let mut opportunities = Vec::new();
+        for (event, probability) in predictions {
+            let Some(probability) = probability.as_f64() else {
+                continue;
+            };
+            if !(self.config.min_probability..=self.config.max_probability).contains(&probability) {
+                continue;
+            }
+            let key = (decision.stream_id.clone(), event.clone());
+            if open.contains_key(&key) {
+                continue;
+            }
+
+            let closes_at = now + Duration::seconds(window as i64);
+            open.insert(key, closes_at);
+            opportunities.push(BettingOpportunity {
+                market_id: Uuid::new_v4().to_string(),
+                stream_id: decision.stream_id.clone(),
+                decision_id: decision.decision_id.clone(),
+                market: MarketTemplate {
+                    event: event.clone(),
+                    bet_type: BetType::Binary,
+                    time_window_seconds: window,
+                    title: format!("Will {} happen in the next {} seconds?", event.replace('_', " "), window),
+                },
+                probability,
+                suggested_odds: (1.0 / probability * (1.0 - self.config.margin)).max(MIN_ODDS),
+                confidence: decision.confidence,
+                opens_at: now,
+                closes_at,
+            });
+        }
+        opportunities
+    }
+
+    /// Stores the market `opportunity` describes, in its stream's tenant.
+    pub async fn create_market(&self, opportunity: &BettingOpportunity) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO betting_markets (
+                market_id, tenant_id, stream_id, decision_id, event, bet_type, title,
+                time_window_seconds, probability, suggested_odds, confidence, opens_at, closes_at
+            )
+            SELECT $1, COALESCE((SELECT tenant_id FROM streams WHERE id = $2), 'default'),
+                $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12
+            "#
+        )
+        .bind(&opportunity.market_id)
+        .bind(&opportunity.stream_id)
+        .bind(&opportunity.decision_id)
+        .bind(&opportunity.market.event)
+        .bind(opportunity.market.bet_type.as_str())
+        .bind(&opportunity.market.title)
+        .bind(opportunity.market.time_window_seconds as i64)
+        .bind(opportunity.probability)
+        .bind(opportunity.suggested_odds)
+        .bind(opportunity.confidence)
+        .bind(opportunity.opens_at)
+        .bind(opportunity.closes_at)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// `stream_id`'s markets that haven't closed yet, closing soonest first.
+    pub async fn open_markets(&self, stream_id: &str) -> Result<Vec<BettingOpportunity>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT market_id, stream_id, decision_id, event, bet_type, title, time_window_seconds,
+                probability, suggested_odds, confidence, opens_at, closes_at
+            FROM betting_markets
+            WHERE stream_id = $1 AND closes_at > NOW()
+            ORDER BY closes_at
+            "#
+        )
+        .bind(stream_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows.iter()
+            .filter_map(|row| {
+                let bet_type = row.get::<String, _>("bet_type").parse().ok()?;
+                Some(BettingOpportunity {
+                    market_id: row.get("market_id"),
+                    stream_id: row.get("stream_id"),
+                    decision_id: row.get("decision_id"),
+                    market: MarketTemplate {
+                        event: row.get("event"),
+                        bet_type,
+                        time_window_seconds: row.get::<i64, _>("time_window_seconds").max(0) as u64,
+                        title: row.get("title"),
+                    },
+                    probability: row.get("probability"),
+                    suggested_odds: row.get("suggested_odds"),
+                    confidence: row.get("confidence"),
+                    opens_at: row.get("opens_at"),
+                    closes_at: row.get("closes_at"),
+                })
+            })
+            .collect())
+    }
+}
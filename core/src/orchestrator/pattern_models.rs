@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use sqlx::{Pool, Postgres, Row};
+use uuid::Uuid;
+
+use super::onnx::OnnxModelSpec;
+use crate::validation::{Validate, ValidationErrors};
+
+type PatternModelError = Box<dyn std::error::Error + Send + Sync>;
+
+pub const DEFAULT_CATEGORY: &str = "default";
+
+/// A saved, versioned copy of the intuition layer's learned patterns for one
+/// stream category.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternModelSnapshot {
+    pub model_id: String,
+    pub stream_category: String,
+    pub version: i32,
+    pub patterns: serde_json::Value,
+    pub training_window_start: Option<DateTime<Utc>>,
+    pub training_window_end: Option<DateTime<Utc>>,
+    pub metrics: serde_json::Value,
+    pub notes: Option<String>,
+    // Run by the intuition layer alongside the patterns while this version is active
+    pub onnx: Option<OnnxModelSpec>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct NewPatternModel {
+    // Defaults to the dreaming module's current patterns when omitted
+    pub patterns: Option<serde_json::Value>,
+    pub training_window_start: Option<DateTime<Utc>>,
+    pub training_window_end: Option<DateTime<Utc>>,
+    pub metrics: Option<serde_json::Value>,
+    pub notes: Option<String>,
+    pub onnx: Option<OnnxModelSpec>,
+    #[serde(default)]
+    pub activate: bool,
+}
+
+impl Validate for NewPatternModel {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        if let (Some(start), Some(end)) = (self.training_window_start, self.training_window_end) {
+            if end < start {
+                errors.add("training_window_end", "must not be before training_window_start");
+            }
+        }
+        if self.patterns.as_ref().map(|p| p.is_null()).unwrap_or(false) {
+            errors.add("patterns", "must not be null; omit it to use the current patterns");
+        }
+        if let Some(onnx) = &self.onnx {
+            errors.nested("onnx", onnx);
+        }
+    }
+}
+
+/// Postgres-backed registry of pattern model versions, with the active version
+/// per stream category cached for the hot path.
+pub struct PatternModelRegistry {
+    db_pool: Pool<Postgres>,
+    active: RwLock<HashMap<String, Arc<PatternModelSnapshot>>>,
+}
+
+impl PatternModelRegistry {
+    pub fn new(db_pool: Pool<Postgres>) -> Self {
+        Self {
+            db_pool,
+            active: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Fills the active-version cache from the database, e.g. at startup.
+    pub async fn load_active(&self) -> Result<usize, PatternModelError> {
+        let rows = sqlx::query(&format!("{} WHERE is_active", SELECT_SNAPSHOT))
+            .fetch_all(&self.db_pool)
+            .await?;
+
+        let mut active = HashMap::new();
+        for row in rows {
+            let snapshot = snapshot_from_row(&row);
+            active.insert(snapshot.stream_category.clone(), Arc::new(snapshot));
+        }
+
+        let loaded = active.len();
+        *self.active.write() = active;
+        Ok(loaded)
+    }
+
+    pub fn active_for(&self, stream_category: &str) -> Option<Arc<PatternModelSnapshot>> {
+        let active = self.active.read();
+        active.get(stream_category)
+            .or_else(|| active.get(DEFAULT_CATEGORY))
+            .cloned()
+    }
+
+    /// The active version of every category that has one.
+    pub fn active(&self) -> Vec<Arc<PatternModelSnapshot>> {
+        self.active.read().values().cloned().collect()
+    }
+
+    /// Saves a new version for the category, numbered after the latest one.
+    pub async fn save_snapshot(
+        &self,
+        stream_category: &str,
+        patterns: serde_json::Value,
+        request: &NewPatternModel,
+    ) -> Result<PatternModelSnapshot, PatternModelError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        // Serialize version allocation per category
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(stream_category)
+            .execute(&mut *tx)
+            .await?;
+
+        let version: i32 = sqlx::query_scalar(
+            "SELECT COALESCE(MAX(version), 0) + 1 FROM intuition_pattern_models WHERE stream_category = $1"
+        )
+        .bind(stream_category)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO intuition_pattern_models (
+                model_id, stream_category, version, patterns,
+                training_window_start, training_window_end, metrics, notes, onnx
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING {}
+            "#,
+            SNAPSHOT_COLUMNS
+        ))
+        .bind(Uuid::new_v4().to_string())
+        .bind(stream_category)
+        .bind(version)
+        .bind(&patterns)
+        .bind(request.training_window_start)
+        .bind(request.training_window_end)
+        .bind(request.metrics.clone().unwrap_or_else(|| serde_json::json!({})))
+        .bind(&request.notes)
+        .bind(request.onnx.as_ref().map(serde_json::to_value).transpose()?)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(snapshot_from_row(&row))
+    }
+
+    // Newest first
+    pub async fn list_versions(&self, stream_category: &str) -> Result<Vec<PatternModelSnapshot>, PatternModelError> {
+        let rows = sqlx::query(&format!(
+            "{} WHERE stream_category = $1 ORDER BY version DESC",
+            SELECT_SNAPSHOT
+        ))
+        .bind(stream_category)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows.iter().map(snapshot_from_row).collect())
+    }
+
+    pub async fn get_version(
+        &self,
+        stream_category: &str,
+        version: i32,
+    ) -> Result<Option<PatternModelSnapshot>, PatternModelError> {
+        let row = sqlx::query(&format!(
+            "{} WHERE stream_category = $1 AND version = $2",
+            SELECT_SNAPSHOT
+        ))
+        .bind(stream_category)
+        .bind(version)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row.as_ref().map(snapshot_from_row))
+    }
+
+    /// Makes `version` the one loaded for the category's streams.
+    pub async fn activate(
+        &self,
+        stream_category: &str,
+        version: i32,
+    ) -> Result<PatternModelSnapshot, PatternModelError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        sqlx::query("UPDATE intuition_pattern_models SET is_active = FALSE WHERE stream_category = $1 AND is_active")
+            .bind(stream_category)
+            .execute(&mut *tx)
+            .await?;
+
+        let row = sqlx::query(&format!(
+            r#"
+            UPDATE intuition_pattern_models SET is_active = TRUE
+            WHERE stream_category = $1 AND version = $2
+            RETURNING {}
+            "#,
+            SNAPSHOT_COLUMNS
+        ))
+        .bind(stream_category)
+        .bind(version)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or("Pattern model version not found")?;
+
+        tx.commit().await?;
+
+        let snapshot = snapshot_from_row(&row);
+        self.active.write().insert(stream_category.to_string(), Arc::new(snapshot.clone()));
+        Ok(snapshot)
+    }
+
+    /// Re-activates the newest version older than the active one.
+    pub async fn rollback(&self, stream_category: &str) -> Result<PatternModelSnapshot, PatternModelError> {
+        let current = self.active.read().get(stream_category)
+            .map(|snapshot| snapshot.version)
+            .ok_or("No active pattern model for category")?;
+
+        let previous: Option<i32> = sqlx::query_scalar(
+            "SELECT MAX(version) FROM intuition_pattern_models WHERE stream_category = $1 AND version < $2"
+        )
+        .bind(stream_category)
+        .bind(current)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        let previous = previous.ok_or("No earlier pattern model version to roll back to")?;
+        self.activate(stream_category, previous).await
+    }
+}
+
+const SNAPSHOT_COLUMNS: &str = "model_id, stream_category, version, patterns, training_window_start, \
+    training_window_end, metrics, notes, onnx, is_active, created_at";
+
+const SELECT_SNAPSHOT: &str = "SELECT model_id, stream_category, version, patterns, training_window_start, \
+    training_window_end, metrics, notes, onnx, is_active, created_at FROM intuition_pattern_models";
+
+fn snapshot_from_row(row: &sqlx::postgres::PgRow) -> PatternModelSnapshot {
+    PatternModelSnapshot {
+        model_id: row.get("model_id"),
+        stream_category: row.get("stream_category"),
+        version: row.get("version"),
+        patterns: row.get("patterns"),
+        training_window_start: row.get("training_window_start"),
+        training_window_end: row.get("training_window_end"),
+        metrics: row.get("metrics"),
+        notes: row.get("notes"),
+        // Validated on the way in
+        onnx: row.get::<Option<serde_json::Value>, _>("onnx").and_then(|spec| serde_json::from_value(spec).ok()),
+        is_active: row.get("is_active"),
+        created_at: row.get("created_at"),
+    }
+}
@@ -0,0 +1,157 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use serde::{Deserialize, Serialize};
+
+use super::StreamingContext;
+
+/// Urgency of a streaming context; higher variants are processed first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextPriority {
+    #[default]
+    Routine,
+    Elevated,
+    Settlement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShedPolicy {
+    // Discard routine contexts outright
+    Drop,
+    // Collapse routine contexts into a single summarized context
+    Summarize,
+}
+
+struct QueuedContext {
+    priority: ContextPriority,
+    sequence: u64,
+    context: StreamingContext,
+}
+
+impl PartialEq for QueuedContext {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.sequence == other.sequence
+    }
+}
+
+impl Eq for QueuedContext {}
+
+impl PartialOrd for QueuedContext {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedContext {
+    // Highest priority first, then oldest first within a priority
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// Per-stream priority queue of pending contexts with load shedding.
+pub struct ContextQueue {
+    heap: BinaryHeap<QueuedContext>,
+    next_sequence: u64,
+    shed_count: u64,
+}
+
+impl ContextQueue {
+    pub fn new() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            next_sequence: 0,
+            shed_count: 0,
+        }
+    }
+
+    pub fn push(&mut self, context: StreamingContext) {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.heap.push(QueuedContext {
+            priority: context.priority,
+            sequence,
+            context,
+        });
+    }
+
+    pub fn pop(&mut self) -> Option<StreamingContext> {
+        self.heap.pop().map(|queued| queued.context)
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn shed_count(&self) -> u64 {
+        self.shed_count
+    }
+
+    /// Sheds routine contexts according to the policy. Elevated and settlement
+    /// contexts are never shed. Returns the number of contexts removed.
+    pub fn shed(&mut self, policy: ShedPolicy) -> usize {
+        let (routine, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.heap)
+            .into_vec()
+            .into_iter()
+            .partition(|queued| queued.priority == ContextPriority::Routine);
+
+        self.heap = kept.into_iter().collect();
+
+        let routine_count = routine.len();
+        let removed = match policy {
+            ShedPolicy::Drop => routine_count,
+            ShedPolicy::Summarize if routine_count > 1 => {
+                let summary_sequence = routine.iter().map(|q| q.sequence).max().unwrap_or(0);
+                let summary = Self::summarize(routine.into_iter().map(|q| q.context).collect());
+                self.heap.push(QueuedContext {
+                    priority: ContextPriority::Routine,
+                    sequence: summary_sequence,
+                    context: summary,
+                });
+                routine_count - 1
+            }
+            ShedPolicy::Summarize => {
+                for queued in routine {
+                    self.heap.push(queued);
+                }
+                0
+            }
+        };
+
+        self.shed_count += removed as u64;
+        removed
+    }
+
+    // Merges contexts oldest-to-newest so later values win; confidence is averaged
+    fn summarize(mut contexts: Vec<StreamingContext>) -> StreamingContext {
+        contexts.sort_by(|a, b| a.timestamp.partial_cmp(&b.timestamp).unwrap_or(Ordering::Equal));
+
+        let count = contexts.len();
+        let mean_confidence = contexts.iter().map(|c| c.confidence_level).sum::<f64>() / count as f64;
+
+        let mut partial_data = HashMap::new();
+        for context in &contexts {
+            for (key, value) in &context.partial_data {
+                partial_data.insert(key.clone(), value.clone());
+            }
+        }
+        partial_data.insert("summarized_count".to_string(), serde_json::json!(count));
+
+        let mut summary = contexts.pop().expect("summarize requires at least one context");
+        summary.partial_data = partial_data;
+        summary.confidence_level = mean_confidence;
+        summary
+    }
+}
+
+impl Default for ContextQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
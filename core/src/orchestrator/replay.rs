@@ -0,0 +1,425 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::sync::Arc;
+use sqlx::{Pool, Postgres, Row};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use super::{MetacognitiveDecision, StreamingContext};
+use super::backpressure::now_seconds;
+use crate::analytics::AnalyticsEvent;
+use crate::config::ReplayConfig;
+use crate::replica::ReadRouter;
+use crate::shutdown::Shutdown;
+use crate::tenant;
+use crate::validation::{Validate, ValidationErrors};
+
+type ReplayError = Box<dyn std::error::Error + Send + Sync>;
+
+// Present in partial_data on replayed contexts; the pipeline skips persistent side effects for them
+pub const REPLAY_MARKER: &str = "replay_run_id";
+
+pub fn is_replay(context: &StreamingContext) -> bool {
+    context.partial_data.contains_key(REPLAY_MARKER)
+}
+
+/// One analytics frame as it was received.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedFrame {
+    pub timestamp: DateTime<Utc>,
+    pub analytics: AnalyticsEvent,
+}
+
+// A frame waiting for the writer, with the tenant that sent it
+struct PendingFrame {
+    stream_id: String,
+    received_at: DateTime<Utc>,
+    analytics: AnalyticsEvent,
+    tenant_id: Option<String>,
+}
+
+/// Durable copy of the analytics frames fed to the orchestrator, in
+/// `analytics_events`: the cold store that keeps analytics past Redis's
+/// retention, for replays, disputes and backtests. Frames are queued and
+/// written in batches in the background; while the queue is full, new frames
+/// are dropped rather than holding up the pipeline.
+pub struct AnalyticsArchive {
+    db_pool: Pool<Postgres>,
+    // Replays read from a replica; a frame recorded moments ago may not be there yet
+    reads: Arc<ReadRouter>,
+    config: ReplayConfig,
+    pending: mpsc::Sender<PendingFrame>,
+    // Taken by the writer when it starts
+    receiver: Mutex<Option<mpsc::Receiver<PendingFrame>>>,
+}
+
+impl AnalyticsArchive {
+    pub fn new(db_pool: Pool<Postgres>, reads: Arc<ReadRouter>, config: ReplayConfig) -> Self {
+        let (pending, receiver) = mpsc::channel(config.archive_queue_capacity);
+        Self { db_pool, reads, config, pending, receiver: Mutex::new(Some(receiver)) }
+    }
+
+    /// Queues a frame for the writer, as received now by the current tenant.
+    pub fn record(&self, stream_id: &str, analytics: &AnalyticsEvent) {
+        let frame = PendingFrame {
+            stream_id: stream_id.to_string(),
+            received_at: Utc::now(),
+            analytics: analytics.clone(),
+            tenant_id: tenant::current().map(|tenant| tenant.id),
+        };
+        if let Err(mpsc::error::TrySendError::Full(frame)) = self.pending.try_send(frame) {
+            warn!("Analytics archive queue is full; dropped a frame for stream {}", frame.stream_id);
+        }
+    }
+
+    /// Writes queued frames until shutdown, then writes what is left.
+    pub fn start(self: &Arc<Self>, shutdown: &Shutdown) {
+        let Some(mut receiver) = self.receiver.lock().take() else {
+            return;
+        };
+        let archive = self.clone();
+        let stopping = shutdown.clone();
+        shutdown.spawn_tracked("orchestrator:analytics_archive", async move {
+            let batch_size = archive.config.archive_batch_size;
+            let mut interval = tokio::time::interval(Duration::from_millis(archive.config.archive_flush_interval_ms));
+            let mut batch: Vec<PendingFrame> = Vec::with_capacity(batch_size);
+
+            loop {
+                tokio::select! {
+                    frame = receiver.recv() => match frame {
+                        Some(frame) => {
+                            batch.push(frame);
+                            if batch.len() >= batch_size {
+                                archive.flush(&mut batch).await;
+                            }
+                        }
+                        None => break,
+                    },
+                    _ = interval.tick() => archive.flush(&mut batch).await,
+                    _ = stopping.triggered() => break,
+                }
+            }
+
+            receiver.close();
+            while let Some(frame) = receiver.recv().await {
+                batch.push(frame);
+            }
+            archive.flush(&mut batch).await;
+            info!("Analytics archive writer stopped");
+        });
+    }
+
+    // A failed batch is kept and retried with the next, until holding it would outgrow the queue
+    async fn flush(&self, batch: &mut Vec<PendingFrame>) {
+        if batch.is_empty() {
+            return;
+        }
+        match self.write(batch).await {
+            Ok(()) => batch.clear(),
+            Err(e) if batch.len() >= self.config.archive_queue_capacity => {
+                warn!("Dropped {} analytics frames the archive couldn't write: {}", batch.len(), e);
+                batch.clear();
+            }
+            Err(e) => warn!("Failed to archive {} analytics frames, retrying: {}", batch.len(), e),
+        }
+    }
+
+    async fn write(&self, batch: &[PendingFrame]) -> Result<(), ReplayError> {
+        let mut stream_ids = Vec::with_capacity(batch.len());
+        let mut timestamps = Vec::with_capacity(batch.len());
+        let mut event_types = Vec::with_capacity(batch.len());
+        let mut confidences = Vec::with_capacity(batch.len());
+        let mut data = Vec::with_capacity(batch.len());
+        let mut tenant_ids = Vec::with_capacity(batch.len());
+        for frame in batch {
+            let confidence = frame.analytics.confidence().unwrap_or(0.5).clamp(0.0, 1.0);
+
+            stream_ids.push(frame.stream_id.clone());
+            timestamps.push(frame.received_at);
+            event_types.push(frame.analytics.event_type().to_string());
+            confidences.push(confidence);
+            data.push(frame.analytics.to_value());
+            tenant_ids.push(frame.tenant_id.clone());
+        }
+
+        // The writer's connection has no tenant set, so each row names its own
+        sqlx::query(
+            r#"
+            INSERT INTO analytics_events (stream_id, timestamp, event_type, confidence, data, tenant_id)
+            SELECT stream_id, timestamp, event_type, confidence::numeric, data,
+                COALESCE(tenant_id, morphine_current_tenant(), 'default')
+            FROM UNNEST($1::text[], $2::timestamptz[], $3::text[], $4::float8[], $5::jsonb[], $6::text[])
+                AS frame(stream_id, timestamp, event_type, confidence, data, tenant_id)
+            "#
+        )
+        .bind(&stream_ids)
+        .bind(&timestamps)
+        .bind(&event_types)
+        .bind(&confidences)
+        .bind(&data)
+        .bind(&tenant_ids)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// A page of the stream's frames between `from` and `to`, oldest first
+    /// unless `descending`, with the total in the range.
+    pub async fn query(
+        &self,
+        stream_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        descending: bool,
+        limit: usize,
+        offset: usize,
+    ) -> Result<(Vec<ArchivedFrame>, usize), ReplayError> {
+        let reader = self.reads.reader();
+        let total: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM analytics_events WHERE stream_id = $1 AND timestamp >= $2 AND timestamp <= $3"
+        )
+        .bind(stream_id)
+        .bind(from)
+        .bind(to)
+        .fetch_one(reader)
+        .await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT timestamp, data
+            FROM analytics_events
+            WHERE stream_id = $1 AND timestamp >= $2 AND timestamp <= $3
+            ORDER BY CASE WHEN $4 THEN timestamp END DESC, timestamp ASC, id ASC
+            LIMIT $5 OFFSET $6
+            "#
+        )
+        .bind(stream_id)
+        .bind(from)
+        .bind(to)
+        .bind(descending)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(reader)
+        .await?;
+
+        let frames = rows.iter()
+            .map(|row| ArchivedFrame {
+                timestamp: row.get("timestamp"),
+                analytics: AnalyticsEvent::from(row.get::<serde_json::Value, _>("data")),
+            })
+            .collect();
+        Ok((frames, total as usize))
+    }
+
+    // Oldest first, so frames replay in the order they arrived
+    pub async fn load(
+        &self,
+        stream_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<ArchivedFrame>, ReplayError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT timestamp, data
+            FROM analytics_events
+            WHERE stream_id = $1 AND timestamp >= $2 AND timestamp <= $3
+            ORDER BY timestamp ASC, id ASC
+            LIMIT $4
+            "#
+        )
+        .bind(stream_id)
+        .bind(from)
+        .bind(to)
+        .bind(limit)
+        .fetch_all(self.reads.reader())
+        .await?;
+
+        Ok(rows.iter()
+            .map(|row| ArchivedFrame {
+                timestamp: row.get("timestamp"),
+                analytics: AnalyticsEvent::from(row.get::<serde_json::Value, _>("data")),
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ReplayRequest {
+    pub stream_id: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    // Multiple of real time; 0 replays as fast as the pipeline allows
+    #[serde(default)]
+    pub speed: f64,
+    pub limit: Option<i64>,
+}
+
+impl ReplayRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        ValidationErrors::collect(self).map_err(|e| e.to_string())
+    }
+}
+
+impl Validate for ReplayRequest {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("stream_id", &self.stream_id);
+        if self.to <= self.from {
+            errors.add("to", "must be after from");
+        }
+        if !self.speed.is_finite() || self.speed < 0.0 {
+            errors.add("speed", "must be zero or a positive multiple of real time");
+        }
+        if self.limit.map(|limit| limit <= 0).unwrap_or(false) {
+            errors.add("limit", "must be greater than zero");
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayStatus {
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayedDecision {
+    pub frame_timestamp: DateTime<Utc>,
+    pub decision: MetacognitiveDecision,
+}
+
+/// Decision mix of a replay next to what was originally decided over the same period.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayComparison {
+    pub replayed_types: HashMap<String, usize>,
+    pub replayed_mean_confidence: f64,
+    pub original_types: HashMap<String, usize>,
+    pub original_mean_confidence: f64,
+}
+
+impl ReplayComparison {
+    pub fn new(replayed: &[&MetacognitiveDecision], original: &[&MetacognitiveDecision]) -> Self {
+        let (replayed_types, replayed_mean_confidence) = decision_mix(replayed);
+        let (original_types, original_mean_confidence) = decision_mix(original);
+        Self {
+            replayed_types,
+            replayed_mean_confidence,
+            original_types,
+            original_mean_confidence,
+        }
+    }
+}
+
+fn decision_mix(decisions: &[&MetacognitiveDecision]) -> (HashMap<String, usize>, f64) {
+    let mut types = HashMap::new();
+    for decision in decisions {
+        *types.entry(format!("{:?}", decision.decision_type)).or_insert(0) += 1;
+    }
+    let mean = if decisions.is_empty() {
+        0.0
+    } else {
+        decisions.iter().map(|d| d.confidence).sum::<f64>() / decisions.len() as f64
+    };
+    (types, mean)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayRun {
+    pub run_id: String,
+    pub stream_id: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub speed: f64,
+    pub status: ReplayStatus,
+    pub frames_total: usize,
+    pub frames_replayed: usize,
+    pub decisions: Vec<ReplayedDecision>,
+    pub comparison: Option<ReplayComparison>,
+    pub error: Option<String>,
+    pub started_at: f64,
+    pub finished_at: Option<f64>,
+}
+
+/// In-memory record of recent replay runs and their cancellation tokens.
+pub struct ReplayRegistry {
+    config: ReplayConfig,
+    runs: RwLock<HashMap<String, ReplayRun>>,
+    order: RwLock<VecDeque<String>>,
+    cancellations: RwLock<HashMap<String, CancellationToken>>,
+}
+
+impl ReplayRegistry {
+    pub fn new(config: ReplayConfig) -> Self {
+        Self {
+            config,
+            runs: RwLock::new(HashMap::new()),
+            order: RwLock::new(VecDeque::new()),
+            cancellations: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &ReplayConfig {
+        &self.config
+    }
+
+    pub fn start(&self, run: ReplayRun) -> CancellationToken {
+        let cancel = CancellationToken::new();
+        let run_id = run.run_id.clone();
+
+        let mut runs = self.runs.write();
+        let mut order = self.order.write();
+        runs.insert(run_id.clone(), run);
+        order.push_back(run_id.clone());
+
+        // Evict the oldest finished runs beyond the retention limit
+        while runs.len() > self.config.max_runs_retained {
+            let evictable = order.iter()
+                .position(|id| runs.get(id).map(|r| r.status != ReplayStatus::Running).unwrap_or(true));
+            match evictable.and_then(|index| order.remove(index)) {
+                Some(evicted) => { runs.remove(&evicted); }
+                None => break,
+            }
+        }
+
+        self.cancellations.write().insert(run_id, cancel.clone());
+        cancel
+    }
+
+    pub fn update(&self, run_id: &str, apply: impl FnOnce(&mut ReplayRun)) {
+        if let Some(run) = self.runs.write().get_mut(run_id) {
+            apply(run);
+        }
+    }
+
+    pub fn finish(&self, run_id: &str, status: ReplayStatus, error: Option<String>) {
+        self.cancellations.write().remove(run_id);
+        self.update(run_id, |run| {
+            run.status = status;
+            run.error = error;
+            run.finished_at = Some(now_seconds());
+        });
+    }
+
+    pub fn get(&self, run_id: &str) -> Option<ReplayRun> {
+        self.runs.read().get(run_id).cloned()
+    }
+
+    pub fn cancel(&self, run_id: &str) -> bool {
+        match self.cancellations.read().get(run_id) {
+            Some(cancel) => {
+                cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
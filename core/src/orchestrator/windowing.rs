@@ -0,0 +1,211 @@
+use std::collections::{HashMap, VecDeque};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::StreamingContext;
+use super::priority_queue::ContextPriority;
+use crate::validation::{Validate, ValidationErrors};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowKind {
+    // Every frame becomes its own context
+    None,
+    // Non-overlapping windows, each emitted once when it closes
+    Tumbling,
+    // Overlapping windows over the last `window_ms`, emitted every `slide_ms`
+    Sliding,
+}
+
+/// How a stream's analytics frames are batched into contexts.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WindowPolicy {
+    pub kind: WindowKind,
+    pub window_ms: u64,
+    pub slide_ms: u64,
+    // Closes a tumbling window early, or caps a sliding one, at this many frames
+    pub max_frames: usize,
+    // Batch only while glycolytic load is at or above this; 0 batches always
+    pub min_load: f64,
+}
+
+impl WindowPolicy {
+    pub fn is_batching(&self) -> bool {
+        self.kind != WindowKind::None
+    }
+
+    // Shared by config loading and the admin override endpoint
+    pub fn validate(&self) -> Result<(), String> {
+        ValidationErrors::collect(self).map_err(|e| e.to_string())
+    }
+}
+
+impl Validate for WindowPolicy {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        if !self.is_batching() {
+            return;
+        }
+        if self.window_ms == 0 {
+            errors.add("window_ms", "must be greater than zero");
+        }
+        if self.max_frames == 0 {
+            errors.add("max_frames", "must be greater than zero");
+        }
+        if self.kind == WindowKind::Sliding && (self.slide_ms == 0 || self.slide_ms > self.window_ms) {
+            errors.add("slide_ms", "must be greater than zero and not exceed window_ms");
+        }
+        errors.require_range("min_load", self.min_load, 0.0, 1.0);
+    }
+}
+
+/// Frames buffered for one stream under its window policy.
+pub struct StreamWindow {
+    policy: WindowPolicy,
+    frames: VecDeque<StreamingContext>,
+    // When the current tumbling window opened
+    opened_at: Option<f64>,
+    // Sliding windows only re-emit once something new has arrived
+    last_emit_at: f64,
+    new_since_emit: usize,
+}
+
+impl StreamWindow {
+    pub fn new(policy: WindowPolicy) -> Self {
+        Self {
+            policy,
+            frames: VecDeque::new(),
+            opened_at: None,
+            last_emit_at: 0.0,
+            new_since_emit: 0,
+        }
+    }
+
+    pub fn policy(&self) -> &WindowPolicy {
+        &self.policy
+    }
+
+    pub fn buffered(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Buffers a frame, returning a context if it closed the window.
+    pub fn push(&mut self, context: StreamingContext, now: f64) -> Option<StreamingContext> {
+        self.frames.push_back(context);
+        self.new_since_emit += 1;
+
+        match self.policy.kind {
+            WindowKind::None => self.flush(),
+            WindowKind::Tumbling => {
+                self.opened_at.get_or_insert(now);
+                if self.frames.len() >= self.policy.max_frames {
+                    return self.flush();
+                }
+                None
+            }
+            WindowKind::Sliding => {
+                self.evict(now);
+                while self.frames.len() > self.policy.max_frames {
+                    self.frames.pop_front();
+                }
+                None
+            }
+        }
+    }
+
+    /// Emits whatever the window owes as of `now`; called on a timer.
+    pub fn poll(&mut self, now: f64) -> Option<StreamingContext> {
+        match self.policy.kind {
+            WindowKind::None => self.flush(),
+            WindowKind::Tumbling => {
+                let opened_at = self.opened_at?;
+                if (now - opened_at) * 1000.0 >= self.policy.window_ms as f64 {
+                    return self.flush();
+                }
+                None
+            }
+            WindowKind::Sliding => {
+                self.evict(now);
+                if self.new_since_emit == 0 || (now - self.last_emit_at) * 1000.0 < self.policy.slide_ms as f64 {
+                    return None;
+                }
+                self.last_emit_at = now;
+                self.new_since_emit = 0;
+                summarize_window(self.frames.iter().cloned().collect(), self.policy.kind)
+            }
+        }
+    }
+
+    /// Drains every buffered frame into one context, e.g. before a policy change
+    /// or when a high-priority frame must not overtake them.
+    pub fn flush(&mut self) -> Option<StreamingContext> {
+        self.opened_at = None;
+        self.new_since_emit = 0;
+        let frames: Vec<StreamingContext> = self.frames.drain(..).collect();
+        summarize_window(frames, self.policy.kind)
+    }
+
+    fn evict(&mut self, now: f64) {
+        let horizon = now - self.policy.window_ms as f64 / 1000.0;
+        while self.frames.front().map(|f| f.timestamp < horizon).unwrap_or(false) {
+            self.frames.pop_front();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct FeatureSummary {
+    count: usize,
+    mean: f64,
+    min: f64,
+    max: f64,
+    last: f64,
+}
+
+/// Collapses a window of frames into one context. The latest value of every
+/// field is kept as-is so the layers read it as usual; numeric fields are also
+/// summarized under `window_features`.
+pub fn summarize_window(mut frames: Vec<StreamingContext>, kind: WindowKind) -> Option<StreamingContext> {
+    if frames.len() <= 1 {
+        return frames.pop();
+    }
+    frames.sort_by(|a, b| a.timestamp.partial_cmp(&b.timestamp).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut features: HashMap<String, FeatureSummary> = HashMap::new();
+    let mut partial_data = HashMap::new();
+    for frame in &frames {
+        for (key, value) in &frame.partial_data {
+            if let Some(number) = value.as_f64() {
+                let summary = features.entry(key.clone()).or_insert(FeatureSummary {
+                    min: f64::INFINITY,
+                    max: f64::NEG_INFINITY,
+                    ..Default::default()
+                });
+                summary.count += 1;
+                summary.mean += (number - summary.mean) / summary.count as f64;
+                summary.min = summary.min.min(number);
+                summary.max = summary.max.max(number);
+                summary.last = number;
+            }
+            partial_data.insert(key.clone(), value.clone());
+        }
+    }
+
+    let frame_count = frames.len();
+    let window_start = frames[0].timestamp;
+    let confidence_level = frames.iter().map(|f| f.confidence_level).sum::<f64>() / frame_count as f64;
+    let priority = frames.iter().map(|f| f.priority).max().unwrap_or(ContextPriority::Routine);
+
+    let mut summary = frames.pop().expect("window holds at least two frames");
+    partial_data.insert("window_features".to_string(), serde_json::to_value(features).unwrap_or_default());
+    partial_data.insert("window".to_string(), serde_json::json!({
+        "kind": kind,
+        "frame_count": frame_count,
+        "start": window_start,
+        "end": summary.timestamp,
+    }));
+
+    summary.partial_data = partial_data;
+    summary.confidence_level = confidence_level;
+    summary.priority = priority;
+    Some(summary)
+}
@@ -0,0 +1,196 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{Executor, Pool, Postgres, Row};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::OutboxConfig;
+use crate::shutdown::Shutdown;
+use crate::tenant::{self, Tenant};
+
+pub type OutboxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A domain event waiting in, or relayed from, the outbox.
+#[derive(Debug, Clone)]
+pub struct OutboxEvent {
+    // Stays the same across retries, so consumers can drop duplicates
+    pub event_id: String,
+    pub event_type: String,
+    pub data: Value,
+    pub occurred_at: DateTime<Utc>,
+    pub tenant_id: String,
+}
+
+/// Adds an event to the outbox. Pass the transaction making the change the
+/// event describes, so both commit or neither does. Returns the event ID.
+pub async fn enqueue<'e, E>(executor: E, event_type: &str, data: &Value) -> Result<String, OutboxError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let event_id = Uuid::new_v4().to_string();
+    sqlx::query("INSERT INTO outbox_events (event_id, event_type, data) VALUES ($1, $2, $3)")
+        .bind(&event_id)
+        .bind(event_type)
+        .bind(data)
+        .execute(executor)
+        .await?;
+    Ok(event_id)
+}
+
+/// A consumer of outbox events. Each event reaches each handler at least
+/// once; a handler that fails gets the event again later, without the
+/// handlers that already took it seeing it twice.
+#[async_trait::async_trait]
+pub trait OutboxHandler: Send + Sync {
+    // Recorded against events the handler has taken; renaming it redelivers in-flight events
+    fn name(&self) -> &'static str;
+
+    async fn handle(&self, event: &OutboxEvent) -> Result<(), OutboxError>;
+}
+
+/// Relays committed outbox events to their handlers in the background, on
+/// every instance; rows are claimed so each event is relayed by one at a time.
+pub struct OutboxRelay {
+    db_pool: Pool<Postgres>,
+    handlers: Vec<Arc<dyn OutboxHandler>>,
+    config: OutboxConfig,
+    // Events are relayed as the tenant that wrote them
+    default_tenant: String,
+    shutdown: Shutdown,
+}
+
+impl OutboxRelay {
+    pub fn new(
+        db_pool: Pool<Postgres>,
+        handlers: Vec<Arc<dyn OutboxHandler>>,
+        config: OutboxConfig,
+        default_tenant: String,
+        shutdown: Shutdown,
+    ) -> Self {
+        Self { db_pool, handlers, config, default_tenant, shutdown }
+    }
+
+    pub fn start(self: &Arc<Self>) {
+        let relay = self.clone();
+        self.shutdown.spawn_loop("outbox:relay", async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(relay.config.poll_interval_ms));
+
+            loop {
+                interval.tick().await;
+                // Drain a backlog without waiting a poll interval between batches
+                loop {
+                    match relay.relay_due().await {
+                        Ok(relayed) if relayed < relay.config.batch_size as usize => break,
+                        Ok(_) => continue,
+                        Err(e) => {
+                            warn!("Outbox relay failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        info!("Outbox relay started with {} handlers", self.handlers.len());
+    }
+
+    async fn relay_due(&self) -> Result<usize, OutboxError> {
+        // Leased rather than locked, so no transaction stays open while handlers run
+        let lease_secs = self.config.lease_secs as f64;
+        let rows = sqlx::query(
+            r#"
+            UPDATE outbox_events e
+            SET next_attempt_at = NOW() + make_interval(secs => $2)
+            FROM (
+                SELECT event_id
+                FROM outbox_events
+                WHERE published_at IS NULL AND next_attempt_at <= NOW()
+                ORDER BY occurred_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            ) due
+            WHERE e.event_id = due.event_id
+            RETURNING e.event_id, e.event_type, e.data, e.occurred_at, e.tenant_id, e.completed_handlers, e.attempts
+            "#
+        )
+        .bind(self.config.batch_size)
+        .bind(lease_secs)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let mut due: Vec<(OutboxEvent, Vec<String>, i32)> = rows.iter()
+            .map(|row| {
+                let event = OutboxEvent {
+                    event_id: row.get("event_id"),
+                    event_type: row.get("event_type"),
+                    data: row.get("data"),
+                    occurred_at: row.get("occurred_at"),
+                    tenant_id: row.get("tenant_id"),
+                };
+                (event, row.get("completed_handlers"), row.get("attempts"))
+            })
+            .collect();
+        // Claimed in a set; relayed oldest first
+        due.sort_by_key(|(event, _, _)| event.occurred_at);
+
+        let count = due.len();
+        for (event, completed, attempts) in due {
+            let tenant = Tenant { is_default: event.tenant_id == self.default_tenant, id: event.tenant_id.clone() };
+            tenant::scope(tenant, self.relay(event, completed, attempts)).await?;
+        }
+        Ok(count)
+    }
+
+    async fn relay(&self, event: OutboxEvent, mut completed: Vec<String>, attempts: i32) -> Result<(), OutboxError> {
+        let mut last_error = None;
+        for handler in &self.handlers {
+            if completed.iter().any(|name| name == handler.name()) {
+                continue;
+            }
+            match handler.handle(&event).await {
+                Ok(()) => completed.push(handler.name().to_string()),
+                Err(e) => {
+                    warn!("Outbox handler {} failed on {} event {}: {}", handler.name(), event.event_type, event.event_id, e);
+                    last_error = Some(format!("{}: {}", handler.name(), e));
+                }
+            }
+        }
+
+        match last_error {
+            None => {
+                sqlx::query("UPDATE outbox_events SET published_at = NOW(), completed_handlers = $2, last_error = NULL WHERE event_id = $1")
+                    .bind(&event.event_id)
+                    .bind(&completed)
+                    .execute(&self.db_pool)
+                    .await?;
+            }
+            Some(error) => {
+                let attempts = attempts + 1;
+                sqlx::query(
+                    r#"
+                    UPDATE outbox_events
+                    SET attempts = $2, completed_handlers = $3, last_error = $4,
+                        next_attempt_at = NOW() + make_interval(secs => $5)
+                    WHERE event_id = $1
+                    "#
+                )
+                .bind(&event.event_id)
+                .bind(attempts)
+                .bind(&completed)
+                .bind(error)
+                .bind(self.backoff(attempts).as_secs_f64())
+                .execute(&self.db_pool)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    // Events are never given up on; a handler that keeps failing is retried at the longest backoff
+    fn backoff(&self, attempts: i32) -> Duration {
+        let exponent = (attempts.max(1) - 1).min(31) as u32;
+        let ms = self.config.initial_backoff_ms.saturating_mul(1u64 << exponent);
+        Duration::from_millis(ms.min(self.config.max_backoff_ms))
+    }
+}
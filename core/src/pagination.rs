@@ -0,0 +1,230 @@
+use std::cmp::Ordering;
+use axum::async_trait;
+use axum::extract::{FromRequestParts, Query};
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use utoipa::IntoParams;
+
+use crate::encoding::{Encoding, Negotiated};
+use crate::error::ApiError;
+use crate::validation::ValidationErrors;
+
+pub const DEFAULT_PAGE_LIMIT: usize = 50;
+pub const MAX_PAGE_LIMIT: usize = 200;
+
+/// Query parameters shared by every list endpoint.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    /// Items per page, at most 200 (default 50)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// `next_cursor` from the previous page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Field to sort by; prefix with `-` for descending, e.g. `-timestamp`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// Comma-separated top-level fields to return, e.g. `id,title`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sort {
+    pub field: String,
+    pub descending: bool,
+}
+
+/// A validated page request. Extract it in list handlers and pass the full
+/// result set to [`PageRequest::paginate`].
+#[derive(Debug, Clone)]
+pub struct PageRequest {
+    pub limit: usize,
+    pub offset: usize,
+    pub sort: Option<Sort>,
+    pub fields: Option<Vec<String>>,
+}
+
+// Cursors are opaque to clients; today they carry the offset of the next page
+fn encode_cursor(offset: usize) -> String {
+    format!("o{:x}", offset)
+}
+
+fn decode_cursor(cursor: &str) -> Option<usize> {
+    cursor.strip_prefix('o').and_then(|hex| usize::from_str_radix(hex, 16).ok())
+}
+
+impl PageRequest {
+    /// Validates page parameters that arrived some other way than a query string.
+    pub fn from_params(params: PageParams) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        let limit = params.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        if limit == 0 || limit > MAX_PAGE_LIMIT {
+            errors.add("limit", format!("must be between 1 and {}", MAX_PAGE_LIMIT));
+        }
+
+        let offset = match params.cursor.as_deref() {
+            None | Some("") => 0,
+            Some(cursor) => decode_cursor(cursor).unwrap_or_else(|| {
+                errors.add("cursor", "is not a cursor returned by this endpoint");
+                0
+            }),
+        };
+
+        let sort = params.sort.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(|sort| {
+            match sort.strip_prefix('-') {
+                Some(field) => Sort { field: field.to_string(), descending: true },
+                None => Sort { field: sort.trim_start_matches('+').to_string(), descending: false },
+            }
+        });
+
+        let fields = params.fields.as_deref().map(|fields| {
+            fields.split(',').map(str::trim).filter(|f| !f.is_empty()).map(str::to_string).collect::<Vec<_>>()
+        }).filter(|fields| !fields.is_empty());
+
+        if errors.is_empty() {
+            Ok(Self { limit, offset, sort, fields })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Sorts `items` (only by a field in `sortable`), cuts out the requested
+    /// page and remembers the field selection for rendering. Items without the
+    /// sort field go last.
+    pub fn paginate<T: Serialize>(&self, mut items: Vec<T>, sortable: &[&str]) -> Result<Paginated<T>, ApiError> {
+        self.check_sort(sortable)?;
+        if let Some(sort) = &self.sort {
+            let mut keyed: Vec<(Option<Value>, T)> = items.into_iter()
+                .map(|item| {
+                    let key = serde_json::to_value(&item).ok().and_then(|v| v.get(&sort.field).cloned());
+                    (key, item)
+                })
+                .collect();
+            keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+                (Some(a), Some(b)) if sort.descending => compare_values(b, a),
+                (Some(a), Some(b)) => compare_values(a, b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+            items = keyed.into_iter().map(|(_, item)| item).collect();
+        }
+
+        let total = items.len();
+        let page: Vec<T> = items.into_iter().skip(self.offset).take(self.limit).collect();
+        Ok(self.page_of(page, total))
+    }
+
+    /// Rejects a sort on any field not in `sortable`.
+    pub fn check_sort(&self, sortable: &[&str]) -> Result<(), ApiError> {
+        match &self.sort {
+            Some(sort) if !sortable.contains(&sort.field.as_str()) => {
+                let mut errors = ValidationErrors::new();
+                errors.add("sort", format!("must be one of: {}", sortable.join(", ")));
+                Err(errors.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Wraps a page the source already cut out itself (e.g. with SQL `LIMIT`
+    /// and `OFFSET` from this request), out of `total` items.
+    pub fn page_of<T>(&self, items: Vec<T>, total: usize) -> Paginated<T> {
+        let next_offset = self.offset + items.len();
+        Paginated {
+            items,
+            next_cursor: (next_offset < total).then(|| encode_cursor(next_offset)),
+            total,
+            limit: self.limit,
+            fields: self.fields.clone(),
+        }
+    }
+}
+
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => {
+            a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for PageRequest
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<PageParams>::from_request_parts(parts, state).await
+            .map_err(|rejection| ApiError::bad_request(rejection.body_text()))?;
+        Ok(Self::from_params(params)?)
+    }
+}
+
+/// One page of a list endpoint. Renders as
+/// `{"success": true, "data": [...], "pagination": {"limit", "total", "next_cursor"}}`,
+/// with `next_cursor` null on the last page.
+#[derive(Debug, Clone)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub total: usize,
+    pub limit: usize,
+    fields: Option<Vec<String>>,
+}
+
+impl<T: Serialize> Paginated<T> {
+    fn render_items(&self) -> Value {
+        let Some(fields) = &self.fields else {
+            return json!(self.items);
+        };
+
+        // Field selection applies to top-level keys of object items
+        let items: Vec<Value> = self.items.iter()
+            .map(|item| match serde_json::to_value(item) {
+                Ok(Value::Object(object)) => Value::Object(
+                    object.into_iter().filter(|(key, _)| fields.contains(key)).collect(),
+                ),
+                Ok(other) => other,
+                Err(_) => Value::Null,
+            })
+            .collect();
+        Value::Array(items)
+    }
+}
+
+impl<T: Serialize> Paginated<T> {
+    fn render(&self) -> Value {
+        json!({
+            "success": true,
+            "data": self.render_items(),
+            "pagination": {
+                "limit": self.limit,
+                "total": self.total,
+                "next_cursor": self.next_cursor,
+            }
+        })
+    }
+
+    /// The page in the encoding the client negotiated, rather than always JSON.
+    pub fn negotiated(self, encoding: Encoding) -> Negotiated<Value> {
+        encoding.respond(self.render())
+    }
+}
+
+impl<T: Serialize> IntoResponse for Paginated<T> {
+    fn into_response(self) -> Response {
+        Json(self.render()).into_response()
+    }
+}
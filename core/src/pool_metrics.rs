@@ -0,0 +1,60 @@
+use prometheus::{Encoder, GaugeVec, IntGaugeVec, Opts, Registry, TextEncoder};
+use sqlx::PgPool;
+
+type MetricsError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Prometheus view of the Postgres pools, read from the pools when scraped.
+/// Saturation near 1 means queries are about to wait for a connection, and
+/// past the acquire timeout fail.
+pub struct PoolMetrics {
+    registry: Registry,
+    pools: Vec<(String, PgPool)>,
+    connections: IntGaugeVec,
+    max_connections: IntGaugeVec,
+    saturation: GaugeVec,
+}
+
+impl PoolMetrics {
+    /// Reports on `pools`, each labelled with its name.
+    pub fn new(pools: Vec<(String, PgPool)>) -> Result<Self, MetricsError> {
+        let registry = Registry::new_custom(Some("morphine".to_string()), None)?;
+
+        let connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Open Postgres connections by state"),
+            &["pool", "state"],
+        )?;
+        let max_connections = IntGaugeVec::new(
+            Opts::new("db_pool_max_connections", "Most connections the pool will open"),
+            &["pool"],
+        )?;
+        let saturation = GaugeVec::new(
+            Opts::new("db_pool_saturation", "Fraction of the pool's connection limit in use"),
+            &["pool"],
+        )?;
+
+        registry.register(Box::new(connections.clone()))?;
+        registry.register(Box::new(max_connections.clone()))?;
+        registry.register(Box::new(saturation.clone()))?;
+
+        Ok(Self { registry, pools, connections, max_connections, saturation })
+    }
+
+    /// Encodes the pools' current state in the Prometheus text exposition format.
+    pub fn encode(&self) -> Result<String, MetricsError> {
+        for (name, pool) in &self.pools {
+            let size = pool.size() as i64;
+            let idle = pool.num_idle() as i64;
+            let max = pool.options().get_max_connections() as i64;
+            let in_use = (size - idle).max(0);
+
+            self.connections.with_label_values(&[name, "in_use"]).set(in_use);
+            self.connections.with_label_values(&[name, "idle"]).set(idle);
+            self.max_connections.with_label_values(&[name]).set(max);
+            self.saturation.with_label_values(&[name]).set(if max > 0 { in_use as f64 / max as f64 } else { 0.0 });
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
@@ -0,0 +1,579 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{Pool, Postgres, Row};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::betting::Bet;
+use crate::config::PushConfig;
+use crate::outbox::{OutboxError, OutboxEvent, OutboxHandler};
+use crate::validation::{Validate, ValidationErrors};
+
+pub type PushError = Box<dyn std::error::Error + Send + Sync>;
+
+// Longer than any token either platform issues today
+const MAX_TOKEN_LENGTH: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PushPlatform {
+    // Firebase Cloud Messaging: Android, and iOS apps that go through Firebase
+    Fcm,
+    // Apple Push Notification service, directly
+    Apns,
+}
+
+impl PushPlatform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PushPlatform::Fcm => "fcm",
+            PushPlatform::Apns => "apns",
+        }
+    }
+
+    fn parse(platform: &str) -> Option<Self> {
+        match platform {
+            "fcm" => Some(PushPlatform::Fcm),
+            "apns" => Some(PushPlatform::Apns),
+            _ => None,
+        }
+    }
+}
+
+/// A registered device, without its token.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PushDevice {
+    pub device_id: String,
+    pub platform: PushPlatform,
+    pub app_version: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_registered_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NewPushDevice {
+    pub platform: PushPlatform,
+    pub token: String,
+    pub app_version: Option<String>,
+}
+
+impl Validate for NewPushDevice {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        errors.require_non_empty("token", &self.token);
+        if self.token.len() > MAX_TOKEN_LENGTH {
+            errors.add("token", format!("must be at most {} characters", MAX_TOKEN_LENGTH));
+        }
+    }
+}
+
+/// Which notifications a user wants pushed; everything is on until they say otherwise.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PushPreferences {
+    pub bet_settled: bool,
+    pub stream_activated: bool,
+}
+
+impl Default for PushPreferences {
+    fn default() -> Self {
+        Self { bet_settled: true, stream_activated: true }
+    }
+}
+
+// Both flags are required, so deserializing is all the checking there is
+impl Validate for PushPreferences {
+    fn validate_fields(&self, _errors: &mut ValidationErrors) {}
+}
+
+/// One notification, to one device.
+pub struct PushMessage<'a> {
+    pub token: &'a str,
+    pub title: &'a str,
+    pub body: &'a str,
+    // Handed to the app alongside the notification, e.g. to open the bet
+    pub data: &'a HashMap<String, String>,
+}
+
+pub enum PushOutcome {
+    Delivered,
+    // The platform no longer knows the token: the app was uninstalled or the token rotated
+    Unregistered,
+}
+
+/// A push gateway for one platform.
+#[async_trait]
+pub trait PushSender: Send + Sync {
+    /// Errors are transient failures worth trying again.
+    async fn send(&self, message: PushMessage<'_>) -> Result<PushOutcome, PushError>;
+}
+
+/// Builds a sender for each platform that has credentials configured.
+pub fn senders(config: &PushConfig) -> Result<HashMap<PushPlatform, Box<dyn PushSender>>, PushError> {
+    let http_client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(config.send_timeout_ms))
+        .build()?;
+
+    let mut senders: HashMap<PushPlatform, Box<dyn PushSender>> = HashMap::new();
+    if let (Some(project_id), Some(path)) = (&config.fcm_project_id, &config.fcm_credentials_path) {
+        senders.insert(PushPlatform::Fcm, Box::new(FcmSender::new(project_id, path, http_client)?));
+    }
+    if let (Some(path), Some(key_id), Some(team_id), Some(topic)) =
+        (&config.apns_key_path, &config.apns_key_id, &config.apns_team_id, &config.apns_topic)
+    {
+        // APNs only speaks HTTP/2
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.send_timeout_ms))
+            .http2_prior_knowledge()
+            .build()?;
+        senders.insert(PushPlatform::Apns, Box::new(ApnsSender {
+            key: EncodingKey::from_ec_pem(&std::fs::read(path)?)?,
+            key_id: key_id.clone(),
+            team_id: team_id.clone(),
+            topic: topic.clone(),
+            host: if config.apns_sandbox { "api.sandbox.push.apple.com" } else { "api.push.apple.com" },
+            token: Mutex::new(None),
+            http_client,
+        }));
+    }
+    Ok(senders)
+}
+
+// A bearer token and when to stop using it
+type CachedToken = Mutex<Option<(String, DateTime<Utc>)>>;
+
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+/// Firebase Cloud Messaging, through the HTTP v1 API. Access tokens are
+/// exchanged for a service-account-signed JWT and reused until near expiry.
+struct FcmSender {
+    project_id: String,
+    account: ServiceAccountKey,
+    key: EncodingKey,
+    access_token: CachedToken,
+    http_client: reqwest::Client,
+}
+
+const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+
+impl FcmSender {
+    fn new(project_id: &str, credentials_path: &str, http_client: reqwest::Client) -> Result<Self, PushError> {
+        let account: ServiceAccountKey = serde_json::from_str(&std::fs::read_to_string(credentials_path)?)?;
+        Ok(Self {
+            project_id: project_id.to_string(),
+            key: EncodingKey::from_rsa_pem(account.private_key.as_bytes())?,
+            account,
+            access_token: Mutex::new(None),
+            http_client,
+        })
+    }
+
+    async fn access_token(&self) -> Result<String, PushError> {
+        let cached = self.access_token.lock().clone();
+        if let Some((token, expires_at)) = cached {
+            if expires_at > Utc::now() {
+                return Ok(token);
+            }
+        }
+
+        let now = Utc::now().timestamp();
+        let assertion = jsonwebtoken::encode(
+            &Header::new(Algorithm::RS256),
+            &json!({
+                "iss": self.account.client_email,
+                "scope": FCM_SCOPE,
+                "aud": self.account.token_uri,
+                "iat": now,
+                "exp": now + 3600,
+            }),
+            &self.key,
+        )?;
+        let response: Value = self.http_client.post(&self.account.token_uri)
+            .form(&[("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"), ("assertion", assertion.as_str())])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let token = response.get("access_token").and_then(Value::as_str)
+            .ok_or("Google token response has no access_token")?
+            .to_string();
+        let lifetime = response.get("expires_in").and_then(Value::as_i64).unwrap_or(3600);
+        // Renewed a minute early so a token never expires mid-request
+        let expires_at = Utc::now() + chrono::Duration::seconds(lifetime - 60);
+        *self.access_token.lock() = Some((token.clone(), expires_at));
+        Ok(token)
+    }
+}
+
+#[async_trait]
+impl PushSender for FcmSender {
+    async fn send(&self, message: PushMessage<'_>) -> Result<PushOutcome, PushError> {
+        let body = json!({
+            "message": {
+                "token": message.token,
+                "notification": { "title": message.title, "body": message.body },
+                "data": message.data,
+            }
+        });
+        let response = self.http_client
+            .post(format!("https://fcm.googleapis.com/v1/projects/{}/messages:send", self.project_id))
+            .bearer_auth(self.access_token().await?)
+            .json(&body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(PushOutcome::Delivered);
+        }
+        let detail = response.text().await.unwrap_or_default();
+        if status == reqwest::StatusCode::NOT_FOUND || detail.contains("UNREGISTERED") {
+            return Ok(PushOutcome::Unregistered);
+        }
+        Err(format!("FCM responded {}: {}", status, detail).into())
+    }
+}
+
+/// Apple Push Notification service, with token-based authentication.
+struct ApnsSender {
+    key: EncodingKey,
+    key_id: String,
+    team_id: String,
+    topic: String,
+    host: &'static str,
+    // APNs turns away tokens older than an hour and throttles ones renewed more often than every 20 minutes
+    token: CachedToken,
+    http_client: reqwest::Client,
+}
+
+impl ApnsSender {
+    fn provider_token(&self) -> Result<String, PushError> {
+        let mut cached = self.token.lock();
+        if let Some((token, renew_at)) = cached.as_ref() {
+            if *renew_at > Utc::now() {
+                return Ok(token.clone());
+            }
+        }
+
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(self.key_id.clone());
+        let token = jsonwebtoken::encode(&header, &json!({ "iss": self.team_id, "iat": Utc::now().timestamp() }), &self.key)?;
+        *cached = Some((token.clone(), Utc::now() + chrono::Duration::minutes(40)));
+        Ok(token)
+    }
+}
+
+#[async_trait]
+impl PushSender for ApnsSender {
+    async fn send(&self, message: PushMessage<'_>) -> Result<PushOutcome, PushError> {
+        let mut payload = json!({
+            "aps": {
+                "alert": { "title": message.title, "body": message.body },
+                "sound": "default",
+            }
+        });
+        for (key, value) in message.data {
+            payload[key.as_str()] = json!(value);
+        }
+
+        let response = self.http_client
+            .post(format!("https://{}/3/device/{}", self.host, message.token))
+            .bearer_auth(self.provider_token()?)
+            .header("apns-topic", &self.topic)
+            .header("apns-push-type", "alert")
+            .header("apns-priority", "10")
+            .json(&payload)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(PushOutcome::Delivered);
+        }
+        let detail = response.text().await.unwrap_or_default();
+        if status == reqwest::StatusCode::GONE || detail.contains("BadDeviceToken") {
+            return Ok(PushOutcome::Unregistered);
+        }
+        Err(format!("APNs responded {}: {}", status, detail).into())
+    }
+}
+
+struct Notification {
+    title: String,
+    body: String,
+    data: HashMap<String, String>,
+}
+
+struct Target {
+    device_id: String,
+    platform: PushPlatform,
+    token: String,
+}
+
+/// Device registration, push preferences, and pushes for settled bets and
+/// streams going live, sent as the outbox relays those events.
+pub struct PushService {
+    db_pool: Pool<Postgres>,
+    senders: HashMap<PushPlatform, Box<dyn PushSender>>,
+}
+
+impl PushService {
+    pub fn new(db_pool: Pool<Postgres>, senders: HashMap<PushPlatform, Box<dyn PushSender>>) -> Self {
+        Self { db_pool, senders }
+    }
+
+    pub fn is_enabled(&self, platform: PushPlatform) -> bool {
+        self.senders.contains_key(&platform)
+    }
+
+    pub fn has_senders(&self) -> bool {
+        !self.senders.is_empty()
+    }
+
+    /// Registers the device for the user, or refreshes it if the token is
+    /// already known, taking it over from whoever registered it before.
+    pub async fn register_device(&self, user_id: &str, device: &NewPushDevice) -> Result<PushDevice, PushError> {
+        let row = sqlx::query(
+            r#"
+            INSERT INTO push_devices (device_id, user_id, platform, token, app_version)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (tenant_id, platform, token) DO UPDATE
+            SET user_id = EXCLUDED.user_id, app_version = EXCLUDED.app_version, last_registered_at = NOW()
+            RETURNING device_id, platform, app_version, created_at, last_registered_at
+            "#
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(device.platform.as_str())
+        .bind(&device.token)
+        .bind(&device.app_version)
+        .fetch_one(&self.db_pool)
+        .await?;
+        device_from_row(&row)
+    }
+
+    pub async fn list_devices(&self, user_id: &str) -> Result<Vec<PushDevice>, PushError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT device_id, platform, app_version, created_at, last_registered_at
+            FROM push_devices
+            WHERE user_id = $1
+            ORDER BY last_registered_at DESC
+            "#
+        )
+        .bind(user_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+        rows.iter().map(device_from_row).collect()
+    }
+
+    /// Returns false if the user has no such device.
+    pub async fn remove_device(&self, user_id: &str, device_id: &str) -> Result<bool, PushError> {
+        let result = sqlx::query("DELETE FROM push_devices WHERE device_id = $1 AND user_id = $2")
+            .bind(device_id)
+            .bind(user_id)
+            .execute(&self.db_pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn preferences(&self, user_id: &str) -> Result<PushPreferences, PushError> {
+        let row = sqlx::query("SELECT bet_settled, stream_activated FROM push_preferences WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+        Ok(row
+            .map(|row| PushPreferences {
+                bet_settled: row.get("bet_settled"),
+                stream_activated: row.get("stream_activated"),
+            })
+            .unwrap_or_default())
+    }
+
+    pub async fn set_preferences(&self, user_id: &str, preferences: &PushPreferences) -> Result<(), PushError> {
+        sqlx::query(
+            r#"
+            INSERT INTO push_preferences (user_id, bet_settled, stream_activated)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (tenant_id, user_id) DO UPDATE
+            SET bet_settled = EXCLUDED.bet_settled, stream_activated = EXCLUDED.stream_activated, updated_at = NOW()
+            "#
+        )
+        .bind(user_id)
+        .bind(preferences.bet_settled)
+        .bind(preferences.stream_activated)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    // Devices of the given users who haven't turned this notification off
+    async fn targets(&self, user_ids: &[String], preference: &str) -> Result<Vec<Target>, PushError> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT d.device_id, d.platform, d.token
+            FROM push_devices d
+            LEFT JOIN push_preferences p USING (tenant_id, user_id)
+            WHERE d.user_id = ANY($1) AND COALESCE(p.{}, TRUE)
+            "#,
+            preference
+        ))
+        .bind(user_ids)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows.iter()
+            .filter_map(|row| {
+                let platform = PushPlatform::parse(row.get("platform"))?;
+                Some(Target { device_id: row.get("device_id"), platform, token: row.get("token") })
+            })
+            .filter(|target| self.is_enabled(target.platform))
+            .collect())
+    }
+
+    async fn pledgers(&self, stream_id: &str) -> Result<Vec<String>, PushError> {
+        let user_ids = sqlx::query_scalar(
+            "SELECT user_id FROM user_balances WHERE stream_id = $1 AND total_deposited > 0 AND deleted_at IS NULL"
+        )
+        .bind(stream_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+        Ok(user_ids)
+    }
+
+    async fn stream_title(&self, stream_id: &str) -> Result<Option<String>, PushError> {
+        let title = sqlx::query_scalar("SELECT title FROM streams WHERE id = $1")
+            .bind(stream_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+        Ok(title)
+    }
+
+    /// Sends to every device at once. Tokens the platform has forgotten are
+    /// removed. Fails only if nothing got through and some sends are worth
+    /// retrying, so a partial outage doesn't push the same thing twice.
+    async fn push(&self, targets: Vec<Target>, notification: &Notification) -> Result<(), PushError> {
+        let sends = targets.iter().map(|target| async move {
+            let sender = &self.senders[&target.platform];
+            let outcome = sender.send(PushMessage {
+                token: &target.token,
+                title: &notification.title,
+                body: &notification.body,
+                data: &notification.data,
+            }).await;
+            (target, outcome)
+        });
+
+        let mut delivered = 0;
+        let mut failed = 0;
+        for (target, outcome) in futures::future::join_all(sends).await {
+            match outcome {
+                Ok(PushOutcome::Delivered) => delivered += 1,
+                Ok(PushOutcome::Unregistered) => {
+                    info!("Removing unregistered {} device {}", target.platform.as_str(), target.device_id);
+                    if let Err(e) = sqlx::query("DELETE FROM push_devices WHERE device_id = $1")
+                        .bind(&target.device_id)
+                        .execute(&self.db_pool)
+                        .await
+                    {
+                        warn!("Failed to remove push device {}: {}", target.device_id, e);
+                    }
+                }
+                Err(e) => {
+                    warn!("Push to {} device {} failed: {}", target.platform.as_str(), target.device_id, e);
+                    failed += 1;
+                }
+            }
+        }
+
+        if delivered == 0 && failed > 0 {
+            return Err(format!("All {} pushes failed", failed).into());
+        }
+        Ok(())
+    }
+}
+
+fn device_from_row(row: &sqlx::postgres::PgRow) -> Result<PushDevice, PushError> {
+    let platform: String = row.get("platform");
+    Ok(PushDevice {
+        device_id: row.get("device_id"),
+        platform: PushPlatform::parse(&platform).ok_or_else(|| format!("Unknown push platform {}", platform))?,
+        app_version: row.get("app_version"),
+        created_at: row.get("created_at"),
+        last_registered_at: row.get("last_registered_at"),
+    })
+}
+
+fn settlement_notification(bet: &Bet) -> Notification {
+    let resolution = bet.resolution_result.as_ref();
+    let (title, body) = match resolution {
+        Some(resolution) if resolution.won => (
+            "You won!".to_string(),
+            format!("Your {} bet on {} paid out ${:.2}", bet.bet_type.as_str(), bet.stream_id, resolution.payout_amount),
+        ),
+        _ => (
+            "Bet settled".to_string(),
+            format!("Your ${:.2} {} bet on {} didn't win this time", bet.stake_amount, bet.bet_type.as_str(), bet.stream_id),
+        ),
+    };
+    Notification {
+        title,
+        body,
+        data: HashMap::from([
+            ("event_type".to_string(), "bet_settled".to_string()),
+            ("bet_id".to_string(), bet.id.clone()),
+            ("stream_id".to_string(), bet.stream_id.clone()),
+        ]),
+    }
+}
+
+#[async_trait]
+impl OutboxHandler for PushService {
+    fn name(&self) -> &'static str {
+        "push"
+    }
+
+    async fn handle(&self, event: &OutboxEvent) -> Result<(), OutboxError> {
+        let (targets, notification) = match event.event_type.as_str() {
+            "bet_settled" => {
+                let bet: Bet = serde_json::from_value(event.data.clone())?;
+                (self.targets(std::slice::from_ref(&bet.user_id), "bet_settled").await?, settlement_notification(&bet))
+            }
+            "stream_activated" => {
+                let stream_id = event.data.get("stream_id").and_then(Value::as_str)
+                    .ok_or("Stream activation event has no stream_id")?;
+                let title = self.stream_title(stream_id).await?.unwrap_or_else(|| stream_id.to_string());
+                let notification = Notification {
+                    title: format!("{} is live", title),
+                    body: "A stream you pledged to is live. Your betting balance is ready.".to_string(),
+                    data: HashMap::from([
+                        ("event_type".to_string(), "stream_activated".to_string()),
+                        ("stream_id".to_string(), stream_id.to_string()),
+                    ]),
+                };
+                (self.targets(&self.pledgers(stream_id).await?, "stream_activated").await?, notification)
+            }
+            _ => return Ok(()),
+        };
+
+        if targets.is_empty() {
+            return Ok(());
+        }
+        self.push(targets, &notification).await?;
+        Ok(())
+    }
+}
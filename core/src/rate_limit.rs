@@ -0,0 +1,198 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+use axum::extract::{ConnectInfo, Request};
+use axum::http::{HeaderMap, Method};
+use axum::response::{IntoResponse, Response};
+use futures::future::BoxFuture;
+use redis::aio::ConnectionManager;
+use tokio::sync::watch;
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::auth::{Principal, Role};
+use crate::config::RateLimitConfig;
+use crate::error::ApiError;
+use crate::tenant;
+
+type RateLimitError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Which counter a request is charged to. Expensive endpoints each get their
+/// own, so a burst of bets doesn't also lock a user out of browsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bucket {
+    Default,
+    PlaceBet,
+    VerifyLocation,
+}
+
+impl Bucket {
+    pub fn for_request(method: &Method, path: &str) -> Self {
+        match (method, path) {
+            (&Method::POST, "/api/betting/place") => Bucket::PlaceBet,
+            (&Method::POST, "/api/geolocation/verify") => Bucket::VerifyLocation,
+            _ => Bucket::Default,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bucket::Default => "default",
+            Bucket::PlaceBet => "place_bet",
+            Bucket::VerifyLocation => "verify_location",
+        }
+    }
+}
+
+pub enum RateLimitDecision {
+    Allowed,
+    Limited { retry_after_secs: u64 },
+}
+
+/// Fixed-window request counters in Redis, shared by every core instance.
+/// Limits are read per request, so a configuration reload applies at once.
+pub struct RateLimiter {
+    config: watch::Receiver<RateLimitConfig>,
+    redis: ConnectionManager,
+}
+
+impl RateLimiter {
+    pub async fn connect(redis_url: &str, config: watch::Receiver<RateLimitConfig>) -> Result<Self, RateLimitError> {
+        let client = redis::Client::open(redis_url)?;
+        let redis = ConnectionManager::new(client).await?;
+        Ok(Self { config, redis })
+    }
+
+    fn enabled(&self) -> bool {
+        self.config.borrow().enabled
+    }
+
+    fn limit_for(&self, bucket: Bucket) -> u64 {
+        let config = self.config.borrow();
+        match bucket {
+            Bucket::Default => config.default_limit,
+            Bucket::PlaceBet | Bucket::VerifyLocation => config.expensive_limit,
+        }
+    }
+
+    /// Counts one request from `identity` against `bucket`.
+    pub async fn check(&self, bucket: Bucket, identity: &str) -> Result<RateLimitDecision, RateLimitError> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let window = self.config.borrow().window_secs;
+        let window_start = now - now % window;
+        let key = tenant::redis_key(&format!("ratelimit:{}:{}:{}", bucket.name(), identity, window_start));
+
+        // The key outlives its window slightly so a late INCR can't resurrect it without a TTL
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(&key, 1u64)
+            .expire(&key, (window + 1) as i64).ignore()
+            .query_async(&mut self.redis.clone())
+            .await?;
+
+        if count > self.limit_for(bucket) {
+            Ok(RateLimitDecision::Limited { retry_after_secs: (window_start + window - now).max(1) })
+        } else {
+            Ok(RateLimitDecision::Allowed)
+        }
+    }
+
+    pub fn layer(self: &Arc<Self>) -> RateLimitLayer {
+        RateLimitLayer { limiter: self.clone() }
+    }
+}
+
+// Signed-in callers are counted per principal, everyone else per client IP
+fn identity_for(request: &Request) -> Option<String> {
+    if let Some(principal) = request.extensions().get::<Principal>() {
+        return Some(format!("principal:{}", principal.subject));
+    }
+    client_ip(request.headers())
+        .or_else(|| {
+            request.extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string())
+        })
+        .map(|ip| format!("ip:{}", ip))
+}
+
+// The first hop in X-Forwarded-For is the original client when behind the gateway
+pub(crate) fn client_ip(headers: &HeaderMap) -> Option<String> {
+    headers.get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty())
+}
+
+fn is_exempt(request: &Request) -> bool {
+    // Internal callers (admins, and services such as the analytics sidecar
+    // pushing frames) are trusted not to flood us
+    request.extensions()
+        .get::<Principal>()
+        .map(|principal| principal.is_admin() || principal.has_role(Role::Service))
+        .unwrap_or(false)
+}
+
+/// Tower layer that rejects requests over their bucket's limit with `429`.
+/// Must run inside the authentication layer so principals are known.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit { inner, limiter: self.limiter.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S> Service<Request> for RateLimit<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let limiter = self.limiter.clone();
+        // Keep the instance poll_ready was called on; leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            if !limiter.enabled() || is_exempt(&request) {
+                return inner.call(request).await;
+            }
+
+            let bucket = Bucket::for_request(request.method(), request.uri().path());
+            if let Some(identity) = identity_for(&request) {
+                match limiter.check(bucket, &identity).await {
+                    Ok(RateLimitDecision::Limited { retry_after_secs }) => {
+                        return Ok(ApiError::rate_limited(retry_after_secs).into_response());
+                    }
+                    Ok(RateLimitDecision::Allowed) => {}
+                    // Fail open: an unavailable Redis shouldn't take the API down with it
+                    Err(e) => warn!("Rate limit check failed for {}: {}", identity, e),
+                }
+            }
+
+            inner.call(request).await
+        })
+    }
+}
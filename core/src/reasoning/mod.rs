@@ -2,11 +2,21 @@ pub mod imperative;
 pub mod logical;
 pub mod fuzzy;
 pub mod hybrid_engine;
+pub mod simulation;
+pub mod traces;
 
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use tracing::warn;
+
+use crate::analytics::AnalyticsEvent;
+use crate::cache::{BoundedCache, CacheStats};
+use crate::config::ReasoningConfig;
+use crate::shutdown::Shutdown;
+use traces::{StoredEvaluation, TraceStore};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BetOutcome {
@@ -167,15 +177,27 @@ pub struct HybridReasoningEngine {
     // State management
     active_bets: Arc<RwLock<HashMap<String, BetCondition>>>,
     prize_pools: Arc<RwLock<HashMap<String, PrizePool>>>,
-    reasoning_cache: Arc<RwLock<HashMap<String, BetOutcome>>>,
+    reasoning_cache: Arc<BoundedCache<String, BetOutcome>>,
+    // Every evaluation, kept after the cache lets it go
+    traces: TraceStore,
     
     // Paradigm weights for hybrid decisions
     paradigm_weights: Arc<RwLock<HashMap<String, f64>>>,
+    
+    config: watch::Receiver<ReasoningConfig>,
 }
 
 impl HybridReasoningEngine {
-    pub async fn new() -> Self {
-        Self {
+    pub async fn new(
+        config: watch::Receiver<ReasoningConfig>,
+        db_pool: Pool<Postgres>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let initial = config.borrow().clone();
+        initial.validate().map_err(|e| e.to_string())?;
+        
+        // The sub-engines have no settings of their own: every ReasoningConfig field
+        // gates, weights or classifies their results here, so reloads need no rebuild
+        Ok(Self {
             imperative_engine: Arc::new(imperative::ImperativeEngine::new()),
             logical_engine: Arc::new(logical::LogicalEngine::new()),
             fuzzy_engine: Arc::new(fuzzy::FuzzyEngine::new()),
@@ -183,20 +205,44 @@ impl HybridReasoningEngine {
             
             active_bets: Arc::new(RwLock::new(HashMap::new())),
             prize_pools: Arc::new(RwLock::new(HashMap::new())),
-            reasoning_cache: Arc::new(RwLock::new(HashMap::new())),
+            reasoning_cache: Arc::new(BoundedCache::new("reasoning_outcomes", initial.reasoning_cache_size, None)),
+            traces: TraceStore::new(db_pool),
             
-            paradigm_weights: Arc::new(RwLock::new(HashMap::from([
-                ("imperative".to_string(), 0.4),
-                ("logical".to_string(), 0.3),
-                ("fuzzy".to_string(), 0.3),
-            ]))),
-        }
+            paradigm_weights: Arc::new(RwLock::new(Self::configured_weights(&initial))),
+            
+            config,
+        })
+    }
+    
+    fn configured_weights(config: &ReasoningConfig) -> HashMap<String, f64> {
+        HashMap::from([
+            ("imperative".to_string(), config.imperative_weight),
+            ("logical".to_string(), config.logical_weight),
+            ("fuzzy".to_string(), config.fuzzy_weight),
+        ])
+    }
+    
+    /// Resets the paradigm weights whenever the configuration is reloaded.
+    /// Thresholds and toggles need no help; they are read at each evaluation.
+    pub fn watch_config(self: &Arc<Self>, shutdown: &Shutdown) {
+        let engine = self.clone();
+        let mut config = self.config.clone();
+        shutdown.spawn_loop("reasoning:config_watch", async move {
+            while config.changed().await.is_ok() {
+                let weights = Self::configured_weights(&config.borrow_and_update());
+                engine.update_paradigm_weights(weights).await;
+            }
+        });
+    }
+    
+    pub fn config(&self) -> ReasoningConfig {
+        self.config.borrow().clone()
     }
     
     pub async fn evaluate_bet_outcome(
         &self,
         bet_id: &str,
-        event_data: &serde_json::Value,
+        event: &AnalyticsEvent,
         context: &HashMap<String, serde_json::Value>
     ) -> Result<BetOutcome, Box<dyn std::error::Error + Send + Sync>> {
         let bet_condition = {
@@ -204,14 +250,38 @@ impl HybridReasoningEngine {
             bets.get(bet_id).cloned()
                 .ok_or("Bet not found")?
         };
+        // The paradigms look fields up by name, whatever the event's type
+        let event_data = &event.to_value();
+        
+        let outcome = self.evaluate_condition(bet_id, &bet_condition, event_data, context).await?;
+        
+        // An outcome that can't be stored is still returned; only its explanation is lost
+        let max_payload_bytes = self.config.borrow().trace_max_payload_bytes;
+        if let Err(e) = self.traces.record(bet_id, event_data, context, &outcome, max_payload_bytes).await {
+            warn!("Failed to store the reasoning trace for bet {}: {}", bet_id, e);
+        }
+        
+        // Cache result
+        self.cache_outcome(bet_id, outcome.clone()).await;
         
+        Ok(outcome)
+    }
+    
+    // Runs all paradigms and synthesizes an outcome without touching the cache
+    async fn evaluate_condition(
+        &self,
+        bet_id: &str,
+        bet_condition: &BetCondition,
+        event_data: &serde_json::Value,
+        context: &HashMap<String, serde_json::Value>
+    ) -> Result<BetOutcome, Box<dyn std::error::Error + Send + Sync>> {
         let mut reasoning_trace = Vec::new();
         
         // Parallel evaluation across paradigms
         let (imperative_result, logical_result, fuzzy_result) = tokio::join!(
-            self.evaluate_imperative(&bet_condition, event_data, context),
-            self.evaluate_logical(&bet_condition, event_data, context),
-            self.evaluate_fuzzy(&bet_condition, event_data, context)
+            self.evaluate_imperative(bet_condition, event_data, context),
+            self.evaluate_logical(bet_condition, event_data, context),
+            self.evaluate_fuzzy(bet_condition, event_data, context)
         );
         
         // Record reasoning steps
@@ -249,22 +319,20 @@ impl HybridReasoningEngine {
         }
         
         // Hybrid synthesis
-        let hybrid_outcome = self.synthesize_hybrid_outcome(
+        self.synthesize_hybrid_outcome(
             bet_id,
-            &bet_condition,
+            bet_condition,
             imperative_result.ok(),
             logical_result.ok(),
             fuzzy_result.ok(),
             reasoning_trace
-        ).await?;
-        
-        // Cache result
-        {
-            let mut cache = self.reasoning_cache.write().await;
-            cache.insert(bet_id.to_string(), hybrid_outcome.clone());
-        }
-        
-        Ok(hybrid_outcome)
+        ).await
+    }
+    
+    async fn cache_outcome(&self, bet_id: &str, outcome: BetOutcome) {
+        // The size follows configuration reloads
+        self.reasoning_cache.set_max_entries(self.config.borrow().reasoning_cache_size);
+        self.reasoning_cache.insert(bet_id.to_string(), outcome);
     }
     
     async fn evaluate_imperative(
@@ -273,6 +341,9 @@ impl HybridReasoningEngine {
         event_data: &serde_json::Value,
         context: &HashMap<String, serde_json::Value>
     ) -> Result<imperative::ImperativeResult, Box<dyn std::error::Error + Send + Sync>> {
+        if !self.config.borrow().imperative_enabled {
+            return Err("Imperative engine disabled".into());
+        }
         self.imperative_engine.evaluate(bet_condition, event_data, context).await
     }
     
@@ -282,6 +353,9 @@ impl HybridReasoningEngine {
         event_data: &serde_json::Value,
         context: &HashMap<String, serde_json::Value>
     ) -> Result<logical::LogicalResult, Box<dyn std::error::Error + Send + Sync>> {
+        if !self.config.borrow().logical_enabled {
+            return Err("Logical engine disabled".into());
+        }
         self.logical_engine.evaluate(bet_condition, event_data, context).await
     }
     
@@ -291,6 +365,9 @@ impl HybridReasoningEngine {
         event_data: &serde_json::Value,
         context: &HashMap<String, serde_json::Value>
     ) -> Result<fuzzy::FuzzyResult, Box<dyn std::error::Error + Send + Sync>> {
+        if !self.config.borrow().fuzzy_enabled {
+            return Err("Fuzzy engine disabled".into());
+        }
         self.fuzzy_engine.evaluate(bet_condition, event_data, context).await
     }
     
@@ -303,19 +380,20 @@ impl HybridReasoningEngine {
         fuzzy_result: Option<fuzzy::FuzzyResult>,
         reasoning_trace: Vec<ReasoningStep>
     ) -> Result<BetOutcome, Box<dyn std::error::Error + Send + Sync>> {
+        let cfg = self.config();
         let weights = self.paradigm_weights.read().await;
         
         // Calculate hybrid scores
         let imperative_score = imperative_result.as_ref()
-            .map(|r| r.score * weights.get("imperative").unwrap_or(&0.4))
+            .map(|r| r.score * weights.get("imperative").unwrap_or(&cfg.imperative_weight))
             .unwrap_or(0.0);
             
         let logical_score = logical_result.as_ref()
-            .map(|r| if r.satisfied { 1.0 } else { 0.0 } * weights.get("logical").unwrap_or(&0.3))
+            .map(|r| if r.satisfied { 1.0 } else { 0.0 } * weights.get("logical").unwrap_or(&cfg.logical_weight))
             .unwrap_or(0.0);
             
         let fuzzy_score = fuzzy_result.as_ref()
-            .map(|r| r.membership * weights.get("fuzzy").unwrap_or(&0.3))
+            .map(|r| r.membership * weights.get("fuzzy").unwrap_or(&cfg.fuzzy_weight))
             .unwrap_or(0.0);
         
         let total_score = imperative_score + logical_score + fuzzy_score;
@@ -381,11 +459,14 @@ impl HybridReasoningEngine {
     }
     
     fn determine_outcome_type(&self, total_score: f64, confidence: f64) -> OutcomeType {
+        let cfg = self.config.borrow();
         match (total_score, confidence) {
-            (s, c) if s >= 0.8 && c >= 0.9 => OutcomeType::Win,
-            (s, c) if s <= 0.2 && c >= 0.9 => OutcomeType::Loss,
-            (s, c) if s >= 0.4 && s < 0.8 && c >= 0.7 => OutcomeType::PartialWin,
-            (_, c) if c < 0.5 => OutcomeType::Uncertain,
+            (s, c) if s >= cfg.win_score_threshold && c >= cfg.high_confidence_threshold => OutcomeType::Win,
+            (s, c) if s <= cfg.loss_score_threshold && c >= cfg.high_confidence_threshold => OutcomeType::Loss,
+            (s, c) if s >= cfg.partial_win_score_threshold
+                && s < cfg.win_score_threshold
+                && c >= cfg.partial_win_confidence_threshold => OutcomeType::PartialWin,
+            (_, c) if c < cfg.uncertain_confidence_threshold => OutcomeType::Uncertain,
             (s, _) if s >= 0.3 && s < 0.7 => OutcomeType::Split,
             _ => OutcomeType::Void,
         }
@@ -399,7 +480,7 @@ impl HybridReasoningEngine {
         confidence: f64
     ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
         // This would integrate with the actual betting system to get bet amounts
-        let base_amount = 100.0; // Placeholder
+        let base_amount = self.config.borrow().default_settlement_base;
         
         match outcome_type {
             OutcomeType::Win => Ok(base_amount * 2.0),
@@ -562,6 +643,10 @@ impl HybridReasoningEngine {
         bets.insert(bet_id, condition);
     }
     
+    pub async fn get_active_bet_conditions(&self) -> HashMap<String, BetCondition> {
+        self.active_bets.read().await.clone()
+    }
+    
     pub async fn add_prize_pool(&self, pool: PrizePool) {
         let mut pools = self.prize_pools.write().await;
         pools.insert(pool.pool_id.clone(), pool);
@@ -574,8 +659,38 @@ impl HybridReasoningEngine {
         }
     }
     
+    /// The outcome cache's size and counters, for `/metrics`.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.reasoning_cache.stats()
+    }
+    
+    /// The latest evaluation's reasoning steps, from the cache or, once it
+    /// has moved on, the stored trace.
     pub async fn get_reasoning_trace(&self, bet_id: &str) -> Option<Vec<ReasoningStep>> {
-        let cache = self.reasoning_cache.read().await;
-        cache.get(bet_id).map(|outcome| outcome.reasoning_trace.clone())
+        if let Some(outcome) = self.reasoning_cache.get(&bet_id.to_string()) {
+            return Some(outcome.reasoning_trace);
+        }
+        match self.traces.latest(bet_id).await {
+            Ok(stored) => stored.and_then(|evaluation| evaluation.steps()),
+            Err(e) => {
+                warn!("Failed to load the stored reasoning trace for bet {}: {}", bet_id, e);
+                None
+            }
+        }
+    }
+    
+    /// The latest stored evaluation of a bet, with the inputs it was made from.
+    pub async fn explain(&self, bet_id: &str) -> Result<Option<StoredEvaluation>, Box<dyn std::error::Error + Send + Sync>> {
+        self.traces.latest(bet_id).await
+    }
+    
+    /// Every stored evaluation of a bet, newest first, and how many there are.
+    pub async fn evaluations(
+        &self,
+        bet_id: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<(Vec<StoredEvaluation>, usize), Box<dyn std::error::Error + Send + Sync>> {
+        self.traces.for_bet(bet_id, limit, offset).await
     }
 } 
\ No newline at end of file
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{Pool, Postgres, Row};
+use uuid::Uuid;
+
+use super::{BetOutcome, ReasoningStep};
+
+pub type TraceError = Box<dyn std::error::Error + Send + Sync>;
+
+/// One evaluation of a bet as stored: what it was evaluated against, how
+/// each paradigm reasoned about it, and what it concluded.
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredEvaluation {
+    pub evaluation_id: String,
+    pub bet_id: String,
+    pub event_snapshot: Value,
+    pub context_snapshot: Value,
+    // The `ReasoningStep`s, unless `payloads_truncated` cut them down to a preview
+    pub trace: Value,
+    pub outcome: Value,
+    pub payloads_truncated: bool,
+    pub evaluated_at: DateTime<Utc>,
+}
+
+impl StoredEvaluation {
+    /// The reasoning steps, or None if they were too large to store whole.
+    pub fn steps(&self) -> Option<Vec<ReasoningStep>> {
+        serde_json::from_value(self.trace.clone()).ok()
+    }
+}
+
+/// Reasoning traces in Postgres, kept for every evaluation; the engine's
+/// cache holds only the latest outcome for recent bets.
+pub struct TraceStore {
+    db_pool: Pool<Postgres>,
+}
+
+impl TraceStore {
+    pub fn new(db_pool: Pool<Postgres>) -> Self {
+        Self { db_pool }
+    }
+
+    /// Stores an evaluation, cutting any payload over `max_payload_bytes`
+    /// down to a preview. Returns the evaluation ID.
+    pub async fn record(
+        &self,
+        bet_id: &str,
+        event_data: &Value,
+        context: &HashMap<String, Value>,
+        outcome: &BetOutcome,
+        max_payload_bytes: usize,
+    ) -> Result<String, TraceError> {
+        // The trace is stored on its own, so the outcome doesn't carry it twice
+        let mut outcome_value = serde_json::to_value(outcome)?;
+        if let Some(fields) = outcome_value.as_object_mut() {
+            fields.remove("reasoning_trace");
+        }
+
+        let (event_snapshot, event_cut) = bounded(event_data.clone(), max_payload_bytes)?;
+        let (context_snapshot, context_cut) = bounded(serde_json::to_value(context)?, max_payload_bytes)?;
+        let (trace, trace_cut) = bounded(serde_json::to_value(&outcome.reasoning_trace)?, max_payload_bytes)?;
+
+        let evaluation_id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO reasoning_traces (
+                evaluation_id, bet_id, event_snapshot, context_snapshot, trace, outcome, payloads_truncated
+            ) VALUES ($1::uuid, $2, $3, $4, $5, $6, $7)
+            "#
+        )
+        .bind(&evaluation_id)
+        .bind(bet_id)
+        .bind(event_snapshot)
+        .bind(context_snapshot)
+        .bind(trace)
+        .bind(outcome_value)
+        .bind(event_cut || context_cut || trace_cut)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(evaluation_id)
+    }
+
+    pub async fn latest(&self, bet_id: &str) -> Result<Option<StoredEvaluation>, TraceError> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM reasoning_traces WHERE bet_id = $1 ORDER BY evaluated_at DESC LIMIT 1",
+            TRACE_COLUMNS
+        ))
+        .bind(bet_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+        Ok(row.map(|row| evaluation_from_row(&row)))
+    }
+
+    /// A bet's evaluations, newest first, and how many there are.
+    pub async fn for_bet(&self, bet_id: &str, limit: usize, offset: usize) -> Result<(Vec<StoredEvaluation>, usize), TraceError> {
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM reasoning_traces WHERE bet_id = $1")
+            .bind(bet_id)
+            .fetch_one(&self.db_pool)
+            .await?;
+
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM reasoning_traces WHERE bet_id = $1 ORDER BY evaluated_at DESC LIMIT $2 OFFSET $3",
+            TRACE_COLUMNS
+        ))
+        .bind(bet_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok((rows.iter().map(evaluation_from_row).collect(), total as usize))
+    }
+}
+
+const TRACE_COLUMNS: &str =
+    "evaluation_id::text AS evaluation_id, bet_id, event_snapshot, context_snapshot, trace, outcome, payloads_truncated, evaluated_at";
+
+fn evaluation_from_row(row: &sqlx::postgres::PgRow) -> StoredEvaluation {
+    StoredEvaluation {
+        evaluation_id: row.get("evaluation_id"),
+        bet_id: row.get("bet_id"),
+        event_snapshot: row.get("event_snapshot"),
+        context_snapshot: row.get("context_snapshot"),
+        trace: row.get("trace"),
+        outcome: row.get("outcome"),
+        payloads_truncated: row.get("payloads_truncated"),
+        evaluated_at: row.get("evaluated_at"),
+    }
+}
+
+// Replaces a payload too large to keep with the start of its JSON, and says whether it did
+fn bounded(value: Value, max_bytes: usize) -> Result<(Value, bool), TraceError> {
+    let encoded = serde_json::to_string(&value)?;
+    if encoded.len() <= max_bytes {
+        return Ok((value, false));
+    }
+
+    let mut end = max_bytes;
+    while !encoded.is_char_boundary(end) {
+        end -= 1;
+    }
+    Ok((
+        serde_json::json!({
+            "truncated": true,
+            "original_bytes": encoded.len(),
+            "preview": &encoded[..end],
+        }),
+        true,
+    ))
+}
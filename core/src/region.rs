@@ -0,0 +1,237 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+
+use crate::config::RegionConfig;
+use crate::events::EventSubscriber;
+use crate::outbox::{OutboxError, OutboxEvent, OutboxHandler};
+use crate::shutdown::Shutdown;
+use crate::stream::StreamManager;
+use crate::tenant::{self, Tenant};
+use crate::webhooks::WebhookEventType;
+
+// Round trip per kilometre of great-circle distance: light in fibre covers
+// about 200km a millisecond, and routes are rarely straight
+const RTT_MS_PER_KM: f64 = 1.0 / 80.0;
+
+/// A region's public endpoints and where it is, for routing clients to it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RegionEndpoints {
+    pub id: String,
+    // Base of the region's WebSocket URLs, e.g. wss://eu-west.example.com/ws
+    pub websocket_url: String,
+    // Base of the region's HLS playlists, served as `<hls_url>/<stream_id>/index.m3u8`
+    pub hls_url: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// One region a client may use, with the round trip expected from where it is.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RegionRoute {
+    pub region: String,
+    pub url: String,
+    // An estimate from distance alone; good for ordering, not a promise
+    pub estimated_rtt_ms: f64,
+}
+
+/// Where a client should connect, best first. Empty lists mean there is
+/// only the one region, and the client should stay where it is.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct GeoRoutes {
+    // The region that answered
+    pub region: String,
+    // Where the stream's ingest lands, if a stream was asked about and it has been activated
+    pub stream_region: Option<String>,
+    pub websocket: Vec<RegionRoute>,
+    pub hls: Vec<RegionRoute>,
+}
+
+/// Ranks the configured regions for a client by estimated latency.
+pub struct RegionRouter {
+    config: RegionConfig,
+}
+
+impl RegionRouter {
+    pub fn new(config: RegionConfig) -> Self {
+        Self { config }
+    }
+
+    /// This instance's region.
+    pub fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    /// Routes for a client at `position` (latitude, longitude), or near this
+    /// region if it didn't say. WebSocket endpoints are ranked by the round
+    /// trip to them; HLS endpoints also pay for the hop from the stream's
+    /// ingest region, which the other regions pull segments from.
+    pub fn route(&self, stream: Option<(&str, Option<&str>)>, position: Option<(f64, f64)>) -> GeoRoutes {
+        let here = self.config.regions.iter().find(|region| region.id == self.config.id);
+        let origin = position.or_else(|| here.map(|region| (region.latitude, region.longitude)));
+        let stream_region = stream.and_then(|(_, region)| region);
+        let ingest = stream_region.and_then(|id| self.config.regions.iter().find(|region| region.id == id));
+
+        let rtt_to = |region: &RegionEndpoints| {
+            origin.map(|(lat, lon)| rtt_ms(lat, lon, region.latitude, region.longitude)).unwrap_or(0.0)
+        };
+
+        let mut websocket: Vec<RegionRoute> = self.config.regions.iter()
+            .map(|region| RegionRoute {
+                region: region.id.clone(),
+                url: region.websocket_url.clone(),
+                estimated_rtt_ms: rtt_to(region),
+            })
+            .collect();
+
+        let mut hls = Vec::new();
+        if let Some((stream_id, _)) = stream {
+            hls = self.config.regions.iter()
+                .map(|region| {
+                    let pull = ingest
+                        .map(|ingest| rtt_ms(region.latitude, region.longitude, ingest.latitude, ingest.longitude))
+                        .unwrap_or(0.0);
+                    RegionRoute {
+                        region: region.id.clone(),
+                        url: format!("{}/{}/index.m3u8", region.hls_url.trim_end_matches('/'), stream_id),
+                        estimated_rtt_ms: rtt_to(region) + pull,
+                    }
+                })
+                .collect();
+        }
+
+        for routes in [&mut websocket, &mut hls] {
+            routes.sort_by(|a, b| a.estimated_rtt_ms.total_cmp(&b.estimated_rtt_ms));
+        }
+
+        GeoRoutes {
+            region: self.config.id.clone(),
+            stream_region: stream_region.map(str::to_string),
+            websocket,
+            hls,
+        }
+    }
+}
+
+fn rtt_ms(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    // Haversine, in kilometres
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    let km = 6371.0 * 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+    km * RTT_MS_PER_KM
+}
+
+/// An event as `EventBusRelay` puts it on the bus.
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    event_id: String,
+    event_type: String,
+    occurred_at: DateTime<Utc>,
+    tenant_id: String,
+    region: Option<String>,
+    data: Value,
+}
+
+/// Hands events other regions published on the bus to this region's
+/// handlers. Delivery is best effort: each region's outbox stays the record
+/// of its own events, and a handler that fails isn't retried.
+pub struct RegionReplicator {
+    region: String,
+    handlers: Vec<Arc<dyn OutboxHandler>>,
+    default_tenant: String,
+    shutdown: Shutdown,
+}
+
+impl RegionReplicator {
+    pub fn new(region: String, handlers: Vec<Arc<dyn OutboxHandler>>, default_tenant: String, shutdown: Shutdown) -> Self {
+        Self { region, handlers, default_tenant, shutdown }
+    }
+
+    pub fn start(self: &Arc<Self>, mut subscriber: Box<dyn EventSubscriber>) {
+        let replicator = self.clone();
+        self.shutdown.spawn_loop("region:replicator", async move {
+            while let Some(message) = subscriber.next().await {
+                match message {
+                    Ok(payload) => replicator.apply(&payload).await,
+                    Err(e) => warn!("Failed to receive a replicated event: {}", e),
+                }
+            }
+            warn!("Event bus subscription ended; events from other regions are no longer replicated");
+        });
+        info!("Replicating events from other regions into {} with {} handlers", self.region, self.handlers.len());
+    }
+
+    async fn apply(&self, payload: &[u8]) {
+        let envelope: Envelope = match serde_json::from_slice(payload) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                warn!("Skipping an event bus message that isn't an event envelope: {}", e);
+                return;
+            }
+        };
+        // Our own events were handled by our outbox; unlabelled ones predate regions
+        match &envelope.region {
+            Some(region) if *region != self.region => {}
+            _ => return,
+        }
+
+        let event = OutboxEvent {
+            event_id: envelope.event_id,
+            event_type: envelope.event_type,
+            data: envelope.data,
+            occurred_at: envelope.occurred_at,
+            tenant_id: envelope.tenant_id,
+        };
+        let tenant = Tenant { is_default: event.tenant_id == self.default_tenant, id: event.tenant_id.clone() };
+        tenant::scope(tenant, async {
+            for handler in &self.handlers {
+                if let Err(e) = handler.handle(&event).await {
+                    warn!(
+                        "Replica handler {} failed on {} event {} from {:?}: {}",
+                        handler.name(), event.event_type, event.event_id, envelope.region, e
+                    );
+                }
+            }
+        }).await;
+        debug!("Replicated {} event {} from {:?}", event.event_type, event.event_id, envelope.region);
+    }
+}
+
+/// Reloads streams another region activated or stopped, so this region
+/// serves their current state rather than what it last cached.
+pub struct ReplicatedStreams {
+    streams: Arc<StreamManager>,
+}
+
+impl ReplicatedStreams {
+    pub fn new(streams: Arc<StreamManager>) -> Self {
+        Self { streams }
+    }
+}
+
+#[async_trait]
+impl OutboxHandler for ReplicatedStreams {
+    fn name(&self) -> &'static str {
+        "replicated_streams"
+    }
+
+    async fn handle(&self, event: &OutboxEvent) -> Result<(), OutboxError> {
+        let is_stream_event = [WebhookEventType::StreamActivated, WebhookEventType::StreamStopped]
+            .iter()
+            .any(|event_type| event_type.as_str() == event.event_type);
+        if !is_stream_event {
+            return Ok(());
+        }
+        let Some(stream_id) = event.data.get("stream_id").and_then(Value::as_str) else {
+            return Ok(());
+        };
+        self.streams.reload_stream(stream_id).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::watch;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::config::{ApiKeyConfig, BettingConfig, Config, DreamingConfig, RateLimitConfig, ReasoningConfig};
+
+type ReloadError = Box<dyn std::error::Error + Send + Sync>;
+
+// Sections long-running tasks re-read on change; everything else needs a restart
+const RELOADABLE: &[&str] = &["rate_limit", "api_keys", "reasoning_config", "betting", "orchestrator.dreaming"];
+
+/// What one reload changed, by dotted section name.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReloadReport {
+    // Sections now in effect
+    pub applied: Vec<String>,
+    // Sections that changed on disk or in the environment but were left as they were
+    pub requires_restart: Vec<String>,
+}
+
+/// Re-reads the configuration file and environment on demand and publishes the
+/// safe-to-change sections to the modules subscribed to them.
+pub struct ConfigReloader {
+    path: Option<PathBuf>,
+    // What the process is running with: reloadable sections as last applied, the rest as started
+    running: Mutex<Config>,
+    rate_limit: watch::Sender<RateLimitConfig>,
+    api_keys: watch::Sender<ApiKeyConfig>,
+    reasoning: watch::Sender<ReasoningConfig>,
+    betting: watch::Sender<BettingConfig>,
+    dreaming: watch::Sender<DreamingConfig>,
+}
+
+impl ConfigReloader {
+    pub fn new(path: Option<PathBuf>, config: &Config) -> Self {
+        Self {
+            path,
+            running: Mutex::new(config.clone()),
+            rate_limit: watch::Sender::new(config.rate_limit.clone()),
+            api_keys: watch::Sender::new(config.api_keys.clone()),
+            reasoning: watch::Sender::new(config.reasoning_config.clone()),
+            betting: watch::Sender::new(config.betting.clone()),
+            dreaming: watch::Sender::new(config.orchestrator.dreaming.clone()),
+        }
+    }
+
+    pub fn rate_limit(&self) -> watch::Receiver<RateLimitConfig> {
+        self.rate_limit.subscribe()
+    }
+
+    pub fn api_keys(&self) -> watch::Receiver<ApiKeyConfig> {
+        self.api_keys.subscribe()
+    }
+
+    pub fn reasoning(&self) -> watch::Receiver<ReasoningConfig> {
+        self.reasoning.subscribe()
+    }
+
+    pub fn betting(&self) -> watch::Receiver<BettingConfig> {
+        self.betting.subscribe()
+    }
+
+    pub fn dreaming(&self) -> watch::Receiver<DreamingConfig> {
+        self.dreaming.subscribe()
+    }
+
+    /// Loads the configuration again and applies the reloadable sections that
+    /// changed. Nothing is applied if the new configuration is invalid.
+    pub fn reload(&self) -> Result<ReloadReport, ReloadError> {
+        let loaded = Config::load(self.path.as_deref())?;
+        let mut running = self.running.lock();
+
+        let before = sections(&running)?;
+        let after = sections(&loaded)?;
+        let mut report = ReloadReport { applied: Vec::new(), requires_restart: Vec::new() };
+        for (name, value) in &after {
+            if before.iter().any(|(old_name, old_value)| old_name == name && old_value == value) {
+                continue;
+            }
+            if RELOADABLE.contains(&name.as_str()) {
+                report.applied.push(name.clone());
+            } else {
+                report.requires_restart.push(name.clone());
+            }
+        }
+
+        let changed = |section: &str| report.applied.iter().any(|name| name == section);
+        if changed("rate_limit") {
+            running.rate_limit = loaded.rate_limit.clone();
+            self.rate_limit.send_replace(loaded.rate_limit);
+        }
+        if changed("api_keys") {
+            running.api_keys = loaded.api_keys.clone();
+            self.api_keys.send_replace(loaded.api_keys);
+        }
+        if changed("reasoning_config") {
+            running.reasoning_config = loaded.reasoning_config.clone();
+            self.reasoning.send_replace(loaded.reasoning_config);
+        }
+        if changed("betting") {
+            running.betting = loaded.betting.clone();
+            self.betting.send_replace(loaded.betting);
+        }
+        if changed("orchestrator.dreaming") {
+            running.orchestrator.dreaming = loaded.orchestrator.dreaming.clone();
+            self.dreaming.send_replace(loaded.orchestrator.dreaming);
+        }
+
+        if !report.applied.is_empty() {
+            info!("Reloaded configuration: {}", report.applied.join(", "));
+        }
+        if !report.requires_restart.is_empty() {
+            warn!("Configuration changes need a restart to take effect: {}", report.requires_restart.join(", "));
+        }
+        Ok(report)
+    }
+}
+
+// Top-level settings and sections by name, with orchestrator sections split out
+fn sections(config: &Config) -> Result<Vec<(String, Value)>, ReloadError> {
+    let Value::Object(top) = serde_json::to_value(config)? else {
+        return Err("Configuration did not serialize to an object".into());
+    };
+
+    let mut sections = Vec::new();
+    for (name, value) in top {
+        match value {
+            Value::Object(inner) if name == "orchestrator" => {
+                for (inner_name, inner_value) in inner {
+                    sections.push((format!("orchestrator.{}", inner_name), inner_value));
+                }
+            }
+            value => sections.push((name, value)),
+        }
+    }
+    Ok(sections)
+}
+
+/// Reloads the configuration each time the process receives SIGHUP.
+pub async fn reload_on_sighup(reloader: Arc<ConfigReloader>) {
+    #[cfg(unix)]
+    {
+        let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+            Ok(signal) => signal,
+            Err(e) => {
+                warn!("Failed to listen for SIGHUP; reload through the admin API instead: {}", e);
+                return;
+            }
+        };
+
+        while hangup.recv().await.is_some() {
+            info!("SIGHUP received; reloading configuration");
+            if let Err(e) = reloader.reload() {
+                warn!("Configuration reload failed; keeping the running configuration: {}", e);
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = reloader;
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+use chrono::Utc;
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+
+use crate::config::ReplayProtectionConfig;
+use crate::tenant;
+
+type ConnectError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Why a WebSocket message that moves money was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayRejection {
+    // Empty, too long, or not plain visible ASCII
+    InvalidNonce,
+    // Further from the server clock than the allowed skew
+    StaleTimestamp,
+    // Already used by this account
+    NonceReused,
+}
+
+impl fmt::Display for ReplayRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayRejection::InvalidNonce => write!(f, "Nonce must be 1 to the configured number of visible ASCII characters"),
+            ReplayRejection::StaleTimestamp => write!(f, "Timestamp is too far from the server clock"),
+            ReplayRejection::NonceReused => write!(f, "Nonce has already been used"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Rejected(ReplayRejection),
+    // Redis couldn't say whether the nonce was used, so the message isn't trusted
+    Store(redis::RedisError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Rejected(rejection) => rejection.fmt(f),
+            ReplayError::Store(e) => write!(f, "Nonce store unavailable: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<redis::RedisError> for ReplayError {
+    fn from(e: redis::RedisError) -> Self {
+        ReplayError::Store(e)
+    }
+}
+
+/// Nonces consumed by WebSocket messages that move money, kept in Redis
+/// just long enough to outlive the timestamp check, so a replay is caught on
+/// whichever instance it reaches.
+pub struct ReplayGuard {
+    config: ReplayProtectionConfig,
+    redis: ConnectionManager,
+}
+
+impl ReplayGuard {
+    pub async fn connect(redis_url: &str, config: ReplayProtectionConfig) -> Result<Self, ConnectError> {
+        let client = redis::Client::open(redis_url)?;
+        let redis = ConnectionManager::new(client).await?;
+        Ok(Self { config, redis })
+    }
+
+    /// Accepts a message from `user_id` carrying `nonce`, sent at `timestamp`
+    /// (Unix milliseconds), at most once. The nonce is spent even if the
+    /// message then fails, so a retry needs a new one.
+    pub async fn consume(&self, user_id: &str, nonce: &str, timestamp: i64) -> Result<(), ReplayError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        if nonce.is_empty() || nonce.len() > self.config.max_nonce_length || !nonce.chars().all(|c| c.is_ascii_graphic()) {
+            return Err(ReplayError::Rejected(ReplayRejection::InvalidNonce));
+        }
+        let skew_ms = (Utc::now().timestamp_millis() - timestamp).unsigned_abs();
+        if skew_ms > self.config.max_clock_skew_secs * 1000 {
+            return Err(ReplayError::Rejected(ReplayRejection::StaleTimestamp));
+        }
+
+        let key = tenant::redis_key(&format!("ws_nonce:{}:{}", user_id, nonce));
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(timestamp)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.config.nonce_ttl_secs())
+            .query_async(&mut self.redis.clone())
+            .await?;
+        match claimed {
+            Some(_) => Ok(()),
+            None => Err(ReplayError::Rejected(ReplayRejection::NonceReused)),
+        }
+    }
+}
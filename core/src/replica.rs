@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use sqlx::PgPool;
+use tracing::{info, warn};
+
+use crate::config::{DatabasePoolConfig, ReplicaConfig};
+use crate::shutdown::Shutdown;
+use crate::tenant;
+
+type ReplicaError = Box<dyn std::error::Error + Send + Sync>;
+
+// Lag of a replica that hasn't been checked yet or didn't answer
+const UNKNOWN_LAG: u64 = u64::MAX;
+
+// Caught up while the primary is idle, even though the last replayed transaction is old
+const LAG_QUERY: &str = r#"
+    SELECT CASE
+        WHEN NOT pg_is_in_recovery() THEN 0
+        WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+        ELSE COALESCE(EXTRACT(EPOCH FROM NOW() - pg_last_xact_replay_timestamp()) * 1000, 0)
+    END::float8
+"#;
+
+struct Replica {
+    // Password-free, for logs
+    name: String,
+    pool: PgPool,
+    lag_ms: AtomicU64,
+}
+
+/// Sends read-only queries that can tolerate a little staleness to a read
+/// replica, keeping them off the primary that settlement writes to. Replicas
+/// further behind than the configured lag, or not answering, are skipped, and
+/// with none left reads go to the primary.
+pub struct ReadRouter {
+    primary: PgPool,
+    replicas: Vec<Replica>,
+    config: ReplicaConfig,
+    next: AtomicUsize,
+}
+
+impl ReadRouter {
+    /// Connects to every configured replica. With none configured, every read goes to `primary`.
+    pub async fn connect(primary: PgPool, config: ReplicaConfig, pool: &DatabasePoolConfig) -> Result<Self, ReplicaError> {
+        let mut replicas = Vec::with_capacity(config.urls.len());
+        for url in &config.urls {
+            let name = crate::config::redact_url_password(url);
+            // Scoped to the request's tenant like the primary, so row-level security holds on replicas too
+            let pool = tenant::connect(url, pool).await
+                .map_err(|e| format!("Failed to connect to read replica {}: {}", name, e))?;
+            replicas.push(Replica { name, pool, lag_ms: AtomicU64::new(UNKNOWN_LAG) });
+        }
+        if !replicas.is_empty() {
+            info!("Connected to {} read replicas", replicas.len());
+        }
+
+        Ok(Self { primary, replicas, config, next: AtomicUsize::new(0) })
+    }
+
+    /// Each replica's pool, named for metrics.
+    pub fn replica_pools(&self) -> Vec<(String, PgPool)> {
+        self.replicas.iter()
+            .map(|replica| (format!("replica:{}", replica.name), replica.pool.clone()))
+            .collect()
+    }
+
+    /// A pool for a read that may be up to the configured lag behind.
+    pub fn reader(&self) -> &PgPool {
+        self.reader_within(Duration::from_millis(self.config.max_lag_ms))
+    }
+
+    /// A pool for a read that may be at most `max_lag` behind; the primary if no replica is that fresh.
+    pub fn reader_within(&self, max_lag: Duration) -> &PgPool {
+        let max_lag_ms = max_lag.as_millis() as u64;
+        let count = self.replicas.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        (0..count)
+            .map(|offset| &self.replicas[(start + offset) % count])
+            .find(|replica| replica.lag_ms.load(Ordering::Relaxed) <= max_lag_ms)
+            .map(|replica| &replica.pool)
+            .unwrap_or(&self.primary)
+    }
+
+    /// Measures each replica's lag until shutdown.
+    pub fn start(self: &Arc<Self>, shutdown: &Shutdown) {
+        if self.replicas.is_empty() {
+            return;
+        }
+
+        let router = self.clone();
+        shutdown.spawn_loop("replica:health_checks", async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(router.config.check_interval_ms));
+
+            loop {
+                interval.tick().await;
+                for replica in &router.replicas {
+                    router.check(replica).await;
+                }
+            }
+        });
+    }
+
+    async fn check(&self, replica: &Replica) {
+        let timeout = Duration::from_millis(self.config.check_interval_ms.max(1000));
+        let measured = tokio::time::timeout(timeout, sqlx::query_scalar::<_, f64>(LAG_QUERY).fetch_one(&replica.pool)).await;
+        let lag_ms = match measured {
+            Ok(Ok(lag)) => lag.max(0.0) as u64,
+            Ok(Err(e)) => {
+                warn!("Read replica {} didn't report its lag: {}", replica.name, e);
+                UNKNOWN_LAG
+            }
+            Err(_) => {
+                warn!("Read replica {} didn't report its lag within {:?}", replica.name, timeout);
+                UNKNOWN_LAG
+            }
+        };
+
+        let previous = replica.lag_ms.swap(lag_ms, Ordering::Relaxed);
+        let usable = |lag: u64| lag <= self.config.max_lag_ms;
+        if usable(previous) && !usable(lag_ms) && lag_ms != UNKNOWN_LAG {
+            warn!("Read replica {} is {}ms behind; sending its reads to the primary", replica.name, lag_ms);
+        } else if !usable(previous) && usable(lag_ms) {
+            info!("Read replica {} caught up ({}ms behind)", replica.name, lag_ms);
+        }
+    }
+}
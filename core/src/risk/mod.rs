@@ -0,0 +1,520 @@
+mod models;
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres, Row};
+use tokio::sync::broadcast;
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::audit::{AuditAction, AuditLog, AuditRecord};
+use crate::betting::Bet;
+use crate::config::RiskConfig;
+use crate::orchestrator::admin::AdminEvent;
+use crate::orchestrator::alerts::AlertSeverity;
+use crate::outbox::{OutboxError, OutboxEvent, OutboxHandler};
+use crate::pagination::{PageRequest, Paginated};
+use crate::validation::{Validate, ValidationErrors};
+
+use models::{Finding, UserModel};
+
+pub type RiskError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskSignal {
+    // A bettor winning far more often than the odds they took imply
+    OddsExploitation,
+    ImprobableWinStreak,
+    // Several accounts backing the same outcome with near-identical stakes at once
+    CoordinatedStakes,
+}
+
+impl RiskSignal {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskSignal::OddsExploitation => "odds_exploitation",
+            RiskSignal::ImprobableWinStreak => "improbable_win_streak",
+            RiskSignal::CoordinatedStakes => "coordinated_stakes",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "odds_exploitation" => RiskSignal::OddsExploitation,
+            "improbable_win_streak" => RiskSignal::ImprobableWinStreak,
+            _ => RiskSignal::CoordinatedStakes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskAlertStatus {
+    Open,
+    // Reviewed and upheld; any freezes stay until released
+    Confirmed,
+    Dismissed,
+}
+
+impl RiskAlertStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskAlertStatus::Open => "open",
+            RiskAlertStatus::Confirmed => "confirmed",
+            RiskAlertStatus::Dismissed => "dismissed",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "confirmed" => RiskAlertStatus::Confirmed,
+            "dismissed" => RiskAlertStatus::Dismissed,
+            _ => RiskAlertStatus::Open,
+        }
+    }
+}
+
+/// Anomalous betting the risk monitor raised, published on the admin
+/// channel as it's raised and kept for review.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RiskAlert {
+    pub alert_id: String,
+    pub signal: RiskSignal,
+    #[schema(value_type = String)]
+    pub severity: AlertSeverity,
+    // Stable across repeats of the same anomaly
+    pub dedup_key: String,
+    pub user_ids: Vec<String>,
+    pub stream_id: Option<String>,
+    pub summary: String,
+    // The z-score, streak probability or number of accounts, by signal
+    pub score: f64,
+    #[schema(value_type = Object)]
+    pub details: Value,
+    // Whether the accounts named were frozen when it was raised
+    pub froze_accounts: bool,
+    pub status: RiskAlertStatus,
+    pub raised_at: DateTime<Utc>,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_note: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RiskAlertQuery {
+    #[param(value_type = Option<String>)]
+    pub status: Option<RiskAlertStatus>,
+    #[param(value_type = Option<String>)]
+    pub signal: Option<RiskSignal>,
+    // Alerts naming this account
+    pub user_id: Option<String>,
+}
+
+/// The body of `POST /api/admin/risk/alerts/{alert_id}/review`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AlertReview {
+    pub status: RiskAlertStatus,
+    pub note: Option<String>,
+    // Release the accounts the alert froze; by default only when dismissing it
+    pub unfreeze: Option<bool>,
+}
+
+impl Validate for AlertReview {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        if self.status == RiskAlertStatus::Open {
+            errors.add("status", "must be confirmed or dismissed");
+        }
+        if self.note.as_ref().map(|note| note.len() > 2000).unwrap_or(false) {
+            errors.add("note", "must be at most 2000 characters");
+        }
+    }
+}
+
+// An alert about to be raised
+struct AlertDraft {
+    signal: RiskSignal,
+    severity: AlertSeverity,
+    dedup_key: String,
+    user_ids: Vec<String>,
+    stream_id: Option<String>,
+    summary: String,
+    score: f64,
+    details: Value,
+}
+
+/// Watches placements and settlements as the outbox relays them, keeps an
+/// online model per bettor and raises alerts for odds exploitation,
+/// improbable win streaks and coordinated stakes. Model state lives in
+/// Postgres, so it's the same whichever instance relays an event.
+pub struct RiskMonitor {
+    db_pool: Pool<Postgres>,
+    config: RiskConfig,
+    audit_log: Arc<AuditLog>,
+    // Alerts reach admins subscribed on the instance that raised them; the rest list them
+    admin_events: broadcast::Sender<AdminEvent>,
+}
+
+impl RiskMonitor {
+    pub fn new(
+        db_pool: Pool<Postgres>,
+        config: RiskConfig,
+        audit_log: Arc<AuditLog>,
+        admin_events: broadcast::Sender<AdminEvent>,
+    ) -> Self {
+        Self { db_pool, config, audit_log, admin_events }
+    }
+
+    /// The current tenant's alerts matching `query`, newest first unless
+    /// sorted by `raised_at`.
+    pub async fn list_alerts(&self, query: &RiskAlertQuery, page: &PageRequest) -> Result<Paginated<RiskAlert>, RiskError> {
+        let ascending = page.sort.as_ref().map(|sort| !sort.descending).unwrap_or(false);
+        let filter = "($1::text IS NULL OR status = $1) AND ($2::text IS NULL OR signal = $2) \
+            AND ($3::text IS NULL OR $3 = ANY(user_ids))";
+        let status = query.status.map(|status| status.as_str());
+        let signal = query.signal.map(|signal| signal.as_str());
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM risk_alerts WHERE {}", filter))
+            .bind(status)
+            .bind(signal)
+            .bind(&query.user_id)
+            .fetch_one(&self.db_pool)
+            .await?;
+
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM risk_alerts WHERE {} ORDER BY raised_at {} LIMIT $4 OFFSET $5",
+            ALERT_COLUMNS,
+            filter,
+            if ascending { "ASC" } else { "DESC" }
+        ))
+        .bind(status)
+        .bind(signal)
+        .bind(&query.user_id)
+        .bind(page.limit as i64)
+        .bind(page.offset as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(page.page_of(rows.iter().map(alert_from_row).collect(), total as usize))
+    }
+
+    /// Records the current actor's verdict on an alert, releasing the
+    /// accounts it froze if asked to. Returns the alert as reviewed and the
+    /// accounts released, or None for an unknown alert.
+    pub async fn review(&self, alert_id: &str, review: &AlertReview) -> Result<Option<(RiskAlert, Vec<String>)>, RiskError> {
+        let mut tx = self.db_pool.begin().await?;
+        let row = sqlx::query(&format!(
+            r#"
+            UPDATE risk_alerts
+            SET status = $2, review_note = $3, reviewed_at = NOW(),
+                reviewed_by = COALESCE(morphine_current_actor(), 'system')
+            WHERE alert_id = $1
+            RETURNING {}
+            "#,
+            ALERT_COLUMNS
+        ))
+        .bind(alert_id)
+        .bind(review.status.as_str())
+        .bind(&review.note)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let unfreeze = review.unfreeze.unwrap_or(review.status == RiskAlertStatus::Dismissed);
+        let released = if unfreeze {
+            sqlx::query_scalar(
+                r#"
+                UPDATE account_freezes
+                SET released_at = NOW(), released_by = COALESCE(morphine_current_actor(), 'system')
+                WHERE alert_id = $1 AND released_at IS NULL
+                RETURNING user_id
+                "#
+            )
+            .bind(alert_id)
+            .fetch_all(&mut *tx)
+            .await?
+        } else {
+            Vec::new()
+        };
+        tx.commit().await?;
+
+        Ok(Some((alert_from_row(&row), released)))
+    }
+
+    /// Lets a frozen account bet again, whatever froze it. False if it
+    /// wasn't frozen.
+    pub async fn release(&self, user_id: &str) -> Result<bool, RiskError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE account_freezes
+            SET released_at = NOW(), released_by = COALESCE(morphine_current_actor(), 'system')
+            WHERE user_id = $1 AND released_at IS NULL
+            "#
+        )
+        .bind(user_id)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn observe_settlement(&self, bet: &Bet) -> Result<(), RiskError> {
+        let mut tx = self.db_pool.begin().await?;
+        sqlx::query("INSERT INTO risk_user_models (user_id, state) VALUES ($1, '{}') ON CONFLICT DO NOTHING")
+            .bind(&bet.user_id)
+            .execute(&mut *tx)
+            .await?;
+        // Held to commit, so one bettor's settlements relayed at once apply in turn
+        let state: Value = sqlx::query_scalar("SELECT state FROM risk_user_models WHERE user_id = $1 FOR UPDATE")
+            .bind(&bet.user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let mut model: UserModel = serde_json::from_value(state).unwrap_or_default();
+        let findings = model.observe(bet, &self.config);
+        sqlx::query("UPDATE risk_user_models SET state = $2, updated_at = NOW() WHERE user_id = $1")
+            .bind(&bet.user_id)
+            .bind(serde_json::to_value(&model)?)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        for finding in findings {
+            let draft = match finding {
+                Finding::OddsExploitation { z_score, settled } => AlertDraft {
+                    signal: RiskSignal::OddsExploitation,
+                    severity: if z_score >= self.config.exploitation_z_score * 1.5 {
+                        AlertSeverity::Critical
+                    } else {
+                        AlertSeverity::Warning
+                    },
+                    dedup_key: format!("odds_exploitation:{}", bet.user_id),
+                    user_ids: vec![bet.user_id.clone()],
+                    stream_id: Some(bet.stream_id.clone()),
+                    summary: format!(
+                        "User {} is winning {:.1} standard deviations more often than their odds imply",
+                        bet.user_id, z_score
+                    ),
+                    score: z_score,
+                    details: json!({ "settled_bets": settled, "bet_id": bet.id }),
+                },
+                Finding::ImprobableStreak { length, probability } => AlertDraft {
+                    signal: RiskSignal::ImprobableWinStreak,
+                    severity: if probability <= self.config.streak_max_probability / 100.0 {
+                        AlertSeverity::Critical
+                    } else {
+                        AlertSeverity::Warning
+                    },
+                    dedup_key: format!("improbable_win_streak:{}", bet.user_id),
+                    user_ids: vec![bet.user_id.clone()],
+                    stream_id: Some(bet.stream_id.clone()),
+                    summary: format!(
+                        "User {} has won {} bets in a row, a 1 in {:.0} chance at the odds taken",
+                        bet.user_id, length, 1.0 / probability
+                    ),
+                    score: probability,
+                    details: json!({ "streak": length, "bet_id": bet.id }),
+                },
+            };
+            self.raise(draft).await?;
+        }
+        Ok(())
+    }
+
+    async fn observe_placement(&self, bet: &Bet) -> Result<(), RiskError> {
+        let prediction = serde_json::to_value(&bet.prediction)?;
+        let rows = sqlx::query(
+            r#"
+            SELECT user_id, SUM(stake_amount)::float8 AS stake
+            FROM bets
+            WHERE stream_id = $1 AND bet_type = $2 AND prediction = $3 AND deleted_at IS NULL
+                AND created_at > $4 - make_interval(secs => $5) AND created_at <= $4
+            GROUP BY user_id
+            ORDER BY user_id
+            "#
+        )
+        .bind(&bet.stream_id)
+        .bind(bet.bet_type.as_str())
+        .bind(&prediction)
+        .bind(bet.created_at)
+        .bind(self.config.coordination_window_secs as f64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let user_ids: Vec<String> = rows.iter().map(|row| row.get("user_id")).collect();
+        let stakes: Vec<f64> = rows.iter().map(|row| row.get("stake")).collect();
+        let Some(cv) = models::coordinated(&stakes, &self.config) else {
+            return Ok(());
+        };
+
+        let outcome = hex::encode(Sha256::digest(prediction.to_string().as_bytes()));
+        self.raise(AlertDraft {
+            signal: RiskSignal::CoordinatedStakes,
+            severity: if user_ids.len() >= self.config.coordination_min_accounts as usize * 2 {
+                AlertSeverity::Critical
+            } else {
+                AlertSeverity::Warning
+            },
+            dedup_key: format!("coordinated_stakes:{}:{}:{}", bet.stream_id, bet.bet_type.as_str(), &outcome[..16]),
+            summary: format!(
+                "{} accounts staked near-identical amounts on the same {} outcome on stream {} within {}s",
+                user_ids.len(), bet.bet_type.as_str(), bet.stream_id, self.config.coordination_window_secs
+            ),
+            user_ids,
+            stream_id: Some(bet.stream_id.clone()),
+            score: stakes.len() as f64,
+            details: json!({
+                "prediction": prediction,
+                "total_stake": stakes.iter().sum::<f64>(),
+                "stake_cv": cv,
+                "bet_id": bet.id,
+            }),
+        })
+        .await
+    }
+
+    // Stores and publishes the alert unless the same anomaly was raised within
+    // the cooldown, freezing the accounts it names if configured to
+    async fn raise(&self, draft: AlertDraft) -> Result<(), RiskError> {
+        let mut tx = self.db_pool.begin().await?;
+        // Held to commit, so two instances can't both raise the same anomaly
+        sqlx::query(
+            "SELECT pg_advisory_xact_lock(hashtext('morphine.risk_alerts:' || COALESCE(morphine_current_tenant(), 'default') || ':' || $1))"
+        )
+        .bind(&draft.dedup_key)
+        .execute(&mut *tx)
+        .await?;
+        let recent: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM risk_alerts WHERE dedup_key = $1 AND raised_at > NOW() - make_interval(secs => $2))"
+        )
+        .bind(&draft.dedup_key)
+        .bind(self.config.alert_cooldown_secs as f64)
+        .fetch_one(&mut *tx)
+        .await?;
+        if recent {
+            return Ok(());
+        }
+
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO risk_alerts (alert_id, signal, severity, dedup_key, user_ids, stream_id, summary, score,
+                details, froze_accounts)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING {}
+            "#,
+            ALERT_COLUMNS
+        ))
+        .bind(Uuid::new_v4().to_string())
+        .bind(draft.signal.as_str())
+        .bind(severity_str(draft.severity))
+        .bind(&draft.dedup_key)
+        .bind(&draft.user_ids)
+        .bind(&draft.stream_id)
+        .bind(&draft.summary)
+        .bind(draft.score)
+        .bind(&draft.details)
+        .bind(self.config.freeze_accounts)
+        .fetch_one(&mut *tx)
+        .await?;
+        let alert = alert_from_row(&row);
+
+        let mut frozen = Vec::new();
+        if self.config.freeze_accounts {
+            for user_id in &alert.user_ids {
+                // An account already frozen stays frozen by the alert that froze it
+                let result = sqlx::query(
+                    r#"
+                    INSERT INTO account_freezes (user_id, alert_id) VALUES ($1, $2)
+                    ON CONFLICT (tenant_id, user_id) WHERE released_at IS NULL DO NOTHING
+                    "#
+                )
+                .bind(user_id)
+                .bind(&alert.alert_id)
+                .execute(&mut *tx)
+                .await?;
+                if result.rows_affected() > 0 {
+                    frozen.push(user_id.clone());
+                }
+            }
+        }
+        tx.commit().await?;
+
+        warn!(
+            "Risk alert {} ({}): {}{}",
+            alert.alert_id,
+            alert.signal.as_str(),
+            alert.summary,
+            if frozen.is_empty() { String::new() } else { format!("; froze {}", frozen.join(", ")) }
+        );
+        for user_id in &frozen {
+            let record = AuditRecord {
+                action: AuditAction::AccountFrozen,
+                target_type: "user",
+                target_id: user_id,
+                before: None,
+                after: Some(json!({ "alert_id": alert.alert_id, "signal": alert.signal })),
+            };
+            if let Err(e) = self.audit_log.record(record).await {
+                warn!("Failed to record freezing {} in the audit log: {}", user_id, e);
+            }
+        }
+        // No subscribers is fine; the alert is stored for review either way
+        let _ = self.admin_events.send(AdminEvent::RiskAlert(alert));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl OutboxHandler for RiskMonitor {
+    fn name(&self) -> &'static str {
+        "risk"
+    }
+
+    async fn handle(&self, event: &OutboxEvent) -> Result<(), OutboxError> {
+        match event.event_type.as_str() {
+            "bet_placed" => self.observe_placement(&serde_json::from_value(event.data.clone())?).await,
+            "bet_settled" => self.observe_settlement(&serde_json::from_value(event.data.clone())?).await,
+            _ => Ok(()),
+        }
+    }
+}
+
+const ALERT_COLUMNS: &str = "alert_id, signal, severity, dedup_key, user_ids, stream_id, summary, score, details, \
+    froze_accounts, status, raised_at, reviewed_by, reviewed_at, review_note";
+
+fn alert_from_row(row: &sqlx::postgres::PgRow) -> RiskAlert {
+    let signal: String = row.get("signal");
+    let severity: String = row.get("severity");
+    let status: String = row.get("status");
+    RiskAlert {
+        alert_id: row.get("alert_id"),
+        signal: RiskSignal::parse(&signal),
+        severity: if severity == "critical" { AlertSeverity::Critical } else { AlertSeverity::Warning },
+        dedup_key: row.get("dedup_key"),
+        user_ids: row.get("user_ids"),
+        stream_id: row.get("stream_id"),
+        summary: row.get("summary"),
+        score: row.get("score"),
+        details: row.get("details"),
+        froze_accounts: row.get("froze_accounts"),
+        status: RiskAlertStatus::parse(&status),
+        raised_at: row.get("raised_at"),
+        reviewed_by: row.get("reviewed_by"),
+        reviewed_at: row.get("reviewed_at"),
+        review_note: row.get("review_note"),
+    }
+}
+
+fn severity_str(severity: AlertSeverity) -> &'static str {
+    match severity {
+        AlertSeverity::Warning => "warning",
+        AlertSeverity::Critical => "critical",
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+
+use crate::betting::Bet;
+use crate::config::RiskConfig;
+
+// Settled bets remembered per bettor, so a redelivered settlement isn't counted twice
+const RECENT_BETS: usize = 64;
+
+/// One bettor's online model, updated a settlement at a time. Each bet is
+/// judged against the probability its odds imply, `1 / odds`: a fair bettor
+/// wins about that often, whatever odds they take.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct UserModel {
+    pub settled: u64,
+    // Decayed sum of wins beyond the implied probability, and that sum's variance
+    pub excess_wins: f64,
+    pub variance: f64,
+    // Consecutive wins up to the latest settlement, and the log of how likely they were
+    pub streak: u32,
+    pub streak_log_probability: f64,
+    pub recent_bet_ids: VecDeque<String>,
+}
+
+/// What a settlement showed about its bettor.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Finding {
+    // Wins this many standard deviations above what the odds imply
+    OddsExploitation { z_score: f64, settled: u64 },
+    ImprobableStreak { length: u32, probability: f64 },
+}
+
+impl UserModel {
+    /// Folds in a settled bet, returning what now stands out. Bets already
+    /// seen, unsettled or at odds that imply no probability change nothing.
+    pub fn observe(&mut self, bet: &Bet, config: &RiskConfig) -> Vec<Finding> {
+        let Some(resolution) = &bet.resolution_result else {
+            return Vec::new();
+        };
+        if self.recent_bet_ids.contains(&bet.id) || bet.odds <= 1.0 || !bet.odds.is_finite() {
+            return Vec::new();
+        }
+        self.recent_bet_ids.push_back(bet.id.clone());
+        if self.recent_bet_ids.len() > RECENT_BETS {
+            self.recent_bet_ids.pop_front();
+        }
+
+        let implied = 1.0 / bet.odds;
+        let outcome = if resolution.won { 1.0 } else { 0.0 };
+        self.settled += 1;
+        self.excess_wins = config.decay * self.excess_wins + (outcome - implied);
+        // The variance of a decayed sum decays with the square of the weight
+        self.variance = config.decay * config.decay * self.variance + implied * (1.0 - implied);
+        if resolution.won {
+            self.streak += 1;
+            self.streak_log_probability += implied.ln();
+        } else {
+            self.streak = 0;
+            self.streak_log_probability = 0.0;
+        }
+
+        let mut findings = Vec::new();
+        if self.settled >= config.min_settled_bets as u64 && self.variance > 0.0 {
+            let z_score = self.excess_wins / self.variance.sqrt();
+            if z_score >= config.exploitation_z_score {
+                findings.push(Finding::OddsExploitation { z_score, settled: self.settled });
+            }
+        }
+        if self.streak >= config.streak_min_length {
+            let probability = self.streak_log_probability.exp();
+            if probability <= config.streak_max_probability {
+                findings.push(Finding::ImprobableStreak { length: self.streak, probability });
+            }
+        }
+        findings
+    }
+}
+
+/// Whether stakes of this size from this many accounts look like one bettor
+/// spread across several. Returns how uniform the stakes are, as standard
+/// deviation over mean, if they qualify.
+pub(crate) fn coordinated(stakes: &[f64], config: &RiskConfig) -> Option<f64> {
+    if stakes.len() < config.coordination_min_accounts as usize {
+        return None;
+    }
+    let mean = stakes.iter().sum::<f64>() / stakes.len() as f64;
+    if mean <= 0.0 {
+        return None;
+    }
+    let variance = stakes.iter().map(|stake| (stake - mean).powi(2)).sum::<f64>() / stakes.len() as f64;
+    let cv = variance.sqrt() / mean;
+    (cv <= config.coordination_max_stake_cv).then_some(cv)
+}
@@ -0,0 +1,49 @@
+use std::future::Future;
+use tokio::task::JoinHandle;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::prelude::*;
+
+use crate::error_reporting;
+
+#[cfg(all(feature = "tokio-console", not(tokio_unstable)))]
+compile_error!("the tokio-console feature needs tokio's instrumentation: build with RUSTFLAGS=\"--cfg tokio_unstable\"");
+
+/// Starts logging at INFO. ERROR lines are also reported as events, and
+/// INFO and WARN ones kept as their breadcrumbs, once `error_reporting` is
+/// initialized. A build with the `tokio-console` feature also serves its
+/// tasks, their names and what they wait on to `tokio-console`, on
+/// `TOKIO_CONSOLE_BIND` (127.0.0.1:6669 unless set).
+pub fn init_tracing() {
+    let logs = tracing_subscriber::fmt::layer().with_filter(LevelFilter::INFO);
+    let reporting = sentry::integrations::tracing::layer().with_filter(LevelFilter::INFO);
+
+    #[cfg(feature = "tokio-console")]
+    tracing_subscriber::registry().with(console_subscriber::spawn()).with(logs).with(reporting).init();
+    #[cfg(not(feature = "tokio-console"))]
+    tracing_subscriber::registry().with(logs).with(reporting).init();
+}
+
+/// Spawns `task` under `name`, which is what tokio-console lists it as.
+/// Loops are named for what they do and per-stream tasks for their stream,
+/// so a stuck or leaked one can be told from its siblings. Names are only
+/// kept in builds with tokio's instrumentation; errors and panics the task
+/// reports are tagged with it in every build.
+pub fn spawn<F>(name: &str, task: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let task = error_reporting::isolate(name, task);
+    #[cfg(tokio_unstable)]
+    {
+        tokio::task::Builder::new()
+            .name(name)
+            .spawn(task)
+            .expect("tasks are only spawned from inside the runtime")
+    }
+    #[cfg(not(tokio_unstable))]
+    {
+        let _ = name;
+        tokio::spawn(task)
+    }
+}
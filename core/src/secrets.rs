@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::postgres::PgConnectOptions;
+use sqlx::{Pool, Postgres};
+use tracing::{info, warn};
+
+use crate::auth::Authenticator;
+use crate::config::{Config, SecretsConfig};
+use crate::shutdown::Shutdown;
+
+type SecretError = Box<dyn std::error::Error + Send + Sync>;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretProviderKind {
+    // Everything is configured inline
+    None,
+    File,
+    Vault,
+    Aws,
+}
+
+/// A backend secrets are fetched from by name.
+#[async_trait]
+pub trait SecretProvider: Send + Sync {
+    /// The current value of `name`; an error if it doesn't exist.
+    async fn fetch(&self, name: &str) -> Result<String, SecretError>;
+}
+
+/// One file per secret in a directory, e.g. Docker or Kubernetes secret mounts.
+pub struct FileSecrets {
+    dir: PathBuf,
+}
+
+impl FileSecrets {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl SecretProvider for FileSecrets {
+    async fn fetch(&self, name: &str) -> Result<String, SecretError> {
+        // Names may nest into subdirectories but never leave the secrets directory
+        let relative = Path::new(name);
+        if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+            return Err(format!("Secret name {} must be a relative path without '..'", name).into());
+        }
+
+        let contents = tokio::fs::read_to_string(self.dir.join(relative)).await
+            .map_err(|e| format!("Failed to read secret {}: {}", name, e))?;
+        Ok(contents.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+/// A HashiCorp Vault KV version 2 engine. `path#key` reads one key of the
+/// secret at `path`; a bare path reads its `value` key.
+pub struct VaultSecrets {
+    address: String,
+    mount: String,
+    token: String,
+    http_client: reqwest::Client,
+}
+
+impl VaultSecrets {
+    pub fn new(address: &str, mount: &str, token: &str) -> Self {
+        Self {
+            address: address.trim_end_matches('/').to_string(),
+            mount: mount.trim_matches('/').to_string(),
+            token: token.to_string(),
+            http_client: http_client(),
+        }
+    }
+}
+
+#[async_trait]
+impl SecretProvider for VaultSecrets {
+    async fn fetch(&self, name: &str) -> Result<String, SecretError> {
+        let (path, key) = split_key(name);
+        let response = self.http_client
+            .get(format!("{}/v1/{}/data/{}", self.address, self.mount, path.trim_start_matches('/')))
+            .header("X-Vault-Token", &self.token)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(format!("Vault responded {} for secret {}", response.status(), path).into());
+        }
+
+        let body: Value = response.json().await?;
+        let value = body.pointer("/data/data")
+            .and_then(|data| data.get(key.unwrap_or("value")))
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("Vault secret {} has no string key {}", path, key.unwrap_or("value")))?;
+        Ok(value.to_string())
+    }
+}
+
+/// AWS Secrets Manager, called directly with SigV4-signed requests.
+/// `name#key` reads one key of a JSON secret; a bare name reads the whole string.
+pub struct AwsSecretsManager {
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    http_client: reqwest::Client,
+}
+
+impl AwsSecretsManager {
+    /// Credentials come from the standard AWS environment variables.
+    pub fn from_env(region: &str) -> Result<Self, SecretError> {
+        let access_key_id = std::env::var("AWS_ACCESS_KEY_ID")
+            .map_err(|_| "AWS_ACCESS_KEY_ID must be set for the aws secrets provider")?;
+        let secret_access_key = std::env::var("AWS_SECRET_ACCESS_KEY")
+            .map_err(|_| "AWS_SECRET_ACCESS_KEY must be set for the aws secrets provider")?;
+
+        Ok(Self {
+            region: region.to_string(),
+            access_key_id,
+            secret_access_key,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok().filter(|token| !token.is_empty()),
+            http_client: http_client(),
+        })
+    }
+
+    // Signature Version 4 `Authorization` header for a GetSecretValue call
+    fn authorization(&self, host: &str, amz_date: &str, body: &str) -> String {
+        let date = &amz_date[..8];
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1".to_string()),
+            ("host", host.to_string()),
+            ("x-amz-date", amz_date.to_string()),
+            ("x-amz-target", "secretsmanager.GetSecretValue".to_string()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        headers.sort_by(|a, b| a.0.cmp(b.0));
+
+        let canonical_headers: String = headers.iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect();
+        let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+        let canonical_request = format!(
+            "POST\n/\n\n{}\n{}\n{}",
+            canonical_headers,
+            signed_headers,
+            hex::encode(Sha256::digest(body.as_bytes())),
+        );
+
+        let scope = format!("{}/{}/secretsmanager/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes())),
+        );
+
+        let mut key = hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), date);
+        for part in [self.region.as_str(), "secretsmanager", "aws4_request"] {
+            key = hmac_sha256(&key, part);
+        }
+        let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        )
+    }
+}
+
+#[async_trait]
+impl SecretProvider for AwsSecretsManager {
+    async fn fetch(&self, name: &str) -> Result<String, SecretError> {
+        let (secret_id, key) = split_key(name);
+        let host = format!("secretsmanager.{}.amazonaws.com", self.region);
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let body = json!({ "SecretId": secret_id }).to_string();
+
+        let mut request = self.http_client.post(format!("https://{}/", host))
+            .header(reqwest::header::CONTENT_TYPE, "application/x-amz-json-1.1")
+            .header("X-Amz-Date", &amz_date)
+            .header("X-Amz-Target", "secretsmanager.GetSecretValue")
+            .header(reqwest::header::AUTHORIZATION, self.authorization(&host, &amz_date, &body));
+        if let Some(token) = &self.session_token {
+            request = request.header("X-Amz-Security-Token", token);
+        }
+
+        let response = request.body(body).send().await?;
+        if !response.status().is_success() {
+            return Err(format!("Secrets Manager responded {} for secret {}", response.status(), secret_id).into());
+        }
+
+        let body: Value = response.json().await?;
+        let secret = body.get("SecretString")
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("Secret {} has no string value", secret_id))?;
+        match key {
+            None => Ok(secret.to_string()),
+            Some(key) => serde_json::from_str::<Value>(secret)?
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| format!("Secret {} has no string key {}", secret_id, key).into()),
+        }
+    }
+}
+
+/// Secrets from the configured provider, cached and fetched again once the
+/// refresh interval has passed.
+pub struct SecretStore {
+    provider: Option<Arc<dyn SecretProvider>>,
+    // Zero keeps the first value fetched for good
+    refresh_interval: Duration,
+    cache: RwLock<HashMap<String, (String, Instant)>>,
+}
+
+impl SecretStore {
+    pub fn new(config: &SecretsConfig) -> Result<Self, SecretError> {
+        let provider: Option<Arc<dyn SecretProvider>> = match config.provider {
+            SecretProviderKind::None => None,
+            SecretProviderKind::File => Some(Arc::new(FileSecrets::new(&config.file_dir))),
+            SecretProviderKind::Vault => Some(Arc::new(VaultSecrets::new(
+                config.vault_address.as_deref().unwrap_or_default(),
+                &config.vault_mount,
+                config.vault_token.as_deref().unwrap_or_default(),
+            ))),
+            SecretProviderKind::Aws => Some(Arc::new(AwsSecretsManager::from_env(
+                config.aws_region.as_deref().unwrap_or_default(),
+            )?)),
+        };
+
+        Ok(Self {
+            provider,
+            refresh_interval: Duration::from_secs(config.refresh_interval_secs),
+            cache: RwLock::new(HashMap::new()),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    /// The value of `name`, from cache while it is fresh. If fetching a stale
+    /// secret fails, the last value fetched is returned instead.
+    pub async fn get(&self, name: &str) -> Result<String, SecretError> {
+        let cached = self.cache.read().get(name).cloned();
+        if let Some((value, fetched_at)) = &cached {
+            if self.refresh_interval.is_zero() || fetched_at.elapsed() < self.refresh_interval {
+                return Ok(value.clone());
+            }
+        }
+
+        match self.fetch(name).await {
+            Ok(value) => Ok(value),
+            Err(e) => match cached {
+                Some((value, _)) => {
+                    warn!("Failed to refresh secret {}; using the last value fetched: {}", name, e);
+                    Ok(value)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    // Fetches `name` now and returns it if it differs from the last value fetched
+    async fn refresh(&self, name: &str) -> Result<Option<String>, SecretError> {
+        let previous = self.cache.read().get(name).map(|(value, _)| value.clone());
+        let value = self.fetch(name).await?;
+        Ok(Some(value).filter(|value| previous.as_ref() != Some(value)))
+    }
+
+    async fn fetch(&self, name: &str) -> Result<String, SecretError> {
+        let provider = self.provider.as_ref().ok_or("No secrets provider is configured")?;
+        let value = tokio::time::timeout(FETCH_TIMEOUT, provider.fetch(name)).await
+            .map_err(|_| format!("Timed out fetching secret {}", name))??;
+        self.cache.write().insert(name.to_string(), (value.clone(), Instant::now()));
+        Ok(value)
+    }
+
+    /// Replaces settings that name a secret with the secret's value. Runs once
+    /// at startup, before anything connects.
+    pub async fn resolve_config(&self, config: &mut Config) -> Result<(), SecretError> {
+        let names = config.secrets.clone();
+        if let Some(name) = &names.database_url {
+            config.database_url = self.get(name).await?;
+        }
+        if let Some(name) = &names.redis_url {
+            config.redis_url = self.get(name).await?;
+        }
+        if let Some(name) = &names.jwt_secret {
+            let secret = self.get(name).await?;
+            if secret.len() < 16 {
+                return Err(format!("Secret {} must be at least 16 characters to sign user tokens", name).into());
+            }
+            config.user_auth.jwt_secret = Some(secret);
+        }
+        Ok(())
+    }
+
+    /// Fetches the secrets named in `names` every refresh interval and applies
+    /// rotations: database credentials to `db_pools` for new connections, the
+    /// JWT secret to `authenticator`. A rotated Redis URL needs a restart.
+    pub fn watch_rotations(
+        self: &Arc<Self>,
+        names: SecretsConfig,
+        db_pools: Vec<Pool<Postgres>>,
+        authenticator: Arc<Authenticator>,
+        shutdown: &Shutdown,
+    ) {
+        if !self.is_enabled() || self.refresh_interval.is_zero() {
+            return;
+        }
+
+        let store = self.clone();
+        shutdown.spawn_loop("secrets:refresh", async move {
+            let mut interval = tokio::time::interval(store.refresh_interval);
+            // Everything was just fetched at startup
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+
+                if let Some(url) = store.rotated(names.database_url.as_deref()).await {
+                    match PgConnectOptions::from_str(&url) {
+                        Ok(options) => {
+                            for pool in &db_pools {
+                                pool.set_connect_options(options.clone());
+                            }
+                            info!("Database credentials rotated; new connections use them");
+                        }
+                        Err(e) => warn!("Rotated database URL is invalid; keeping the old one: {}", e),
+                    }
+                }
+
+                if let Some(secret) = store.rotated(names.jwt_secret.as_deref()).await {
+                    if secret.len() < 16 {
+                        warn!("Rotated JWT secret is under 16 characters; keeping the old one");
+                    } else {
+                        authenticator.rotate_user_token_secret(&secret);
+                        info!("JWT secret rotated; tokens signed with the previous one are still accepted");
+                    }
+                }
+
+                if store.rotated(names.redis_url.as_deref()).await.is_some() {
+                    warn!("Redis URL secret rotated; restart to reconnect with it");
+                }
+            }
+        });
+    }
+
+    async fn rotated(&self, name: Option<&str>) -> Option<String> {
+        let name = name?;
+        match self.refresh(name).await {
+            Ok(rotated) => rotated,
+            Err(e) => {
+                warn!("Failed to refresh secret {}: {}", name, e);
+                None
+            }
+        }
+    }
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+// "path#key" into its parts
+fn split_key(name: &str) -> (&str, Option<&str>) {
+    match name.split_once('#') {
+        Some((path, key)) => (path, Some(key)),
+        None => (name, None),
+    }
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
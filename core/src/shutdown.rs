@@ -0,0 +1,114 @@
+use std::future::Future;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{info, warn};
+
+use crate::runtime;
+
+/// Coordinates a graceful shutdown: one signal that background loops and open
+/// connections watch, and a tracker for work that must finish before exit.
+#[derive(Clone)]
+pub struct Shutdown {
+    token: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            token: CancellationToken::new(),
+            tracker: TaskTracker::new(),
+        }
+    }
+
+    pub fn trigger(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub async fn triggered(&self) {
+        self.token.cancelled().await
+    }
+
+    /// A token cancelled on shutdown, or earlier by its holder.
+    pub fn child_token(&self) -> CancellationToken {
+        self.token.child_token()
+    }
+
+    /// Runs a background loop, as task `name`, until shutdown; it is dropped at its next await point.
+    pub fn spawn_loop<F>(&self, name: &str, task: F) -> JoinHandle<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let token = self.token.clone();
+        runtime::spawn(name, async move {
+            tokio::select! {
+                _ = task => {}
+                _ = token.cancelled() => {}
+            }
+        })
+    }
+
+    /// Spawns work that shutdown waits for, such as writes that must not be cut off, as task `name`.
+    pub fn spawn_tracked<F>(&self, name: &str, task: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        runtime::spawn(name, self.tracker.track_future(task))
+    }
+
+    /// Waits for tracked work to finish, up to `timeout`. Returns false if some was abandoned.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.trigger();
+        self.tracker.close();
+        info!("Draining {} in-flight background tasks", self.tracker.len());
+
+        match tokio::time::timeout(timeout, self.tracker.wait()).await {
+            Ok(()) => true,
+            Err(_) => {
+                warn!("Gave up waiting on {} background tasks after {:?}", self.tracker.len(), timeout);
+                false
+            }
+        }
+    }
+}
+
+/// Resolves on Ctrl-C or SIGTERM, then triggers `shutdown`.
+pub async fn wait_for_signal(shutdown: Shutdown) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+
+    info!("Shutdown signal received; no longer accepting new requests");
+    shutdown.trigger();
+}
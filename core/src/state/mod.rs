@@ -1,81 +1,76 @@
 use anyhow::{Result, Context};
-use redis::{AsyncCommands, Client};
+use deadpool_redis::{Connection, Pool, PoolConfig, Runtime, Timeouts};
+use redis::AsyncCommands;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tokio::sync::Mutex;
+use std::time::Duration;
 use serde_json;
 
+use crate::analytics::AnalyticsEvent;
+use crate::config::RedisPoolConfig;
 use crate::stream::{StreamInfo, StreamActivity};
+use crate::tenant;
 
+/// Typed access to Redis over a pool of connections, so handlers don't
+/// queue behind each other's round trips. Connections are pinged before
+/// they're reused, and replaced if the ping fails.
 pub struct StateManager {
-    redis_client: Client,
-    connection_pool: Arc<RwLock<Option<redis::aio::Connection>>>,
-    connection: Arc<Mutex<redis::aio::Connection>>,
+    pool: Pool,
 }
 
 impl StateManager {
-    pub async fn new(redis_url: &str) -> Result<Self> {
-        let client = Client::open(redis_url)
-            .context("Failed to create Redis client")?;
-
-        let mut connection = client.get_async_connection().await
-            .context("Failed to connect to Redis")?;
-
-        // Test connection
-        let _: String = redis::cmd("PING").query_async(&mut connection).await
+    pub async fn new(redis_url: &str, config: &RedisPoolConfig) -> Result<Self> {
+        let mut pool_config = PoolConfig::new(config.max_size);
+        pool_config.timeouts = Timeouts {
+            wait: Some(Duration::from_millis(config.acquire_timeout_ms)),
+            create: Some(Duration::from_millis(config.connect_timeout_ms)),
+            recycle: Some(Duration::from_millis(config.health_check_timeout_ms)),
+        };
+        let pool = deadpool_redis::Config { pool: Some(pool_config), ..deadpool_redis::Config::from_url(redis_url) }
+            .create_pool(Some(Runtime::Tokio1))
+            .context("Failed to create Redis pool")?;
+
+        let state_manager = Self { pool };
+        // Fail at startup rather than on the first request
+        state_manager.ping().await
             .context("Failed to ping Redis")?;
-
-        Ok(Self {
-            redis_client: client,
-            connection_pool: Arc::new(RwLock::new(Some(connection))),
-            connection: Arc::new(Mutex::new(connection)),
-        })
+        Ok(state_manager)
     }
 
-    async fn get_connection(&self) -> Result<redis::aio::Connection> {
-        let mut pool = self.connection_pool.write().await;
-        
-        if let Some(conn) = pool.take() {
-            Ok(conn)
-        } else {
-            self.redis_client.get_async_connection().await
-                .context("Failed to get Redis connection")
-        }
+    /// A pooled connection, returned to the pool when dropped.
+    pub async fn connection(&self) -> Result<Connection> {
+        self.pool.get().await
+            .context("Failed to get Redis connection")
     }
 
-    async fn return_connection(&self, connection: redis::aio::Connection) {
-        let mut pool = self.connection_pool.write().await;
-        *pool = Some(connection);
+    /// How many connections are open, how many are idle, and the most the pool opens.
+    pub fn pool_status(&self) -> deadpool_redis::Status {
+        self.pool.status()
     }
 
     pub async fn set_stream(&self, stream_id: &str, stream_info: &StreamInfo) -> Result<()> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.connection().await?;
         
         let serialized = serde_json::to_string(stream_info)
             .context("Failed to serialize stream info")?;
         
-        let key = format!("stream:{}", stream_id);
+        let key = tenant::redis_key(&format!("stream:{}", stream_id));
         conn.set(&key, serialized).await
             .context("Failed to set stream in Redis")?;
 
         // Add to stream list
-        conn.sadd("streams", stream_id).await
+        conn.sadd(tenant::redis_key("streams"), stream_id).await
             .context("Failed to add stream to list")?;
 
-        self.return_connection(conn).await;
         Ok(())
     }
 
     pub async fn get_stream(&self, stream_id: &str) -> Result<Option<StreamInfo>> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.connection().await?;
         
-        let key = format!("stream:{}", stream_id);
+        let key = tenant::redis_key(&format!("stream:{}", stream_id));
         let result: Option<String> = conn.get(&key).await
             .context("Failed to get stream from Redis")?;
 
-        self.return_connection(conn).await;
-
         if let Some(serialized) = result {
             let stream_info = serde_json::from_str(&serialized)
                 .context("Failed to deserialize stream info")?;
@@ -86,22 +81,21 @@ impl StateManager {
     }
 
     pub async fn get_stream_keys(&self) -> Result<Vec<String>> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.connection().await?;
         
-        let keys: Vec<String> = conn.smembers("streams").await
+        let keys: Vec<String> = conn.smembers(tenant::redis_key("streams")).await
             .context("Failed to get stream keys")?;
 
-        self.return_connection(conn).await;
         Ok(keys)
     }
 
     pub async fn add_stream_activity(&self, stream_id: &str, activity: &StreamActivity) -> Result<()> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.connection().await?;
         
         let serialized = serde_json::to_string(activity)
             .context("Failed to serialize activity")?;
         
-        let key = format!("stream:{}:activity", stream_id);
+        let key = tenant::redis_key(&format!("stream:{}:activity", stream_id));
         
         // Add to list (keep last 100 activities)
         conn.lpush(&key, &serialized).await
@@ -110,19 +104,16 @@ impl StateManager {
         conn.ltrim(&key, 0, 99).await
             .context("Failed to trim activity list")?;
 
-        self.return_connection(conn).await;
         Ok(())
     }
 
     pub async fn get_stream_activity(&self, stream_id: &str) -> Result<Vec<StreamActivity>> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.connection().await?;
         
-        let key = format!("stream:{}:activity", stream_id);
+        let key = tenant::redis_key(&format!("stream:{}:activity", stream_id));
         let activities: Vec<String> = conn.lrange(&key, 0, -1).await
             .context("Failed to get activities")?;
 
-        self.return_connection(conn).await;
-
         let mut parsed_activities = Vec::new();
         for activity_str in activities {
             if let Ok(activity) = serde_json::from_str::<StreamActivity>(&activity_str) {
@@ -134,42 +125,39 @@ impl StateManager {
     }
 
     pub async fn set_user_balance(&self, user_id: &str, stream_id: &str, balance: f64) -> Result<()> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.connection().await?;
         
-        let key = format!("balance:{}:{}", user_id, stream_id);
+        let key = tenant::redis_key(&format!("balance:{}:{}", user_id, stream_id));
         conn.set(&key, balance).await
             .context("Failed to set user balance")?;
 
-        self.return_connection(conn).await;
         Ok(())
     }
 
     pub async fn get_user_balance(&self, user_id: &str, stream_id: &str) -> Result<f64> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.connection().await?;
         
-        let key = format!("balance:{}:{}", user_id, stream_id);
+        let key = tenant::redis_key(&format!("balance:{}:{}", user_id, stream_id));
         let balance: Option<f64> = conn.get(&key).await
             .context("Failed to get user balance")?;
 
-        self.return_connection(conn).await;
         Ok(balance.unwrap_or(0.0))
     }
 
     pub async fn update_user_balance(&self, user_id: &str, stream_id: &str, delta: f64) -> Result<f64> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.connection().await?;
         
-        let key = format!("balance:{}:{}", user_id, stream_id);
+        let key = tenant::redis_key(&format!("balance:{}:{}", user_id, stream_id));
         let new_balance: f64 = conn.incr(&key, delta).await
             .context("Failed to update user balance")?;
 
-        self.return_connection(conn).await;
         Ok(new_balance)
     }
 
     pub async fn store_bet(&self, bet_id: &str, bet_data: &str) -> Result<()> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.connection().await?;
         
-        let key = format!("bet:{}", bet_id);
+        let key = tenant::redis_key(&format!("bet:{}", bet_id));
         conn.set(&key, bet_data).await
             .context("Failed to store bet")?;
 
@@ -177,75 +165,126 @@ impl StateManager {
         conn.expire(&key, 86400).await
             .context("Failed to set bet expiration")?;
 
-        self.return_connection(conn).await;
         Ok(())
     }
 
     pub async fn get_bet(&self, bet_id: &str) -> Result<Option<String>> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.connection().await?;
         
-        let key = format!("bet:{}", bet_id);
+        let key = tenant::redis_key(&format!("bet:{}", bet_id));
         let result: Option<String> = conn.get(&key).await
             .context("Failed to get bet")?;
 
-        self.return_connection(conn).await;
         Ok(result)
     }
 
     pub async fn increment_counter(&self, key: &str) -> Result<i64> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.connection().await?;
         
-        let count: i64 = conn.incr(key, 1).await
+        let count: i64 = conn.incr(tenant::redis_key(key), 1).await
             .context("Failed to increment counter")?;
 
-        self.return_connection(conn).await;
         Ok(count)
     }
 
     pub async fn set_key_with_expiry(&self, key: &str, value: &str, expiry_seconds: usize) -> Result<()> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.connection().await?;
         
-        conn.set_ex(key, value, expiry_seconds).await
+        conn.set_ex(tenant::redis_key(key), value, expiry_seconds).await
             .context("Failed to set key with expiry")?;
 
-        self.return_connection(conn).await;
         Ok(())
     }
 
+    /// Sets every key in one round trip, each expiring after `expiry_seconds`.
+    pub async fn set_keys_with_expiry(&self, entries: &[(String, String)], expiry_seconds: usize) -> Result<()> {
+        let mut conn = self.connection().await?;
+
+        let mut pipeline = redis::pipe();
+        for (key, value) in entries {
+            pipeline.set_ex(tenant::redis_key(key), value, expiry_seconds).ignore();
+        }
+        let _: () = pipeline.query_async(&mut conn).await
+            .context("Failed to set keys with expiry")?;
+
+        Ok(())
+    }
+
+    /// Takes the lock `name` for `ttl` unless another holder has it. `token`
+    /// identifies this holder; only the same token releases the lock early.
+    pub async fn try_lock(&self, name: &str, token: &str, ttl: std::time::Duration) -> Result<bool> {
+        let mut conn = self.connection().await?;
+
+        let acquired: Option<String> = redis::cmd("SET")
+            .arg(tenant::redis_key(&format!("lock:{}", name)))
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl.as_millis() as u64)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to take lock")?;
+
+        Ok(acquired.is_some())
+    }
+
+    /// Releases the lock `name` if `token` still holds it. Returns false if
+    /// it had expired, and perhaps been taken by someone else, first.
+    pub async fn unlock(&self, name: &str, token: &str) -> Result<bool> {
+        let mut conn = self.connection().await?;
+
+        // Compared and deleted in one step, so a lock that expired and was retaken isn't released
+        let released: i32 = redis::Script::new(
+            "if redis.call('GET', KEYS[1]) == ARGV[1] then return redis.call('DEL', KEYS[1]) else return 0 end",
+        )
+        .key(tenant::redis_key(&format!("lock:{}", name)))
+        .arg(token)
+        .invoke_async(&mut conn)
+        .await
+        .context("Failed to release lock")?;
+
+        Ok(released == 1)
+    }
+
     pub async fn set_stream_data(&self, stream_id: &str, stream_data: &str) -> Result<()> {
-        let key = format!("morphine:stream:{}", stream_id);
-        let mut conn = self.connection.lock().await;
+        let key = tenant::redis_key(&format!("morphine:stream:{}", stream_id));
+        let mut conn = self.connection().await?;
         conn.set(&key, stream_data).await?;
         conn.expire(&key, 86400).await?; // 24 hours TTL
         Ok(())
     }
 
     pub async fn get_stream_data(&self, stream_id: &str) -> Result<Option<String>> {
-        let key = format!("morphine:stream:{}", stream_id);
-        let mut conn = self.connection.lock().await;
+        let key = tenant::redis_key(&format!("morphine:stream:{}", stream_id));
+        let mut conn = self.connection().await?;
         let result: Option<String> = conn.get(&key).await?;
         Ok(result)
     }
 
     pub async fn delete_stream(&self, stream_id: &str) -> Result<()> {
-        let key = format!("morphine:stream:{}", stream_id);
-        let mut conn = self.connection.lock().await;
+        let key = tenant::redis_key(&format!("morphine:stream:{}", stream_id));
+        let mut conn = self.connection().await?;
         conn.del(&key).await?;
+        // The cached stream `set_stream` wrote, and its entry in the stream list
+        conn.del(tenant::redis_key(&format!("stream:{}", stream_id))).await?;
+        conn.srem(tenant::redis_key("streams"), stream_id).await?;
         Ok(())
     }
 
-    pub async fn set_analytics(&self, stream_id: &str, analytics_data: &str) -> Result<()> {
-        let key = format!("morphine:analytics:{}", stream_id);
+    pub async fn set_analytics(&self, stream_id: &str, analytics: &AnalyticsEvent) -> Result<()> {
+        let analytics_data = serde_json::to_string(analytics)
+            .context("Failed to serialize analytics event")?;
+        let key = tenant::redis_key(&format!("morphine:analytics:{}", stream_id));
         let timestamp = chrono::Utc::now().timestamp_millis();
         
-        let mut conn = self.connection.lock().await;
+        let mut conn = self.connection().await?;
         
         // Store latest analytics
-        conn.set(&format!("{}:latest", key), analytics_data).await?;
+        conn.set(&format!("{}:latest", key), &analytics_data).await?;
         conn.expire(&format!("{}:latest", key), 300).await?; // 5 minutes TTL
         
         // Store in time series (sorted set)
-        conn.zadd(&format!("{}:history", key), analytics_data, timestamp).await?;
+        conn.zadd(&format!("{}:history", key), &analytics_data, timestamp).await?;
         conn.expire(&format!("{}:history", key), 3600).await?; // 1 hour TTL
         
         // Limit history size
@@ -257,98 +296,160 @@ impl StateManager {
         Ok(())
     }
 
-    pub async fn get_latest_analytics(&self, stream_id: &str) -> Result<Option<String>> {
-        let key = format!("morphine:analytics:{}:latest", stream_id);
-        let mut conn = self.connection.lock().await;
+    pub async fn get_latest_analytics(&self, stream_id: &str) -> Result<Option<AnalyticsEvent>> {
+        let key = tenant::redis_key(&format!("morphine:analytics:{}:latest", stream_id));
+        let mut conn = self.connection().await?;
         let result: Option<String> = conn.get(&key).await?;
-        Ok(result)
+        result
+            .map(|serialized| serde_json::from_str(&serialized).context("Failed to deserialize analytics event"))
+            .transpose()
     }
 
-    pub async fn get_analytics_history(&self, stream_id: &str, start_time: i64, end_time: i64) -> Result<Vec<String>> {
-        let key = format!("morphine:analytics:{}:history", stream_id);
-        let mut conn = self.connection.lock().await;
+    /// Frames stored between the two times, in milliseconds; frames that no
+    /// longer parse are skipped.
+    pub async fn get_analytics_history(&self, stream_id: &str, start_time: i64, end_time: i64) -> Result<Vec<AnalyticsEvent>> {
+        let key = tenant::redis_key(&format!("morphine:analytics:{}:history", stream_id));
+        let mut conn = self.connection().await?;
         let results: Vec<String> = conn.zrangebyscore(&key, start_time, end_time).await?;
-        Ok(results)
+        Ok(results.iter()
+            .filter_map(|serialized| serde_json::from_str(serialized).ok())
+            .collect())
     }
 
     pub async fn set_bet(&self, bet_id: &str, bet_data: &str) -> Result<()> {
-        let key = format!("morphine:bet:{}", bet_id);
-        let mut conn = self.connection.lock().await;
+        let key = tenant::redis_key(&format!("morphine:bet:{}", bet_id));
+        let mut conn = self.connection().await?;
         conn.set(&key, bet_data).await?;
         conn.expire(&key, 86400).await?; // 24 hours TTL
         Ok(())
     }
 
     pub async fn add_user_bet(&self, user_id: &str, bet_id: &str, timestamp: i64) -> Result<()> {
-        let key = format!("morphine:user:{}:bets", user_id);
-        let mut conn = self.connection.lock().await;
+        let key = tenant::redis_key(&format!("morphine:user:{}:bets", user_id));
+        let mut conn = self.connection().await?;
         conn.zadd(&key, bet_id, timestamp).await?;
         conn.expire(&key, 86400 * 30).await?; // 30 days TTL
         Ok(())
     }
 
     pub async fn get_user_bets(&self, user_id: &str, limit: i64) -> Result<Vec<String>> {
-        let key = format!("morphine:user:{}:bets", user_id);
-        let mut conn = self.connection.lock().await;
+        let key = tenant::redis_key(&format!("morphine:user:{}:bets", user_id));
+        let mut conn = self.connection().await?;
         let results: Vec<String> = conn.zrevrange(&key, 0, limit - 1).await?;
         Ok(results)
     }
 
-    pub async fn set_session(&self, session_id: &str, user_data: &str) -> Result<()> {
+    // Sessions are checked before a request's tenant is resolved, so their
+    // keys aren't tenant-scoped; session and user IDs are unique across tenants
+    pub async fn set_session(&self, session_id: &str, user_data: &str, ttl_secs: usize) -> Result<()> {
         let key = format!("morphine:session:{}", session_id);
-        let mut conn = self.connection.lock().await;
-        conn.set(&key, user_data).await?;
-        conn.expire(&key, 3600).await?; // 1 hour TTL
+        let mut conn = self.connection().await?;
+        let _: () = conn.set_ex(&key, user_data, ttl_secs).await?;
         Ok(())
     }
 
     pub async fn get_session(&self, session_id: &str) -> Result<Option<String>> {
         let key = format!("morphine:session:{}", session_id);
-        let mut conn = self.connection.lock().await;
+        let mut conn = self.connection().await?;
         let result: Option<String> = conn.get(&key).await?;
         Ok(result)
     }
 
     pub async fn delete_session(&self, session_id: &str) -> Result<()> {
         let key = format!("morphine:session:{}", session_id);
-        let mut conn = self.connection.lock().await;
-        conn.del(&key).await?;
+        let mut conn = self.connection().await?;
+        let _: () = conn.del(&key).await?;
+        Ok(())
+    }
+
+    /// Indexes `session_id` under its user. The index outlives every session
+    /// in it by being extended to `ttl_secs` on each addition.
+    pub async fn add_user_session(&self, user_id: &str, session_id: &str, ttl_secs: usize) -> Result<()> {
+        let key = format!("morphine:user:{}:sessions", user_id);
+        let mut conn = self.connection().await?;
+        let _: () = conn.sadd(&key, session_id).await?;
+        let _: () = conn.expire(&key, ttl_secs).await?;
+        Ok(())
+    }
+
+    pub async fn get_user_sessions(&self, user_id: &str) -> Result<Vec<String>> {
+        let key = format!("morphine:user:{}:sessions", user_id);
+        let mut conn = self.connection().await?;
+        let session_ids: Vec<String> = conn.smembers(&key).await?;
+        Ok(session_ids)
+    }
+
+    pub async fn remove_user_session(&self, user_id: &str, session_id: &str) -> Result<()> {
+        let key = format!("morphine:user:{}:sessions", user_id);
+        let mut conn = self.connection().await?;
+        let _: () = conn.srem(&key, session_id).await?;
+        Ok(())
+    }
+
+    /// Marks `session_id` revoked for `ttl_secs`, which must cover whatever
+    /// is left of the credential's lifetime.
+    pub async fn revoke_session(&self, session_id: &str, ttl_secs: usize) -> Result<()> {
+        let key = format!("morphine:session:{}:revoked", session_id);
+        let mut conn = self.connection().await?;
+        let _: () = conn.set_ex(&key, chrono::Utc::now().timestamp(), ttl_secs).await?;
         Ok(())
     }
 
+    pub async fn is_session_revoked(&self, session_id: &str) -> Result<bool> {
+        let key = format!("morphine:session:{}:revoked", session_id);
+        let mut conn = self.connection().await?;
+        let revoked: bool = conn.exists(&key).await?;
+        Ok(revoked)
+    }
+
+    // A user's latest location verification, as far as betting is concerned
+    pub async fn set_location_clearance(&self, user_id: &str, clearance: &str, ttl_secs: usize) -> Result<()> {
+        let key = tenant::redis_key(&format!("morphine:location:clearance:{}", user_id));
+        let mut conn = self.connection().await?;
+        let _: () = conn.set_ex(&key, clearance, ttl_secs).await?;
+        Ok(())
+    }
+
+    pub async fn get_location_clearance(&self, user_id: &str) -> Result<Option<String>> {
+        let key = tenant::redis_key(&format!("morphine:location:clearance:{}", user_id));
+        let mut conn = self.connection().await?;
+        let result: Option<String> = conn.get(&key).await?;
+        Ok(result)
+    }
+
     pub async fn add_viewer(&self, stream_id: &str, viewer_id: &str) -> Result<()> {
-        let key = format!("morphine:stream:{}:viewers", stream_id);
-        let mut conn = self.connection.lock().await;
+        let key = tenant::redis_key(&format!("morphine:stream:{}:viewers", stream_id));
+        let mut conn = self.connection().await?;
         conn.sadd(&key, viewer_id).await?;
         conn.expire(&key, 300).await?; // 5 minutes TTL
         Ok(())
     }
 
     pub async fn remove_viewer(&self, stream_id: &str, viewer_id: &str) -> Result<()> {
-        let key = format!("morphine:stream:{}:viewers", stream_id);
-        let mut conn = self.connection.lock().await;
+        let key = tenant::redis_key(&format!("morphine:stream:{}:viewers", stream_id));
+        let mut conn = self.connection().await?;
         conn.srem(&key, viewer_id).await?;
         Ok(())
     }
 
     pub async fn get_viewer_count(&self, stream_id: &str) -> Result<u32> {
-        let key = format!("morphine:stream:{}:viewers", stream_id);
-        let mut conn = self.connection.lock().await;
+        let key = tenant::redis_key(&format!("morphine:stream:{}:viewers", stream_id));
+        let mut conn = self.connection().await?;
         let count: u32 = conn.scard(&key).await?;
         Ok(count)
     }
 
     pub async fn ping(&self) -> Result<()> {
-        let mut conn = self.connection.lock().await;
+        let mut conn = self.connection().await?;
         let _: String = conn.ping().await?;
         Ok(())
     }
 
     pub async fn cleanup_expired_data(&self) -> Result<()> {
-        let mut conn = self.connection.lock().await;
+        let mut conn = self.connection().await?;
         
         // Clean up expired analytics history
-        let analytics_keys: Vec<String> = conn.keys("morphine:analytics:*:history").await?;
+        let analytics_keys: Vec<String> = conn.keys("*morphine:analytics:*:history").await?;
         for key in analytics_keys {
             let cutoff_time = chrono::Utc::now().timestamp_millis() - (3600 * 1000); // 1 hour ago
             let _: i64 = conn.zremrangebyscore(&key, 0, cutoff_time).await?;
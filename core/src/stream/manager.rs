@@ -1,4 +1,5 @@
 use super::types::*;
+use crate::runtime;
 use crate::state::StateManager;
 use anyhow::{Result, Context};
 use dashmap::DashMap;
@@ -271,7 +272,7 @@ impl StreamManager {
         let state_manager = self.state_manager.clone();
         let streams = self.active_streams.clone();
 
-        tokio::spawn(async move {
+        runtime::spawn("streams:activation_monitor", async move {
             let mut interval = tokio::time::interval(Duration::from_secs(5));
             
             loop {
@@ -293,7 +294,7 @@ impl StreamManager {
         let state_manager = self.state_manager.clone();
         let streams = self.active_streams.clone();
 
-        tokio::spawn(async move {
+        runtime::spawn("streams:timeout_monitor", async move {
             let mut interval = tokio::time::interval(Duration::from_secs(30));
             
             loop {
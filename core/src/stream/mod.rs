@@ -1,4 +1,6 @@
 pub mod manager;
+pub mod quotas;
+pub mod store;
 pub mod types;
 
 pub use manager::StreamManager;
@@ -10,8 +12,14 @@ use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use anyhow::{Result, anyhow};
+use sqlx::{Pool, Postgres};
 
+use crate::error_reporting::{self, ReportContext};
+use crate::runtime;
 use crate::state::StateManager;
+use crate::tenant::{self, Tenant};
+use quotas::StreamQuotas;
+use store::StreamStore;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamInfo {
@@ -22,6 +30,9 @@ pub struct StreamInfo {
     pub settings: StreamSettings,
     pub analytics_enabled: bool,
     pub viewer_count: u32,
+    // Where its ingest lands: the region that last activated it
+    #[serde(default)]
+    pub region: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,52 +62,124 @@ pub struct ActivationResult {
 
 pub struct StreamManager {
     state_manager: Arc<StateManager>,
+    // The durable record; Redis and `streams` cache it
+    store: Arc<StreamStore>,
     streams: Arc<RwLock<HashMap<String, StreamInfo>>>,
     activation_queue: Arc<RwLock<Vec<String>>>,
+    default_tenant: String,
+    // This instance's region, tagged on the streams it creates and activates
+    region: String,
+    quotas: Arc<StreamQuotas>,
 }
 
 impl StreamManager {
-    pub async fn new(state_manager: Arc<StateManager>) -> Result<Self> {
+    pub async fn new(
+        state_manager: Arc<StateManager>,
+        db_pool: Pool<Postgres>,
+        default_tenant: String,
+        region: String,
+        quotas: Arc<StreamQuotas>,
+    ) -> Result<Self> {
         let streams = Arc::new(RwLock::new(HashMap::new()));
         let activation_queue = Arc::new(RwLock::new(Vec::new()));
         
         let manager = Self {
             state_manager,
+            store: Arc::new(StreamStore::new(db_pool)),
             streams,
             activation_queue,
+            default_tenant,
+            region,
+            quotas,
         };
         
-        // Load existing streams from state
         manager.load_streams().await?;
         
         Ok(manager)
     }
     
+    /// Reconciles Redis with Postgres: streams only Redis has are saved to
+    /// Postgres, then every stored stream is loaded and re-cached as its tenant.
     async fn load_streams(&self) -> Result<()> {
-        // Load stream data from Redis/state storage
-        let stream_keys = self.state_manager.get_stream_keys().await?;
-        let mut streams = self.streams.write().await;
-        
-        for key in stream_keys {
-            if let Ok(Some(stream_data)) = self.state_manager.get_stream(&key).await {
-                if let Ok(stream_info) = serde_json::from_str::<StreamInfo>(&stream_data) {
-                    streams.insert(stream_info.id.clone(), stream_info);
+        // Streams created before Postgres kept them; only the default tenant's keyspace is visible here
+        let cached_ids = self.state_manager.get_stream_keys().await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to list cached streams: {}", e);
+            Vec::new()
+        });
+        let mut adopted = 0;
+        for stream_id in cached_ids {
+            if let Ok(Some(stream)) = self.state_manager.get_stream(&stream_id).await {
+                if self.store.find(&stream.id).await?.is_none() {
+                    self.store.save(&stream).await?;
+                    adopted += 1;
                 }
             }
         }
+        if adopted > 0 {
+            tracing::info!("Saved {} streams found only in Redis to Postgres", adopted);
+        }
+
+        let stored = self.store.load_all().await?;
+        let mut streams = self.streams.write().await;
+        for (tenant_id, stream) in stored {
+            let tenant = Tenant { is_default: tenant_id == self.default_tenant, id: tenant_id };
+            if let Err(e) = tenant::scope(tenant, self.state_manager.set_stream(&stream.id, &stream)).await {
+                tracing::warn!("Failed to cache stream {}: {}", stream.id, e);
+            }
+            streams.insert(stream.id.clone(), stream);
+        }
         
-        tracing::info!("Loaded {} streams from state", streams.len());
+        tracing::info!("Loaded {} streams from Postgres", streams.len());
         Ok(())
     }
     
+    // Postgres first; a cache that missed the write is corrected on the next change or restart
+    async fn persist(store: &StreamStore, state_manager: &StateManager, stream: &StreamInfo) -> Result<()> {
+        store.save(stream).await?;
+        if let Err(e) = state_manager.set_stream(&stream.id, stream).await {
+            tracing::warn!("Failed to cache stream {}: {}", stream.id, e);
+        }
+        Ok(())
+    }
+    
+    /// The concurrency limits activation is held to.
+    pub fn quotas(&self) -> &Arc<StreamQuotas> {
+        &self.quotas
+    }
+    
     pub async fn list_streams(&self) -> Result<Vec<StreamInfo>> {
         let streams = self.streams.read().await;
         Ok(streams.values().cloned().collect())
     }
     
+    /// A stream this instance has in memory, or else one another instance
+    /// created, from the Redis cache or Postgres.
     pub async fn get_stream(&self, stream_id: &str) -> Result<Option<StreamInfo>> {
-        let streams = self.streams.read().await;
-        Ok(streams.get(stream_id).cloned())
+        if let Some(stream) = self.streams.read().await.get(stream_id) {
+            return Ok(Some(stream.clone()));
+        }
+        
+        let stream = match self.state_manager.get_stream(stream_id).await {
+            Ok(Some(stream)) => stream,
+            _ => match self.store.find(stream_id).await? {
+                Some(stream) => {
+                    if let Err(e) = self.state_manager.set_stream(stream_id, &stream).await {
+                        tracing::warn!("Failed to cache stream {}: {}", stream_id, e);
+                    }
+                    stream
+                }
+                None => return Ok(None),
+            },
+        };
+        self.streams.write().await.insert(stream.id.clone(), stream.clone());
+        Ok(Some(stream))
+    }
+    
+    /// The tenant the stream was created in, for work that arrives without
+    /// one, such as datagrams. None if there's no such stream.
+    pub async fn stream_tenant(&self, stream_id: &str) -> Result<Option<Tenant>> {
+        let tenant_id = self.store.tenant_of(stream_id).await?;
+        Ok(tenant_id.map(|id| Tenant { is_default: id == self.default_tenant, id }))
     }
     
     pub async fn create_stream(&self, title: String, settings: StreamSettings) -> Result<StreamInfo> {
@@ -109,11 +192,10 @@ impl StreamManager {
             settings,
             analytics_enabled: true,
             viewer_count: 0,
+            region: Some(self.region.clone()),
         };
         
-        // Store in state
-        let stream_data = serde_json::to_string(&stream_info)?;
-        self.state_manager.set_stream(&stream_id, &stream_data).await?;
+        Self::persist(&self.store, &self.state_manager, &stream_info).await?;
         
         // Add to local cache
         let mut streams = self.streams.write().await;
@@ -126,6 +208,11 @@ impl StreamManager {
     pub async fn try_activate_stream(&self, stream_id: &str) -> Result<ActivationResult> {
         let mut streams = self.streams.write().await;
         
+        // Counted before borrowing the stream to change it
+        let active_count = streams.values()
+            .filter(|s| matches!(s.status, StreamStatus::Active | StreamStatus::Activating))
+            .count();
+        
         let stream = streams.get_mut(stream_id)
             .ok_or_else(|| anyhow!("Stream not found: {}", stream_id))?;
         
@@ -149,16 +236,8 @@ impl StreamManager {
             _ => {}
         }
         
-        // Check if we can activate more streams
-        let active_count = streams.values()
-            .filter(|s| matches!(s.status, StreamStatus::Active | StreamStatus::Activating))
-            .count();
-            
-        let max_concurrent = std::env::var("MAX_CONCURRENT_STREAMS")
-            .unwrap_or_else(|_| "10".to_string())
-            .parse::<usize>()
-            .unwrap_or(10);
-            
+        // Check if we can activate more streams, here and under the creator's and tenant's quotas
+        let max_concurrent = self.quotas.instance_limit();
         if active_count >= max_concurrent {
             return Ok(ActivationResult {
                 success: false,
@@ -167,13 +246,19 @@ impl StreamManager {
                 message: format!("Maximum concurrent streams reached: {}", max_concurrent),
             });
         }
+        if let Some(message) = self.quotas.refusal(stream_id).await? {
+            return Ok(ActivationResult {
+                success: false,
+                stream_id: stream_id.to_string(),
+                status: stream.status.clone(),
+                message,
+            });
+        }
         
-        // Set to activating state
+        // Set to activating state; ingest lands in this region from now on
         stream.status = StreamStatus::Activating;
-        
-        // Update state
-        let stream_data = serde_json::to_string(&stream)?;
-        self.state_manager.set_stream(stream_id, &stream_data).await?;
+        stream.region = Some(self.region.clone());
+        Self::persist(&self.store, &self.state_manager, stream).await?;
         
         // Add to activation queue
         let mut queue = self.activation_queue.write().await;
@@ -181,18 +266,27 @@ impl StreamManager {
         
         // Spawn activation task
         let state_manager = self.state_manager.clone();
+        let store = self.store.clone();
         let streams_ref = self.streams.clone();
         let stream_id_owned = stream_id.to_string();
-        
-        tokio::spawn(async move {
+        let activation = async move {
+            error_reporting::set_context(ReportContext { stream_id: Some(&stream_id_owned), ..Default::default() });
             if let Err(e) = Self::activate_stream_async(
                 state_manager,
+                store,
                 streams_ref,
                 stream_id_owned
             ).await {
                 tracing::error!("Failed to activate stream: {}", e);
             }
-        });
+        };
+        
+        // Cached in the keyspace of the tenant activating it
+        let task = format!("streams:activation:{}", stream_id);
+        match tenant::current() {
+            Some(tenant) => runtime::spawn(&task, tenant::scope(tenant, activation)),
+            None => runtime::spawn(&task, activation),
+        };
         
         Ok(ActivationResult {
             success: true,
@@ -204,6 +298,7 @@ impl StreamManager {
     
     async fn activate_stream_async(
         state_manager: Arc<StateManager>,
+        store: Arc<StreamStore>,
         streams: Arc<RwLock<HashMap<String, StreamInfo>>>,
         stream_id: String,
     ) -> Result<()> {
@@ -215,10 +310,7 @@ impl StreamManager {
             let mut streams_guard = streams.write().await;
             if let Some(stream) = streams_guard.get_mut(&stream_id) {
                 stream.status = StreamStatus::Active;
-                
-                // Update state
-                let stream_data = serde_json::to_string(&stream)?;
-                state_manager.set_stream(&stream_id, &stream_data).await?;
+                Self::persist(&store, &state_manager, stream).await?;
             }
         }
         
@@ -232,10 +324,7 @@ impl StreamManager {
         if let Some(stream) = streams.get_mut(stream_id) {
             stream.status = StreamStatus::Inactive;
             stream.viewer_count = 0;
-            
-            // Update state
-            let stream_data = serde_json::to_string(&stream)?;
-            self.state_manager.set_stream(stream_id, &stream_data).await?;
+            Self::persist(&self.store, &self.state_manager, stream).await?;
             
             tracing::info!("Stream deactivated: {}", stream_id);
         }
@@ -253,10 +342,7 @@ impl StreamManager {
         
         if let Some(stream) = streams.get_mut(stream_id) {
             stream.viewer_count = count;
-            
-            // Update state
-            let stream_data = serde_json::to_string(&stream)?;
-            self.state_manager.set_stream(stream_id, &stream_data).await?;
+            Self::persist(&self.store, &self.state_manager, stream).await?;
         }
         
         Ok(())
@@ -266,6 +352,8 @@ impl StreamManager {
         // First deactivate if active
         self.deactivate_stream(stream_id).await?;
         
+        self.store.delete(stream_id).await?;
+        
         // Remove from local cache
         let mut streams = self.streams.write().await;
         streams.remove(stream_id);
@@ -277,6 +365,34 @@ impl StreamManager {
         Ok(())
     }
     
+    /// Replaces this instance's copy of a stream with the stored one, after
+    /// another instance changed it. Returns None if it's gone, and forgets it.
+    pub async fn reload_stream(&self, stream_id: &str) -> Result<Option<StreamInfo>> {
+        let Some(stream) = self.store.find(stream_id).await? else {
+            self.streams.write().await.remove(stream_id);
+            return Ok(None);
+        };
+        if let Err(e) = self.state_manager.set_stream(stream_id, &stream).await {
+            tracing::warn!("Failed to cache stream {}: {}", stream_id, e);
+        }
+        self.streams.write().await.insert(stream.id.clone(), stream.clone());
+        Ok(Some(stream))
+    }
+    
+    /// Brings back a deleted stream, inactive as deletion left it. Returns None if it wasn't deleted.
+    pub async fn restore_stream(&self, stream_id: &str) -> Result<Option<StreamInfo>> {
+        let Some(stream) = self.store.restore(stream_id).await? else {
+            return Ok(None);
+        };
+        if let Err(e) = self.state_manager.set_stream(stream_id, &stream).await {
+            tracing::warn!("Failed to cache stream {}: {}", stream_id, e);
+        }
+        self.streams.write().await.insert(stream.id.clone(), stream.clone());
+        
+        tracing::info!("Stream restored: {}", stream_id);
+        Ok(Some(stream))
+    }
+    
     pub async fn get_active_streams(&self) -> Result<Vec<StreamInfo>> {
         let streams = self.streams.read().await;
         Ok(streams.values()
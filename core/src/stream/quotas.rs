@@ -0,0 +1,264 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres, Row};
+use utoipa::ToSchema;
+
+use crate::config::StreamQuotaConfig;
+use crate::tenant;
+
+// Streams holding a slot, by the status names the store writes
+const HOLDING_SLOT: &str = "status IN ('Active', 'Activating') AND deleted_at IS NULL";
+
+/// Whose streams a quota counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaScope {
+    Creator,
+    Tenant,
+}
+
+impl QuotaScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaScope::Creator => "creator",
+            QuotaScope::Tenant => "tenant",
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "creator" => Ok(QuotaScope::Creator),
+            "tenant" => Ok(QuotaScope::Tenant),
+            other => Err(anyhow!("Unknown quota scope {:?}", other)),
+        }
+    }
+}
+
+/// An admin's replacement for the configured quota of one creator, or of the tenant.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QuotaOverride {
+    pub scope: QuotaScope,
+    // The creator's user ID, or the tenant's ID
+    pub subject_id: String,
+    // 0 means no limit
+    pub max_active: u32,
+    pub updated_by: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// How many streams a creator or tenant has active or activating, against
+/// how many they may.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QuotaUsage {
+    pub scope: QuotaScope,
+    pub subject_id: String,
+    pub active: u32,
+    // None when there's no limit
+    pub limit: Option<u32>,
+    // Whether an admin set the limit rather than `[stream_quotas]`
+    pub overridden: bool,
+}
+
+impl QuotaUsage {
+    fn is_full(&self) -> bool {
+        self.limit.is_some_and(|limit| self.active >= limit)
+    }
+}
+
+/// Per-creator and per-tenant limits on concurrent streams, counted from
+/// Postgres so they hold across instances, and the instance-wide limit the
+/// stream manager counts itself.
+pub struct StreamQuotas {
+    db_pool: Pool<Postgres>,
+    config: StreamQuotaConfig,
+    instance_limit: usize,
+    default_tenant: String,
+}
+
+impl StreamQuotas {
+    pub fn new(db_pool: Pool<Postgres>, config: StreamQuotaConfig, instance_limit: usize, default_tenant: String) -> Self {
+        Self { db_pool, config, instance_limit, default_tenant }
+    }
+
+    /// Streams one instance may have active or activating at once.
+    pub fn instance_limit(&self) -> usize {
+        self.instance_limit
+    }
+
+    /// The tenant a request is acting in, whose quota `QuotaScope::Tenant` means.
+    pub fn current_tenant(&self) -> String {
+        tenant::current().map(|tenant| tenant.id).unwrap_or_else(|| self.default_tenant.clone())
+    }
+
+    /// Why activating `stream_id` would take its creator or tenant over
+    /// quota, if it would. The stream itself isn't counted, so one already
+    /// holding a slot is never refused.
+    pub async fn refusal(&self, stream_id: &str) -> Result<Option<String>> {
+        let row = sqlx::query(
+            "SELECT s.tenant_id, o.creator_id FROM streams s LEFT JOIN stream_owners o ON o.stream_id = s.id WHERE s.id = $1"
+        )
+        .bind(stream_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+        // Not stored yet; the manager reports streams it doesn't know
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let tenant_id: String = row.get("tenant_id");
+        let creator_id: Option<String> = row.get("creator_id");
+
+        if let Some(creator_id) = creator_id {
+            let usage = self.usage_excluding(&tenant_id, QuotaScope::Creator, &creator_id, Some(stream_id)).await?;
+            if usage.is_full() {
+                return Ok(Some(format!(
+                    "Creator quota reached: {} of {} streams active",
+                    usage.active,
+                    usage.limit.unwrap_or_default()
+                )));
+            }
+        }
+        let usage = self.usage_excluding(&tenant_id, QuotaScope::Tenant, &tenant_id, Some(stream_id)).await?;
+        if usage.is_full() {
+            return Ok(Some(format!(
+                "Tenant quota reached: {} of {} streams active",
+                usage.active,
+                usage.limit.unwrap_or_default()
+            )));
+        }
+        Ok(None)
+    }
+
+    /// A creator's, or with `QuotaScope::Tenant` the tenant's, usage in the current tenant.
+    pub async fn usage(&self, scope: QuotaScope, subject_id: &str) -> Result<QuotaUsage> {
+        self.usage_excluding(&self.current_tenant(), scope, subject_id, None).await
+    }
+
+    async fn usage_excluding(
+        &self,
+        tenant_id: &str,
+        scope: QuotaScope,
+        subject_id: &str,
+        excluding: Option<&str>,
+    ) -> Result<QuotaUsage> {
+        let active: i64 = match scope {
+            QuotaScope::Creator => sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM streams s JOIN stream_owners o ON o.stream_id = s.id \
+                 WHERE s.tenant_id = $1 AND o.creator_id = $2 AND ($3::text IS NULL OR s.id <> $3) AND {}",
+                HOLDING_SLOT
+            ))
+            .bind(tenant_id)
+            .bind(subject_id)
+            .bind(excluding)
+            .fetch_one(&self.db_pool)
+            .await?,
+            QuotaScope::Tenant => sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM streams WHERE tenant_id = $1 AND ($2::text IS NULL OR id <> $2) AND {}",
+                HOLDING_SLOT
+            ))
+            .bind(tenant_id)
+            .bind(excluding)
+            .fetch_one(&self.db_pool)
+            .await?,
+        };
+
+        let overridden: Option<i32> = sqlx::query_scalar(
+            "SELECT max_active FROM stream_quotas WHERE tenant_id = $1 AND scope = $2 AND subject_id = $3"
+        )
+        .bind(tenant_id)
+        .bind(scope.as_str())
+        .bind(subject_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+        let configured = match scope {
+            QuotaScope::Creator => self.config.per_creator,
+            QuotaScope::Tenant => self.config.per_tenant,
+        };
+        let limit = overridden.map(|max_active| max_active.max(0) as u32).unwrap_or(configured);
+
+        Ok(QuotaUsage {
+            scope,
+            subject_id: subject_id.to_string(),
+            active: active.max(0) as u32,
+            limit: (limit > 0).then_some(limit),
+            overridden: overridden.is_some(),
+        })
+    }
+
+    /// The current tenant's overrides, the tenant's own first.
+    pub async fn list_overrides(&self) -> Result<Vec<QuotaOverride>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM stream_quotas WHERE tenant_id = $1 ORDER BY scope DESC, subject_id",
+            OVERRIDE_COLUMNS
+        ))
+        .bind(self.current_tenant())
+        .fetch_all(&self.db_pool)
+        .await?;
+        rows.iter().map(override_from_row).collect()
+    }
+
+    pub async fn find_override(&self, scope: QuotaScope, subject_id: &str) -> Result<Option<QuotaOverride>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM stream_quotas WHERE tenant_id = $1 AND scope = $2 AND subject_id = $3",
+            OVERRIDE_COLUMNS
+        ))
+        .bind(self.current_tenant())
+        .bind(scope.as_str())
+        .bind(subject_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+        row.as_ref().map(override_from_row).transpose()
+    }
+
+    /// Replaces the quota for `subject_id` in the current tenant. Streams
+    /// already active over a lowered quota keep running; only activations
+    /// are refused.
+    pub async fn set_override(&self, scope: QuotaScope, subject_id: &str, max_active: u32) -> Result<QuotaOverride> {
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO stream_quotas (tenant_id, scope, subject_id, max_active)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (tenant_id, scope, subject_id) DO UPDATE SET
+                max_active = EXCLUDED.max_active,
+                updated_by = COALESCE(morphine_current_actor(), 'system'),
+                updated_at = NOW()
+            RETURNING {}
+            "#,
+            OVERRIDE_COLUMNS
+        ))
+        .bind(self.current_tenant())
+        .bind(scope.as_str())
+        .bind(subject_id)
+        .bind(i32::try_from(max_active)?)
+        .fetch_one(&self.db_pool)
+        .await?;
+        override_from_row(&row)
+    }
+
+    /// Puts `subject_id` back on the configured quota. Returns the override
+    /// removed, or None if there wasn't one.
+    pub async fn clear_override(&self, scope: QuotaScope, subject_id: &str) -> Result<Option<QuotaOverride>> {
+        let row = sqlx::query(&format!(
+            "DELETE FROM stream_quotas WHERE tenant_id = $1 AND scope = $2 AND subject_id = $3 RETURNING {}",
+            OVERRIDE_COLUMNS
+        ))
+        .bind(self.current_tenant())
+        .bind(scope.as_str())
+        .bind(subject_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+        row.as_ref().map(override_from_row).transpose()
+    }
+}
+
+const OVERRIDE_COLUMNS: &str = "scope, subject_id, max_active, updated_by, updated_at";
+
+fn override_from_row(row: &sqlx::postgres::PgRow) -> Result<QuotaOverride> {
+    Ok(QuotaOverride {
+        scope: QuotaScope::parse(row.get::<&str, _>("scope"))?,
+        subject_id: row.get("subject_id"),
+        max_active: row.get::<i32, _>("max_active").max(0) as u32,
+        updated_by: row.get("updated_by"),
+        updated_at: row.get("updated_at"),
+    })
+}
@@ -0,0 +1,140 @@
+use anyhow::{anyhow, Result};
+use sqlx::{Pool, Postgres, Row};
+
+use super::{StreamInfo, StreamStatus};
+
+/// Streams in Postgres, the durable record of them. Redis and the stream
+/// manager's memory only cache what is here. Deleted streams keep their row,
+/// with `deleted_at` set, and only `restore` reads them.
+pub struct StreamStore {
+    db_pool: Pool<Postgres>,
+}
+
+impl StreamStore {
+    pub fn new(db_pool: Pool<Postgres>) -> Self {
+        Self { db_pool }
+    }
+
+    /// Saves `stream`, replacing the stored stream with the same ID unless that was deleted.
+    pub async fn save(&self, stream: &StreamInfo) -> Result<()> {
+        let (status, status_detail) = status_parts(&stream.status);
+        sqlx::query(
+            r#"
+            INSERT INTO streams (id, title, status, status_detail, created_at, settings, analytics_enabled, viewer_count, region)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (id) DO UPDATE SET
+                title = EXCLUDED.title,
+                status = EXCLUDED.status,
+                status_detail = EXCLUDED.status_detail,
+                settings = EXCLUDED.settings,
+                analytics_enabled = EXCLUDED.analytics_enabled,
+                viewer_count = EXCLUDED.viewer_count,
+                region = EXCLUDED.region,
+                updated_at = NOW()
+            WHERE streams.deleted_at IS NULL
+            "#
+        )
+        .bind(&stream.id)
+        .bind(&stream.title)
+        .bind(status)
+        .bind(status_detail)
+        .bind(stream.created_at)
+        .bind(serde_json::to_value(&stream.settings)?)
+        .bind(stream.analytics_enabled)
+        .bind(stream.viewer_count as i32)
+        .bind(&stream.region)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find(&self, stream_id: &str) -> Result<Option<StreamInfo>> {
+        let row = sqlx::query(&format!("SELECT {} FROM streams WHERE id = $1 AND deleted_at IS NULL", STREAM_COLUMNS))
+            .bind(stream_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+        row.map(|row| stream_from_row(&row)).transpose()
+    }
+
+    /// Every stored stream, with the tenant it belongs to, oldest first.
+    pub async fn load_all(&self) -> Result<Vec<(String, StreamInfo)>> {
+        let rows = sqlx::query(&format!("SELECT {}, tenant_id FROM streams WHERE deleted_at IS NULL ORDER BY created_at", STREAM_COLUMNS))
+            .fetch_all(&self.db_pool)
+            .await?;
+
+        let mut streams = Vec::with_capacity(rows.len());
+        for row in &rows {
+            // A row the API didn't write, without settings, isn't a stream the manager can serve
+            match stream_from_row(row) {
+                Ok(stream) => streams.push((row.get("tenant_id"), stream)),
+                Err(e) => tracing::warn!("Skipping stored stream {}: {}", row.get::<String, _>("id"), e),
+            }
+        }
+        Ok(streams)
+    }
+
+    /// The tenant the stream belongs to, or None if there's no such stream.
+    pub async fn tenant_of(&self, stream_id: &str) -> Result<Option<String>> {
+        let tenant_id = sqlx::query_scalar("SELECT tenant_id FROM streams WHERE id = $1 AND deleted_at IS NULL")
+            .bind(stream_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+        Ok(tenant_id)
+    }
+
+    /// Marks the stream deleted. Returns false if there was no such stream, or it was already deleted.
+    pub async fn delete(&self, stream_id: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE streams SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
+            .bind(stream_id)
+            .execute(&self.db_pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Undoes a deletion. Returns the stream, or None if it wasn't deleted.
+    pub async fn restore(&self, stream_id: &str) -> Result<Option<StreamInfo>> {
+        let row = sqlx::query(&format!(
+            "UPDATE streams SET deleted_at = NULL, updated_at = NOW() WHERE id = $1 AND deleted_at IS NOT NULL RETURNING {}",
+            STREAM_COLUMNS
+        ))
+        .bind(stream_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+        row.map(|row| stream_from_row(&row)).transpose()
+    }
+}
+
+const STREAM_COLUMNS: &str = "id, title, status, status_detail, created_at, settings, analytics_enabled, viewer_count, region";
+
+// The variant name, and the message of an error
+fn status_parts(status: &StreamStatus) -> (&'static str, Option<&str>) {
+    match status {
+        StreamStatus::Inactive => ("Inactive", None),
+        StreamStatus::Activating => ("Activating", None),
+        StreamStatus::Active => ("Active", None),
+        StreamStatus::Error(message) => ("Error", Some(message)),
+    }
+}
+
+fn stream_from_row(row: &sqlx::postgres::PgRow) -> Result<StreamInfo> {
+    let status = match row.get::<String, _>("status").as_str() {
+        "Inactive" => StreamStatus::Inactive,
+        "Activating" => StreamStatus::Activating,
+        "Active" => StreamStatus::Active,
+        "Error" => StreamStatus::Error(row.get::<Option<String>, _>("status_detail").unwrap_or_default()),
+        other => return Err(anyhow!("Unknown stream status {:?}", other)),
+    };
+    let settings: Option<serde_json::Value> = row.get("settings");
+    let settings = settings.ok_or_else(|| anyhow!("Stream has no settings"))?;
+
+    Ok(StreamInfo {
+        id: row.get("id"),
+        title: row.get("title"),
+        status,
+        created_at: row.get("created_at"),
+        settings: serde_json::from_value(settings)?,
+        analytics_enabled: row.get("analytics_enabled"),
+        viewer_count: row.get::<i32, _>("viewer_count").max(0) as u32,
+        region: row.get("region"),
+    })
+}
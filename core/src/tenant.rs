@@ -0,0 +1,139 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap};
+use axum::middleware::Next;
+use axum::response::Response;
+use futures::future::BoxFuture;
+use sqlx::postgres::{PgConnection, PgPool, PgPoolOptions};
+use sqlx::pool::PoolConnectionMetadata;
+use tracing::warn;
+
+use crate::audit;
+use crate::auth::Principal;
+use crate::config::{DatabasePoolConfig, TenancyConfig};
+use crate::error::ApiError;
+
+// Session setting the row-level security policies compare `tenant_id` against
+const TENANT_SETTING: &str = "morphine.tenant_id";
+// Session setting the audit columns record as `created_by` and `updated_by`
+const ACTOR_SETTING: &str = "morphine.actor";
+
+tokio::task_local! {
+    static CURRENT_TENANT: Tenant;
+}
+
+/// The deployment a request belongs to. Handlers can take `Extension<Tenant>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tenant {
+    pub id: String,
+    // The default tenant keeps the unprefixed Redis keys single-tenant deployments already have
+    pub is_default: bool,
+}
+
+/// The tenant of the request being handled. None outside a request, in
+/// background work that spans tenants.
+pub fn current() -> Option<Tenant> {
+    CURRENT_TENANT.try_with(|tenant| tenant.clone()).ok()
+}
+
+/// Runs `future` as `tenant`, for work a request hands off to another task.
+pub async fn scope<F: Future>(tenant: Tenant, future: F) -> F::Output {
+    CURRENT_TENANT.scope(tenant, future).await
+}
+
+/// `key` in the current tenant's Redis keyspace.
+pub fn redis_key(key: &str) -> String {
+    match current() {
+        Some(tenant) if !tenant.is_default => format!("tenant:{}:{}", tenant.id, key),
+        _ => key.to_string(),
+    }
+}
+
+/// A Postgres pool whose connections are scoped to the current tenant each
+/// time they are handed out, so row-level security filters every query.
+/// Connections taken outside a request see all tenants. They also carry the
+/// request's actor, for the audit columns.
+pub async fn connect(database_url: &str, config: &DatabasePoolConfig) -> Result<PgPool, sqlx::Error> {
+    let statement_timeout_ms = config.statement_timeout_ms;
+    let nonzero = |secs: u64| (secs > 0).then(|| Duration::from_secs(secs));
+    PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(Duration::from_millis(config.acquire_timeout_ms))
+        .idle_timeout(nonzero(config.idle_timeout_secs))
+        .max_lifetime(nonzero(config.max_lifetime_secs))
+        .after_connect(move |conn, _meta| Box::pin(async move {
+            // Set per session rather than in the URL, so it survives credential rotations replacing the connect options
+            sqlx::query(&format!("SET statement_timeout = {}", statement_timeout_ms))
+                .execute(&mut *conn)
+                .await?;
+            apply_tenant(conn).await
+        }))
+        .before_acquire(|conn, _meta: PoolConnectionMetadata| {
+            let applied = apply_tenant(conn);
+            Box::pin(async move { applied.await.map(|_| true) })
+        })
+        .connect(database_url)
+        .await
+}
+
+// Reads the tenant and actor now, on the acquiring task, rather than when the future is polled
+fn apply_tenant(conn: &mut PgConnection) -> BoxFuture<'_, Result<(), sqlx::Error>> {
+    let tenant_id = current().map(|tenant| tenant.id).unwrap_or_default();
+    let actor = audit::current().unwrap_or_default();
+    Box::pin(async move {
+        sqlx::query("SELECT set_config($1, $2, false), set_config($3, $4, false)")
+            .bind(TENANT_SETTING)
+            .bind(tenant_id)
+            .bind(ACTOR_SETTING)
+            .bind(actor)
+            .execute(conn)
+            .await?;
+        Ok(())
+    })
+}
+
+// Behind the gateway the original host arrives in X-Forwarded-Host
+fn request_host(headers: &HeaderMap) -> Option<String> {
+    headers.get("x-forwarded-host")
+        .or_else(|| headers.get(header::HOST))
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(|host| host.trim().split(':').next().unwrap_or("").to_ascii_lowercase())
+        .filter(|host| !host.is_empty())
+}
+
+/// Resolves the tenant from the request host and the caller's credential,
+/// and runs the rest of the request scoped to it. A credential issued for one
+/// tenant is refused on another tenant's host. Must run inside the
+/// authentication layer.
+pub async fn enforce(
+    State(config): State<Arc<TenancyConfig>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let tenant_id = if config.enabled {
+        let host_tenant = request_host(request.headers()).and_then(|host| config.hosts.get(&host).cloned());
+        let credential_tenant = request.extensions().get::<Principal>().and_then(|principal| principal.tenant.clone());
+        match (host_tenant, credential_tenant) {
+            (Some(host_tenant), Some(credential_tenant)) if host_tenant != credential_tenant => {
+                warn!(
+                    "Refused a {} credential on a {} host for {}",
+                    credential_tenant, host_tenant, request.uri().path()
+                );
+                return Err(ApiError::forbidden());
+            }
+            (host_tenant, credential_tenant) => host_tenant
+                .or(credential_tenant)
+                .unwrap_or_else(|| config.default_tenant.clone()),
+        }
+    } else {
+        config.default_tenant.clone()
+    };
+
+    let tenant = Tenant { is_default: tenant_id == config.default_tenant, id: tenant_id };
+    request.extensions_mut().insert(tenant.clone());
+    Ok(scope(tenant, next.run(request)).await)
+}
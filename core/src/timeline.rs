@@ -0,0 +1,181 @@
+use std::str::FromStr;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Pool, Postgres, Row};
+use utoipa::ToSchema;
+
+/// The most entities or events one read returns; past it the result is marked truncated.
+pub const MAX_EVENTS: i64 = 10_000;
+
+/// Reads the log of stream, bet and balance changes that triggers append to
+/// `state_events`, to see what the system held at some earlier instant:
+/// what a bettor saw when they placed a bet they now dispute, or what a
+/// balance was before a settlement went wrong.
+pub struct Timeline {
+    db_pool: Pool<Postgres>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityType {
+    Stream,
+    Bet,
+    // Identified as `user_id:stream_id`
+    Balance,
+}
+
+/// One change to an entity, with the row as it stood after the change.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StateEvent {
+    pub event_id: i64,
+    pub entity_type: EntityType,
+    pub entity_id: String,
+    // created, updated, deleted or restored
+    pub change: String,
+    #[schema(value_type = Object)]
+    pub state: Value,
+    // Who made the change; `system` for background work
+    pub actor: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Narrows a reconstruction to what one bettor or one stream touched.
+#[derive(Debug, Clone, Default)]
+pub struct TimelineFilter {
+    // Their bets and balances, and the streams those are on
+    pub user_id: Option<String>,
+    // The stream and the bets and balances on it
+    pub stream_id: Option<String>,
+}
+
+/// Every stream, bet and balance as it stood at `as_of`, each as the last
+/// change made to it by then. Entities deleted by then are left out.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StateAsOf {
+    pub as_of: DateTime<Utc>,
+    pub streams: Vec<StateEvent>,
+    pub bets: Vec<StateEvent>,
+    pub balances: Vec<StateEvent>,
+    // Set when there were more than the limit; narrow the filter to see them all
+    pub truncated: bool,
+}
+
+impl Timeline {
+    pub fn new(db_pool: Pool<Postgres>) -> Self {
+        Self { db_pool }
+    }
+
+    /// Reconstructs state as of `as_of`, returning at most `limit` entities.
+    pub async fn state_as_of(&self, as_of: DateTime<Utc>, filter: &TimelineFilter, limit: i64) -> Result<StateAsOf> {
+        let limit = limit.clamp(1, MAX_EVENTS);
+        let rows = sqlx::query(
+            r#"
+            SELECT event_id, entity_type, entity_id, change, state, actor, occurred_at FROM (
+                SELECT DISTINCT ON (entity_type, entity_id) event_id, entity_type, entity_id, change, state, actor, occurred_at
+                FROM state_events
+                WHERE occurred_at <= $1
+                  AND ($2::text IS NULL
+                    OR (entity_type <> 'stream' AND state->>'user_id' = $2)
+                    OR (entity_type = 'stream' AND entity_id IN (
+                        SELECT state->>'stream_id' FROM state_events
+                        WHERE entity_type <> 'stream' AND state->>'user_id' = $2 AND occurred_at <= $1
+                    )))
+                  AND ($3::text IS NULL
+                    OR (entity_type = 'stream' AND entity_id = $3)
+                    OR (entity_type <> 'stream' AND state->>'stream_id' = $3))
+                ORDER BY entity_type, entity_id, event_id DESC
+            ) latest
+            WHERE change <> 'deleted'
+            ORDER BY entity_type, entity_id
+            LIMIT $4
+            "#
+        )
+        .bind(as_of)
+        .bind(&filter.user_id)
+        .bind(&filter.stream_id)
+        .bind(limit + 1)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let truncated = rows.len() as i64 > limit;
+        let mut state = StateAsOf { as_of, streams: Vec::new(), bets: Vec::new(), balances: Vec::new(), truncated };
+        for row in rows.iter().take(limit as usize) {
+            let event = event_from_row(row)?;
+            match event.entity_type {
+                EntityType::Stream => state.streams.push(event),
+                EntityType::Bet => state.bets.push(event),
+                EntityType::Balance => state.balances.push(event),
+            }
+        }
+        Ok(state)
+    }
+
+    /// The changes made to one entity between `from` and `to`, oldest first,
+    /// at most `limit` of them.
+    pub async fn history(
+        &self,
+        entity_type: EntityType,
+        entity_id: &str,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<StateEvent>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT event_id, entity_type, entity_id, change, state, actor, occurred_at
+            FROM state_events
+            WHERE entity_type = $1 AND entity_id = $2
+              AND ($3::timestamptz IS NULL OR occurred_at >= $3)
+              AND ($4::timestamptz IS NULL OR occurred_at <= $4)
+            ORDER BY event_id
+            LIMIT $5
+            "#
+        )
+        .bind(entity_type.as_str())
+        .bind(entity_id)
+        .bind(from)
+        .bind(to)
+        .bind(limit.clamp(1, MAX_EVENTS))
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        rows.iter().map(event_from_row).collect()
+    }
+}
+
+impl EntityType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stream => "stream",
+            Self::Bet => "bet",
+            Self::Balance => "balance",
+        }
+    }
+}
+
+impl FromStr for EntityType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "stream" => Ok(Self::Stream),
+            "bet" => Ok(Self::Bet),
+            "balance" => Ok(Self::Balance),
+            other => Err(anyhow!("Unknown entity type {:?}; expected stream, bet or balance", other)),
+        }
+    }
+}
+
+fn event_from_row(row: &sqlx::postgres::PgRow) -> Result<StateEvent> {
+    Ok(StateEvent {
+        event_id: row.get("event_id"),
+        entity_type: row.get::<String, _>("entity_type").parse()?,
+        entity_id: row.get("entity_id"),
+        change: row.get("change"),
+        state: row.get("state"),
+        actor: row.get("actor"),
+        occurred_at: row.get("occurred_at"),
+    })
+}
@@ -0,0 +1,79 @@
+use std::time::Duration;
+use anyhow::{Context, Result};
+use axum_server::tls_rustls::RustlsConfig;
+use futures::StreamExt;
+use rustls_acme::{caches::DirCache, AcmeConfig};
+use rustls_acme::axum::AxumAcceptor;
+use tracing::{info, warn};
+
+use crate::config::TlsConfig;
+use crate::shutdown::Shutdown;
+
+/// How the listener gets its certificate.
+pub enum TlsAcceptor {
+    // PEM files on disk, re-read periodically so renewals apply without a restart
+    Files(RustlsConfig),
+    // Ordered and renewed over ACME, answering TLS-ALPN-01 challenges on the listener itself
+    Acme(AxumAcceptor),
+}
+
+/// Loads the certificate, or starts ACME ordering, and the background work
+/// that keeps it fresh until shutdown.
+pub async fn acceptor(config: &TlsConfig, shutdown: &Shutdown) -> Result<TlsAcceptor> {
+    if !config.acme_domains.is_empty() {
+        return Ok(TlsAcceptor::Acme(acme_acceptor(config, shutdown)));
+    }
+
+    let (cert_path, key_path) = match (&config.cert_path, &config.key_path) {
+        (Some(cert_path), Some(key_path)) => (cert_path.clone(), key_path.clone()),
+        _ => anyhow::bail!("TLS_CERT_PATH and TLS_KEY_PATH must be set unless TLS_ACME_DOMAINS is"),
+    };
+    let rustls_config = RustlsConfig::from_pem_file(&cert_path, &key_path).await
+        .with_context(|| format!("Failed to load TLS certificate {} and key {}", cert_path, key_path))?;
+    info!("Serving TLS with the certificate in {}", cert_path);
+
+    if config.cert_reload_interval_secs > 0 {
+        let reloading = rustls_config.clone();
+        let period = Duration::from_secs(config.cert_reload_interval_secs);
+        shutdown.spawn_loop("tls:cert_reload", async move {
+            let mut interval = tokio::time::interval(period);
+            // The certificate was just loaded
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = reloading.reload_from_pem_file(&cert_path, &key_path).await {
+                    warn!("Failed to reload TLS certificate; keeping the current one: {}", e);
+                }
+            }
+        });
+    }
+
+    Ok(TlsAcceptor::Files(rustls_config))
+}
+
+fn acme_acceptor(config: &TlsConfig, shutdown: &Shutdown) -> AxumAcceptor {
+    let mut state = AcmeConfig::new(config.acme_domains.clone())
+        .contact(config.acme_contact_email.iter().map(|email| format!("mailto:{}", email)))
+        .cache(DirCache::new(config.acme_cache_dir.clone()))
+        .directory_lets_encrypt(!config.acme_staging)
+        .state();
+    let acceptor = state.axum_acceptor(state.default_rustls_config());
+    info!(
+        "Ordering TLS certificates for {} from Let's Encrypt{}",
+        config.acme_domains.join(", "),
+        if config.acme_staging { " staging" } else { "" }
+    );
+
+    // Orders, renews and caches certificates; each event is one step of that
+    shutdown.spawn_loop("tls:acme", async move {
+        while let Some(event) = state.next().await {
+            match event {
+                Ok(event) => info!("ACME: {:?}", event),
+                Err(e) => warn!("ACME certificate order failed: {}", e),
+            }
+        }
+    });
+
+    acceptor
+}
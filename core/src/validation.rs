@@ -0,0 +1,142 @@
+use std::fmt;
+use axum::async_trait;
+use axum::extract::{FromRequest, Request};
+use axum::http::StatusCode;
+use bytes::Bytes;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::encoding::Encoding;
+use crate::error::{ApiError, ErrorCode};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldError {
+    // Dotted path into the body, e.g. `gps_data.latitude`
+    pub field: String,
+    pub message: String,
+}
+
+/// Every problem found with a request body, reported together.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.push(FieldError { field: field.into(), message: message.into() });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_fields(self) -> Vec<FieldError> {
+        self.0
+    }
+
+    /// Validates `value` and returns everything wrong with it.
+    pub fn collect(value: &impl Validate) -> Result<(), ValidationErrors> {
+        let mut errors = Self::new();
+        value.validate_fields(&mut errors);
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Validates a nested value, prefixing its fields with `field`.
+    pub fn nested(&mut self, field: &str, value: &impl Validate) {
+        if let Err(nested) = Self::collect(value) {
+            for error in nested.0 {
+                self.add(format!("{}.{}", field, error.field), error.message);
+            }
+        }
+    }
+
+    pub fn require_non_empty(&mut self, field: &str, value: &str) {
+        if value.trim().is_empty() {
+            self.add(field, "must not be empty");
+        }
+    }
+
+    pub fn require_range(&mut self, field: &str, value: f64, min: f64, max: f64) {
+        if !value.is_finite() || value < min || value > max {
+            self.add(field, format!("must be between {} and {}", min, max));
+        }
+    }
+
+    pub fn require_positive(&mut self, field: &str, value: f64) {
+        if !value.is_finite() || value <= 0.0 {
+            self.add(field, "must be a positive number");
+        }
+    }
+
+    pub fn require_coordinates(&mut self, latitude: f64, longitude: f64) {
+        self.require_range("latitude", latitude, -90.0, 90.0);
+        self.require_range("longitude", longitude, -180.0, 180.0);
+    }
+}
+
+// Used where a single message is still expected, e.g. config loading
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<String> = self.0.iter().map(|e| format!("{} {}", e.field, e.message)).collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+impl IntoResponse for ValidationErrors {
+    fn into_response(self) -> Response {
+        ApiError::from(self).into_response()
+    }
+}
+
+/// A request body with field-level rules beyond what its types enforce.
+pub trait Validate {
+    /// Records every problem found rather than stopping at the first.
+    fn validate_fields(&self, errors: &mut ValidationErrors);
+}
+
+/// JSON body extractor that rejects bodies that don't deserialize or don't
+/// validate, in both cases naming the offending fields. Bodies sent as
+/// `application/msgpack` are decoded as MessagePack instead.
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let value = match Encoding::from_content_type(request.headers()) {
+            // serde's message names the path to the bad field
+            Encoding::Json => {
+                let Json(value) = Json::<T>::from_request(request, state).await
+                    .map_err(|rejection| match rejection.status() {
+                        StatusCode::PAYLOAD_TOO_LARGE => ApiError::new(ErrorCode::PayloadTooLarge, rejection.body_text()),
+                        _ => ApiError::malformed_body(rejection.body_text()),
+                    })?;
+                value
+            }
+            Encoding::MessagePack => {
+                let body = Bytes::from_request(request, state).await
+                    .map_err(|rejection| match rejection.status() {
+                        StatusCode::PAYLOAD_TOO_LARGE => ApiError::new(ErrorCode::PayloadTooLarge, rejection.body_text()),
+                        _ => ApiError::malformed_body(rejection.body_text()),
+                    })?;
+                rmp_serde::from_slice(&body)
+                    .map_err(|e| ApiError::malformed_body(e.to_string()))?
+            }
+        };
+
+        ValidationErrors::collect(&value)?;
+        Ok(ValidJson(value))
+    }
+}
@@ -0,0 +1,585 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::{Pool, Postgres, Row};
+use tracing::{info, warn};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::config::WebhookConfig;
+use crate::outbox::{OutboxError, OutboxEvent, OutboxHandler};
+use crate::pagination::{PageRequest, Paginated};
+use crate::secrets::SecretStore;
+use crate::shutdown::Shutdown;
+use crate::validation::{Validate, ValidationErrors};
+
+type WebhookError = Box<dyn std::error::Error + Send + Sync>;
+
+pub const EVENT_HEADER: &str = "x-morphine-event";
+pub const DELIVERY_HEADER: &str = "x-morphine-delivery";
+pub const SIGNATURE_HEADER: &str = "x-morphine-signature";
+
+// Every signing secret starts with this, which makes leaked secrets easy to spot
+const SECRET_PREFIX: &str = "whsec_";
+const MAX_DESCRIPTION_LENGTH: usize = 500;
+
+/// What integrators can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    BetPlaced,
+    BetSettled,
+    // Cancelled by an emergency stop on its stream, stake refunded
+    BetVoided,
+    StreamActivated,
+    StreamStopped,
+    UserExcluded,
+}
+
+impl WebhookEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventType::BetPlaced => "bet_placed",
+            WebhookEventType::BetSettled => "bet_settled",
+            WebhookEventType::BetVoided => "bet_voided",
+            WebhookEventType::StreamActivated => "stream_activated",
+            WebhookEventType::StreamStopped => "stream_stopped",
+            WebhookEventType::UserExcluded => "user_excluded",
+        }
+    }
+
+    pub fn parse(event_type: &str) -> Option<Self> {
+        match event_type {
+            "bet_placed" => Some(WebhookEventType::BetPlaced),
+            "bet_settled" => Some(WebhookEventType::BetSettled),
+            "bet_voided" => Some(WebhookEventType::BetVoided),
+            "stream_activated" => Some(WebhookEventType::StreamActivated),
+            "stream_stopped" => Some(WebhookEventType::StreamStopped),
+            "user_excluded" => Some(WebhookEventType::UserExcluded),
+            _ => None,
+        }
+    }
+}
+
+/// A stored subscription, without its signing secret.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookSubscription {
+    pub subscription_id: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub event_types: Vec<WebhookEventType>,
+    // Name of the secrets-provider secret deliveries are signed with, if not a generated one
+    pub secret_ref: Option<String>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A freshly created subscription. A generated secret is only ever returned
+/// here; there is none for subscriptions signing with a provider secret.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct IssuedWebhookSubscription {
+    pub subscription: WebhookSubscription,
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NewWebhookSubscription {
+    pub url: String,
+    pub description: Option<String>,
+    pub event_types: Vec<WebhookEventType>,
+    // Sign with this secret from the secrets provider, rotated there, instead of a generated one
+    pub secret_ref: Option<String>,
+}
+
+impl Validate for NewWebhookSubscription {
+    fn validate_fields(&self, errors: &mut ValidationErrors) {
+        // Plain http is only for receivers on the same machine, e.g. in development
+        let local = ["http://localhost", "http://127.0.0.1"].iter().any(|prefix| self.url.starts_with(prefix));
+        if !self.url.starts_with("https://") && !local {
+            errors.add("url", "must be an https URL");
+        }
+        if self.event_types.is_empty() {
+            errors.add("event_types", "needs at least one event type");
+        }
+        if self.description.as_ref().map(|d| d.chars().count() > MAX_DESCRIPTION_LENGTH).unwrap_or(false) {
+            errors.add("description", format!("must be at most {} characters", MAX_DESCRIPTION_LENGTH));
+        }
+        if let Some(secret_ref) = &self.secret_ref {
+            errors.require_non_empty("secret_ref", secret_ref);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    // Gave up after the configured number of attempts; can be retried by hand
+    DeadLettered,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Succeeded => "succeeded",
+            DeliveryStatus::DeadLettered => "dead_lettered",
+        }
+    }
+
+    fn parse(status: &str) -> Self {
+        match status {
+            "succeeded" => DeliveryStatus::Succeeded,
+            "dead_lettered" => DeliveryStatus::DeadLettered,
+            _ => DeliveryStatus::Pending,
+        }
+    }
+}
+
+/// An entry in the delivery log: one event sent to one subscription.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WebhookDelivery {
+    pub delivery_id: String,
+    pub subscription_id: String,
+    pub event_id: String,
+    pub event_type: String,
+    #[schema(value_type = Object)]
+    pub payload: Value,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeliveryQuery {
+    pub subscription_id: Option<String>,
+    #[param(value_type = Option<String>)]
+    pub status: Option<DeliveryStatus>,
+}
+
+// A delivery claimed by this instance, with what it takes to send it
+struct ClaimedDelivery {
+    delivery_id: String,
+    event_type: String,
+    payload: Value,
+    attempts: i32,
+    url: String,
+    secret: Option<String>,
+    secret_ref: Option<String>,
+}
+
+/// Signs `body` as sent at `timestamp` (Unix seconds). Receivers recompute
+/// HMAC-SHA256 over `"{timestamp}.{body}"` with their secret and compare it to
+/// the `v1` value of the `X-Morphine-Signature` header, `t={timestamp},v1={hex}`.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes any key length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Postgres-backed webhook subscriptions and deliveries. Publishing queues one
+/// delivery per matching subscription; a dispatcher sends due deliveries with
+/// exponential backoff and dead-letters those that keep failing. Deliveries
+/// are claimed with `SKIP LOCKED`, so several instances can dispatch at once.
+pub struct WebhookService {
+    db_pool: Pool<Postgres>,
+    config: WebhookConfig,
+    secrets: Arc<SecretStore>,
+    http_client: reqwest::Client,
+    shutdown: Shutdown,
+}
+
+impl WebhookService {
+    pub fn new(db_pool: Pool<Postgres>, config: WebhookConfig, secrets: Arc<SecretStore>, shutdown: Shutdown) -> Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.delivery_timeout_ms))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            db_pool,
+            config,
+            secrets,
+            http_client,
+            shutdown,
+        }
+    }
+
+    /// Starts the dispatcher. A delivery cut off by shutdown is retried once its
+    /// lease runs out, so receivers may see an event more than once.
+    pub fn start(self: &Arc<Self>) {
+        if !self.config.enabled {
+            info!("Webhook delivery disabled; events are queued but not sent");
+            return;
+        }
+
+        let service = self.clone();
+        self.shutdown.spawn_loop("webhooks:dispatcher", async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(service.config.poll_interval_ms));
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.dispatch_due().await {
+                    warn!("Webhook dispatch failed: {}", e);
+                }
+            }
+        });
+    }
+
+    pub async fn create_subscription(
+        &self,
+        request: &NewWebhookSubscription,
+    ) -> Result<IssuedWebhookSubscription, WebhookError> {
+        ValidationErrors::collect(request)?;
+        let secret_ref = request.secret_ref.as_deref().map(str::trim);
+
+        // A provider secret must resolve now, not at the first delivery
+        let secret = match secret_ref {
+            Some(name) => {
+                if let Err(e) = self.secrets.get(name).await {
+                    warn!("Rejected webhook secret_ref {}: {}", name, e);
+                    let mut errors = ValidationErrors::new();
+                    errors.add("secret_ref", "must name a secret the secrets provider can read");
+                    return Err(errors.into());
+                }
+                None
+            }
+            None => Some(format!("{}{}", SECRET_PREFIX, Uuid::new_v4().simple())),
+        };
+
+        let row = sqlx::query(&format!(
+            r#"
+            INSERT INTO webhook_subscriptions (subscription_id, url, description, event_types, secret, secret_ref)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING {}
+            "#,
+            SUBSCRIPTION_COLUMNS
+        ))
+        .bind(Uuid::new_v4().to_string())
+        .bind(request.url.trim())
+        .bind(request.description.as_deref().map(str::trim))
+        .bind(event_type_strings(&request.event_types))
+        .bind(&secret)
+        .bind(secret_ref)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(IssuedWebhookSubscription { subscription: subscription_from_row(&row), secret })
+    }
+
+    // Newest first, deactivated subscriptions included
+    pub async fn list_subscriptions(&self) -> Result<Vec<WebhookSubscription>, WebhookError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM webhook_subscriptions ORDER BY created_at DESC",
+            SUBSCRIPTION_COLUMNS
+        ))
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows.iter().map(subscription_from_row).collect())
+    }
+
+    /// Stops deliveries to a subscription, keeping its delivery log. Returns
+    /// false if it doesn't exist or was already deactivated.
+    pub async fn deactivate_subscription(&self, subscription_id: &str) -> Result<bool, WebhookError> {
+        let result = sqlx::query(
+            "UPDATE webhook_subscriptions SET is_active = FALSE WHERE subscription_id = $1 AND is_active"
+        )
+        .bind(subscription_id)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Queues an outbox event for every active subscription to its type.
+    /// Returns the number of deliveries queued; none for a subscription that
+    /// already has this event.
+    async fn publish(&self, event: &OutboxEvent, event_type: WebhookEventType) -> Result<u64, WebhookError> {
+        let payload = json!({
+            "event_id": event.event_id,
+            "event_type": event_type,
+            "occurred_at": event.occurred_at,
+            "data": event.data,
+        });
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (delivery_id, subscription_id, event_id, event_type, payload)
+            SELECT gen_random_uuid()::text, subscription_id, $1, $2, $3
+            FROM webhook_subscriptions
+            WHERE is_active AND $2 = ANY(event_types)
+            ON CONFLICT (subscription_id, event_id) DO NOTHING
+            "#
+        )
+        .bind(&event.event_id)
+        .bind(event_type.as_str())
+        .bind(&payload)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// The delivery log, newest first unless sorted by `created_at` ascending.
+    pub async fn list_deliveries(
+        &self,
+        query: &DeliveryQuery,
+        page: &PageRequest,
+    ) -> Result<Paginated<WebhookDelivery>, WebhookError> {
+        let ascending = page.sort.as_ref().map(|sort| !sort.descending).unwrap_or(false);
+        let filter = "($1::text IS NULL OR subscription_id = $1) AND ($2::text IS NULL OR status = $2)";
+        let status = query.status.map(|status| status.as_str());
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM webhook_deliveries WHERE {}", filter))
+            .bind(&query.subscription_id)
+            .bind(status)
+            .fetch_one(&self.db_pool)
+            .await?;
+
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM webhook_deliveries WHERE {} ORDER BY created_at {} LIMIT $3 OFFSET $4",
+            DELIVERY_COLUMNS,
+            filter,
+            if ascending { "ASC" } else { "DESC" }
+        ))
+        .bind(&query.subscription_id)
+        .bind(status)
+        .bind(page.limit as i64)
+        .bind(page.offset as i64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(page.page_of(rows.iter().map(delivery_from_row).collect(), total as usize))
+    }
+
+    /// Requeues a dead-lettered delivery with a fresh set of attempts. Returns
+    /// None if there's no such dead-lettered delivery.
+    pub async fn retry_delivery(&self, delivery_id: &str) -> Result<Option<WebhookDelivery>, WebhookError> {
+        let row = sqlx::query(&format!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'pending', attempts = 0, next_attempt_at = NOW()
+            WHERE delivery_id = $1 AND status = 'dead_lettered'
+            RETURNING {}
+            "#,
+            DELIVERY_COLUMNS
+        ))
+        .bind(delivery_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row.as_ref().map(delivery_from_row))
+    }
+
+    async fn dispatch_due(&self) -> Result<(), WebhookError> {
+        // Claimed deliveries are leased past the send timeout so no other
+        // instance picks them up while they're in flight
+        let lease_secs = (self.config.delivery_timeout_ms / 1000 + 1) as f64 * 2.0;
+        let rows = sqlx::query(
+            r#"
+            UPDATE webhook_deliveries d
+            SET next_attempt_at = NOW() + make_interval(secs => $2)
+            FROM (
+                SELECT delivery_id
+                FROM webhook_deliveries
+                JOIN webhook_subscriptions USING (subscription_id)
+                WHERE status = 'pending' AND next_attempt_at <= NOW() AND is_active
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE OF webhook_deliveries SKIP LOCKED
+            ) due, webhook_subscriptions s
+            WHERE d.delivery_id = due.delivery_id AND s.subscription_id = d.subscription_id
+            RETURNING d.delivery_id, d.event_type, d.payload, d.attempts, s.url, s.secret, s.secret_ref
+            "#
+        )
+        .bind(self.config.batch_size)
+        .bind(lease_secs)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let claimed: Vec<ClaimedDelivery> = rows.iter()
+            .map(|row| ClaimedDelivery {
+                delivery_id: row.get("delivery_id"),
+                event_type: row.get("event_type"),
+                payload: row.get("payload"),
+                attempts: row.get("attempts"),
+                url: row.get("url"),
+                secret: row.get("secret"),
+                secret_ref: row.get("secret_ref"),
+            })
+            .collect();
+
+        futures::future::join_all(claimed.into_iter().map(|delivery| self.attempt(delivery))).await;
+        Ok(())
+    }
+
+    async fn attempt(&self, delivery: ClaimedDelivery) {
+        let body = delivery.payload.to_string();
+        let secret = match (&delivery.secret_ref, &delivery.secret) {
+            (Some(name), _) => self.secrets.get(name).await
+                .map_err(|e| format!("Signing secret {} unavailable: {}", name, e)),
+            (None, Some(secret)) => Ok(secret.clone()),
+            (None, None) => Err("Subscription has no signing secret".to_string()),
+        };
+
+        let outcome = match secret {
+            Ok(secret) => {
+                let signature = sign(&secret, Utc::now().timestamp(), &body);
+                self.http_client.post(&delivery.url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .header(EVENT_HEADER, &delivery.event_type)
+                    .header(DELIVERY_HEADER, &delivery.delivery_id)
+                    .header(SIGNATURE_HEADER, signature)
+                    .body(body)
+                    .send()
+                    .await
+                    .map_err(|e| (e.status().map(|s| s.as_u16()), e.to_string()))
+            }
+            // Not sent, but retried like a failed send so a rotation in progress can catch up
+            Err(e) => Err((None, e)),
+        };
+
+        let (response_status, error) = match outcome {
+            Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+            Ok(response) => (Some(response.status().as_u16()), Some(format!("Receiver responded {}", response.status()))),
+            Err((status, error)) => (status, Some(error)),
+        };
+
+        let recorded = match error {
+            None => self.record_success(&delivery, response_status).await,
+            Some(error) => self.record_failure(&delivery, response_status, &error).await,
+        };
+        if let Err(e) = recorded {
+            warn!("Failed to record webhook delivery {}: {}", delivery.delivery_id, e);
+        }
+    }
+
+    async fn record_success(&self, delivery: &ClaimedDelivery, response_status: Option<u16>) -> Result<(), WebhookError> {
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'succeeded', attempts = attempts + 1, delivered_at = NOW(),
+                last_response_status = $2, last_error = NULL
+            WHERE delivery_id = $1
+            "#
+        )
+        .bind(&delivery.delivery_id)
+        .bind(response_status.map(i32::from))
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn record_failure(
+        &self,
+        delivery: &ClaimedDelivery,
+        response_status: Option<u16>,
+        error: &str,
+    ) -> Result<(), WebhookError> {
+        let attempts = delivery.attempts + 1;
+        let dead_lettered = attempts as u32 >= self.config.max_attempts;
+        let status = if dead_lettered { DeliveryStatus::DeadLettered } else { DeliveryStatus::Pending };
+
+        if dead_lettered {
+            warn!(
+                "Dead-lettered webhook delivery {} after {} attempts: {}",
+                delivery.delivery_id, attempts, error
+            );
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = $2, attempts = $3, next_attempt_at = NOW() + make_interval(secs => $4),
+                last_response_status = $5, last_error = $6
+            WHERE delivery_id = $1
+            "#
+        )
+        .bind(&delivery.delivery_id)
+        .bind(status.as_str())
+        .bind(attempts)
+        .bind(self.backoff(attempts).as_secs_f64())
+        .bind(response_status.map(i32::from))
+        .bind(error)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    // Doubles from the initial backoff with each failed attempt, up to the max
+    fn backoff(&self, attempts: i32) -> Duration {
+        let exponent = (attempts.max(1) - 1).min(31) as u32;
+        let secs = self.config.initial_backoff_secs.saturating_mul(1u64 << exponent);
+        Duration::from_secs(secs.min(self.config.max_backoff_secs))
+    }
+}
+
+fn event_type_strings(event_types: &[WebhookEventType]) -> Vec<String> {
+    event_types.iter().map(|event_type| event_type.as_str().to_string()).collect()
+}
+
+const SUBSCRIPTION_COLUMNS: &str = "subscription_id, url, description, event_types, secret_ref, is_active, created_at";
+
+fn subscription_from_row(row: &sqlx::postgres::PgRow) -> WebhookSubscription {
+    let event_types: Vec<String> = row.get("event_types");
+    WebhookSubscription {
+        subscription_id: row.get("subscription_id"),
+        url: row.get("url"),
+        description: row.get("description"),
+        // Event types were validated on the way in
+        event_types: event_types.iter().filter_map(|e| WebhookEventType::parse(e)).collect(),
+        secret_ref: row.get("secret_ref"),
+        is_active: row.get("is_active"),
+        created_at: row.get("created_at"),
+    }
+}
+
+const DELIVERY_COLUMNS: &str = "delivery_id, subscription_id, event_id, event_type, payload, status, attempts, \
+    next_attempt_at, last_response_status, last_error, created_at, delivered_at";
+
+fn delivery_from_row(row: &sqlx::postgres::PgRow) -> WebhookDelivery {
+    let status: String = row.get("status");
+    WebhookDelivery {
+        delivery_id: row.get("delivery_id"),
+        subscription_id: row.get("subscription_id"),
+        event_id: row.get("event_id"),
+        event_type: row.get("event_type"),
+        payload: row.get("payload"),
+        status: DeliveryStatus::parse(&status),
+        attempts: row.get("attempts"),
+        next_attempt_at: row.get("next_attempt_at"),
+        last_response_status: row.get("last_response_status"),
+        last_error: row.get("last_error"),
+        created_at: row.get("created_at"),
+        delivered_at: row.get("delivered_at"),
+    }
+}
+
+/// Outbox events of a subscribable type become deliveries; the outbox relay
+/// calls this as the tenant that wrote the event, so only its subscribers get it.
+#[async_trait::async_trait]
+impl OutboxHandler for WebhookService {
+    fn name(&self) -> &'static str {
+        "webhooks"
+    }
+
+    async fn handle(&self, event: &OutboxEvent) -> Result<(), OutboxError> {
+        let Some(event_type) = WebhookEventType::parse(&event.event_type) else {
+            return Ok(());
+        };
+        self.publish(event, event_type).await?;
+        Ok(())
+    }
+}
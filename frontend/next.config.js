@@ -13,7 +13,7 @@ const nextConfig = {
     return [
       {
         source: '/api/core/:path*',
-        destination: `${process.env.NEXT_PUBLIC_CORE_URL}/api/:path*`,
+        destination: `${process.env.NEXT_PUBLIC_CORE_URL}/api/v1/:path*`,
       },
       {
         source: '/api/analytics/:path*',
@@ -0,0 +1,38 @@
+#!/bin/bash
+
+set -e
+
+# Runs the core service's Criterion benchmarks against a saved baseline.
+#   bench.sh save [baseline] [suite]     record a baseline (default "main")
+#   bench.sh compare [baseline] [suite]  compare this tree against it
+# Suites are the files in core/benches: reasoning, settlement, analytics.
+
+mode=${1:-compare}
+baseline=${2:-main}
+suite=${3:-}
+
+cd "$(dirname "$0")/../core"
+
+bench_args=()
+if [ -n "$suite" ]; then
+    bench_args+=(--bench "$suite")
+fi
+
+case "$mode" in
+    save)
+        echo "📏 Saving benchmark baseline '$baseline'..."
+        cargo bench -p morphine-core "${bench_args[@]}" -- --save-baseline "$baseline"
+        ;;
+    compare)
+        if [ ! -d "target/criterion" ]; then
+            echo "❌ No saved baselines. Run '$0 save $baseline' on the reference tree first."
+            exit 1
+        fi
+        echo "📊 Comparing against baseline '$baseline'..."
+        cargo bench -p morphine-core "${bench_args[@]}" -- --baseline "$baseline"
+        ;;
+    *)
+        echo "Usage: $0 [save|compare] [baseline] [suite]"
+        exit 1
+        ;;
+esac